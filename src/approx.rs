@@ -0,0 +1,172 @@
+//! Sampling-based approximate counting for Brogue Seed Scanner.
+//!
+//! `--approx [PERCENT]` searches a random sample of about PERCENT of the catalog files
+//! (at least one), counting every matching seed instead of stopping at `--matches`, then
+//! extrapolates the match rate to the whole library with a 95% confidence interval.  The
+//! library size is estimated from file sizes, so it's exact only when every catalog file
+//! is complete.
+
+use crate::file_handling::{file_size, shuffle_paths};
+use crate::search::{search_files_batch, SearchMatch, SearchParameters};
+use crate::status::no_files;
+use anyhow::Result;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// z-score for a 95% confidence interval.
+const Z_95: f64 = 1.96;
+
+/// Approximate match count for a single search.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Approx {
+    /// Number of catalog files sampled, out of `files`.
+    pub sampled_files: usize,
+    pub files: usize,
+    /// Number of seeds searched in the sample.
+    pub sampled_seeds: u64,
+    /// Number of matching seeds in the sample.
+    pub matching_seeds: u64,
+    /// Match rate per seed, and its 95% confidence interval.
+    pub rate: f64,
+    pub rate_low: f64,
+    pub rate_high: f64,
+    /// Estimated number of seeds in the whole library.
+    pub library_seeds: u64,
+    /// Estimated matching seeds in the whole library, and its 95% confidence interval.
+    pub estimate: f64,
+    pub estimate_low: f64,
+    pub estimate_high: f64,
+}
+
+impl Approx {
+    /// Makes a new instance from sample counts, extrapolated to the library size.
+    fn new(
+        sampled_files: usize,
+        files: usize,
+        sampled_seeds: u64,
+        matching_seeds: u64,
+        scale: f64,
+    ) -> Self {
+        let (rate, rate_low, rate_high) = wilson_interval(matching_seeds, sampled_seeds);
+        let library_seeds = (sampled_seeds as f64 * scale).round() as u64;
+        let library = library_seeds as f64;
+
+        Self {
+            sampled_files,
+            files,
+            sampled_seeds,
+            matching_seeds,
+            rate,
+            rate_low,
+            rate_high,
+            library_seeds,
+            estimate: rate * library,
+            estimate_low: rate_low * library,
+            estimate_high: rate_high * library,
+        }
+    }
+}
+
+/// Returns the proportion `k / n` and its Wilson score 95% confidence interval.
+fn wilson_interval(k: u64, n: u64) -> (f64, f64, f64) {
+    if n == 0 {
+        return (0.0, 0.0, 1.0);
+    }
+
+    let n = n as f64;
+    let p = k as f64 / n;
+    let z2 = Z_95 * Z_95;
+    let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let margin = Z_95 * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+
+    (p, (center - margin).max(0.0), (center + margin).min(1.0))
+}
+
+/// Returns the number of distinct seeds in a list of matches (grouped by seed).
+fn matching_seeds(matches: &[SearchMatch]) -> u64 {
+    let mut seeds = 0;
+    let mut prev_seed = None;
+
+    for m in matches {
+        if prev_seed != Some(m.seed) {
+            prev_seed = Some(m.seed);
+            seeds += 1;
+        }
+    }
+
+    seeds
+}
+
+/// Searches a random sample of `percent` of the catalog files (from the first search)
+/// with every search, and returns an approximate count for each.
+pub fn approximate(searches: &mut [SearchParameters], percent: f64) -> Result<Vec<Approx>> {
+    let mut file_paths: Vec<PathBuf> = match searches.first() {
+        Some(search) => search.file_paths.clone(),
+        None => return Ok(Vec::new()),
+    };
+
+    if file_paths.is_empty() {
+        return Err(no_files());
+    }
+
+    let files = file_paths.len();
+    let sample_size = ((files as f64 * percent / 100.0).ceil() as usize).clamp(1, files);
+    let total_bytes: u64 = file_paths.iter().map(|p| file_size(p)).sum();
+
+    shuffle_paths(&mut file_paths, searches[0].random_seed);
+    file_paths.truncate(sample_size);
+
+    let sampled_bytes: u64 = file_paths.iter().map(|p| file_size(p)).sum();
+    let scale = match sampled_bytes {
+        0 => 1.0,
+        _ => total_bytes as f64 / sampled_bytes as f64,
+    };
+
+    for search in searches.iter_mut() {
+        search.file_paths = file_paths.clone();
+        search.unbounded = true;
+    }
+
+    let results = search_files_batch(searches)?;
+
+    Ok(searches
+        .iter()
+        .zip(results.iter())
+        .map(|(search, matches)| {
+            Approx::new(
+                sample_size,
+                files,
+                search.seeds_scanned,
+                matching_seeds(matches),
+                scale,
+            )
+        })
+        .collect())
+}
+
+/// Prints an approximate count, formatting counts as for the search.
+pub fn display_approx(approx: &Approx, search: &SearchParameters) {
+    // Estimates are rounded to whole seeds
+    let estimate = |value: f64| search.number(value.round().max(0.0) as u64);
+
+    println!(
+        "Approximate (sampled {} of {} files, {} seeds):\n",
+        search.number(approx.sampled_files as u64),
+        search.number(approx.files as u64),
+        search.number(approx.sampled_seeds)
+    );
+    println!(
+        "  match rate: {:.4}% (95% CI {:.4}% to {:.4}%), {} matching seeds in sample",
+        approx.rate * 100.0,
+        approx.rate_low * 100.0,
+        approx.rate_high * 100.0,
+        search.number(approx.matching_seeds),
+    );
+    println!(
+        "  library:    ~{} seeds, ~{} matching (95% CI {} to {})\n",
+        search.number(approx.library_seeds),
+        estimate(approx.estimate),
+        estimate(approx.estimate_low),
+        estimate(approx.estimate_high)
+    );
+}
@@ -0,0 +1,188 @@
+//! Zip archives of catalog files for Brogue Seed Scanner.
+//!
+//! A large library of catalogs is easiest to share as a single `.zip` archive, so zip
+//! archives in a catalog folder (or given to `--filepath` themselves) are searched like
+//! folders, without extracting them to disk.  A catalog in an archive has the path of
+//! the archive joined with its name in the archive (e.g.
+//! `catalogs.zip/part1/seeds-1-100000.csv`), which `open_raw` and the rest of
+//! `file_handling` read like any other catalog path.
+//!
+//! Catalogs may be stored or deflated (what every zip tool writes), and may themselves
+//! be gzip-compressed (`*.csv.gz`).  Zip64 archives (over 4 GB, or over 65535 files) are
+//! read too;  encrypted entries aren't.  `.7z` archives can't be read:  repack them as
+//! `.zip`.
+//!
+//! Archives are only read in builds with the `zip` feature; other builds name archives in
+//! catalog folders but can't open them.
+
+use crate::manifest::file_stamp;
+use anyhow::{anyhow, Context, Result};
+use flate2::read::DeflateDecoder;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Archives by path, with the size and modification time of the file each was read from.
+type Archives = HashMap<PathBuf, ((u64, u64), Arc<ZipArchive>)>;
+
+/// Archives read so far.
+static ARCHIVES: OnceLock<Mutex<Archives>> = OnceLock::new();
+
+/// How a file in a zip archive is compressed.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "zip"), allow(dead_code))]
+enum Method {
+    Stored,
+    Deflated,
+    /// Any other method, by name.
+    Other(String),
+}
+
+/// A file in a zip archive.
+#[derive(Debug, Clone)]
+struct ZipEntry {
+    name: String,
+    method: Method,
+    encrypted: bool,
+    compressed_size: u64,
+    /// Offset of the entry's data in the archive.
+    data_start: u64,
+}
+
+/// The files of a zip archive, from its central directory.
+#[derive(Debug)]
+pub struct ZipArchive {
+    path: PathBuf,
+    entries: Vec<ZipEntry>,
+}
+
+impl ZipArchive {
+    /// Returns the archive at a path, reading its central directory unless it was read
+    /// before (and hasn't changed since).
+    pub fn open(path: &Path) -> Result<Arc<Self>> {
+        let stamp =
+            file_stamp(path).with_context(|| format!("could not read archive {:?}", path))?;
+        let archives = ARCHIVES.get_or_init(Default::default);
+
+        if let Some((cached, archive)) = archives.lock().unwrap().get(path) {
+            if *cached == stamp {
+                return Ok(archive.clone());
+            }
+        }
+        let archive =
+            Arc::new(Self::read(path).with_context(|| format!("invalid zip archive {:?}", path))?);
+        archives
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), (stamp, archive.clone()));
+
+        Ok(archive)
+    }
+    /// Reads the central directory of an archive (with the `zip` crate, which checks its
+    /// sizes and offsets against the file), and where each file's data starts.
+    #[cfg(feature = "zip")]
+    fn read(path: &Path) -> Result<Self> {
+        use zip::CompressionMethod;
+
+        let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+        let mut entries = Vec::with_capacity(archive.len());
+
+        for ix in 0..archive.len() {
+            let entry = archive.by_index_raw(ix)?;
+            let method = match entry.compression() {
+                CompressionMethod::STORE => Method::Stored,
+                CompressionMethod::DEFLATE => Method::Deflated,
+                method => Method::Other(method.to_string()),
+            };
+            entries.push(ZipEntry {
+                name: entry.name().to_string(),
+                method,
+                encrypted: entry.encrypted(),
+                compressed_size: entry.compressed_size(),
+                data_start: entry.data_start(),
+            });
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+    /// Reads the central directory of an archive (only in builds with the `zip` feature).
+    #[cfg(not(feature = "zip"))]
+    fn read(_path: &Path) -> Result<Self> {
+        Err(anyhow!(
+            "this build can't read zip archives (built without the 'zip' feature)"
+        ))
+    }
+    /// Returns the names of the files in the archive (not folders), in archive order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .filter(|name| !name.ends_with('/'))
+    }
+    /// Returns the compressed size of a file in the archive.
+    pub fn compressed_size(&self, name: &str) -> Option<u64> {
+        self.entry(name).ok().map(|entry| entry.compressed_size)
+    }
+    /// Opens a file in the archive, decompressing it as it's read.
+    pub fn open_entry(&self, name: &str) -> Result<Box<dyn Read + Send>> {
+        let entry = self.entry(name)?;
+        if entry.encrypted {
+            return Err(anyhow!(
+                "can't read encrypted file '{}' in {:?}",
+                name,
+                self.path
+            ));
+        }
+
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(entry.data_start))?;
+        let data = file.take(entry.compressed_size);
+
+        match &entry.method {
+            Method::Stored => Ok(Box::new(data)),
+            Method::Deflated => Ok(Box::new(DeflateDecoder::new(data))),
+            Method::Other(method) => Err(anyhow!(
+                "can't read '{}' in {:?}:  compression method {} isn't supported (use deflate)",
+                name,
+                self.path,
+                method
+            )),
+        }
+    }
+    /// Returns a file of the archive by name.
+    fn entry(&self, name: &str) -> Result<&ZipEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| anyhow!("no '{}' in archive {:?}", name, self.path))
+    }
+}
+
+/// Returns `true` if a file is named like a zip archive (`*.zip`).
+pub fn is_zip<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Splits the path of a file in an archive into the path of the archive and the file's
+/// name in it, or returns `None` for other paths.
+pub fn split_archive_path(path: &Path) -> Option<(&Path, String)> {
+    let archive = path
+        .ancestors()
+        .skip(1)
+        .find(|ancestor| is_zip(ancestor) && ancestor.is_file())?;
+    let name: Vec<_> = path
+        .strip_prefix(archive)
+        .ok()?
+        .iter()
+        .map(|part| part.to_string_lossy())
+        .collect();
+
+    Some((archive, name.join("/")))
+}
@@ -28,7 +28,7 @@ impl BitFlags16 {
     /// Inserts flags into current `BitFlags16` (bitwise OR).
     #[inline]
     pub fn insert(&mut self, other: Self) {
-        self.0 = self.0 | other.0;
+        self.0 |= other.0;
     }
 }
 
@@ -0,0 +1,381 @@
+//! Name tables from Brogue CE source for Brogue Seed Scanner.
+//!
+//! `update-tables --brogue-src PATH` reads the item tables (`potionTable`, `weaponTable`,
+//! etc.), runic names, and monster, monster class, and mutation catalogs from the C
+//! source of a Brogue CE release, and writes name tables for `--data-dir` with its
+//! names.  Names the scanner has are kept in table order, names the source adds are
+//! appended (if the scanner has their kind, e.g. "war axe" as `WarAxe`), and names it
+//! drops are removed, unless a kind would be left without a name.  Tables the source
+//! doesn't have (altars and ally statuses) are left alone.
+//!
+//! Arrays are found by name in every `.c` and `.h` file under the path, preferring
+//! `NAME` to a variant's `NAME_Brogue`.  Each entry's name is its first string.
+
+use crate::objects::{tables, RawTables, Tables};
+use anyhow::{anyhow, Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Name tables read from Brogue CE source:  table, array, and the runic (if any)
+/// named once per monster class (e.g. "dragon slaying").
+const SOURCE_TABLES: [(&str, &str, Option<&str>); 14] = [
+    ("armor_kinds", "armorTable", None),
+    ("armor_runics", "armorRunicNames", Some("immunity")),
+    ("charm_kinds", "charmTable", None),
+    ("food_kinds", "foodTable", None),
+    ("key_kinds", "keyTable", None),
+    ("monster_kinds", "monsterCatalog", None),
+    ("mutations", "mutationCatalog", None),
+    ("potion_kinds", "potionTable", None),
+    ("ring_kinds", "ringTable", None),
+    ("scroll_kinds", "scrollTable", None),
+    ("staff_kinds", "staffTable", None),
+    ("wand_kinds", "wandTable", None),
+    ("weapon_kinds", "weaponTable", None),
+    ("weapon_runics", "weaponRunicNames", Some("slaying")),
+];
+
+/// `[name, kind]` entries of a table.
+type Entries = Vec<(String, String)>;
+
+/// Array of monster classes, for slaying and immunity runics.
+const CLASS_ARRAY: &str = "monsterClassCatalog";
+
+/// Changes to a name table from Brogue CE source.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableUpdate {
+    pub table: &'static str,
+    /// Array of the table, if not found in the source.
+    pub missing: Option<&'static str>,
+    /// Names added by the source.
+    pub added: Vec<String>,
+    /// Names dropped by the source.
+    pub removed: Vec<String>,
+    /// Names added by the source for kinds the scanner doesn't have (not written).
+    pub unknown: Vec<String>,
+    /// Names dropped by the source but kept, as their kind's only name.
+    pub kept: Vec<String>,
+}
+
+impl std::fmt::Display for TableUpdate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(array) = self.missing {
+            return write!(f, "{}:  no '{}' in source (unchanged)", self.table, array);
+        }
+
+        let changes: Vec<String> = [
+            ("added", &self.added),
+            ("removed", &self.removed),
+            ("no kind in scanner for", &self.unknown),
+            ("kept (only name of its kind)", &self.kept),
+        ]
+        .iter()
+        .filter(|(_, names)| !names.is_empty())
+        .map(|(label, names)| format!("{} {}", label, names.join(", ")))
+        .collect();
+
+        match changes.is_empty() {
+            true => write!(f, "{}:  unchanged", self.table),
+            false => write!(f, "{}:  {}", self.table, changes.join(";  ")),
+        }
+    }
+}
+
+/// Updates the process's name tables from the Brogue CE source at `brogue_src`, writing
+/// them to `output` for `--data-dir`.  Returns the number of source files read and the
+/// changes to each table.
+pub fn update_tables(brogue_src: &Path, output: &Path) -> Result<(usize, Vec<TableUpdate>)> {
+    let sources = read_sources(brogue_src)?;
+    if sources.is_empty() {
+        return Err(anyhow!("no C source files in {:?}", brogue_src));
+    }
+
+    let current = tables();
+    let mut raw = current.to_raw();
+    let mut replace = RawTables::new();
+    let mut updates = Vec::new();
+    let classes = find_names(&sources, CLASS_ARRAY)
+        .ok_or_else(|| anyhow!("no '{}' in Brogue source {:?}", CLASS_ARRAY, brogue_src))?;
+
+    for (table, array, per_class) in SOURCE_TABLES.iter() {
+        let names = match find_names(&sources, array) {
+            Some(names) => names,
+            None => {
+                updates.push(TableUpdate {
+                    table,
+                    missing: Some(array),
+                    ..TableUpdate::default()
+                });
+                continue;
+            }
+        };
+        let names: Entries = match per_class {
+            Some(runic) => names
+                .into_iter()
+                .flat_map(|name| match name == *runic {
+                    true => classes
+                        .iter()
+                        .map(|class| (format!("{} {}", class, name), format!("{}({})", kind_of(&name), kind_of(class))))
+                        .collect(),
+                    false => vec![(name.clone(), kind_of(&name))],
+                })
+                .collect(),
+            None => names.into_iter().map(|name| (name.clone(), kind_of(&name))).collect(),
+        };
+        let entries = raw.get(*table).cloned().unwrap_or_default();
+        let (update, entries) = update_table(table, entries, &names, current.kinds(table));
+
+        raw.insert(table.to_string(), entries.clone());
+        replace.insert(table.to_string(), entries);
+        updates.push(update);
+    }
+
+    Tables::from_raw(raw).context("updated name tables are invalid")?;
+    write_tables(output, &replace)?;
+
+    Ok((sources.len(), updates))
+}
+
+/// Updates a table's `[name, kind]` entries with the names of the source (each with the
+/// kind it would have if new).
+fn update_table(
+    table: &'static str,
+    entries: Entries,
+    names: &[(String, String)],
+    kinds: &[String],
+) -> (TableUpdate, Entries) {
+    let mut update = TableUpdate {
+        table,
+        ..TableUpdate::default()
+    };
+    let in_source = |name: &str| names.iter().any(|(n, _)| n == name);
+    let (added, unknown): (Entries, Entries) = names
+        .iter()
+        .filter(|(name, _)| !entries.iter().any(|(n, _)| n == name))
+        .cloned()
+        .partition(|(_, kind)| kinds.contains(kind));
+    let named = |kind: &str| {
+        entries.iter().any(|(n, k)| k == kind && in_source(n))
+            || added.iter().any(|(_, k)| k == kind)
+    };
+    let mut updated = Vec::with_capacity(entries.len() + added.len());
+
+    for (name, kind) in entries.iter() {
+        if in_source(name) {
+            updated.push((name.clone(), kind.clone()));
+        } else if named(kind) {
+            update.removed.push(name.clone());
+        } else {
+            update.kept.push(name.clone());
+            updated.push((name.clone(), kind.clone()));
+        }
+    }
+    update.added = added.iter().map(|(name, _)| name.clone()).collect();
+    update.unknown = unknown.into_iter().map(|(name, _)| name).collect();
+    updated.extend(added);
+
+    (update, updated)
+}
+
+/// Returns the kind a new name would have (e.g. "war axe" as "WarAxe").
+fn kind_of(name: &str) -> String {
+    name.split([' ', '-'])
+        .filter_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+        })
+        .collect()
+}
+
+/// Writes name tables for `--data-dir`, replacing the built-in ones.
+fn write_tables(path: &Path, replace: &RawTables) -> Result<()> {
+    let quote = |s: &str| serde_json::to_string(s).unwrap();
+    let tables: Vec<String> = replace
+        .iter()
+        .map(|(table, names)| {
+            let names: Vec<String> = names
+                .iter()
+                .map(|(name, kind)| format!("      [{}, {}]", quote(name), quote(kind)))
+                .collect();
+            format!("    {}: [\n{}\n    ]", quote(table), names.join(",\n"))
+        })
+        .collect();
+    let text = format!("{{\n  \"replace\": {{\n{}\n  }}\n}}\n", tables.join(",\n"));
+
+    std::fs::write(path, text).with_context(|| format!("could not write name tables {:?}", path))
+}
+
+/// Reads every C source file (`.c` or `.h`) at or under `path`, without comments, in
+/// path order.
+fn read_sources(path: &Path) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    source_files(path, &mut files)
+        .with_context(|| format!("could not read Brogue source {:?}", path))?;
+    files.sort();
+
+    files
+        .iter()
+        .map(|file| {
+            std::fs::read(file)
+                .map(|bytes| strip_comments(&String::from_utf8_lossy(&bytes)))
+                .with_context(|| format!("could not read Brogue source {:?}", file))
+        })
+        .collect()
+}
+
+/// Adds the C source files at or under `path`.
+fn source_files(path: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if path.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            source_files(&entry?.path(), files)?;
+        }
+    } else if path.extension().is_some_and(|ext| ext == "c" || ext == "h") {
+        files.push(path.to_path_buf());
+    }
+
+    Ok(())
+}
+
+/// Returns C source without its comments.
+fn strip_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+                out.push(' ');
+            }
+            ('"', _) | ('\'', _) => {
+                out.push(c);
+                while let Some(s) = chars.next() {
+                    out.push(s);
+                    if s == '\\' {
+                        out.extend(chars.next());
+                    } else if s == c {
+                        break;
+                    }
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Returns the names of the entries of an array (`name`, or else `name_Brogue`) in the
+/// sources, lowercased, each once.
+fn find_names(sources: &[String], name: &str) -> Option<Vec<String>> {
+    let variant = format!("{}_Brogue", name);
+
+    [name, variant.as_str()]
+        .iter()
+        .find_map(|ident| sources.iter().find_map(|source| initializer(source, ident)))
+        .map(|init| {
+            let mut names: Vec<String> = Vec::new();
+            for name in entry_names(init).into_iter().map(|name| name.to_lowercase()) {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            names
+        })
+}
+
+/// Returns the initializer (inside its braces) of the array `ident` defined in a source.
+fn initializer<'a>(source: &'a str, ident: &str) -> Option<&'a str> {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    for (ix, _) in source.match_indices(ident) {
+        let before = source[..ix].chars().next_back();
+        let rest = &source[ix + ident.len()..];
+
+        if before.is_some_and(is_ident) || !rest.trim_start().starts_with('[') {
+            continue;
+        }
+        let open = match (rest.find('{'), rest.find(';')) {
+            (Some(open), Some(end)) if open < end && rest[..open].contains('=') => open,
+            _ => continue,
+        };
+        let body = &rest[open + 1..];
+        let mut depth = 0;
+        let mut in_string = None;
+        let mut escaped = false;
+
+        for (i, c) in body.char_indices() {
+            match (in_string, c) {
+                (Some(_), _) if escaped => escaped = false,
+                (Some(_), '\\') => escaped = true,
+                (Some(quote), c) if c == quote => in_string = None,
+                (Some(_), _) => {}
+                (None, '"') | (None, '\'') => in_string = Some(c),
+                (None, '{') => depth += 1,
+                (None, '}') if depth == 0 => return Some(&body[..i]),
+                (None, '}') => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns the name of each entry of an initializer:  the entry itself if a string, or
+/// else its first string.
+fn entry_names(init: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut depth = 0;
+    let mut named = false;
+    let mut chars = init.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                if depth == 0 {
+                    named = false;
+                }
+                depth += 1;
+            }
+            '}' => depth -= 1,
+            '\'' => {
+                while let Some(s) = chars.next() {
+                    match s {
+                        '\\' => drop(chars.next()),
+                        '\'' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '"' => {
+                let mut text = String::new();
+                while let Some(s) = chars.next() {
+                    match s {
+                        '\\' => text.extend(chars.next()),
+                        '"' => break,
+                        _ => text.push(s),
+                    }
+                }
+                if depth == 0 || !named {
+                    names.push(text);
+                    named = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    names
+}
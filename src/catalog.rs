@@ -0,0 +1,163 @@
+//! Columnar in-memory catalogs for Brogue Seed Scanner.
+//!
+//! The `daemon` keeps every catalog file it has searched in memory as a `Catalog`:  one
+//! array per column, with numbers parsed once (seed, depth, quantity, category,
+//! enchantment) and text columns stored as ids into a table of each column's distinct
+//! values.  Repeated searches then skip most Records with integer comparisons (category
+//! and depth), and only rebuild the few Records that might match.  A catalog is reloaded
+//! when its file's size or modification time changes.
+
+use crate::columns::{self, CatalogSchema};
+use crate::file_handling::{open_catalog, FileFormat};
+use crate::objects::Category;
+use anyhow::{anyhow, Result};
+use csv::StringRecord;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::path::Path;
+
+/// Catalog columns stored as text (by column index):  version, kind, runic, vault number,
+/// opens vault number, carried by, ally status, and mutation.
+const TEXT_COLUMNS: [usize; 8] = [
+    columns::VERSION,
+    columns::KIND,
+    columns::RUNIC,
+    columns::VAULT,
+    columns::OPENS_VAULT,
+    columns::CARRIED_BY,
+    columns::ALLY_STATUS,
+    columns::MUTATION,
+];
+
+/// Number of catalog columns.
+const COLUMNS: usize = columns::HEADERS.len();
+
+/// Column of a catalog stored as ids into a table of its distinct values.
+#[derive(Debug, Clone, Default)]
+struct TextColumn {
+    /// Distinct values, by id.
+    values: Vec<String>,
+    /// Value id of each Record.
+    ids: Vec<u16>,
+    /// Ids by value (only used while loading).
+    lookup: HashMap<String, u16>,
+}
+
+impl TextColumn {
+    /// Adds a Record's value.
+    fn push(&mut self, value: &str) -> Result<()> {
+        let id = match self.lookup.get(value) {
+            Some(id) => *id,
+            None => {
+                let id = u16::try_from(self.values.len())
+                    .map_err(|_| anyhow!("too many distinct values in a catalog column"))?;
+                self.values.push(value.to_string());
+                self.lookup.insert(value.to_string(), id);
+                id
+            }
+        };
+        self.ids.push(id);
+
+        Ok(())
+    }
+    /// Returns a Record's value.
+    #[inline]
+    fn get(&self, row: usize) -> &str {
+        &self.values[usize::from(self.ids[row])]
+    }
+}
+
+/// A single catalog file, held in memory by column.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    /// Header of the catalog file.
+    pub(crate) headers: StringRecord,
+    /// Schema the catalog file was read with (`--schema`).
+    pub(crate) schema: CatalogSchema,
+    pub(crate) seeds: Vec<u32>,
+    pub(crate) depths: Vec<u8>,
+    pub(crate) quantities: Vec<u32>,
+    pub(crate) categories: Vec<Category>,
+    /// Enchantment (or charges), if any.
+    pub(crate) enchants: Vec<Option<i8>>,
+    /// Text columns, in `TEXT_COLUMNS` order, then any extra columns.
+    texts: Vec<TextColumn>,
+}
+
+impl Catalog {
+    /// Loads a catalog file, read with the given schema.
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        format: FileFormat,
+        schema: &CatalogSchema,
+    ) -> Result<Self> {
+        let mut rdr = open_catalog(path, format, schema)?;
+        let mut catalog = Self {
+            headers: rdr.headers()?.clone(),
+            schema: schema.clone(),
+            texts: vec![TextColumn::default(); TEXT_COLUMNS.len() + rdr.headers()?.len() - COLUMNS],
+            ..Self::default()
+        };
+
+        for record_result in rdr.records() {
+            let record = record_result?;
+            let seed = record[columns::SEED].parse::<u32>()?;
+            let category = match Category::parse(&record[columns::CATEGORY]) {
+                Some(category) => category,
+                None => return Err(anyhow!("Invalid category '{}' for seed {}", &record[columns::CATEGORY], seed)),
+            };
+            let enchant = match &record[columns::ENCHANTMENT] {
+                "" => None,
+                value => Some(value.parse::<i8>()?),
+            };
+
+            catalog.seeds.push(seed);
+            catalog.depths.push(record[columns::DEPTH].parse::<u8>()?);
+            catalog.quantities.push(record[columns::QUANTITY].parse::<u32>()?);
+            catalog.categories.push(category);
+            catalog.enchants.push(enchant);
+
+            let columns = TEXT_COLUMNS.iter().copied().chain(COLUMNS..record.len());
+
+            for (column, text) in columns.zip(catalog.texts.iter_mut()) {
+                text.push(&record[column])?;
+            }
+        }
+
+        for text in catalog.texts.iter_mut() {
+            text.lookup = HashMap::new();
+        }
+
+        Ok(catalog)
+    }
+    /// Returns the number of Records.
+    pub fn len(&self) -> usize {
+        self.seeds.len()
+    }
+    /// Returns `true` if the vault number of a Record is empty.
+    #[inline]
+    pub(crate) fn is_vault_empty(&self, row: usize) -> bool {
+        self.texts[3].get(row).is_empty()
+    }
+    /// Rebuilds a Record, as read from the catalog file.
+    pub(crate) fn record(&self, row: usize) -> StringRecord {
+        let mut record = StringRecord::with_capacity(64, self.headers.len());
+        let mut texts = self.texts.iter();
+
+        for column in 0..self.headers.len() {
+            match column {
+                columns::SEED => record.push_field(&self.seeds[row].to_string()),
+                columns::DEPTH => record.push_field(&self.depths[row].to_string()),
+                columns::QUANTITY => record.push_field(&self.quantities[row].to_string()),
+                columns::CATEGORY => record.push_field(&self.categories[row].to_string()),
+                columns::ENCHANTMENT => match self.enchants[row] {
+                    Some(enchant) => record.push_field(&enchant.to_string()),
+                    None => record.push_field(""),
+                },
+                _ => record.push_field(texts.next().map_or("", |text| text.get(row))),
+            }
+        }
+
+        record
+    }
+}
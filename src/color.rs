@@ -0,0 +1,74 @@
+//! Colored output for Brogue Seed Scanner.
+//!
+//! Colors are only used where they help scan long listings (e.g. the categories of a
+//! seed's objects in `show`).  `--color` picks when:  `auto` (the default) colors output
+//! only if standard output is a terminal and the `NO_COLOR` environment variable isn't
+//! set (see <https://no-color.org>), `always` colors it even when piped, and `never`
+//! doesn't color it at all.
+
+use crate::objects::Category;
+use anyhow::{anyhow, Result};
+use std::io::IsTerminal;
+
+/// ANSI escape that resets the terminal color.
+pub const RESET: &str = "\x1b[0m";
+
+/// When to color output (`--color`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorMode {
+    /// Colors output to a terminal, unless `NO_COLOR` is set (the default).
+    Auto,
+    /// Always colors output.
+    Always,
+    /// Never colors output.
+    Never,
+}
+
+impl ColorMode {
+    /// Parses a color mode by name (`auto`, `always`, or `never`).
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(anyhow!(
+                "invalid color mode '{}' (use auto, always, or never)",
+                name
+            )),
+        }
+    }
+    /// Returns `true` if standard output is colored (never in builds without the `color`
+    /// feature).
+    pub fn is_enabled(self) -> bool {
+        if !cfg!(feature = "color") {
+            return false;
+        }
+
+        match self {
+            Self::Auto => !no_color() && std::io::stdout().is_terminal(),
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+/// Returns `true` if the `NO_COLOR` environment variable is set (to anything but an
+/// empty string).
+pub fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// Returns the ANSI color of an object category.
+pub fn category_color(category: Category) -> &'static str {
+    use Category::*;
+
+    match category {
+        Ally => "\x1b[32m",
+        Altar | Key => "\x1b[35m",
+        Armor | Weapon => "\x1b[36m",
+        Charm | Ring | Staff | Wand => "\x1b[34m",
+        Gold => "\x1b[33m",
+        Potion | Scroll => "\x1b[31m",
+        _ => "\x1b[37m",
+    }
+}
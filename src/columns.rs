@@ -0,0 +1,242 @@
+//! Catalog columns for Brogue Seed Scanner.
+//!
+//! Columns are found by their header names rather than their place in the file, so a
+//! Brogue release that reorders the catalog columns can't silently corrupt matching.  A
+//! catalog's `ColumnMap` is built from its header when the file is opened, and each
+//! Record is read into the standard column order below (that of Brogue CE), which the
+//! rest of the scanner indexes by name.
+//!
+//! Columns the scanner doesn't know (e.g. added by a later Brogue release) are kept after
+//! the standard ones, in file order:  they can be filtered on with `--where` by header
+//! name, and are written out with the rest of each Record by `compact`, `split`, and
+//! `convert`.
+//!
+//! Modded Brogue forks whose exporter names its columns or categories differently are
+//! read with a `CatalogSchema`, a `[schema.NAME]` section of the config file
+//! (`brogue-scanner.toml` in the catalog folder, or `--config`) selected with
+//! `--schema NAME`.  Its `columns` table gives the fork's header
+//! name of any standard column it renames, and its `categories` table the Brogue CE
+//! category of any category it renames:
+//! ```toml
+//! [schema.rapid]
+//! columns = { kind = "item_kind", vault_number = "vault" }
+//! categories = { weapons = "weapon", potions = "potion" }
+//! ```
+//! Records are read with the standard header names and categories, so searches (and
+//! catalogs written by `compact`) look just like those of Brogue CE.
+
+use crate::objects::Category;
+use crate::variant::DataPack;
+use anyhow::{anyhow, Context, Result};
+use csv::StringRecord;
+use std::path::Path;
+use std::sync::Arc;
+use toml::Value;
+
+pub const VERSION: usize = 0;
+pub const SEED: usize = 1;
+pub const DEPTH: usize = 2;
+pub const QUANTITY: usize = 3;
+pub const CATEGORY: usize = 4;
+pub const KIND: usize = 5;
+pub const ENCHANTMENT: usize = 6;
+pub const RUNIC: usize = 7;
+pub const VAULT: usize = 8;
+pub const OPENS_VAULT: usize = 9;
+pub const CARRIED_BY: usize = 10;
+pub const ALLY_STATUS: usize = 11;
+pub const MUTATION: usize = 12;
+
+/// Config file, in the (first) catalog folder unless set with `--config`.
+pub const CONFIG_FILE: &str = "brogue-scanner.toml";
+
+/// Header names of the catalog columns, in standard order.
+pub const HEADERS: [&str; 13] = [
+    "dungeon_version",
+    "seed",
+    "depth",
+    "quantity",
+    "category",
+    "kind",
+    "enchantment",
+    "runic",
+    "vault_number",
+    "opens_vault_number",
+    "carried_by_monster_name",
+    "ally_status_name",
+    "mutation_name",
+];
+
+/// Column header names and category names of a catalog exporter (`--schema`).  The
+/// default is that of Brogue CE.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatalogSchema {
+    /// Name of the schema (`None` for Brogue CE).
+    pub name: Option<String>,
+    /// Header name of each standard column, in standard order.
+    headers: Vec<String>,
+    /// Categories renamed by the exporter, with the Brogue CE category of each.
+    categories: Vec<(String, String)>,
+    /// Names of the Brogue variant the catalogs are from, if any (`--variant`).
+    pub variant: Option<Arc<DataPack>>,
+}
+
+impl Default for CatalogSchema {
+    fn default() -> Self {
+        Self {
+            name: None,
+            headers: HEADERS.iter().map(|h| h.to_string()).collect(),
+            categories: Vec::new(),
+            variant: None,
+        }
+    }
+}
+
+impl CatalogSchema {
+    /// Loads the `[schema.NAME]` section of a config file.
+    pub fn load(path: &Path, name: &str) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("could not read config file {:?} (for --schema)", path))?;
+        let table: toml::Table = text
+            .parse()
+            .with_context(|| format!("invalid TOML in config file {:?}", path))?;
+        let section = match table.get("schema").and_then(|schemas| schemas.get(name)) {
+            Some(Value::Table(section)) => section,
+            Some(_) => return Err(anyhow!("'schema.{}' must be a table in config file {:?}", name, path)),
+            None => return Err(anyhow!("no '[schema.{}]' section in config file {:?}", name, path)),
+        };
+        let mut schema = Self {
+            name: Some(name.to_string()),
+            ..Self::default()
+        };
+
+        for (key, value) in section.iter() {
+            match (key.as_str(), value) {
+                ("columns", Value::Table(columns)) => {
+                    for (column, header) in columns.iter() {
+                        let ix = match HEADERS.iter().position(|h| h == column) {
+                            Some(ix) => ix,
+                            None => return Err(anyhow!("unknown column '{}' in schema '{}' ({})", column, name, HEADERS.join(", "))),
+                        };
+                        schema.headers[ix] = string_value(header, name)?;
+                    }
+                }
+                ("categories", Value::Table(categories)) => {
+                    for (category, standard) in categories.iter() {
+                        schema
+                            .categories
+                            .push((category.clone(), string_value(standard, name)?));
+                    }
+                }
+                _ => return Err(anyhow!("invalid key '{}' in schema '{}' (expected 'columns' or 'categories' table)", key, name)),
+            }
+        }
+        // Two columns can't be read from the same file column
+        for (ix, header) in schema.headers.iter().enumerate() {
+            if let Some(other) = schema.headers[..ix].iter().position(|h| h == header) {
+                return Err(anyhow!(
+                    "columns '{}' and '{}' both read '{}' in schema '{}'",
+                    HEADERS[other],
+                    HEADERS[ix],
+                    header,
+                    name
+                ));
+            }
+        }
+
+        Ok(schema)
+    }
+}
+
+/// Returns a schema value that must be a string.
+fn string_value(value: &Value, name: &str) -> Result<String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        _ => Err(anyhow!(
+            "expected a string in schema '{}', found '{}'",
+            name,
+            value
+        )),
+    }
+}
+
+/// Where each catalog column is found in a file, by its header names, and the categories
+/// to rename.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMap {
+    /// File column of each column, in standard order, then of each extra column.
+    order: Vec<usize>,
+    /// Categories renamed by the exporter, with the Brogue CE category of each.
+    categories: Vec<(String, String)>,
+    /// Names of the Brogue variant, if any.
+    variant: Option<Arc<DataPack>>,
+}
+
+impl ColumnMap {
+    /// Builds the map from a catalog's header.  Every standard column must be present,
+    /// once;  any other columns are extras.
+    pub fn from_headers(headers: &StringRecord, schema: &CatalogSchema) -> Result<Self> {
+        let mut order = Vec::with_capacity(headers.len());
+
+        for name in schema.headers.iter() {
+            let mut found = headers
+                .iter()
+                .enumerate()
+                .filter(|(_, h)| h.trim_start_matches('\u{feff}').trim() == name);
+
+            match (found.next(), found.next()) {
+                (Some((ix, _)), None) => order.push(ix),
+                (None, _) => return Err(anyhow!("Invalid Brogue csv header:  no '{}' column", name)),
+                (Some(_), Some(_)) => return Err(anyhow!("Invalid Brogue csv header:  '{}' column repeated", name)),
+            };
+        }
+        let extras: Vec<usize> = (0..headers.len()).filter(|ix| !order.contains(ix)).collect();
+        order.extend(extras);
+
+        Ok(Self {
+            order,
+            categories: schema.categories.clone(),
+            variant: schema.variant.clone(),
+        })
+    }
+    /// Returns `true` if the file's columns are already in standard order, with standard
+    /// names.
+    pub fn is_standard(&self) -> bool {
+        self.categories.is_empty()
+            && self.variant.is_none()
+            && self.order.iter().enumerate().all(|(i, column)| i == *column)
+    }
+    /// Returns the header of the file in standard column order, with the standard header
+    /// names.
+    pub fn headers(&self, headers: &StringRecord) -> StringRecord {
+        let extras = self.order[HEADERS.len()..].iter().map(|ix| &headers[*ix]);
+
+        HEADERS.iter().copied().chain(extras).collect()
+    }
+    /// Returns a Record of the file in standard column order, with its standard category
+    /// (and the Brogue CE names of a variant's kinds, runics, and monsters).
+    pub fn reorder(&self, record: StringRecord) -> StringRecord {
+        if self.is_standard() {
+            return record;
+        }
+
+        let mut fields: Vec<&str> = self.order.iter().map(|ix| &record[*ix]).collect();
+
+        if let Some((_, standard)) = self
+            .categories
+            .iter()
+            .find(|(category, _)| category == fields[CATEGORY])
+        {
+            fields[CATEGORY] = standard;
+        }
+        if let (Some(variant), Some(category)) =
+            (self.variant.as_deref(), Category::parse(fields[CATEGORY]))
+        {
+            variant.translate(category, &mut fields);
+        }
+
+        let mut reordered: StringRecord = fields.into_iter().collect();
+        reordered.set_position(record.position().cloned());
+        reordered
+    }
+}
@@ -0,0 +1,154 @@
+//! Resident daemon mode for Brogue Seed Scanner.
+//!
+//! `brogue-scanner [OPTIONS] daemon` keeps a single `Engine` (and its caches) alive and
+//! answers one request per line of standard input.  A request is a command line without
+//! the program name, e.g. `-a banded +3 --ndjson` or `stats potion life`; the general
+//! options given before `daemon` apply to every request, and the request's own options
+//! come after them.  Each response is the request's normal output, followed by a line
+//! starting with `%%`:
+//!
+//! - `%% ok`:  the request finished
+//! - `%% error: MESSAGE`:  the request failed (the daemon keeps running)
+//!
+//! Empty lines are ignored, and `quit` (or end of input) stops the daemon.  With
+//! `--socket PATH`, the daemon is served over a local socket instead (see `ipc`).  With
+//! `--log FILE`, every request is logged (see `request_log`), and with `--metrics ADDR`,
+//! counters are served for Prometheus (see `metrics`).
+
+use crate::engine::Engine;
+use crate::file_handling::expand_path;
+use crate::new_app;
+use crate::request_log::{LogEntry, RequestLog};
+use anyhow::{anyhow, Result};
+use std::io::{BufRead, Write};
+use std::time::Instant;
+
+/// Returns the arguments before the `daemon` subcommand (the program name and general
+/// options), which are the base of every request.
+pub fn daemon_base_args<I: IntoIterator<Item = String>>(args: I) -> Vec<String> {
+    args.into_iter().take_while(|arg| arg != "daemon").collect()
+}
+
+/// Opens the request log at `path`, with the rotation settings of `daemon` subcommand
+/// matches.
+pub fn open_request_log(path: &str, daemon_matches: &clap::ArgMatches) -> Result<RequestLog> {
+    // LOG_MAX_SIZE and LOG_KEEP have defaults, so are always present.
+    let max_size = match daemon_matches.value_of("log_max_size").unwrap().parse::<u64>() {
+        Ok(mb) if mb > 0 => mb.saturating_mul(1024 * 1024),
+        _ => return Err(anyhow!("--log-max-size must be at least 1 (megabyte)")),
+    };
+    let keep = match daemon_matches.value_of("log_keep").unwrap().parse::<u32>() {
+        Ok(keep) => keep,
+        _ => return Err(anyhow!("--log-keep must be from 0 to 4294967295")),
+    };
+
+    RequestLog::open(expand_path(path)?, max_size, keep)
+}
+
+/// Splits a request line into arguments on whitespace.  Single or double quotes group
+/// words into one argument (e.g. `--where "col:carried_by~goblin conjurer"`).
+pub fn split_line(line: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => arg.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut arg));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                arg.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err(anyhow!("unclosed quote in request '{}'", line));
+    }
+    if in_arg {
+        args.push(arg);
+    }
+
+    Ok(args)
+}
+
+/// Parses request arguments after the base arguments.  Returns the matches and the full
+/// list of arguments.
+pub fn run_args(
+    base_args: &[String],
+    request_args: &[String],
+) -> Result<(clap::ArgMatches<'static>, Vec<String>)> {
+    let mut args = base_args.to_vec();
+    args.extend_from_slice(request_args);
+
+    let matches = new_app()
+        .get_matches_from_safe(args.iter())
+        .map_err(|e| anyhow!("{}", e.message))?;
+
+    Ok((matches, args))
+}
+
+/// Runs a single request line with the engine.
+fn run_request(engine: &mut Engine, base_args: &[String], line: &str) -> Result<()> {
+    let (matches, args) = run_args(base_args, &split_line(line)?)?;
+
+    engine.run(matches, &args)
+}
+
+/// Answers requests from standard input until `quit` or end of input.
+pub fn run_daemon(engine: &mut Engine, base_args: &[String]) -> Result<()> {
+    let stdin = std::io::stdin();
+    let mut id = 0;
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        let line = line.trim();
+
+        match line {
+            "" => continue,
+            "quit" | "exit" => break,
+            _ => (),
+        }
+
+        id += 1;
+        let started = Instant::now();
+        let response = run_request(engine, base_args, line);
+        let mut stdout = std::io::stdout().lock();
+
+        let error = match response {
+            Ok(()) => {
+                writeln!(stdout, "%% ok")?;
+                None
+            }
+            Err(e) => {
+                let message = e.to_string().replace('\n', " ");
+                writeln!(stdout, "%% error: {}", message)?;
+                Some(message)
+            }
+        };
+        stdout.flush()?;
+
+        let status = if error.is_some() { "error" } else { "ok" };
+        engine.record(LogEntry::request(
+            id,
+            &[line.to_string()],
+            started,
+            status,
+            error,
+        ));
+    }
+
+    Ok(())
+}
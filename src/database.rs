@@ -0,0 +1,86 @@
+//! SQLite results database for Brogue Seed Scanner.
+//!
+//! `--save-db FILE` appends every search run to a local SQLite database:  one row in
+//! `queries` per run, and one row in `matches` per matching object.  Because every run
+//! lands in the same database, results can be cross-referenced later, e.g. seeds that
+//! matched both query 1 and query 2:
+//!
+//! ```sql
+//! SELECT seed FROM matches WHERE query_id = 1
+//! INTERSECT
+//! SELECT seed FROM matches WHERE query_id = 2;
+//! ```
+
+use crate::search::{MatchV1, SearchMatch};
+use anyhow::Result;
+use rusqlite::{params, Connection};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CREATE_TABLES: &str = "
+    CREATE TABLE IF NOT EXISTS queries (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        timestamp   INTEGER NOT NULL,
+        query       TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS matches (
+        query_id    INTEGER NOT NULL REFERENCES queries(id),
+        seed        INTEGER NOT NULL,
+        depth       INTEGER NOT NULL,
+        quantity    INTEGER NOT NULL,
+        category    TEXT NOT NULL,
+        kind        TEXT NOT NULL,
+        enchantment INTEGER,
+        runic       TEXT,
+        vault       INTEGER,
+        carried_by  TEXT,
+        ally_status TEXT,
+        mutation    TEXT
+    );
+    CREATE INDEX IF NOT EXISTS matches_seed ON matches(seed);
+";
+
+/// Appends a search run and its matches to the results database at `path`, creating
+/// the database if needed.  Returns the id of the new query.
+pub fn save_matches<P: AsRef<Path>>(path: P, query: &str, matches: &[SearchMatch]) -> Result<i64> {
+    let mut conn = Connection::open(path)?;
+    conn.execute_batch(CREATE_TABLES)?;
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let tx = conn.transaction()?;
+
+    tx.execute(
+        "INSERT INTO queries (timestamp, query) VALUES (?1, ?2)",
+        params![timestamp, query],
+    )?;
+    let query_id = tx.last_insert_rowid();
+
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO matches (query_id, seed, depth, quantity, category, kind, \
+            enchantment, runic, vault, carried_by, ally_status, mutation) \
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        )?;
+
+        for m in matches.iter().map(MatchV1::from_match) {
+            stmt.execute(params![
+                query_id,
+                m.seed,
+                m.depth,
+                m.quantity,
+                m.category,
+                m.kind,
+                m.enchantment,
+                m.runic,
+                m.vault,
+                m.carried_by,
+                m.ally_status,
+                m.mutation,
+            ])?;
+        }
+    }
+
+    tx.commit()?;
+
+    Ok(query_id)
+}
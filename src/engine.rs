@@ -0,0 +1,811 @@
+//! Search engine for Brogue Seed Scanner.
+//!
+//! The `Engine` runs a parsed command line (a search, or the `stats` and `rarity`
+//! subcommands) and keeps what's expensive to load, such as rarity tables, for as long as
+//! it lives.  A one-shot run uses a fresh engine; the `daemon` keeps one resident engine
+//! for every request it answers, which also holds the catalog files it searches in memory
+//! (see `catalog`).
+
+use crate::approx::{approximate, display_approx};
+use crate::brogue_source::update_tables;
+use crate::catalog::Catalog;
+#[cfg(feature = "save-db")]
+use crate::database::save_matches;
+use crate::file_handling::{expand_path, OutputEncoding};
+use crate::index::{refresh_indexes, update_indexes};
+use crate::library::{compact, convert_utf8, split};
+use crate::manifest::{file_stamp, stale_summary};
+use crate::rarity::{RarityTable, display_explain, report_selectivity};
+use crate::search::{
+    SearchParameters, SearchMatch, SummaryV1, OutputMode, finish_output, search_catalogs, search_files, search_files_batch,
+    display_grades, display_matches, display_param_stats, sort_by_required_depth, write_explain, write_machine_matches,
+    write_stats, write_approx, base_args,
+    create_seed_list, load_query_file, load_seed_list, parse_seed, write_export, write_seed_list, write_seeds, SeedSetOp
+};
+use crate::seed_detail::{display_seed, seed_catalog};
+use crate::share;
+use crate::stats::{StatsParameters, collect_stats, display_stats};
+use crate::status::{bad_query, Status};
+use crate::upload::upload_matches;
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+#[cfg(feature = "server")]
+use {
+    crate::metrics::{Cache, Metrics},
+    crate::request_log::{LogEntry, RequestLog},
+    crate::search::{CancelToken, SeedSink, StreamedSeed},
+    std::sync::mpsc::Sender,
+    std::sync::Arc,
+    std::time::Instant,
+};
+
+/// Shared search engine, with caches that persist between runs.
+#[derive(Debug, Default)]
+pub struct Engine {
+    /// Rarity tables by cache path (`None` if there's no table at that path).
+    rarity_tables: HashMap<PathBuf, Option<RarityTable>>,
+    /// Whether searches read catalog files into memory and keep them there.
+    resident: bool,
+    /// In-memory catalogs by file path, with the size and modification time of the file
+    /// they were loaded from.
+    catalogs: HashMap<PathBuf, ((u64, u64), Catalog)>,
+    /// Log of the requests the daemon answers (`daemon --log`), if any.
+    #[cfg(feature = "server")]
+    log: Option<RequestLog>,
+    /// Metrics served by the daemon (`daemon --metrics`), if any.
+    #[cfg(feature = "server")]
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl Engine {
+    /// Makes a new engine with empty caches.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Makes a new engine that keeps the catalog files it searches in memory.
+    #[cfg(feature = "server")]
+    pub fn resident() -> Self {
+        Self {
+            resident: true,
+            ..Self::default()
+        }
+    }
+    /// Starts logging requests (see `request_log`).
+    #[cfg(feature = "server")]
+    pub fn set_log(&mut self, log: RequestLog) {
+        self.log = Some(log);
+    }
+    /// Starts counting requests, scans, and cache lookups (see `metrics`).
+    #[cfg(feature = "server")]
+    pub fn set_metrics(&mut self, metrics: Arc<Metrics>) {
+        self.metrics = Some(metrics);
+    }
+    /// Records an entry in the request log and metrics, if any.  Log errors are reported,
+    /// not returned.
+    #[cfg(feature = "server")]
+    pub fn record(&mut self, entry: LogEntry) {
+        if let Some(metrics) = self.metrics.as_ref() {
+            match (entry.event, entry.status) {
+                ("request", Some(status)) => metrics.add_request(status),
+                ("connection_error", _) => metrics.add_connection_error(),
+                _ => (),
+            }
+        }
+        if let Some(log) = self.log.as_mut() {
+            if let Err(e) = log.write(&entry) {
+                eprintln!("warning: can't write request log:  {:#}", e);
+            }
+        }
+    }
+    /// Counts a cache lookup, if metrics are kept.
+    #[cfg(feature = "server")]
+    fn add_cache_lookup(&self, cache: Cache, hit: bool) {
+        if let Some(metrics) = self.metrics.as_ref() {
+            metrics.add_cache_lookup(cache, hit);
+        }
+    }
+    /// Returns the rarity table cached for a search, loading it on first use.  A table
+    /// whose catalog files have changed is rebuilt with `--rebuild-stale`, and refused
+    /// (`None`) otherwise.
+    fn rarity_table(&mut self, search: &SearchParameters) -> Result<Option<&RarityTable>> {
+        let path = &search.rarity_cache;
+        #[cfg(feature = "server")]
+        self.add_cache_lookup(Cache::Rarity, self.rarity_tables.contains_key(path));
+        let cached = self
+            .rarity_tables
+            .entry(path.clone())
+            .or_insert_with(|| RarityTable::load(path).ok());
+        let stale = match cached.as_ref() {
+            Some(table) => table.stale_files(&search.file_paths),
+            None => return Ok(None),
+        };
+
+        if !stale.is_empty() {
+            let changed = stale_summary(&stale);
+
+            match (search.rebuild_stale, cached.as_ref()) {
+                (true, Some(table)) => {
+                    let table = table.rebuild(search)?;
+                    table.save(path)?;
+                    eprintln!(
+                        "note: rarity table {:?} rebuilt ({} changed).",
+                        path, changed
+                    );
+                    *cached = Some(table);
+                }
+                _ => {
+                    eprintln!(
+                        "warning: rarity table {:?} is out of date ({} changed); run \
+                        'brogue-scanner rarity' or pass --rebuild-stale.",
+                        path, changed
+                    );
+                    return Ok(None);
+                }
+            }
+        }
+
+        Ok(cached.as_ref())
+    }
+    /// Runs a parsed command line.  `args` are the raw arguments (including the program
+    /// name), used to load query files and to label saved results.
+    pub fn run(&mut self, matches: clap::ArgMatches, args: &[String]) -> Result<()> {
+        if let Some(stats_matches) = matches.subcommand_matches("stats").cloned() {
+            return run_stats(matches, &stats_matches);
+        }
+        if matches.subcommand_matches("rarity").is_some() {
+            return self.run_rarity(matches);
+        }
+        if matches.subcommand_matches("index").is_some() {
+            return run_index(matches);
+        }
+        if let Some(compact_matches) = matches.subcommand_matches("compact").cloned() {
+            return run_compact(matches, &compact_matches);
+        }
+        if let Some(split_matches) = matches.subcommand_matches("split").cloned() {
+            return run_split(matches, &split_matches);
+        }
+        if let Some(show_matches) = matches.subcommand_matches("show").cloned() {
+            let seed = show_matches.value_of("seed").unwrap();
+            return run_show(matches.clone(), seed, !show_matches.is_present("no_color"));
+        }
+        if let Some(seed) = matches.value_of("dump_seed") {
+            return run_show(matches.clone(), seed, false);
+        }
+        if let Some(decode_matches) = matches.subcommand_matches("decode").cloned() {
+            return run_decode(matches, &decode_matches);
+        }
+        if let Some(seeds_matches) = matches.subcommand_matches("seeds") {
+            return run_seeds(&matches, seeds_matches);
+        }
+        if let Some(convert_matches) = matches.subcommand_matches("convert").cloned() {
+            return run_convert(matches, &convert_matches);
+        }
+        if let Some(update_matches) = matches.subcommand_matches("self-update") {
+            return run_self_update(update_matches);
+        }
+        if let Some(update_matches) = matches.subcommand_matches("update-tables") {
+            return run_update_tables(update_matches);
+        }
+        if matches.subcommand_matches("daemon").is_some() {
+            return Err(anyhow!("the daemon is already running"));
+        }
+
+        // --- Get Params and Perform Search --- //
+        let mut searches = parse_searches(matches, args)?;
+
+        if searches[0].explain_json {
+            let mut stdout = std::io::stdout().lock();
+            for search in searches.iter() {
+                write_explain(&mut stdout, search)?;
+            }
+            return Ok(());
+        }
+
+        if searches[0].output_mode == OutputMode::Text || searches[0].explain {
+            println!("\n=====  BROGUE SEED SCANNER  =====\n");
+        }
+
+        if searches[0].explain {
+            for search in searches.iter() {
+                println!("{}", search);
+                display_explain(self.rarity_table(search)?, search);
+            }
+            return Ok(());
+        }
+
+        if let Some(percent) = searches[0].approx {
+            let approxes = approximate(&mut searches, percent)?;
+
+            for (search, approx) in searches.iter().zip(approxes.iter()) {
+                match search.output_mode {
+                    OutputMode::Text => {
+                        if let Some(name) = search.name.as_ref() {
+                            println!("=====  QUERY: {}  =====\n", name);
+                        }
+                        display_approx(approx, search);
+                    }
+                    _ => write_approx(&mut std::io::stdout().lock(), approx, search)?,
+                }
+            }
+            return Ok(());
+        }
+
+        // Estimate selectivity up front, if there's a rarity table to estimate from
+        if let Some(table) = self.rarity_table(&searches[0])? {
+            for search in searches.iter() {
+                report_selectivity(table, search);
+            }
+        }
+
+        // Documents replace an output file, while lines are added to it seed by seed
+        for search in searches.iter().filter(|search| !search.output_mode.is_line_based()) {
+            if let Some(path) = search.output.as_ref() {
+                std::fs::File::create(path)
+                    .with_context(|| format!("can't create output {:?}", path))?;
+            }
+        }
+
+        // Searches to relax start over from the search as given
+        let originals: Vec<Option<SearchParameters>> = searches
+            .iter()
+            .map(|search| if search.relax { Some(search.clone()) } else { None })
+            .collect();
+        let mut results = self.search_batch(&mut searches)?;
+        let mut relaxed = vec![Vec::new(); searches.len()];
+
+        for (i, original) in originals.into_iter().enumerate() {
+            if let Some(original) = original {
+                relaxed[i] = self.relax_search(original, &mut searches[i], &mut results[i])?;
+            }
+        }
+
+        for (search, search_matches) in searches
+            .iter()
+            .zip(results.iter_mut())
+            .filter(|(search, _)| search.sort_depth)
+        {
+            sort_by_required_depth(search_matches, search);
+        }
+
+        for ((search, search_matches), relaxed) in
+            searches.iter().zip(results.iter()).zip(relaxed.iter())
+        {
+            report(search, search_matches, relaxed, args)?;
+        }
+
+        write_seed_lists(&searches, &results)?;
+        write_exports(&searches, &results)?;
+
+        // Machine output carries the status in each summary, and an output file leaves
+        // standard output to it
+        let seeds = results
+            .iter()
+            .map(|matches| SummaryV1::from_matches(matches).seeds)
+            .sum();
+        if searches[0].output_mode == OutputMode::Text {
+            println!("Status: {}.", Status::of_seeds(seeds));
+        } else if searches[0].output.is_some() {
+            eprintln!("Status: {}.", Status::of_seeds(seeds));
+        }
+
+        Ok(())
+    }
+    /// Runs the search(es) of a parsed command line without printing anything, and returns
+    /// each search with its matches.  Used by front ends that format results themselves.
+    /// Cancelling `cancel` stops the searches, which keep the matches found so far.
+    ///
+    /// The matches of each seed are also sent to `sender` (if any) as soon as the seed is
+    /// confirmed.  Seeds of `--best` searches are only known once the search ends, and
+    /// are sent then.
+    #[cfg(feature = "server")]
+    pub fn search(
+        &mut self,
+        matches: clap::ArgMatches,
+        args: &[String],
+        cancel: &CancelToken,
+        sender: Option<&Sender<StreamedSeed>>,
+    ) -> Result<Vec<(SearchParameters, Vec<SearchMatch>)>> {
+        if matches.subcommand_name().is_some() {
+            return Err(anyhow!("only searches are supported here"));
+        }
+
+        let mut searches = parse_searches(matches, args)?;
+
+        if searches[0].explain || searches[0].explain_json || searches[0].approx.is_some() {
+            return Err(anyhow!(
+                "--explain, --explain-json, and --approx are not supported here"
+            ));
+        }
+
+        // Machine output mode keeps the search summary from being printed
+        for search in searches.iter_mut() {
+            search.output_mode = OutputMode::Ndjson;
+            search.cancel = cancel.clone();
+        }
+        if let Some(sender) = sender {
+            for search in searches.iter_mut().filter(|search| search.best.is_none()) {
+                search.seed_sink = Some(SeedSink::new(search.name.clone(), sender.clone()));
+            }
+        }
+
+        let results = self.search_batch(&mut searches)?;
+
+        if let Some(sender) = sender {
+            for (search, matches) in searches.iter().zip(results.iter()) {
+                if search.best.is_some() {
+                    SeedSink::new(search.name.clone(), sender.clone()).send(matches);
+                }
+            }
+        }
+
+        Ok(searches.into_iter().zip(results).collect())
+    }
+    /// Loosens a search that found fewer matching seeds than requested (`--relax`) one
+    /// step at a time, searching again after each step, until enough seeds match or nothing
+    /// is left to relax.  Replaces the search and its matches with the last one searched,
+    /// and returns the final value of each constraint relaxed.
+    fn relax_search(
+        &mut self,
+        mut relaxed: SearchParameters,
+        search: &mut SearchParameters,
+        search_matches: &mut Vec<SearchMatch>,
+    ) -> Result<Vec<String>> {
+        let wanted = search.seed_limit().unwrap_or(1);
+        let output_mode = search.output_mode;
+        let mut steps: Vec<(String, String)> = Vec::new();
+
+        while SummaryV1::from_matches(search_matches).seeds < wanted {
+            // Steps relaxing the same constraint in a row are reported as one
+            match (relaxed.relax(), steps.last_mut()) {
+                (Some((constraint, value)), Some(last)) if last.0 == constraint => last.1 = value,
+                (Some(step), _) => steps.push(step),
+                (None, _) => {
+                    steps.push((
+                        "nothing left to relax".to_string(),
+                        format!("fewer than {} seeds match", wanted),
+                    ));
+                    break;
+                }
+            }
+
+            // Machine output mode keeps the search summary from being printed again
+            let mut candidate = relaxed.clone();
+            candidate.output_mode = OutputMode::Ndjson;
+            *search_matches = self
+                .search_batch(std::slice::from_mut(&mut candidate))?
+                .pop()
+                .unwrap_or_default();
+            candidate.output_mode = output_mode;
+            *search = candidate;
+        }
+
+        Ok(steps
+            .into_iter()
+            .map(|(constraint, value)| format!("{}: {}", constraint, value))
+            .collect())
+    }
+    /// Searches the catalog files of the first search with every search (see
+    /// `scan_batch`), timing the scan if metrics are kept.
+    fn search_batch(&mut self, searches: &mut [SearchParameters]) -> Result<Vec<Vec<SearchMatch>>> {
+        #[cfg(feature = "server")]
+        let (started, rows_before) = (
+            Instant::now(),
+            searches.first().map_or(0, |search| search.rows_scanned),
+        );
+        let results = self.scan_batch(searches)?;
+
+        #[cfg(feature = "server")]
+        if let Some(metrics) = self.metrics.as_ref() {
+            let rows = searches.first().map_or(0, |search| search.rows_scanned - rows_before);
+            metrics.add_scan(started.elapsed(), rows);
+        }
+
+        Ok(results)
+    }
+    /// Searches the catalog files of the first search with every search:  from memory if
+    /// the engine is resident, or else from disk.
+    fn scan_batch(&mut self, searches: &mut [SearchParameters]) -> Result<Vec<Vec<SearchMatch>>> {
+        if !self.resident {
+            if searches.first().is_some_and(|search| search.rebuild_stale) {
+                for (path, update) in refresh_indexes(&searches[0])? {
+                    eprintln!(
+                        "note: index {:?} was out of date and has been updated:  {}.",
+                        path, update
+                    );
+                }
+            }
+
+            return match searches {
+                [search] => Ok(vec![search_files(search)?]),
+                _ => search_files_batch(searches),
+            };
+        }
+
+        let (file_paths, format, schema) = match searches.first() {
+            Some(search) => (
+                search.file_paths.clone(),
+                search.format,
+                search.schema.clone(),
+            ),
+            None => return Ok(Vec::new()),
+        };
+
+        // Load new or changed files; files that can't be read are skipped, as in a search
+        for path in file_paths.iter() {
+            let stamp = match file_stamp(path) {
+                Ok(stamp) => stamp,
+                Err(_) => continue,
+            };
+            let current = self
+                .catalogs
+                .get(path)
+                .is_some_and(|(loaded, catalog)| *loaded == stamp && catalog.schema == schema);
+            #[cfg(feature = "server")]
+            self.add_cache_lookup(Cache::Catalog, current);
+            if current {
+                continue;
+            }
+            match Catalog::load(path, format, &schema) {
+                Ok(catalog) => self.catalogs.insert(path.clone(), (stamp, catalog)),
+                Err(_) => self.catalogs.remove(path),
+            };
+        }
+
+        let catalogs: Vec<&Catalog> = file_paths
+            .iter()
+            .filter_map(|path| self.catalogs.get(path).map(|(_, catalog)| catalog))
+            .collect();
+
+        search_catalogs(searches, &catalogs)
+    }
+    /// Runs the `rarity` subcommand, replacing any cached table.
+    fn run_rarity(&mut self, matches: clap::ArgMatches) -> Result<()> {
+        let search = SearchParameters::from_matches(matches)?;
+        let table = RarityTable::build(&search)?;
+        table.save(&search.rarity_cache)?;
+
+        println!(
+            "Rarity table for {} seeds ({} objects) saved to {:?}.",
+            table.seeds,
+            table.objects.len(),
+            search.rarity_cache
+        );
+
+        self.rarity_tables.insert(search.rarity_cache.clone(), Some(table));
+
+        Ok(())
+    }
+}
+
+/// Parses the search(es) of a command line:  one per query file, or a single search.
+fn parse_searches(matches: clap::ArgMatches, args: &[String]) -> Result<Vec<SearchParameters>> {
+    match matches.values_of("query_file") {
+        Some(paths) => {
+            let base_args = base_args(args.iter().cloned());
+            paths
+                .map(|path| load_query_file(expand_path(path)?, &base_args))
+                .collect::<Result<Vec<_>>>()
+                .map_err(bad_query)
+        }
+        None => Ok(vec![
+            SearchParameters::from_matches(matches).map_err(bad_query)?,
+        ]),
+    }
+}
+
+/// Displays (or writes) the matches of a single search, and saves them if requested.
+/// `relaxed` lists the constraints loosened by `--relax`, if any.
+fn report(
+    search: &SearchParameters,
+    search_matches: &[SearchMatch],
+    relaxed: &[String],
+    args: &[String],
+) -> Result<()> {
+    match search.output_mode {
+        OutputMode::Text => {
+            if let Some(name) = search.name.as_ref() {
+                println!("=====  QUERY: {}  =====\n", name);
+            }
+            if !relaxed.is_empty() {
+                println!("Relaxed:\n");
+                for step in relaxed.iter() {
+                    println!("    {}", step);
+                }
+                println!();
+            }
+
+            display_matches(search_matches, search);
+
+            if search.param_stats {
+                display_param_stats(search);
+            }
+
+            if let Some(thresholds) = search.grade.as_ref() {
+                display_grades(search_matches, search, thresholds);
+            }
+        }
+        _ => {
+            if !relaxed.is_empty() {
+                eprintln!("note: relaxed {}.", relaxed.join("; "));
+            }
+            match search.output.as_ref() {
+                Some(path) => {
+                    finish_output(path, search_matches, search)?;
+                    let summary = SummaryV1::from_matches(search_matches);
+                    eprintln!(
+                        "Wrote {} of {} to {:?}.",
+                        search.counted(summary.matches as u64, "match", "matches"),
+                        search.counted(summary.seeds as u64, "seed", "seeds"),
+                        path
+                    );
+                }
+                None => write_machine_matches(&mut std::io::stdout().lock(), search_matches, search)?,
+            }
+        }
+    }
+
+    if let Some(path) = search.save_db.as_ref() {
+        let mut query = args.iter().skip(1).cloned().collect::<Vec<_>>().join(" ");
+
+        if let Some(name) = search.name.as_ref() {
+            query = format!("{} [query: {}]", query, name);
+        }
+
+        let query_id = save_matches(path, &query, search_matches)?;
+
+        if search.output_mode == OutputMode::Text {
+            println!("Saved as query {} in {:?}.", query_id, path);
+        }
+    }
+
+    // A failed upload doesn't lose the results, which are already out
+    if let Some(endpoint) = search.upload.as_ref().filter(|_| !search_matches.is_empty()) {
+        match upload_matches(endpoint, search_matches) {
+            Ok(seeds) if search.output_mode == OutputMode::Text => println!("Uploaded {} seeds to {}.", seeds, endpoint),
+            Ok(_) => (),
+            Err(e) => eprintln!("warning: couldn't upload matches to {}:  {:#}", endpoint, e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the matching seeds of every search with `--write-seedlist`.  Searches writing to
+/// the same file share it.
+fn write_seed_lists(searches: &[SearchParameters], results: &[Vec<SearchMatch>]) -> Result<()> {
+    let mut paths: Vec<&PathBuf> = Vec::new();
+
+    for path in searches.iter().filter_map(|search| search.write_seedlist.as_ref()) {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+
+    for path in paths {
+        let lists = searches
+            .iter()
+            .zip(results.iter())
+            .filter(|(search, _)| search.write_seedlist.as_ref() == Some(path));
+        let seeds = write_seed_list(path, searches[0].out_encoding, lists)?;
+
+        if searches[0].output_mode == OutputMode::Text {
+            println!("Wrote {} seeds to {:?}.", seeds, path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the matches of every search with `--export`.  Searches exporting to the same
+/// file share it.
+fn write_exports(searches: &[SearchParameters], results: &[Vec<SearchMatch>]) -> Result<()> {
+    let mut paths: Vec<&PathBuf> = Vec::new();
+
+    for path in searches.iter().filter_map(|search| search.export.as_ref()) {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+
+    for path in paths {
+        let lists = searches
+            .iter()
+            .zip(results.iter())
+            .filter(|(search, _)| search.export.as_ref() == Some(path))
+            .map(|(_, matches)| matches);
+        let rows = write_export(path, searches[0].out_encoding, lists)?;
+
+        if searches[0].output_mode == OutputMode::Text {
+            println!("Exported {} matches to {:?}.", rows, path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs the `compact` subcommand.
+fn run_compact(matches: clap::ArgMatches, compact_matches: &clap::ArgMatches) -> Result<()> {
+    let search = SearchParameters::from_matches(matches)?;
+    let output = expand_path(compact_matches.value_of("output").unwrap())?;
+    let stats = compact(&search, &output, compact_matches.is_present("gzip"))?;
+
+    println!("Compacted into {:?}:  {}.", output, stats);
+
+    Ok(())
+}
+
+/// Runs the `split` subcommand.
+fn run_split(matches: clap::ArgMatches, split_matches: &clap::ArgMatches) -> Result<()> {
+    let search = SearchParameters::from_matches(matches)?;
+    let output = expand_path(split_matches.value_of("output").unwrap())?;
+    let chunk = match split_matches.value_of("chunk").unwrap().parse::<u32>() {
+        Ok(chunk) if chunk > 0 => chunk,
+        _ => return Err(anyhow!("--chunk must be from 1 to 4294967295")),
+    };
+    let (stats, files) = split(&search, &output, chunk, split_matches.is_present("gzip"))?;
+
+    println!("Split into {} files in {:?}:  {}.", files, output, stats);
+
+    Ok(())
+}
+
+/// Runs the `convert` subcommand.
+fn run_convert(matches: clap::ArgMatches, convert_matches: &clap::ArgMatches) -> Result<()> {
+    let search = SearchParameters::from_matches(matches)?;
+    let output = convert_matches.value_of("output").map(expand_path).transpose()?;
+
+    if output.is_none() && !convert_matches.is_present("in_place") {
+        return Err(anyhow!("convert needs --in-place or --output DIR"));
+    }
+
+    let files = convert_utf8(&search, output.as_deref())?;
+
+    match output {
+        Some(dir) => println!("Converted {} files to UTF-8 in {:?}.", files, dir),
+        None => println!("Converted {} files to UTF-8.", files),
+    }
+
+    Ok(())
+}
+
+/// Runs the `update-tables` subcommand.
+fn run_update_tables(update_matches: &clap::ArgMatches) -> Result<()> {
+    let brogue_src = expand_path(update_matches.value_of("brogue_src").unwrap())?;
+    let output = expand_path(update_matches.value_of("output").unwrap())?;
+    let (files, updates) = update_tables(&brogue_src, &output)?;
+
+    println!("Read {} source files in {:?}:", files, brogue_src);
+    for update in updates.iter() {
+        println!("  {}", update);
+    }
+    println!(
+        "Wrote name tables to {:?} (use its folder with --data-dir).",
+        output
+    );
+
+    Ok(())
+}
+
+/// Runs the `self-update` subcommand.
+#[cfg(feature = "self-update")]
+fn run_self_update(update_matches: &clap::ArgMatches) -> Result<()> {
+    println!(
+        "{}",
+        crate::self_update::self_update(update_matches.is_present("check"))?
+    );
+
+    Ok(())
+}
+
+/// Runs the `self-update` subcommand (only in builds with the `self-update` feature).
+#[cfg(not(feature = "self-update"))]
+fn run_self_update(_update_matches: &clap::ArgMatches) -> Result<()> {
+    Err(anyhow!(
+        "this build can't update itself (built without the 'self-update' feature)"
+    ))
+}
+
+/// Saves the matches of a search with `--save-db` (only in builds with the `save-db`
+/// feature).
+#[cfg(not(feature = "save-db"))]
+fn save_matches(_path: &std::path::Path, _query: &str, _matches: &[SearchMatch]) -> Result<i64> {
+    Err(anyhow!(
+        "this build can't save matches (built without the 'save-db' feature)"
+    ))
+}
+
+/// Runs the `show` subcommand (or `--dump-seed`), coloring objects if `color` is allowed
+/// and `--color` enables it.
+fn run_show(matches: clap::ArgMatches, seed: &str, color: bool) -> Result<()> {
+    let search = SearchParameters::from_matches(matches).map_err(bad_query)?;
+    let seed = parse_seed(seed, "SEED").map_err(bad_query)?;
+    let objects = seed_catalog(&search, seed)?;
+
+    match search.output_mode {
+        OutputMode::Text => {
+            println!("\n=====  BROGUE SEED SCANNER  =====\n");
+            display_seed(seed, &objects, color && search.color.is_enabled());
+        }
+        _ => write_machine_matches(&mut std::io::stdout().lock(), &objects, &search)?,
+    }
+
+    Ok(())
+}
+
+/// Runs the `decode` subcommand, printing the objects of each share string like `show`.
+fn run_decode(matches: clap::ArgMatches, decode_matches: &clap::ArgMatches) -> Result<()> {
+    let search = SearchParameters::from_matches(matches)?;
+    let seeds = decode_matches
+        .values_of("share")
+        .unwrap()
+        .map(share::decode)
+        .collect::<Result<Vec<_>>>()?;
+
+    match search.output_mode {
+        OutputMode::Text => {
+            println!("\n=====  BROGUE SEED SCANNER  =====\n");
+            for objects in seeds.iter() {
+                println!("Dungeon version {}", objects[0].version);
+                display_seed(objects[0].seed, objects, search.color.is_enabled());
+            }
+        }
+        _ => write_machine_matches(&mut std::io::stdout().lock(), &seeds.concat(), &search)?,
+    }
+
+    Ok(())
+}
+
+/// Runs the `seeds` subcommand.
+fn run_seeds(matches: &clap::ArgMatches, seeds_matches: &clap::ArgMatches) -> Result<()> {
+    let op = SeedSetOp::parse(seeds_matches.value_of("operation").unwrap())
+        .ok_or_else(|| anyhow!("invalid seed list operation"))?;
+    let lists = seeds_matches
+        .values_of("files")
+        .unwrap()
+        .map(|path| load_seed_list(expand_path(path)?))
+        .collect::<Result<Vec<_>>>()?;
+    let seeds = op.apply(&lists);
+
+    match seeds_matches.value_of("output") {
+        Some(path) => {
+            let encoding = OutputEncoding::parse(matches.value_of("out_encoding").unwrap())?;
+            let mut writer = create_seed_list(&expand_path(path)?, encoding)?;
+            write_seeds(&mut writer, &seeds)?;
+            writer.into_inner()?.flush()?;
+            println!("Wrote {} seeds to {:?}.", seeds.len(), path);
+        }
+        None => write_seeds(&mut std::io::stdout().lock(), &seeds)?,
+    }
+
+    Ok(())
+}
+
+/// Runs the `index` subcommand.
+fn run_index(matches: clap::ArgMatches) -> Result<()> {
+    let search = SearchParameters::from_matches(matches)?;
+
+    for (path, update) in update_indexes(&search)? {
+        println!("Index {:?} updated:  {}.", path, update);
+    }
+
+    Ok(())
+}
+
+/// Runs the `stats` subcommand.
+fn run_stats(matches: clap::ArgMatches, stats_matches: &clap::ArgMatches) -> Result<()> {
+    let search = SearchParameters::from_matches(matches)?;
+    let params = StatsParameters::from_matches(stats_matches)?;
+    let stats = collect_stats(&search, &params)?;
+
+    match search.output_mode {
+        OutputMode::Text => {
+            println!("\n=====  BROGUE SEED SCANNER  =====\n");
+            display_stats(&stats, &search);
+        }
+        _ => write_stats(&mut std::io::stdout().lock(), &stats, &search)?,
+    }
+
+    Ok(())
+}
@@ -1,14 +1,24 @@
+use crate::archive::{is_zip, split_archive_path, ZipArchive};
+use crate::columns::{CatalogSchema, ColumnMap};
+use crate::status::NoFilesError;
 use anyhow::{anyhow, Result};
-use encoding_rs::Encoding;
+use csv::{ReaderBuilder, StringRecord, StringRecordsIntoIter};
+#[cfg(feature = "utf16")]
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use flate2::read::GzDecoder;
 use std::fmt::Debug;
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read, Write};
 use std::path::{Path, PathBuf};
+#[cfg(feature = "utf16")]
+use std::sync::mpsc::{self, Receiver};
+#[cfg(feature = "utf16")]
+use std::thread;
 
 /// The two file formats that can be used for Brogue CSVs.  Files produced by the
 /// Brogue CE executable produce files in UTF-16LE format, while Rust takes UTF-8 for
 /// its strings (used by CSV readers).
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FileFormat {
     Utf8,
     Utf16, 
@@ -24,11 +34,402 @@ impl FileFormat {
     }
 }
 
+/// Encodings for files the scanner writes (catalogs and seed lists), set by
+/// `--out-encoding`.  Some Windows tools (Excel, PowerShell 5.1) only read UTF-8 files
+/// correctly if they start with a byte order mark (BOM).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputEncoding {
+    /// UTF-8 without a BOM (the default).
+    Utf8,
+    /// UTF-8 with a BOM.
+    Utf8Bom,
+    /// UTF-16LE with a BOM (the format Brogue CE writes).
+    Utf16,
+}
+
+impl OutputEncoding {
+    /// Parses an encoding by name (`utf8`, `utf8-bom`, or `utf16`).
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "utf8" => Ok(Self::Utf8),
+            "utf8-bom" => Ok(Self::Utf8Bom),
+            "utf16" => Ok(Self::Utf16),
+            _ => Err(anyhow!(
+                "invalid output encoding '{}' (use utf8, utf8-bom, or utf16)",
+                name
+            )),
+        }
+    }
+    /// Returns the byte order mark written at the start of a file.
+    fn bom(&self) -> &'static [u8] {
+        match self {
+            Self::Utf8 => &[],
+            Self::Utf8Bom => &[0xEF, 0xBB, 0xBF],
+            Self::Utf16 => &[0xFF, 0xFE],
+        }
+    }
+}
+
+/// Writer that takes UTF-8 text and writes it in an output encoding, starting with the
+/// encoding's byte order mark (if any).
+pub struct EncodedWriter<W: Write> {
+    inner: W,
+    encoding: OutputEncoding,
+    /// Bytes of a character split across writes (UTF-16 only).
+    pending: Vec<u8>,
+}
+
+impl<W: Write> EncodedWriter<W> {
+    /// Makes a new writer, writing the byte order mark.
+    pub fn new(mut inner: W, encoding: OutputEncoding) -> std::io::Result<Self> {
+        inner.write_all(encoding.bom())?;
+
+        Ok(Self {
+            inner,
+            encoding,
+            pending: Vec::new(),
+        })
+    }
+    /// Returns the underlying writer once all text is written.
+    pub fn into_inner(self) -> std::io::Result<W> {
+        match self.pending.is_empty() {
+            true => Ok(self.inner),
+            false => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "incomplete UTF-8 character",
+            )),
+        }
+    }
+}
+
+impl<W: Write> Write for EncodedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.encoding != OutputEncoding::Utf16 {
+            return self.inner.write(buf);
+        }
+
+        self.pending.extend_from_slice(buf);
+
+        // Only whole characters are encoded; a split one waits for the next write
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid UTF-8 text")),
+        };
+        let text = std::str::from_utf8(&self.pending[..valid]).unwrap_or_default();
+        let bytes: Vec<u8> = text.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+
+        self.inner.write_all(&bytes)?;
+        self.pending.drain(..valid);
+
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// CSV reader for a Brogue seed catalog, decoding from UTF-16LE if needed.  Records
+/// (and the header) are read in standard column order, whatever the file's order, with
+/// standard header names and categories.
+pub struct CatalogReader {
+    rdr: csv::Reader<Box<dyn Read>>,
+    columns: ColumnMap,
+    /// Header in standard column order, with standard names.
+    headers: StringRecord,
+}
+
+impl CatalogReader {
+    /// Returns the header, in standard column order.
+    pub fn headers(&mut self) -> csv::Result<&StringRecord> {
+        Ok(&self.headers)
+    }
+    /// Returns an iterator over the Records, in standard column order.
+    pub fn records(&mut self) -> impl Iterator<Item = csv::Result<StringRecord>> + '_ {
+        let columns = &self.columns;
+        self.rdr
+            .records()
+            .map(move |record| record.map(|record| columns.reorder(record)))
+    }
+    /// Returns an owned iterator over the Records, in standard column order.
+    pub fn into_records(self) -> CatalogRecords {
+        CatalogRecords {
+            records: self.rdr.into_records(),
+            columns: self.columns,
+        }
+    }
+}
+
+/// Owned iterator over the Records of a catalog, in standard column order.
+pub struct CatalogRecords {
+    records: StringRecordsIntoIter<Box<dyn Read>>,
+    columns: ColumnMap,
+}
+
+impl Iterator for CatalogRecords {
+    type Item = csv::Result<StringRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let columns = &self.columns;
+        self.records
+            .next()
+            .map(|record| record.map(|record| columns.reorder(record)))
+    }
+}
+
+/// Expands a path given by the user, as a shell would:  a leading `~` to the home folder
+/// (`HOME`, or `USERPROFILE` on Windows), and environment variables written `$NAME`,
+/// `${NAME}`, or `%NAME%` (e.g. `%USERPROFILE%\Documents`) to their values.  Variables
+/// that aren't set are an error, so a path copied from a shell never silently names the
+/// wrong folder.
+pub fn expand_path(value: &str) -> Result<PathBuf> {
+    let is_name = |name: &str| {
+        name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || "_()".contains(c))
+    };
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    if let Some(tail) = rest
+        .strip_prefix('~')
+        .filter(|tail| tail.is_empty() || tail.starts_with(['/', '\\']))
+    {
+        let home = std::env::var("HOME")
+            .or_else(|_| std::env::var("USERPROFILE"))
+            .map_err(|_| anyhow!("can't expand '~' in path '{}':  neither HOME nor USERPROFILE is set", value))?;
+        expanded.push_str(&home);
+        rest = tail;
+    }
+
+    while let Some(ix) = rest.find(['$', '%']) {
+        expanded.push_str(&rest[..ix]);
+        let after = &rest[ix + 1..];
+        let (name, len) = match (&rest[ix..ix + 1], after.strip_prefix('{')) {
+            ("$", Some(braced)) => braced.find('}').map_or(("", 0), |end| (&braced[..end], end + 2)),
+            ("$", None) => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            }
+            _ => after.find('%').map_or(("", 0), |end| (&after[..end], end + 1)),
+        };
+
+        // Anything else (e.g. a lone '%' or '$') is part of the path
+        if !is_name(name) {
+            expanded.push_str(&rest[ix..ix + 1]);
+            rest = after;
+            continue;
+        }
+        let variable = std::env::var(name)
+            .map_err(|_| anyhow!("can't expand '{}' in path '{}':  {} isn't set", &rest[ix..=ix + len], value, name))?;
+        expanded.push_str(&variable);
+        rest = &after[len..];
+    }
+    expanded.push_str(rest);
+
+    Ok(PathBuf::from(expanded))
+}
+
+/// Returns `true` if a catalog file is gzip-compressed (named `*.csv.gz`).
+pub fn is_gzip<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref().extension().is_some_and(|ext| ext == "gz")
+}
+
+/// Opens a file (or a file in a zip archive), decompressing it if it's gzip-compressed.
+pub fn open_raw<P: AsRef<Path>>(path: P) -> Result<Box<dyn Read + Send>> {
+    let file = open_file(path.as_ref())?;
+
+    match is_gzip(&path) {
+        true => Ok(Box::new(GzDecoder::new(file))),
+        false => Ok(file),
+    }
+}
+
+/// Opens a file, or a file in a zip archive (see `archive`), as it's stored.
+pub fn open_file(path: &Path) -> Result<Box<dyn Read + Send>> {
+    match split_archive_path(path) {
+        Some((archive, name)) => ZipArchive::open(archive)?.open_entry(&name),
+        None => Ok(Box::new(File::open(path)?)),
+    }
+}
+
+/// Returns the size of a file in bytes (compressed, for a file in a zip archive), or 0 if
+/// unknown.
+pub fn file_size(path: &Path) -> u64 {
+    match split_archive_path(path) {
+        Some((archive, name)) => ZipArchive::open(archive)
+            .ok()
+            .and_then(|a| a.compressed_size(&name))
+            .unwrap_or(0),
+        None => fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+    }
+}
+
+/// Decodes a UTF-16LE file as UTF-8, keeping or dropping its byte order mark.  The file
+/// is decoded on a thread of its own (see `PipelinedReader`), so decoding overlaps with
+/// the matching of what was decoded before.
+#[cfg(feature = "utf16")]
+pub fn decode_utf16(file: Box<dyn Read + Send>, strip_bom: bool) -> Result<Box<dyn Read>> {
+    let decoder = DecodeReaderBytesBuilder::new()
+        .encoding(Some(encoding_rs::UTF_16LE))
+        .strip_bom(strip_bom)
+        .build(file);
+
+    Ok(Box::new(PipelinedReader::new(decoder)))
+}
+
+/// Bytes decoded per chunk by a `PipelinedReader`.
+#[cfg(feature = "utf16")]
+const PIPELINE_CHUNK: usize = 64 * 1024;
+
+/// Chunks a `PipelinedReader` decodes ahead of its reads, at most.
+#[cfg(feature = "utf16")]
+const PIPELINE_DEPTH: usize = 4;
+
+/// Reads a file decoded on a worker thread, which sends decoded chunks over a bounded
+/// channel.  The worker stops at the end of the file, at the first read error (passed on
+/// to the reader), or once the reader is dropped.
+#[cfg(feature = "utf16")]
+pub struct PipelinedReader {
+    chunks: Receiver<std::io::Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+#[cfg(feature = "utf16")]
+impl PipelinedReader {
+    /// Starts decoding `inner` on a new worker thread.
+    pub fn new<R: Read + Send + 'static>(mut inner: R) -> Self {
+        let (sender, chunks) = mpsc::sync_channel(PIPELINE_DEPTH);
+
+        thread::spawn(move || loop {
+            let mut chunk = vec![0; PIPELINE_CHUNK];
+            let mut len = 0;
+
+            // Fills the chunk, unless the file ends first
+            let result = loop {
+                match inner.read(&mut chunk[len..]) {
+                    Ok(0) => break Ok(()),
+                    Ok(n) => len += n,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                    Err(e) => break Err(e),
+                }
+                if len == chunk.len() {
+                    break Ok(());
+                }
+            };
+            chunk.truncate(len);
+
+            let done = len < PIPELINE_CHUNK || result.is_err();
+            if len > 0 && sender.send(Ok(chunk)).is_err() {
+                break;
+            }
+            if let Err(e) = result {
+                let _ = sender.send(Err(e));
+            }
+            if done {
+                break;
+            }
+        });
+
+        Self {
+            chunks,
+            chunk: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+#[cfg(feature = "utf16")]
+impl Read for PipelinedReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pos == self.chunk.len() {
+            match self.chunks.recv() {
+                Ok(chunk) => self.chunk = chunk?,
+                // The worker is done:  end of file
+                Err(_) => return Ok(0),
+            }
+            self.pos = 0;
+        }
+        let len = buf.len().min(self.chunk.len() - self.pos);
+        buf[..len].copy_from_slice(&self.chunk[self.pos..self.pos + len]);
+        self.pos += len;
+
+        Ok(len)
+    }
+}
+
+/// Decodes a UTF-16LE file as UTF-8 (only in builds with the `utf16` feature).
+#[cfg(not(feature = "utf16"))]
+pub fn decode_utf16(_file: Box<dyn Read + Send>, _strip_bom: bool) -> Result<Box<dyn Read>> {
+    Err(no_utf16())
+}
+
+/// Returns the error for UTF-16 files in builds without the `utf16` feature.
+#[cfg(not(feature = "utf16"))]
+pub fn no_utf16() -> anyhow::Error {
+    anyhow!(
+        "this build can't read UTF-16 files (built without the 'utf16' feature); use UTF-8 catalogs (-U)"
+    )
+}
+
+/// Reads a text file in UTF-8, or in UTF-16 if it starts with a UTF-16 byte order mark.
+#[cfg(feature = "utf16")]
+pub fn read_text<R: Read>(reader: R) -> Result<String> {
+    let mut text = String::new();
+    DecodeReaderBytesBuilder::new()
+        .build(reader)
+        .read_to_string(&mut text)?;
+
+    Ok(text)
+}
+
+/// Reads a text file in UTF-8 (UTF-16 needs the `utf16` feature).
+#[cfg(not(feature = "utf16"))]
+pub fn read_text<R: Read>(mut reader: R) -> Result<String> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+        return Err(no_utf16());
+    }
+    let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF][..]).unwrap_or(&bytes);
+
+    Ok(String::from_utf8(bytes.to_vec())?)
+}
+
+/// Opens a Brogue seed catalog file in the given format and maps its columns by header
+/// name (as named by the schema).
+pub fn open_catalog<P: AsRef<Path>>(
+    path: P,
+    format: FileFormat,
+    schema: &CatalogSchema,
+) -> Result<CatalogReader> {
+    let file = open_raw(path)?;
+    let file: Box<dyn Read> = match format {
+        FileFormat::Utf8 => Box::new(file),
+        FileFormat::Utf16 => decode_utf16(file, false)?,
+    };
+
+    let mut rdr = ReaderBuilder::new().from_reader(file);
+    let columns = ColumnMap::from_headers(rdr.headers()?, schema)?;
+    let headers = columns.headers(rdr.headers()?);
+
+    Ok(CatalogReader {
+        rdr,
+        columns,
+        headers,
+    })
+}
+
 /// Gets list of valid Brogue seed CSV files for a given folder path.  Attempts to
 /// gather files of the specified format (default UTF-16LE), but if no files found,
 /// will switch to the other format (UTF-8).
 ///
-/// Also returns the format that was ultimately chosen (in case intended one failed).
+/// Also returns the format that was ultimately chosen (in case intended one failed).  Files
+/// are sorted by seed range (see `sort_catalog_paths`), so output order doesn't depend on
+/// the order the file system lists them in.
 pub fn get_brogue_csv_paths<P>(
     path: P, 
     nesting_max: usize, 
@@ -37,19 +438,32 @@ pub fn get_brogue_csv_paths<P>(
 where 
     P: AsRef<Path> + Clone + Debug
 {
-    let paths = get_csv_paths(path.clone(), nesting_max, format)?;
+    let mut paths = get_csv_paths(path.clone(), nesting_max, format)?;
+    let mut format = format;
 
-    match paths.is_empty() {
-        false => Ok((paths, format)),
-        true => {
-            let paths = get_csv_paths(path.clone(), nesting_max, format.toggled())?;
-            Ok((paths, format.toggled()))
-        }
-    } 
+    // UTF-16 files can only be read in builds with the `utf16` feature
+    if paths.is_empty() && cfg!(feature = "utf16") {
+        paths = get_csv_paths(path.clone(), nesting_max, format.toggled())?;
+        format = format.toggled();
+    }
+    sort_catalog_paths(&mut paths);
+
+    Ok((paths, format))
+}
+
+/// Sorts catalog file paths by the first seed in their names (see `seed_range_from_name`),
+/// so seeds are scanned in ascending order when the files don't overlap.  Files not named
+/// by a seed range come last, by path.
+pub fn sort_catalog_paths(paths: &mut [PathBuf]) {
+    paths.sort_by_cached_key(|path| {
+        let first = seed_range_from_name(path).map(|(first, _)| first);
+        (first.is_none(), first, path.clone())
+    });
 }
 
 /// Gets list of valid Brogue seed CSV files for a given folder path. Can search 
-/// in nested folders.
+/// in nested folders.  Zip archives (in the folder, or the path itself) are searched like
+/// folders.
 fn get_csv_paths<P>(
     path: P, 
     nesting_max: usize, 
@@ -59,41 +473,81 @@ where
     P: AsRef<Path> + Debug
 {
     let mut nesting_lvl: usize = 0;
-    let file_exts = ["csv"];
     let mut result: Vec<PathBuf> = Vec::new();
 
+    if is_zip(&path) && path.as_ref().is_file() {
+        return archive_csv_paths(path.as_ref(), format);
+    }
+
     if let Ok(entries) = fs::read_dir(&path) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-
-                let path = entry.path();              
-                if path.is_dir() {
-                    if nesting_lvl < nesting_max {
-                        nesting_lvl += 1;
-                        if let Ok(nested) = get_csv_paths(&path, nesting_max, format) {
-                            result.extend(nested.iter().cloned()); 
-                        }
-                    }
-                } else {
-                    // Find all files with matching extensions
-                    if path.extension().is_none() {
-                        continue;
-                    }
-                    let ext = path.extension().unwrap().to_str().expect("UTF-8");
-                    if file_exts.contains(&ext) {
-                        if is_valid_csv_format(&path, format) {
-                            result.push(path);
-                        }            
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                if nesting_lvl < nesting_max {
+                    nesting_lvl += 1;
+                    if let Ok(nested) = get_csv_paths(&path, nesting_max, format) {
+                        result.extend(nested.iter().cloned());
                     }
                 }
+            } else if is_zip(&path) {
+                result.extend(archive_csv_paths(&path, format)?);
+            } else {
+                // Find all files with matching extensions (.csv, or compressed .csv.gz)
+                if is_catalog_name(&path) && is_valid_csv_format(&path, format) {
+                    result.push(path);
+                }
             }
         }
     } else {
-        return Err(anyhow!("couldn't find files in path {:?}", &path));
+        return Err(NoFilesError(format!("couldn't find files in path {:?}", &path)).into());
     }
     Ok(result)
 }
 
+/// Gets list of valid Brogue seed CSV files in a zip archive, as paths in the archive.
+fn archive_csv_paths(path: &Path, format: FileFormat) -> Result<Vec<PathBuf>> {
+    let archive = ZipArchive::open(path)?;
+
+    Ok(archive
+        .names()
+        .map(|name| path.join(name))
+        .filter(|entry| is_catalog_name(entry) && is_valid_csv_format(entry, format))
+        .collect())
+}
+
+/// Returns the seed range in a catalog file's name, if it's named like the files written
+/// by `split` (`NAME-FIRST-LAST.csv`, e.g. `seeds-100001-200000.csv`).
+pub fn seed_range_from_name<P: AsRef<Path>>(path: P) -> Option<(u32, u32)> {
+    let name = path.as_ref().file_name()?.to_str()?;
+    let stem = name.strip_suffix(".csv.gz").or_else(|| name.strip_suffix(".csv"))?;
+    let mut parts = stem.rsplitn(3, '-');
+    let last = parts.next()?.parse::<u32>().ok()?;
+    let first = parts.next()?.parse::<u32>().ok()?;
+
+    match (parts.next(), first <= last) {
+        (Some(_), true) => Some((first, last)),
+        _ => None,
+    }
+}
+
+/// Shuffles file paths, in the same order every time for a given `seed` (`--random-seed`).
+pub fn shuffle_paths(paths: &mut [PathBuf], seed: Option<u64>) {
+    match seed {
+        Some(seed) => fastrand::Rng::with_seed(seed).shuffle(paths),
+        None => fastrand::shuffle(paths),
+    }
+}
+
+/// Returns `true` if a file is named like a catalog file (`*.csv` or `*.csv.gz`).
+fn is_catalog_name(path: &Path) -> bool {
+    let name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+
+    name.ends_with(".csv") || name.ends_with(".csv.gz")
+}
+
 /// Validates a proper Brogue seed catalog file by checking file format.
 ///
 /// CSV file is valid if:
@@ -101,21 +555,21 @@ where
 /// - File format matches specified format (UTF-8 / UTF-16LE by Byte Order Mark (BOM))
 ///
 /// Note that this is a non-exhaustive, perfunctory check.  Headers are checked in the 
-/// `search_files()` function.
+/// `open_catalog()` function.
 fn is_valid_csv_format<P>(path: P, format: FileFormat) -> bool 
 where 
     P: AsRef<Path> + Debug
 {    
-    if let Ok(f) = File::open(&path) {
+    if let Ok(f) = open_raw(&path) {
         let mut reader = BufReader::with_capacity(10, f);
-        reader.fill_buf().unwrap();    
+        if reader.fill_buf().is_err() {
+            return false;
+        }
         let buffer = reader.buffer();
 
-        return match (format, Encoding::for_bom(buffer)) {
-            (FileFormat::Utf16, Some(encoding)) => encoding.0 == encoding_rs::UTF_16LE,
-            (FileFormat::Utf16, None) => false,
-            (FileFormat::Utf8, Some(_)) => true,
-            (FileFormat::Utf8, None) => true,
+        return match format {
+            FileFormat::Utf16 => buffer.starts_with(&[0xFF, 0xFE]),
+            FileFormat::Utf8 => true,
         }
     }
 
@@ -0,0 +1,60 @@
+//! HTTP client for Brogue Seed Scanner.
+//!
+//! Uploads (`--upload`) and `self-update` share a single HTTP client, `ureq`, with TLS
+//! through `rustls` and the Mozilla root certificates built in, so nothing depends on
+//! the tools or certificate stores of the machine.  Builds with neither the `upload` nor
+//! the `self-update` feature leave it out.
+
+use anyhow::{anyhow, Result};
+#[cfg(feature = "self-update")]
+use std::io::Read;
+use std::time::Duration;
+
+/// Time allowed to connect, and for each read or write.
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Largest response read (self-update downloads a release binary).
+#[cfg(feature = "self-update")]
+const MAX_RESPONSE: u64 = 256 * 1024 * 1024;
+
+/// Returns a client, limited to `https://` URLs (redirects included) if `https_only`.
+fn agent(https_only: bool) -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(TIMEOUT)
+        .timeout_read(TIMEOUT)
+        .timeout_write(TIMEOUT)
+        .user_agent(concat!("brogue-scanner/", env!("CARGO_PKG_VERSION")))
+        .https_only(https_only)
+        .build()
+}
+
+/// Downloads a file over HTTPS.
+#[cfg(feature = "self-update")]
+pub fn get(url: &str) -> Result<Vec<u8>> {
+    let response = agent(true).get(url).call().map_err(|e| request_error(url, e))?;
+    let mut body = Vec::new();
+    response.into_reader().take(MAX_RESPONSE).read_to_end(&mut body)?;
+
+    Ok(body)
+}
+
+/// POSTs a JSON document, over HTTPS only (redirects included) if `https_only`.  Any
+/// `2xx` answer counts as accepted.
+#[cfg(feature = "upload")]
+pub fn post_json(url: &str, body: &[u8], https_only: bool) -> Result<()> {
+    agent(https_only)
+        .post(url)
+        .set("Content-Type", "application/json")
+        .send_bytes(body)
+        .map_err(|e| request_error(url, e))?;
+
+    Ok(())
+}
+
+/// Describes a failed request:  the answer of the server, or why there was none.
+fn request_error(url: &str, error: ureq::Error) -> anyhow::Error {
+    match error {
+        ureq::Error::Status(code, response) => anyhow!("{} answered '{} {}'", url, code, response.status_text()),
+        ureq::Error::Transport(e) => anyhow!("couldn't reach {}:  {}", url, e),
+    }
+}
@@ -0,0 +1,550 @@
+//! Catalog index for Brogue Seed Scanner.
+//!
+//! `brogue-scanner [OPTIONS] index` records the seed range of every catalog file in each
+//! `--filepath` folder in a binary index file (`brogue-index.bin` in the folder), along
+//! with a Bloom filter of the object kinds and runics in the file.  Searches then skip
+//! files without opening them if their seeds are all outside the searched range, or if
+//! they can't hold a kind or runic the search requires (common for rare runics).
+//!
+//! Running `index` again only rescans what changed:  files are matched to their entries by
+//! name, and an entry is kept as long as the file's size and modification time are the
+//! same (or, if only the time changed, its checksum is).  New files are added, changed files
+//! rescanned, and entries of removed files dropped.  Files that changed since the index was
+//! last updated are always searched, or with `--rebuild-stale`, the index is updated before
+//! the search (see `manifest`).
+
+use crate::columns::{self, CatalogSchema};
+use crate::file_handling::{open_catalog, FileFormat};
+use crate::manifest::{checksum, file_stamp};
+use crate::objects::Category;
+use crate::search::{CountType, ObjectParameter, SearchParameters};
+use anyhow::{anyhow, Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default index file name, in the catalog folder.
+pub const INDEX_FILE: &str = "brogue-index.bin";
+
+/// Leading bytes of every index file.
+const INDEX_MAGIC: &[u8; 4] = b"BSIX";
+
+/// Version of the index layout.  Indexes of other versions are rebuilt.
+const INDEX_VERSION: u32 = 2;
+
+/// Size of each file's kind filter, in 64-bit words (16,384 bits).
+const FILTER_WORDS: usize = 256;
+
+/// Number of bits set per filter key.
+const FILTER_HASHES: u64 = 4;
+
+/// Filter key tag added to a category's tag for runics (kinds use the category alone).
+const RUNIC_TAG: u8 = 0x40;
+
+/// Bloom filter of the object kinds and runics in a catalog file.  Kind and runic terms
+/// match as substrings, so the filter holds every 3-byte substring (trigram) of each kind
+/// and runic, tagged by category:  a term can only be in the file if all of its trigrams
+/// are.  Terms shorter than 3 bytes are always assumed present.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KindFilter {
+    bits: Vec<u64>,
+}
+
+impl Default for KindFilter {
+    fn default() -> Self {
+        Self {
+            bits: vec![0; FILTER_WORDS],
+        }
+    }
+}
+
+impl KindFilter {
+    /// Adds every trigram of a kind or runic.
+    fn insert(&mut self, tag: u8, text: &str) {
+        for trigram in text.as_bytes().windows(3) {
+            for bit in filter_bits(tag, trigram) {
+                self.bits[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+    }
+    /// Returns `true` if the term might be (part of) a kind or runic in the file.
+    pub fn may_contain(&self, tag: u8, term: &str) -> bool {
+        term.as_bytes().windows(3).all(|trigram| {
+            filter_bits(tag, trigram).all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+        })
+    }
+    /// Returns `true` if the file might hold an object matching the parameter's kind and
+    /// runic terms.
+    pub fn may_match(&self, param: &ObjectParameter) -> bool {
+        // Only single categories are tagged (`item` and `equipment` have no kind terms)
+        if param.category_flags != param.category.to_flags() {
+            return true;
+        }
+
+        let tag = category_tag(param.category);
+
+        param.kind.as_ref().is_none_or(|kind| self.may_contain(tag, kind))
+            && (param.runic.is_none()
+                || param.runics().any(|runic| self.may_contain(tag + RUNIC_TAG, runic)))
+    }
+}
+
+/// Returns the filter key tag of a category.
+fn category_tag(category: Category) -> u8 {
+    category as u8
+}
+
+/// Returns the filter bits of a tagged trigram (FNV-1a, with double hashing).
+fn filter_bits(tag: u8, trigram: &[u8]) -> impl Iterator<Item = usize> {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+
+    for byte in std::iter::once(&tag).chain(trigram.iter()) {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+
+    let (h1, h2) = (hash, (hash >> 32) | 1);
+    let bits = (FILTER_WORDS * 64) as u64;
+
+    (0..FILTER_HASHES).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % bits) as usize)
+}
+
+/// Index entry for a single catalog file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileEntry {
+    /// File name, relative to the catalog folder.
+    pub name: String,
+    /// File size in bytes.
+    pub size: u64,
+    /// Modification time, in nanoseconds since the Unix epoch (0 if unknown).
+    pub modified: u64,
+    /// FNV-1a hash of the file's contents.
+    pub checksum: u64,
+    /// Lowest and highest seed in the file.
+    pub seed_min: u32,
+    pub seed_max: u32,
+    /// Number of seeds in the file.
+    pub seeds: u32,
+    /// Kinds and runics in the file.
+    pub filter: KindFilter,
+}
+
+impl FileEntry {
+    /// Scans a catalog file into a new entry.
+    fn scan(
+        path: &Path,
+        name: String,
+        stamp: (u64, u64),
+        format: FileFormat,
+        schema: &CatalogSchema,
+    ) -> Result<Self> {
+        let mut rdr = open_catalog(path, format, schema)?;
+        let (mut seed_min, mut seed_max, mut seeds) = (u32::MAX, 0, 0);
+        let mut prev_seed = None;
+        let mut filter = KindFilter::default();
+        // Kinds and runics already in the filter
+        let mut added: HashSet<(u8, String)> = HashSet::new();
+
+        for record_result in rdr.records() {
+            let record = record_result?;
+            let seed = record[columns::SEED].parse::<u32>()?;
+
+            if prev_seed != Some(seed) {
+                prev_seed = Some(seed);
+                seeds += 1;
+            }
+            seed_min = seed_min.min(seed);
+            seed_max = seed_max.max(seed);
+
+            let tag = match Category::parse(&record[columns::CATEGORY]) {
+                Some(category) => category_tag(category),
+                None => return Err(anyhow!("Invalid category '{}' for seed {}", &record[columns::CATEGORY], seed)),
+            };
+
+            for (tag, text) in [
+                (tag, &record[columns::KIND]),
+                (tag + RUNIC_TAG, &record[columns::RUNIC]),
+            ] {
+                if !text.is_empty() && !added.contains(&(tag, text.to_string())) {
+                    filter.insert(tag, text);
+                    added.insert((tag, text.to_string()));
+                }
+            }
+        }
+
+        if seeds == 0 {
+            seed_min = 0;
+        }
+
+        let (size, modified) = stamp;
+        let checksum = checksum(path)?;
+
+        Ok(Self {
+            name,
+            size,
+            modified,
+            checksum,
+            seed_min,
+            seed_max,
+            seeds,
+            filter,
+        })
+    }
+    /// Returns `true` if the entry matches a file's current size and modification time.
+    pub fn is_current(&self, stamp: (u64, u64)) -> bool {
+        (self.size, self.modified) == stamp
+    }
+    /// Returns `true` if any seed in the file is in the given range.
+    pub fn overlaps(&self, seed_min: u32, seed_max: u32) -> bool {
+        self.seeds > 0 && self.seed_min <= seed_max && self.seed_max >= seed_min
+    }
+    /// Returns the number of seeds the search would scan in the file if no seed in it can
+    /// match, or `None` if the file has to be searched.
+    fn skipped_seeds(&self, search: &SearchParameters) -> Option<u64> {
+        if search.is_complete() || !self.overlaps(search.seed_min, search.seed_max) {
+            return Some(0);
+        }
+
+        // Seeds scanned can't be counted without reading the file, unless the seed list
+        // names none of its seeds
+        if let Some(seeds) = search.seed_list.as_ref() {
+            let first = seeds.partition_point(|seed| *seed < self.seed_min);
+            return match seeds.get(first) {
+                Some(seed) if *seed <= self.seed_max => None,
+                _ => Some(0),
+            };
+        }
+
+        // Every seed in the file must be in range, so that the seed count is exact.  With a
+        // seed step, the file's seeds must also have no gaps.
+        if self.seed_min < search.seed_min || self.seed_max > search.seed_max {
+            return None;
+        }
+        let seeds = match search.seed_step {
+            1 => u64::from(self.seeds),
+            _ if u64::from(self.seeds) == u64::from(self.seed_max - self.seed_min) + 1 => {
+                search.stepped_seeds(self.seed_min, self.seed_max)
+            }
+            _ => return None,
+        };
+
+        // Only objects the logic needs (not under `||` or `!`) can rule a file out
+        let required = |(i, param): &(usize, &ObjectParameter)| {
+            search.logic.requires(*i)
+                && param.count_target > 0
+                && !param.totalled
+                && !param.optional
+                && matches!(param.count_type, CountType::AtLeast | CountType::EqualTo)
+        };
+
+        match search
+            .object_params
+            .iter()
+            .enumerate()
+            .filter(required)
+            .any(|(_, param)| !self.filter.may_match(param))
+        {
+            true => Some(seeds),
+            false => None,
+        }
+    }
+}
+
+/// Changes made to an index by an update.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct IndexUpdate {
+    pub added: usize,
+    pub rescanned: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+    /// Files that couldn't be read (left out of the index).
+    pub unreadable: usize,
+}
+
+impl std::fmt::Display for IndexUpdate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} added, {} rescanned, {} removed, {} unchanged",
+            self.added, self.rescanned, self.removed, self.unchanged
+        )?;
+        match self.unreadable {
+            0 => Ok(()),
+            n => write!(f, " ({} unreadable)", n),
+        }
+    }
+}
+
+/// Index of the catalog files in a single folder.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CatalogIndex {
+    /// Entries, sorted by name.
+    pub entries: Vec<FileEntry>,
+}
+
+impl CatalogIndex {
+    /// Returns the entry for a file name, if any.
+    pub fn entry(&self, name: &str) -> Option<&FileEntry> {
+        self.entries
+            .binary_search_by(|entry| entry.name.as_str().cmp(name))
+            .ok()
+            .map(|i| &self.entries[i])
+    }
+    /// Brings the index up to date with the given catalog files (all in `dir`), only
+    /// scanning files that are new or changed.  Files that can't be read are left out.
+    pub fn update(
+        &mut self,
+        dir: &Path,
+        file_paths: &[PathBuf],
+        format: FileFormat,
+        schema: &CatalogSchema,
+        debug: bool,
+    ) -> IndexUpdate {
+        let mut old: HashMap<String, FileEntry> = std::mem::take(&mut self.entries)
+            .into_iter()
+            .map(|entry| (entry.name.clone(), entry))
+            .collect();
+        let mut update = IndexUpdate::default();
+
+        for path in file_paths.iter() {
+            let name = match relative_name(dir, path) {
+                Some(name) => name,
+                None => continue,
+            };
+            let stamp = match file_stamp(path) {
+                Ok(stamp) => stamp,
+                Err(_) => {
+                    update.unreadable += 1;
+                    continue;
+                }
+            };
+
+            // An unchanged file keeps its entry; a touched file keeps it if its contents
+            // are the same
+            let (indexed, kept) = match old.remove(&name) {
+                Some(entry) if entry.is_current(stamp) => (true, Some(entry)),
+                Some(mut entry)
+                    if entry.size == stamp.0 && checksum(path).ok() == Some(entry.checksum) =>
+                {
+                    entry.modified = stamp.1;
+                    (true, Some(entry))
+                }
+                Some(_) => (true, None),
+                None => (false, None),
+            };
+
+            if let Some(entry) = kept {
+                update.unchanged += 1;
+                self.entries.push(entry);
+                continue;
+            }
+
+            if debug {
+                println!("indexing file: {:?}", path);
+            }
+            match FileEntry::scan(path, name, stamp, format, schema) {
+                Ok(entry) => {
+                    self.entries.push(entry);
+                    match indexed {
+                        true => update.rescanned += 1,
+                        false => update.added += 1,
+                    }
+                }
+                Err(_) => update.unreadable += 1,
+            }
+        }
+
+        update.removed = old.len();
+        self.entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        update
+    }
+    /// Loads an index file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).with_context(|| format!("could not read index {:?}", path))?;
+
+        Self::decode(&bytes).with_context(|| format!("invalid index {:?}", path))
+    }
+    /// Saves the index to a file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, self.encode()).with_context(|| format!("could not write index {:?}", path))
+    }
+    /// Encodes the index (all numbers little-endian; names are length-prefixed UTF-8).
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(12 + self.entries.len() * (64 + FILTER_WORDS * 8));
+        bytes.extend_from_slice(INDEX_MAGIC);
+        bytes.extend_from_slice(&INDEX_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+
+        for entry in self.entries.iter() {
+            bytes.extend_from_slice(&(entry.name.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(entry.name.as_bytes());
+            bytes.extend_from_slice(&entry.size.to_le_bytes());
+            bytes.extend_from_slice(&entry.modified.to_le_bytes());
+            bytes.extend_from_slice(&entry.checksum.to_le_bytes());
+            bytes.extend_from_slice(&entry.seed_min.to_le_bytes());
+            bytes.extend_from_slice(&entry.seed_max.to_le_bytes());
+            bytes.extend_from_slice(&entry.seeds.to_le_bytes());
+            for word in entry.filter.bits.iter() {
+                bytes.extend_from_slice(&word.to_le_bytes());
+            }
+        }
+
+        bytes
+    }
+    /// Decodes an index.
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut decoder = Decoder { bytes };
+
+        if decoder.take(4)? != INDEX_MAGIC {
+            return Err(anyhow!("not a catalog index"));
+        }
+        if decoder.u32()? != INDEX_VERSION {
+            return Err(anyhow!("index is from another version; rebuild it"));
+        }
+
+        let count = decoder.u32()? as usize;
+        let mut entries = Vec::with_capacity(count.min(bytes.len()));
+
+        for _ in 0..count {
+            let len = decoder.u32()? as usize;
+            let name = String::from_utf8(decoder.take(len)?.to_vec())?;
+
+            entries.push(FileEntry {
+                name,
+                size: decoder.u64()?,
+                modified: decoder.u64()?,
+                checksum: decoder.u64()?,
+                seed_min: decoder.u32()?,
+                seed_max: decoder.u32()?,
+                seeds: decoder.u32()?,
+                filter: KindFilter {
+                    bits: (0..FILTER_WORDS).map(|_| decoder.u64()).collect::<Result<_>>()?,
+                },
+            });
+        }
+
+        Ok(Self { entries })
+    }
+}
+
+/// Reads values from the front of a byte slice.
+struct Decoder<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.bytes.len() {
+            return Err(anyhow!("index is truncated"));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+}
+
+/// Returns a file's name relative to its catalog folder (`None` if it isn't in the folder).
+fn relative_name(dir: &Path, path: &Path) -> Option<String> {
+    path.strip_prefix(dir)
+        .ok()
+        .and_then(|name| name.to_str())
+        .map(String::from)
+}
+
+/// Updates (or creates) the index of every catalog folder of a search, and returns each
+/// index path with the changes made.
+pub fn update_indexes(search: &SearchParameters) -> Result<Vec<(PathBuf, IndexUpdate)>> {
+    let mut updates = Vec::with_capacity(search.file_dirs.len());
+
+    for (dir, file_paths) in search.file_dirs.iter().zip(search.dir_groups(&search.file_paths)) {
+        let path = dir.join(INDEX_FILE);
+        // An unreadable (or outdated) index is rebuilt from scratch
+        let mut index = CatalogIndex::load(&path).unwrap_or_default();
+        let update = index.update(
+            dir,
+            &file_paths,
+            search.format,
+            &search.schema,
+            search.debug,
+        );
+
+        index.save(&path)?;
+        updates.push((path, update));
+    }
+
+    Ok(updates)
+}
+
+/// Updates the indexes of a search's catalog folders that have one and are out of date
+/// (`--rebuild-stale`).  Returns each index updated, with its changes.
+pub fn refresh_indexes(search: &SearchParameters) -> Result<Vec<(PathBuf, IndexUpdate)>> {
+    let mut updates = Vec::new();
+
+    for (dir, file_paths) in search.file_dirs.iter().zip(search.dir_groups(&search.file_paths)) {
+        let path = dir.join(INDEX_FILE);
+        if !path.is_file() {
+            continue;
+        }
+
+        let mut index = CatalogIndex::load(&path).unwrap_or_default();
+        let previous = index.clone();
+        let update = index.update(
+            dir,
+            &file_paths,
+            search.format,
+            &search.schema,
+            search.debug,
+        );
+
+        // Touched files only need their new modification times saved
+        if index != previous {
+            index.save(&path)?;
+            updates.push((path, update));
+        }
+    }
+
+    Ok(updates)
+}
+
+/// Indexes of a search's catalog folders, used to skip files while searching.
+#[derive(Debug, Default)]
+pub struct CatalogIndexes {
+    /// Catalog folders, each with its index (if it has a readable one).
+    dirs: Vec<(PathBuf, Option<CatalogIndex>)>,
+}
+
+impl CatalogIndexes {
+    /// Loads the index of each catalog folder.
+    pub fn load(file_dirs: &[PathBuf]) -> Self {
+        let dirs = file_dirs
+            .iter()
+            .map(|dir| (dir.clone(), CatalogIndex::load(dir.join(INDEX_FILE)).ok()))
+            .collect();
+
+        Self { dirs }
+    }
+    /// Returns the number of seeds each search would scan in a file if the file can be
+    /// skipped:  its index entry is current, and for every search, either no seed in the
+    /// file is in range, or the file can't hold a kind or runic the search requires.
+    /// Returns `None` if the file has to be searched.
+    pub fn skipped_seeds(&self, path: &Path, searches: &[SearchParameters]) -> Option<Vec<u64>> {
+        let (dir, index) = self.dirs.iter().find(|(dir, _)| path.starts_with(dir))?;
+        let entry = index.as_ref()?.entry(&relative_name(dir, path)?)?;
+
+        if !file_stamp(path).is_ok_and(|stamp| entry.is_current(stamp)) {
+            return None;
+        }
+
+        searches.iter().map(|search| entry.skipped_seeds(search)).collect()
+    }
+}
@@ -0,0 +1,375 @@
+//! Local IPC protocol for the daemon.
+//!
+//! `brogue-scanner [OPTIONS] daemon --socket PATH` serves the daemon's engine over a unix
+//! domain socket, so editors and bots on the same machine can query seeds without going
+//! through stdin.  Connections are served one at a time.  Every message, in either
+//! direction, is a frame:  a 4-byte big-endian length followed by that many bytes of UTF-8
+//! JSON, tagged by `type`.
+//!
+//! Requests:
+//!
+//! - `query`:  `id` (integer, chosen by the client), `args` (array of strings:  search
+//!   options as on the command line, after the daemon's general options)
+//! - `cancel`:  `id` of a queued or running query
+//!
+//! Responses (each carries the `id` of its query):
+//!
+//! - `match`:  one matching object, with the fields of a version 1 `match` record (see
+//!   `search::schema`), plus `query` when the search comes from a query file
+//! - `done`:  the query finished, with its `seeds` and `matches` totals
+//! - `cancelled`:  the query was cancelled, and nothing more is sent for it
+//! - `error`:  `message` (string); `id` is null if the request itself couldn't be read
+//!
+//! Queries run in the order received.  The `match` frames of each seed are sent as soon as
+//! the scan confirms it (for `--best` searches, and seeds found by parallel workers, once
+//! they're ranked or merged).  A running scan is stopped by `cancel`, and no more of its
+//! matches are sent.
+//!
+//! Only unix domain sockets are served (the module, and `--socket`, are unix-only):
+//! Windows named pipes are out of scope, and Windows clients can use the daemon's
+//! standard input instead.
+
+use crate::daemon::run_args;
+use crate::engine::Engine;
+use crate::request_log::LogEntry;
+use crate::search::{CancelToken, MatchV1, StreamedSeed};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Largest frame accepted, in bytes.
+const FRAME_MAX: usize = 16 * 1024 * 1024;
+
+/// A client request.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    Query { id: u64, args: Vec<String> },
+    Cancel { id: u64 },
+}
+
+/// A daemon response.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Match {
+        id: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        query: Option<String>,
+        #[serde(flatten)]
+        record: MatchV1,
+    },
+    Done {
+        id: u64,
+        seeds: usize,
+        matches: usize,
+    },
+    Cancelled {
+        id: u64,
+    },
+    Error {
+        id: Option<u64>,
+        message: String,
+    },
+}
+
+/// Reads a single frame.  Returns `None` at end of input (between frames).
+pub fn read_frame<R: Read>(reader: &mut R) -> Result<Option<Vec<u8>>> {
+    let mut len_bytes = [0u8; 4];
+    let mut read = 0;
+
+    while read < len_bytes.len() {
+        match reader.read(&mut len_bytes[read..]) {
+            Ok(0) if read == 0 => return Ok(None),
+            Ok(0) => return Err(anyhow!("input ended inside a frame length")),
+            Ok(n) => read += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let len = u32::from_be_bytes(len_bytes) as usize;
+
+    if len > FRAME_MAX {
+        return Err(anyhow!(
+            "frame of {} bytes is over the {} byte limit",
+            len,
+            FRAME_MAX
+        ));
+    }
+
+    let mut frame = vec![0u8; len];
+    reader.read_exact(&mut frame)?;
+
+    Ok(Some(frame))
+}
+
+/// Writes a value as a single JSON frame.
+pub fn write_frame<W: Write, T: Serialize>(writer: &mut W, value: &T) -> Result<()> {
+    let json = serde_json::to_vec(value)?;
+
+    writer.write_all(&(json.len() as u32).to_be_bytes())?;
+    writer.write_all(&json)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Incoming messages for a connection, read on their own thread so that cancels arrive
+/// while a query is running.
+enum Incoming {
+    Request(Request),
+    Invalid(String),
+}
+
+/// Serves a single connection until the client disconnects.
+pub fn serve<R, W>(
+    engine: &mut Engine,
+    base_args: &[String],
+    reader: R,
+    writer: &mut W,
+) -> Result<()>
+where
+    R: Read + Send + 'static,
+    W: Write,
+{
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut reader = reader;
+
+        loop {
+            // A bad frame can't be skipped, so it's reported and ends the connection
+            let (incoming, last) = match read_frame(&mut reader) {
+                Ok(Some(frame)) => match serde_json::from_slice::<Request>(&frame) {
+                    Ok(request) => (Incoming::Request(request), false),
+                    Err(e) => (Incoming::Invalid(e.to_string()), false),
+                },
+                Ok(None) => break,
+                Err(e) => (Incoming::Invalid(e.to_string()), true),
+            };
+            if sender.send(incoming).is_err() || last {
+                break;
+            }
+        }
+    });
+
+    let mut queue: VecDeque<(u64, Vec<String>)> = VecDeque::new();
+    // Cancels for queries that aren't queued (e.g. already finished) are ignored
+    let mut cancelled: Vec<u64> = Vec::new();
+
+    loop {
+        // Take every request that has arrived, waiting only if there's nothing to run
+        let mut disconnected = false;
+
+        loop {
+            let incoming = match queue.is_empty() {
+                true => receiver.recv().map_err(|_| TryRecvError::Disconnected),
+                false => receiver.try_recv(),
+            };
+            match incoming {
+                Ok(incoming) => handle_incoming(incoming, &mut queue, &mut cancelled, writer)?,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    disconnected = true;
+                    break;
+                }
+            }
+        }
+
+        match queue.pop_front() {
+            Some((id, args)) => {
+                let started = Instant::now();
+                let (status, error) =
+                    match run_query(engine, base_args, id, &args, &receiver, &mut queue, writer)? {
+                        QueryEnd::Done => ("ok", None),
+                        QueryEnd::Cancelled => ("cancelled", None),
+                        QueryEnd::Error(message) => ("error", Some(message)),
+                    };
+                engine.record(LogEntry::request(id, &args, started, status, error));
+            }
+            None if disconnected => return Ok(()),
+            None => (),
+        }
+        cancelled.clear();
+    }
+}
+
+/// Queues a query, or handles a cancel or invalid request.
+fn handle_incoming<W: Write>(
+    incoming: Incoming,
+    queue: &mut VecDeque<(u64, Vec<String>)>,
+    cancelled: &mut Vec<u64>,
+    writer: &mut W,
+) -> Result<()> {
+    match incoming {
+        Incoming::Request(Request::Query { id, args }) => queue.push_back((id, args)),
+        Incoming::Request(Request::Cancel { id }) => {
+            if let Some(index) = queue.iter().position(|(queued, _)| *queued == id) {
+                queue.remove(index);
+                write_frame(writer, &Response::Cancelled { id })?;
+            } else {
+                cancelled.push(id);
+            }
+        }
+        Incoming::Invalid(message) => write_frame(writer, &Response::Error { id: None, message })?,
+    }
+
+    Ok(())
+}
+
+/// How a query ended.
+enum QueryEnd {
+    Done,
+    Cancelled,
+    Error(String),
+}
+
+/// Runs a single query, streaming the matches of each seed as it's confirmed, and stopping
+/// early if it's cancelled.
+fn run_query<W: Write>(
+    engine: &mut Engine,
+    base_args: &[String],
+    id: u64,
+    args: &[String],
+    receiver: &Receiver<Incoming>,
+    queue: &mut VecDeque<(u64, Vec<String>)>,
+    writer: &mut W,
+) -> Result<QueryEnd> {
+    let cancel = CancelToken::new();
+    let (seed_sender, seed_receiver) = mpsc::channel();
+    let mut cancelled = Vec::new();
+    let (mut seeds, mut matches) = (0, 0);
+
+    // The scan runs on its own thread, so its seeds are sent while it runs and a cancel
+    // arriving meanwhile can stop it
+    let results = thread::scope(|scope| {
+        let search = scope.spawn(|| {
+            run_args(base_args, args).and_then(|(matches, args)| {
+                engine.search(matches, &args, &cancel, Some(&seed_sender))
+            })
+        });
+
+        let mut stream = || -> Result<()> {
+            loop {
+                // Seeds sent before the scan finished are all received by now
+                let finished = search.is_finished();
+
+                while let Ok(seed) = seed_receiver.try_recv() {
+                    // Nothing more is sent for a cancelled query
+                    if !cancelled.contains(&id) {
+                        matches += write_seed(writer, id, seed)?;
+                        seeds += 1;
+                    }
+                }
+                if finished {
+                    return Ok(());
+                }
+                while let Ok(incoming) = receiver.try_recv() {
+                    handle_incoming(incoming, queue, &mut cancelled, writer)?;
+                }
+                if cancelled.contains(&id) {
+                    cancel.cancel();
+                }
+                if let Ok(seed) = seed_receiver.recv_timeout(Duration::from_millis(20)) {
+                    if !cancelled.contains(&id) {
+                        matches += write_seed(writer, id, seed)?;
+                        seeds += 1;
+                    }
+                }
+            }
+        };
+
+        // A connection that can't be written to has no use for the rest of the scan
+        let streamed = stream();
+        if streamed.is_err() {
+            cancel.cancel();
+        }
+        let results = search
+            .join()
+            .unwrap_or_else(|_| Err(anyhow!("search thread panicked")));
+
+        streamed.map(|_| results)
+    })?;
+
+    if cancelled.contains(&id) {
+        write_frame(writer, &Response::Cancelled { id })?;
+        return Ok(QueryEnd::Cancelled);
+    }
+    if let Err(e) = results {
+        let message = e.to_string();
+        write_frame(
+            writer,
+            &Response::Error {
+                id: Some(id),
+                message: message.clone(),
+            },
+        )?;
+        return Ok(QueryEnd::Error(message));
+    }
+
+    write_frame(writer, &Response::Done { id, seeds, matches })?;
+
+    Ok(QueryEnd::Done)
+}
+
+/// Writes a `match` frame for each match of a seed, and returns the number written.
+fn write_seed<W: Write>(writer: &mut W, id: u64, seed: StreamedSeed) -> Result<usize> {
+    for m in seed.matches.iter() {
+        write_frame(
+            writer,
+            &Response::Match {
+                id,
+                query: seed.name.clone(),
+                record: MatchV1::from_match(m),
+            },
+        )?;
+    }
+
+    Ok(seed.matches.len())
+}
+
+/// Listens on a unix domain socket, serving one connection at a time.
+pub fn run_socket(engine: &mut Engine, base_args: &[String], path: &Path) -> Result<()> {
+    let listener = bind_socket(path)?;
+    eprintln!("listening on {:?}", path);
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let reader = stream.try_clone()?;
+        let mut writer = stream;
+
+        if let Err(e) = serve(engine, base_args, reader, &mut writer) {
+            eprintln!("connection error: {}", e);
+            engine.record(LogEntry::event("connection_error", Some(e.to_string())));
+        }
+    }
+
+    Ok(())
+}
+
+/// Binds a unix domain socket that only its owner can connect to.  A stale socket from a
+/// previous run is replaced, but anything else at the path is left alone.
+pub(crate) fn bind_socket(path: &Path) -> Result<std::os::unix::net::UnixListener> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixListener;
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => return Err(anyhow!("can't listen on {:?}:  it exists and isn't a socket", path)),
+        Err(_) => (),
+    }
+
+    // The socket is created without group or other permissions (changing them after
+    // binding would leave a window for other users to connect)
+    let mask = unsafe { libc::umask(0o177) };
+    let listener = UnixListener::bind(path);
+    unsafe { libc::umask(mask) };
+
+    listener.map_err(|e| anyhow!("can't listen on {:?}:  {}", path, e))
+}
@@ -0,0 +1,379 @@
+//! Catalog library maintenance for Brogue Seed Scanner.
+//!
+//! `brogue-scanner [OPTIONS] compact OUTPUT` merges every catalog file in the `--filepath`
+//! folder(s) into a single UTF-8 catalog, in ascending seed order.  Seeds found in more
+//! than one file (overlapping seed ranges) are only written once, taken from the first file
+//! listed.  With `--gzip`, the output is compressed (name it `*.csv.gz`); compressed
+//! catalogs are searched like any other.
+//!
+//! `brogue-scanner [OPTIONS] split --chunk SEEDS OUTPUT` merges the catalog files the same
+//! way, but divides them into files of a fixed seed range each (seeds 1 to SEEDS, and so on)
+//! in the OUTPUT folder, named by their range (e.g. `seeds-100001-200000.csv`).  Searches
+//! skip files named by a seed range outside the searched range without opening them.
+//!
+//! `brogue-scanner [OPTIONS] convert --to utf8 (--in-place | --output DIR)` rewrites UTF-16
+//! catalog files (as produced by Brogue CE) in UTF-8, which halves their size.  Byte order
+//! marks are dropped (unless `--out-encoding utf8-bom` is given), and compressed files stay
+//! compressed.  With `--in-place`, each file
+//! is replaced once its UTF-8 copy is complete; with `--output`, copies of the same name
+//! are written to DIR.
+//!
+//! Catalogs are written in UTF-8 without a byte order mark, unless another encoding is set
+//! with `--out-encoding`.  Apart from `convert --in-place`, the source files are left as
+//! they are.
+
+use crate::columns;
+use crate::file_handling::{decode_utf16, is_gzip, open_catalog, open_raw, CatalogReader, CatalogRecords, EncodedWriter, FileFormat, OutputEncoding};
+use crate::search::SearchParameters;
+use crate::status::no_files;
+use anyhow::{anyhow, Context, Result};
+use csv::StringRecord;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+
+/// Totals for a compacted catalog.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CompactStats {
+    /// Number of source files merged.
+    pub files: usize,
+    /// Number of seeds and Records written.
+    pub seeds: u64,
+    pub records: u64,
+    /// Number of duplicate seeds left out.
+    pub duplicates: u64,
+}
+
+impl std::fmt::Display for CompactStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} files merged:  {} seeds ({} records) written, {} duplicate seeds dropped",
+            self.files, self.seeds, self.records, self.duplicates
+        )
+    }
+}
+
+/// Records of a source catalog, with the seed of the next one.
+type Source = Peekable<CatalogRecords>;
+
+/// Returns the seed of the next Record of a source, if any.
+fn next_seed(source: &mut Source, path: &Path) -> Result<Option<u32>> {
+    match source.peek() {
+        Some(Ok(record)) => Ok(Some(record[columns::SEED].parse::<u32>().with_context(|| {
+            format!("invalid seed '{}' in {:?}", &record[columns::SEED], path)
+        })?)),
+        Some(Err(_)) => Err(source.next().unwrap().unwrap_err().into()),
+        None => Ok(None),
+    }
+}
+
+/// Output catalog file, compressed or not.
+enum CatalogWriter {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl CatalogWriter {
+    /// Creates an output catalog file.
+    fn create(path: &Path, gzip: bool) -> Result<Self> {
+        let file = BufWriter::new(
+            File::create(path).with_context(|| format!("couldn't create {:?}", path))?,
+        );
+
+        match gzip {
+            true => Ok(Self::Gzip(GzEncoder::new(file, Compression::default()))),
+            false => Ok(Self::Plain(file)),
+        }
+    }
+    /// Finishes writing the file.
+    fn finish(self) -> Result<()> {
+        match self {
+            Self::Plain(mut file) => file.flush()?,
+            Self::Gzip(encoder) => encoder.finish()?.flush()?,
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for CatalogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Plain(file) => file.write(buf),
+            Self::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Plain(file) => file.flush(),
+            Self::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Output catalog file, in the output encoding of a search.
+type OutputFile = EncodedWriter<CatalogWriter>;
+
+/// Creates an output catalog file in the given encoding.
+fn create_output(path: &Path, gzip: bool, encoding: OutputEncoding) -> Result<OutputFile> {
+    Ok(EncodedWriter::new(
+        CatalogWriter::create(path, gzip)?,
+        encoding,
+    )?)
+}
+
+/// Finishes writing a CSV file.
+fn finish(wtr: csv::Writer<OutputFile>) -> Result<()> {
+    wtr.into_inner()
+        .map_err(|e| anyhow!("{}", e.error()))?
+        .into_inner()?
+        .finish()
+}
+
+/// Source catalogs of a search, opened for a merge.
+struct Sources<'a> {
+    /// Header of the first catalog.
+    headers: StringRecord,
+    paths: Vec<&'a PathBuf>,
+    sources: Vec<Source>,
+}
+
+impl<'a> Sources<'a> {
+    /// Opens every catalog file of a search, refusing to merge into one of them.
+    fn open(search: &'a SearchParameters, output: &Path) -> Result<Self> {
+        if search.file_paths.is_empty() {
+            return Err(no_files());
+        }
+
+        let mut headers = None;
+        let mut paths = Vec::with_capacity(search.file_paths.len());
+        let mut sources = Vec::with_capacity(search.file_paths.len());
+
+        for path in search.file_paths.iter() {
+            if same_file(path, output)
+                || (output.is_dir() && path.parent().is_some_and(|dir| same_file(dir, output)))
+            {
+                return Err(anyhow!(
+                    "can't write into {:?}, which holds (or is) a source file",
+                    output
+                ));
+            }
+
+            let mut rdr: CatalogReader = open_catalog(path, search.format, &search.schema)
+                .with_context(|| format!("couldn't open catalog {:?}", path))?;
+            // Extra columns are written as they are, so every source must have the same ones
+            let file_headers = rdr.headers()?.clone();
+            if *headers.get_or_insert_with(|| file_headers.clone()) != file_headers {
+                return Err(anyhow!(
+                    "catalog {:?} has different columns than {:?}",
+                    path,
+                    paths[0]
+                ));
+            }
+            paths.push(path);
+            sources.push(rdr.into_records().peekable());
+        }
+
+        Ok(Self {
+            headers: headers.unwrap_or_default(),
+            paths,
+            sources,
+        })
+    }
+    /// Passes the Records of every source to `emit` in ascending seed order (a k-way
+    /// merge), leaving out Records of seeds not searched (outside the seed range or seed
+    /// list) and seeds already passed on.
+    fn merge<F>(&mut self, search: &SearchParameters, mut emit: F) -> Result<CompactStats>
+    where
+        F: FnMut(u32, &StringRecord) -> Result<()>,
+    {
+        let Self { sources, paths, .. } = self;
+        let mut stats = CompactStats {
+            files: sources.len(),
+            ..CompactStats::default()
+        };
+        let mut heap = BinaryHeap::with_capacity(sources.len());
+        let mut last_seed: Option<u32> = None;
+
+        for (i, source) in sources.iter_mut().enumerate() {
+            if let Some(seed) = next_seed(source, paths[i])? {
+                heap.push(Reverse((seed, i)));
+            }
+        }
+
+        // Equal seeds are taken in file order, so the first file listed wins
+        while let Some(Reverse((seed, i))) = heap.pop() {
+            let duplicate = match last_seed {
+                Some(last) if seed < last => {
+                    return Err(anyhow!("seeds in {:?} are out of order at seed {}", paths[i], seed))
+                }
+                Some(last) => seed == last,
+                None => false,
+            };
+            let in_range = search.is_seed_searched(seed);
+
+            match (duplicate, in_range) {
+                (true, _) => stats.duplicates += 1,
+                (false, true) => {
+                    stats.seeds += 1;
+                    last_seed = Some(seed);
+                }
+                (false, false) => (),
+            }
+
+            while next_seed(&mut sources[i], paths[i])? == Some(seed) {
+                let record = sources[i].next().unwrap()?;
+
+                if in_range && !duplicate {
+                    emit(seed, &record)?;
+                    stats.records += 1;
+                }
+            }
+
+            if let Some(seed) = next_seed(&mut sources[i], paths[i])? {
+                heap.push(Reverse((seed, i)));
+            }
+        }
+
+        Ok(stats)
+    }
+}
+
+/// Merges the catalog files of a search (within its seed range) into a single UTF-8 file.
+pub fn compact(search: &SearchParameters, output: &Path, gzip: bool) -> Result<CompactStats> {
+    let mut sources = Sources::open(search, output)?;
+    let mut wtr = csv::Writer::from_writer(create_output(output, gzip, search.out_encoding)?);
+
+    wtr.write_record(&sources.headers)?;
+    let stats = sources.merge(search, |_, record| Ok(wtr.write_record(record)?))?;
+    finish(wtr)?;
+
+    Ok(stats)
+}
+
+/// Divides the catalog files of a search (within its seed range) into UTF-8 files of
+/// `chunk` seeds each, named by seed range (e.g. `seeds-100001-200000.csv`) in `output`.
+/// Returns the totals and the number of files written.
+pub fn split(
+    search: &SearchParameters,
+    output: &Path,
+    chunk: u32,
+    gzip: bool,
+) -> Result<(CompactStats, usize)> {
+    let mut sources = Sources::open(search, output)?;
+    let headers = sources.headers.clone();
+    let extension = match gzip {
+        true => "csv.gz",
+        false => "csv",
+    };
+    let mut current: Option<(u32, csv::Writer<OutputFile>)> = None;
+    let mut files = 0;
+
+    std::fs::create_dir_all(output).with_context(|| format!("couldn't create {:?}", output))?;
+
+    let stats = sources.merge(search, |seed, record| {
+        let (first, last) = chunk_range(seed, chunk);
+
+        if current
+            .as_ref()
+            .is_none_or(|(current_first, _)| *current_first != first)
+        {
+            if let Some((_, wtr)) = current.take() {
+                finish(wtr)?;
+            }
+
+            let path = output.join(format!("seeds-{}-{}.{}", first, last, extension));
+            let mut wtr =
+                csv::Writer::from_writer(create_output(&path, gzip, search.out_encoding)?);
+            wtr.write_record(&headers)?;
+            current = Some((first, wtr));
+            files += 1;
+        }
+
+        if let Some((_, wtr)) = current.as_mut() {
+            wtr.write_record(record)?;
+        }
+
+        Ok(())
+    })?;
+
+    if let Some((_, wtr)) = current {
+        finish(wtr)?;
+    }
+
+    Ok((stats, files))
+}
+
+/// Returns the first and last seed of the chunk holding a seed (chunks start at seed 1).
+fn chunk_range(seed: u32, chunk: u32) -> (u32, u32) {
+    let index = seed.saturating_sub(1) / chunk;
+    let first = index * chunk + 1;
+
+    (first, first.saturating_add(chunk - 1))
+}
+
+/// Rewrites the UTF-16 catalog files of a search in UTF-8, either in place (if `output`
+/// is `None`) or into the `output` folder.  Returns the number of files converted.
+pub fn convert_utf8(search: &SearchParameters, output: Option<&Path>) -> Result<usize> {
+    if search.out_encoding == OutputEncoding::Utf16 {
+        return Err(anyhow!(
+            "convert only writes UTF-8 (use --out-encoding utf8 or utf8-bom)"
+        ));
+    }
+
+    // Only UTF-8 files were found, so there's nothing to convert
+    if search.format == FileFormat::Utf8 {
+        return Ok(0);
+    }
+
+    if let Some(dir) = output {
+        std::fs::create_dir_all(dir).with_context(|| format!("couldn't create {:?}", dir))?;
+    }
+
+    for path in search.file_paths.iter() {
+        // Check that it's a catalog file before writing anything
+        open_catalog(path, search.format, &search.schema)
+            .with_context(|| format!("couldn't open catalog {:?}", path))?;
+
+        let name = path.file_name().ok_or_else(|| anyhow!("{:?} isn't a file", path))?;
+        let target = match output {
+            Some(dir) => dir.join(name),
+            None => path.with_file_name(format!("{}.tmp", name.to_string_lossy())),
+        };
+
+        if same_file(path, &target) {
+            return Err(anyhow!(
+                "can't convert {:?} onto itself (use --in-place)",
+                path
+            ));
+        }
+
+        let mut reader = decode_utf16(open_raw(path)?, true)?;
+        let mut writer = create_output(&target, is_gzip(path), search.out_encoding)?;
+
+        std::io::copy(&mut reader, &mut writer)
+            .with_context(|| format!("couldn't convert {:?}", path))?;
+        writer.into_inner()?.finish()?;
+
+        if output.is_none() {
+            std::fs::rename(&target, path)
+                .with_context(|| format!("couldn't replace {:?}", path))?;
+        }
+    }
+
+    Ok(search.file_paths.len())
+}
+
+/// Returns `true` if two paths name the same existing file.
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
@@ -20,7 +20,7 @@
 //! 
 //! Optionally, you can halve the size of each CSV by coverting them to UTF-8:
 //! ```
-//! Get-Content .\2001-3000_utf16.csv | Set-Content -Encoding utf8 .\2001-3000.csv
+//! brogue-scanner convert --to utf8 --in-place
 //! ```
 //! 
 //! Finally, search using `brogue-scanner`, run from the same folder as your CSV:
@@ -31,16 +31,51 @@
 //! The above query searches for: 1 or more that `scale mail` armor items with the 'mutuality' runic 
 //! and `+2` enchantment level.
 
+mod approx;
+mod archive;
 mod bitflags;
+mod brogue_source;
+mod catalog;
+mod color;
+mod columns;
+#[cfg(feature = "server")]
+mod daemon;
+#[cfg(feature = "save-db")]
+mod database;
+mod engine;
 mod file_handling;
+#[cfg(any(feature = "self-update", feature = "upload"))]
+mod http;
+mod index;
+#[cfg(all(feature = "server", unix))]
+mod ipc;
+mod library;
+mod manifest;
+#[cfg(feature = "server")]
+mod metrics;
 mod objects;
+mod progress;
+mod rarity;
+#[cfg(feature = "server")]
+mod request_log;
 mod search;
+mod seed_detail;
+#[cfg(feature = "self-update")]
+mod self_update;
+mod share;
+mod stats;
+mod status;
+mod throttle;
+mod upload;
+mod variant;
 #[cfg(test)]
 mod tests;
 
 use anyhow::Result;
-use clap::{App, Arg};
-use search::{SearchParameters, search_files, display_matches};
+use clap::{App, AppSettings, Arg, SubCommand};
+use engine::Engine;
+use search::{write_status, OutputMode};
+use status::Status;
 
 /// Creates a new instance of a `brogue-scanner` app.
 pub(crate) fn new_app<'a, 'b>() -> App<'a, 'b> {
@@ -48,7 +83,82 @@ pub(crate) fn new_app<'a, 'b>() -> App<'a, 'b> {
         .version("0.9.6")
         .author("ArchTangent")
         .about("Search Brogue CE seeds for items and allies")     
+        .setting(match color::no_color() {
+            true => AppSettings::ColorNever,
+            false => AppSettings::ColorAuto,
+        })
+        .after_help(
+            "Prefix any term of an object with '?' or 'opt:' (e.g. '-p ?life', '-S opt:3 \
+            enchanting') to make the object optional:  seeds match without it, but score \
+            higher with it (see --grade).\n\n\
+            A count can be required by an earlier depth with 'N@dD' terms, any number per \
+            object:  '-S enchanting 2@d3 5@d10' needs 2 enchanting scrolls by depth 3 and 5 \
+            by depth 10 (without a COUNT or DEPTH, the largest stage count and depth are used).\n\n\
+            Multi-word kinds and runics needn't be quoted:  '-w war axe' is read as a war \
+            axe (not a war hammer and an axe), and '-S scroll of magic mapping' as magic \
+            mapping."
+        )
         // --- GENERAL --- //
+        .arg(Arg::with_name("ally_weights")
+            .long("ally-weights")
+            .value_name("LEGENDARY,MUTATION,TIER")
+            .requires("grade")
+            .help(
+                "Weights of ally quality in --grade scores:  points for a legendary ally, for \
+                a mutated ally, and per tier of the ally's kind (1 to 3, e.g. 1 for a monkey \
+                and 3 for a dar blademaster).  Default '4,2,1'."
+            )
+        )
+        .arg(Arg::with_name("approx")
+            .long("approx")
+            .value_name("PERCENT")
+            .min_values(0)
+            .max_values(1)
+            .help(
+                "If set, searches a random sample of about PERCENT of catalog files \
+                (default '1') for every matching seed, and estimates the match rate and \
+                number of matching seeds in the whole library with a 95% confidence interval."
+            )
+        )
+        .arg(Arg::with_name("best")
+            .long("best")
+            .value_name("RANK")
+            .possible_values(&["depth", "score"])
+            .help(
+                "If set, scans every seed in range and keeps the best --matches seeds (instead \
+                of the first found), best first:  'depth' ranks by the depth needed to meet \
+                every object (shallowest first), 'score' by the --grade score."
+            )
+        )
+        .arg(Arg::with_name("color")
+            .long("color")
+            .value_name("WHEN")
+            .possible_values(&["auto", "always", "never"])
+            .default_value("auto")
+            .help(
+                "When to color output:  'auto' colors it on a terminal unless the NO_COLOR \
+                environment variable is set, 'always' also colors piped output, and 'never' \
+                doesn't color it."
+            )
+        )
+        .arg(Arg::with_name("config")
+            .long("config")
+            .value_name("FILE")
+            .help(
+                "Config file holding the --schema sections and the --upload endpoint.  \
+                Defaults to 'brogue-scanner.toml' in the catalog folder."
+            )
+        )
+        .arg(Arg::with_name("data_dir")
+            .long("data-dir")
+            .value_name("DIR")
+            .help(
+                "Folder whose 'tables.json' changes the built-in names of kinds, runics, \
+                monsters, ally statuses, and mutations:  its 'replace' tables take the place \
+                of the built-in ones, and its 'extend' tables add names to them.  Read at \
+                startup (a daemon's requests can't change it)."
+            )
+        )
         .arg(Arg::with_name("debug")
             .short("D")
             .long("debug")
@@ -68,25 +178,404 @@ pub(crate) fn new_app<'a, 'b>() -> App<'a, 'b> {
             .default_value("26")
             .help("Maximum dungeon depth to search, from 1 to 26.")
         )
+        .arg(Arg::with_name("dump_seed")
+            .long("dump-seed")
+            .value_name("SEED")
+            .conflicts_with_all(&["approx", "explain", "explain_json", "relax"])
+            .help(
+                "Prints every object of SEED within the depth range instead of searching \
+                (as 'show SEED' without colors), or writes them as --json/--ndjson matches."
+            )
+        )
+        .arg(Arg::with_name("exclude_category")
+            .long("exclude-category")
+            .value_name("CATEGORY[:KIND],...")
+            .multiple(true)
+            .number_of_values(1)
+            .help(
+                "Rejects seeds with any of the given objects in the depth range, as \
+                comma-separated CATEGORY or CATEGORY:KIND terms (partial KIND allowed).\n\
+                Examples:\n  \
+                  '--exclude-category scroll:aggravate,potion:darkness'\n  \
+                  '--exclude-category ring'"
+            )
+        )
+        .arg(Arg::with_name("explain")
+            .long("explain")
+            .help(
+                "If set, explains the search instead of running it:  prints its \
+                parameters and how rare each object is (from the rarity table)."
+            )
+        )
+        .arg(Arg::with_name("explain_json")
+            .long("explain-json")
+            .conflicts_with_all(&["explain", "approx", "json", "ndjson", "porcelain"])
+            .help(
+                "If set, writes the parsed search as a JSON document instead of running it:  \
+                every object with its defaults applied, and the names each KIND, RUNIC, and \
+                MUTATION term matches (e.g. 'axe' matches 'war axe' and 'axe')."
+            )
+        )
+        .arg(Arg::with_name("export")
+            .long("export")
+            .value_name("FILE")
+            .help(
+                "Writes the matching objects to FILE as a table for spreadsheets (seed, depth, \
+                category, kind, enchantment, runic, vault, carried_by):  tab-separated if \
+                FILE ends in '.tsv', comma-separated otherwise."
+            )
+        )
         .arg(Arg::with_name("filepath")
             .short("F")
             .long("--filepath")
             .value_name("FILEPATH")
+            .multiple(true)
+            .number_of_values(1)
             .help("Filepath in which seed catalog .csv files are found. Defaults\n\
-                  to the current working directory.")
+                  to the current working directory.  May be repeated:  each folder\n\
+                  (e.g. on a separate drive) is then scanned on its own thread.  Zip\n\
+                  archives of catalogs (in the folder, or given as FILEPATH) are\n\
+                  searched without extracting them.  A\n\
+                  leading '~' and environment variables ($VAR, ${VAR}, %VAR%) are\n\
+                  expanded, as in --query-file, --config, and --data-dir paths.")
         )        
+        .arg(Arg::with_name("template")
+            .long("format")
+            .value_name("TEMPLATE")
+            .conflicts_with_all(&["json", "ndjson", "porcelain", "report", "share", "stream"])
+            .help(
+                "Prints a line per match from TEMPLATE, with no banner or summary:  fields in \
+                braces are replaced by the match's values (e.g. '{seed}\\t{depth}\\t{object}').  \
+                Fields:  seed, version, depth, quantity, category, kind, enchantment, runic, \
+                ally_status, mutation, vault, opens_vault, carried_by, object, and match (the \
+                object with its vault or carrier).  '{{' and '}}' are braces, and '\\t' and '\\n' \
+                a tab and a line break."
+            )
+        )
+        .arg(Arg::with_name("format_version")
+            .long("format-version")
+            .value_name("VERSION")
+            .default_value("1")
+            .help("Schema version of --json / --ndjson output.  Only version 1 exists so far.")
+        )
+        .arg(Arg::with_name("grade")
+            .long("grade")
+            .value_name("S,A,B")
+            .min_values(0)
+            .max_values(1)
+            .help(
+                "If set, prints a leaderboard grading matching seeds into tiers S/A/B/C by \
+                score (matching objects + enchant potential + ally quality, see --ally-weights), \
+                with gold in the depth range breaking ties.  Optional thresholds give the \
+                minimum score for S, A, and B.  Default '12,8,4'."
+            )
+        )
+        .arg(Arg::with_name("json")
+            .long("json")
+            .conflicts_with_all(&["ndjson", "porcelain"])
+            .help("If set, results are printed as a single JSON document (see --format-version).")
+        )
+        .arg(Arg::with_name("ndjson")
+            .long("ndjson")
+            .conflicts_with_all(&["json", "porcelain"])
+            .help("If set, results are printed as newline-delimited JSON, one match per line.")
+        )
+        .arg(Arg::with_name("output")
+            .long("output")
+            .value_name("FILE")
+            .conflicts_with_all(&["best", "relax", "stream"])
+            .help(
+                "Writes the matches to FILE instead of standard output, in the format chosen \
+                (e.g. --json or --report), or as NDJSON if none is, and prints only notes \
+                and the status, on standard error.  Line-based formats (NDJSON, --porcelain, \
+                --share, --format) are appended to FILE as soon as each seed is confirmed \
+                (synced to disk), so a long scan that's cut short keeps the results found so \
+                far (with several '--filepath' folders, once the folders are merged); \
+                documents replace FILE."
+            )
+        )
+        .arg(Arg::with_name("out_encoding")
+            .long("out-encoding")
+            .value_name("ENCODING")
+            .possible_values(&["utf8", "utf8-bom", "utf16"])
+            .default_value("utf8")
+            .help(
+                "Encoding of the files written (catalogs from 'compact', 'split', and \
+                'convert', and seed lists).  'utf8-bom' adds a byte order mark for Excel \
+                and PowerShell 5.1; 'utf16' is UTF-16LE, as written by Brogue CE."
+            )
+        )
+        .arg(Arg::with_name("plain_numbers")
+            .long("plain-numbers")
+            .help(
+                "If set, numbers in summaries (seed ranges, seed counts, gold) are printed \
+                without thousands separators (e.g. '4294967295' instead of \
+                '4,294,967,295'), for scripts."
+            )
+        )
+        .arg(Arg::with_name("porcelain")
+            .long("porcelain")
+            .help(
+                "If set, matches are printed as stable tab-separated lines for scripts:  \
+                seed, depth, category, kind, enchantment, runic, vault, and carrier (empty \
+                if none), with no banner or summary.  This format never changes."
+            )
+        )
         .arg(Arg::with_name("matches_max")
             .short("m")        
             .long("matches")
             .value_name("MATCHES")
             .default_value("10")
-            .help("Maximum number of matching seeds to return, from 1 to 255.")
+            .help("Maximum number of matching seeds to return, or 0 (or 'all') for every matching seed.")
+        )
+        .arg(Arg::with_name("met_depths")
+            .long("met-depths")
+            .conflicts_with_all(&["json", "ndjson", "porcelain"])
+            .help(
+                "If set, reports under each matching seed the depth at which each object was \
+                first met (e.g. 'enchanting x5 complete at depth 7'), and the depth of its \
+                last match."
+            )
+        )
+        .arg(Arg::with_name("nice")
+            .long("nice")
+            .help(
+                "If set, scans in the background:  at a lower CPU priority (on unix), and \
+                pausing between seeds so about half the time is spent searching.  Scans \
+                take about twice as long, but leave the machine usable meanwhile."
+            )
+        )
+        .arg(Arg::with_name("no_malevolent")
+            .long("no-malevolent")
+            .value_name("DEPTH")
+            .min_values(0)
+            .max_values(1)
+            .help(
+                "If set, rejects seeds with any malevolent object by DEPTH (default \
+                '--depth'):  malevolent potions, scrolls, and wands, cursed (negative) armor, \
+                rings, and weapons, and negative runics."
+            )
+        )
+        .arg(Arg::with_name("ordered")
+            .long("ordered")
+            .help(
+                "If set, results from several '--filepath' folders (scanned in parallel) \
+                are buffered and merged in ascending seed order, so output is reproducible.  \
+                Files within a folder are always scanned in order of the seed ranges in \
+                their names (unless --random is set)."
+            )
+        )
+        .arg(Arg::with_name("relax")
+            .long("relax")
+            .conflicts_with_all(&["approx", "explain", "explain_json"])
+            .help(
+                "If set, and fewer seeds match than '--matches' asks for (or none, for 'all'), \
+                loosens numeric constraints one step at a time until enough do:  'or more' \
+                counts, then enchantments, then object depths, then '--depth'.  What was \
+                relaxed is reported with the results."
+            )
+        )
+        .arg(Arg::with_name("param_stats")
+            .long("param-stats")
+            .conflicts_with_all(&["json", "ndjson", "porcelain"])
+            .help(
+                "If set, reports how many scanned seeds met each object on its own after the \
+                search, to show which object rules out the most seeds."
+            )
+        )
+        .arg(Arg::with_name("progress")
+            .long("progress")
+            .help(
+                "If set, shows the file and seed being searched and the number of matching \
+                seeds so far on a status line (on standard error, if it's a terminal)."
+            )
+        )
+        .arg(Arg::with_name("query_file")
+            .long("query-file")
+            .value_name("FILE")
+            .multiple(true)
+            .number_of_values(1)
+            .help(
+                "Runs the query in a TOML query file.  May be given more than once:  all \
+                queries are searched in a single pass, and matches reported per query.  \
+                Other options given on the command line apply to every query."
+            )
         )
         .arg(Arg::with_name("random")
             .short("R")
             .long("random")
             .help("If set, csv files will be checked in random order.")
         )        
+        .arg(Arg::with_name("random_seed")
+            .long("random-seed")
+            .value_name("N")
+            .help(
+                "Seed for the random file order of --random and the files sampled by --approx, \
+                so a randomized scan can be repeated exactly."
+            )
+        )
+        .arg(Arg::with_name("rarity_cache")
+            .long("rarity-cache")
+            .value_name("FILE")
+            .help(
+                "Rarity table file used by --explain and built by 'rarity'.  Defaults \
+                to 'brogue-rarity.json' in the catalog folder."
+            )
+        )
+        .arg(Arg::with_name("rebuild_stale")
+            .long("rebuild-stale")
+            .help(
+                "If set, a rarity table or catalog index built from catalog files that have \
+                changed since is rebuilt before searching.  Otherwise, a stale rarity table \
+                isn't used, and changed files are searched in full."
+            )
+        )
+        .arg(Arg::with_name("report")
+            .long("report")
+            .value_name("FORMAT")
+            .possible_values(&["html", "md"])
+            .conflicts_with_all(&["json", "ndjson", "porcelain", "share", "stream"])
+            .help(
+                "Prints the matches as a report instead of text:  'md' writes a Markdown \
+                document, with a heading per seed and a bullet list of objects per depth, \
+                ready to post, and 'html' a standalone web page with a sortable, filterable \
+                table of matches (e.g. '--report html > finds.html')."
+            )
+        )
+        .arg(Arg::with_name("require_key")
+            .long("require-key")
+            .help(
+                "If set, objects in a vault only match if a key opening the vault (its \
+                'opens' vault number) is found on the same depth."
+            )
+        )
+        .arg(Arg::with_name("rescue")
+            .long("rescue")
+            .conflicts_with_all(&["json", "ndjson", "porcelain"])
+            .help(
+                "If set, reports under each matching seed the cage keys (and who carries \
+                them) and the monsters carrying items on the depth of each caged or shackled \
+                ally matched, to judge how realistic its rescue is."
+            )
+        )
+        .arg(Arg::with_name("logic")
+            .long("logic")
+            .value_name("EXPR")
+            .takes_value(true)
+            .help(
+                "Combines objects with !, &&, ||, and parentheses, by object number (in the \
+                order listed under 'Objects' in the search summary), instead of requiring \
+                every object.  Every object must be used.\n\
+                Examples:\n  \
+                  '-a banded -w \"war axe\" --logic \"1 || 2\"'\n  \
+                  '-a banded -p life -w \"war axe\" --logic \"(1 || 3) && !2\"'"
+            )
+        )
+        .arg(Arg::with_name("same_depth")
+            .long("same-depth")
+            .value_name("OBJECTS")
+            .multiple(true)
+            .number_of_values(1)
+            .help(
+                "Objects that must all be found on the same depth, as comma-separated object \
+                numbers (in the order listed under 'Objects' in the search summary) or 'all'.  \
+                Each object's COUNT must be met by that depth alone.\n\
+                Examples:\n  \
+                  '--altar commutation -S enchanting --same-depth all'\n  \
+                  '-a banded -p life -S enchanting --same-depth 1,3'"
+            )
+        )
+        .arg(Arg::with_name("save_db")
+            .long("save-db")
+            .value_name("FILE")
+            .help(
+                "Appends the query and its matches to a SQLite results database (created \
+                if missing), for later cross-query analysis."
+            )
+        )
+        .arg(Arg::with_name("schema")
+            .long("schema")
+            .value_name("NAME")
+            .help(
+                "Reads catalog files exported by a modded Brogue fork, with the column \
+                header names and categories of the '[schema.NAME]' section of the config \
+                file (see --config):  a 'columns' table of the fork's header name for each \
+                standard column it renames, and a 'categories' table of the Brogue CE \
+                category for each category it renames.\n\
+                Example section:\n  \
+                  [schema.rapid]\n  \
+                  columns = { kind = \"item_kind\", vault_number = \"vault\" }\n  \
+                  categories = { weapons = \"weapon\" }"
+            )
+        )
+        .arg(Arg::with_name("score_expr")
+            .long("score-expr")
+            .value_name("EXPR")
+            .requires("grade")
+            .help(
+                "Scoring expression replacing the default --grade score, computed for each \
+                matching seed.  Variables:  base (the default score), objects, enchant, allies, \
+                and gold.  count(\"TERM\") is the quantity of matching objects of the category \
+                or kind (partial match) TERM, and depth(\"TERM\") their earliest depth (100 if \
+                none).  Also min(A, B), max(A, B), + - * /, and comparisons (1 if true, else 0).\n\
+                Example:  --score-expr 'objects + 3 * count(\"enchanting\") + gold / 1000'"
+            )
+        )
+        .arg(Arg::with_name("sort_depth")
+            .long("sort-depth")
+            .help(
+                "If set, matching seeds are sorted by the depth needed to meet every object \
+                (shown as 'complete by depth N' beside each seed), shallowest first.  Only \
+                the seeds found (see --matches) are sorted."
+            )
+        )
+        .arg(Arg::with_name("stream")
+            .long("stream")
+            .conflicts_with_all(&["best", "json", "ndjson", "ordered", "porcelain", "relax", "sort_depth"])
+            .help(
+                "If set, prints each matching seed as soon as it's found, instead of after \
+                the whole scan.  The summary is still printed at the end.  Only one \
+                '--filepath' folder may be searched."
+            )
+        )
+        .arg(Arg::with_name("seedlist")
+            .long("seedlist")
+            .value_name("FILE")
+            .help(
+                "Only searches the seeds listed in FILE:  seeds separated by whitespace or \
+                commas, or the output of a search with --json or --ndjson.  Combines with \
+                --minseed and --maxseed."
+            )
+        )
+        .arg(Arg::with_name("write_seedlist")
+            .long("write-seedlist")
+            .value_name("FILE")
+            .help(
+                "Writes the matching seeds to FILE, one per line, for a later search's \
+                --seedlist."
+            )
+        )
+        .arg(Arg::with_name("seed")
+            .long("seed")
+            .value_name("SEED")
+            .multiple(true)
+            .number_of_values(1)
+            .conflicts_with_all(&["seed_min", "seed_max"])
+            .help(
+                "Only searches SEED, from 1 to 4294967295 (same as '--minseed SEED \
+                --maxseed SEED').  Repeat to search several seeds (e.g. '--seed 14 \
+                --seed 9001'), combined with --seedlist if given."
+            )
+        )
+        .arg(Arg::with_name("seed_step")
+            .long("seed-step")
+            .value_name("K")
+            .default_value("1")
+            .help(
+                "Only searches every K-th seed from --minseed (e.g. '--seed-step 100' for a \
+                coarse sweep of a large range before searching it all)."
+            )
+        )
         .arg(Arg::with_name("seed_min")
             .long("minseed")
             .alias("start")
@@ -107,6 +596,93 @@ pub(crate) fn new_app<'a, 'b>() -> App<'a, 'b> {
                 Cannot be less than --minseed."
             )
         )
+        .arg(Arg::with_name("share")
+            .long("share")
+            .conflicts_with_all(&["json", "ndjson", "porcelain"])
+            .help(
+                "If set, prints each matching seed as a share string holding its dungeon \
+                version, seed, and matching objects (e.g. \
+                'brogue:1/CE+1.9/12345/3,1,armor,banded+mail,2'), one per line, with no \
+                banner or summary.  Read share strings back with 'decode'."
+            )
+        )
+        .arg(Arg::with_name("where")
+            .long("where")
+            .value_name("FILTER")
+            .multiple(true)
+            .number_of_values(1)
+            .help(
+                "Raw filter on any catalog column (including columns the scanner doesn't \
+                know), by header name (partial match allowed) or index (the 13 standard \
+                columns first, then any others), in the form 'col:COLUMN OP VALUE'.  OP is \
+                one of =, !=, ~ (contains), <, <=, >, >=.  Only objects passing every filter \
+                can match.\n\
+                Examples:\n  \
+                  '--where col:opens=3'\n  \
+                  '--where col:9>=2'"
+            )
+        )
+        .arg(Arg::with_name("fuzzy")
+            .long("fuzzy")
+            .help(
+                "If set, a kind that matches nothing is read as the nearest kind within two \
+                typos (e.g. 'broadsord' as 'broadsword').  Each kind read this way is shown \
+                in the search summary."
+            )
+        )
+        .arg(Arg::with_name("filter")
+            .long("filter")
+            .value_name("EXPR")
+            .multiple(true)
+            .number_of_values(1)
+            .help(
+                "Searches for an object of any category passing a row predicate over catalog \
+                columns (named as for --where).  Comparisons (==, !=, ~, <, <=, >, >=) combine \
+                with !, &&, ||, and parentheses; a column on its own means not empty.\n\
+                Examples:\n  \
+                  --filter \"category=='weapon' && ench>=2 && depth<5\"\n  \
+                  --filter \"kind~'slaying' || (category=='armor' && runic)\""
+            )
+        )
+        .arg(Arg::with_name("within")
+            .long("within")
+            .value_name("A,B,LEVELS")
+            .multiple(true)
+            .number_of_values(1)
+            .help(
+                "Object B must be found within LEVELS depths of object A (either direction).  \
+                Objects are numbered in the order listed under 'Objects' in the search summary.\n\
+                Examples:\n  \
+                  '-w quietus -S protect --within 2,1,3'"
+            )
+        )
+        .arg(Arg::with_name("total")
+            .long("total")
+            .value_name("COUNT:OBJECTS")
+            .multiple(true)
+            .number_of_values(1)
+            .help(
+                "Objects (of any categories) found for the given objects must number at least \
+                COUNT together, as comma-separated object numbers (in the order listed under \
+                'Objects' in the search summary) or 'all'.  The objects needn't meet their \
+                own COUNT.\n\
+                Examples:\n  \
+                  '-p life -p healing -c health --total 3:all'\n  \
+                  '-p d6 -S d6 --total 12:1,2'"
+            )
+        )
+        .arg(Arg::with_name("upload")
+            .long("upload")
+            .value_name("URL")
+            .min_values(0)
+            .max_values(1)
+            .help(
+                "Opts in to sending the matches of the search to a community seed database \
+                at URL (an 'https://' endpoint, or 'http://' on this machine), or at the \
+                'endpoint' of the [upload] section of the config file (see --config).  Only \
+                seeds, dungeon versions, and matching objects are sent."
+            )
+        )
         .arg(Arg::with_name("utf8")
             .short("U")
             .long("utf8")
@@ -124,6 +700,24 @@ pub(crate) fn new_app<'a, 'b>() -> App<'a, 'b> {
                 Seed catalogs produced by Brogue CE are in UTF-16 format."
             )
         )
+        .arg(Arg::with_name("variant")
+            .long("variant")
+            .value_name("NAME")
+            .help(
+                "Reads catalog files of a Brogue variant (e.g. 'rapid') with its data pack:  \
+                'variants/NAME.toml' (or '.json') in the catalog folder, or a pack FILE.  A \
+                pack maps the variant's kinds, runics, and monsters to Brogue CE names (used \
+                in searches), and sets which kinds and runics are malevolent."
+            )
+        )
+        .arg(Arg::with_name("vaults")
+            .long("vaults")
+            .conflicts_with_all(&["json", "ndjson", "porcelain"])
+            .help(
+                "If set, lists under each matching seed every vault on the searched depths, \
+                with its full contents and whether its key was found on the depth."
+            )
+        )
         .arg(Arg::with_name("verbose")
             .short("v")
             .long("verbose")
@@ -131,7 +725,7 @@ pub(crate) fn new_app<'a, 'b>() -> App<'a, 'b> {
             .help(
                 "Sets search verbosity from 1 to 3 (-v, -vv or -vvv), default '3'.\n  \
                   Level 3: display seeds + depths + matches\n  \
-                  Level 2: display seeds + depths\n  \
+                  Level 2: display seeds + depths (with match counts by category)\n  \
                   Level 1: display seeds"
             )
         )   
@@ -178,7 +772,7 @@ pub(crate) fn new_app<'a, 'b>() -> App<'a, 'b> {
             .min_values(1)
             .multiple(true)
             .help(
-                "Armor matching [COUNT] [DEPTH] [ENCHANTMENT] [KIND] [MAGIC] [RUNIC] [VAULT] in any order.\n  \
+                "Armor matching [ARMOR VALUE] [COUNT] [DEPTH] [ENCHANTMENT] [KIND] [MAGIC] [RUNIC] [STRENGTH] [VAULT] in any order.\n  \
                   COUNT: quantity (e.g. '2'). Default '1'. Max 255.\n  \
                   DEPTH: maximum dungeon depth to search for this object.\n  \
                   ENCHANTMENT: integer in form +N or N- ('+3', '+0', '-1'). Default 'any.'\n    \
@@ -186,14 +780,21 @@ pub(crate) fn new_app<'a, 'b>() -> App<'a, 'b> {
                     (N-) : find objects with enchantment <= N\n\
                   KIND: any armor kind (e.g. 'scale'). Partial match allowed.\n  \
                   MAGIC: 'bad', 'good' - whether object is blessed or malevolent (default either).\n  \
-                  RUNIC: any armor runic (e.g. 'goblin'). Partial match allowed.\n  \
+                  RUNIC: any armor runic (e.g. 'goblin'). Partial match allowed.  Alternatives \
+                  are separated by '|' (e.g. 'reflection|respiration').\n  \
+                  STRENGTH: 'str<=N' - armor needing at most N strength (e.g. 'str<=15' skips \
+                  splint and plate).\n  \
+                  ARMOR VALUE: 'av>=N' - armor of base armor value N or more, before enchantment \
+                  (leather 3, scale 4, chain 5, banded 7, splint 9, plate 11).\n  \
                   VAULT: 'vault' or 'novault' - whether object is in vault (default either).\n\
                 Special Term(s):\n  \
-                  'runic': finds any runic armor matching specified params.\n\
+                  'runic': finds any runic armor matching specified params.\n  \
+                  'anyimmunity': finds armor of any monster immunity (e.g. 'goblin immunity').\n\
                 Examples: \n  \
                   '--armor 2 +3 scale mutuality'\n  \
                   '--armor 1- chain immolation'\n  \
-                  '--armor +2 runic'"
+                  '--armor +2 runic'\n  \
+                  '--armor av>=9 +1'"
             )
         )
         .arg(Arg::with_name("charm")
@@ -215,6 +816,22 @@ pub(crate) fn new_app<'a, 'b>() -> App<'a, 'b> {
                   '--charm telepathy'"
             )
         )
+        .arg(Arg::with_name("empower")
+            .long("empower")
+            .value_name("PARAMS")
+            .min_values(0)
+            .max_values(2)
+            .help(
+                "Empowered-ally build matching [CHARGES] [DEPTH] in any order:  a caged or \
+                shackled ally plus wands of empowerment with at least CHARGES charges in total, \
+                all by DEPTH.\n  \
+                  CHARGES: total wand of empowerment charges (e.g. '2'). Default '1'.\n  \
+                  DEPTH: maximum dungeon depth for the ally and the wands.\n\
+                Examples: \n  \
+                  '--empower 2 d10'\n  \
+                  '--empower'"
+            )
+        )
         .arg(Arg::with_name("equipment")
             .short("e")
             .long("equipment")
@@ -344,6 +961,23 @@ pub(crate) fn new_app<'a, 'b>() -> App<'a, 'b> {
                   '--scroll 18 enchantment'"
             )
         )     
+        .arg(Arg::with_name("strength")
+            .long("strength")
+            .value_name("PARAMS")
+            .min_values(0)
+            .max_values(6)
+            .help(
+                "Potions of strength matching [COUNT] [DEPTH] [VAULT] in any order (same as \
+                '--potion strength ...'), for planning strength by depth.\n  \
+                  COUNT: quantity (e.g. '3'). Default '1'.  Stages ('2@d4') may also be given.\n  \
+                  DEPTH: maximum dungeon depth to search for the potions.\n  \
+                  VAULT: 'novault' leaves out potions in vaults (only one vault item can be \
+                  taken), 'vault' finds only those.  Default either.\n\
+                Examples: \n  \
+                  '--strength 3 d8'\n  \
+                  '--strength 1@d4 3@d10 novault'"
+            )
+        )
         .arg(Arg::with_name("staff")
             .short("s")
             .long("staff")
@@ -354,14 +988,16 @@ pub(crate) fn new_app<'a, 'b>() -> App<'a, 'b> {
                 "Staves matching [COUNT] [DEPTH] [ENCHANTMENT] [KIND] [MAGIC] [VAULT] in any order.\n  \
                   COUNT: quantity (e.g. '2'). Default '1'. Max 255.\n  \
                   DEPTH: maximum dungeon depth to search for this object.\n  \
-                  ENCHANTMENT: integer in form +N ('+3', '+0'). Default 'any.'\n    \
-                    (+N) : find objects with enchantment >= N\n  \
+                  ENCHANTMENT: integer in form +N or N- ('+3', '+0', '2-'). Default 'any.'\n    \
+                    (+N) : find objects with enchantment >= N\n    \
+                    (N-) : find objects with enchantment <= N (both may be given)\n  \
                   KIND: any staff kind (e.g. 'firebolt'). Partial match allowed.\n  \
                   MAGIC: 'bad', 'good' - whether object is blessed or malevolent (default either).\n  \
                   VAULT: 'vault' or 'novault' - whether object is in vault (default either).\n\
                 Examples: \n  \
                   '--staff 3 +2 lightning'\n  \
-                  '--staff entrancement'"
+                  '--staff entrancement'\n  \
+                  '--staff +2 3- firebolt'"
             )
         )   
         .arg(Arg::with_name("wand")
@@ -374,14 +1010,16 @@ pub(crate) fn new_app<'a, 'b>() -> App<'a, 'b> {
                 "Wands matching [COUNT] [DEPTH] [ENCHANTMENT] [KIND] [MAGIC] [VAULT] in any order.\n  \
                   COUNT: quantity (e.g. '2'). Default '1'. Max 255.\n  \
                   DEPTH: maximum dungeon depth to search for this object.\n  \
-                  ENCHANTMENT: integer in form +N ('+3', '+0'). Default 'any.'\n    \
-                    (+N) : find objects with enchantment >= N. In the case of wands, this is the number of charges.\n  \
+                  ENCHANTMENT: integer in form +N or N- ('+3', '+0', '1-'). Default 'any.'\n    \
+                    (+N) : find objects with enchantment >= N. In the case of wands, this is the number of charges.\n    \
+                    (N-) : find objects with enchantment <= N (both may be given)\n  \
                   KIND: any wand kind (e.g. 'domination'). Partial match allowed.\n  \
                   MAGIC: 'bad', 'good' - whether object is blessed or malevolent (default either).\n  \
                   VAULT: 'vault' or 'novault' - whether object is in vault (default either).\n\
                 Examples: \n  \
                   '--wand 1 +2 plenty'\n  \
-                  '--wand empowerment'"
+                  '--wand empowerment'\n  \
+                  '--wand plenty 1-'"
             )
         )                      
         .arg(Arg::with_name("weapon")
@@ -391,24 +1029,264 @@ pub(crate) fn new_app<'a, 'b>() -> App<'a, 'b> {
             .min_values(1)
             .multiple(true)
             .help(
-                "Weapons matching [COUNT] [DEPTH] [ENCHANTMENT] [KIND] [MAGIC] [RUNIC] [VAULT] in any order.\n  \
+                "Weapons matching [COUNT] [DAMAGE] [DEPTH] [ENCHANTMENT] [KIND] [MAGIC] [RUNIC] [STRENGTH] [VAULT] in any order.\n  \
                   COUNT: quantity (e.g. '2'). Default '1'. Max 255.\n  \
                   DEPTH: maximum dungeon depth to search for this object.\n  \
                   ENCHANTMENT: integer in form +N or N- ('+3', '+0', '1-'). Default 'any.'\n    \
                     (+N) : find objects with enchantment >= N\n    \
                     (N-) : find objects with enchantment <= N\n\
                   KIND: any weapon kind (e.g. 'spear'). Partial match allowed.\n  \
-                  RUNIC: any weapon runic (e.g. 'paralysis'). Partial match allowed.\n  \
+                  RUNIC: any weapon runic (e.g. 'paralysis'). Partial match allowed.  Alternatives \
+                  are separated by '|' (e.g. 'speed|quietus|paralysis').\n  \
+                  STRENGTH: 'str<=N' - weapons needing at most N strength (e.g. 'str<=16' skips \
+                  war hammers and war pikes).\n  \
+                  DAMAGE: 'dmg>=tierN' - weapons of damage tier N or more, by average base damage:  \
+                  1 light (dagger, spear, whip, darts), 2 medium (sword, axe, rapier, javelin), 3 \
+                  heavy (mace, flail, broadsword, and the war weapons).\n  \
                   MAGIC: 'bad', 'good' - whether object is blessed or malevolent (default either).\n  \
                   VAULT: 'vault' or 'novault' - whether object is in vault (default either).\n\
                 Special Term(s):\n  \
-                  'runic': finds any runic weapon matching specified params.\n\
+                  'runic': finds any runic weapon matching specified params.\n  \
+                  'anyslaying': finds a weapon of any monster slaying (e.g. 'dragon slaying').\n  \
+                  '1h', '2h': finds one-handed weapons, or two-handed ones.  Brogue has no \
+                  handedness, so two-handed weapons are those needing 18 or more strength \
+                  (broadsword, war axe, war hammer, and war pike).\n  \
+                  'thrown': finds darts, incendiary darts, and javelins, counted together.\n\
                 Examples:\n  \
                   '--weapon 2 +3 whip quietus'\n  \
                   '--weapon sword mercy 1-'\n  \
-                  '--weapon +2 runic'"
+                  '--weapon sword \"speed|quietus|paralysis\"'\n  \
+                  '--weapon +2 runic'\n  \
+                  '--weapon 1h +3 runic'\n  \
+                  '--weapon 20 thrown d6'\n  \
+                  '--weapon dmg>=tier3 runic'"
               )
           )
+        // --- SUBCOMMANDS --- //
+        .subcommand(SubCommand::with_name("compact")
+            .about(
+                "Merges all catalog files into a single UTF-8 catalog in ascending seed \
+                order, writing each seed only once (from the first file that has it).  \
+                General options (e.g. --filepath, --minseed) go before 'compact'."
+            )
+            .arg(Arg::with_name("output")
+                .value_name("OUTPUT")
+                .required(true)
+                .help("Path of the compacted catalog (e.g. 'seeds-1-100000.csv').")
+            )
+            .arg(Arg::with_name("gzip")
+                .long("gzip")
+                .help("Compresses the output with gzip (name it '*.csv.gz').")
+            )
+        )
+        .subcommand(SubCommand::with_name("convert")
+            .about(
+                "Rewrites UTF-16 catalog files (as produced by Brogue CE) in UTF-8, halving \
+                their size.  Byte order marks are dropped.  General options (e.g. \
+                --filepath) go before 'convert'."
+            )
+            .arg(Arg::with_name("to")
+                .long("to")
+                .value_name("FORMAT")
+                .possible_values(&["utf8"])
+                .default_value("utf8")
+                .help("Format to convert to.")
+            )
+            .arg(Arg::with_name("in_place")
+                .long("in-place")
+                .conflicts_with("output")
+                .help("Replaces each file with its UTF-8 version.")
+            )
+            .arg(Arg::with_name("output")
+                .long("output")
+                .value_name("DIR")
+                .help("Writes the UTF-8 files (with the same names) to DIR instead.")
+            )
+        )
+        .subcommand(SubCommand::with_name("daemon")
+            .about(
+                "Stays resident and answers one search per line of standard input (options \
+                as on the command line, e.g. '-a banded +3 --ndjson'), keeping caches loaded \
+                between searches.  Each response ends with a '%% ok' or '%% error: ...' \
+                line.  General options go before 'daemon' and apply to every search."
+            )
+            .arg(Arg::with_name("log")
+                .long("log")
+                .value_name("FILE")
+                .help(
+                    "Appends a line of JSON to FILE for every request (its id, arguments, \
+                    duration, and status or error), rotating FILE by size and date."
+                )
+            )
+            .arg(Arg::with_name("log_max_size")
+                .long("log-max-size")
+                .value_name("MB")
+                .default_value("10")
+                .help("Size in megabytes past which the log is rotated (to FILE.1, FILE.2, ...).")
+            )
+            .arg(Arg::with_name("log_keep")
+                .long("log-keep")
+                .value_name("FILES")
+                .default_value("5")
+                .help("Number of rotated logs kept.")
+            )
+            .arg(Arg::with_name("metrics")
+                .long("metrics")
+                .value_name("ADDR")
+                .help(
+                    "Serves Prometheus metrics (requests, rows scanned, scan durations, and \
+                    cache hits) over HTTP at ADDR (e.g. '127.0.0.1:9184'), at '/metrics'."
+                )
+            )
+            .args(&socket_args())
+        )
+        .subcommand(SubCommand::with_name("decode")
+            .about(
+                "Reads share strings (see --share) back into the objects of their seeds, \
+                printed as with 'show' or as --json/--ndjson matches.  No catalogs are \
+                needed."
+            )
+            .arg(Arg::with_name("share")
+                .value_name("SHARE")
+                .required(true)
+                .multiple(true)
+                .help("Share strings to read (e.g. 'brogue:1/CE+1.9/12345/3,1,armor,banded+mail,2').")
+            )
+        )
+        .subcommand(SubCommand::with_name("index")
+            .about(
+                "Builds or updates the catalog index of each --filepath folder, so searches \
+                can skip files outside their seed range or without the kinds and runics \
+                they require.  Only new or changed files are rescanned.  General options \
+                go before 'index'."
+            )
+        )
+        .subcommand(SubCommand::with_name("rarity")
+            .about(
+                "Builds the rarity table used by --explain from all scanned seeds, and \
+                caches it (see --rarity-cache).  General options go before 'rarity'."
+            )
+        )
+        .subcommand(SubCommand::with_name("seeds")
+            .about(
+                "Combines seed lists (see --seedlist and --write-seedlist) without \
+                rescanning:  'intersect' keeps seeds in every list, 'union' seeds in any \
+                list, and 'diff' seeds in the first list but none of the others."
+            )
+            .arg(Arg::with_name("operation")
+                .value_name("OPERATION")
+                .required(true)
+                .possible_values(&["intersect", "union", "diff"])
+                .help("Set operation.")
+            )
+            .arg(Arg::with_name("files")
+                .value_name("FILE")
+                .required(true)
+                .multiple(true)
+                .help("Seed list files.")
+            )
+            .arg(Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("FILE")
+                .help("Writes the seeds to FILE instead of standard output.")
+            )
+        )
+        .subcommand(SubCommand::with_name("self-update")
+            .about(
+                "Replaces this executable with the latest release for this platform, \
+                downloaded over HTTPS and checked against the release's SHA-256 \
+                checksums.  The checksums come with the release, so they only catch a \
+                damaged download, not a tampered release."
+            )
+            .arg(Arg::with_name("check")
+                .long("check")
+                .help("Only reports whether a newer release is out.")
+            )
+        )
+        .subcommand(SubCommand::with_name("show")
+            .about(
+                "Prints the complete catalog of a seed:  every object on every depth, \
+                color-coded by category (see --color), or as --json/--ndjson matches.  \
+                General options (e.g. --filepath, --maxdepth) go before 'show'."
+            )
+            .arg(Arg::with_name("seed")
+                .value_name("SEED")
+                .required(true)
+                .help("Seed to show.")
+            )
+            .arg(Arg::with_name("no_color")
+                .long("no-color")
+                .help("Prints without colors (same as '--color never').")
+            )
+        )
+        .subcommand(SubCommand::with_name("split")
+            .about(
+                "Merges all catalog files like 'compact', but divides them into files of a \
+                fixed seed range each, named by range (e.g. 'seeds-100001-200000.csv').  \
+                Searches skip files named by a range outside their seed range.  General \
+                options go before 'split'."
+            )
+            .arg(Arg::with_name("output")
+                .value_name("OUTPUT")
+                .required(true)
+                .help("Folder for the split catalogs (created if needed).")
+            )
+            .arg(Arg::with_name("chunk")
+                .long("chunk")
+                .value_name("SEEDS")
+                .default_value("100000")
+                .help("Number of seeds per file.")
+            )
+            .arg(Arg::with_name("gzip")
+                .long("gzip")
+                .help("Compresses each file with gzip.")
+            )
+        )
+        .subcommand(SubCommand::with_name("stats")
+            .about(
+                "Counts a category or kind of object per depth bucket across all scanned \
+                seeds.  General options (e.g. --filepath, --minseed, --json) go before \
+                'stats'."
+            )
+            .arg(Arg::with_name("category")
+                .value_name("CATEGORY")
+                .required(true)
+                .help("Object category (e.g. 'armor', 'ally', 'item').  Partial match allowed.")
+            )
+            .arg(Arg::with_name("kind")
+                .value_name("KIND")
+                .help("Object kind (e.g. 'banded', 'goblin').  Partial match allowed.")
+            )
+            .arg(Arg::with_name("bucket")
+                .short("b")
+                .long("bucket")
+                .value_name("DEPTHS")
+                .default_value("3")
+                .help("Number of depths per bucket, from 1 to 26.")
+            )
+        )
+        .subcommand(SubCommand::with_name("update-tables")
+            .about(
+                "Reads the item tables, runic names, and monster and mutation catalogs of a \
+                Brogue CE source tree, and writes name tables with its names for --data-dir \
+                (starting from the current tables, including any --data-dir changes).  \
+                Reports the names added and removed for each table."
+            )
+            .arg(Arg::with_name("brogue_src")
+                .long("brogue-src")
+                .value_name("PATH")
+                .required(true)
+                .help("Brogue CE source folder (e.g. 'BrogueCE/src'), or a single source file.")
+            )
+            .arg(Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("FILE")
+                .default_value("tables.json")
+                .help("Name tables file to write.")
+            )
+        )
 }
 
 //  ##    ##     ##     ########  ##    ##
@@ -419,15 +1297,93 @@ pub(crate) fn new_app<'a, 'b>() -> App<'a, 'b> {
 
 //* To call find .csvs in ".\\src" folder, use "-F '.\\src'"
 fn main() -> Result<()> {
-    println!("\n=====  BROGUE SEED SCANNER  =====\n");
- 
     let matches = new_app().get_matches();
-        
-    // --- Get Params and Perform Search --- //
-    let mut search = SearchParameters::from_matches(matches)?;
-    let search_matches = search_files(&mut search)?;
+    let args: Vec<String> = std::env::args().collect();
+    let data_dir = matches
+        .value_of("data_dir")
+        .map(file_handling::expand_path)
+        .transpose();
+    if let Err(e) = data_dir.and_then(|dir| objects::init_tables(dir.as_deref())) {
+        exit_with_error(e, &matches)?;
+    }
 
-    display_matches(&search_matches, &search);
+    if let Some(daemon_matches) = matches.subcommand_matches("daemon") {
+        if let Err(e) = run_server(daemon_matches, args) {
+            exit_with_error(e, &matches)?;
+        }
+        return Ok(());
+    }
+
+    if let Err(e) = Engine::new().run(matches.clone(), &args) {
+        exit_with_error(e, &matches)?;
+    }
 
     Ok(())
 }
+
+/// Runs the `daemon` subcommand.
+#[cfg(feature = "server")]
+fn run_server(daemon_matches: &clap::ArgMatches, args: Vec<String>) -> Result<()> {
+    use daemon::{daemon_base_args, open_request_log, run_daemon};
+    use metrics::{serve_metrics, Metrics};
+    use request_log::LogEntry;
+    use std::sync::Arc;
+
+    let mut engine = Engine::resident();
+    let base_args = daemon_base_args(args);
+
+    if let Some(addr) = daemon_matches.value_of("metrics") {
+        let metrics = Arc::new(Metrics::default());
+        serve_metrics(metrics.clone(), addr)?;
+        engine.set_metrics(metrics);
+    }
+    if let Some(path) = daemon_matches.value_of("log") {
+        engine.set_log(open_request_log(path, daemon_matches)?);
+        engine.record(LogEntry::event("start", None));
+    }
+
+    #[cfg(unix)]
+    if let Some(path) = daemon_matches.value_of("socket") {
+        return ipc::run_socket(&mut engine, &base_args, &file_handling::expand_path(path)?);
+    }
+
+    run_daemon(&mut engine, &base_args)
+}
+
+/// The daemon's `--socket` option, on unix only (Windows named pipes aren't served).
+fn socket_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    match cfg!(unix) {
+        true => vec![Arg::with_name("socket").long("socket").value_name("PATH").help(
+            "Serves searches over a unix domain socket at PATH instead of standard \
+                input, using length-prefixed JSON frames (see the 'ipc' module docs).",
+        )],
+        false => Vec::new(),
+    }
+}
+
+/// Runs the `daemon` subcommand (only in builds with the `server` feature).
+#[cfg(not(feature = "server"))]
+fn run_server(_daemon_matches: &clap::ArgMatches, _args: Vec<String>) -> Result<()> {
+    Err(anyhow::anyhow!(
+        "this build has no daemon (built without the 'server' feature)"
+    ))
+}
+
+/// Reports an error of the scanner (with its status) and exits with its status code.
+fn exit_with_error(e: anyhow::Error, matches: &clap::ArgMatches) -> Result<()> {
+    let status = Status::of_error(&e);
+    let mode = OutputMode::from_matches(matches);
+
+    if mode == OutputMode::Json || mode == OutputMode::Ndjson {
+        write_status(
+            &mut std::io::stdout().lock(),
+            mode,
+            status,
+            format!("{:#}", e),
+        )?;
+    }
+    eprintln!("Error: {:#}", e);
+    eprintln!("Status: {}.", status);
+
+    std::process::exit(status.exit_code());
+}
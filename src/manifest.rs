@@ -0,0 +1,148 @@
+//! Source manifests for cached artifacts of Brogue Seed Scanner.
+//!
+//! Artifacts built from catalog files (the rarity table, and the catalog index) record the
+//! size, modification time, and checksum of each file they were built from.  Before an
+//! artifact is used, its sources are checked against the files as they are now:  a file is
+//! unchanged if its size and modification time are the same, or (if only the time changed)
+//! its checksum is.  Files added, removed, or changed since make the artifact stale.
+//!
+//! Stale artifacts are never used as they are.  By default, a stale rarity table is refused
+//! (with a note saying how to rebuild it), and index entries of changed files are ignored
+//! (so those files are searched in full).  With `--rebuild-stale`, stale artifacts are
+//! rebuilt before the search instead.
+
+use crate::archive::split_archive_path;
+use crate::file_handling::open_file;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// A source file, as it was when an artifact was built.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// File name.
+    pub name: String,
+    /// Size in bytes.
+    pub size: u64,
+    /// Modification time, in nanoseconds since the Unix epoch.
+    pub modified: u64,
+    /// 64-bit FNV-1a hash of the contents.
+    pub checksum: u64,
+}
+
+impl ManifestEntry {
+    /// Returns `true` if the file is unchanged.
+    fn is_current(&self, path: &Path) -> bool {
+        match file_stamp(path) {
+            Ok(stamp) if stamp == (self.size, self.modified) => true,
+            Ok((size, _)) => size == self.size && checksum(path).ok() == Some(self.checksum),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Source files of an artifact, sorted by name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub files: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    /// Records the given files as they are now.
+    pub fn build(file_paths: &[PathBuf]) -> Result<Self> {
+        let mut files = Vec::with_capacity(file_paths.len());
+
+        for path in file_paths.iter() {
+            let (size, modified) = file_stamp(path)?;
+            files.push(ManifestEntry {
+                name: file_name(path),
+                size,
+                modified,
+                checksum: checksum(path)?,
+            });
+        }
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(Self { files })
+    }
+    /// Returns the names of files added, removed, or changed since the manifest was built
+    /// (empty if the artifact is current).
+    pub fn stale_files(&self, file_paths: &[PathBuf]) -> Vec<String> {
+        let mut stale = Vec::new();
+        let mut seen = vec![false; self.files.len()];
+
+        for path in file_paths.iter() {
+            let name = file_name(path);
+
+            match self.files.binary_search_by(|entry| entry.name.cmp(&name)) {
+                Ok(i) => {
+                    seen[i] = true;
+                    if !self.files[i].is_current(path) {
+                        stale.push(name);
+                    }
+                }
+                Err(_) => stale.push(name),
+            }
+        }
+
+        let removed = self.files.iter().zip(seen).filter(|(_, seen)| !seen);
+        stale.extend(removed.map(|(entry, _)| entry.name.clone()));
+        stale.sort();
+
+        stale
+    }
+}
+
+/// Describes stale files for a message, naming at most a few of them.
+pub fn stale_summary(stale: &[String]) -> String {
+    const NAMED: usize = 3;
+
+    match stale.len() {
+        n if n <= NAMED => stale.join(", "),
+        n => format!("{}, and {} more", stale[..NAMED].join(", "), n - NAMED),
+    }
+}
+
+/// Returns the name of a file.
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Returns a file's size and modification time (in nanoseconds since the Unix epoch).
+/// Files in a zip archive take the stamp of the archive.
+pub(crate) fn file_stamp(path: &Path) -> Result<(u64, u64)> {
+    let path = split_archive_path(path).map_or(path, |(archive, _)| archive);
+    let metadata = fs::metadata(path)?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_nanos() as u64);
+
+    Ok((metadata.len(), modified))
+}
+
+/// Returns the 64-bit FNV-1a hash of a file's contents.
+pub(crate) fn checksum(path: &Path) -> Result<u64> {
+    let mut file = open_file(path)?;
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+
+    loop {
+        let len = file.read(&mut buffer)?;
+        if len == 0 {
+            break;
+        }
+        for byte in buffer[..len].iter() {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    Ok(hash)
+}
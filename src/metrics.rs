@@ -0,0 +1,239 @@
+//! Prometheus metrics for the daemon.
+//!
+//! `brogue-scanner [OPTIONS] daemon --metrics ADDR` serves `GET /metrics` over HTTP at
+//! ADDR (e.g. `127.0.0.1:9184`) in the Prometheus text format, so a seed-search service
+//! built on the daemon can be monitored.  Metrics:
+//!
+//! - `brogue_requests_total{status}`:  requests answered, by status (`ok`, `error`, or
+//!   `cancelled`)
+//! - `brogue_connection_errors_total`:  `--socket` connections dropped by an error
+//! - `brogue_rows_scanned_total`:  catalog Records read by searches
+//! - `brogue_scan_duration_seconds`:  histogram of the time taken by each scan of the
+//!   catalog files (a relaxed search scans them more than once)
+//! - `brogue_cache_hits_total{cache}`, `brogue_cache_misses_total{cache}`:  lookups of the
+//!   in-memory `catalog` files and the `rarity` tables
+//!
+//! Only `GET /metrics` is served (anything else is a 404), one connection at a time on a
+//! thread of its own.
+
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Upper bounds of the scan duration histogram buckets, in seconds.
+const SCAN_BUCKETS: [f64; 10] = [0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0];
+
+/// Request statuses counted by `brogue_requests_total`.
+const STATUSES: [&str; 3] = ["ok", "error", "cancelled"];
+
+/// Caches counted by `brogue_cache_hits_total` and `brogue_cache_misses_total`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cache {
+    Catalog,
+    Rarity,
+}
+
+impl Cache {
+    /// Returns the `cache` label of the cache.
+    fn label(self) -> &'static str {
+        match self {
+            Self::Catalog => "catalog",
+            Self::Rarity => "rarity",
+        }
+    }
+}
+
+/// Counters shared between the engine and the metrics endpoint.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// Requests by status (in `STATUSES` order).
+    requests: [AtomicU64; 3],
+    connection_errors: AtomicU64,
+    rows_scanned: AtomicU64,
+    /// Scans per duration bucket (in `SCAN_BUCKETS` order, then `+Inf`), not cumulative.
+    scan_buckets: [AtomicU64; 11],
+    /// Total time of every scan, in microseconds.
+    scan_micros: AtomicU64,
+    /// Cache hits and misses (catalog, then rarity).
+    cache_hits: [AtomicU64; 2],
+    cache_misses: [AtomicU64; 2],
+}
+
+impl Metrics {
+    /// Counts an answered request with the given status.
+    pub fn add_request(&self, status: &str) {
+        if let Some(i) = STATUSES.iter().position(|s| *s == status) {
+            self.requests[i].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    /// Counts a dropped connection.
+    pub fn add_connection_error(&self) {
+        self.connection_errors.fetch_add(1, Ordering::Relaxed);
+    }
+    /// Counts a scan of the catalog files that took `duration` and read `rows` Records.
+    pub fn add_scan(&self, duration: Duration, rows: u64) {
+        let seconds = duration.as_secs_f64();
+        let bucket = SCAN_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(SCAN_BUCKETS.len());
+
+        self.scan_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.scan_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+        self.rows_scanned.fetch_add(rows, Ordering::Relaxed);
+    }
+    /// Counts a cache lookup.
+    pub fn add_cache_lookup(&self, cache: Cache, hit: bool) {
+        let counters = if hit { &self.cache_hits } else { &self.cache_misses };
+        counters[cache as usize].fetch_add(1, Ordering::Relaxed);
+    }
+    /// Returns every metric in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let mut text = String::new();
+
+        let _ = writeln!(
+            text,
+            "# HELP brogue_requests_total Requests answered, by status."
+        );
+        let _ = writeln!(text, "# TYPE brogue_requests_total counter");
+        for (status, count) in STATUSES.iter().zip(self.requests.iter()) {
+            let _ = writeln!(
+                text,
+                "brogue_requests_total{{status=\"{}\"}} {}",
+                status,
+                load(count)
+            );
+        }
+
+        let _ = writeln!(
+            text,
+            "# HELP brogue_connection_errors_total Socket connections dropped by an error."
+        );
+        let _ = writeln!(text, "# TYPE brogue_connection_errors_total counter");
+        let _ = writeln!(
+            text,
+            "brogue_connection_errors_total {}",
+            load(&self.connection_errors)
+        );
+
+        let _ = writeln!(
+            text,
+            "# HELP brogue_rows_scanned_total Catalog records read by searches."
+        );
+        let _ = writeln!(text, "# TYPE brogue_rows_scanned_total counter");
+        let _ = writeln!(
+            text,
+            "brogue_rows_scanned_total {}",
+            load(&self.rows_scanned)
+        );
+
+        let _ = writeln!(
+            text,
+            "# HELP brogue_scan_duration_seconds Time taken by each scan of the catalog files."
+        );
+        let _ = writeln!(text, "# TYPE brogue_scan_duration_seconds histogram");
+        let mut scans = 0;
+        for (i, count) in self.scan_buckets.iter().enumerate() {
+            scans += load(count);
+            match SCAN_BUCKETS.get(i) {
+                Some(bound) => {
+                    let _ = writeln!(
+                        text,
+                        "brogue_scan_duration_seconds_bucket{{le=\"{}\"}} {}",
+                        bound, scans
+                    );
+                }
+                None => {
+                    let _ = writeln!(
+                        text,
+                        "brogue_scan_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+                        scans
+                    );
+                }
+            }
+        }
+        let _ = writeln!(
+            text,
+            "brogue_scan_duration_seconds_sum {}",
+            load(&self.scan_micros) as f64 / 1e6
+        );
+        let _ = writeln!(text, "brogue_scan_duration_seconds_count {}", scans);
+
+        for (name, help, counters) in [
+            (
+                "brogue_cache_hits_total",
+                "Cache lookups that found a current entry.",
+                &self.cache_hits,
+            ),
+            (
+                "brogue_cache_misses_total",
+                "Cache lookups that had to load an entry.",
+                &self.cache_misses,
+            ),
+        ] {
+            let _ = writeln!(text, "# HELP {} {}", name, help);
+            let _ = writeln!(text, "# TYPE {} counter", name);
+            for cache in [Cache::Catalog, Cache::Rarity] {
+                let _ = writeln!(
+                    text,
+                    "{}{{cache=\"{}\"}} {}",
+                    name,
+                    cache.label(),
+                    load(&counters[cache as usize])
+                );
+            }
+        }
+
+        text
+    }
+}
+
+/// Serves the metrics over HTTP at `addr` on a new thread.
+pub fn serve_metrics(metrics: Arc<Metrics>, addr: &str) -> Result<()> {
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("can't serve metrics at {}", addr))?;
+    eprintln!(
+        "serving metrics at http://{}/metrics",
+        listener.local_addr()?
+    );
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if let Err(e) = answer(&metrics, stream) {
+                eprintln!("metrics connection error: {}", e);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Answers a single HTTP request.
+fn answer(metrics: &Metrics, mut stream: TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let (status, body) = match request_line.split_whitespace().take(2).collect::<Vec<_>>()[..] {
+        ["GET", "/metrics"] => ("200 OK", metrics.render()),
+        _ => ("404 Not Found", "not found\n".to_string()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+
+    Ok(())
+}
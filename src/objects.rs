@@ -11,6 +11,7 @@ mod potions;
 mod rings;
 mod scrolls;
 mod staves;
+mod tables;
 mod wands;
 mod weapons;
 
@@ -26,6 +27,8 @@ pub use potions::{Potion, PotionKind};
 pub use rings::{Ring, RingKind};
 pub use scrolls::{Scroll, ScrollKind};
 pub use staves::{Staff, StaffKind};
+pub use tables::init_tables;
+pub(crate) use tables::{tables, RawTables, Tables};
 pub use wands::{Wand, WandKind};
 pub use weapons::{Weapon, WeaponKind, WeaponRunic};
 
@@ -50,6 +53,8 @@ pub enum Category {
     Item,
     /// Any object that can be equipped (Weapon/Armor/Ring)
     Equipment,
+    /// Any object at all (for `--filter`)
+    Any,
 }
 
 impl Category {
@@ -82,7 +87,7 @@ impl Category {
         None
     }
     /// Converts a `Category` into a u16 `BitFlags` representation.
-    pub fn to_flags(&self) -> BitFlags16 {
+    pub fn to_flags(self) -> BitFlags16 {
         let mut flags = BitFlags16::new();
 
         match self {
@@ -114,14 +119,81 @@ impl Category {
                 flags.insert(ring);
                 flags.insert(weapon);
             }
+            Category::Any => {
+                for index in (Self::Ally as usize)..=(Self::Weapon as usize) {
+                    flags.insert(BitFlags16::from_index(index));
+                }
+            }
             _ => {
-                let val = BitFlags16::from_index(*self as usize);
+                let val = BitFlags16::from_index(self as usize);
                 flags.insert(val);
             }
         }
 
         flags
     }
+    /// Returns the object categories the category covers (several for `Item`,
+    /// `Equipment`, and `Any`).
+    pub fn categories(self) -> Vec<Category> {
+        use Category::*;
+
+        const OBJECT_CATEGORIES: [Category; 13] = [
+            Ally, Altar, Armor, Charm, Food, Gold, Key, Potion, Ring, Scroll, Staff, Wand, Weapon,
+        ];
+        let flags = self.to_flags();
+
+        OBJECT_CATEGORIES
+            .iter()
+            .copied()
+            .filter(|c| flags.intersects(c.to_flags()))
+            .collect()
+    }
+    /// Returns the names of the category's kinds a search term partially matches.
+    pub fn kinds_matching(self, value: &str) -> Vec<&'static str> {
+        match self {
+            Category::Ally => MonsterKind::names_matching(value),
+            Category::Altar => AltarKind::names_matching(value),
+            Category::Armor => ArmorKind::names_matching(value),
+            Category::Charm => CharmKind::names_matching(value),
+            Category::Food => FoodKind::names_matching(value),
+            Category::Potion => PotionKind::names_matching(value),
+            Category::Ring => RingKind::names_matching(value),
+            Category::Scroll => ScrollKind::names_matching(value),
+            Category::Staff => StaffKind::names_matching(value),
+            Category::Wand => WandKind::names_matching(value),
+            Category::Weapon => WeaponKind::names_matching(value),
+            _ => Vec::new(),
+        }
+    }
+    /// Returns the names of the category's runics a search term partially matches.
+    pub fn runics_matching(self, value: &str) -> Vec<&'static str> {
+        match self {
+            Category::Armor => ArmorRunic::names_matching(value),
+            Category::Weapon => WeaponRunic::names_matching(value),
+            _ => Vec::new(),
+        }
+    }
+    /// Returns the name of the category for several objects, e.g. "staves".
+    pub fn plural(self) -> &'static str {
+        match self {
+            Category::Ally => "allies",
+            Category::Altar => "altars",
+            Category::Armor => "armor",
+            Category::Charm => "charms",
+            Category::Food => "food",
+            Category::Gold => "gold",
+            Category::Key => "keys",
+            Category::Potion => "potions",
+            Category::Ring => "rings",
+            Category::Scroll => "scrolls",
+            Category::Staff => "staves",
+            Category::Wand => "wands",
+            Category::Weapon => "weapons",
+            Category::Item => "items",
+            Category::Equipment => "equipment",
+            Category::Any => "any",
+        }
+    }
 }
 
 impl std::fmt::Display for Category {
@@ -142,6 +214,7 @@ impl std::fmt::Display for Category {
             Category::Weapon => { "weapon" }
             Category::Item => { "item" }
             Category::Equipment => { "equipment" }
+            Category::Any => { "any" }
         };
         write!(f, "{}", val)
     }
@@ -217,7 +290,85 @@ impl Object {
     /// Makes a new `Object` from weapon data.
     pub fn new_weapon(kind: WeaponKind, enchantment: i8, runic: Option<WeaponRunic>) -> Self {
         Object::Weapon(Weapon::new(kind, enchantment, runic))
-    }   
+    }
+    /// Returns the `Category` of the object.
+    pub fn category(&self) -> Category {
+        match self {
+            Object::Ally(_) => Category::Ally,
+            Object::Altar(_) => Category::Altar,
+            Object::Armor(_) => Category::Armor,
+            Object::Charm(_) => Category::Charm,
+            Object::Food(_) => Category::Food,
+            Object::Gold(_) => Category::Gold,
+            Object::Key(_) => Category::Key,
+            Object::Ring(_) => Category::Ring,
+            Object::Potion(_) => Category::Potion,
+            Object::Scroll(_) => Category::Scroll,
+            Object::Staff(_) => Category::Staff,
+            Object::Wand(_) => Category::Wand,
+            Object::Weapon(_) => Category::Weapon,
+        }
+    }
+    /// Returns the display name of the object's kind (e.g. "war axe").
+    pub fn kind_name(&self) -> String {
+        match self {
+            Object::Ally(o) => o.kind.to_string(),
+            Object::Altar(o) => o.kind.to_string(),
+            Object::Armor(o) => o.kind.to_string(),
+            Object::Charm(o) => o.kind.to_string(),
+            Object::Food(o) => o.kind.to_string(),
+            Object::Gold(o) => o.kind.to_string(),
+            Object::Key(o) => o.kind.to_string(),
+            Object::Ring(o) => o.kind.to_string(),
+            Object::Potion(o) => o.kind.to_string(),
+            Object::Scroll(o) => o.kind.to_string(),
+            Object::Staff(o) => o.kind.to_string(),
+            Object::Wand(o) => o.kind.to_string(),
+            Object::Weapon(o) => o.kind.to_string(),
+        }
+    }
+    /// Returns the enchantment level of the object, if it has one.  For staves and
+    /// wands, this is the number of charges.
+    pub fn enchantment(&self) -> Option<i8> {
+        match self {
+            Object::Armor(o) => Some(o.enchantment),
+            Object::Charm(o) => Some(o.enchantment),
+            Object::Ring(o) => Some(o.enchantment),
+            Object::Staff(o) => Some(o.enchantment),
+            Object::Wand(o) => Some(o.enchantment),
+            Object::Weapon(o) => Some(o.enchantment),
+            _ => None,
+        }
+    }
+    /// Returns the display name of the object's runic, if it has one.
+    pub fn runic_name(&self) -> Option<String> {
+        match self {
+            Object::Armor(o) => o.runic.map(|r| r.to_string()),
+            Object::Weapon(o) => o.runic.map(|r| r.to_string()),
+            _ => None,
+        }
+    }
+    /// Returns the ally status (caged, shackled, legendary) for allies.
+    pub fn ally_status(&self) -> Option<AllyStatus> {
+        match self {
+            Object::Ally(o) => Some(o.status),
+            _ => None,
+        }
+    }
+    /// Returns the mutation of an ally, if any.
+    pub fn mutation(&self) -> Option<Mutation> {
+        match self {
+            Object::Ally(o) => o.mutation,
+            _ => None,
+        }
+    }
+    /// Returns the vault number a key opens, if any.
+    pub fn opens_vault(&self) -> Option<u8> {
+        match self {
+            Object::Key(o) => o.opens,
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for Object {
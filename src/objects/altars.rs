@@ -1,9 +1,11 @@
 //! Altars for Brogue Seed Scanner.
 
+use super::tables::{tables, Named};
+
 /// Describes a Brogue Altar.
 #[derive(Clone, Debug)]
 pub struct Altar {
-    kind: AltarKind,
+    pub(crate) kind: AltarKind,
 }
 
 impl Altar {
@@ -19,7 +21,7 @@ impl std::fmt::Display for Altar {
 }
 
 /// Kinds for the Charm Category.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum AltarKind {
     CommutationAltar,   
@@ -29,37 +31,25 @@ pub enum AltarKind {
 impl AltarKind {
     /// Attempts to parse from a string using an _exact_ match.
     pub fn parse(value: &str) -> Option<Self> {
-        for (name, kind) in ALTAR_KINDS.iter() {
-            if name == &value {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse(value)
     }       
     /// Attempts to parse from a string using a _partial_ match.
     pub fn parse_partial(value: &str) -> Option<Self> {
-        for (name, kind) in ALTAR_KINDS.iter() {
-            if name.contains(value) {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse_partial(value)
     }   
+    /// Returns the names of all values a search term partially matches.
+    pub fn names_matching(value: &str) -> Vec<&'static str> {
+        tables().names_matching::<Self>(value)
+    }
 }
 
 impl std::fmt::Display for AltarKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let result = match self {
-            AltarKind::CommutationAltar => "commutation altar",
-            AltarKind::ResurrectionAltar => "resurrection altar",
-        };
-        write!(f, "{}", result)
+        write!(f, "{}", tables().name(*self))
     }
 }
 
-const ALTAR_KINDS: [(&str, AltarKind); 2] = [
-    ("commutation altar", AltarKind::CommutationAltar),
-    ("resurrection altar", AltarKind::ResurrectionAltar),
-];
+impl Named for AltarKind {
+    const TABLE: &'static str = "altar_kinds";
+    const ALL: &'static [Self] = &[AltarKind::CommutationAltar, AltarKind::ResurrectionAltar];
+}
@@ -1,13 +1,14 @@
 //! Armor and armor runics for Brogue Seed Scanner.
 
+use super::tables::{tables, Named};
 use super::MonsterClass;
 
 /// Describes a piece of Brogue Armor.
 #[derive(Clone, Debug)]
 pub struct Armor {
-    kind: ArmorKind,
-    enchantment: i8,
-    runic: Option<ArmorRunic>,
+    pub(crate) kind: ArmorKind,
+    pub(crate) enchantment: i8,
+    pub(crate) runic: Option<ArmorRunic>,
 }
 
 impl Armor {
@@ -31,7 +32,7 @@ impl std::fmt::Display for Armor {
 }
 
 /// Kinds for the Armor Category.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum ArmorKind {
     BandedMail, 
@@ -45,43 +46,49 @@ pub enum ArmorKind {
 impl ArmorKind {
     /// Attempts to fully parse from a string using an _exact_ match.
     pub fn parse(value: &str) -> Option<Self> {
-        for (name, kind) in ARMOR_KINDS.iter() {
-            if name == &value {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse(value)
     }
     /// Attempts to parse from a string using a _partial_ match.
     pub fn parse_partial(value: &str) -> Option<Self> {
-        for (name, kind) in ARMOR_KINDS.iter() {
-            if name.contains(value) {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse_partial(value)
     }       
+    /// Returns the names of all values a search term partially matches.
+    pub fn names_matching(value: &str) -> Vec<&'static str> {
+        tables().names_matching::<Self>(value)
+    }
+    /// Returns the base armor value of the armor (as displayed, before enchantment).
+    pub fn armor_value(self) -> u8 {
+        match self {
+            ArmorKind::LeatherArmor => 3,
+            ArmorKind::ScaleMail => 4,
+            ArmorKind::ChainMail => 5,
+            ArmorKind::BandedMail => 7,
+            ArmorKind::SplintMail => 9,
+            ArmorKind::PlateMail => 11,
+        }
+    }
+    /// Returns the strength needed to wear the armor without penalty.
+    pub fn strength(self) -> u8 {
+        match self {
+            ArmorKind::LeatherArmor => 10,
+            ArmorKind::ScaleMail => 11,
+            ArmorKind::ChainMail => 13,
+            ArmorKind::BandedMail => 15,
+            ArmorKind::SplintMail => 17,
+            ArmorKind::PlateMail => 19,
+        }
+    }
 }
 
 impl std::fmt::Display for ArmorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let result = match self {
-            ArmorKind::BandedMail => "banded mail",
-            ArmorKind::ChainMail => "chain mail",
-            ArmorKind::LeatherArmor => "leather armor",
-            ArmorKind::PlateMail => "plate mail",
-            ArmorKind::ScaleMail => "scale mail",
-            ArmorKind::SplintMail => "splint mail",
-        };
-        write!(f, "{}", result)
+        write!(f, "{}", tables().name(*self))
     }
 }
 
 
 // Runics for Armor.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum ArmorRunic {
     // --- Positive --- //
@@ -102,80 +109,70 @@ pub enum ArmorRunic {
 impl ArmorRunic {
     /// Attempts to fully parse from a string using an _exact_ match.
     pub fn parse(value: &str) -> Option<Self> {
-        for (name, kind) in ARMOR_RUNICS.iter() {
-            if name == &value {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse(value)
     }
     /// Attempts to parse from a string using a _partial_ match.
     pub fn parse_partial(value: &str) -> Option<Self> {
-        for (name, kind) in ARMOR_RUNICS.iter() {
-            if name.contains(value) {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse_partial(value)
     }         
+    /// Returns the names of all values a search term partially matches.
+    pub fn names_matching(value: &str) -> Vec<&'static str> {
+        tables().names_matching::<Self>(value)
+    }
+    /// Returns `true` if the runic is negative.
+    pub fn is_malevolent(&self) -> bool {
+        matches!(
+            self,
+            ArmorRunic::Burden | ArmorRunic::Immolation | ArmorRunic::Vulnerability
+        )
+    }
 }
 
 impl std::fmt::Display for ArmorRunic {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ArmorRunic::Absorption => write!(f, "absorption"),
-            ArmorRunic::Dampening => write!(f, "dampening"),
-            ArmorRunic::Multiplicity => write!(f, "multiplicity"),
-            ArmorRunic::Mutuality => write!(f, "mutuality"),
-            ArmorRunic::Reflection => write!(f, "reflection"),
-            ArmorRunic::Reprisal => write!(f, "reprisal"),
-            ArmorRunic::Respiration => write!(f, "respiration"),
-            ArmorRunic::Burden => write!(f, "burden"),
-            ArmorRunic::Immolation => write!(f, "immolation"),
-            ArmorRunic::Vulnerability => write!(f, "vulnerability"),
-            ArmorRunic::Immunity(mclass) => write!(f, "{} immunity", mclass),            
-        }
+        write!(f, "{}", tables().name(*self))
     }
 }
 
-const ARMOR_KINDS: [(&str, ArmorKind); 6] = [
-    ("banded mail", ArmorKind::BandedMail),
-    ("chain mail", ArmorKind::ChainMail),
-    ("leather armor", ArmorKind::LeatherArmor),
-    ("plate armor", ArmorKind::PlateMail),
-    ("scale mail", ArmorKind::ScaleMail),
-    ("splint mail", ArmorKind::SplintMail),            
-];
+impl Named for ArmorKind {
+    const TABLE: &'static str = "armor_kinds";
+    const ALL: &'static [Self] = &[
+        ArmorKind::BandedMail,
+        ArmorKind::ChainMail,
+        ArmorKind::LeatherArmor,
+        ArmorKind::PlateMail,
+        ArmorKind::ScaleMail,
+        ArmorKind::SplintMail,
+    ];
+}
 
-const ARMOR_RUNICS: [(&str, ArmorRunic); 25] = [
-    // --- Positive --- //
-    ("absorption", ArmorRunic::Absorption),
-    ("dampening", ArmorRunic::Dampening),
-    ("multiplicity", ArmorRunic::Multiplicity),
-    ("mutuality", ArmorRunic::Mutuality),
-    ("reflection", ArmorRunic::Reflection),
-    ("reprisal", ArmorRunic::Reprisal),
-    ("respiration", ArmorRunic::Respiration),                     
-    // --- Negative --- //
-    ("burden", ArmorRunic::Burden),            
-    ("immolation", ArmorRunic::Immolation),            
-    ("vulnerability", ArmorRunic::Vulnerability),            
-    // --- Immunity --- //
-    ("airborne immunity", ArmorRunic::Immunity(MonsterClass::Airborne)),            
-    ("abomination immunity", ArmorRunic::Immunity(MonsterClass::Abomination)),
-    ("animal immunity", ArmorRunic::Immunity(MonsterClass::Animal)),
-    ("dar immunity", ArmorRunic::Immunity(MonsterClass::Dar)),       
-    ("dragon immunity", ArmorRunic::Immunity(MonsterClass::Dragon)),
-    ("fireborne immunity", ArmorRunic::Immunity(MonsterClass::Fireborne)),
-    ("goblin immunity", ArmorRunic::Immunity(MonsterClass::Goblin)),       
-    ("infernal immunity", ArmorRunic::Immunity(MonsterClass::Infernal)),
-    ("jelly immunity", ArmorRunic::Immunity(MonsterClass::Jelly)),      
-    ("mage immunity", ArmorRunic::Immunity(MonsterClass::Mage)),
-    ("ogre immunity", ArmorRunic::Immunity(MonsterClass::Ogre)),
-    ("troll immunity", ArmorRunic::Immunity(MonsterClass::Troll)),
-    ("turret immunity", ArmorRunic::Immunity(MonsterClass::Turret)),
-    ("undead immunity", ArmorRunic::Immunity(MonsterClass::Undead)),
-    ("waterborne immunity", ArmorRunic::Immunity(MonsterClass::Waterborne)),    
-];
+impl Named for ArmorRunic {
+    const TABLE: &'static str = "armor_runics";
+    const ALL: &'static [Self] = &[
+        ArmorRunic::Absorption,
+        ArmorRunic::Dampening,
+        ArmorRunic::Multiplicity,
+        ArmorRunic::Mutuality,
+        ArmorRunic::Reflection,
+        ArmorRunic::Reprisal,
+        ArmorRunic::Respiration,
+        ArmorRunic::Burden,
+        ArmorRunic::Immolation,
+        ArmorRunic::Vulnerability,
+        ArmorRunic::Immunity(MonsterClass::Airborne),
+        ArmorRunic::Immunity(MonsterClass::Abomination),
+        ArmorRunic::Immunity(MonsterClass::Animal),
+        ArmorRunic::Immunity(MonsterClass::Dar),
+        ArmorRunic::Immunity(MonsterClass::Dragon),
+        ArmorRunic::Immunity(MonsterClass::Fireborne),
+        ArmorRunic::Immunity(MonsterClass::Goblin),
+        ArmorRunic::Immunity(MonsterClass::Infernal),
+        ArmorRunic::Immunity(MonsterClass::Jelly),
+        ArmorRunic::Immunity(MonsterClass::Mage),
+        ArmorRunic::Immunity(MonsterClass::Ogre),
+        ArmorRunic::Immunity(MonsterClass::Troll),
+        ArmorRunic::Immunity(MonsterClass::Turret),
+        ArmorRunic::Immunity(MonsterClass::Undead),
+        ArmorRunic::Immunity(MonsterClass::Waterborne),
+    ];
+}
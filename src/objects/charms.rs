@@ -1,10 +1,12 @@
 //! Charms for Brogue Seed Scanner.
 
+use super::tables::{tables, Named};
+
 /// Describes a Brogue Charm.
 #[derive(Clone, Debug)]
 pub struct Charm {
-    kind: CharmKind,
-    enchantment: i8,       // Not an Option as all charms have an enchantment
+    pub(crate) kind: CharmKind,
+    pub(crate) enchantment: i8, // Not an Option as all charms have an enchantment
 }
 
 impl Charm {
@@ -24,7 +26,7 @@ impl std::fmt::Display for Charm {
 }
 
 /// Kinds for the Charm Category.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum CharmKind {
     FireImmunity,   
@@ -43,58 +45,39 @@ pub enum CharmKind {
 
 impl std::fmt::Display for CharmKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let result = match self {
-            CharmKind::FireImmunity => "fire immunity",
-            CharmKind::Guardian => "guardian",
-            CharmKind::Haste => "haste",
-            CharmKind::Health => "health",
-            CharmKind::Invisibility => "invisibility",
-            CharmKind::Levitation => "levitation",
-            CharmKind::Negation => "negation",
-            CharmKind::Protection => "protection",
-            CharmKind::Recharging => "recharging",
-            CharmKind::Shattering => "shattering",
-            CharmKind::Telepathy => "telepathy",
-            CharmKind::Teleportation => "teleportation",
-        };
-        write!(f, "{}", result)
+        write!(f, "{}", tables().name(*self))
     }
 }
 
 impl CharmKind {
     /// Attempts to fully parse from a string using an _exact_ match.
     pub fn parse(value: &str) -> Option<Self> {
-        for (name, kind) in CHARM_KINDS.iter() {
-            if name == &value {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse(value)
     }
     /// Attempts to parse from a string using a _partial_ match.
     pub fn parse_partial(value: &str) -> Option<Self> {
-        for (name, kind) in CHARM_KINDS.iter() {
-            if name.contains(value) {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse_partial(value)
     }     
+    /// Returns the names of all values a search term partially matches.
+    pub fn names_matching(value: &str) -> Vec<&'static str> {
+        tables().names_matching::<Self>(value)
+    }
 }
 
-const CHARM_KINDS: [(&str, CharmKind); 12] = [
-    ("fire immunity", CharmKind::FireImmunity),
-    ("guardian", CharmKind::Guardian),
-    ("haste", CharmKind::Haste),
-    ("health", CharmKind::Health),
-    ("invisibility", CharmKind::Invisibility),
-    ("levitation", CharmKind::Levitation),
-    ("negation", CharmKind::Negation),
-    ("protection", CharmKind::Protection),
-    ("recharging", CharmKind::Recharging),
-    ("shattering", CharmKind::Shattering),
-    ("telepathy", CharmKind::Telepathy),
-    ("teleportation", CharmKind::Teleportation),
-];
\ No newline at end of file
+impl Named for CharmKind {
+    const TABLE: &'static str = "charm_kinds";
+    const ALL: &'static [Self] = &[
+        CharmKind::FireImmunity,
+        CharmKind::Guardian,
+        CharmKind::Haste,
+        CharmKind::Health,
+        CharmKind::Invisibility,
+        CharmKind::Levitation,
+        CharmKind::Negation,
+        CharmKind::Protection,
+        CharmKind::Recharging,
+        CharmKind::Shattering,
+        CharmKind::Telepathy,
+        CharmKind::Teleportation,
+    ];
+}
@@ -1,9 +1,11 @@
 //! Food for Brogue Seed Scanner.
 
+use super::tables::{tables, Named};
+
 /// Describes a Food item in Brogue.
 #[derive(Clone, Debug)]
 pub struct Food {
-    kind: FoodKind,
+    pub(crate) kind: FoodKind,
 }
 
 impl Food {
@@ -19,7 +21,7 @@ impl std::fmt::Display for Food {
 }
 
 /// Kinds for the Food Category.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum FoodKind {
     Mango,
@@ -29,37 +31,25 @@ pub enum FoodKind {
 impl FoodKind {
     /// Attempts to fully parse from a string using an _exact_ match.
     pub fn parse(value: &str) -> Option<Self> {
-        for (name, kind) in FOOD_KINDS.iter() {
-            if name == &value {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse(value)
     }
     /// Attempts to parse from a string using a _partial_ match.
     pub fn parse_partial(value: &str) -> Option<Self> {
-        for (name, kind) in FOOD_KINDS.iter() {
-            if name.contains(value) {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse_partial(value)
     }  
+    /// Returns the names of all values a search term partially matches.
+    pub fn names_matching(value: &str) -> Vec<&'static str> {
+        tables().names_matching::<Self>(value)
+    }
 }
 
 impl std::fmt::Display for FoodKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let result = match self {
-            FoodKind::RationOfFood => "ration of food",
-            FoodKind::Mango => "mango",
-        };
-        write!(f, "{}", result)
+        write!(f, "{}", tables().name(*self))
     }
 }
 
-const FOOD_KINDS: [(&str, FoodKind); 2] = [
-    ("mango", FoodKind::Mango),
-    ("ration of food", FoodKind::RationOfFood),
-];
+impl Named for FoodKind {
+    const TABLE: &'static str = "food_kinds";
+    const ALL: &'static [Self] = &[FoodKind::Mango, FoodKind::RationOfFood];
+}
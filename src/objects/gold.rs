@@ -3,8 +3,8 @@
 /// Describes a Gold item in Brogue.
 #[derive(Clone, Debug)]
 pub struct Gold {
-    count: u32,
-    kind: GoldKind,
+    pub(crate) count: u32,
+    pub(crate) kind: GoldKind,
 }
 
 impl Gold {
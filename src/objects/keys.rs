@@ -1,12 +1,14 @@
 //! Keys for Brogue Seed Scanner.
 
+use super::tables::{tables, Named};
+
 /// Describes a Brogue Key.
 #[allow(dead_code)]
 #[derive(Clone, Debug)]
 pub struct Key {
-    kind: KeyKind,
+    pub(crate) kind: KeyKind,
     /// Vault number this key opens, if any
-    opens: Option<u8>,
+    pub(crate) opens: Option<u8>,
 }
 
 impl Key {
@@ -23,7 +25,7 @@ impl std::fmt::Display for Key {
 
 
 /// Kinds for the Key Category.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum KeyKind {
     CageKey,    
@@ -34,29 +36,17 @@ pub enum KeyKind {
 impl KeyKind {
     /// Attempts to fully parse from a string using an _exact_ match.
     pub fn parse(value: &str) -> Option<Self> {
-        for (name, kind) in KEY_KINDS.iter() {
-            if name == &value {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse(value)
     }
 }
 
 impl std::fmt::Display for KeyKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let result = match self {
-            KeyKind::DoorKey => "door key",
-            KeyKind::CageKey => "cage key",
-            KeyKind::CrystalOrb => "crystal orb",
-        };
-        write!(f, "{}", result)
+        write!(f, "{}", tables().name(*self))
     }
 }
 
-const KEY_KINDS: [(&str, KeyKind); 3] = [
-    ("door key", KeyKind::DoorKey),
-    ("cage key", KeyKind::CageKey),
-    ("crystal orb", KeyKind::CrystalOrb),
-];
+impl Named for KeyKind {
+    const TABLE: &'static str = "key_kinds";
+    const ALL: &'static [Self] = &[KeyKind::DoorKey, KeyKind::CageKey, KeyKind::CrystalOrb];
+}
@@ -1,11 +1,13 @@
 //! Monsters, allies, classes, and mutations for Brogue Seed Scanner.
 
+use super::tables::{tables, Named};
+
 /// Describes a Brogue Ally.
 #[derive(Clone, Debug)]
 pub struct Ally {
-    kind: MonsterKind,
-    status: AllyStatus,
-    mutation: Option<Mutation>,
+    pub(crate) kind: MonsterKind,
+    pub(crate) status: AllyStatus,
+    pub(crate) mutation: Option<Mutation>,
 }
 
 impl Ally {
@@ -16,15 +18,20 @@ impl Ally {
 
 impl std::fmt::Display for Ally {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Catalogs call legendary allies "allied", which doesn't describe them
+        let status = match self.status {
+            AllyStatus::Allied => "legendary".to_string(),
+            status => status.to_string(),
+        };
         match self.mutation {
-            Some(mutation) => write!(f, "A {} {} <{}>", self.status, self.kind, mutation),
-            None => write!(f, "A {} {}", self.status, self.kind),
+            Some(mutation) => write!(f, "A {} {} <{}>", status, self.kind, mutation),
+            None => write!(f, "A {} {}", status, self.kind),
         }        
     }
 }
 
 /// An ally's status, under the "ally_status" .csv header.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum AllyStatus {
     /// For legendary allies
@@ -36,29 +43,18 @@ pub enum AllyStatus {
 impl AllyStatus {
     /// Attempts to fully parse from a string using an _exact_ match.
     pub fn parse(value: &str) -> Option<Self> {
-        for (name, kind) in ALLY_STATUS_KINDS.iter() {
-            if name == &value {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse(value)
     }
 }
 
 impl std::fmt::Display for AllyStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let result = match self {
-            AllyStatus::Allied => "legendary",
-            AllyStatus::Caged => "caged",
-            AllyStatus::Shackled => "shackled",
-        };
-        write!(f, "{}", result)
+        write!(f, "{}", tables().name(*self))
     }
 }
 
 /// Monster name, used under "kind" and "carried_by_monster_name" .csv headers.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum MonsterKind {
     AcidMound,
@@ -132,65 +128,39 @@ pub enum MonsterKind {
 impl MonsterKind {
     /// Attempts to fully parse from a string using an _exact_ match.
     pub fn parse(value: &str) -> Option<Self> {
-        for (name, kind) in MONSTER_KINDS.iter() {
-            if name == &value {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse(value)
     }
     /// Attempts to parse from a string using a _partial_ match.
     pub fn parse_partial(value: &str) -> Option<Self> {
-        for (name, kind) in MONSTER_KINDS.iter() {
-            if name.contains(value) {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse_partial(value)
     }    
+    /// Returns the names of all values a search term partially matches.
+    pub fn names_matching(value: &str) -> Vec<&'static str> {
+        tables().names_matching::<Self>(value)
+    }
+    /// Returns how strong the monster is as an ally, from 1 (weakest) to 3 (strongest).
+    pub fn tier(&self) -> u8 {
+        use MonsterKind::*;
+
+        match self {
+            DarBattlemage | DarBlademaster | DarPriestess | Dragon | Golem | Ifrit | Kraken
+            | Lich | Naga | Phoenix | Salamander | TentacleHorror | Troll | Unicorn | Vampire => 3,
+            BogMonster | Centaur | Fury | Goblin | GoblinConjurer | GoblinMystic
+            | GoblinWarlord | Imp | MangroveDryad | Ogre | OgreShaman | Pixie | Spider
+            | WilloTheWisp | Wraith | Zombie => 2,
+            _ => 1,
+        }
+    }
 }
 
 impl std::fmt::Display for MonsterKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let result = match self {
-            MonsterKind::BlackJelly => "black jelly",
-            MonsterKind::Centaur => "centaur",
-            MonsterKind::DarBattlemage => "dar battlemage",
-            MonsterKind::DarBlademaster => "dar blademaster",
-            MonsterKind::DarPriestess => "dar priestess",
-            MonsterKind::Dragon => "dragon",
-            MonsterKind::Flamedancer => "flamedancer",
-            MonsterKind::Goblin => "goblin",
-            MonsterKind::GoblinConjurer => "goblin conjurer",
-            MonsterKind::GoblinMystic => "goblin mystic",
-            MonsterKind::GoblinWarlord => "goblin warlord",
-            MonsterKind::Golem => "golem",
-            MonsterKind::Ifrit => "ifrit",
-            MonsterKind::Imp => "imp",
-            MonsterKind::MangroveDryad => "mangrove dryad",
-            MonsterKind::Monkey => "monkey",
-            MonsterKind::Naga => "naga",
-            MonsterKind::Ogre => "ogre",
-            MonsterKind::OgreShaman => "ogre shaman",
-            MonsterKind::Phoenix => "phoenix",
-            MonsterKind::PhoenixEgg => "phoenix egg",
-            MonsterKind::Pixie => "pixie",
-            MonsterKind::Salamander => "salamander",
-            MonsterKind::StoneGuardian => "stone guardian",
-            MonsterKind::TentacleHorror => "tentacle horror",
-            MonsterKind::Troll => "troll",
-            MonsterKind::Unicorn => "unicorn",
-            MonsterKind::Vampire => "vampire",
-            _ => "ERROR MONSTER KIND",
-        };
-        write!(f, "{}", result)
+        write!(f, "{}", tables().name(*self))
     }
 }
 
 /// Groups used to classify monsters in Brogue.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum MonsterClass {
     Airborne,
@@ -234,7 +204,7 @@ impl std::fmt::Display for MonsterClass {
 }
 
 /// Mutations under the "mutation_name" .csv header.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum Mutation {
     Agile,
@@ -250,39 +220,21 @@ pub enum Mutation {
 impl Mutation {
     /// Attempts to fully parse from a string using an _exact_ match.
     pub fn parse(value: &str) -> Option<Self> {
-        for (name, kind) in MUTATION_KINDS.iter() {
-            if name == &value {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse(value)
     }
     /// Attempts to parse from a string using a _partial_ match.
     pub fn parse_partial(value: &str) -> Option<Self> {
-        for (name, kind) in MUTATION_KINDS.iter() {
-            if name.contains(value) {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse_partial(value)
     }        
+    /// Returns the names of all values a search term partially matches.
+    pub fn names_matching(value: &str) -> Vec<&'static str> {
+        tables().names_matching::<Self>(value)
+    }
 }
 
 impl std::fmt::Display for Mutation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let result = match self {
-            Mutation::Agile => "agile",
-            Mutation::Explosive => "explosive",
-            Mutation::Grappling => "grappling",
-            Mutation::Infested => "infested",
-            Mutation::Juggernaut => "juggernaut",
-            Mutation::Reflective => "reflective",
-            Mutation::Toxic => "toxic",
-            Mutation::Vampiric => "vampiric",
-        };
-        write!(f, "{}", result)
+        write!(f, "{}", tables().name(*self))
     }
 }
 
@@ -292,87 +244,92 @@ impl std::fmt::Display for Mutation {
 //  ##    ##  ##    ##  ##  ####       ##     ##   
 //   ######    ######   ##    ##  ######      ##   
 
-const ALLY_STATUS_KINDS: [(&str, AllyStatus); 3] = [
-    ("allied", AllyStatus::Allied),
-    ("caged", AllyStatus::Caged),         
-    ("shackled", AllyStatus::Shackled),         
-];
+impl Named for AllyStatus {
+    const TABLE: &'static str = "ally_statuses";
+    const ALL: &'static [Self] = &[AllyStatus::Allied, AllyStatus::Caged, AllyStatus::Shackled];
+}
 
-const MONSTER_KINDS: [(&str, MonsterKind); 65] = [
-    ("acid mound", MonsterKind::AcidMound),
-    ("acidic jelly", MonsterKind::AcidicJelly),
-    ("arrow turret", MonsterKind::ArrowTurret),
-    ("black jelly", MonsterKind::BlackJelly),
-    ("bloat", MonsterKind::Bloat),
-    ("bog monster", MonsterKind::BogMonster),
-    ("centaur", MonsterKind::Centaur),
-    ("centipede", MonsterKind::Centipede),
-    ("dar battlemage", MonsterKind::DarBattlemage),
-    ("dar blademaster", MonsterKind::DarBlademaster),
-    ("dar priestess", MonsterKind::DarPriestess),
-    ("dart turret", MonsterKind::DartTurret),
-    ("dragon", MonsterKind::Dragon),
-    ("eel", MonsterKind::Eel),
-    ("explosive bloat", MonsterKind::ExplosiveBloat),
-    ("flame turret", MonsterKind::FlameTurret),
-    ("flamedancer", MonsterKind::Flamedancer),
-    ("fury", MonsterKind::Fury),
-    ("goblin", MonsterKind::Goblin),
-    ("goblin conjurer", MonsterKind::GoblinConjurer),
-    ("goblin mystic", MonsterKind::GoblinMystic),
-    ("goblin totem", MonsterKind::GoblinTotem),
-    ("goblin warlord", MonsterKind::GoblinWarlord),
-    ("golem", MonsterKind::Golem),
-    ("guardian spirit", MonsterKind::GuardianSpirit),
-    ("ifrit", MonsterKind::Ifrit),
-    ("imp", MonsterKind::Imp),
-    ("jackal", MonsterKind::Jackal),
-    ("kobold", MonsterKind::Kobold),
-    ("kraken", MonsterKind::Kraken),
-    ("lich", MonsterKind::Lich),
-    ("mangrove dryad", MonsterKind::MangroveDryad),
-    ("mirrored totem", MonsterKind::MirroredTotem),
-    ("monkey", MonsterKind::Monkey),
-    ("naga", MonsterKind::Naga),
-    ("ogre", MonsterKind::Ogre),
-    ("ogre shaman", MonsterKind::OgreShaman),
-    ("ogre totem", MonsterKind::OgreTotem),
-    ("phantom", MonsterKind::Phantom),
-    ("phoenix", MonsterKind::Phoenix),
-    ("phoenix egg", MonsterKind::PhoenixEgg),
-    ("phylactery", MonsterKind::Phylactery),
-    ("pink jelly", MonsterKind::PinkJelly),
-    ("pit bloat", MonsterKind::PitBloat),
-    ("pixie", MonsterKind::Pixie),
-    ("rat", MonsterKind::Rat),
-    ("revenant", MonsterKind::Revenant),
-    ("salamander", MonsterKind::Salamander),
-    ("sentinel", MonsterKind::Sentinel),
-    ("spark turret", MonsterKind::SparkTurret),
-    ("spectral blade", MonsterKind::SpectralBlade),
-    ("spider", MonsterKind::Spider),
-    ("stone guardian", MonsterKind::StoneGuardian),
-    ("tentacle horror", MonsterKind::TentacleHorror),
-    ("toad", MonsterKind::Toad),
-    ("troll", MonsterKind::Troll),
-    ("underworm", MonsterKind::Underworm),
-    ("unicorn", MonsterKind::Unicorn),
-    ("vampire", MonsterKind::Vampire),
-    ("vampire bat", MonsterKind::VampireBat),
-    ("warden of yendor", MonsterKind::WardenOfYendor),
-    ("will-o-the-wisp", MonsterKind::WilloTheWisp),
-    ("winged guardian", MonsterKind::WingedGuardian),
-    ("wraith", MonsterKind::Wraith),
-    ("zombie", MonsterKind::Zombie),
-];
+impl Named for MonsterKind {
+    const TABLE: &'static str = "monster_kinds";
+    const ALL: &'static [Self] = &[
+        MonsterKind::AcidMound,
+        MonsterKind::AcidicJelly,
+        MonsterKind::ArrowTurret,
+        MonsterKind::BlackJelly,
+        MonsterKind::Bloat,
+        MonsterKind::BogMonster,
+        MonsterKind::Centaur,
+        MonsterKind::Centipede,
+        MonsterKind::DarBattlemage,
+        MonsterKind::DarBlademaster,
+        MonsterKind::DarPriestess,
+        MonsterKind::DartTurret,
+        MonsterKind::Dragon,
+        MonsterKind::Eel,
+        MonsterKind::ExplosiveBloat,
+        MonsterKind::FlameTurret,
+        MonsterKind::Flamedancer,
+        MonsterKind::Fury,
+        MonsterKind::Goblin,
+        MonsterKind::GoblinConjurer,
+        MonsterKind::GoblinMystic,
+        MonsterKind::GoblinTotem,
+        MonsterKind::GoblinWarlord,
+        MonsterKind::Golem,
+        MonsterKind::GuardianSpirit,
+        MonsterKind::Ifrit,
+        MonsterKind::Imp,
+        MonsterKind::Jackal,
+        MonsterKind::Kobold,
+        MonsterKind::Kraken,
+        MonsterKind::Lich,
+        MonsterKind::MangroveDryad,
+        MonsterKind::MirroredTotem,
+        MonsterKind::Monkey,
+        MonsterKind::Naga,
+        MonsterKind::Ogre,
+        MonsterKind::OgreShaman,
+        MonsterKind::OgreTotem,
+        MonsterKind::Phantom,
+        MonsterKind::Phoenix,
+        MonsterKind::PhoenixEgg,
+        MonsterKind::Phylactery,
+        MonsterKind::PinkJelly,
+        MonsterKind::PitBloat,
+        MonsterKind::Pixie,
+        MonsterKind::Rat,
+        MonsterKind::Revenant,
+        MonsterKind::Salamander,
+        MonsterKind::Sentinel,
+        MonsterKind::SparkTurret,
+        MonsterKind::SpectralBlade,
+        MonsterKind::Spider,
+        MonsterKind::StoneGuardian,
+        MonsterKind::TentacleHorror,
+        MonsterKind::Toad,
+        MonsterKind::Troll,
+        MonsterKind::Underworm,
+        MonsterKind::Unicorn,
+        MonsterKind::Vampire,
+        MonsterKind::VampireBat,
+        MonsterKind::WardenOfYendor,
+        MonsterKind::WilloTheWisp,
+        MonsterKind::WingedGuardian,
+        MonsterKind::Wraith,
+        MonsterKind::Zombie,
+    ];
+}
 
-const MUTATION_KINDS: [(&str, Mutation); 8] = [
-    ("agile", Mutation::Agile),
-    ("explosive", Mutation::Explosive),
-    ("grappling", Mutation::Grappling),
-    ("infested", Mutation::Infested),
-    ("juggernaut", Mutation::Juggernaut),
-    ("reflective", Mutation::Reflective),
-    ("toxic", Mutation::Toxic),
-    ("vampiric", Mutation::Vampiric),          
-];
+impl Named for Mutation {
+    const TABLE: &'static str = "mutations";
+    const ALL: &'static [Self] = &[
+        Mutation::Agile,
+        Mutation::Explosive,
+        Mutation::Grappling,
+        Mutation::Infested,
+        Mutation::Juggernaut,
+        Mutation::Reflective,
+        Mutation::Toxic,
+        Mutation::Vampiric,
+    ];
+}
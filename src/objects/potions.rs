@@ -1,9 +1,11 @@
 //! Potions for Brogue Seed Scanner.
 
+use super::tables::{tables, Named};
+
 /// Describes a Brogue Potion.
 #[derive(Clone, Debug)]
 pub struct Potion {
-    kind: PotionKind,
+    pub(crate) kind: PotionKind,
 }
 
 impl Potion {
@@ -19,7 +21,7 @@ impl std::fmt::Display for Potion {
 }
 
 /// Kinds for the Potion Category.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum PotionKind {
     CausticGas,    
@@ -43,83 +45,58 @@ pub enum PotionKind {
 impl PotionKind {
     /// Attempts to fully parse from a string using an _exact_ match.
     pub fn parse(value: &str) -> Option<Self> {
-        for (name, kind) in POTION_KINDS.iter() {
-            if name == &value {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse(value)
     }
     /// Attempts to parse from a string using a _partial_ match.
     pub fn parse_partial(value: &str) -> Option<Self> {
-        for (name, kind) in POTION_KINDS.iter() {
-            if name.contains(value) {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse_partial(value)
     }   
+    /// Returns the names of all values a search term partially matches.
+    pub fn names_matching(value: &str) -> Vec<&'static str> {
+        tables().names_matching::<Self>(value)
+    }
     /// Returns `true` if the potion is malevolent.
     pub fn is_malevolent(&self) -> bool {
         use PotionKind::*;
 
-        match self {
-            CausticGas => true,
-            Confusion =>  true,
-            CreepingDeath => true,
-            Darkness => true,
-            Descent => true,
-            Hallucination => true,
-            Incineration => true,
-            Paralysis => true,
-            _ => false,
-        }
+        matches!(
+            self,
+            CausticGas
+                | Confusion
+                | CreepingDeath
+                | Darkness
+                | Descent
+                | Hallucination
+                | Incineration
+                | Paralysis
+        )
     }
 }
 
 impl std::fmt::Display for PotionKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use PotionKind::*;
-
-        let result = match self {
-            CausticGas => "caustic gas",
-            Confusion => "confusion",
-            CreepingDeath => "creeping death",
-            Darkness => "darkness",
-            Descent => "descent",
-            DetectMagic => "detect magic",
-            FireImmunity => "fire immunity",
-            Hallucination => "hallucination",
-            Incineration => "incineration",
-            Invisibility => "invisibility",
-            Levitation => "levitation",
-            Life => "life",
-            Paralysis => "paralysis",
-            Speed => "speed",
-            Strength => "strength",
-            Telepathy => "telepathy",
-        };
-        write!(f, "{}", result)
+        write!(f, "{}", tables().name(*self))
     }
 }
 
-const POTION_KINDS: [(&str, PotionKind); 16] = [
-    ("caustic gas", PotionKind::CausticGas),
-    ("confusion", PotionKind::Confusion),
-    ("creeping death", PotionKind::CreepingDeath),
-    ("darkness", PotionKind::Darkness),
-    ("descent", PotionKind::Descent),
-    ("detect magic", PotionKind::DetectMagic),
-    ("fire immunity", PotionKind::FireImmunity),
-    ("hallucination", PotionKind::Hallucination),
-    ("incineration", PotionKind::Incineration),
-    ("invisibility", PotionKind::Invisibility),
-    ("levitation", PotionKind::Levitation),
-    ("life", PotionKind::Life),
-    ("paralysis", PotionKind::Paralysis),
-    ("speed", PotionKind::Speed),
-    ("strength", PotionKind::Strength),
-    ("telepathy", PotionKind::Telepathy),
-];
+impl Named for PotionKind {
+    const TABLE: &'static str = "potion_kinds";
+    const ALL: &'static [Self] = &[
+        PotionKind::CausticGas,
+        PotionKind::Confusion,
+        PotionKind::CreepingDeath,
+        PotionKind::Darkness,
+        PotionKind::Descent,
+        PotionKind::DetectMagic,
+        PotionKind::FireImmunity,
+        PotionKind::Hallucination,
+        PotionKind::Incineration,
+        PotionKind::Invisibility,
+        PotionKind::Levitation,
+        PotionKind::Life,
+        PotionKind::Paralysis,
+        PotionKind::Speed,
+        PotionKind::Strength,
+        PotionKind::Telepathy,
+    ];
+}
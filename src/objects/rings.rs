@@ -1,10 +1,12 @@
 //! Rings for Brogue Seed Scanner.
 
+use super::tables::{tables, Named};
+
 /// Describes a Brogue Ring.
 #[derive(Clone, Debug)]
 pub struct Ring {
-    kind: RingKind,
-    enchantment: i8,     // Not an Option as all rings have an enchantment
+    pub(crate) kind: RingKind,
+    pub(crate) enchantment: i8, // Not an Option as all rings have an enchantment
 }
 
 impl Ring {
@@ -24,7 +26,7 @@ impl std::fmt::Display for Ring {
 }
 
 /// Kinds for the Ring Category.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum RingKind {
     Awareness,
@@ -40,49 +42,34 @@ pub enum RingKind {
 impl RingKind {
     /// Attempts to fully parse from a string using an _exact_ match.
     pub fn parse(value: &str) -> Option<Self> {
-        for (name, kind) in RING_KINDS.iter() {
-            if name == &value {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse(value)
     }
     /// Attempts to parse from a string using a _partial_ match.
     pub fn parse_partial(value: &str) -> Option<Self> {
-        for (name, kind) in RING_KINDS.iter() {
-            if name.contains(value) {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse_partial(value)
     }       
+    /// Returns the names of all values a search term partially matches.
+    pub fn names_matching(value: &str) -> Vec<&'static str> {
+        tables().names_matching::<Self>(value)
+    }
 }
 
 impl std::fmt::Display for RingKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let result = match self {
-            RingKind::Awareness => "awarness",
-            RingKind::Clairvoyance => "clairvoyance",
-            RingKind::Light => "light",
-            RingKind::Reaping => "reaping",
-            RingKind::Regeneration => "regeneration",
-            RingKind::Stealth => "stealth",
-            RingKind::Transference => "transference",
-            RingKind::Wisdom => "wisdom",
-        };
-        write!(f, "{}", result)
+        write!(f, "{}", tables().name(*self))
     }
 }
 
-const RING_KINDS: [(&str, RingKind); 8] = [
-    ("awareness", RingKind::Awareness),
-    ("clairvoyance", RingKind::Clairvoyance),
-    ("light", RingKind::Light),
-    ("reaping", RingKind::Reaping),
-    ("regeneration", RingKind::Regeneration),
-    ("stealth", RingKind::Stealth),
-    ("transference", RingKind::Transference),
-    ("wisdom", RingKind::Wisdom),
-];
+impl Named for RingKind {
+    const TABLE: &'static str = "ring_kinds";
+    const ALL: &'static [Self] = &[
+        RingKind::Awareness,
+        RingKind::Clairvoyance,
+        RingKind::Light,
+        RingKind::Reaping,
+        RingKind::Regeneration,
+        RingKind::Stealth,
+        RingKind::Transference,
+        RingKind::Wisdom,
+    ];
+}
@@ -1,9 +1,11 @@
 //! Scrolls for Brogue Seed Scanner.
 
+use super::tables::{tables, Named};
+
 /// Describes a Brogue Scroll.
 #[derive(Clone, Debug)]
 pub struct Scroll {
-    kind: ScrollKind,
+    pub(crate) kind: ScrollKind,
 }
 
 impl Scroll {
@@ -19,7 +21,7 @@ impl std::fmt::Display for Scroll {
 }
 
 /// Kinds for the Scroll Category.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum ScrollKind {
     AggravateMonsters,
@@ -41,73 +43,46 @@ pub enum ScrollKind {
 impl ScrollKind {
     /// Attempts to fully parse from a string using an _exact_ match.
     pub fn parse(value: &str) -> Option<Self> {
-        for (name, kind) in SCROLL_KINDS.iter() {
-            if name == &value {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse(value)
     }
     /// Attempts to parse from a string using a _partial_ match.
     pub fn parse_partial(value: &str) -> Option<Self> {
-        for (name, kind) in SCROLL_KINDS.iter() {
-            if name.contains(value) {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse_partial(value)
     }  
+    /// Returns the names of all values a search term partially matches.
+    pub fn names_matching(value: &str) -> Vec<&'static str> {
+        tables().names_matching::<Self>(value)
+    }
     /// Returns `true` if the scroll is malevolent.
     pub fn is_malevolent(&self) -> bool {
         use ScrollKind::*;
 
-        match self {
-            AggravateMonsters => true,
-            SummonMonsters =>  true,
-            _ => false,
-        }
+        matches!(self, AggravateMonsters | SummonMonsters)
     }       
 }
 
 impl std::fmt::Display for ScrollKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use ScrollKind::*;
-
-        let result = match self {
-            AggravateMonsters => "aggravate monsters",
-            Discord => "discord",
-            Enchanting => "enchanting",
-            Identify => "identify",
-            MagicMapping => "magic mapping",
-            Negation => "negation",
-            ProtectArmor => "protect armor",
-            ProtectWeapon => "protect weapon",
-            Recharging => "recharging",
-            RemoveCurse => "remove curse",
-            Sanctuary => "sanctuary",
-            Shattering => "shattering",
-            SummonMonsters => "summon monsters",
-            Teleportation => "teleportation",
-        };
-        write!(f, "{}", result)
+        write!(f, "{}", tables().name(*self))
     }
 }
 
-const SCROLL_KINDS: [(&str, ScrollKind); 14] = [
-    ("aggravate monsters", ScrollKind::AggravateMonsters),
-    ("discord", ScrollKind::Discord),
-    ("enchanting", ScrollKind::Enchanting),
-    ("identify", ScrollKind::Identify),
-    ("magic mapping", ScrollKind::MagicMapping),
-    ("negation", ScrollKind::Negation),
-    ("protect armor", ScrollKind::ProtectArmor),
-    ("protect weapon", ScrollKind::ProtectWeapon),
-    ("recharging", ScrollKind::Recharging),
-    ("remove curse", ScrollKind::RemoveCurse),
-    ("sanctuary", ScrollKind::Sanctuary),
-    ("shattering", ScrollKind::Shattering),
-    ("summon monsters", ScrollKind::SummonMonsters),
-    ("teleportation", ScrollKind::Teleportation),
-];
+impl Named for ScrollKind {
+    const TABLE: &'static str = "scroll_kinds";
+    const ALL: &'static [Self] = &[
+        ScrollKind::AggravateMonsters,
+        ScrollKind::Discord,
+        ScrollKind::Enchanting,
+        ScrollKind::Identify,
+        ScrollKind::MagicMapping,
+        ScrollKind::Negation,
+        ScrollKind::ProtectArmor,
+        ScrollKind::ProtectWeapon,
+        ScrollKind::Recharging,
+        ScrollKind::RemoveCurse,
+        ScrollKind::Sanctuary,
+        ScrollKind::Shattering,
+        ScrollKind::SummonMonsters,
+        ScrollKind::Teleportation,
+    ];
+}
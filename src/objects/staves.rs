@@ -1,10 +1,12 @@
 //! Staves for Brogue Seed Scanner.
 
+use super::tables::{tables, Named};
+
 /// Describes a Brogue Staff.
 #[derive(Clone, Debug)]
 pub struct Staff {
-    kind: StaffKind,
-    enchantment: i8,    // Not an Option as all staves have an enchantment
+    pub(crate) kind: StaffKind,
+    pub(crate) enchantment: i8, // Not an Option as all staves have an enchantment
 }
 
 impl Staff {
@@ -20,7 +22,7 @@ impl std::fmt::Display for Staff {
 }
 
 /// Kinds for the Staff Category.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum StaffKind {
     Blinking,
@@ -40,70 +42,44 @@ pub enum StaffKind {
 impl StaffKind {
     /// Attempts to fully parse from a string using an _exact_ match.
     pub fn parse(value: &str) -> Option<Self> {
-        for (name, kind) in STAFF_KINDS.iter() {
-            if name == &value {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse(value)
     }
     /// Attempts to parse from a string using a _partial_ match.
     pub fn parse_partial(value: &str) -> Option<Self> {
-        for (name, kind) in STAFF_KINDS.iter() {
-            if name.contains(value) {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse_partial(value)
     }  
+    /// Returns the names of all values a search term partially matches.
+    pub fn names_matching(value: &str) -> Vec<&'static str> {
+        tables().names_matching::<Self>(value)
+    }
     /// Returns `true` if the staff is malevolent.
     pub fn is_malevolent(&self) -> bool {
         use StaffKind::*;
 
-        match self {
-            Haste => true,
-            Healing => true,
-            Protection => true,
-            _ => false,
-        }
+        matches!(self, Haste | Healing | Protection)
     }    
 }
 
 impl std::fmt::Display for StaffKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use StaffKind::*;
-
-        let result = match self {
-            Blinking => "blinking",
-            Conjuration => "conjuration",
-            Discord => "discord",
-            Entrancement => "entrancement",
-            Firebolt => "firebolt",
-            Haste => "haste",
-            Healing => "healing",
-            Lightning => "lightning",
-            Obstruction => "obstruction",
-            Poison => "poison",
-            Protection => "protection",
-            Tunneling => "tunneling",
-        };
-        write!(f, "{}", result)
+        write!(f, "{}", tables().name(*self))
     }
 }
 
-const STAFF_KINDS: [(&str, StaffKind); 12] = [
-    ("blinking", StaffKind::Blinking),
-    ("conjuration", StaffKind::Conjuration),
-    ("discord", StaffKind::Discord),
-    ("entrancement", StaffKind::Entrancement),
-    ("firebolt", StaffKind::Firebolt),
-    ("haste", StaffKind::Haste),
-    ("healing", StaffKind::Healing),
-    ("lightning", StaffKind::Lightning),
-    ("obstruction", StaffKind::Obstruction),
-    ("poison", StaffKind::Poison),
-    ("protection", StaffKind::Protection),
-    ("tunneling", StaffKind::Tunneling),
-];
+impl Named for StaffKind {
+    const TABLE: &'static str = "staff_kinds";
+    const ALL: &'static [Self] = &[
+        StaffKind::Blinking,
+        StaffKind::Conjuration,
+        StaffKind::Discord,
+        StaffKind::Entrancement,
+        StaffKind::Firebolt,
+        StaffKind::Haste,
+        StaffKind::Healing,
+        StaffKind::Lightning,
+        StaffKind::Obstruction,
+        StaffKind::Poison,
+        StaffKind::Protection,
+        StaffKind::Tunneling,
+    ];
+}
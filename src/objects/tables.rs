@@ -0,0 +1,241 @@
+//! Name tables of kinds, runics, and monsters for Brogue Seed Scanner.
+//!
+//! The names used in catalogs (and searches) for each kind, runic, monster, ally status,
+//! and mutation are data rather than code:  `data/tables.json` is compiled into the
+//! binary, with each table an ordered list of `[name, kind]` pairs (kinds named as in
+//! the code, e.g. "Speed" or "Slaying(Dragon)").  Partial matches take the first name
+//! in table order.
+//!
+//! With `--data-dir DIR`, `DIR/tables.json` changes the tables at startup:  tables under
+//! `replace` take the place of the built-in ones, and entries under `extend` add names
+//! after the built-in ones (or give an existing name another kind):
+//! ```json
+//! {
+//!   "replace": { "ally_statuses": [["allied", "Allied"], ["caged", "Caged"], ["shackled", "Shackled"]] },
+//!   "extend": { "potion_kinds": [["quickness", "Speed"]] }
+//! }
+//! ```
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use super::{
+    AllyStatus, AltarKind, ArmorKind, ArmorRunic, CharmKind, FoodKind, KeyKind, MonsterKind,
+    Mutation, PotionKind, RingKind, ScrollKind, StaffKind, WandKind, WeaponKind, WeaponRunic,
+};
+
+/// Built-in name tables.
+const EMBEDDED: &str = include_str!("../../data/tables.json");
+
+/// File of table changes, in the `--data-dir` folder.
+pub const TABLES_FILE: &str = "tables.json";
+
+/// Name tables of the process, loaded at startup.
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+/// Name tables as read:  `[name, kind]` pairs by table.
+pub(crate) type RawTables = BTreeMap<String, Vec<(String, String)>>;
+
+/// A `--data-dir` tables file, as read.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TableChanges {
+    #[serde(default)]
+    replace: RawTables,
+    #[serde(default)]
+    extend: RawTables,
+}
+
+/// A kind (or runic, monster, etc.) named by a table.
+pub(crate) trait Named: Copy + PartialEq + std::fmt::Debug + 'static {
+    /// Name of the table.
+    const TABLE: &'static str;
+    /// Every value the table can name.
+    const ALL: &'static [Self];
+}
+
+/// Names of every kind, by table, with the index in `Named::ALL` of each.
+#[derive(Debug, Default)]
+pub(crate) struct Tables {
+    tables: BTreeMap<&'static str, Vec<(&'static str, usize)>>,
+    /// Every kind of each table, as named in the tables files.
+    kinds: BTreeMap<&'static str, Vec<String>>,
+}
+
+impl Tables {
+    /// Loads the built-in tables, with the changes of `DIR/tables.json` if a data folder
+    /// is given.
+    pub fn load(data_dir: Option<&Path>) -> Result<Self> {
+        let mut raw: RawTables =
+            serde_json::from_str(EMBEDDED).context("invalid built-in name tables")?;
+
+        if let Some(dir) = data_dir {
+            let path = dir.join(TABLES_FILE);
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("could not read name tables {:?} (for --data-dir)", path))?;
+            let changes: TableChanges = serde_json::from_str(&text)
+                .with_context(|| format!("invalid JSON in name tables {:?}", path))?;
+            apply_changes(&mut raw, changes)
+                .with_context(|| format!("in name tables {:?}", path))?;
+        }
+
+        Self::from_raw(raw)
+    }
+    /// Makes the tables from their `[name, kind]` pairs, checking every table.
+    pub fn from_raw(mut raw: RawTables) -> Result<Self> {
+        let mut tables = Self::default();
+        tables.add::<AllyStatus>(&mut raw)?;
+        tables.add::<AltarKind>(&mut raw)?;
+        tables.add::<ArmorKind>(&mut raw)?;
+        tables.add::<ArmorRunic>(&mut raw)?;
+        tables.add::<CharmKind>(&mut raw)?;
+        tables.add::<FoodKind>(&mut raw)?;
+        tables.add::<KeyKind>(&mut raw)?;
+        tables.add::<MonsterKind>(&mut raw)?;
+        tables.add::<Mutation>(&mut raw)?;
+        tables.add::<PotionKind>(&mut raw)?;
+        tables.add::<RingKind>(&mut raw)?;
+        tables.add::<ScrollKind>(&mut raw)?;
+        tables.add::<StaffKind>(&mut raw)?;
+        tables.add::<WandKind>(&mut raw)?;
+        tables.add::<WeaponKind>(&mut raw)?;
+        tables.add::<WeaponRunic>(&mut raw)?;
+
+        match raw.keys().next() {
+            Some(table) => Err(anyhow!("unknown name table '{}'", table)),
+            None => Ok(tables),
+        }
+    }
+    /// Adds the table of `T`, taking it from the tables as read.  Every value must keep
+    /// a name, and names must be non-empty and different.
+    fn add<T: Named>(&mut self, raw: &mut RawTables) -> Result<()> {
+        let entries = raw
+            .remove(T::TABLE)
+            .ok_or_else(|| anyhow!("no '{}' name table", T::TABLE))?;
+        let mut table: Vec<(&'static str, usize)> = Vec::with_capacity(entries.len());
+
+        for (name, kind) in entries {
+            if name.trim().is_empty() {
+                return Err(anyhow!(
+                    "empty name for '{}' in name table '{}'",
+                    kind,
+                    T::TABLE
+                ));
+            }
+            if table.iter().any(|(n, _)| *n == name) {
+                return Err(anyhow!(
+                    "name '{}' repeated in name table '{}'",
+                    name,
+                    T::TABLE
+                ));
+            }
+            let ix = T::ALL
+                .iter()
+                .position(|value| format!("{:?}", value) == kind)
+                .ok_or_else(|| anyhow!("unknown kind '{}' for '{}' in name table '{}'", kind, name, T::TABLE))?;
+            table.push((&*Box::leak(name.into_boxed_str()), ix));
+        }
+        if let Some(ix) = (0..T::ALL.len()).find(|ix| !table.iter().any(|(_, i)| i == ix)) {
+            return Err(anyhow!(
+                "no name for {:?} in name table '{}'",
+                T::ALL[ix],
+                T::TABLE
+            ));
+        }
+        self.tables.insert(T::TABLE, table);
+        self.kinds.insert(
+            T::TABLE,
+            T::ALL.iter().map(|value| format!("{:?}", value)).collect(),
+        );
+
+        Ok(())
+    }
+    /// Returns every table as `[name, kind]` pairs.
+    pub fn to_raw(&self) -> RawTables {
+        self.tables
+            .iter()
+            .map(|(table, names)| {
+                let kinds = &self.kinds[table];
+                let names = names
+                    .iter()
+                    .map(|(name, ix)| (name.to_string(), kinds[*ix].clone()))
+                    .collect();
+                (table.to_string(), names)
+            })
+            .collect()
+    }
+    /// Returns every kind of a table, as named in the tables files.
+    pub fn kinds(&self, table: &str) -> &[String] {
+        self.kinds.get(table).map(Vec::as_slice).unwrap_or_default()
+    }
+    /// Returns the names of `T`, in table order.
+    fn table<T: Named>(&self) -> impl Iterator<Item = (&'static str, T)> + '_ {
+        self.tables[T::TABLE].iter().map(|(name, ix)| (*name, T::ALL[*ix]))
+    }
+    /// Returns the name of a value:  the first name the table gives it, so that output
+    /// uses the names the tables (and `--data-dir`) set.
+    pub fn name<T: Named>(&self, value: T) -> &'static str {
+        let ix = T::ALL.iter().position(|v| *v == value).unwrap_or_default();
+
+        self.tables[T::TABLE]
+            .iter()
+            .find(|(_, i)| *i == ix)
+            .map_or("", |(name, _)| *name)
+    }
+    /// Attempts to fully parse from a string using an _exact_ match.
+    pub fn parse<T: Named>(&self, value: &str) -> Option<T> {
+        self.table().find(|(name, _)| *name == value).map(|(_, kind)| kind)
+    }
+    /// Attempts to parse from a string using a _partial_ match.
+    pub fn parse_partial<T: Named>(&self, value: &str) -> Option<T> {
+        self.table()
+            .find(|(name, _)| name.contains(value))
+            .map(|(_, kind)| kind)
+    }
+    /// Returns the names of all values of `T` a search term partially matches.
+    pub fn names_matching<T: Named>(&self, value: &str) -> Vec<&'static str> {
+        self.table::<T>()
+            .map(|(name, _)| name)
+            .filter(|name| name.contains(value))
+            .collect()
+    }
+}
+
+/// Applies the `replace` and `extend` tables of a `--data-dir` tables file.
+fn apply_changes(raw: &mut RawTables, changes: TableChanges) -> Result<()> {
+    for (table, entries) in changes.replace {
+        match raw.get_mut(&table) {
+            Some(names) => *names = entries,
+            None => return Err(anyhow!("unknown name table '{}'", table)),
+        }
+    }
+    for (table, entries) in changes.extend {
+        let names = raw
+            .get_mut(&table)
+            .ok_or_else(|| anyhow!("unknown name table '{}'", table))?;
+
+        for (name, kind) in entries {
+            match names.iter_mut().find(|(n, _)| *n == name) {
+                Some(entry) => entry.1 = kind,
+                None => names.push((name, kind)),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads the name tables of the process (`--data-dir`), before any are used.
+pub fn init_tables(data_dir: Option<&Path>) -> Result<()> {
+    let tables = Tables::load(data_dir)?;
+
+    TABLES.set(tables).map_err(|_| anyhow!("name tables already loaded"))
+}
+
+/// Returns the name tables of the process (the built-in ones unless set at startup).
+pub(crate) fn tables() -> &'static Tables {
+    TABLES.get_or_init(|| Tables::load(None).expect("built-in name tables are valid"))
+}
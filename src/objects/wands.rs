@@ -1,10 +1,12 @@
 //! Wands for Brogue Seed Scanner.
 
+use super::tables::{tables, Named};
+
 /// Describes a Brogue Wand.
 #[derive(Clone, Debug)]
 pub struct Wand {
-    kind: WandKind,
-    enchantment: i8,   // Not an Option as all wands have an enchantment
+    pub(crate) kind: WandKind,
+    pub(crate) enchantment: i8, // Not an Option as all wands have an enchantment
 }
 
 impl Wand {
@@ -20,7 +22,7 @@ impl std::fmt::Display for Wand {
 }
 
 /// Kinds for the Wand Category.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum WandKind {
     Beckoning,
@@ -37,64 +39,41 @@ pub enum WandKind {
 impl WandKind {
     /// Attempts to fully parse from a string using an _exact_ match.
     pub fn parse(value: &str) -> Option<Self> {
-        for (name, kind) in WAND_KINDS.iter() {
-            if name == &value {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse(value)
     }
     /// Attempts to parse from a string using a _partial_ match.
     pub fn parse_partial(value: &str) -> Option<Self> {
-        for (name, kind) in WAND_KINDS.iter() {
-            if name.contains(value) {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse_partial(value)
     }   
+    /// Returns the names of all values a search term partially matches.
+    pub fn names_matching(value: &str) -> Vec<&'static str> {
+        tables().names_matching::<Self>(value)
+    }
     /// Returns `true` if the wand is malevolent.
     pub fn is_malevolent(&self) -> bool {
         use WandKind::*;
 
-        match self {
-            Empowerment => true,
-            Invisibility => true,
-            Plenty => true,
-            _ => false,
-        }
+        matches!(self, Empowerment | Invisibility | Plenty)
     }    
 }
 
 impl std::fmt::Display for WandKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use WandKind::*;
-
-        let result = match self {
-            Beckoning => "beckoning",
-            Domination => "domination",
-            Empowerment => "empowerment",
-            Invisibility => "invisibility",
-            Negation => "negation",
-            Plenty => "plenty",
-            Polymorphism => "polymorphism",
-            Slowness => "slowness",
-            Teleportation => "teleportation",
-        };
-        write!(f, "{}", result)
+        write!(f, "{}", tables().name(*self))
     }
 }
 
-const WAND_KINDS: [(&str, WandKind); 9] = [
-    ("beckoning", WandKind::Beckoning),
-    ("domination", WandKind::Domination),
-    ("empowerment", WandKind::Empowerment),
-    ("invisibility", WandKind::Invisibility),
-    ("negation", WandKind::Negation),
-    ("plenty", WandKind::Plenty),
-    ("polymorphism", WandKind::Polymorphism),
-    ("slowness", WandKind::Slowness),
-    ("teleportation", WandKind::Teleportation),
-];
+impl Named for WandKind {
+    const TABLE: &'static str = "wand_kinds";
+    const ALL: &'static [Self] = &[
+        WandKind::Beckoning,
+        WandKind::Domination,
+        WandKind::Empowerment,
+        WandKind::Invisibility,
+        WandKind::Negation,
+        WandKind::Plenty,
+        WandKind::Polymorphism,
+        WandKind::Slowness,
+        WandKind::Teleportation,
+    ];
+}
@@ -1,13 +1,14 @@
 //! Weapons and weapon runics for Brogue Seed Scanner.
 
+use super::tables::{tables, Named};
 use crate::objects::MonsterClass;
 
 /// Describes a Brogue Weapon.
 #[derive(Clone, Debug)]
 pub struct Weapon {
-    kind: WeaponKind,
-    enchantment: i8,
-    runic: Option<WeaponRunic>,
+    pub(crate) kind: WeaponKind,
+    pub(crate) enchantment: i8,
+    pub(crate) runic: Option<WeaponRunic>,
 }
 
 impl Weapon {
@@ -31,7 +32,7 @@ impl std::fmt::Display for Weapon {
 }
 
 /// Kinds for the Weapon Category.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum WeaponKind {
     Axe,
@@ -54,52 +55,91 @@ pub enum WeaponKind {
 impl WeaponKind {
     /// Attempts to fully parse from a string using an _exact_ match.
     pub fn parse(value: &str) -> Option<Self> {
-        for (name, kind) in WEAPON_KINDS.iter() {
-            if name == &value {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse(value)
     }
     /// Attempts to parse from a string using a _partial_ match.
     pub fn parse_partial(value: &str) -> Option<Self> {
-        for (name, kind) in WEAPON_KINDS.iter() {
-            if name.contains(value) {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse_partial(value)
     }   
+    /// Returns the names of all values a search term partially matches.
+    pub fn names_matching(value: &str) -> Vec<&'static str> {
+        tables().names_matching::<Self>(value)
+    }
+    /// Returns the strength needed to wield the weapon without penalty.
+    pub fn strength(self) -> u8 {
+        match self {
+            WeaponKind::Dart => 10,
+            WeaponKind::Dagger => 12,
+            WeaponKind::IncendiaryDart => 12,
+            WeaponKind::Spear => 13,
+            WeaponKind::Sword => 14,
+            WeaponKind::Whip => 14,
+            WeaponKind::Axe => 15,
+            WeaponKind::Javelin => 15,
+            WeaponKind::Rapier => 15,
+            WeaponKind::Mace => 16,
+            WeaponKind::Flail => 17,
+            WeaponKind::WarPike => 18,
+            WeaponKind::Broadsword => 19,
+            WeaponKind::WarAxe => 19,
+            WeaponKind::WarHammer => 20,
+        }
+    }
+    /// Returns the weapon's base damage range (minimum and maximum).
+    pub fn damage(self) -> (u8, u8) {
+        match self {
+            WeaponKind::Axe => (7, 9),
+            WeaponKind::Broadsword => (14, 22),
+            WeaponKind::Dagger => (3, 4),
+            WeaponKind::Dart => (2, 4),
+            WeaponKind::Flail => (10, 22),
+            WeaponKind::IncendiaryDart => (1, 2),
+            WeaponKind::Javelin => (3, 11),
+            WeaponKind::Mace => (16, 20),
+            WeaponKind::Rapier => (3, 11),
+            WeaponKind::Spear => (4, 5),
+            WeaponKind::Sword => (7, 9),
+            WeaponKind::WarAxe => (12, 17),
+            WeaponKind::WarHammer => (25, 35),
+            WeaponKind::WarPike => (11, 15),
+            WeaponKind::Whip => (3, 5),
+        }
+    }
+    /// Returns the weapon's damage tier by average base damage:  1 (light, under 6), 2
+    /// (medium, under 12), or 3 (heavy).
+    pub fn damage_tier(self) -> u8 {
+        let (min, max) = self.damage();
+
+        match min + max {
+            0..=11 => 1,
+            12..=23 => 2,
+            _ => 3,
+        }
+    }
+    /// Returns `true` if the weapon is thrown (darts, incendiary darts, and javelins).
+    pub fn is_thrown(self) -> bool {
+        matches!(
+            self,
+            WeaponKind::Dart | WeaponKind::IncendiaryDart | WeaponKind::Javelin
+        )
+    }
+    /// Returns `true` if the weapon counts as two-handed.  Brogue has no handedness, so
+    /// these are the weapons needing 18 or more strength:  the heavy weapon of each melee
+    /// pair (broadsword, war axe, war hammer, and war pike).
+    pub fn is_two_handed(self) -> bool {
+        self.strength() >= 18
+    }
 }
 
 impl std::fmt::Display for WeaponKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let result = match self {
-            WeaponKind::Axe => "axe",
-            WeaponKind::Broadsword => "broadsword",
-            WeaponKind::Dagger => "dagger",
-            WeaponKind::Dart => "dart",
-            WeaponKind::Flail => "flail",
-            WeaponKind::IncendiaryDart => "incendiary dart",
-            WeaponKind::Javelin => "javelins",
-            WeaponKind::Mace => "mace",
-            WeaponKind::Rapier => "rapier",
-            WeaponKind::Spear => "spear",
-            WeaponKind::Sword => "sword",
-            WeaponKind::WarAxe => "war axe",
-            WeaponKind::WarHammer => "war hammer",
-            WeaponKind::WarPike => "war pike",
-            WeaponKind::Whip => "whip",
-        };
-        write!(f, "{}", result)
+        write!(f, "{}", tables().name(*self))
     }
 }
 
 
 // Runics for Weapons.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum WeaponRunic {
     // --- Positive --- //
@@ -119,95 +159,75 @@ pub enum WeaponRunic {
 impl WeaponRunic {
     /// Attempts to fully parse from a string using an _exact_ match.
     pub fn parse(value: &str) -> Option<Self> {
-        for (name, kind) in WEAPON_RUNICS.iter() {
-            if name == &value {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse(value)
     }
     /// Attempts to parse from a string using a _partial_ match.
     pub fn parse_partial(value: &str) -> Option<Self> {
-        for (name, kind) in WEAPON_RUNICS.iter() {
-            if name.contains(value) {
-                return Some(*kind)
-            }
-        }
-
-        None
+        tables().parse_partial(value)
     }   
+    /// Returns the names of all values a search term partially matches.
+    pub fn names_matching(value: &str) -> Vec<&'static str> {
+        tables().names_matching::<Self>(value)
+    }
+    /// Returns `true` if the runic is negative.
+    pub fn is_malevolent(&self) -> bool {
+        matches!(self, WeaponRunic::Mercy | WeaponRunic::Plenty)
+    }
 }
 
 impl std::fmt::Display for WeaponRunic {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            WeaponRunic::Confusion => write!(f, "confusion"),
-            WeaponRunic::Force => write!(f, "force"),
-            WeaponRunic::Multiplicity => write!(f, "multiplicity"),
-            WeaponRunic::Paralysis => write!(f, "paralysis"),
-            WeaponRunic::Quietus => write!(f, "quietus"),
-            WeaponRunic::Slowing => write!(f, "slowing"),
-            WeaponRunic::Speed => write!(f, "speed"),
-            WeaponRunic::Mercy => write!(f, "mercy"),
-            WeaponRunic::Plenty => write!(f, "plenty"),
-            WeaponRunic::Slaying(mclass) => write!(f, "{} slaying", mclass),            
-        }
+        write!(f, "{}", tables().name(*self))
     }
 }
 
-const WEAPON_KINDS: [(&str, WeaponKind); 15] = [
-    // Sword types
-    ("broadsword", WeaponKind::Broadsword),
-    ("dagger", WeaponKind::Dagger),
-    ("sword", WeaponKind::Sword),
-    // Mace types
-    ("mace", WeaponKind::Mace),
-    ("war hammer", WeaponKind::WarHammer),
-    // Spear types
-    ("spear", WeaponKind::Spear),
-    ("war pike", WeaponKind::WarPike),
-    // Axe types            
-    ("war axe", WeaponKind::WarAxe),
-    ("axe", WeaponKind::Axe),
-    // Rapier types                   
-    ("rapier", WeaponKind::Rapier),
-    // Whip types
-    ("whip", WeaponKind::Whip),
-    // Flail types
-    ("flail", WeaponKind::Flail),
-    // Thrown types
-    ("incendiary dart", WeaponKind::IncendiaryDart),
-    ("dart", WeaponKind::Dart),          
-    ("javelin", WeaponKind::Javelin),          
-];
+impl Named for WeaponKind {
+    const TABLE: &'static str = "weapon_kinds";
+    const ALL: &'static [Self] = &[
+        WeaponKind::Broadsword,
+        WeaponKind::Dagger,
+        WeaponKind::Sword,
+        WeaponKind::Mace,
+        WeaponKind::WarHammer,
+        WeaponKind::Spear,
+        WeaponKind::WarPike,
+        WeaponKind::WarAxe,
+        WeaponKind::Axe,
+        WeaponKind::Rapier,
+        WeaponKind::Whip,
+        WeaponKind::Flail,
+        WeaponKind::IncendiaryDart,
+        WeaponKind::Dart,
+        WeaponKind::Javelin,
+    ];
+}
 
-const WEAPON_RUNICS: [(&str, WeaponRunic); 24] = [
-    // --- Positive --- //
-    ("confusion", WeaponRunic::Confusion),
-    ("force", WeaponRunic::Force),
-    ("multiplicity", WeaponRunic::Multiplicity),
-    ("paralysis", WeaponRunic::Paralysis),
-    ("quietus", WeaponRunic::Quietus),
-    ("slowing", WeaponRunic::Slowing),
-    ("speed", WeaponRunic::Speed),                  
-    // --- Negative --- //
-    ("mercy", WeaponRunic::Mercy),            
-    ("plenty", WeaponRunic::Plenty),            
-    // --- Slaying --- //
-    ("airborne slaying", WeaponRunic::Slaying(MonsterClass::Airborne)),            
-    ("abomination slaying", WeaponRunic::Slaying(MonsterClass::Abomination)),
-    ("animal slaying", WeaponRunic::Slaying(MonsterClass::Animal)),
-    ("dar slaying", WeaponRunic::Slaying(MonsterClass::Dar)),       
-    ("dragon slaying", WeaponRunic::Slaying(MonsterClass::Dragon)),
-    ("fireborne slaying", WeaponRunic::Slaying(MonsterClass::Fireborne)),
-    ("goblin slaying", WeaponRunic::Slaying(MonsterClass::Goblin)),       
-    ("infernal slaying", WeaponRunic::Slaying(MonsterClass::Infernal)),
-    ("jelly slaying", WeaponRunic::Slaying(MonsterClass::Jelly)),      
-    ("mage slaying", WeaponRunic::Slaying(MonsterClass::Mage)),
-    ("ogre slaying", WeaponRunic::Slaying(MonsterClass::Ogre)),
-    ("troll slaying", WeaponRunic::Slaying(MonsterClass::Troll)),
-    ("turret slaying", WeaponRunic::Slaying(MonsterClass::Turret)),
-    ("undead slaying", WeaponRunic::Slaying(MonsterClass::Undead)),
-    ("waterborne slaying", WeaponRunic::Slaying(MonsterClass::Waterborne)),    
-];
+impl Named for WeaponRunic {
+    const TABLE: &'static str = "weapon_runics";
+    const ALL: &'static [Self] = &[
+        WeaponRunic::Confusion,
+        WeaponRunic::Force,
+        WeaponRunic::Multiplicity,
+        WeaponRunic::Paralysis,
+        WeaponRunic::Quietus,
+        WeaponRunic::Slowing,
+        WeaponRunic::Speed,
+        WeaponRunic::Mercy,
+        WeaponRunic::Plenty,
+        WeaponRunic::Slaying(MonsterClass::Airborne),
+        WeaponRunic::Slaying(MonsterClass::Abomination),
+        WeaponRunic::Slaying(MonsterClass::Animal),
+        WeaponRunic::Slaying(MonsterClass::Dar),
+        WeaponRunic::Slaying(MonsterClass::Dragon),
+        WeaponRunic::Slaying(MonsterClass::Fireborne),
+        WeaponRunic::Slaying(MonsterClass::Goblin),
+        WeaponRunic::Slaying(MonsterClass::Infernal),
+        WeaponRunic::Slaying(MonsterClass::Jelly),
+        WeaponRunic::Slaying(MonsterClass::Mage),
+        WeaponRunic::Slaying(MonsterClass::Ogre),
+        WeaponRunic::Slaying(MonsterClass::Troll),
+        WeaponRunic::Slaying(MonsterClass::Turret),
+        WeaponRunic::Slaying(MonsterClass::Undead),
+        WeaponRunic::Slaying(MonsterClass::Waterborne),
+    ];
+}
@@ -0,0 +1,96 @@
+//! Scan progress for Brogue Seed Scanner.
+//!
+//! With `--progress`, long scans keep a single status line on standard error up to date
+//! with the file being searched, the current seed, and the number of matching seeds so
+//! far, so progress is visible even with `-v` (or machine output on standard output).
+//! The line is redrawn at most a few times a second, only when standard error is a
+//! terminal, and erased once the files are searched.  Parallel workers (one per catalog
+//! folder) each draw their own progress, so the line shows whichever updated last.
+
+use crate::search::SearchParameters;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Shortest time between redraws of the status line.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Status line of a scan in progress.
+pub(crate) struct Progress {
+    /// Whether the status line is drawn at all.
+    enabled: bool,
+    /// Where the status line is drawn (standard error).
+    out: Box<dyn Write>,
+    /// Name of the file being searched.
+    file: String,
+    /// Seed of the last update.
+    seed: Option<u32>,
+    /// Time the status line was last drawn, if ever.
+    drawn: Option<Instant>,
+    /// Length of the last status line drawn (to erase it).
+    width: usize,
+}
+
+impl Progress {
+    /// Makes a new instance, drawn if `enabled` and standard error is a terminal.
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self::with_output(
+            enabled && std::io::stderr().is_terminal(),
+            Box::new(std::io::stderr()),
+        )
+    }
+    /// Makes a new instance drawn to `out` if `enabled`, whether a terminal or not.
+    pub(crate) fn with_output(enabled: bool, out: Box<dyn Write>) -> Self {
+        Self {
+            enabled,
+            out,
+            file: String::new(),
+            seed: None,
+            drawn: None,
+            width: 0,
+        }
+    }
+    /// Starts reporting on a new file.
+    pub(crate) fn start_file(&mut self, path: &Path) {
+        if self.enabled {
+            self.file = path
+                .file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned();
+            self.seed = None;
+        }
+    }
+    /// Redraws the status line for a new seed, unless it was drawn too recently.
+    pub(crate) fn update(&mut self, seed: u32, searches: &[SearchParameters]) {
+        if !self.enabled || self.seed == Some(seed) {
+            return;
+        }
+        self.seed = Some(seed);
+
+        if self.drawn.is_some_and(|drawn| drawn.elapsed() < REDRAW_INTERVAL) {
+            return;
+        }
+        self.drawn = Some(Instant::now());
+
+        let matches: u64 = searches.iter().map(|search| u64::from(search.search_matches)).sum();
+        let line = format!(
+            "searching {}  seed {}  {} matching seeds",
+            self.file, seed, matches
+        );
+
+        let _ = write!(self.out, "\r{:<width$}", line, width = self.width);
+        let _ = self.out.flush();
+        self.width = line.len();
+    }
+}
+
+impl Drop for Progress {
+    /// Erases the status line, if drawn.
+    fn drop(&mut self) {
+        if self.drawn.is_some() {
+            let _ = write!(self.out, "\r{:width$}\r", "", width = self.width);
+            let _ = self.out.flush();
+        }
+    }
+}
@@ -0,0 +1,405 @@
+//! Object rarity tables for Brogue Seed Scanner.
+//!
+//! A rarity table records, for every object kind in a sample of seeds, how many seeds
+//! have at least one by each depth and the total quantity by each depth.  Building one
+//! takes a full scan, so tables are cached as JSON (by default `brogue-rarity.json` in
+//! the catalog folder) and read by `--explain` and the selectivity estimate printed before
+//! each search.  To build or refresh the cache:
+//! ```
+//! brogue-scanner --maxseed 20000 rarity
+//! ```
+//! A table records the catalog files it was built from (see `manifest`), and isn't used
+//! once they change:  rebuild it, or pass `--rebuild-stale` to rebuild it (over the same
+//! seed range) automatically.
+
+use crate::columns;
+use crate::file_handling::{open_catalog, CatalogReader};
+use crate::manifest::Manifest;
+use crate::objects::Category;
+use crate::search::{CountType, OutputMode, SearchParameters};
+use crate::status::no_files;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Default rarity cache file name, in the catalog folder.
+pub const RARITY_CACHE_FILE: &str = "brogue-rarity.json";
+
+/// Version of the rarity cache layout.  Caches of other versions are rebuilt.
+const CACHE_VERSION: u32 = 2;
+
+/// Number of dungeon depths.
+const DEPTHS: usize = 26;
+
+/// Queries matching fewer seeds than this (per seed) are warned about as very strict.
+const STRICT_CHANCE: f64 = 1e-6;
+
+/// Rarity of a single object kind (or a whole category).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rarity {
+    /// Number of seeds with at least one by each depth (index 0 is depth 1).
+    pub seeds_by_depth: Vec<u64>,
+    /// Total quantity by each depth (index 0 is depth 1).
+    pub quantity_by_depth: Vec<u64>,
+}
+
+impl Rarity {
+    fn new() -> Self {
+        Self {
+            seeds_by_depth: vec![0; DEPTHS],
+            quantity_by_depth: vec![0; DEPTHS],
+        }
+    }
+}
+
+/// Estimated rarity of an object parameter, by its maximum depth.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    /// Chance of a seed having at least one (0.0 to 1.0).
+    pub chance: f64,
+    /// Average quantity per seed.
+    pub mean: f64,
+}
+
+impl Estimate {
+    /// Returns the chance of a seed meeting a count target.  Quantity among seeds with
+    /// at least one is treated as Poisson-distributed (scaled to the observed chance).
+    pub fn count_chance(&self, count_type: CountType, count_target: u32) -> f64 {
+        let at_least = |n: u32| -> f64 {
+            match (n, self.mean > 0.0) {
+                (0, _) => 1.0,
+                (_, false) => 0.0,
+                _ => {
+                    let scaled = self.chance * poisson_at_least(self.mean, n)
+                        / poisson_at_least(self.mean, 1);
+                    scaled.clamp(0.0, self.chance)
+                }
+            }
+        };
+
+        match count_type {
+            CountType::AtLeast => at_least(count_target),
+            CountType::LessThan => 1.0 - at_least(count_target),
+            CountType::EqualTo => at_least(count_target) - at_least(count_target + 1),
+        }
+    }
+}
+
+/// Returns the chance of a Poisson-distributed count with the given mean being at least
+/// `n`.  Terms are computed in log space, so large means (e.g. gold) don't underflow.
+fn poisson_at_least(mean: f64, n: u32) -> f64 {
+    let mut below = 0.0;
+    let mut log_term = -mean;
+
+    for k in 0..n {
+        below += log_term.exp();
+        log_term += mean.ln() - f64::from(k + 1).ln();
+    }
+
+    (1.0 - below).max(0.0)
+}
+
+/// Per-kind rarity over a sample of seeds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RarityTable {
+    pub cache_version: u32,
+    /// Number of seeds sampled.
+    pub seeds: u64,
+    /// Seed range sampled.
+    pub seed_min: u32,
+    pub seed_max: u32,
+    /// Catalog files the table was built from.
+    pub manifest: Manifest,
+    /// Rarity keyed by "category:kind", or "category:" for all objects of a category.
+    pub objects: BTreeMap<String, Rarity>,
+}
+
+/// Per-seed accumulator for `RarityTable::build`.
+#[derive(Default)]
+struct SeedTally {
+    prev_seed: Option<u32>,
+    /// First depth each object key was found at this seed.
+    first_depths: HashMap<String, u8>,
+}
+
+impl SeedTally {
+    /// Closes out the current seed, adding first depths to the (per-depth) seed counts.
+    fn finish_seed(&mut self, table: &mut RarityTable) {
+        for (key, depth) in self.first_depths.drain() {
+            if let Some(rarity) = table.objects.get_mut(&key) {
+                rarity.seeds_by_depth[usize::from(depth) - 1] += 1;
+            }
+        }
+    }
+    /// Adds a found object for the current seed.
+    fn add(&mut self, table: &mut RarityTable, key: String, depth: u8, quantity: u64) {
+        let rarity = table.objects.entry(key.clone()).or_insert_with(Rarity::new);
+        rarity.quantity_by_depth[usize::from(depth) - 1] += quantity;
+
+        let first = self.first_depths.entry(key).or_insert(depth);
+        *first = (*first).min(depth);
+    }
+}
+
+impl RarityTable {
+    /// Builds a table from the catalog files and seed range of the given search.
+    pub fn build(search: &SearchParameters) -> Result<Self> {
+        if search.file_paths.is_empty() {
+            return Err(no_files());
+        }
+
+        let mut table = Self {
+            cache_version: CACHE_VERSION,
+            seeds: 0,
+            seed_min: search.seed_min,
+            seed_max: search.seed_max,
+            manifest: Manifest::build(&search.file_paths)?,
+            objects: BTreeMap::new(),
+        };
+        let mut tally = SeedTally::default();
+
+        for file_path in search.file_paths.iter() {
+            if search.debug {
+                println!("scanning file: {:?}", file_path);
+            }
+
+            // Invalid files are skipped, as in a search
+            let _ = open_catalog(file_path, search.format, &search.schema)
+                .and_then(|mut rdr| table.add_file(&mut rdr, search, &mut tally));
+        }
+
+        tally.finish_seed(&mut table);
+
+        // Per-depth counts become "by depth" (cumulative) counts
+        for rarity in table.objects.values_mut() {
+            for depth in 1..DEPTHS {
+                rarity.seeds_by_depth[depth] += rarity.seeds_by_depth[depth - 1];
+                rarity.quantity_by_depth[depth] += rarity.quantity_by_depth[depth - 1];
+            }
+        }
+
+        Ok(table)
+    }
+    /// Adds the Records of a single catalog file to the table.
+    fn add_file(
+        &mut self,
+        rdr: &mut CatalogReader,
+        search: &SearchParameters,
+        tally: &mut SeedTally,
+    ) -> Result<()> {
+        for record_result in rdr.records() {
+            let record = record_result?;
+            let seed = record[columns::SEED].parse::<u32>()?;
+
+            // Seeds are ascending within a file
+            if seed < search.seed_min {
+                continue;
+            }
+            if seed > search.seed_max {
+                break;
+            }
+
+            if tally.prev_seed != Some(seed) {
+                tally.finish_seed(self);
+                tally.prev_seed = Some(seed);
+                self.seeds += 1;
+            }
+
+            let depth = record[columns::DEPTH].parse::<u8>()?;
+            let quantity = record[columns::QUANTITY].parse::<u64>()?;
+
+            if depth == 0 || usize::from(depth) > DEPTHS {
+                continue;
+            }
+
+            tally.add(
+                self,
+                format!("{}:", &record[columns::CATEGORY]),
+                depth,
+                quantity,
+            );
+            tally.add(
+                self,
+                format!("{}:{}", &record[columns::CATEGORY], &record[columns::KIND]),
+                depth,
+                quantity,
+            );
+        }
+
+        Ok(())
+    }
+    /// Loads a cached table.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("could not read rarity table {:?}", path))?;
+        let table: Self = serde_json::from_str(&text)
+            .with_context(|| format!("invalid rarity table {:?}", path))?;
+
+        if table.cache_version != CACHE_VERSION {
+            return Err(anyhow!(
+                "rarity table {:?} is from another version; rebuild it",
+                path
+            ));
+        }
+
+        Ok(table)
+    }
+    /// Saves the table to a cache file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let text = serde_json::to_string(self)?;
+        std::fs::write(path, text)
+            .with_context(|| format!("could not write rarity table {:?}", path))
+    }
+    /// Rebuilds the table from the catalog files of the given search, over the seed range
+    /// the table was built from.
+    pub fn rebuild(&self, search: &SearchParameters) -> Result<Self> {
+        Self::build(&SearchParameters {
+            seed_min: self.seed_min,
+            seed_max: self.seed_max,
+            ..search.clone()
+        })
+    }
+    /// Returns the names of catalog files added, removed, or changed since the table was
+    /// built (empty if it's current).
+    pub fn stale_files(&self, file_paths: &[PathBuf]) -> Vec<String> {
+        self.manifest.stale_files(file_paths)
+    }
+    /// Estimates the rarity of objects of a category (and kind, partial match allowed)
+    /// by a given depth.  Matching kinds are assumed to appear independently.  Returns
+    /// `None` if the table is empty.
+    pub fn estimate(&self, category: Category, kind: Option<&str>, depth: u8) -> Option<Estimate> {
+        if self.seeds == 0 {
+            return None;
+        }
+
+        let category_flags = category.to_flags();
+        let index = usize::from(depth.clamp(1, DEPTHS as u8)) - 1;
+        let seeds = self.seeds as f64;
+        let mut none_chance = 1.0;
+        let mut mean = 0.0;
+
+        for (key, rarity) in self.objects.iter() {
+            let (key_category, key_kind) = key.split_once(':').unwrap_or((key, ""));
+
+            let category_match = Category::parse(key_category)
+                .is_some_and(|c| c.to_flags().intersects(category_flags));
+            let kind_match = match kind {
+                Some(kind) => !key_kind.is_empty() && key_kind.contains(kind),
+                None => key_kind.is_empty(),
+            };
+
+            if category_match && kind_match {
+                none_chance *= 1.0 - rarity.seeds_by_depth[index] as f64 / seeds;
+                mean += rarity.quantity_by_depth[index] as f64 / seeds;
+            }
+        }
+
+        Some(Estimate {
+            chance: 1.0 - none_chance,
+            mean,
+        })
+    }
+}
+
+/// Prints an explanation of each object parameter's rarity, using the cached rarity
+/// table of the search (if any).
+pub fn display_explain(table: Option<&RarityTable>, search: &SearchParameters) {
+    let table = match table {
+        Some(table) => table,
+        None => {
+            println!(
+                "No up-to-date rarity table found at {:?}.  Run 'brogue-scanner rarity' to \
+                build one.\n",
+                search.rarity_cache
+            );
+            return;
+        }
+    };
+
+    println!(
+        "Rarity (from {} seeds in {:?}):\n",
+        table.seeds, search.rarity_cache
+    );
+
+    for param in search.object_params.iter() {
+        let depth = param.depth.min(search.depth_max);
+        let name = match param.kind.as_ref() {
+            Some(kind) => format!("{} '{}'", param.category, kind),
+            None => format!("{}", param.category),
+        };
+
+        match table.estimate(param.category, param.kind.as_deref(), depth) {
+            Some(estimate) => println!(
+                "  {} by depth {}:  in {:.1}% of seeds, {:.2} per seed",
+                name,
+                depth,
+                estimate.chance * 100.0,
+                estimate.mean
+            ),
+            None => println!("  {} by depth {}:  unknown", name, depth),
+        }
+    }
+
+    println!(
+        "\n  Rarity ignores enchantment, runics, and other attributes, so matches \
+        with them are rarer.\n"
+    );
+
+    report_selectivity(table, search);
+}
+
+/// Estimates the chance of a seed matching every object parameter of a search, assuming
+/// parameters are independent.  Returns `None` if the table is empty.
+pub fn query_chance(table: &RarityTable, search: &SearchParameters) -> Option<f64> {
+    let mut chance = 1.0;
+
+    for param in search.object_params.iter() {
+        let depth = param.depth.min(search.depth_max);
+        let estimate = table.estimate(param.category, param.kind.as_deref(), depth)?;
+        chance *= estimate.count_chance(param.count_type, param.count_target);
+    }
+
+    Some(chance)
+}
+
+/// Prints the estimated number of matching seeds per 10,000, and warns if the search is
+/// so strict that millions of seeds likely need scanning.  For machine output, only the
+/// warning is printed (to stderr).
+pub fn report_selectivity(table: &RarityTable, search: &SearchParameters) {
+    let chance = match query_chance(table, search) {
+        Some(chance) => chance,
+        None => return,
+    };
+
+    if search.output_mode == OutputMode::Text {
+        println!(
+            "Estimate: ~{:.2} matching seeds per 10,000 (fewer with enchantment, runics, \
+            and other attributes).\n",
+            chance * 10_000.0
+        );
+    }
+
+    if chance < STRICT_CHANCE {
+        // Searches for every matching seed are warned about as if for one
+        let target = search.search_match_target.max(1);
+        let warning = match chance > 0.0 {
+            true => format!(
+                "warning: very strict search:  finding {} matching seeds likely requires \
+                scanning ~{:.0} million seeds.",
+                target,
+                f64::from(target) / chance / 1_000_000.0
+            ),
+            false => "warning: very strict search:  no seed in the rarity sample comes \
+                close to matching it."
+                .to_string(),
+        };
+
+        match search.output_mode {
+            OutputMode::Text => println!("{}\n", warning),
+            _ => eprintln!("{}", warning),
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Request log for the daemon.
+//!
+//! `brogue-scanner [OPTIONS] daemon --log FILE` appends a line of JSON to FILE when the
+//! daemon starts and for every request it answers, so a scanner left running can be
+//! operated and debugged.  Each entry has:
+//!
+//! - `time_ms`:  when it was written, in milliseconds since the Unix epoch
+//! - `event`:  `start`, `request`, or `connection_error`
+//! - `id` (requests):  the query id chosen by a `--socket` client, or the number of the
+//!   request on standard input (from 1)
+//! - `args` (requests):  the request's arguments, after the daemon's general options
+//! - `duration_ms` (requests):  time taken to answer the request
+//! - `status` (requests):  `ok`, `error`, or `cancelled`
+//! - `error`:  the error message, if any
+//!
+//! The log is rotated before an entry that would grow it past `--log-max-size`, or that
+//! falls on a new (UTC) day:  FILE is renamed FILE.1, FILE.1 is renamed FILE.2, and so on,
+//! keeping at most `--log-keep` old files.  Failing to write the log is reported on
+//! standard error, but never stops the daemon.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Milliseconds in a day.
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Returns the time in milliseconds since the Unix epoch.
+fn epoch_ms(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// A single log entry.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LogEntry {
+    pub time_ms: u64,
+    pub event: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl LogEntry {
+    /// Makes an entry for an event other than a request, with an optional error.
+    pub fn event(event: &'static str, error: Option<String>) -> Self {
+        Self {
+            time_ms: epoch_ms(SystemTime::now()),
+            event,
+            id: None,
+            args: None,
+            duration_ms: None,
+            status: None,
+            error,
+        }
+    }
+    /// Makes an entry for a request started at `started`, with its status and error
+    /// message (if any).
+    pub fn request(
+        id: u64,
+        args: &[String],
+        started: Instant,
+        status: &'static str,
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            id: Some(id),
+            args: Some(args.join(" ")),
+            duration_ms: Some((started.elapsed().as_secs_f64() * 1e6).round() / 1e3),
+            status: Some(status),
+            ..Self::event("request", error)
+        }
+    }
+}
+
+/// Log file that rotates by size and date.
+#[derive(Debug)]
+pub struct RequestLog {
+    path: PathBuf,
+    /// Size past which the log is rotated, in bytes.
+    max_size: u64,
+    /// Number of rotated files kept.
+    keep: u32,
+    file: File,
+    /// Current size of the log, in bytes.
+    size: u64,
+    /// Day (since the Unix epoch) the log was last written.
+    day: u64,
+}
+
+impl RequestLog {
+    /// Opens a log for appending, creating it if needed.
+    pub fn open<P: AsRef<Path>>(path: P, max_size: u64, keep: u32) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = open_append(&path)?;
+        let metadata = file.metadata()?;
+        let modified = metadata.modified().map(epoch_ms).unwrap_or(0);
+
+        Ok(Self {
+            path,
+            max_size,
+            keep,
+            file,
+            size: metadata.len(),
+            day: modified / DAY_MS,
+        })
+    }
+    /// Writes an entry, rotating the log first if needed.
+    pub fn write(&mut self, entry: &LogEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        let day = entry.time_ms / DAY_MS;
+        let oversized = self.size + line.len() as u64 > self.max_size;
+
+        if self.size > 0 && (oversized || day != self.day) {
+            self.rotate()?;
+        }
+
+        self.file.write_all(line.as_bytes())?;
+        self.file.flush()?;
+        self.size += line.len() as u64;
+        self.day = day;
+
+        Ok(())
+    }
+    /// Renames the log (and older rotated logs) up by one number, dropping the oldest,
+    /// and starts a new log.
+    fn rotate(&mut self) -> Result<()> {
+        let numbered = |n: u32| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{}", n));
+            PathBuf::from(name)
+        };
+
+        match self.keep {
+            0 => fs::remove_file(&self.path)?,
+            keep => {
+                let _ = fs::remove_file(numbered(keep));
+                for n in (1..keep).rev() {
+                    if numbered(n).exists() {
+                        fs::rename(numbered(n), numbered(n + 1))?;
+                    }
+                }
+                fs::rename(&self.path, numbered(1))?;
+            }
+        }
+
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+
+        Ok(())
+    }
+}
+
+/// Opens a file for appending, creating it if needed.
+fn open_append(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("can't open log {:?}", path))
+}
@@ -1,21 +1,68 @@
 //! Search structs and functionality parameters for Brogue Seed Scanner.
 
+mod cancel;
+mod export;
+mod expr;
+mod filter;
+mod logic;
 mod params;
 mod parse;
+mod predicate;
+mod query_file;
+mod report;
+mod rescue;
+mod schema;
+mod score;
+mod seed_list;
+mod sink;
+mod template;
+mod vaults;
 
+pub use cancel::CancelToken;
+pub use export::write_export;
+pub use expr::ScoreExpr;
 pub use params::SearchParameters;
+pub use query_file::{base_args, load_query_file};
+pub use schema::{OutputError, OutputMode, SummaryV1, append_matches, finish_output, write_approx, write_explain, write_machine_matches, write_stats, write_status};
+#[cfg(any(feature = "save-db", feature = "server", test))]
+pub use schema::MatchV1;
+#[cfg(feature = "upload")]
+pub use schema::write_upload;
+pub use score::{display_grades, AllyWeights, BestBy, GradeThresholds, SeedScore};
+#[allow(unused_imports)] // Used for testing
+pub use score::rank_seeds;
+use score::keep_best;
+pub use seed_list::{create_seed_list, load_seed_list, write_seed_list, write_seeds, SeedSetOp};
+pub use sink::SeedSink;
+#[cfg(feature = "server")]
+pub use sink::StreamedSeed;
+pub use template::MatchTemplate;
+use rescue::{add_level_record, ally_rescues, LevelRows};
+use vaults::{add_vault_record, SeedVault};
+use crate::bitflags::BitFlags16;
+use crate::catalog::Catalog;
+use crate::columns;
 use crate::objects::{
     Category, Object, MagicType, AllyStatus, AltarKind, ArmorKind, ArmorRunic, 
     CharmKind, FoodKind, GoldKind, KeyKind, MonsterKind, Mutation, PotionKind, 
     RingKind, StaffKind, ScrollKind, WandKind, WeaponKind, WeaponRunic
 };
-use crate::file_handling::FileFormat;
+use crate::file_handling::{open_catalog, seed_range_from_name, CatalogReader, FileFormat};
+use crate::index::CatalogIndexes;
+use crate::progress::Progress;
+use crate::status::no_files;
+use crate::throttle::Throttle;
+use crate::variant::DataPack;
 use anyhow::{anyhow, Result};
-use csv::{ReaderBuilder, StringRecord};
-use encoding_rs_io::DecodeReaderBytesBuilder;
-use params::ObjectParameter;
-use std::fs::File;
-use std::io::Read;
+use csv::StringRecord;
+pub(crate) use filter::ColumnError;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::convert::TryFrom;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+pub(crate) use params::{parse_seed, ObjectParameter};
 
 /// Whether or not a search is fully complete (max # of search results met).
 #[repr(u8)]
@@ -32,10 +79,11 @@ pub(crate) enum SearchStatus {
 }
 
 /// Match Count type for object parameters fields, with "At Least" being default.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 #[repr(u8)]
 pub(crate) enum CountType {
     /// Object match count should be ">=" object match target.
+    #[default]
     AtLeast,
     /// Object match count should be "<" object match target.
     LessThan,
@@ -43,12 +91,6 @@ pub(crate) enum CountType {
     EqualTo,
 }
 
-impl Default for CountType {
-    fn default() -> Self {
-        CountType::AtLeast
-    }
-}
-
 /// How search parameters should respond to a given match, beased on the count type
 /// of the object parameters matched.
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -64,34 +106,189 @@ pub enum MatchResponse {
 
 /// Prints all `SearchMatch` instances.
 /// - Verbosity  1: displays only seed with matches
-/// - Verbosity  2: displays seed and depth with matches
+/// - Verbosity  2: displays seed and depth with matches, with a summary of each depth
 /// - Verbosity  3: displays seed, depth, and items in each match
+///
+/// Seeds already printed with `--stream` are only counted.
 // pub fn display_matches(matches: &Vec<SearchMatch>, verbosity: u8) {
-pub fn display_matches(matches: &Vec<SearchMatch>, params: &SearchParameters) {
-    let mut seed_count = 0;
-    let mut seed = 0;
-    let mut depth = 0;
+pub fn display_matches(matches: &[SearchMatch], params: &SearchParameters) {
+    let seeds: Vec<&[SearchMatch]> = matches.chunk_by(|a, b| a.seed == b.seed).collect();
 
-    if matches.len() > 0 {
-        println!("Matches:\n");
+    if !params.stream {
+        if !seeds.is_empty() {
+            println!("Matches:\n");
+        }
+        for seed_matches in seeds.iter() {
+            display_seed(seed_matches, params);
+        }
     }
-    
-    for m in matches {
-        if m.seed != seed {
-            seed = m.seed;
-            depth = 0;
-            seed_count += 1;
-            println!("Seed {}", seed);
+    println!(
+        "\n...{} found.\n",
+        params.counted(seeds.len() as u64, "match", "matches")
+    );
+}
+
+/// Prints the matches of a single seed.
+fn display_seed(matches: &[SearchMatch], params: &SearchParameters) {
+
+    let seed = match matches.first() {
+        Some(m) => m.seed,
+        None => return,
+    };
+    match params.required_depth(seed) {
+        Some(depth) => println!("Seed {}  (complete by depth {})", seed, depth),
+        None => println!("Seed {}", seed),
+    }
+    if params.met_depths {
+        display_met_depths(seed, params);
+    }
+    if params.vaults {
+        for vault in params.seed_vaults.get(&seed).into_iter().flatten() {
+            println!("    {}", vault);
+        }
+    }
+    if params.rescue {
+        for rescue in params.seed_rescues.get(&seed).into_iter().flatten() {
+            println!("    {}", rescue);
         }
-        if m.depth != depth && params.verbosity > 1 {
-            depth = m.depth;
-            println!("    Depth {}", depth);
+    }
+    for depth_matches in matches.chunk_by(|a, b| a.depth == b.depth) {
+        if params.verbosity > 1 {
+            println!("    {}", depth_summary(depth_matches));
         }
         if params.verbosity > 2 {
-            println!("        {}", m);
+            for m in depth_matches {
+                println!("        {}", m);
+            }
+        }
+    }
+}
+
+/// Returns the summary line of the matches at a depth, counting them by category in
+/// order of appearance, e.g. "Depth 3: 2 matches (1 weapon, 1 potion)" ("No matches" for none).
+pub fn depth_summary(matches: &[SearchMatch]) -> String {
+    let depth = match matches.first() {
+        Some(m) => m.depth,
+        None => return "No matches".to_string(),
+    };
+    let mut counts: Vec<(Category, usize)> = Vec::new();
+
+    for m in matches {
+        let category = m.object.category();
+        match counts.iter_mut().find(|(c, _)| *c == category) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((category, 1)),
+        }
+    }
+
+    let counts: Vec<String> = counts
+        .iter()
+        .map(|(category, count)| match count {
+            1 => format!("1 {}", category),
+            _ => format!("{} {}", count, category.plural()),
+        })
+        .collect();
+
+    match matches.len() {
+        1 => format!("Depth {}: 1 match ({})", depth, counts.join(", ")),
+        len => format!("Depth {}: {} matches ({})", depth, len, counts.join(", ")),
+    }
+}
+
+/// Sorts matches by the required depth of their seeds (shallowest first), keeping each
+/// seed's matches together and seeds of equal depth in order (`--sort-depth`).
+pub fn sort_by_required_depth(matches: &mut Vec<SearchMatch>, search: &SearchParameters) {
+    let mut seeds: Vec<&[SearchMatch]> = matches.chunk_by(|a, b| a.seed == b.seed).collect();
+    seeds.sort_by_key(|seed_matches| search.required_depth(seed_matches[0].seed).unwrap_or(0));
+
+    *matches = seeds.concat();
+}
+
+/// Prints the depth each object parameter of a matching seed was first met at, and the
+/// depth of its last match (`--met-depths`).
+fn display_met_depths(seed: u32, params: &SearchParameters) {
+    let met_depths = match params.seed_met_depths.get(&seed) {
+        Some(met_depths) => met_depths,
+        None => return,
+    };
+
+    for (param, met) in params.object_params.iter().zip(met_depths.iter()) {
+        match met {
+            Some((first, last)) if first == last => {
+                println!("    {} complete at depth {}", param.short_name(), first)
+            }
+            Some((first, last)) => {
+                println!(
+                    "    {} complete at depth {} (last found at depth {})",
+                    param.short_name(),
+                    first,
+                    last
+                )
+            }
+            None => println!("    {} met without a match", param.short_name()),
         }
     }
-    println!("\n...{} matches found.\n", seed_count);
+}
+
+/// Prints how many scanned seeds met each object parameter on its own (`--param-stats`),
+/// marking the parameter met by the fewest seeds.
+pub fn display_param_stats(search: &SearchParameters) {
+    let fewest = search.object_params.iter().map(|p| p.seeds_met).min();
+
+    println!(
+        "Objects met (of {} seeds scanned):\n",
+        search.number(search.seeds_scanned)
+    );
+
+    for (i, param) in search.object_params.iter().enumerate() {
+        let percent = match search.seeds_scanned {
+            0 => 0.0,
+            scanned => param.seeds_met as f64 * 100.0 / scanned as f64,
+        };
+        let description = param.to_string();
+        let description: Vec<&str> = description.lines().map(str::trim).collect();
+        let marker = match (
+            search.object_params.len() > 1,
+            Some(param.seeds_met) == fewest,
+        ) {
+            (true, true) => "  <- fewest",
+            _ => "",
+        };
+
+        println!(
+            "{:>3}. {:>6} seeds ({:5.1}%)  {}{}",
+            i + 1,
+            search.number(param.seeds_met),
+            percent,
+            description.join(", "),
+            marker
+        );
+    }
+    println!();
+}
+
+/// Error for a Record whose values aren't valid for its category (e.g. a kind the data
+/// tables of `--variant` or `--data-dir` don't have), or that has more or fewer columns
+/// than the header.  Like `ColumnError`, this ends the search, rather than skipping the
+/// rest of the file.
+#[derive(Debug)]
+pub struct RowError(String);
+
+impl std::fmt::Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for RowError {}
+
+/// Returns a `RowError` for a Record with more or fewer columns than the header (e.g. one
+/// missing an extra column), or the error as is.
+fn row_length_error(error: csv::Error) -> anyhow::Error {
+    match error.kind() {
+        csv::ErrorKind::UnequalLengths { .. } => RowError(error.to_string()).into(),
+        _ => error.into(),
+    }
 }
 
 /// Holds a matching search results for a query.
@@ -100,8 +297,11 @@ pub struct SearchMatch {
     /// Whether a match resulted in success or failure (MatchType::LessThan / EqualTo)
     pub match_resp: MatchResponse,    
     // Object Data
+    /// Dungeon version of the catalog the object was found in
+    pub version: String,
     pub seed: u32,
     pub depth: u8,
+    pub quantity: u32,
     pub object: Object,
     /// Vault in which object is held
     pub vault: Option<u8>,
@@ -110,97 +310,120 @@ pub struct SearchMatch {
 }
 
 impl SearchMatch {
-    /// Creates a new instance from a CSV Record.  `unwrap()` is used because values
-    /// are known to be present.
+    /// Creates a new instance from a CSV Record, or returns a `RowError` (naming the row)
+    /// if its values aren't valid for the category.
     pub(crate) fn from_record(
         category: Category,
         match_resp: MatchResponse, 
         seed: u32,
         depth: u8, 
         record: &StringRecord
-    ) -> Self {        
+    ) -> Result<Self> {
+        Self::parse_record(category, match_resp, seed, depth, record).ok_or_else(|| {
+            let kind = record.get(columns::KIND).unwrap_or_default();
+            let error = format!(
+                "invalid {} '{}' (seed {}, depth {})",
+                category, kind, seed, depth
+            );
+            match record.position() {
+                Some(position) => RowError(format!("line {}:  {}", position.line(), error)).into(),
+                None => RowError(error).into(),
+            }
+        })
+    }
+    /// Creates a new instance from a CSV Record, if its values are valid for the category.
+    pub(crate) fn parse_record(
+        category: Category,
+        match_resp: MatchResponse,
+        seed: u32,
+        depth: u8,
+        record: &StringRecord,
+    ) -> Option<Self> {
         use Category::*;
 
         let object = match category {
             Weapon => {
-                let kind = WeaponKind::parse(&record[5]).unwrap();
-                let enchantment = record[6].parse::<i8>().unwrap();
-                let runic = WeaponRunic::parse(&record[7]);
+                let kind = WeaponKind::parse(&record[columns::KIND])?;
+                let enchantment = record[columns::ENCHANTMENT].parse::<i8>().ok()?;
+                let runic = WeaponRunic::parse(&record[columns::RUNIC]);
                 Object::new_weapon(kind, enchantment, runic)
             }
             Armor => {
-                let kind = ArmorKind::parse(&record[5]).unwrap();
-                let enchantment = record[6].parse::<i8>().unwrap();
-                let runic = ArmorRunic::parse(&record[7]);
+                let kind = ArmorKind::parse(&record[columns::KIND])?;
+                let enchantment = record[columns::ENCHANTMENT].parse::<i8>().ok()?;
+                let runic = ArmorRunic::parse(&record[columns::RUNIC]);
                 Object::new_armor(kind, enchantment, runic)
             }
             Potion => {
-                let kind = PotionKind::parse(&record[5]).unwrap();
+                let kind = PotionKind::parse(&record[columns::KIND])?;
                 Object::new_potion(kind)
             }            
             Scroll => {
-                let kind = ScrollKind::parse(&record[5]).unwrap();
+                let kind = ScrollKind::parse(&record[columns::KIND])?;
                 Object::new_scroll(kind)
             }
             Charm => {
-                let kind = CharmKind::parse(&record[5]).unwrap();
-                let enchantment = record[6].parse::<i8>().unwrap();
+                let kind = CharmKind::parse(&record[columns::KIND])?;
+                let enchantment = record[columns::ENCHANTMENT].parse::<i8>().ok()?;
                 Object::new_charm(kind, enchantment)
             }
             Ring => {
-                let kind = RingKind::parse(&record[5]).unwrap();
-                let enchantment = record[6].parse::<i8>().unwrap();
+                let kind = RingKind::parse(&record[columns::KIND])?;
+                let enchantment = record[columns::ENCHANTMENT].parse::<i8>().ok()?;
                 Object::new_ring(kind, enchantment)
             }
             Staff => {
-                let kind = StaffKind::parse(&record[5]).unwrap();
-                let enchantment = record[6].parse::<i8>().unwrap();
+                let kind = StaffKind::parse(&record[columns::KIND])?;
+                let enchantment = record[columns::ENCHANTMENT].parse::<i8>().ok()?;
                 Object::new_staff(kind, enchantment)
             }
             Wand => {
-                let kind = WandKind::parse(&record[5]).unwrap();
-                let enchantment = record[6].parse::<i8>().unwrap();
+                let kind = WandKind::parse(&record[columns::KIND])?;
+                let enchantment = record[columns::ENCHANTMENT].parse::<i8>().ok()?;
                 Object::new_wand(kind, enchantment)
             }   
             Ally => {
-                let kind = MonsterKind::parse(&record[5]).unwrap();
-                let status = AllyStatus::parse(&record[11]).unwrap();
-                let mutation = Mutation::parse(&record[12]);
+                let kind = MonsterKind::parse(&record[columns::KIND])?;
+                let status = AllyStatus::parse(&record[columns::ALLY_STATUS])?;
+                let mutation = Mutation::parse(&record[columns::MUTATION]);
                 Object::new_ally(kind, status, mutation)
             }
             Food => {
-                let kind = FoodKind::parse(&record[5]).unwrap();
+                let kind = FoodKind::parse(&record[columns::KIND])?;
                 Object::new_food(kind)
             }
             Gold => {
-                let kind = GoldKind::parse(&record[5]).unwrap();
-                let count = record[3].parse::<u32>().unwrap();
+                let kind = GoldKind::parse(&record[columns::KIND])?;
+                let count = record[columns::QUANTITY].parse::<u32>().ok()?;
                 Object::new_gold(kind, count)
             }
             Altar => {
-                let kind = AltarKind::parse(&record[5]).unwrap();
+                let kind = AltarKind::parse(&record[columns::KIND])?;
                 Object::new_altar(kind)
             }
             Key => {
-                let kind = KeyKind::parse(&record[5]).unwrap();
-                let opens = record[9].parse::<u8>().ok();
+                let kind = KeyKind::parse(&record[columns::KIND])?;
+                let opens = record[columns::OPENS_VAULT].parse::<u8>().ok();
                 Object::new_key(kind, opens)
             }
             // Items and Equipment can't be created from csv Records
-            _ => unreachable!(),
+            _ => return None,
         };
 
-        let vault = record[8].parse::<u8>().ok();
-        let carried_by = MonsterKind::parse(&record[10]);
+        let quantity = record[columns::QUANTITY].parse::<u32>().ok()?;
+        let vault = record[columns::VAULT].parse::<u8>().ok();
+        let carried_by = MonsterKind::parse(&record[columns::CARRIED_BY]);
 
-        Self {
+        Some(Self {
             match_resp,
+            version: record[columns::VERSION].to_string(),
             seed,
             depth,
+            quantity,
             object,
             vault,
             carried_by,
-        }
+        })
     }
 }
 
@@ -218,193 +441,892 @@ impl std::fmt::Display for SearchMatch {
 
 /// Searches filepaths specified using given `SearchParameter`s, and 
 /// returns a list of `SearchResult`s based on matches and level of detail (LOD).
+/// If the search's `CancelToken` is cancelled, returns the matches found so far.
 pub fn search_files(
     search: &mut SearchParameters,
 ) -> Result<Vec<SearchMatch>> {
-    // Always display the search information for user feedback
-    println!("{}", search);
+    let mut results = search_files_batch(std::slice::from_mut(search))?;
+
+    Ok(results.pop().unwrap_or_default())
+}
 
-    if search.file_paths.is_empty() {
-        return Err(anyhow!("No files found!"));
+/// Searches filepaths for several independent sets of `SearchParameters` in a single
+/// pass, so each file is only read once.  File paths, format, and schema are taken from
+/// the first set.  Returns one list of `SearchMatch`es per set, in the same order.
+///
+/// If the files come from more than one catalog directory, each directory is scanned on
+/// its own worker thread and the results are merged.
+pub fn search_files_batch(searches: &mut [SearchParameters]) -> Result<Vec<Vec<SearchMatch>>> {
+    display_searches(searches);
+
+    let (file_paths, format, debug) = match searches.first() {
+        Some(search) => (search.file_paths.clone(), search.format, search.debug),
+        None => return Ok(Vec::new()),
+    };
+
+    if file_paths.is_empty() {
+        return Err(no_files());
     }
 
-    let mut results = Vec::with_capacity(search.search_match_target.into());
-    let file_paths = search.file_paths.clone();
+    let groups = searches[0].file_groups(&file_paths);
+    let indexes = CatalogIndexes::load(&searches[0].file_dirs);
 
-    match search.format {
-        FileFormat::Utf8 => {
-            for file_path in file_paths.iter() {
-                if search.debug {
-                    println!("searching file: {:?}", file_path);
-                }                        
-                let file = File::open(file_path)?;
+    match groups.len() {
+        0 | 1 => search_paths(searches, &file_paths, format, debug, &indexes),
+        _ => search_parallel(searches, &groups, format, debug, &indexes),
+    }
+}
 
-                match search_file(file, search, &mut results) {
-                    Ok(SearchStatus::EndOfSearch) => return Ok(results),
-                    _ => (),
+/// Searches in-memory catalogs (one per file path of the first set of parameters, in the
+/// same order) for several sets of `SearchParameters`, just like `search_files_batch`.
+/// Catalogs are searched one after the other.
+pub fn search_catalogs(
+    searches: &mut [SearchParameters],
+    catalogs: &[&Catalog],
+) -> Result<Vec<Vec<SearchMatch>>> {
+    display_searches(searches);
+
+    if catalogs.is_empty() {
+        return Err(no_files());
+    }
+
+    let mut results: Vec<Vec<SearchMatch>> = searches.iter().map(match_list).collect();
+
+    for catalog in catalogs.iter() {
+        match search_catalog(catalog, searches, &mut results) {
+            Ok(SearchStatus::EndOfSearch) => break,
+            Err(e) if e.is::<ColumnError>() || e.is::<RowError>() || e.is::<OutputError>() => return Err(e),
+            _ => (),
+        }
+    }
+
+    Ok(results)
+}
+
+/// Returns an empty list for the matches of a search.
+fn match_list(search: &SearchParameters) -> Vec<SearchMatch> {
+    // Lists for unlimited (or very large) match counts grow as needed
+    Vec::with_capacity(search.seed_limit().unwrap_or(0).min(256))
+}
+
+/// Always displays the search information for user feedback (except machine output,
+/// where only the kinds read by `--fuzzy` are noted on standard error).
+fn display_searches(searches: &[SearchParameters]) {
+    for search in searches.iter() {
+        match search.output_mode {
+            OutputMode::Text => println!("{}", search),
+            _ => {
+                for (term, kind) in search.fuzzy_terms.iter() {
+                    eprintln!("note: '{}' read as '{}'", term, kind);
                 }
-            }   
+            }
         }
-        FileFormat::Utf16 => {
-            for file_path in file_paths.iter() {
-                if search.debug {
-                    println!("searching file: {:?}", file_path);
-                }                
-                let file = File::open(file_path)?;
-                let new_file = DecodeReaderBytesBuilder::new()
-                    .encoding(Some(encoding_rs::UTF_16LE))
-                    .build(file);
-
-                match search_file(new_file, search, &mut results) {
-                    Ok(SearchStatus::EndOfSearch) => return Ok(results),
-                    _ => (),
+    }
+    if searches.iter().any(|search| search.stream) {
+        println!("Matches:\n");
+    }
+}
+
+/// Searches a list of files, one after the other.  Files the catalog index shows can't
+/// hold a match for any search are skipped (their seeds still count as scanned).
+fn search_paths(
+    searches: &mut [SearchParameters],
+    file_paths: &[PathBuf],
+    format: FileFormat,
+    debug: bool,
+    indexes: &CatalogIndexes,
+) -> Result<Vec<Vec<SearchMatch>>> {
+    let mut results: Vec<Vec<SearchMatch>> = searches.iter().map(match_list).collect();
+    let mut progress = Progress::new(searches[0].progress);
+    let mut throttle = Throttle::new(searches[0].nice);
+    let schema = searches[0].schema.clone();
+
+    for file_path in file_paths.iter() {
+        // Files named by a seed range outside every search's range hold no seeds to scan
+        let out_of_range = seed_range_from_name(file_path).map(|(first, last)| {
+            searches
+                .iter()
+                .all(|search| search.is_complete() || last < search.seed_min || first > search.seed_max)
+        });
+        if out_of_range == Some(true) {
+            if debug {
+                println!("skipping file: {:?}", file_path);
+            }
+            continue;
+        }
+        if let Some(skipped_seeds) = indexes.skipped_seeds(file_path, searches) {
+            if debug {
+                println!("skipping file: {:?}", file_path);
+            }
+            for (search, seeds) in searches.iter_mut().zip(skipped_seeds) {
+                search.seeds_scanned += seeds;
+
+                // Skipped seeds have no matching objects at all
+                for param in search.object_params.iter_mut().filter(|p| p.is_valid_count(0)) {
+                    param.seeds_met += seeds;
                 }
             }
+            continue;
+        }
+        if debug {
+            println!("searching file: {:?}", file_path);
+        }
+        progress.start_file(file_path);
+        let status = open_catalog(file_path, format, &schema)
+            .and_then(|mut rdr| search_file(&mut rdr, searches, &mut results, &mut progress, &mut throttle));
+
+        match status {
+            Ok(SearchStatus::EndOfSearch) => break,
+            Err(e) if e.is::<ColumnError>() || e.is::<OutputError>() => return Err(e),
+            Err(e) if e.is::<RowError>() => return Err(e.context(format!("invalid catalog file {:?}", file_path))),
+            _ => (),
         }
     }
 
     Ok(results)
 }
 
-/// Searches specified filepath using given search parameters, and passes results
-/// into given list of search results.  If `find_all` is `true`, the seed will continue 
-/// to be explored even after ObjectParameters have been satisfied.
-fn search_file<F: Read>(
-    file: F,
-    search: &mut SearchParameters,
-    results: &mut Vec<SearchMatch>,
-) -> Result<SearchStatus> {
-    use SearchStatus::*;
+/// Searches each group of files (one per catalog directory) on its own worker thread,
+/// with its own copy of the searches.  Worker results are merged in the order workers
+/// finish (or in ascending seed order, if `--ordered`), then cut down to each search's
+/// requested number of matching seeds.  Workers don't append to `--output` (or stream
+/// seeds), as they find more seeds than are kept:  the kept matches are appended once
+/// they're merged.
+fn search_parallel(
+    searches: &mut [SearchParameters],
+    groups: &[Vec<PathBuf>],
+    format: FileFormat,
+    debug: bool,
+    indexes: &CatalogIndexes,
+) -> Result<Vec<Vec<SearchMatch>>> {
+    let (sender, receiver) = mpsc::channel();
+    let ordered = searches[0].ordered;
 
-    let depth_min = search.depth_min;
-    let depth_max = search.depth_max;
-    let mut next_seed = search.seed_min;
-    let mut temp = Vec::with_capacity(10);
-    let mut prev_seed = 0;
-    // Flag for AllObjectsFound condition.
-    let mut all_object_flag = false;
+    thread::scope(|scope| {
+        for (worker, group) in groups.iter().enumerate() {
+            let sender = sender.clone();
+            let mut worker_searches: Vec<SearchParameters> = searches
+                .iter()
+                .map(|search| SearchParameters {
+                    seeds_scanned: 0,
+                    rows_scanned: 0,
+                    output: None,
+                    seed_sink: None,
+                    ..search.clone()
+                })
+                .collect();
 
-    let mut rdr = ReaderBuilder::new()
-        .from_reader(file);
+            scope.spawn(move || {
+                let result = search_paths(&mut worker_searches, group, format, debug, indexes);
+                let seeds_scanned: Vec<(u64, u64)> = worker_searches
+                    .iter()
+                    .map(|s| (s.seeds_scanned, s.rows_scanned))
+                    .collect();
+                let seed_gold: Vec<_> = worker_searches
+                    .iter_mut()
+                    .map(|s| {
+                        (
+                            std::mem::take(&mut s.seed_gold),
+                            std::mem::take(&mut s.seed_optionals),
+                            std::mem::take(&mut s.seed_met_depths),
+                            std::mem::take(&mut s.seed_vaults),
+                            std::mem::take(&mut s.seed_rescues),
+                        )
+                    })
+                    .collect();
+                let seeds_met: Vec<Vec<u64>> = worker_searches
+                    .iter()
+                    .map(|s| s.object_params.iter().map(|p| p.seeds_met).collect())
+                    .collect();
+                let _ = sender.send(
+                    result.map(|results| (worker, seeds_scanned, seed_gold, seeds_met, results)),
+                );
+            });
+        }
+    });
+    drop(sender);
 
-    {
-        // Validate header
-        let headers = rdr.headers()?;
-        if !(headers.len() == 13)
-            || !headers.as_slice().contains("dungeon_versionseeddepth") 
+    // Match lists per search, per worker (in the order workers finish)
+    let mut worker_lists: Vec<Vec<(usize, Vec<SearchMatch>)>> = vec![Vec::new(); searches.len()];
+
+    for worker_result in receiver.iter() {
+        let (worker, seeds_scanned, seed_gold, seeds_met, worker_results) = worker_result?;
+
+        for ((i, matches), (gold, optionals, met_depths, vaults, rescues)) in
+            worker_results.into_iter().enumerate().zip(seed_gold)
         {
-            return Err(anyhow!("Invalid Brogue csv header"));
+            searches[i].seeds_scanned += seeds_scanned[i].0;
+            searches[i].rows_scanned += seeds_scanned[i].1;
+            searches[i].seed_gold.extend(gold);
+            searches[i].seed_optionals.extend(optionals);
+            searches[i].seed_met_depths.extend(met_depths);
+            searches[i].seed_vaults.extend(vaults);
+            searches[i].seed_rescues.extend(rescues);
+            for (param, seeds) in searches[i].object_params.iter_mut().zip(seeds_met[i].iter()) {
+                param.seeds_met += seeds;
+            }
+            worker_lists[i].push((worker, matches));
+        }
+    }
+
+    let mut results: Vec<Vec<SearchMatch>> = worker_lists
+        .into_iter()
+        .map(|mut lists| match ordered {
+            true => {
+                lists.sort_by_key(|(worker, _)| *worker);
+                merge_ordered(lists.into_iter().map(|(_, matches)| matches).collect())
+            }
+            false => lists.into_iter().flat_map(|(_, matches)| matches).collect(),
+        })
+        .collect();
+
+    for (search, matches) in searches.iter_mut().zip(results.iter_mut()) {
+        match search.best {
+            Some(_) => keep_best(matches, search),
+            None => {
+                let seeds = search.seed_limit().unwrap_or(usize::MAX);
+                search.search_matches =
+                    u32::try_from(truncate_seeds(matches, seeds)).unwrap_or(u32::MAX);
+            }
+        }
+        if let Some(path) = search.output.as_ref().filter(|_| search.output_mode.is_line_based()) {
+            append_matches(path, matches, search)?;
+        }
+        if let Some(sink) = search.seed_sink.as_ref() {
+            sink.send(matches);
+        }
+    }
+
+    Ok(results)
+}
+
+/// Merges lists of matches (each grouped by seed, in ascending seed order) into a single
+/// list in ascending seed order with a k-way merge.  Each seed's matches stay together,
+/// and equal seeds are taken in list order, so the output is reproducible.
+fn merge_ordered(lists: Vec<Vec<SearchMatch>>) -> Vec<SearchMatch> {
+    let mut merged = Vec::with_capacity(lists.iter().map(|list| list.len()).sum());
+    let mut iters: Vec<_> = lists.into_iter().map(|list| list.into_iter().peekable()).collect();
+    let mut heap = BinaryHeap::with_capacity(iters.len());
+
+    for (i, iter) in iters.iter_mut().enumerate() {
+        if let Some(m) = iter.peek() {
+            heap.push(Reverse((m.seed, i)));
+        }
+    }
+
+    while let Some(Reverse((seed, i))) = heap.pop() {
+        while let Some(m) = iters[i].next_if(|m| m.seed == seed) {
+            merged.push(m);
+        }
+        if let Some(m) = iters[i].peek() {
+            heap.push(Reverse((m.seed, i)));
+        }
+    }
+
+    merged
+}
+
+/// Truncates a list of matches (grouped by seed) to the first `seeds` seeds, and returns
+/// the number of seeds kept.
+fn truncate_seeds(matches: &mut Vec<SearchMatch>, seeds: usize) -> usize {
+    let mut kept = 0;
+    let mut prev_seed = None;
+
+    for (i, m) in matches.iter().enumerate() {
+        if prev_seed != Some(m.seed) {
+            if kept == seeds {
+                matches.truncate(i);
+                break;
+            }
+            prev_seed = Some(m.seed);
+            kept += 1;
+        }
+    }
+
+    kept
+}
+
+/// Per-file scanning state for one set of `SearchParameters`.
+struct ScanState {
+    /// Records with a seed below this are out of bounds (used to skip rest of a seed).
+    next_seed: u32,
+    /// Seed of the previous record, if any.
+    prev_seed: Option<u32>,
+    /// Matches for the current seed, only kept if the seed satisfies all parameters.
+    temp: Vec<SearchMatch>,
+    /// Flag for AllObjectsFound condition.
+    all_object_flag: bool,
+    /// Flag for a seed rejected by an exclusion (e.g. `--no-malevolent`).
+    rejected: bool,
+    /// Depth of the Records in `keys` and `pending` (`--require-key`).
+    key_depth: Option<u8>,
+    /// Vault numbers opened by keys found on `key_depth`.
+    keys: Vec<u8>,
+    /// Vault Records on `key_depth`, searched once every key on the depth is known.
+    pending: Vec<(RecordInfo, StringRecord)>,
+    /// Gold found this seed in the depth range (only counted for `--grade`).
+    gold: u32,
+    /// Vaults found this seed in the depth range (only kept for `--vaults`).
+    vaults: Vec<SeedVault>,
+    /// Cage keys and captors found this seed in the depth range (only kept for `--rescue`).
+    levels: Vec<LevelRows>,
+}
+
+impl ScanState {
+    /// Makes a new instance for the start of a file.
+    fn new(search: &SearchParameters) -> Self {
+        Self {
+            next_seed: search.seed_min,
+            prev_seed: None,
+            temp: Vec::with_capacity(10),
+            all_object_flag: false,
+            rejected: false,
+            key_depth: None,
+            keys: Vec::new(),
+            pending: Vec::new(),
+            gold: 0,
+            vaults: Vec::new(),
+            levels: Vec::new(),
+        }
+    }
+    /// Moves on to the next Record (line in .csv file), closing out the previous seed
+    /// first if the Record starts a new one.  Called for every Record, even those that
+    /// are prefiltered out.
+    fn next_record(
+        &mut self,
+        seed: u32,
+        search: &mut SearchParameters,
+        results: &mut Vec<SearchMatch>,
+    ) -> Result<()> {
+        if self.prev_seed != Some(seed) {
+            if self.prev_seed.is_some() {
+                self.finish_seed(search, results)?;
+            }
+            match search.is_seed_searched(seed) {
+                true => search.seeds_scanned += 1,
+                // Seeds out of range (or left out of the seed list) are skipped
+                false => {
+                    self.next_seed = self.next_seed.max(seed.saturating_add(1));
+                    self.rejected = true;
+                }
+            }
+        }
+        self.prev_seed = Some(seed);
+
+        Ok(())
+    }
+    /// Searches a single Record (line in .csv file) that passed the shared prefilter.
+    fn search_record(
+        &mut self,
+        info: &RecordInfo,
+        record: &StringRecord,
+        search: &mut SearchParameters,
+    ) -> Result<()> {
+        let in_bounds = info.seed >= self.next_seed
+            && info.seed <= search.seed_max
+            && info.depth >= search.depth_min
+            && info.depth <= search.depth_max;
+
+        if in_bounds && is_excluded(info, record, search) {
+            // Remaining records of this seed are now out of bounds
+            self.next_seed = info.seed.saturating_add(1);
+            self.rejected = true;
+            return Ok(());
+        }
+
+        // Gold breaks ties between seeds with the same score
+        if in_bounds && info.category == Category::Gold && search.is_scored() {
+            self.gold = self.gold.saturating_add(record[columns::QUANTITY].parse::<u32>()?);
+        }
+
+        if in_bounds && search.vaults {
+            add_vault_record(
+                &mut self.vaults,
+                info.category,
+                info.seed,
+                info.depth,
+                record,
+            )?;
+        }
+        if in_bounds && search.rescue {
+            add_level_record(&mut self.levels, info.category, info.depth, record);
+        }
+
+        if in_bounds && search.require_key {
+            if self.key_depth != Some(info.depth) {
+                self.search_pending(search)?;
+                self.key_depth = Some(info.depth);
+            }
+            if info.category == Category::Key {
+                if let Ok(vault) = record[columns::OPENS_VAULT].parse::<u8>() {
+                    self.keys.push(vault);
+                }
+            }
+            // Vault objects wait until every key on the depth has been seen
+            if !record[columns::VAULT].is_empty() {
+                self.pending.push((*info, record.clone()));
+                return Ok(());
+            }
+        }
+
+        match in_bounds {
+            true => self.match_record(info, record, search),
+            false => Ok(()),
         }
     }
+    /// Searches the pending vault Records whose vault has a key on the same depth, then
+    /// clears the keys and pending Records.
+    fn search_pending(&mut self, search: &mut SearchParameters) -> Result<()> {
+        let pending = std::mem::take(&mut self.pending);
 
-    // Clear any search data from a previous file (as it's a new seed)
-    search.clear();
+        for (info, record) in pending.iter() {
+            let keyed = record[columns::VAULT]
+                .parse::<u8>()
+                .is_ok_and(|vault| self.keys.contains(&vault));
 
-    // Validate then search 1st line
-    if let Some(result) = rdr.records().next() {
-        let record = result?;  
+            if keyed && info.seed >= self.next_seed && !self.rejected {
+                self.match_record(info, record, search)?;
+            }
+        }
 
-        // Early exit if 1st line is OOB (e.g. seed > seed_max)
-        let (in_bounds, seed, depth) = bounds_check(
-            &record, next_seed, search.seed_max, depth_min, depth_max
-        )?;
+        self.keys.clear();
+        self.key_depth = None;
 
-        prev_seed = seed;
+        Ok(())
+    }
+    /// Matches an in-bounds Record against the search's object parameters.
+    fn match_record(
+        &mut self,
+        info: &RecordInfo,
+        record: &StringRecord,
+        search: &mut SearchParameters,
+    ) -> Result<()> {
+        use SearchStatus::*;
+
+        if search.row_filters.iter().all(|filter| filter.matches(record)) {
+            let search_match = search_record(info, record, search)?;
+            let empowered = search_empower(info, record, search)?;
 
-        if in_bounds {
-            if let Some(search_match) = search_record(seed, depth, &record, search)? {
+            // Empower matches are only kept for display, so aren't counted as objects
+            if empowered && search_match.is_none() {
+                self.temp.push(SearchMatch::from_record(
+                    info.category,
+                    MatchResponse::DoNothing,
+                    info.seed,
+                    info.depth,
+                    record,
+                )?);
+            }
+
+            if let Some(search_match) = search_match {
                 let status = search.search_status(search_match.match_resp);
-                temp.push(search_match);     
-                
+                self.temp.push(search_match);
+
                 match status {
                     AllObjectsFound => {
-                        all_object_flag = true
+                        self.all_object_flag = true;
                     }
                     EarlySeedExit => {
-                        next_seed += 1;
-                        all_object_flag = false;
+                        // Remaining records of this seed are now out of bounds
+                        self.next_seed = info.seed.saturating_add(1);
+                        self.all_object_flag = false;
                     }
                     _ => (),
                 }
             }
-        } else {
-            return Ok(EndOfFile);   
         }
+
+        Ok(())
     }
+    /// Closes out the current seed:  its matches are added to the results if all object
+    /// parameters (and the empower constraint, if any) were met.  Clears the temp buffer,
+    /// search and object counters.
+    fn finish_seed(
+        &mut self,
+        search: &mut SearchParameters,
+        results: &mut Vec<SearchMatch>,
+    ) -> Result<()> {
+        self.search_pending(search)?;
 
-    // Search remaining lines in the file
-    for record_result in rdr.records() {
-        let record = record_result?;
-       
-        let (in_bounds, seed, depth) = bounds_check(
-            &record, next_seed, search.seed_max, depth_min, depth_max
-        )?;
-
-        // Clear the temp buffer, search and object counters on new seed
-        if seed != prev_seed {
-            if all_object_flag && search.is_valid() {
-                results.extend_from_slice(&temp);
-                search.search_matches += 1;
-                all_object_flag = false;
-
-                if search.is_complete() {
-                    break;
+        // Object logic other than "every object" is only checked by `is_valid`
+        if self.prev_seed.is_some_and(|seed| search.is_seed_searched(seed)) {
+            for param in search.object_params.iter_mut().filter(|p| p.is_valid()) {
+                param.seeds_met += 1;
+            }
+        }
+
+        let objects_found =
+            self.all_object_flag || search.object_match_target == 0 || !search.logic.is_flat();
+
+        if objects_found && !self.rejected && !search.is_empty() && search.is_valid() {
+            if let Some(path) =
+                search.output.as_ref().filter(|_| search.output_mode.is_line_based())
+            {
+                append_matches(path, &self.temp, search)?;
+            }
+            if let Some(sink) = search.seed_sink.as_ref() {
+                sink.send(&self.temp);
+            }
+            if let (Some(seed), true) = (self.prev_seed, search.rescue) {
+                search
+                    .seed_rescues
+                    .insert(seed, ally_rescues(&self.temp, &self.levels));
+            }
+            let seed_start = results.len();
+            results.append(&mut self.temp);
+            search.search_matches = search.search_matches.saturating_add(1);
+
+            if let (Some(seed), true) = (self.prev_seed, search.is_scored()) {
+                let optionals = search
+                    .object_params
+                    .iter()
+                    .filter(|p| p.optional && p.is_valid())
+                    .count();
+                search.seed_gold.insert(seed, self.gold);
+                search.seed_optionals.insert(seed, optionals as u32);
+            }
+            if let Some(seed) = self.prev_seed {
+                let params = &search.object_params;
+                let met_depths = params
+                    .iter()
+                    .map(|p| p.met_depths())
+                    .chain(search.totals.iter().map(|total| total.met_depths(params)))
+                    .collect();
+                search.seed_met_depths.insert(seed, met_depths);
+            }
+            if let (Some(seed), true) = (self.prev_seed, search.vaults) {
+                search.seed_vaults.insert(seed, std::mem::take(&mut self.vaults));
+            }
+            if search.stream {
+                display_seed(&results[seed_start..], search);
+            }
+            keep_best(results, search);
+        }
+        self.all_object_flag = false;
+        self.rejected = false;
+        self.gold = 0;
+        self.vaults.clear();
+        self.levels.clear();
+        search.clear();
+        self.temp.clear();
+
+        Ok(())
+    }
+}
+
+/// Record (line in .csv file) fields needed by every search, parsed once per Record.
+#[derive(Debug, Clone, Copy)]
+struct RecordInfo {
+    seed: u32,
+    depth: u8,
+    category: Category,
+    category_flags: BitFlags16,
+}
+
+impl RecordInfo {
+    /// Parses depth and category from a Record with an already-parsed seed.
+    fn parse(record: &StringRecord, seed: u32) -> Result<Self> {
+        let depth = record[columns::DEPTH].parse::<u8>()?;
+        let category = match Category::parse(&record[columns::CATEGORY]) {
+            Some(category) => category,
+            None => return Err(anyhow!("Invalid category '{}' for seed {}", &record[columns::CATEGORY], seed)),
+        };
+
+        Ok(Self {
+            seed,
+            depth,
+            category,
+            category_flags: category.to_flags(),
+        })
+    }
+}
+
+/// Prefilter shared by all searches in a pass.  Records that no object parameter of any
+/// search could match (by category or depth) skip per-search evaluation entirely.
+#[derive(Debug, Clone, Copy)]
+struct Prefilter {
+    /// Union of the category flags of every object parameter.
+    category_flags: BitFlags16,
+    /// Deepest depth at which any object parameter is searched for.
+    depth_max: u8,
+    /// Whether every object parameter requires a vault, so Records with an empty vault
+    /// column (the vast majority) can be skipped before parsing.
+    vault_only: bool,
+}
+
+impl Prefilter {
+    /// Makes a new instance covering every object parameter of every search.
+    fn new(searches: &[SearchParameters]) -> Self {
+        let mut category_flags = BitFlags16::empty();
+        let mut depth_max = 0;
+        let mut vault_only = true;
+
+        for search in searches {
+            for param in search.object_params.iter() {
+                category_flags.insert(param.category_flags);
+                depth_max = depth_max.max(param.depth.min(search.depth_max));
+                vault_only &= param.in_vault == Some(true);
+            }
+            if let Some(empower) = search.empower.as_ref() {
+                category_flags.insert(Category::Ally.to_flags());
+                category_flags.insert(Category::Wand.to_flags());
+                depth_max = depth_max.max(empower.depth);
+                vault_only = false;
+            }
+            if search.require_key {
+                category_flags.insert(Category::Key.to_flags());
+                vault_only = false;
+            }
+            if search.is_scored() {
+                category_flags.insert(Category::Gold.to_flags());
+                depth_max = depth_max.max(search.depth_max);
+                vault_only = false;
+            }
+            if !search.exclusions.is_empty() {
+                for exclusion in search.exclusions.iter() {
+                    category_flags.insert(exclusion.category_flags);
                 }
+                depth_max = depth_max.max(search.depth_max);
+                vault_only = false;
+            }
+            if search.vaults || search.rescue {
+                category_flags.insert(BitFlags16(u16::MAX));
+                depth_max = depth_max.max(search.depth_max);
+                vault_only = false;
+            }
+            if let Some(depth) = search.no_malevolent {
+                for category in MALEVOLENT_CATEGORIES.iter() {
+                    category_flags.insert(category.to_flags());
+                }
+                depth_max = depth_max.max(depth);
+                vault_only = false;
             }
-            all_object_flag = false;
-            search.clear();
-            temp.clear();
         }
 
-        prev_seed = seed;
+        Self {
+            category_flags,
+            depth_max,
+            vault_only,
+        }
+    }
+    /// Returns `true` if a Record can be skipped without parsing (vault-only fast path),
+    /// given whether its vault column is empty.
+    #[inline]
+    fn skips(&self, vault_empty: bool) -> bool {
+        self.vault_only && vault_empty
+    }
+    /// Returns `true` if at least one search might match the Record.
+    #[inline]
+    fn matches(&self, info: &RecordInfo) -> bool {
+        info.category_flags.intersects(self.category_flags) && info.depth <= self.depth_max
+    }
+}
 
-        if in_bounds {
-            if let Some(search_match) = search_record(seed, depth, &record, search)? {
-                let status = search.search_status(search_match.match_resp);
-                temp.push(search_match);           
+/// Scanning state for a single file, for every set of search parameters.
+struct FileScan {
+    states: Vec<ScanState>,
+    prefilter: Prefilter,
+    first_record: bool,
+    /// Number of Records read.
+    rows: u64,
+}
 
-                match status {
-                    AllObjectsFound =>{
-                        all_object_flag = true;
-                    } 
-                    EarlySeedExit => {
-                        next_seed += 1;
-                        all_object_flag = false;
-                    }
-                    _ => (),
+impl FileScan {
+    /// Makes a new instance for the start of a file, resolving raw column filters and row
+    /// predicates against its header.
+    fn new(headers: &StringRecord, searches: &mut [SearchParameters]) -> Result<Self> {
+        for search in searches.iter_mut() {
+            for filter in search.row_filters.iter_mut() {
+                filter.resolve(headers)?;
+            }
+            for predicate in search.object_params.iter_mut().filter_map(|p| p.predicate.as_mut()) {
+                predicate.resolve(headers)?;
+            }
+        }
+
+        // Clear any search data from a previous file (as it's a new seed)
+        let states = searches
+            .iter_mut()
+            .map(|search| {
+                search.clear();
+                ScanState::new(search)
+            })
+            .collect();
+
+        Ok(Self {
+            states,
+            prefilter: Prefilter::new(searches),
+            first_record: true,
+            rows: 0,
+        })
+    }
+    /// Returns `true` if the file's 1st Record is already past every seed range (seeds are
+    /// ascending), so the file can be skipped.
+    fn is_past_range(&mut self, seed: u32, searches: &[SearchParameters]) -> bool {
+        let first_record = self.first_record;
+        self.first_record = false;
+
+        first_record && searches.iter().all(|search| seed > search.seed_max)
+    }
+    /// Moves every search in progress on to the next Record, and searches the Record if
+    /// it passed the prefilter.  Returns `false` once no search is in progress.
+    fn next_record(
+        &mut self,
+        seed: u32,
+        relevant: Option<(&RecordInfo, &StringRecord)>,
+        searches: &mut [SearchParameters],
+        results: &mut [Vec<SearchMatch>],
+    ) -> Result<bool> {
+        let mut in_progress = false;
+        self.rows += 1;
+
+        for ((search, state), results) in searches
+            .iter_mut()
+            .zip(self.states.iter_mut())
+            .zip(results.iter_mut())
+        {
+            if !search.is_complete() {
+                in_progress = true;
+                state.next_record(seed, search, results)?;
+
+                if let Some((info, record)) = relevant {
+                    state.search_record(info, record, search)?;
                 }
             }
-        }       
+        }
+
+        Ok(in_progress)
+    }
+    /// Final status check at end of file (in case of matches on final seed in file).
+    fn finish(
+        mut self,
+        searches: &mut [SearchParameters],
+        results: &mut [Vec<SearchMatch>],
+    ) -> Result<SearchStatus> {
+        for ((search, state), results) in searches
+            .iter_mut()
+            .zip(self.states.iter_mut())
+            .zip(results.iter_mut())
+        {
+            if !search.is_complete() {
+                state.finish_seed(search, results)?;
+            }
+            search.rows_scanned += self.rows;
+        }
+
+        match searches.iter().all(|search| search.is_complete()) {
+            false => Ok(SearchStatus::EndOfFile),
+            true => Ok(SearchStatus::EndOfSearch),
+        }
     }
+}
+
+/// Searches specified file using each set of search parameters, and passes results
+/// into the matching list of search results.  Returns `EndOfSearch` once every set of
+/// parameters has found its requested number of matching seeds.
+fn search_file(
+    rdr: &mut CatalogReader,
+    searches: &mut [SearchParameters],
+    results: &mut [Vec<SearchMatch>],
+    progress: &mut Progress,
+    throttle: &mut Throttle,
+) -> Result<SearchStatus> {
+    let mut scan = FileScan::new(rdr.headers()?, searches)?;
+
+    for record_result in rdr.records() {
+        let record = record_result.map_err(row_length_error)?;
+        let seed = record[columns::SEED].parse::<u32>()?;
+        progress.update(seed, searches);
+        throttle.update(seed);
 
-    // Final status check at end of file (in case of matches on final seed in file).
-    if all_object_flag && search.is_valid() {
-        results.extend_from_slice(&temp);
-        search.search_matches += 1;  
+        if scan.is_past_range(seed, searches) {
+            return Ok(SearchStatus::EndOfFile);
+        }
+
+        // Records that no search could match are only used to track seed changes
+        let info = match scan.prefilter.skips(record[columns::VAULT].is_empty()) {
+            true => None,
+            false => Some(RecordInfo::parse(&record, seed)?),
+        };
+        let relevant = info
+            .as_ref()
+            .filter(|info| scan.prefilter.matches(info))
+            .map(|info| (info, &record));
+
+        if !scan.next_record(seed, relevant, searches, results)? {
+            break;
+        }
     }
 
-    match search.is_complete() {
-        false => Ok(EndOfFile),
-        true => Ok(EndOfSearch)
+    scan.finish(searches, results)
+}
+
+/// Searches an in-memory catalog just like `search_file`, but reads the seed, depth, and
+/// category of each Record from integer columns, and only rebuilds the Records that pass
+/// the prefilter.
+fn search_catalog(
+    catalog: &Catalog,
+    searches: &mut [SearchParameters],
+    results: &mut [Vec<SearchMatch>],
+) -> Result<SearchStatus> {
+    let mut scan = FileScan::new(&catalog.headers, searches)?;
+
+    for row in 0..catalog.len() {
+        let seed = catalog.seeds[row];
+
+        if scan.is_past_range(seed, searches) {
+            return Ok(SearchStatus::EndOfFile);
+        }
+
+        let category = catalog.categories[row];
+        let info = RecordInfo {
+            seed,
+            depth: catalog.depths[row],
+            category,
+            category_flags: category.to_flags(),
+        };
+        let relevant =
+            !scan.prefilter.skips(catalog.is_vault_empty(row)) && scan.prefilter.matches(&info);
+        let record = match relevant {
+            true => Some(catalog.record(row)),
+            false => None,
+        };
+
+        if !scan.next_record(
+            seed,
+            record.as_ref().map(|record| (&info, record)),
+            searches,
+            results,
+        )? {
+            break;
+        }
     }
+
+    scan.finish(searches, results)
 }
 
 /// Searches specified Record (line in .csv file) using given search parameters, and 
 /// passes results into given list of search results.  Assumes that CSVs are in proper
 /// format, and as such uses `unwrap` on each Record's fields.
 fn search_record(
-    seed: u32,
-    depth: u8,
+    info: &RecordInfo,
     record: &StringRecord,
     search: &mut SearchParameters,
 ) -> Result<Option<SearchMatch>> {
-    let category = Category::parse(&record[4]).unwrap();
-    let category_flags = category.to_flags();
-  
-    // Return the first matching SearchResult (at most one per Record)
-    for param in search.object_params.iter_mut() {
-        if category_flags.intersects(param.category_flags) && depth <= param.depth {
-            if let Some(result) = search_category(seed, depth, param.category, &record, param)? {                
+    let variant = search.schema.variant.as_deref();
+
+    // Return the first matching SearchResult (at most one per Record), trying the most
+    // selective parameters first
+    for &index in search.param_order.iter() {
+        let param = &mut search.object_params[index];
+        if info.category_flags.intersects(param.category_flags) && info.depth <= param.depth {
+            if let Some(mut result) = search_category(info, record, param, variant)? {
+                // Seeds only exit early for parameters the object logic requires, and not
+                // while counting the seeds each parameter is met in
+                let early_exit = search.logic.requires(index) && !search.param_stats;
+                if result.match_resp == MatchResponse::EarlyExit && !early_exit {
+                    result.match_resp = MatchResponse::DoNothing;
+                }
                 return Ok(Some(result));
             }
         } 
@@ -413,204 +1335,285 @@ fn search_record(
     Ok(None)
 }
 
+/// Checks specified Record (line in .csv file) against the empower constraint, if any:
+/// a caged or shackled ally, or a wand of empowerment (adding its charges).  Returns
+/// `true` if the Record counts toward the constraint.
+fn search_empower(
+    info: &RecordInfo,
+    record: &StringRecord,
+    search: &mut SearchParameters,
+) -> Result<bool> {
+    let empower = match search.empower.as_mut() {
+        Some(empower) if info.depth <= empower.depth => empower,
+        _ => return Ok(false),
+    };
+
+    match info.category {
+        Category::Ally if matches!(&record[columns::ALLY_STATUS], "caged" | "shackled") => {
+            empower.ally_found = true;
+            Ok(true)
+        }
+        Category::Wand if &record[columns::KIND] == "empowerment" => {
+            let charges = record[columns::ENCHANTMENT].parse::<u32>()?
+                * record[columns::QUANTITY].parse::<u32>()?;
+            empower.charges_found = empower.charges_found.saturating_add(charges);
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
 /// Searches specified Record (line in .csv file) for a given Category.  If a match,
 /// updates search results. Assumes that CSVs are in proper format, and as such uses 
 /// `unwrap` on each Record's fields.
 fn search_category(
-    seed: u32,
-    depth: u8,
-    param_category: Category,
+    info: &RecordInfo,
     record: &StringRecord,
     param: &mut ObjectParameter,
+    variant: Option<&DataPack>,
 ) -> Result<Option<SearchMatch>> {
+    if !matches_param(info, record, param, variant)? {
+        return Ok(None);
+    }
+
+    // If a successful match, add SearchResult for given seed and depth
+    let count = record[columns::QUANTITY].parse::<u32>()?;
+    param.add_count(info.depth, count);
+    let pc = param.count;
+    let pc_tgt = param.count_target;
+
+    // NOTE: 'DoNothing' matches still added, but don't count toward 'count target'.
+    // 'AtLeast'  - increments unless count target was already met (a stack of several
+    //              objects may pass it), never exits
+    // 'LessThan' - early exits if >= count target, else do nothing
+    // 'EqualTo'  - early exits if > count target, else do nothing
+    let met_before = pc.saturating_sub(count) >= pc_tgt;
+    let match_type = match (param.count_type, pc < pc_tgt, pc > pc_tgt) {
+        (CountType::AtLeast, _, _) if !met_before => MatchResponse::Increment,
+        (CountType::LessThan, true, _) => MatchResponse::Increment,
+        (CountType::LessThan, false, _) => MatchResponse::EarlyExit,
+        (CountType::EqualTo, _, false) => MatchResponse::Increment,
+        (CountType::EqualTo, _, true) => MatchResponse::EarlyExit,
+        _ => MatchResponse::DoNothing,
+    };
+
+    Ok(Some(SearchMatch::from_record(
+        info.category,
+        match_type,
+        info.seed,
+        info.depth,
+        record,
+    )?))
+}
+
+/// Returns `true` if the Record passes every check of an object parameter.  Checks run
+/// from cheapest to most expensive, stopping at the first that fails:  empty fields,
+/// numbers, then text, then kind lookups (`magic_check`) and row predicates.
+fn matches_param(
+    info: &RecordInfo,
+    record: &StringRecord,
+    param: &ObjectParameter,
+    variant: Option<&DataPack>,
+) -> Result<bool> {
     use Category::*;
 
-    let mut matched = true;
-    let record_category = Category::parse(&record[4]).unwrap();
+    let category = param.category;
+    let record_category = info.category;
+    let vaulted = matches!(
+        category,
+        Weapon | Armor | Charm | Ring | Staff | Wand | Potion | Scroll | Equipment | Item
+    );
+    let enchanted = matches!(
+        category,
+        Weapon | Armor | Charm | Ring | Staff | Wand | Equipment | Item
+    );
+    let runic = matches!(category, Weapon | Armor | Equipment | Item);
 
-    match param_category {
-        Weapon | Armor => {
-            if let Some(kind) = param.kind.as_ref() {
-                matched &= record[5].contains(kind);
-            }
-            if let Some(enchantment) = param.enchantment {
-                let rec_enchantment = record[6].parse::<i8>()?;
+    // Empty fields
+    if let (true, Some(in_vault)) = (vaulted, param.in_vault) {
+        if in_vault == record[columns::VAULT].is_empty() {
+            return Ok(false);
+        }
+    }
+    if runic
+        && param.any_runic
+        && (!matches!(record_category, Armor | Weapon) || record[columns::RUNIC].is_empty())
+    {
+        return Ok(false);
+    }
+    if category == Ally && param.any_mutation && record[columns::MUTATION].is_empty() {
+        return Ok(false);
+    }
 
-                if enchantment >= 0 {
-                    matched &= rec_enchantment >= enchantment;
-                } else {
-                    matched &= rec_enchantment <= enchantment;
-                }
-            }
-            if param.any_runic {
-                matched &= !&record[7].is_empty();
-            } else if let Some(runic) = param.runic.as_ref() {
-                matched &= record[7].contains(runic);
-            }
-            if let Some(in_vault) = param.in_vault.as_ref() {
-                matched &= match (in_vault, record[8].is_empty()) {
-                    (true, true) => false,
-                    (true, false) => true,
-                    (false, true) => true,
-                    (false, false) => false,
-                }
-            }
-            if let Some(magic_type) = param.magic_type.as_ref() {
-                matched &= magic_check(record_category, *magic_type, record)
-            }            
+    // Numbers
+    if let (true, Some(enchantment)) = (enchanted, param.enchantment) {
+        if !matches!(
+            record_category,
+            Armor | Charm | Ring | Staff | Wand | Weapon
+        ) {
+            return Ok(false);
         }
-        Charm | Ring | Staff | Wand => {
-            if let Some(kind) = param.kind.as_ref() {
-                matched &= record[5].contains(kind);
-            }
-            if let Some(enchantment) = param.enchantment {
-                let rec_enchantment = record[6].parse::<i8>()?;
 
-                if enchantment >= 0 {
-                    matched &= rec_enchantment >= enchantment;
-                } else {
-                    matched &= rec_enchantment <= enchantment;
-                }
-            }
-            if let Some(in_vault) = param.in_vault.as_ref() {
-                matched &= match (in_vault, record[8].is_empty()) {
-                    (true, true) => false,
-                    (true, false) => true,
-                    (false, true) => true,
-                    (false, false) => false,
-                }
-            }
-            if let Some(magic_type) = param.magic_type.as_ref() {
-                matched &= magic_check(record_category, *magic_type, record)
-            }                      
+        let rec_enchantment = record[columns::ENCHANTMENT].parse::<i8>()?;
+        let passes = match enchantment >= 0 {
+            true => rec_enchantment >= enchantment,
+            false => rec_enchantment <= enchantment,
+        };
+        if !passes {
+            return Ok(false);
         }
-        Potion | Scroll => {
-            if let Some(kind) = param.kind.as_ref() {
-                matched &= record[5].contains(kind);
-            }
-            if let Some(in_vault) = param.in_vault.as_ref() {
-                matched &= match (in_vault, record[8].is_empty()) {
-                    (true, true) => false,
-                    (true, false) => true,
-                    (false, true) => true,
-                    (false, false) => false,
-                }
+    }
+    if let (Staff | Wand, Some(charges)) = (category, param.max_charges) {
+        if record[columns::ENCHANTMENT].parse::<i16>()? > i16::from(charges) {
+            return Ok(false);
+        }
+    }
+
+    // Text
+    if category == Ally {
+        if param.any_legendary && &record[columns::ALLY_STATUS] != "allied" {
+            return Ok(false);
+        }
+        if let (false, Some(ally_status)) = (param.any_legendary, param.ally_status.as_ref()) {
+            if ally_status != &record[columns::ALLY_STATUS] {
+                return Ok(false);
             }
-            if let Some(magic_type) = param.magic_type.as_ref() {
-                matched &= magic_check(record_category, *magic_type, record)
-            }               
-        }
-        Food | Altar => {
-            if let Some(kind) = param.kind.as_ref() {
-                matched &= record[5].contains(kind);
-            }
-        }
-        Ally => {
-            if let Some(kind) = param.kind.as_ref() {
-                matched &= record[5].contains(kind);
-            }
-            if param.any_legendary {
-                matched &= &record[11] == "allied";
-            } else if let Some(ally_status) = param.ally_status.as_ref() {
-                matched &= ally_status == &record[11];
-            }    
-            if param.any_mutation {
-                matched &= !&record[12].is_empty();
-            } else if let Some(mutation) = param.mutation.as_ref() {
-                matched &= record[12].contains(mutation);
-            }                        
-        }
-        Equipment | Item => {
-            if let Some(enchantment) = param.enchantment {
-                match record_category {
-                    Armor | Charm | Ring | Staff | Wand | Weapon => {
-                        let rec_enchantment = record[6].parse::<i8>()?;
-
-                        if enchantment >= 0 {
-                            matched &= rec_enchantment >= enchantment;
-                        } else {
-                            matched &= rec_enchantment <= enchantment;
-                        }
-                    }
-                    _ => matched = false
-                }                
-            }   
-            if param.any_runic {
-                match record_category {
-                    Armor | Weapon => {
-                        matched &= !&record[7].is_empty();
-                    }
-                    _ => matched = false
-                }                      
-                
-            }                
-            if let Some(in_vault) = param.in_vault.as_ref() {
-                matched &= match (in_vault, record[8].is_empty()) {
-                    (true, true) => false,
-                    (true, false) => true,
-                    (false, true) => true,
-                    (false, false) => false,
-                }
-            }                 
-            if let Some(magic_type) = param.magic_type.as_ref() {
-                matched &= magic_check(record_category, *magic_type, record)
-            }               
         }
-        // Key and Gold don't have any specific parameters to check aside from COUNT
-        _ => (),
+    }
+    if let Some(kind) = param.kind.as_ref() {
+        if !matches!(category, Equipment | Item | Key | Gold | Any)
+            && !record[columns::KIND].contains(kind.as_str())
+        {
+            return Ok(false);
+        }
+    }
+    let runic_checked =
+        matches!(category, Weapon | Armor) && !param.any_runic && param.runic.is_some();
+    if runic_checked && !param.runics().any(|runic| record[columns::RUNIC].contains(runic)) {
+        return Ok(false);
+    }
+    if let (true, false, Some(mutation)) = (
+        category == Ally,
+        param.any_mutation,
+        param.mutation.as_ref(),
+    ) {
+        if !record[columns::MUTATION].contains(mutation.as_str()) {
+            return Ok(false);
+        }
     }
 
-    // If a successful match, add SearchResult for given seed and depth
-    if matched {
-        let count = record[3].parse::<u32>()?;
-        param.count += count;
-        let pc = param.count;
-        let pc_tgt = param.count_target; 
-
-        // NOTE: 'DoNothing' matches still added, but don't count toward 'count target'.
-        // 'AtLeast'  - increments unless count > count target, never exits
-        // 'LessThan' - early exits if >= count target, else do nothing
-        // 'EqualTo'  - early exits if > count target, else do nothing
-        let match_type = match (param.count_type, pc < pc_tgt, pc > pc_tgt) {
-            (CountType::AtLeast, _, false) => MatchResponse::Increment,
-            (CountType::LessThan, true, _) => MatchResponse::Increment,
-            (CountType::LessThan, false, _) => MatchResponse::EarlyExit,
-            (CountType::EqualTo, _, false) => MatchResponse::Increment,
-            (CountType::EqualTo, _, true) => MatchResponse::EarlyExit,            
-            _ => MatchResponse::DoNothing,
+    // Kind lookups and row predicates
+    if let Some(strength) = param.max_strength {
+        let needed = match category {
+            Armor => ArmorKind::parse(&record[columns::KIND]).map(ArmorKind::strength),
+            _ => WeaponKind::parse(&record[columns::KIND]).map(WeaponKind::strength),
         };
-
-        return Ok(Some(
-            SearchMatch::from_record(record_category, match_type, seed, depth, &record))
-        );   
+        if needed.is_none_or(|needed| needed > strength) {
+            return Ok(false);
+        }
+    }
+    if category == Weapon
+        && param.thrown
+        && WeaponKind::parse(&record[columns::KIND]).is_none_or(|kind| !kind.is_thrown())
+    {
+        return Ok(false);
+    }
+    if let (Armor, Some(armor_value)) = (category, param.min_armor_value) {
+        if ArmorKind::parse(&record[columns::KIND])
+            .is_none_or(|kind| kind.armor_value() < armor_value)
+        {
+            return Ok(false);
+        }
+    }
+    if let (Weapon, Some(tier)) = (category, param.min_damage_tier) {
+        if WeaponKind::parse(&record[columns::KIND]).is_none_or(|kind| kind.damage_tier() < tier) {
+            return Ok(false);
+        }
+    }
+    if let (Weapon, Some(two_handed)) = (category, param.two_handed) {
+        if WeaponKind::parse(&record[columns::KIND])
+            .is_none_or(|kind| kind.is_two_handed() != two_handed)
+        {
+            return Ok(false);
+        }
+    }
+    if let (true, Some(magic_type)) = (vaulted, param.magic_type) {
+        if !magic_check(record_category, magic_type, record, variant) {
+            return Ok(false);
+        }
+    }
+    if let Some(predicate) = param.predicate.as_ref() {
+        if !predicate.matches(record) {
+            return Ok(false);
+        }
     }
 
-    Ok(None)
+    Ok(true)
 }
 
-/// Helper function to filter a CSV record by seed and depth.
-#[inline]
-fn bounds_check(r: &StringRecord, s1: u32, s2: u32, d1: u8, d2: u8) -> Result<(bool, u32, u8)> {
-    let seed = r[1].parse::<u32>()?;        
-    let depth = r[2].parse::<u8>()?;   
-    let in_bounds = seed >= s1 
-        && seed <= s2 
-        && depth >= d1 
-        && depth <= d2;
+/// Categories that can have malevolent objects (`--no-malevolent`).
+const MALEVOLENT_CATEGORIES: [Category; 6] = [
+    Category::Armor,
+    Category::Potion,
+    Category::Ring,
+    Category::Scroll,
+    Category::Wand,
+    Category::Weapon,
+];
+
+/// Returns `true` if the Record rejects its seed for the given search (an excluded object
+/// within the exclusion's depth).
+fn is_excluded(info: &RecordInfo, record: &StringRecord, search: &SearchParameters) -> bool {
+    let malevolent = match search.no_malevolent {
+        Some(depth) => info.depth <= depth && is_malevolent(info.category, record, search.schema.variant.as_deref()),
+        None => false,
+    };
+
+    malevolent
+        || search
+            .exclusions
+            .iter()
+            .any(|e| e.matches(info.category_flags, &record[columns::KIND]))
+}
+
+/// Returns `true` if the Record's object is malevolent:  a malevolent potion, scroll, or
+/// wand, negative armor, ring, or weapon, or a negative runic (as set by the variant's
+/// data pack, if any).
+fn is_malevolent(
+    record_category: Category,
+    record: &StringRecord,
+    variant: Option<&DataPack>,
+) -> bool {
+    use Category::*;
+    let cursed = record[columns::ENCHANTMENT].parse::<i8>().is_ok_and(|e| e < 0);
+    let (kind, runic) = (&record[columns::KIND], &record[columns::RUNIC]);
+    let set = |name: &str| variant.and_then(|v| v.malevolence(record_category, name));
 
-    Ok((in_bounds, seed, depth))   
+    match record_category {
+        Armor => cursed || set(runic).unwrap_or_else(|| ArmorRunic::parse(runic).is_some_and(|r| r.is_malevolent())),
+        Weapon => cursed || set(runic).unwrap_or_else(|| WeaponRunic::parse(runic).is_some_and(|r| r.is_malevolent())),
+        Ring => cursed,
+        Potion => set(kind).unwrap_or_else(|| PotionKind::parse(kind).is_some_and(|k| k.is_malevolent())),
+        Scroll => set(kind).unwrap_or_else(|| ScrollKind::parse(kind).is_some_and(|k| k.is_malevolent())),
+        Wand => set(kind).unwrap_or_else(|| WandKind::parse(kind).is_some_and(|k| k.is_malevolent())),
+        _ => false,
+    }
 }
 
-/// Returns true if the object's `MagicType` (benevolent/malevolent) matches.
+/// Returns true if the object's `MagicType` (benevolent/malevolent) matches, as set by the
+/// variant's data pack (if any).
 #[inline]
 fn magic_check(
     record_category: Category, 
     magic_type: MagicType,  
     record: &StringRecord,
+    variant: Option<&DataPack>,
 ) -> bool {  
     use Category::*;
     use MagicType::*;
-    let enchantment = record[6].parse::<i8>().ok();
+    let enchantment = record[columns::ENCHANTMENT].parse::<i8>().ok();
+    let kind = &record[columns::KIND];
+    let set = |name: &str| variant.and_then(|v| v.malevolence(record_category, name));
 
     match record_category {
         Armor | Charm | Ring | Weapon => {
@@ -620,37 +1623,18 @@ fn magic_check(
                 _ => true,
             }
         }
-        Potion => {
-            let is_malevolent = PotionKind::parse(&record[5]).unwrap().is_malevolent();
-            match (magic_type, is_malevolent) {
-                (Malevolent, true) => true,
-                (Benevolent, false) => true,
-                _ => false,
-            }
-        }
-        Scroll => {
-            let is_malevolent = ScrollKind::parse(&record[5]).unwrap().is_malevolent();
-            match (magic_type, is_malevolent) {
-                (Malevolent, true) => true,
-                (Benevolent, false) => true,
-                _ => false,
-            }
-        }
-        Staff => {
-            let is_malevolent = StaffKind::parse(&record[5]).unwrap().is_malevolent();
-            match (magic_type, is_malevolent) {
-                (Malevolent, true) => true,
-                (Benevolent, false) => true,
-                _ => false,
-            }
-        }
-        Wand => {
-            let is_malevolent = WandKind::parse(&record[5]).unwrap().is_malevolent();
-            match (magic_type, is_malevolent) {
-                (Malevolent, true) => true,
-                (Benevolent, false) => true,
-                _ => false,
-            }
+        Potion | Scroll | Staff | Wand => {
+            // A kind the data tables don't have is neither (and can't match)
+            let is_malevolent = set(kind).or_else(|| match record_category {
+                Potion => PotionKind::parse(kind).map(|k| k.is_malevolent()),
+                Scroll => ScrollKind::parse(kind).map(|k| k.is_malevolent()),
+                Staff => StaffKind::parse(kind).map(|k| k.is_malevolent()),
+                _ => WandKind::parse(kind).map(|k| k.is_malevolent()),
+            });
+            matches!(
+                (magic_type, is_malevolent),
+                (Malevolent, Some(true)) | (Benevolent, Some(false))
+            )
         }
         // Ally, Altar, Food, Gold, Key aren't magical, and Records can't be Item/Equipment
         _ => false,
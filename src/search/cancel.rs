@@ -0,0 +1,31 @@
+//! Cancellation of running searches for Brogue Seed Scanner.
+//!
+//! Callers embedding the scanner (a GUI, a server) pass a `CancelToken` to
+//! `Engine::search`, and cancel it from another thread to stop the search (the daemon's
+//! socket does so for `cancel` requests).  The search then returns the matching seeds
+//! found so far.  The seed being scanned when the token is cancelled is dropped, as
+//! it wasn't fully read.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared flag for stopping a search.  Clones (including those of the searches run by
+/// parallel workers) share the flag.
+#[derive(Debug, Default, Clone)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Makes a new token, not yet cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Cancels every search holding this token.
+    #[cfg(feature = "server")]
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+    /// Returns `true` once the token is cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
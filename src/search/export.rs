@@ -0,0 +1,92 @@
+//! Spreadsheet export (`--export`) for Brogue Seed Scanner.
+//!
+//! `--export FILE` writes every matching object of a search as a row of a flat table,
+//! ready to load into a spreadsheet:
+//! ```text
+//! seed,depth,category,kind,enchantment,runic,vault,carried_by
+//! 12345,3,armor,banded mail,2,reflection,,
+//! ```
+//! Files named `*.tsv` are tab-separated, others comma-separated.  Fields with no value
+//! (e.g. the runic of an unrunic weapon) are left empty.  Searches from query files that
+//! export to the same file share it, under a single header row.
+
+use crate::file_handling::{EncodedWriter, OutputEncoding};
+use crate::search::SearchMatch;
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// A matching object, as exported.
+#[derive(Debug, Serialize)]
+struct ExportRow {
+    seed: u32,
+    depth: u8,
+    category: String,
+    kind: String,
+    enchantment: Option<i8>,
+    runic: Option<String>,
+    vault: Option<u8>,
+    carried_by: Option<String>,
+}
+
+impl ExportRow {
+    fn from_match(m: &SearchMatch) -> Self {
+        Self {
+            seed: m.seed,
+            depth: m.depth,
+            category: m.object.category().to_string(),
+            kind: m.object.kind_name(),
+            enchantment: m.object.enchantment(),
+            runic: m.object.runic_name(),
+            vault: m.vault,
+            carried_by: m.carried_by.map(|c| c.to_string()),
+        }
+    }
+}
+
+/// Returns `true` if an export file is named as tab-separated (`*.tsv`).
+pub fn is_tsv(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("tsv"))
+}
+
+/// Writes the matches of one or more searches to an export file, and returns the number
+/// of rows written.
+pub fn write_export<'a, I>(path: &Path, encoding: OutputEncoding, lists: I) -> Result<usize>
+where
+    I: IntoIterator<Item = &'a Vec<SearchMatch>>,
+{
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("could not create export file {:?}", path))?;
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(if is_tsv(path) { b'\t' } else { b',' })
+        .from_writer(EncodedWriter::new(BufWriter::new(file), encoding)?);
+    let mut count = 0;
+
+    for matches in lists {
+        for m in matches {
+            wtr.serialize(ExportRow::from_match(m))?;
+            count += 1;
+        }
+    }
+
+    // With no rows, serde never got to write the header
+    if count == 0 {
+        wtr.write_record([
+            "seed",
+            "depth",
+            "category",
+            "kind",
+            "enchantment",
+            "runic",
+            "vault",
+            "carried_by",
+        ])?;
+    }
+    wtr.into_inner()
+        .map_err(|e| anyhow!("{}", e.error()))?
+        .into_inner()?
+        .flush()?;
+
+    Ok(count)
+}
@@ -0,0 +1,384 @@
+//! Scoring expressions (`--score-expr`) for Brogue Seed Scanner.
+//!
+//! A scoring expression replaces the default `--grade` score with one of the user's own,
+//! computed for each matching seed from its matching objects.  For example:
+//!
+//! - `objects + 2 * enchant`:  weighs enchant potential twice as much as usual
+//! - `count("enchanting") * 3 + gold / 1000`:  scrolls of enchanting, then gold
+//! - `base + (depth("war axe") <= 3) * 5`:  the usual score, plus 5 for an early war axe
+//!
+//! Values are numbers.  The final value is rounded to the nearest whole score.
+//!
+//! - Variables:  `base` (the default score), `objects`, `enchant` (enchant potential),
+//!   `allies` (ally quality, see `--ally-weights`), `optionals` (optional objects met),
+//!   and `gold` (gold in the depth range)
+//! - `count("TERM")`:  quantity of matching objects whose category is TERM, or whose
+//!   kind, runic, or mutation contains TERM
+//! - `depth("TERM")`:  earliest depth of such an object (100 if there's none)
+//! - `min(A, B)` and `max(A, B)`
+//! - Operators, from lowest precedence:  comparisons (`<`, `<=`, `>`, `>=`, `==`, `!=`,
+//!   which are 1 if true and 0 if false), `+` and `-`, then `*` and `/` (division by 0
+//!   is 0), and unary `-`.  Parentheses group as usual.
+
+use crate::search::{SearchMatch, SeedScore};
+use anyhow::{anyhow, Result};
+
+/// Depth of an object that isn't found, for `depth("TERM")`.
+const NOT_FOUND_DEPTH: f64 = 100.0;
+
+/// Per-seed values a scoring expression can use.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variable {
+    Base,
+    Objects,
+    Enchant,
+    Allies,
+    Optionals,
+    Gold,
+}
+
+impl Variable {
+    /// Parses a variable by name.
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "base" => Some(Self::Base),
+            "objects" => Some(Self::Objects),
+            "enchant" => Some(Self::Enchant),
+            "allies" => Some(Self::Allies),
+            "optionals" => Some(Self::Optionals),
+            "gold" => Some(Self::Gold),
+            _ => None,
+        }
+    }
+}
+
+/// Binary operators, by ascending precedence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl BinaryOp {
+    /// Applies the operator.
+    fn apply(self, a: f64, b: f64) -> f64 {
+        let truth = |value: bool| if value { 1.0 } else { 0.0 };
+
+        match self {
+            Self::Less => truth(a < b),
+            Self::LessEqual => truth(a <= b),
+            Self::Greater => truth(a > b),
+            Self::GreaterEqual => truth(a >= b),
+            Self::Equal => truth(a == b),
+            Self::NotEqual => truth(a != b),
+            Self::Add => a + b,
+            Self::Subtract => a - b,
+            Self::Multiply => a * b,
+            Self::Divide if b == 0.0 => 0.0,
+            Self::Divide => a / b,
+        }
+    }
+}
+
+/// A parsed scoring expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScoreExpr {
+    Number(f64),
+    Variable(Variable),
+    Count(String),
+    Depth(String),
+    Min(Box<ScoreExpr>, Box<ScoreExpr>),
+    Max(Box<ScoreExpr>, Box<ScoreExpr>),
+    Negate(Box<ScoreExpr>),
+    Binary(BinaryOp, Box<ScoreExpr>, Box<ScoreExpr>),
+}
+
+impl ScoreExpr {
+    /// Parses a scoring expression.
+    pub fn parse(text: &str) -> Result<Self> {
+        let tokens = tokenize(text).map_err(|e| anyhow!("--score-expr '{}': {}", text, e))?;
+        let mut parser = Parser { tokens, pos: 0 };
+
+        let expr = parser.comparison().and_then(|expr| match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(anyhow!("unexpected {}", token)),
+        });
+
+        expr.map_err(|e| anyhow!("--score-expr '{}': {}", text, e))
+    }
+    /// Evaluates the expression for a seed, given its default score and matches.
+    pub fn eval(&self, score: &SeedScore, matches: &[SearchMatch]) -> f64 {
+        match self {
+            Self::Number(value) => *value,
+            Self::Variable(variable) => match variable {
+                Variable::Base => score.score as f64,
+                Variable::Objects => score.objects as f64,
+                Variable::Enchant => score.enchant_potential as f64,
+                Variable::Allies => score.ally_quality as f64,
+                Variable::Optionals => score.optionals as f64,
+                Variable::Gold => score.gold as f64,
+            },
+            Self::Count(term) => matches
+                .iter()
+                .filter(|m| is_term_match(m, term))
+                .map(|m| m.quantity as f64)
+                .sum(),
+            Self::Depth(term) => matches
+                .iter()
+                .filter(|m| is_term_match(m, term))
+                .map(|m| m.depth as f64)
+                .fold(NOT_FOUND_DEPTH, f64::min),
+            Self::Min(a, b) => a.eval(score, matches).min(b.eval(score, matches)),
+            Self::Max(a, b) => a.eval(score, matches).max(b.eval(score, matches)),
+            Self::Negate(a) => -a.eval(score, matches),
+            Self::Binary(op, a, b) => op.apply(a.eval(score, matches), b.eval(score, matches)),
+        }
+    }
+}
+
+/// Returns `true` if a match is of the category named by a term, or its kind, runic, or
+/// mutation contains the term.
+fn is_term_match(m: &SearchMatch, term: &str) -> bool {
+    let object = &m.object;
+
+    object.category().to_string() == term
+        || object.kind_name().contains(term)
+        || object.runic_name().is_some_and(|runic| runic.contains(term))
+        || object
+            .mutation()
+            .is_some_and(|mutation| mutation.to_string().contains(term))
+}
+
+/// Tokens of a scoring expression or row predicate (`--filter`).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Token {
+    Number(f64),
+    Name(String),
+    Text(String),
+    Op(&'static str),
+    Open,
+    Close,
+    Comma,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "number {}", value),
+            Token::Name(name) => write!(f, "'{}'", name),
+            Token::Text(text) => write!(f, "\"{}\"", text),
+            Token::Op(op) => write!(f, "'{}'", op),
+            Token::Open => write!(f, "'('"),
+            Token::Close => write!(f, "')'"),
+            Token::Comma => write!(f, "','"),
+        }
+    }
+}
+
+/// Operator symbols, longest first.
+const OPERATORS: [&str; 15] = [
+    "<=", ">=", "==", "!=", "&&", "||", "<", ">", "=", "!", "~", "+", "-", "*", "/",
+];
+
+/// Splits a scoring expression or row predicate into tokens.  Text may be quoted with
+/// either `"` or `'`.
+pub(super) fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_ascii_digit() || c == '.' {
+            let len = rest
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(rest.len());
+            let value = rest[..len]
+                .parse::<f64>()
+                .map_err(|_| anyhow!("invalid number '{}'", &rest[..len]))?;
+            tokens.push(Token::Number(value));
+            len
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            tokens.push(Token::Name(rest[..len].to_string()));
+            len
+        } else if c == '"' || c == '\'' {
+            let end = rest[1..].find(c).ok_or_else(|| anyhow!("unterminated string"))?;
+            tokens.push(Token::Text(rest[1..end + 1].to_lowercase()));
+            end + 2
+        } else if c == '(' || c == ')' || c == ',' {
+            tokens.push(match c {
+                '(' => Token::Open,
+                ')' => Token::Close,
+                _ => Token::Comma,
+            });
+            1
+        } else {
+            match OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+                Some(op) => {
+                    tokens.push(Token::Op(op));
+                    op.len()
+                }
+                None => return Err(anyhow!("unexpected '{}'", c)),
+            }
+        };
+        rest = rest[len..].trim_start();
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser over the tokens of a scoring expression.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    /// Returns the next token, without consuming it.
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+    /// Consumes and returns the next token.
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| anyhow!("unexpected end"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+    /// Consumes the next token, which must be the given one.
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next()? {
+            token if token == expected => Ok(()),
+            token => Err(anyhow!("expected {}, found {}", expected, token)),
+        }
+    }
+    /// Consumes the next token if it's one of the given operators.
+    fn operator(&mut self, ops: &[(&str, BinaryOp)]) -> Option<BinaryOp> {
+        let op = match self.peek() {
+            Some(Token::Op(symbol)) => ops.iter().find(|(s, _)| s == symbol).map(|(_, op)| *op),
+            _ => None,
+        };
+        if op.is_some() {
+            self.pos += 1;
+        }
+        op
+    }
+    /// Parses a comparison (lowest precedence).
+    fn comparison(&mut self) -> Result<ScoreExpr> {
+        use BinaryOp::*;
+        const OPS: [(&str, BinaryOp); 6] = [
+            ("<", Less),
+            ("<=", LessEqual),
+            (">", Greater),
+            (">=", GreaterEqual),
+            ("==", Equal),
+            ("!=", NotEqual),
+        ];
+
+        let mut expr = self.sum()?;
+        while let Some(op) = self.operator(&OPS) {
+            expr = ScoreExpr::Binary(op, Box::new(expr), Box::new(self.sum()?));
+        }
+
+        Ok(expr)
+    }
+    /// Parses a sum or difference.
+    fn sum(&mut self) -> Result<ScoreExpr> {
+        const OPS: [(&str, BinaryOp); 2] = [("+", BinaryOp::Add), ("-", BinaryOp::Subtract)];
+
+        let mut expr = self.product()?;
+        while let Some(op) = self.operator(&OPS) {
+            expr = ScoreExpr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+
+        Ok(expr)
+    }
+    /// Parses a product or quotient.
+    fn product(&mut self) -> Result<ScoreExpr> {
+        const OPS: [(&str, BinaryOp); 2] = [("*", BinaryOp::Multiply), ("/", BinaryOp::Divide)];
+
+        let mut expr = self.unary()?;
+        while let Some(op) = self.operator(&OPS) {
+            expr = ScoreExpr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+
+        Ok(expr)
+    }
+    /// Parses a negated value, or a value.
+    fn unary(&mut self) -> Result<ScoreExpr> {
+        match self.peek() {
+            Some(Token::Op("-")) => {
+                self.pos += 1;
+                Ok(ScoreExpr::Negate(Box::new(self.unary()?)))
+            }
+            _ => self.value(),
+        }
+    }
+    /// Parses a number, variable, function call, or parenthesized expression.
+    fn value(&mut self) -> Result<ScoreExpr> {
+        match self.next()? {
+            Token::Number(value) => Ok(ScoreExpr::Number(value)),
+            Token::Open => {
+                let expr = self.comparison()?;
+                self.expect(Token::Close)?;
+                Ok(expr)
+            }
+            Token::Name(name) if self.peek() == Some(&Token::Open) => {
+                self.pos += 1;
+                let expr = self.call(&name)?;
+                self.expect(Token::Close)?;
+                Ok(expr)
+            }
+            Token::Name(name) => match Variable::parse(&name) {
+                Some(variable) => Ok(ScoreExpr::Variable(variable)),
+                None => Err(anyhow!(
+                    "unknown variable '{}' (expected base, objects, enchant, allies, optionals, or gold)",
+                    name
+                )),
+            },
+            token => Err(anyhow!("unexpected {}", token)),
+        }
+    }
+    /// Parses the arguments of a function call (after its opening parenthesis).
+    fn call(&mut self, name: &str) -> Result<ScoreExpr> {
+        match name {
+            "count" | "depth" => {
+                let term = match self.next()? {
+                    // An empty term would be contained in every object.
+                    Token::Text(term) if term.trim().is_empty() => return Err(anyhow!("{}() takes a non-empty term", name)),
+                    Token::Text(term) => term,
+                    token => return Err(anyhow!("{}() takes a quoted term, found {}", name, token)),
+                };
+                match name {
+                    "count" => Ok(ScoreExpr::Count(term)),
+                    _ => Ok(ScoreExpr::Depth(term)),
+                }
+            }
+            "min" | "max" => {
+                let a = Box::new(self.comparison()?);
+                self.expect(Token::Comma)?;
+                let b = Box::new(self.comparison()?);
+                match name {
+                    "min" => Ok(ScoreExpr::Min(a, b)),
+                    _ => Ok(ScoreExpr::Max(a, b)),
+                }
+            }
+            _ => Err(anyhow!(
+                "unknown function '{}' (expected count, depth, min, or max)",
+                name
+            )),
+        }
+    }
+}
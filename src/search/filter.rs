@@ -0,0 +1,224 @@
+//! Raw column filters (`--where`) for Brogue Seed Scanner.
+//!
+//! A raw filter compares any catalog column, by header name or zero-based index, for
+//! columns the typed search parameters don't cover yet (new Brogue versions, modded
+//! builds).  Filters take the form `col:COLUMN OP VALUE`, e.g.:
+//!
+//! - `col:opens=3`:  the `opens_vault_number` column is exactly "3"
+//! - `col:9>=2`:  column 9 is 2 or more
+//! - `col:carried_by~goblin`:  the `carried_by_monster_name` column contains "goblin"
+//!
+//! Column names may be partial, as long as they match only one header (headers starting
+//! with the name are preferred, e.g. `vault` is `vault_number`).  Operators are
+//! `=`, `!=`, `~` (contains), `<`, `<=`, `>`, and `>=`.  Comparisons are numeric when
+//! both sides are numbers, otherwise text, except that an empty or text cell is never
+//! `<`, `<=`, `>`, or `>=` a number.  Only objects whose Record passes every filter
+//! can match.
+
+use anyhow::{anyhow, Result};
+use csv::StringRecord;
+
+/// Error for a filter column that can't be found in a catalog's header.  Unlike other
+/// errors in a catalog file, this ends the search (every file would fail the same way).
+#[derive(Debug)]
+pub struct ColumnError(String);
+
+impl std::fmt::Display for ColumnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ColumnError {}
+
+/// Comparison operators for raw column filters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterOp {
+    Equal,
+    NotEqual,
+    Contains,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+impl FilterOp {
+    /// Operators by symbol.  Two-character symbols come first so that they're matched
+    /// before their one-character prefixes.
+    const SYMBOLS: [(&'static str, FilterOp); 7] = [
+        ("!=", FilterOp::NotEqual),
+        ("<=", FilterOp::LessEqual),
+        (">=", FilterOp::GreaterEqual),
+        ("=", FilterOp::Equal),
+        ("~", FilterOp::Contains),
+        ("<", FilterOp::Less),
+        (">", FilterOp::Greater),
+    ];
+}
+
+impl std::fmt::Display for FilterOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (symbol, op) in FilterOp::SYMBOLS.iter() {
+            if op == self {
+                return write!(f, "{}", symbol);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A catalog column, given by header name (partial match allowed) or index.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    Name(String),
+    Index(usize),
+}
+
+impl std::fmt::Display for Column {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Column::Name(name) => write!(f, "{}", name),
+            Column::Index(index) => write!(f, "{}", index),
+        }
+    }
+}
+
+/// A raw filter on a single catalog column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowFilter {
+    pub column: Column,
+    pub op: FilterOp,
+    pub value: String,
+    /// Column index, resolved against the header of each catalog file.
+    index: Option<usize>,
+}
+
+impl RowFilter {
+    /// Parses a filter in the form `col:COLUMN OP VALUE` (the `col:` prefix is optional).
+    pub fn parse(text: &str) -> Result<Self> {
+        let body = text.trim();
+        let body = body.strip_prefix("col:").unwrap_or(body);
+
+        // Operator is the first operator symbol found (longest symbol at that position)
+        let found = FilterOp::SYMBOLS
+            .iter()
+            .filter_map(|(symbol, op)| body.find(symbol).map(|pos| (pos, *symbol, *op)))
+            .min_by_key(|(pos, symbol, _)| (*pos, std::cmp::Reverse(symbol.len())));
+
+        let (pos, symbol, op) = match found {
+            Some(found) => found,
+            None => return Err(anyhow!(
+                "--where filter '{}' has no operator (=, !=, ~, <, <=, >, >=)", text
+            )),
+        };
+
+        let column = body[..pos].trim();
+        let value = body[pos + symbol.len()..].trim().to_string();
+
+        if column.is_empty() {
+            return Err(anyhow!("--where filter '{}' has no column", text));
+        }
+
+        Ok(Self::new(column, op, value))
+    }
+    /// Makes a new filter comparing a column (by header name or index) to a value.
+    pub fn new(column: &str, op: FilterOp, value: String) -> Self {
+        let column = match column.parse::<usize>() {
+            Ok(index) => Column::Index(index),
+            Err(_) => Column::Name(column.to_lowercase()),
+        };
+
+        Self {
+            column,
+            op,
+            value,
+            index: None,
+        }
+    }
+    /// Resolves the column index against the header of a catalog file.
+    pub fn resolve(&mut self, headers: &StringRecord) -> Result<()> {
+        let index = match &self.column {
+            Column::Index(index) if *index < headers.len() => *index,
+            Column::Index(index) => {
+                return Err(ColumnError(format!(
+                    "filter column {} is out of range (catalog has {} columns)",
+                    index, headers.len()
+                )).into())
+            }
+            Column::Name(name) => {
+                match headers.iter().position(|h| h == name) {
+                    Some(index) => index,
+                    None => {
+                        // Partial match:  prefer headers starting with the name
+                        let find = |check: &dyn Fn(&str) -> bool| -> Vec<usize> {
+                            headers
+                                .iter()
+                                .enumerate()
+                                .filter(|(_, h)| check(h))
+                                .map(|(i, _)| i)
+                                .collect()
+                        };
+                        let mut partial = find(&|h| h.starts_with(name.as_str()));
+
+                        if partial.is_empty() {
+                            partial = find(&|h| h.contains(name.as_str()));
+                        }
+
+                        match partial.as_slice() {
+                            [index] => *index,
+                            [] => return Err(ColumnError(format!(
+                                "filter column '{}' not found", name
+                            )).into()),
+                            _ => return Err(ColumnError(format!(
+                                "filter column '{}' matches more than one header", name
+                            )).into()),
+                        }
+                    }
+                }
+            }
+        };
+
+        self.index = Some(index);
+
+        Ok(())
+    }
+    /// Returns `true` if the Record passes the filter.  The filter must be resolved.
+    pub fn matches(&self, record: &StringRecord) -> bool {
+        let field = match self.index.and_then(|index| record.get(index)) {
+            Some(field) => field,
+            None => return false,
+        };
+
+        let numbers = match (field.parse::<f64>(), self.value.parse::<f64>()) {
+            (Ok(a), Ok(b)) => Some((a, b)),
+            // Ordering an empty or text cell against a number compares nothing
+            (Err(_), Ok(_)) if !matches!(self.op, FilterOp::Equal | FilterOp::NotEqual | FilterOp::Contains) => {
+                return false
+            }
+            _ => None,
+        };
+
+        match (self.op, numbers) {
+            (FilterOp::Contains, _) => field.contains(self.value.as_str()),
+            (FilterOp::Equal, Some((a, b))) => a == b,
+            (FilterOp::Equal, None) => field == self.value,
+            (FilterOp::NotEqual, Some((a, b))) => a != b,
+            (FilterOp::NotEqual, None) => field != self.value,
+            (FilterOp::Less, Some((a, b))) => a < b,
+            (FilterOp::LessEqual, Some((a, b))) => a <= b,
+            (FilterOp::Greater, Some((a, b))) => a > b,
+            (FilterOp::GreaterEqual, Some((a, b))) => a >= b,
+            (FilterOp::Less, None) => field < self.value.as_str(),
+            (FilterOp::LessEqual, None) => field <= self.value.as_str(),
+            (FilterOp::Greater, None) => field > self.value.as_str(),
+            (FilterOp::GreaterEqual, None) => field >= self.value.as_str(),
+        }
+    }
+}
+
+impl std::fmt::Display for RowFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "col:{}{}{}", self.column, self.op, self.value)
+    }
+}
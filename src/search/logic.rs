@@ -0,0 +1,224 @@
+//! Object logic (`--logic`) for Brogue Seed Scanner.
+//!
+//! By default, a seed matches only if every object parameter (that isn't optional) is met.
+//! `--logic` combines the object parameters into any boolean tree instead, using the same
+//! 1-based object numbers as `--same-depth` (the order listed by the search summary).  For
+//! example, with `-a banded -p life -w "war axe"` (objects 1, 2, and 3):
+//!
+//! - `1 || 2 || 3`:  any of the three
+//! - `(1 || 3) && !2`:  banded mail or a war axe, and no potion of life
+//!
+//! Conditions combine with `!`, `&&`, and `||` (from highest precedence), and parentheses.
+//! Every object must be used, except optional objects (which can't be) and objects of a
+//! `--total` (which may be).  The logic is
+//! compiled into a tree of parameter groups, which is checked once per seed.
+
+use crate::search::expr::{tokenize, Token};
+use crate::search::params::ObjectParameter;
+use anyhow::{anyhow, Result};
+
+/// Tree of object parameters (by zero-based index) that must be met for a seed to match.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamTree {
+    /// A single object parameter.
+    Param(usize),
+    /// Every group must be met.
+    All(Vec<ParamTree>),
+    /// At least one group must be met.
+    Any(Vec<ParamTree>),
+    /// The group must not be met.
+    Not(Box<ParamTree>),
+}
+
+impl ParamTree {
+    /// Returns the default tree:  every object parameter that isn't optional must be met.
+    pub(crate) fn every(params: &[ObjectParameter]) -> Self {
+        Self::All(
+            (0..params.len())
+                .filter(|i| !params[*i].optional && !params[*i].totalled)
+                .map(Self::Param)
+                .collect(),
+        )
+    }
+    /// Parses object logic over the given object parameters.
+    pub(crate) fn parse(text: &str, params: &[ObjectParameter]) -> Result<Self> {
+        let parse = || -> Result<ParamTree> {
+            let mut parser = Parser {
+                tokens: tokenize(text)?,
+                pos: 0,
+                param_count: params.len(),
+            };
+            let tree = parser.or()?;
+
+            if let Some(token) = parser.tokens.get(parser.pos) {
+                return Err(anyhow!("unexpected {}", token));
+            }
+            for (i, param) in params.iter().enumerate() {
+                match (param.optional, tree.uses(i)) {
+                    (false, false) if !param.totalled => return Err(anyhow!("object {} isn't used", i + 1)),
+                    (true, true) => return Err(anyhow!("object {} is optional", i + 1)),
+                    _ => (),
+                }
+            }
+
+            Ok(tree)
+        };
+
+        parse().map_err(|e| anyhow!("--logic '{}': {}", text, e))
+    }
+    /// Returns `true` if the tree is met by the object parameters' counts this seed.
+    pub(crate) fn is_met(&self, params: &[ObjectParameter]) -> bool {
+        match self {
+            Self::Param(index) => params[*index].is_valid(),
+            Self::All(groups) => groups.iter().all(|g| g.is_met(params)),
+            Self::Any(groups) => groups.iter().any(|g| g.is_met(params)),
+            Self::Not(group) => !group.is_met(params),
+        }
+    }
+    /// Returns `true` if the object parameter must be met for any seed to match, so a
+    /// seed can be skipped as soon as the parameter can no longer be met.
+    pub(crate) fn requires(&self, index: usize) -> bool {
+        match self {
+            Self::Param(i) => *i == index,
+            Self::All(groups) => groups.iter().any(|g| g.requires(index)),
+            _ => false,
+        }
+    }
+    /// Returns `true` if every object parameter in the tree must be met (as by default).
+    pub(crate) fn is_flat(&self) -> bool {
+        match self {
+            Self::Param(_) => true,
+            Self::All(groups) => groups.iter().all(|g| matches!(g, Self::Param(_))),
+            _ => false,
+        }
+    }
+    /// Returns `true` if the object parameter appears anywhere in the tree.
+    fn uses(&self, index: usize) -> bool {
+        match self {
+            Self::Param(i) => *i == index,
+            Self::All(groups) | Self::Any(groups) => groups.iter().any(|g| g.uses(index)),
+            Self::Not(group) => group.uses(index),
+        }
+    }
+}
+
+impl std::fmt::Display for ParamTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |f: &mut std::fmt::Formatter<'_>, groups: &[ParamTree], op: &str| {
+            for (i, group) in groups.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", op)?;
+                }
+                match group {
+                    Self::All(_) | Self::Any(_) => write!(f, "({})", group)?,
+                    _ => write!(f, "{}", group)?,
+                }
+            }
+            Ok(())
+        };
+
+        match self {
+            Self::Param(index) => write!(f, "{}", index + 1),
+            Self::All(groups) => join(f, groups, "&&"),
+            Self::Any(groups) => join(f, groups, "||"),
+            Self::Not(group) => match group.as_ref() {
+                Self::All(_) | Self::Any(_) => write!(f, "!({})", group),
+                _ => write!(f, "!{}", group),
+            },
+        }
+    }
+}
+
+/// Recursive descent parser over the tokens of object logic.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    param_count: usize,
+}
+
+impl Parser {
+    /// Consumes and returns the next token.
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| anyhow!("unexpected end"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+    /// Consumes the next token if it's the given operator.
+    fn operator(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.tokens.get(self.pos), Some(Token::Op(op)) if *op == symbol);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+    /// Parses groups joined by `||` (lowest precedence).
+    fn or(&mut self) -> Result<ParamTree> {
+        let mut groups = vec![self.and()?];
+        while self.operator("||") {
+            groups.push(self.and()?);
+        }
+
+        Ok(flatten(groups, ParamTree::Any))
+    }
+    /// Parses groups joined by `&&`.
+    fn and(&mut self) -> Result<ParamTree> {
+        let mut groups = vec![self.not()?];
+        while self.operator("&&") {
+            groups.push(self.not()?);
+        }
+
+        Ok(flatten(groups, ParamTree::All))
+    }
+    /// Parses a negated group, or a group.
+    fn not(&mut self) -> Result<ParamTree> {
+        match self.operator("!") {
+            true => Ok(ParamTree::Not(Box::new(self.not()?))),
+            false => self.group(),
+        }
+    }
+    /// Parses an object number, or a parenthesized group.
+    fn group(&mut self) -> Result<ParamTree> {
+        match self.next()? {
+            Token::Open => {
+                let tree = self.or()?;
+                match self.next()? {
+                    Token::Close => Ok(tree),
+                    token => Err(anyhow!("expected ')', found {}", token)),
+                }
+            }
+            Token::Number(n) if n.fract() == 0.0 && n >= 1.0 && n <= self.param_count as f64 => {
+                Ok(ParamTree::Param(n as usize - 1))
+            }
+            token => Err(anyhow!(
+                "expected an object number (1 to {}), found {}",
+                self.param_count,
+                token
+            )),
+        }
+    }
+}
+
+/// Joins groups with the same operator into a single group, merging nested groups of the
+/// same kind (`1 && (2 && 3)` is `1 && 2 && 3`).
+fn flatten(groups: Vec<ParamTree>, make: fn(Vec<ParamTree>) -> ParamTree) -> ParamTree {
+    if groups.len() == 1 {
+        return groups.into_iter().next().unwrap();
+    }
+
+    let mut flat = Vec::with_capacity(groups.len());
+
+    for group in groups {
+        match (make(Vec::new()), group) {
+            (ParamTree::All(_), ParamTree::All(inner)) | (ParamTree::Any(_), ParamTree::Any(inner)) => {
+                flat.extend(inner)
+            }
+            (_, group) => flat.push(group),
+        }
+    }
+
+    make(flat)
+}
@@ -2,16 +2,31 @@
 
 use anyhow::{anyhow, Result};
 use crate::bitflags::BitFlags16;
-use crate::file_handling::{get_brogue_csv_paths, FileFormat};
+use crate::color::ColorMode;
+use crate::columns::{CatalogSchema, CONFIG_FILE};
+use crate::variant::DataPack;
+use crate::file_handling::{expand_path, get_brogue_csv_paths, seed_range_from_name, shuffle_paths, FileFormat, OutputEncoding};
 use crate::objects::{Category, MagicType};
-use crate::search::{SearchStatus, CountType, MatchResponse};
+use crate::rarity::RARITY_CACHE_FILE;
+use crate::search::{SearchStatus, AllyWeights, BestBy, CancelToken, SeedSink, ScoreExpr, CountType, GradeThresholds, MatchResponse, MatchTemplate, OutputMode};
+use crate::search::filter::RowFilter;
+use crate::search::logic::ParamTree;
+use crate::search::predicate::Predicate;
+use crate::search::schema::{parse_format_version, FORMAT_VERSION};
+use crate::search::seed_list::load_seed_list;
+use crate::search::rescue::AllyRescue;
+use crate::search::vaults::SeedVault;
 use crate::search::parse::*;
+use crate::upload::Endpoint;
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::env::current_dir;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 /// Specific search parameter for an object category (armor, weapon, etc.).
 /// Checked against each line of a csv record.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ObjectParameter {
     /// Current count matched for the active seed
     pub(crate) count: u32,
@@ -27,12 +42,27 @@ pub struct ObjectParameter {
     pub(crate) kind: Option<String>,
     /// Maximum depth at which to search for object (specific to this object)
     pub(crate) depth: u8,      
+    /// Counts that must be met by earlier depths (`N@dD` terms), as (count, depth).
+    pub(crate) stages: Vec<(u32, u8)>,
     /// Enchantment level.
     pub(crate) enchantment: Option<i8>,
-    /// Weapon or Armor runic.
+    /// Weapon or Armor runic (or alternatives, separated by `|`).
     pub(crate) runic: Option<String>,
     /// Special case where any (non-empty) runic is valid - when "runic" term used.
     pub(crate) any_runic: bool,
+    /// Most strength the armor or weapon may need (`str<=N` term).
+    pub(crate) max_strength: Option<u8>,
+    /// Lowest base armor value of the armor (`av>=N` term).
+    pub(crate) min_armor_value: Option<u8>,
+    /// Most charges of the staff or wand (`N-` term).
+    pub(crate) max_charges: Option<u8>,
+    /// Whether the weapon is two-handed (`2h` term) or one-handed (`1h` term), by its
+    /// strength requirement (see `WeaponKind::is_two_handed`).
+    pub(crate) two_handed: Option<bool>,
+    /// Special case where any thrown weapon is valid - when "thrown" term used.
+    pub(crate) thrown: bool,
+    /// Lowest damage tier (1 to 3) of the weapon (`dmg>=tierN` term).
+    pub(crate) min_damage_tier: Option<u8>,
     /// Ally status.
     pub(crate) ally_status: Option<String>,
     /// Special case for legendary allies - when "legendary" term is used.
@@ -45,85 +75,246 @@ pub struct ObjectParameter {
     pub(crate) in_vault: Option<bool>,
     /// Whether Potion / Scroll / Staff / Wand is benevolent or malevolent.
     pub(crate) magic_type: Option<MagicType>,    
+    /// Row predicate the record must pass (`--filter`).
+    pub(crate) predicate: Option<Predicate>,
+    /// Whether the parameter is optional (`?` or `opt:` prefix):  it doesn't need to be
+    /// met for a seed to match, but adds to the seed's score.
+    pub(crate) optional: bool,
+    /// Whether the parameter only counts toward a combined total (`--total`), instead of
+    /// being met on its own.
+    pub(crate) totalled: bool,
+    /// Number of seeds scanned so far where the parameter was met on its own.  Seeds cut
+    /// short by an exclusion may under-count it, as may seeds cut short by a 'less than' or
+    /// 'exactly' count exceeded (unless `--param-stats` is set).
+    pub(crate) seeds_met: u64,
+    /// Quantity matched at each depth this seed, in ascending depth order.  Used by
+    /// constraints linking parameters by depth (e.g. `--same-depth`).
+    pub(crate) depth_counts: Vec<(u8, u32)>,
 }
 
 impl ObjectParameter {
     /// Makes a new search parameter from a `PrepParams` struct.
+    /// Without a COUNT or DEPTH, the largest stage count and depth are used.
     pub fn from_prep(category: Category, prep: &mut PrepParams) -> Self {
+        let stage_max = prep
+            .stages
+            .iter()
+            .copied()
+            .reduce(|(c1, d1), (c2, d2)| (c1.max(c2), d1.max(d2)));
+
         Self {
             count: 0,
-            count_target: prep.count.unwrap_or(1),
+            count_target: prep.count.or(stage_max.map(|(count, _)| count)).unwrap_or(1),
             count_type: prep.count_type,
             category,
             category_flags: category.to_flags(),
             kind: prep.kind.take(),
-            depth: prep.depth.unwrap_or(40),
+            depth: prep.depth.or(stage_max.map(|(_, depth)| depth)).unwrap_or(40),
+            stages: std::mem::take(&mut prep.stages),
             enchantment: prep.enchantment,
             runic: prep.runic.take(),
             any_runic: prep.any_runic,
+            max_strength: prep.max_strength.take(),
+            min_armor_value: prep.min_armor_value.take(),
+            max_charges: prep.max_charges.take(),
+            two_handed: prep.two_handed.take(),
+            thrown: prep.thrown,
+            min_damage_tier: prep.min_damage_tier.take(),
             ally_status: prep.ally_status.take(),
             any_legendary: prep.any_legendary,
             mutation: prep.mutation.take(),
             any_mutation: prep.any_mutation,
             in_vault: prep.in_vault.take(),
             magic_type: prep.magic_type.take(),
+            predicate: None,
+            optional: prep.optional,
+            totalled: false,
+            seeds_met: 0,
+            depth_counts: Vec::new(),
         }
     }
-    /// Clears `count` field.
+    /// Returns a static estimate of how many Records the parameter matches, for ordering
+    /// checks:  lower values match fewer Records.  Parameters with more checks, covering
+    /// fewer categories, match fewer.
+    pub(crate) fn selectivity(&self) -> (Reverse<usize>, u32) {
+        let checks = [
+            self.kind.is_some() || self.thrown,
+            self.enchantment.is_some(),
+            self.runic.is_some() || self.any_runic,
+            self.max_strength.is_some(),
+            self.min_armor_value.is_some(),
+            self.max_charges.is_some(),
+            self.two_handed.is_some(),
+            self.min_damage_tier.is_some(),
+            self.ally_status.is_some() || self.any_legendary,
+            self.mutation.is_some() || self.any_mutation,
+            self.in_vault.is_some(),
+            self.magic_type.is_some(),
+            self.predicate.is_some(),
+        ];
+
+        (
+            Reverse(checks.iter().filter(|check| **check).count()),
+            self.category_flags.0.count_ones(),
+        )
+    }
+    /// Clears `count` and `depth_counts` fields.
     pub fn clear(&mut self) {
         self.count = 0;
+        self.depth_counts.clear();
+    }
+    /// Adds a matched quantity to `count` and to the count for its depth.
+    pub(crate) fn add_count(&mut self, depth: u8, quantity: u32) {
+        self.count += quantity;
+
+        match self.depth_counts.last_mut() {
+            Some((d, count)) if *d == depth => *count += quantity,
+            _ => self.depth_counts.push((depth, quantity)),
+        }
+    }
+    /// Returns the quantity matched at a single depth this seed.
+    pub(crate) fn count_at(&self, depth: u8) -> u32 {
+        self.depth_counts
+            .iter()
+            .find(|(d, _)| *d == depth)
+            .map_or(0, |(_, count)| *count)
+    }
+    /// Returns the depth at which the parameter was first met this seed, and the depth of
+    /// its last match, if it was met by a match ('less than' parameters never are).
+    pub(crate) fn met_depths(&self) -> Option<(u8, u8)> {
+        if self.count_type == CountType::LessThan {
+            return None;
+        }
+
+        let mut count = 0;
+        let (first, _) = self.depth_counts.iter().find(|(_, quantity)| {
+            count += quantity;
+            count >= self.count_target
+        })?;
+        let (last, _) = self.depth_counts.last()?;
+
+        Some((*first, *last))
+    }
+    /// Returns each runic the parameter accepts (alternatives are separated by `|`).
+    pub(crate) fn runics(&self) -> impl Iterator<Item = &str> {
+        self.runic.iter().flat_map(|runic| runic.split('|'))
+    }
+    /// Returns a short name for the parameter:  its kind (or category), with its count
+    /// if more than one (e.g. `enchanting x5`).
+    pub(crate) fn short_name(&self) -> String {
+        let name = match self.kind.as_ref() {
+            Some(kind) => kind.clone(),
+            None => self.category.to_string(),
+        };
+
+        match self.count_target {
+            1 => name,
+            count => format!("{} x{}", name, count),
+        }
     }
     /// Returns `true` if and ObjectParameters is valid based on `CountType`:
     /// - AtLeast:   count > count_target
     /// - EqualTo:   count == count_target
     /// - LessThan:  count < count_target
     pub(crate) fn is_valid(&self) -> bool {
+        self.is_valid_count(self.count) && self.stages.iter().all(|stage| self.is_stage_met(*stage))
+    }
+    /// Returns `true` if at least `count` objects were matched by `depth` this seed.
+    fn is_stage_met(&self, (count, depth): (u32, u8)) -> bool {
+        let matched: u32 = self
+            .depth_counts
+            .iter()
+            .filter(|(d, _)| *d <= depth)
+            .map(|(_, c)| c)
+            .sum();
+        matched >= count
+    }
+    /// Returns `true` if a given count satisfies the `CountType` and count target.
+    pub(crate) fn is_valid_count(&self, count: u32) -> bool {
         match self.count_type {
-            CountType::AtLeast => self.count >= self.count_target,
-            CountType::LessThan => self.count < self.count_target,
-            CountType::EqualTo => self.count == self.count_target,
+            CountType::AtLeast => count >= self.count_target,
+            CountType::LessThan => count < self.count_target,
+            CountType::EqualTo => count == self.count_target,
         }
-    }    
+    }
 }
 
 impl std::fmt::Display for ObjectParameter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use CountType::*;
 
-        write!(f, "  category: {}\n", self.category)?;
+        writeln!(f, "  category: {}", self.category)?;
 
-        match self.count_type {
-            AtLeast => write!(f, "     count: {} or more\n", self.count_target)?,
-            LessThan => write!(f, "     count: less than {}", self.count_target)?,
-            EqualTo => write!(f, "     count: exactly {}\n", self.count_target)?,
+        match (self.totalled, self.count_type) {
+            (true, _) => writeln!(f, "     count: toward a total")?,
+            (false, AtLeast) => writeln!(f, "     count: {} or more", self.count_target)?,
+            (false, LessThan) => writeln!(f, "     count: less than {}", self.count_target)?,
+            (false, EqualTo) => writeln!(f, "     count: exactly {}", self.count_target)?,
         };       
         match self.depth {
             26 | 40 => (),
-            _ => write!(f, "     depth: {} or less\n", self.depth)?,
+            _ => writeln!(f, "     depth: {} or less", self.depth)?,
         };   
+        for (count, depth) in self.stages.iter() {
+            writeln!(f, "     stage: {} or more by depth {}", count, depth)?;
+        }
         if let Some(kind) = self.kind.as_ref() {
-            write!(f, "      kind: {}\n", kind)?;
+            writeln!(f, "      kind: {}", kind)?;
+        }
+        if self.thrown {
+            writeln!(f, "      kind: any thrown")?;
         }
         if let Some(enchantment) = self.enchantment.as_ref() {
-            write!(f, "      ench: {}\n", enchantment)?;
+            writeln!(f, "      ench: {}", enchantment)?;
         }
-        if let Some(runic) = self.runic.as_ref() {
-            write!(f, "     runic: {}\n", runic)?;
+        if self.runic.is_some() {
+            writeln!(f, "     runic: {}", self.runics().collect::<Vec<_>>().join(" or "))?;
         }        
         if self.any_runic {
-            write!(f, "     runic: any\n")?;
+            writeln!(f, "     runic: any")?;
+        }
+        if let Some(strength) = self.max_strength {
+            writeln!(f, "  strength: {} or less", strength)?;
+        }
+        if let Some(armor_value) = self.min_armor_value {
+            writeln!(f, "     armor: {} or more", armor_value)?;
+        }
+        if let Some(charges) = self.max_charges {
+            writeln!(f, "   charges: {} or less", charges)?;
+        }
+        match self.two_handed {
+            Some(true) => writeln!(f, "   handles: two-handed")?,
+            Some(false) => writeln!(f, "   handles: one-handed")?,
+            None => (),
+        }
+        if let Some(tier) = self.min_damage_tier {
+            writeln!(f, "    damage: tier {} or more", tier)?;
         }
         if let Some(ally_status) = self.ally_status.as_ref() {
-            write!(f, "    status: {}\n", ally_status)?;
+            writeln!(f, "    status: {}", ally_status)?;
         }     
         if self.any_legendary {
-            write!(f, "    status: legendary\n")?;
+            writeln!(f, "    status: legendary")?;
         }
         if let Some(mutation) = self.mutation.as_ref() {
-            write!(f, "  mutation: {}\n", mutation)?;
+            writeln!(f, "  mutation: {}", mutation)?;
         }         
         if self.any_mutation {
-            write!(f, "  mutation: any\n")?;
+            writeln!(f, "  mutation: any")?;
+        }
+        match self.in_vault {
+            Some(true) => writeln!(f, "     vault: in vault")?,
+            Some(false) => writeln!(f, "     vault: not in vault")?,
+            None => (),
+        }
+        if let Some(magic_type) = self.magic_type.as_ref() {
+            writeln!(f, "     magic: {}", magic_type)?;
+        }
+        if let Some(predicate) = self.predicate.as_ref() {
+            writeln!(f, "    filter: {}", predicate)?;
+        }
+        if self.optional {
+            writeln!(f, "  optional: adds to score only")?;
         }
 
         Ok(())
@@ -137,15 +328,23 @@ pub struct PrepParams {
     pub(crate) count: Option<u32>,
     pub(crate) count_type: CountType,
     pub(crate) depth: Option<u8>,  
+    pub(crate) stages: Vec<(u32, u8)>,
     pub(crate) enchantment: Option<i8>,
     pub(crate) runic: Option<String>,
     pub(crate) any_runic: bool,
+    pub(crate) max_strength: Option<u8>,
+    pub(crate) min_armor_value: Option<u8>,
+    pub(crate) max_charges: Option<u8>,
+    pub(crate) two_handed: Option<bool>,
+    pub(crate) thrown: bool,
+    pub(crate) min_damage_tier: Option<u8>,
     pub(crate) ally_status: Option<String>,
     pub(crate) any_legendary : bool,
     pub(crate) mutation: Option<String>,
     pub(crate) any_mutation: bool,
     pub(crate) in_vault: Option<bool>,
     pub(crate) magic_type: Option<MagicType>,          
+    pub(crate) optional: bool,
 }
 
 impl PrepParams {
@@ -159,26 +358,329 @@ impl PrepParams {
     }
 }
 
+/// Composite constraint for the empowered-ally strategy (`--empower`):  a captive
+/// (caged or shackled) ally plus at least `charges` wand of empowerment charges, all
+/// found by `depth`.  The ally and charges are a single constraint, checked per seed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Empower {
+    /// Minimum number of empowerment charges (wand charges x quantity).
+    pub(crate) charges: u32,
+    /// Maximum depth for the ally and the wands.
+    pub(crate) depth: u8,
+    /// Whether a captive ally was found this seed.
+    pub(crate) ally_found: bool,
+    /// Empowerment charges found this seed.
+    pub(crate) charges_found: u32,
+}
+
+impl Empower {
+    /// Makes a new instance.
+    pub fn new(charges: u32, depth: u8) -> Self {
+        Self {
+            charges,
+            depth,
+            ally_found: false,
+            charges_found: 0,
+        }
+    }
+    /// Clears per-seed counters.
+    pub fn clear(&mut self) {
+        self.ally_found = false;
+        self.charges_found = 0;
+    }
+    /// Returns `true` if a captive ally and enough charges were found this seed.
+    pub(crate) fn is_valid(&self) -> bool {
+        self.ally_found && self.charges_found >= self.charges
+    }
+}
+
+impl std::fmt::Display for Empower {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "  category: empower (captive ally + wand of empowerment)"
+        )?;
+        writeln!(f, "   charges: {} or more", self.charges)?;
+        match self.depth {
+            26 | 40 => Ok(()),
+            _ => writeln!(f, "     depth: {} or less", self.depth),
+        }
+    }
+}
+
+/// Proximity constraint (`--within`):  objects `a` and `b` (parameter indices) must be
+/// matched within `levels` depths of each other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Within {
+    pub(crate) a: usize,
+    pub(crate) b: usize,
+    pub(crate) levels: u8,
+}
+
+impl Within {
+    /// Returns `true` if any depth that matched `a` this seed is within `levels` of any
+    /// depth that matched `b`.
+    pub(crate) fn is_valid(&self, params: &[ObjectParameter]) -> bool {
+        let (a, b) = (&params[self.a], &params[self.b]);
+
+        a.depth_counts.iter().any(|(depth_a, _)| {
+            b.depth_counts
+                .iter()
+                .any(|(depth_b, _)| depth_a.abs_diff(*depth_b) <= self.levels)
+        })
+    }
+}
+
+impl std::fmt::Display for Within {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "object {} within {} levels of object {}",
+            self.b + 1,
+            self.levels,
+            self.a + 1
+        )
+    }
+}
+
+/// Combined total (`--total`):  the objects matched for several object parameters
+/// (indices), of any categories, must number at least `count` together.  The parameters
+/// aren't met on their own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Total {
+    pub(crate) count: u32,
+    pub(crate) objects: Vec<usize>,
+}
+
+impl Total {
+    /// Returns `true` if the objects matched this seed for every parameter of the total
+    /// number at least `count`.
+    pub(crate) fn is_valid(&self, params: &[ObjectParameter]) -> bool {
+        self.objects.iter().map(|i| params[*i].count).sum::<u32>() >= self.count
+    }
+    /// Returns the depth at which the total was reached this seed, and the depth of its
+    /// last match, if it was reached.
+    pub(crate) fn met_depths(&self, params: &[ObjectParameter]) -> Option<(u8, u8)> {
+        let mut depth_counts: Vec<(u8, u32)> = self
+            .objects
+            .iter()
+            .flat_map(|i| params[*i].depth_counts.iter().copied())
+            .collect();
+        depth_counts.sort_by_key(|(depth, _)| *depth);
+
+        let mut count = 0;
+        let (first, _) = depth_counts.iter().find(|(_, quantity)| {
+            count += quantity;
+            count >= self.count
+        })?;
+        let (last, _) = depth_counts.last()?;
+
+        Some((*first, *last))
+    }
+}
+
+impl std::fmt::Display for Total {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let numbers: Vec<String> = self.objects.iter().map(|i| (i + 1).to_string()).collect();
+        write!(
+            f,
+            "{} or more of objects {}",
+            self.count,
+            numbers.join(", ")
+        )
+    }
+}
+
+/// Global exclusion (`--exclude-category`):  seeds with an object of this category (and
+/// kind, partial match allowed) in the depth range are rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exclusion {
+    pub(crate) category: Category,
+    pub(crate) category_flags: BitFlags16,
+    pub(crate) kind: Option<String>,
+}
+
+impl Exclusion {
+    /// Parses an exclusion in the form `CATEGORY[:KIND]` (e.g. 'scroll:aggravate').
+    pub fn parse(term: &str) -> Result<Self> {
+        let term = term.trim().to_lowercase();
+        let (category_val, kind) = match term.split_once(':') {
+            Some((category, kind)) if !kind.trim().is_empty() => {
+                (category.to_string(), Some(kind.trim().to_string()))
+            }
+            Some((category, _)) => (category.to_string(), None),
+            None => (term.clone(), None),
+        };
+
+        // An empty category would partially match the first category listed.
+        match Category::parse(category_val.trim()).filter(|_| !category_val.trim().is_empty()) {
+            Some(category) => Ok(Self {
+                category,
+                category_flags: category.to_flags(),
+                kind,
+            }),
+            None => Err(anyhow!(
+                "'{}' is not a valid category to exclude",
+                category_val
+            )),
+        }
+    }
+    /// Returns `true` if the Record's object is excluded.
+    pub(crate) fn matches(&self, category_flags: BitFlags16, kind: &str) -> bool {
+        category_flags.intersects(self.category_flags)
+            && self.kind.as_ref().is_none_or(|k| kind.contains(k.as_str()))
+    }
+}
+
+impl std::fmt::Display for Exclusion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind.as_ref() {
+            Some(kind) => write!(f, "{} '{}'", self.category, kind),
+            None => write!(f, "{}", self.category),
+        }
+    }
+}
+
 /// Contains all possible parameters used for a Brogue seed search, including:
 /// - General:  depth_min, depth_max, detail, etc.
 /// - Object:  parameters for a given object category (armor, weapon, etc.)
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SearchParameters {
     // Total number of object params fully matched this seed (inc. COUNT)
     pub(crate) object_matches: usize,
     pub(crate) object_match_target: usize,
     // Total number of successfully-matched searches (seeds w/all params met)
-    pub(crate) search_matches: u8,
-    pub(crate) search_match_target: u8,
+    pub(crate) search_matches: u32,
+    /// Number of matching seeds to find (0 for every matching seed).
+    pub(crate) search_match_target: u32,
+    /// If set, the search never completes early (counts every matching seed).
+    pub(crate) unbounded: bool,
+    /// Number of seeds in range searched so far.
+    pub(crate) seeds_scanned: u64,
+    /// Number of Records read so far.
+    pub(crate) rows_scanned: u64,
+    /// Percent of catalog files to sample for an approximate count (`--approx`).
+    pub(crate) approx: Option<f64>,
     pub(crate) debug: bool,
     pub(crate) depth_min: u8,
     pub(crate) depth_max: u8,
+    /// If set, explains the search (with object rarity) instead of running it.
+    pub(crate) explain: bool,
+    /// If set, writes the parsed search as JSON instead of running it (`--explain-json`).
+    pub(crate) explain_json: bool,
     pub(crate) file_paths: Vec<PathBuf>,
+    /// Catalog directories the file paths were found in (one per `--filepath`).
+    pub(crate) file_dirs: Vec<PathBuf>,
+    /// If set, results from parallel workers are merged in ascending seed order.
+    pub(crate) ordered: bool,
+    /// If set, numeric constraints are loosened until enough seeds match (`--relax`).
+    pub(crate) relax: bool,
+    /// If set, reports how many seeds met each object parameter (`--param-stats`).
+    pub(crate) param_stats: bool,
+    /// If set, reports the depths each object was met at for every matching seed
+    /// (`--met-depths`).
+    pub(crate) met_depths: bool,
+    /// If set, lists every vault on the searched depths of each matching seed (`--vaults`).
+    pub(crate) vaults: bool,
+    /// If set, reports the cage keys and captors on the level of each caged or shackled
+    /// ally matched (`--rescue`).
+    pub(crate) rescue: bool,
+    /// If set, matching seeds are sorted by their required depth (`--sort-depth`).
+    pub(crate) sort_depth: bool,
+    /// If set, each matching seed is printed as soon as it's found (`--stream`).
+    pub(crate) stream: bool,
+    /// If set, every seed is scanned and only the best matching seeds are kept (`--best`).
+    pub(crate) best: Option<BestBy>,
+    /// Seed of the random file order and `--approx` samples, if set (`--random-seed`).
+    pub(crate) random_seed: Option<u64>,
+    /// Stops the search (and its copies) early when cancelled, e.g. from another thread.
+    pub(crate) cancel: CancelToken,
+    /// File the matches are written to instead of standard output (`--output`).  Line-based
+    /// output is appended seed by seed, as each seed is confirmed.
+    pub(crate) output: Option<PathBuf>,
+    /// Receives the matches of each seed as it's confirmed, for callers embedding the
+    /// scanner (see `sink`).  Never set for `--best` searches.
+    pub(crate) seed_sink: Option<SeedSink>,
+    /// If set, shows scan progress on a status line (`--progress`).
+    pub(crate) progress: bool,
+    /// If set, catalog files are scanned at low priority, with pauses (`--nice`).
+    pub(crate) nice: bool,
+    /// If set, numbers in summaries aren't grouped by thousands (`--plain-numbers`).
+    pub(crate) plain_numbers: bool,
+    /// Search arguments read as other kinds by `--fuzzy`, with the kind each was read as.
+    pub(crate) fuzzy_terms: Vec<(String, String)>,
     pub(crate) format: FileFormat,
+    /// Column and category names of the catalog files' exporter (`--schema`).
+    pub(crate) schema: CatalogSchema,
+    pub(crate) format_version: u32,
+    pub(crate) grade: Option<GradeThresholds>,
+    /// Weights of ally quality in the scores used by `--grade`.
+    pub(crate) ally_weights: AllyWeights,
+    /// Scoring expression replacing the default score used by `--grade`.
+    pub(crate) score_expr: Option<ScoreExpr>,
+    /// Gold in the depth range of each matching seed (only counted for `--grade`).
+    pub(crate) seed_gold: HashMap<u32, u32>,
+    /// Optional object parameters met by each matching seed (only counted for `--grade`).
+    pub(crate) seed_optionals: HashMap<u32, u32>,
+    /// Depths each object parameter (then each total) was first met and last matched at,
+    /// for each matching seed.
+    pub(crate) seed_met_depths: HashMap<u32, Vec<Option<(u8, u8)>>>,
+    /// Vaults on the searched depths of each matching seed (only kept for `--vaults`).
+    pub(crate) seed_vaults: HashMap<u32, Vec<SeedVault>>,
+    /// Captive allies matched by each matching seed (only kept for `--rescue`).
+    pub(crate) seed_rescues: HashMap<u32, Vec<AllyRescue>>,
+    /// Name of the query (set for query files).
+    pub(crate) name: Option<String>,
+    pub(crate) output_mode: OutputMode,
+    /// Template of the line printed for each match (`--format`).
+    pub(crate) template: Option<MatchTemplate>,
+    /// Encoding of the files written (catalogs and seed lists).
+    pub(crate) out_encoding: OutputEncoding,
+    /// When output is colored (`--color`).
+    pub(crate) color: ColorMode,
+    /// Cached rarity table, used by `--explain`.
+    pub(crate) rarity_cache: PathBuf,
+    /// If set, cached artifacts (rarity table, indexes) built from catalog files that have
+    /// changed since are rebuilt before the search.
+    pub(crate) rebuild_stale: bool,
+    /// Raw column filters (`--where`) every matching Record must pass.
+    pub(crate) row_filters: Vec<RowFilter>,
+    pub(crate) save_db: Option<PathBuf>,
+    /// Community seed database the matches are uploaded to (`--upload`), if any.
+    pub(crate) upload: Option<Endpoint>,
     pub(crate) seed_min:  u32,
     pub(crate) seed_max:  u32,
+    /// If set, only these seeds (ascending) are searched (`--seedlist`).
+    pub(crate) seed_list: Option<Vec<u32>>,
+    /// Only every `seed_step`-th seed from `seed_min` is searched (`--seed-step`).
+    pub(crate) seed_step: u32,
+    /// Seed list file the matching seeds are written to (`--write-seedlist`).
+    pub(crate) write_seedlist: Option<PathBuf>,
+    /// Spreadsheet file the matches are written to (`--export`).
+    pub(crate) export: Option<PathBuf>,
     pub(crate) verbosity: u8,
     pub(crate) object_params: Vec<ObjectParameter>,
+    /// Order in which object parameters (indices) are checked against each Record, most
+    /// selective first (see `evaluation_order`).
+    pub(crate) param_order: Vec<usize>,
+    /// Tree of object parameters that must be met for a seed to match (`--logic`).
+    pub(crate) logic: ParamTree,
+    /// Empowered-ally composite constraint (`--empower`).
+    pub(crate) empower: Option<Empower>,
+    /// Groups of object parameters (indices) that must all be met on a single depth.
+    pub(crate) same_depth: Vec<Vec<usize>>,
+    /// Pairs of object parameters (indices) that must be matched within a number of levels
+    /// of each other.
+    pub(crate) within: Vec<Within>,
+    /// Groups of object parameters (indices) whose matched objects must reach a combined
+    /// count.
+    pub(crate) totals: Vec<Total>,
+    /// If set, seeds with any malevolent object by this depth are rejected.
+    pub(crate) no_malevolent: Option<u8>,
+    /// Seeds with any of these objects in the depth range are rejected.
+    pub(crate) exclusions: Vec<Exclusion>,
+    /// Whether vault objects only match if a key opening the vault is on the same depth.
+    pub(crate) require_key: bool,
 }
 
 impl SearchParameters {
@@ -206,11 +708,14 @@ impl SearchParameters {
             return Err(anyhow!("--mindepth cannot be greater than --maxdepth"));
         }
 
-        // MAXMATCHES has default of 10, so always present.  Must be 1 to 255.
+        // MAXMATCHES has default of 10, so always present.  0 or 'all' is unlimited.
         let max_matches_val = matches.value_of("matches_max").unwrap();
-        let search_match_target = match max_matches_val.parse::<u8>() {
-            Ok(val) => val,
-            Err(_) => return Err(anyhow!("--matches must be from 1 to 255")),
+        let search_match_target = match max_matches_val {
+            "all" => 0,
+            val => match val.parse::<u32>() {
+                Ok(val) => val,
+                Err(_) => return Err(anyhow!("--matches must be a number of seeds, or 0 or 'all'")),
+            },
         };
 
         // MINSEED has default of 1, so always present.  Cannot be > MAXSEED.
@@ -231,30 +736,212 @@ impl SearchParameters {
             return Err(anyhow!("--minseed cannot be greater than --maxseed"));
         }
 
+        // SEED, if set, replaces MINSEED and MAXSEED (which it conflicts with).  Several
+        // seeds are searched as a seed list.
+        let mut seeds = Vec::new();
+        for seed_val in matches.values_of("seed").into_iter().flatten() {
+            seeds.push(parse_seed(seed_val, "--seed")?);
+        }
+        seeds.sort_unstable();
+        seeds.dedup();
+        let (seed_min, seed_max) = match (seeds.first(), seeds.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => (seed_min, seed_max),
+        };
+
+        // SEEDLIST, if set, keeps the listed seeds in range (and given by SEED, if several
+        // are), and narrows the range to them.
+        let seed_list = match matches.value_of("seedlist") {
+            Some(path) => {
+                let mut list = load_seed_list(expand_path(path)?)?;
+                list.retain(|seed| *seed >= seed_min && *seed <= seed_max);
+                if seeds.len() > 1 {
+                    list.retain(|seed| seeds.binary_search(seed).is_ok());
+                }
+                if list.is_empty() {
+                    return Err(anyhow!(
+                        "seed list {:?} has no seeds in the seed range",
+                        path
+                    ));
+                }
+                Some(list)
+            }
+            None if seeds.len() > 1 => Some(seeds),
+            None => None,
+        };
+        let (seed_min, seed_max) = match seed_list.as_ref() {
+            Some(seeds) => (seeds[0], seeds[seeds.len() - 1]),
+            None => (seed_min, seed_max),
+        };
+
+        // SEED_STEP has default of 1, so always present.
+        let seed_step = match matches.value_of("seed_step").unwrap().parse::<u32>() {
+            Ok(val) if val > 0 => val,
+            _ => return Err(anyhow!("--seed-step must be from 1 to 4294967295")),
+        };
+
         // DEBUG defaults to `false`
         let debug = matches.is_present("debug");
 
-        // FORMAT assumes UTF-16LE (default CE format) unless UTF-8 is specified.
-        // If no files of the format are found, the formatting is switched 
-        // (from `format_arg` to `format`).
-        let format_arg = match matches.is_present("utf8") {
+        // APPROX, if set, samples a percent of files (default 1%).
+        let approx = match (matches.is_present("approx"), matches.value_of("approx")) {
+            (true, value) => match value.unwrap_or("1").parse::<f64>() {
+                Ok(val) if val > 0.0 && val <= 100.0 => Some(val),
+                _ => return Err(anyhow!("--approx must be a percent above 0, up to 100")),
+            },
+            _ => None,
+        };
+
+        // FORMAT assumes UTF-16LE (default CE format) unless UTF-8 is specified (or the
+        // build can't read UTF-16).  If no files of the format are found, the formatting
+        // is switched (from `format_arg` to `format`).
+        #[cfg(not(feature = "utf16"))]
+        if matches.is_present("utf16") {
+            return Err(crate::file_handling::no_utf16());
+        }
+        let format_arg = match matches.is_present("utf8") || !cfg!(feature = "utf16") {
             true => FileFormat::Utf8,
             false => FileFormat::Utf16,        
         };
 
         // FILEPATH in which .csv files are found. Defaults to CWD if not given.  
         // Returned paths are UTF-16LE (Brogue CE format) unless UTF-8 is specified.
-        let path = match matches.is_present("filepath") {
-            true => Path::new(matches.value_of("filepath").unwrap()).into(),
-            false => current_dir()?,
+        // Several FILEPATHs may be given, but must all use the same format.
+        let file_dirs: Vec<PathBuf> = match matches.values_of("filepath") {
+            Some(values) => values.map(expand_path).collect::<Result<_>>()?,
+            None => vec![current_dir()?],
+        };
+        // STREAM prints seeds as found, but parallel workers find more seeds than are kept
+        if matches.is_present("stream") && file_dirs.len() > 1 {
+            return Err(anyhow!(
+                "--stream can't be used with more than one --filepath folder"
+            ));
+        }
+        let mut file_paths = Vec::new();
+        let mut format = None;
+        let mut empty_format = None;
+
+        for path in file_dirs.iter() {
+            let (mut paths, path_format) = get_brogue_csv_paths(path.clone(), 0, format_arg)?;
+
+            match (format, paths.is_empty()) {
+                (_, true) => {
+                    empty_format.get_or_insert(path_format);
+                }
+                (Some(format), false) if format != path_format => {
+                    return Err(anyhow!("catalog files in {:?} use a different format", path))
+                }
+                (_, false) => format = Some(path_format),
+            }
+            file_paths.append(&mut paths);
+        }
+        let format = format.or(empty_format).unwrap_or(format_arg);
+
+        // RARITY CACHE defaults to a file in the (first) catalog folder.
+        let rarity_cache = match matches.value_of("rarity_cache") {
+            Some(value) => expand_path(value)?,
+            None => file_dirs[0].join(RARITY_CACHE_FILE),
+        };
+
+        // SCHEMA is read from the config file, which defaults to a file in the (first)
+        // catalog folder.
+        let config = match matches.value_of("config") {
+            Some(_) if !matches.is_present("schema") && !matches.is_present("upload") => {
+                return Err(anyhow!("--config is only used with --schema or --upload"))
+            }
+            Some(value) => expand_path(value)?,
+            None => file_dirs[0].join(CONFIG_FILE),
+        };
+        let mut schema = match matches.value_of("schema") {
+            Some(name) => CatalogSchema::load(&config, name)?,
+            None => CatalogSchema::default(),
+        };
+
+        // VARIANT data packs default to the `variants` folder of the (first) catalog folder.
+        if let Some(value) = matches.value_of("variant") {
+            schema.variant = Some(Arc::new(DataPack::load(&DataPack::find(
+                value,
+                &file_dirs[0],
+            )?)?));
+        }
+
+        // EXPLAIN defaults to `false`
+        let explain = matches.is_present("explain");
+        let explain_json = matches.is_present("explain_json");
+
+        // RANDOM SEED, if set, makes random file orders (and samples) reproducible.
+        let random_seed = match matches.value_of("random_seed") {
+            Some(value) => Some(
+                value
+                    .parse::<u64>()
+                    .map_err(|_| anyhow!("--random-seed must be a whole number"))?,
+            ),
+            None => None,
         };
-        let (mut file_paths, format) = get_brogue_csv_paths(path, 0, format_arg)?;
 
         // RANDOM, if set, shuffles the list of file paths.
         if matches.is_present("random") {
-            fastrand::shuffle(&mut file_paths);
+            shuffle_paths(&mut file_paths, random_seed);
         }
 
+        // OUTPUT MODE is text unless JSON, NDJSON, or porcelain output is requested.  An
+        // OUTPUT file takes NDJSON unless another machine format is requested.
+        let output_mode = match OutputMode::from_matches(&matches) {
+            OutputMode::Text if matches.is_present("output") => OutputMode::Ndjson,
+            mode => mode,
+        };
+
+        // FORMAT VERSION of machine output has a default of the latest version.
+        let format_version = parse_format_version(matches.value_of("format_version").unwrap())?;
+
+        // GRADE, if set, buckets matching seeds into tiers.  Thresholds are optional.
+        let grade = match (matches.is_present("grade"), matches.value_of("grade")) {
+            (true, Some(value)) => Some(GradeThresholds::parse(value)?),
+            (true, None) => Some(GradeThresholds::default()),
+            _ => None,
+        };
+
+        // ALLY WEIGHTS score allies for GRADE, with defaults if not set.
+        let ally_weights = match matches.value_of("ally_weights") {
+            Some(value) => AllyWeights::parse(value)?,
+            None => AllyWeights::default(),
+        };
+
+        // BEST, if set, keeps the best matching seeds of the whole range.
+        let best = match matches.value_of("best") {
+            Some(value) => Some(BestBy::parse(value)?),
+            None => None,
+        };
+
+        // SCORE EXPR, if set, computes the score used by GRADE.
+        let score_expr = match matches.value_of("score_expr") {
+            Some(value) => Some(ScoreExpr::parse(value)?),
+            None => None,
+        };
+
+        // FORMAT, if set, is the template of each match line.
+        let template = match matches.value_of("template") {
+            Some(value) => Some(MatchTemplate::parse(value)?),
+            None => None,
+        };
+
+        // WHERE filters compare raw catalog columns.
+        let row_filters = match matches.values_of("where") {
+            Some(values) => values.map(RowFilter::parse).collect::<Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+
+        // SAVE DB, if set, is the SQLite database that results are appended to.
+        let save_db = matches.value_of("save_db").map(expand_path).transpose()?;
+
+        // UPLOAD, if set, is the community seed database that matches are sent to (the
+        // config file's endpoint if no URL is given).  Nothing is sent unless it's set.
+        let upload = match (matches.is_present("upload"), matches.value_of("upload")) {
+            (true, Some(url)) => Some(Endpoint::parse(url)?),
+            (true, None) => Some(Endpoint::from_config(&config)?),
+            _ => None,
+        };
+
         // VERBOSITY can be from 1 to 3, and has default of 3 (always present).
         let verbosity: u8 = match matches.occurrences_of("verbose") {
             1 => 1,
@@ -262,9 +949,13 @@ impl SearchParameters {
             _ => 3,
         };
 
+        // FUZZY, if set, reads misspelled kinds as the nearest kinds (and records them).
+        let fuzzy = matches.is_present("fuzzy");
+        let mut fuzzy_terms = Vec::new();
+
         // --- Ally --- //    
-        if let Some(values) = matches.values_of("ally") {
-            for search_result in parse_allies(values).into_iter() {
+        if let Some(values) = category_values(&matches, "ally", Category::Ally, fuzzy, &mut fuzzy_terms) {
+            for search_result in parse_allies(values.iter().map(String::as_str)) {
                 match search_result {
                     Ok(param) => object_params.push(param),
                     Err(e) => return Err(e),
@@ -273,8 +964,8 @@ impl SearchParameters {
         }
 
         // --- Altar --- //    
-        if let Some(values) = matches.values_of("altar") {
-            for search_result in parse_altars(values).into_iter() {
+        if let Some(values) = category_values(&matches, "altar", Category::Altar, fuzzy, &mut fuzzy_terms) {
+            for search_result in parse_altars(values.iter().map(String::as_str)) {
                 match search_result {
                     Ok(param) => object_params.push(param),
                     Err(e) => return Err(e),
@@ -283,8 +974,8 @@ impl SearchParameters {
         }
         
         // --- Armor --- //    
-        if let Some(values) = matches.values_of("armor") {
-            for search_result in parse_armors(values).into_iter() {
+        if let Some(values) = category_values(&matches, "armor", Category::Armor, fuzzy, &mut fuzzy_terms) {
+            for search_result in parse_armors(values.iter().map(String::as_str)) {
                 match search_result {
                     Ok(param) => object_params.push(param),
                     Err(e) => return Err(e),
@@ -293,8 +984,8 @@ impl SearchParameters {
         }
 
         // --- Charm --- //    
-        if let Some(values) = matches.values_of("charm") {
-            for search_result in parse_charms(values).into_iter() {
+        if let Some(values) = category_values(&matches, "charm", Category::Charm, fuzzy, &mut fuzzy_terms) {
+            for search_result in parse_charms(values.iter().map(String::as_str)) {
                 match search_result {
                     Ok(param) => object_params.push(param),
                     Err(e) => return Err(e),
@@ -303,8 +994,8 @@ impl SearchParameters {
         }
 
         // --- Food --- //    
-        if let Some(values) = matches.values_of("food") {
-            for search_result in parse_food(values).into_iter() {
+        if let Some(values) = category_values(&matches, "food", Category::Food, fuzzy, &mut fuzzy_terms) {
+            for search_result in parse_food(values.iter().map(String::as_str)) {
                 match search_result {
                     Ok(param) => object_params.push(param),
                     Err(e) => return Err(e),
@@ -313,8 +1004,8 @@ impl SearchParameters {
         }  
         
         // --- Gold --- //    
-        if let Some(values) = matches.values_of("gold") {
-            for search_result in parse_gold(values).into_iter() {
+        if let Some(values) = category_values(&matches, "gold", Category::Gold, fuzzy, &mut fuzzy_terms) {
+            for search_result in parse_gold(values.iter().map(String::as_str)) {
                 match search_result {
                     Ok(param) => object_params.push(param),
                     Err(e) => return Err(e),
@@ -323,8 +1014,8 @@ impl SearchParameters {
         }        
 
         // --- Potion --- //    
-        if let Some(values) = matches.values_of("potion") {
-            for search_result in parse_potions(values).into_iter() {
+        if let Some(values) = category_values(&matches, "potion", Category::Potion, fuzzy, &mut fuzzy_terms) {
+            for search_result in parse_potions(values.iter().map(String::as_str)) {
                 match search_result {
                     Ok(param) => object_params.push(param),
                     Err(e) => return Err(e),
@@ -332,9 +1023,19 @@ impl SearchParameters {
             }
         }   
 
+        // --- Strength --- //
+        if let Some(values) = matches.values_of("strength") {
+            for search_result in parse_strength(values) {
+                match search_result {
+                    Ok(param) => object_params.push(param),
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
         // --- Ring --- //    
-        if let Some(values) = matches.values_of("ring") {
-            for search_result in parse_rings(values).into_iter() {
+        if let Some(values) = category_values(&matches, "ring", Category::Ring, fuzzy, &mut fuzzy_terms) {
+            for search_result in parse_rings(values.iter().map(String::as_str)) {
                 match search_result {
                     Ok(param) => object_params.push(param),
                     Err(e) => return Err(e),
@@ -343,8 +1044,8 @@ impl SearchParameters {
         }     
         
         // --- Scroll --- //    
-        if let Some(values) = matches.values_of("scroll") {
-            for search_result in parse_scrolls(values).into_iter() {
+        if let Some(values) = category_values(&matches, "scroll", Category::Scroll, fuzzy, &mut fuzzy_terms) {
+            for search_result in parse_scrolls(values.iter().map(String::as_str)) {
                 match search_result {
                     Ok(param) => object_params.push(param),
                     Err(e) => return Err(e),
@@ -353,8 +1054,8 @@ impl SearchParameters {
         }           
 
         // --- Staff --- //    
-        if let Some(values) = matches.values_of("staff") {
-            for search_result in parse_staves(values).into_iter() {
+        if let Some(values) = category_values(&matches, "staff", Category::Staff, fuzzy, &mut fuzzy_terms) {
+            for search_result in parse_staves(values.iter().map(String::as_str)) {
                 match search_result {
                     Ok(param) => object_params.push(param),
                     Err(e) => return Err(e),
@@ -363,8 +1064,8 @@ impl SearchParameters {
         }   
 
         // --- Wand --- //    
-        if let Some(values) = matches.values_of("wand") {
-            for search_result in parse_wands(values).into_iter() {
+        if let Some(values) = category_values(&matches, "wand", Category::Wand, fuzzy, &mut fuzzy_terms) {
+            for search_result in parse_wands(values.iter().map(String::as_str)) {
                 match search_result {
                     Ok(param) => object_params.push(param),
                     Err(e) => return Err(e),
@@ -373,8 +1074,14 @@ impl SearchParameters {
         }           
 
         // --- Weapon --- //
-        if let Some(values) = matches.values_of("weapon") {
-            for search_result in parse_weapons(values).into_iter() {
+        if let Some(values) = category_values(
+            &matches,
+            "weapon",
+            Category::Weapon,
+            fuzzy,
+            &mut fuzzy_terms,
+        ) {
+            for search_result in parse_weapons(values.iter().map(String::as_str)) {
                 match search_result {
                     Ok(param) => object_params.push(param),
                     Err(e) => return Err(e),
@@ -383,8 +1090,14 @@ impl SearchParameters {
         }
     
         // --- Equipment --- //
-        if let Some(values) = matches.values_of("equipment") {
-            for search_result in parse_equipment(values).into_iter() {
+        if let Some(values) = category_values(
+            &matches,
+            "equipment",
+            Category::Equipment,
+            fuzzy,
+            &mut fuzzy_terms,
+        ) {
+            for search_result in parse_equipment(values.iter().map(String::as_str)) {
                 match search_result {
                     Ok(param) => object_params.push(param),
                     Err(e) => return Err(e),
@@ -393,8 +1106,10 @@ impl SearchParameters {
         }
 
         // --- Items --- //
-        if let Some(values) = matches.values_of("item") {
-            for search_result in parse_items(values).into_iter() {
+        if let Some(values) =
+            category_values(&matches, "item", Category::Item, fuzzy, &mut fuzzy_terms)
+        {
+            for search_result in parse_items(values.iter().map(String::as_str)) {
                 match search_result {
                     Ok(param) => object_params.push(param),
                     Err(e) => return Err(e),
@@ -402,6 +1117,83 @@ impl SearchParameters {
             }
         }        
 
+        // --- Filters --- //
+        if let Some(values) = matches.values_of("filter") {
+            for value in values {
+                let mut param = ObjectParameter::from_prep(Category::Any, &mut PrepParams::new());
+                param.predicate = Some(Predicate::parse(value)?);
+                object_params.push(param);
+            }
+        }
+
+        // --- Empower --- //
+        let empower = match matches.values_of("empower") {
+            Some(values) => Some(parse_empower(values, depth_max)?),
+            None => None,
+        };
+
+        // TOTALS use the same object numbers as SAME DEPTH groups.  Their objects are only
+        // met together, so are left out of the default LOGIC.
+        let totals = match matches.values_of("total") {
+            Some(values) => values
+                .map(|value| parse_total(value, object_params.len()))
+                .collect::<Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+        for total in totals.iter() {
+            for i in total.objects.iter() {
+                object_params[*i].totalled = true;
+            }
+        }
+
+        // LOGIC uses 1-based object numbers, and defaults to every object that isn't
+        // optional (or totalled).
+        let logic = match matches.value_of("logic") {
+            Some(value) => ParamTree::parse(value, &object_params)?,
+            None => ParamTree::every(&object_params),
+        };
+
+        // SAME DEPTH groups are 1-based object numbers, in the order listed by the search
+        // summary, or 'all'.
+        let same_depth = match matches.values_of("same_depth") {
+            Some(values) => values
+                .map(|value| parse_param_group(value, object_params.len()))
+                .collect::<Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+
+        // WITHIN pairs use the same object numbers as SAME DEPTH groups.
+        let within = match matches.values_of("within") {
+            Some(values) => values
+                .map(|value| parse_within(value, object_params.len()))
+                .collect::<Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+
+        // NO MALEVOLENT depth is optional, and defaults to the maximum depth.
+        let no_malevolent = match (
+            matches.is_present("no_malevolent"),
+            matches.value_of("no_malevolent"),
+        ) {
+            (true, Some(value)) => match value.parse::<u8>() {
+                Ok(depth) if depth >= 1 => Some(depth.min(depth_max)),
+                _ => return Err(anyhow!("--no-malevolent depth must be from 1 to 26")),
+            },
+            (true, None) => Some(depth_max),
+            _ => None,
+        };
+
+        // EXCLUDE CATEGORY lists are comma-separated CATEGORY[:KIND] terms.
+        let mut exclusions = Vec::new();
+
+        if let Some(values) = matches.values_of("exclude_category") {
+            for value in values {
+                for term in value.split(',').filter(|term| !term.trim().is_empty()) {
+                    exclusions.push(Exclusion::parse(term)?);
+                }
+            }
+        }
+
         // If any params are duplicates ("scale scale"), return an error
         let slice = &object_params;
         if (1..slice.len()).any(|i| slice[i..].contains(&slice[i - 1])) {
@@ -411,18 +1203,75 @@ impl SearchParameters {
         Ok(
             Self {
                 object_matches: 0,
-                object_match_target: object_params.len(),  
+                object_match_target: object_params.iter().filter(|p| !p.optional && !p.totalled).count(),  
                 search_matches: 0,
                 search_match_target,                  
+                unbounded: false,
+                seeds_scanned: 0,
+                rows_scanned: 0,
+                approx,
                 debug,
                 depth_min,
                 depth_max,
+                explain,
+                explain_json,
                 file_paths,
+                file_dirs,
+                ordered: matches.is_present("ordered"),
+                relax: matches.is_present("relax"),
+                param_stats: matches.is_present("param_stats"),
+                met_depths: matches.is_present("met_depths"),
+                vaults: matches.is_present("vaults"),
+                rescue: matches.is_present("rescue"),
+                sort_depth: matches.is_present("sort_depth"),
+                stream: matches.is_present("stream"),
+                best,
+                random_seed,
+                cancel: CancelToken::new(),
+                output: matches.value_of("output").map(expand_path).transpose()?,
+                seed_sink: None,
+                progress: matches.is_present("progress"),
+                nice: matches.is_present("nice"),
+                plain_numbers: matches.is_present("plain_numbers"),
+                fuzzy_terms,
                 format,
+                schema,
+                format_version,
+                grade,
+                ally_weights,
+                score_expr,
+                seed_gold: HashMap::new(),
+                seed_optionals: HashMap::new(),
+                seed_met_depths: HashMap::new(),
+                seed_vaults: HashMap::new(),
+                seed_rescues: HashMap::new(),
+                name: None,
+                output_mode,
+                template,
+                out_encoding: OutputEncoding::parse(matches.value_of("out_encoding").unwrap())?,
+                color: ColorMode::parse(matches.value_of("color").unwrap())?,
+                rarity_cache,
+                rebuild_stale: matches.is_present("rebuild_stale"),
+                row_filters,
+                save_db,
+                upload,
                 seed_min,
                 seed_max,
+                seed_list,
+                seed_step,
+                write_seedlist: matches.value_of("write_seedlist").map(expand_path).transpose()?,
+                export: matches.value_of("export").map(expand_path).transpose()?,
                 verbosity,
+                param_order: evaluation_order(&object_params),
                 object_params,
+                logic,
+                empower,
+                same_depth,
+                within,
+                totals,
+                no_malevolent,
+                exclusions,
+                require_key: matches.is_present("require_key"),
             }
         )
     }
@@ -432,20 +1281,202 @@ impl SearchParameters {
         for obj_param in self.object_params.iter_mut() {
             obj_param.clear();
         }
+        if let Some(empower) = self.empower.as_mut() {
+            empower.clear();
+        }
+    }
+    /// Returns the depth a matching seed must be explored to for every (required) object
+    /// parameter and total to be met, if any was met by a match.
+    pub(crate) fn required_depth(&self, seed: u32) -> Option<u8> {
+        let met_depths = self.seed_met_depths.get(&seed)?;
+        let (param_depths, total_depths) =
+            met_depths.split_at(self.object_params.len().min(met_depths.len()));
+
+        self.object_params
+            .iter()
+            .zip(param_depths.iter())
+            .filter(|(param, _)| !param.optional && !param.totalled)
+            .map(|(_, met)| met)
+            .chain(total_depths.iter())
+            .filter_map(|met| met.map(|(first, _)| first))
+            .max()
+    }
+    /// Formats a number for a summary, with thousands separators (e.g. `4,294,967,295`)
+    /// unless `--plain-numbers` is set.
+    pub(crate) fn number(&self, value: u64) -> String {
+        let digits = value.to_string();
+
+        if self.plain_numbers {
+            return digits;
+        }
+
+        let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push(',');
+            }
+            grouped.push(digit);
+        }
+
+        grouped
+    }
+    /// Formats a count for a summary as `number` does, followed by the singular or plural
+    /// name of what's counted (e.g. "1 seed", "2,500 seeds").
+    pub(crate) fn counted(&self, value: u64, one: &str, many: &str) -> String {
+        match value {
+            1 => format!("1 {}", one),
+            _ => format!("{} {}", self.number(value), many),
+        }
+    }
+    /// Returns `true` if a seed is searched:  in the seed range, on the seed step, and in
+    /// the seed list (if any).
+    pub(crate) fn is_seed_searched(&self, seed: u32) -> bool {
+        seed >= self.seed_min
+            && seed <= self.seed_max
+            && (seed - self.seed_min).is_multiple_of(self.seed_step)
+            && self
+                .seed_list
+                .as_ref()
+                .is_none_or(|seeds| seeds.binary_search(&seed).is_ok())
+    }
+    /// Returns the number of seeds from `first` to `last` (within the seed range) on the
+    /// seed step.
+    pub(crate) fn stepped_seeds(&self, first: u32, last: u32) -> u64 {
+        let step = u64::from(self.seed_step);
+        let offset = |seed: u32| u64::from(seed - self.seed_min);
+
+        // Seeds on the step are at offsets 0, step, 2 * step, ... from `seed_min`
+        offset(last) / step + 1 - offset(first).div_ceil(step)
+    }
+    /// Returns the number of catalog files outside the seed range, and the number of seeds
+    /// in range held by the files (`None` unless every file is named by its seed range).
+    pub(crate) fn file_coverage(&self) -> (usize, Option<u64>) {
+        let ranges: Vec<Option<(u32, u32)>> =
+            self.file_paths.iter().map(seed_range_from_name).collect();
+        let outside = ranges
+            .iter()
+            .flatten()
+            .filter(|(first, last)| *last < self.seed_min || *first > self.seed_max)
+            .count();
+
+        // Seeds in range of the merged file ranges
+        let mut ranges: Vec<(u32, u32)> = match ranges.into_iter().collect::<Option<_>>() {
+            Some(ranges) => ranges,
+            None => return (outside, None),
+        };
+        ranges.sort_unstable();
+
+        let mut covered = 0;
+        let mut next = self.seed_min as u64;
+
+        for (first, last) in ranges {
+            let first = (first as u64).max(next);
+            let last = (last as u64).min(self.seed_max as u64);
+            if first <= last {
+                covered += last - first + 1;
+                next = last + 1;
+            }
+        }
+
+        (outside, Some(covered))
+    }
+    /// Loosens a single numeric constraint (`--relax`), and returns the constraint and its
+    /// new value, or `None` if nothing is left to relax.  Constraints are loosened one step at a time in
+    /// a fixed order:  'or more' counts of any object (down to 1), then enchantments of any
+    /// object (toward 0, then dropped), then object depths (up to the maximum depth), then
+    /// the maximum depth (up to 26).
+    pub(crate) fn relax(&mut self) -> Option<(String, String)> {
+        let depth_max = self.depth_max;
+
+        for (i, param) in self.object_params.iter_mut().enumerate() {
+            if param.count_type == CountType::AtLeast && param.count_target > 1 {
+                param.count_target -= 1;
+                return Some((
+                    format!("object {} count", i + 1),
+                    format!("{} or more", param.count_target),
+                ));
+            }
+        }
+        for (i, param) in self.object_params.iter_mut().enumerate() {
+            if let Some(enchantment) = param.enchantment {
+                param.enchantment = match enchantment {
+                    2..=i8::MAX => Some(enchantment - 1),
+                    i8::MIN..=-2 => Some(enchantment + 1),
+                    _ => None,
+                };
+                let value = param.enchantment.map_or("any".to_string(), |e| e.to_string());
+                return Some((format!("object {} ench", i + 1), value));
+            }
+        }
+        for (i, param) in self.object_params.iter_mut().enumerate() {
+            if param.depth < depth_max {
+                param.depth += 1;
+                return Some((
+                    format!("object {} depth", i + 1),
+                    format!("{} or less", param.depth),
+                ));
+            }
+        }
+        if self.depth_max < 26 {
+            self.depth_max += 1;
+            return Some((
+                "depth".to_string(),
+                format!("{} to {}", self.depth_min, self.depth_max),
+            ));
+        }
+
+        None
+    }
+    /// Returns `true` if the search has nothing to search for.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.object_params.is_empty() && self.empower.is_none()
     }
     /// Returns `true` if the search if the requested number of matches (set by
-    /// '--matches' option has been met.
+    /// '--matches' option has been met.  Searches keeping the best seeds (`--best`)
+    /// never complete early.  Cancelled searches are always complete.
     pub(crate) fn is_complete(&self) -> bool {
-        self.search_matches == self.search_match_target
+        self.cancel.is_cancelled()
+            || (self.best.is_none()
+                && self
+                    .seed_limit()
+                    .is_some_and(|limit| self.search_matches as usize >= limit))
+    }
+    /// Returns `true` if matching seeds are scored:  for `--grade`, or to keep the best
+    /// seeds by score.
+    pub(crate) fn is_scored(&self) -> bool {
+        self.grade.is_some() || self.best == Some(BestBy::Score)
+    }
+    /// Returns the number of matching seeds to keep, or `None` if every matching seed is
+    /// kept (`--matches all`, or an unbounded search).
+    pub(crate) fn seed_limit(&self) -> Option<usize> {
+        match (self.unbounded, self.search_match_target) {
+            (true, _) | (false, 0) => None,
+            (false, target) => Some(target as usize),
+        }
     }          
-    /// Returns `true` if all ObjectParameters are valid according to their `CountType`.
-    /// A Search is valid if:
-    /// - object_matches == object_match_target
+    /// Returns `true` if the object logic is met by ObjectParameters valid according to
+    /// their `CountType` (by default, every one of them must be).  An ObjectParameter is
+    /// valid if:
+    /// - AtLeast object parameters have count >= count_target
     /// - EqualTo object parameters have count == count_target
     /// - LessThan object parameters have count < count_target
     pub(crate) fn is_valid(&self) -> bool {
-        self.object_params.iter().all(|p| p.is_valid())
+        self.logic.is_met(&self.object_params)
+            && self.empower.as_ref().is_none_or(|e| e.is_valid())
+            && self.same_depth.iter().all(|group| self.is_same_depth(group))
+            && self.within.iter().all(|within| within.is_valid(&self.object_params))
+            && self.totals.iter().all(|total| total.is_valid(&self.object_params))
     }  
+    /// Returns `true` if every parameter in the group is met on a single depth, using
+    /// only the objects matched on that depth.
+    fn is_same_depth(&self, group: &[usize]) -> bool {
+        let params: Vec<&ObjectParameter> = group.iter().map(|i| &self.object_params[*i]).collect();
+
+        params
+            .iter()
+            .flat_map(|p| p.depth_counts.iter().map(|(depth, _)| *depth))
+            .any(|depth| params.iter().all(|p| p.is_valid_count(p.count_at(depth))))
+    }
     /// Processes state of matches for the search and returns appropriate status.
     pub(crate) fn search_status(&mut self, match_resp: MatchResponse) -> SearchStatus {
         match match_resp {
@@ -463,6 +1494,30 @@ impl SearchParameters {
             MatchResponse::DoNothing => SearchStatus::InProgress,
         }
     }    
+    /// Returns the given file paths grouped by catalog directory (in `file_dirs` order),
+    /// leaving out empty groups.  Files outside every directory form a group of their own.
+    pub(crate) fn file_groups(&self, file_paths: &[PathBuf]) -> Vec<Vec<PathBuf>> {
+        let mut groups = self.dir_groups(file_paths);
+
+        groups.retain(|group| !group.is_empty());
+        groups
+    }
+    /// Returns the given file paths grouped by catalog directory:  one group per entry of
+    /// `file_dirs` (possibly empty), then a last group of files outside every directory.
+    pub(crate) fn dir_groups(&self, file_paths: &[PathBuf]) -> Vec<Vec<PathBuf>> {
+        let mut groups: Vec<Vec<PathBuf>> = vec![Vec::new(); self.file_dirs.len() + 1];
+
+        for path in file_paths.iter() {
+            let index = self
+                .file_dirs
+                .iter()
+                .position(|dir| path.starts_with(dir))
+                .unwrap_or(self.file_dirs.len());
+            groups[index].push(path.clone());
+        }
+
+        groups
+    }
     /// Manually sets file to open.  Used for testing.
     #[allow(dead_code)]
     pub(crate) fn set_file(&mut self, file: &str) {
@@ -479,42 +1534,226 @@ impl Default for SearchParameters {
             object_match_target: 0,   
             search_matches: 0,
             search_match_target: 10,   
+            unbounded: false,
+            seeds_scanned: 0,
+            rows_scanned: 0,
+            approx: None,
             debug: false,              
             depth_min: 1,
             depth_max: 6,
+            explain: false,
+            explain_json: false,
             file_paths: Vec::new(),
+            file_dirs: Vec::new(),
+            ordered: false,
+            relax: false,
+            param_stats: false,
+            met_depths: false,
+            vaults: false,
+            rescue: false,
+            sort_depth: false,
+            stream: false,
+            best: None,
+            random_seed: None,
+            cancel: CancelToken::new(),
+            output: None,
+            seed_sink: None,
+            progress: false,
+            nice: false,
+            plain_numbers: false,
+            fuzzy_terms: Vec::new(),
             format: FileFormat::Utf8,
+            schema: CatalogSchema::default(),
+            format_version: FORMAT_VERSION,
+            grade: None,
+            ally_weights: AllyWeights::default(),
+            score_expr: None,
+            seed_gold: HashMap::new(),
+            seed_optionals: HashMap::new(),
+            seed_met_depths: HashMap::new(),
+            seed_vaults: HashMap::new(),
+            seed_rescues: HashMap::new(),
+            name: None,
+            output_mode: OutputMode::Text,
+            template: None,
+            out_encoding: OutputEncoding::Utf8,
+            color: ColorMode::Auto,
+            rarity_cache: PathBuf::from(RARITY_CACHE_FILE),
+            rebuild_stale: false,
+            row_filters: Vec::new(),
+            save_db: None,
+            upload: None,
             seed_min: 1,
             seed_max: u32::MAX,
+            seed_list: None,
+            seed_step: 1,
+            write_seedlist: None,
+            export: None,
             verbosity: 3,
             object_params: Vec::new(),
+            param_order: Vec::new(),
+            logic: ParamTree::every(&[]),
+            empower: None,
+            same_depth: Vec::new(),
+            within: Vec::new(),
+            totals: Vec::new(),
+            no_malevolent: None,
+            exclusions: Vec::new(),
+            require_key: false,
         }
     }
 }
 
 impl std::fmt::Display for SearchParameters {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Search:\n")?;
+        writeln!(f, "Search:")?;
+
+        if let Some(name) = self.name.as_ref() {
+            writeln!(f, "     query: {}", name)?;
+        }
 
-        write!(f, " verbosity: {}\n", self.verbosity)?;
+        writeln!(f, " verbosity: {}", self.verbosity)?;
 
         match self.format {
-            FileFormat::Utf8 => write!(f, "    format: UTF-8\n")?,
-            FileFormat::Utf16 => write!(f, "    format: UTF-16LE\n")?,
+            FileFormat::Utf8 => writeln!(f, "    format: UTF-8")?,
+            FileFormat::Utf16 => writeln!(f, "    format: UTF-16LE")?,
+        }
+        if let Some(name) = self.schema.name.as_ref() {
+            writeln!(f, "    schema: {}", name)?;
+        }
+        if let Some(variant) = self.schema.variant.as_ref() {
+            writeln!(f, "   variant: {}", variant.name)?;
+        }
+
+        writeln!(f, "     depth: {} to {}", self.depth_min, self.depth_max)?;
+        match self.seed_min == self.seed_max {
+            true => writeln!(f, "      seed: {}", self.seed_min)?,
+            false => writeln!(
+                f,
+                "      seed: {} to {}",
+                self.number(self.seed_min.into()),
+                self.number(self.seed_max.into())
+            )?,
+        }
+        if self.seed_step > 1 {
+            writeln!(
+                f,
+                "      step: every {} seeds",
+                self.number(self.seed_step.into())
+            )?;
         }
 
-        write!(f, "     depth: {} to {}\n", self.depth_min, self.depth_max)?;
-        write!(f, "      seed: {} to {}\n", self.seed_min, self.seed_max)?;
-        write!(f, "Objects:\n")?;
+        let (outside, covered) = self.file_coverage();
+        match outside {
+            0 => writeln!(f, "     files: {}", self.file_paths.len())?,
+            _ => writeln!(
+                f,
+                "     files: {} ({} outside seed range)",
+                self.file_paths.len(),
+                outside
+            )?,
+        }
+        if let Some(covered) = covered {
+            let seeds = (self.seed_max - self.seed_min) as u64 + 1;
+            let percent = 100.0 * covered as f64 / seeds as f64;
+            writeln!(
+                f,
+                "  coverage: {} of {} seeds in range ({:.1}%)",
+                self.number(covered),
+                self.number(seeds),
+                percent
+            )?;
+        }
+
+        match self.seed_list.as_ref() {
+            Some(seeds) if seeds.len() <= 10 => {
+                let list: Vec<String> = seeds.iter().map(|seed| seed.to_string()).collect();
+                writeln!(
+                    f,
+                    "  seedlist: {} ({})",
+                    self.counted(seeds.len() as u64, "seed", "seeds"),
+                    list.join(", ")
+                )?;
+            }
+            Some(seeds) => writeln!(
+                f,
+                "  seedlist: {}",
+                self.counted(seeds.len() as u64, "seed", "seeds")
+            )?,
+            None => (),
+        }
+
+        for filter in self.row_filters.iter() {
+            writeln!(f, "     where: {}", filter)?;
+        }
+        if !self.logic.is_flat() {
+            writeln!(f, "     logic: {}", self.logic)?;
+        }
+        for group in self.same_depth.iter() {
+            let numbers: Vec<String> = group.iter().map(|i| (i + 1).to_string()).collect();
+            writeln!(f, "same depth: objects {}", numbers.join(", "))?;
+        }
+        for within in self.within.iter() {
+            writeln!(f, "    within: {}", within)?;
+        }
+        for total in self.totals.iter() {
+            writeln!(f, "     total: {}", total)?;
+        }
+        if let Some(depth) = self.no_malevolent {
+            writeln!(f, "   exclude: malevolent objects to depth {}", depth)?;
+        }
+        for exclusion in self.exclusions.iter() {
+            writeln!(f, "   exclude: {}", exclusion)?;
+        }
+        if self.require_key {
+            writeln!(f, "    vaults: key on same depth required")?;
+        }
+
+        for (term, kind) in self.fuzzy_terms.iter() {
+            writeln!(f, "     fuzzy: '{}' read as '{}'", term, kind)?;
+        }
+
+        writeln!(f, "Objects:")?;
         
         for param in self.object_params.iter() {
             write!(f, "{}", param)?;
         }
+        if let Some(empower) = self.empower.as_ref() {
+            write!(f, "{}", empower)?;
+        }
 
         Ok(())
     }
 }
 
+/// Returns the values of a category's search argument, with the words of multi-word kinds
+/// joined, and any that `--fuzzy` reads as other kinds replaced (and recorded in
+/// `fuzzy_terms`).
+fn category_values(
+    matches: &clap::ArgMatches,
+    name: &str,
+    category: Category,
+    fuzzy: bool,
+    fuzzy_terms: &mut Vec<(String, String)>,
+) -> Option<Vec<String>> {
+    let values = matches.values_of(name)?.map(String::from).collect();
+
+    let values = join_kind_tokens(category, values)
+        .into_iter()
+        .map(
+            |value| match fuzzy.then(|| fuzzy_kind(category, &value)).flatten() {
+                Some(kind) => {
+                    fuzzy_terms.push((value, kind.clone()));
+                    kind
+                }
+                None => value,
+            },
+        )
+        .collect();
+
+    Some(values)
+}
+
 /// Checks if `PrepParam` struct is valid `SearchParameter` based on `Category`.
 /// If so, converts it and adds to Vec of parameters. Most categories need only be 
 // non-empty (at least one value is `Some` or `true`).
@@ -529,7 +1768,7 @@ pub fn add_parameter(
 
     match category {
         Food | Gold => {
-            if prep.count.is_none() {
+            if prep.count.is_none() && prep.stages.is_empty() {
                 params.push(
                     Err(anyhow!("COUNT is required for the '{}' category", category))
                 );    
@@ -548,3 +1787,34 @@ pub fn add_parameter(
     params.push(param);
     *prep = PrepParams::new();
 }
+
+/// Parses a seed (1 to 4294967295), as given to `what` (e.g. "--seed").
+pub(crate) fn parse_seed(value: &str, what: &str) -> Result<u32> {
+    match value.parse::<u32>() {
+        Ok(seed) if seed > 0 => Ok(seed),
+        _ => Err(anyhow!("{} must be from 1 to 4294967295", what)),
+    }
+}
+
+/// Returns the order in which object parameters (indices) are checked against each Record:
+/// most selective first, so most Records are rejected (or matched) sooner.  A Record only
+/// counts toward the first parameter it matches, so parameters that can match the same
+/// categories keep the order given.
+pub(crate) fn evaluation_order(params: &[ObjectParameter]) -> Vec<usize> {
+    let mut order: Vec<usize> = Vec::with_capacity(params.len());
+
+    for (i, param) in params.iter().enumerate() {
+        // Must stay after every earlier parameter sharing a category
+        let first = order
+            .iter()
+            .rposition(|j| params[*j].category_flags.intersects(param.category_flags))
+            .map_or(0, |position| position + 1);
+        let position = (first..order.len())
+            .find(|p| param.selectivity() < params[order[*p]].selectivity())
+            .unwrap_or(order.len());
+
+        order.insert(position, i);
+    }
+
+    order
+}
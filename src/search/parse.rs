@@ -1,7 +1,7 @@
 //! Search parsing functionality for Brogue Seed Scanner.
 
 use crate::search::*;
-use crate::search::params::{PrepParams, add_parameter};
+use crate::search::params::{Empower, PrepParams, Total, Within, add_parameter};
 
 //  #######     ##     #######    ######   ########
 //  ##    ##  ##  ##   ##    ##  ##        ##
@@ -13,12 +13,20 @@ use crate::search::params::{PrepParams, add_parameter};
 pub(crate) enum ParseResult {
     NoMatch,
     Count(CountType, u32),
+    Stage(u32, u8),
     Depth(u8),
     Enchantment(i8),
     InVault(bool),
     Kind,
     Runic,
     AnyRunic,
+    RunicFamily(&'static str),
+    MaxStrength(u8),
+    TwoHanded(bool),
+    Thrown,
+    MinDamageTier(u8),
+    MinArmorValue(u8),
+    MaxCharges(u8),
     AllyStatus,
     LegendaryAlly,
     Mutation,
@@ -26,6 +34,132 @@ pub(crate) enum ParseResult {
     MagicType(MagicType),
 }
 
+/// Splits the optional marker (a `?` or `opt:` prefix) from a search argument.  Any
+/// argument of an object with the marker makes the object optional.  A marker on its own
+/// is left as is (and isn't a valid term).
+fn split_optional(value: &str) -> (&str, bool) {
+    match value.strip_prefix('?').or_else(|| value.strip_prefix("opt:")) {
+        Some(value) if !value.is_empty() => (value, true),
+        _ => (value, false),
+    }
+}
+
+/// Joins adjacent search arguments that together name one of the category's multi-word
+/// kinds or runics (e.g. `war` `axe`, `scroll` `of` `magic` `mapping`, or `potion` `of`
+/// `life`), so unquoted names aren't read as separate terms.  The longest name is joined
+/// first.
+pub(crate) fn join_kind_tokens(category: Category, values: Vec<String>) -> Vec<String> {
+    // Single-word names are only joined after the category's prefix (e.g. `potion of`)
+    let mut names: Vec<&str> = category.kinds_matching("");
+    names.extend(category.runics_matching(""));
+
+    let name_of = |words: &[String]| {
+        let (first, _) = split_optional(&words[0]);
+        let phrase = std::iter::once(first)
+            .chain(words[1..].iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let prefix = format!("{} of ", category);
+
+        names.iter().copied().find(|name| {
+            phrase == *name || phrase.strip_prefix(prefix.as_str()) == Some(*name)
+        })
+    };
+
+    let mut joined = Vec::with_capacity(values.len());
+    let mut i = 0;
+
+    while i < values.len() {
+        let name = (i + 2..=values.len())
+            .rev()
+            .find_map(|end| name_of(&values[i..end]).map(|name| (end, name)));
+
+        match name {
+            Some((end, name)) => {
+                let (first, _) = split_optional(&values[i]);
+                joined.push(format!(
+                    "{}{}",
+                    &values[i][..values[i].len() - first.len()],
+                    name
+                ));
+                i = end;
+            }
+            None => {
+                joined.push(values[i].clone());
+                i += 1;
+            }
+        }
+    }
+
+    joined
+}
+
+/// Most edits (insertions, deletions, or substitutions) between a `--fuzzy` search
+/// argument and the kind it's read as.
+const FUZZY_DISTANCE: usize = 2;
+
+/// Returns the number of single-character edits that turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + (ca != *cb) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Reads a search argument that isn't a valid term of the category as the nearest of
+/// the category's kinds (for `--fuzzy`), keeping any optional marker.  Returns `None` if
+/// the argument is valid as given, or if no kind is within `FUZZY_DISTANCE` edits (fewer
+/// for short arguments).
+pub(crate) fn fuzzy_kind(category: Category, value: &str) -> Option<String> {
+    let (term, _) = split_optional(value);
+    let parse_value: fn(&str) -> ParseResult = match category {
+        Category::Ally => parse_ally_value,
+        Category::Altar => parse_altar_value,
+        Category::Armor => parse_armor_value,
+        Category::Charm => parse_charm_value,
+        Category::Food => parse_food_value,
+        Category::Potion => parse_potion_value,
+        Category::Ring => parse_ring_value,
+        Category::Scroll => parse_scroll_value,
+        Category::Staff => parse_staff_value,
+        Category::Wand => parse_wand_value,
+        Category::Weapon => parse_weapon_value,
+        _ => return None,
+    };
+
+    if !matches!(parse_value(term), ParseResult::NoMatch) {
+        return None;
+    }
+
+    let (distance, name) = category
+        .kinds_matching("")
+        .into_iter()
+        .map(|name| (edit_distance(term, name), name))
+        .min_by_key(|(distance, _)| *distance)?;
+
+    match distance <= FUZZY_DISTANCE.min(term.chars().count() / 3) {
+        true => Some(format!("{}{}", &value[..value.len() - term.len()], name)),
+        false => None,
+    }
+}
+
+/// Returns `true` if a search argument lists runics separated by `|` (e.g.
+/// `speed|quietus`), each a partial match of a runic.
+fn is_runic_alternatives(value: &str, is_runic: impl Fn(&str) -> bool) -> bool {
+    value.contains('|') && value.split('|').all(|runic| !runic.is_empty() && is_runic(runic))
+}
+
 /// Attempts to parse a `u32` COUNT value from a search argument.
 #[inline]
 fn parse_count(value: &str) -> Option<(CountType, u32)> {
@@ -53,16 +187,22 @@ fn parse_count(value: &str) -> Option<(CountType, u32)> {
 fn parse_depth(value: &str) -> Option<u8> {
     // Check if 1st char is `d`, then parse a `u8` for remaining chars.
     if value.starts_with('d') {
-        match value.trim_start_matches('d').parse::<u8>() {
-            Ok(d) => Some(d),
-            Err(_) => None,
-        }
+        value.trim_start_matches('d').parse::<u8>().ok()
     } 
     else { 
         None 
     }
 }
 
+/// Attempts to parse an `N@dD` STAGE value (at least N by depth D) from a search argument.
+/// A stage of 0 isn't valid (it would always be met, but set a COUNT of none).
+fn parse_stage(value: &str) -> Option<(u32, u8)> {
+    let (count, depth) = value.split_once("@d")?;
+    let count = count.parse::<u32>().ok().filter(|count| *count > 0)?;
+
+    Some((count, depth.parse::<u8>().ok()?))
+}
+
 /// Attempts to parse a `+`/`-` `i8` ENCHANTMENT value from a search argument.
 fn parse_enchantment(value: &str) -> Option<i8> {
     // Check if 1st char is `+` or `-`, then parse an `i8` for remaining chars.
@@ -70,7 +210,7 @@ fn parse_enchantment(value: &str) -> Option<i8> {
         return value.trim_start_matches('+').parse::<i8>().ok();
     } else if value.ends_with('-') {
         if let Ok(num) = value.trim_end_matches('-').parse::<i8>() {
-            return Some(num * -1);
+            return Some(-num);
         }
     }
     None
@@ -86,6 +226,31 @@ fn parse_positive_enchantment(value: &str) -> Option<i8> {
     None
 }
 
+/// Attempts to parse an `N-` CHARGES value (at most N charges) from a search argument.
+fn parse_max_charges(value: &str) -> Option<u8> {
+    value.strip_suffix('-')?.parse::<u8>().ok()
+}
+
+/// Attempts to parse a `str<=N` STRENGTH value from a search argument.
+fn parse_max_strength(value: &str) -> Option<u8> {
+    value.strip_prefix("str<=")?.parse::<u8>().ok()
+}
+
+/// Attempts to parse an `av>=N` ARMOR VALUE value from a search argument.
+fn parse_min_armor_value(value: &str) -> Option<u8> {
+    value.strip_prefix("av>=")?.parse::<u8>().ok()
+}
+
+/// Attempts to parse a `dmg>=tierN` (or `dmg>=N`) DAMAGE value from a search argument.
+fn parse_min_damage_tier(value: &str) -> Option<u8> {
+    let tier = value.strip_prefix("dmg>=")?;
+
+    match tier.strip_prefix("tier").unwrap_or(tier).parse::<u8>() {
+        Ok(tier @ 1..=3) => Some(tier),
+        _ => None,
+    }
+}
+
 /// Attempts to parse a `vault`/`novault` VAULT value from a search argument.
 fn parse_in_vault(value: &str) -> Option<bool> {
     if value == "vault" {
@@ -112,6 +277,9 @@ fn parse_magic(value: &str) -> Option<MagicType> {
 
 /// Attempts to parse an altar value from a search argument.
 fn parse_altar_value(value: &str) -> ParseResult {
+    if let Some((c, d)) = parse_stage(value) {
+        return ParseResult::Stage(c, d);
+    }
     if let Some((t, c)) = parse_count(value) {
         return ParseResult::Count(t, c);
     }
@@ -127,6 +295,9 @@ fn parse_altar_value(value: &str) -> ParseResult {
 
 /// Attempts to parse an ally value from a search argument.
 fn parse_ally_value(value: &str) -> ParseResult {
+    if let Some((c, d)) = parse_stage(value) {
+        return ParseResult::Stage(c, d);
+    }
     if let Some((t, c)) = parse_count(value) {
         return ParseResult::Count(t, c);
     }
@@ -160,6 +331,9 @@ fn parse_armor_value(value: &str) -> ParseResult {
     if let Some(e) = parse_enchantment(value) {
         return ParseResult::Enchantment(e);
     }
+    if let Some((c, d)) = parse_stage(value) {
+        return ParseResult::Stage(c, d);
+    }
     if let Some((t, c)) = parse_count(value) {
         return ParseResult::Count(t, c);
     }
@@ -170,6 +344,16 @@ fn parse_armor_value(value: &str) -> ParseResult {
     if value == "runic" {
         return ParseResult::AnyRunic;
     }
+    // Special case with "anyimmunity" term will look for any monster immunity runic.
+    if value == "anyimmunity" {
+        return ParseResult::RunicFamily("immunity");
+    }
+    if let Some(s) = parse_max_strength(value) {
+        return ParseResult::MaxStrength(s);
+    }
+    if let Some(a) = parse_min_armor_value(value) {
+        return ParseResult::MinArmorValue(a);
+    }
     // Partial matches (kind prioritized over runic)
     if ArmorKind::parse_partial(value).is_some() {
         return ParseResult::Kind;
@@ -177,6 +361,9 @@ fn parse_armor_value(value: &str) -> ParseResult {
     if ArmorRunic::parse_partial(value).is_some() {
         return ParseResult::Runic;
     }
+    if is_runic_alternatives(value, |runic| ArmorRunic::parse_partial(runic).is_some()) {
+        return ParseResult::Runic;
+    }
     if let Some(v) = parse_in_vault(value) {
         return ParseResult::InVault(v);
     }
@@ -192,6 +379,9 @@ fn parse_charm_value(value: &str) -> ParseResult {
     if let Some(e) = parse_positive_enchantment(value) {
         return ParseResult::Enchantment(e);
     }
+    if let Some((c, d)) = parse_stage(value) {
+        return ParseResult::Stage(c, d);
+    }
     if let Some((t, c)) = parse_count(value) {
         return ParseResult::Count(t, c);
     }
@@ -213,6 +403,9 @@ fn parse_equipment_value(value: &str) -> ParseResult {
     if let Some(e) = parse_enchantment(value) {
         return ParseResult::Enchantment(e);
     }
+    if let Some((c, d)) = parse_stage(value) {
+        return ParseResult::Stage(c, d);
+    }
     if let Some((t, c)) = parse_count(value) {
         return ParseResult::Count(t, c);
     }
@@ -236,6 +429,9 @@ fn parse_equipment_value(value: &str) -> ParseResult {
 
 /// Attempts to parse a food value from a search argument.
 fn parse_food_value(value: &str) -> ParseResult {
+    if let Some((c, d)) = parse_stage(value) {
+        return ParseResult::Stage(c, d);
+    }
     if let Some((t, c)) = parse_count(value) {
         return ParseResult::Count(t, c);
     }
@@ -250,6 +446,9 @@ fn parse_food_value(value: &str) -> ParseResult {
 
 /// Attempts to parse a gold value from a search argument.
 fn parse_gold_value(value: &str) -> ParseResult {
+    if let Some((c, d)) = parse_stage(value) {
+        return ParseResult::Stage(c, d);
+    }
     if let Some((t, c)) = parse_count(value) {
         return ParseResult::Count(t, c);
     }
@@ -264,6 +463,9 @@ fn parse_item_value(value: &str) -> ParseResult {
     if let Some(e) = parse_enchantment(value) {
         return ParseResult::Enchantment(e);
     }
+    if let Some((c, d)) = parse_stage(value) {
+        return ParseResult::Stage(c, d);
+    }
     if let Some((t, c)) = parse_count(value) {
         return ParseResult::Count(t, c);
     }
@@ -287,6 +489,9 @@ fn parse_item_value(value: &str) -> ParseResult {
 
 /// Attempts to parse a potion value from a search argument.
 fn parse_potion_value(value: &str) -> ParseResult {
+    if let Some((c, d)) = parse_stage(value) {
+        return ParseResult::Stage(c, d);
+    }
     if let Some((t, c)) = parse_count(value) {
         return ParseResult::Count(t, c);
     }
@@ -311,6 +516,9 @@ fn parse_ring_value(value: &str) -> ParseResult {
     if let Some(e) = parse_enchantment(value) {
         return ParseResult::Enchantment(e);
     }
+    if let Some((c, d)) = parse_stage(value) {
+        return ParseResult::Stage(c, d);
+    }
     if let Some((t, c)) = parse_count(value) {
         return ParseResult::Count(t, c);
     }
@@ -332,6 +540,9 @@ fn parse_ring_value(value: &str) -> ParseResult {
 
 /// Attempts to parse a scroll value from a search argument.
 fn parse_scroll_value(value: &str) -> ParseResult {
+    if let Some((c, d)) = parse_stage(value) {
+        return ParseResult::Stage(c, d);
+    }
     if let Some((t, c)) = parse_count(value) {
         return ParseResult::Count(t, c);
     }
@@ -356,6 +567,12 @@ fn parse_staff_value(value: &str) -> ParseResult {
     if let Some(e) = parse_positive_enchantment(value) {
         return ParseResult::Enchantment(e);
     }
+    if let Some(c) = parse_max_charges(value) {
+        return ParseResult::MaxCharges(c);
+    }
+    if let Some((c, d)) = parse_stage(value) {
+        return ParseResult::Stage(c, d);
+    }
     if let Some((t, c)) = parse_count(value) {
         return ParseResult::Count(t, c);
     }
@@ -380,6 +597,12 @@ fn parse_wand_value(value: &str) -> ParseResult {
     if let Some(e) = parse_positive_enchantment(value) {
         return ParseResult::Enchantment(e);
     }
+    if let Some(c) = parse_max_charges(value) {
+        return ParseResult::MaxCharges(c);
+    }
+    if let Some((c, d)) = parse_stage(value) {
+        return ParseResult::Stage(c, d);
+    }
     if let Some((t, c)) = parse_count(value) {
         return ParseResult::Count(t, c);
     }
@@ -404,6 +627,9 @@ fn parse_weapon_value(value: &str) -> ParseResult {
     if let Some(e) = parse_enchantment(value) {
         return ParseResult::Enchantment(e);
     }
+    if let Some((c, d)) = parse_stage(value) {
+        return ParseResult::Stage(c, d);
+    }
     if let Some((t, c)) = parse_count(value) {
         return ParseResult::Count(t, c);
     }
@@ -414,6 +640,26 @@ fn parse_weapon_value(value: &str) -> ParseResult {
     if value == "runic" {
         return ParseResult::AnyRunic;
     }
+    // Special case: "anyslaying" term will look for any monster slaying runic.
+    if value == "anyslaying" {
+        return ParseResult::RunicFamily("slaying");
+    }
+    if let Some(s) = parse_max_strength(value) {
+        return ParseResult::MaxStrength(s);
+    }
+    if let Some(t) = parse_min_damage_tier(value) {
+        return ParseResult::MinDamageTier(t);
+    }
+    // Special case: "1h" and "2h" terms will look for one- or two-handed weapons.
+    match value {
+        "1h" => return ParseResult::TwoHanded(false),
+        "2h" => return ParseResult::TwoHanded(true),
+        _ => (),
+    }
+    // Special case: "thrown" term will look for darts, incendiary darts, and javelins.
+    if value == "thrown" {
+        return ParseResult::Thrown;
+    }
     // Partial matches (kind prioritized over runic)
     if WeaponKind::parse_partial(value).is_some() {
         return ParseResult::Kind;
@@ -421,6 +667,9 @@ fn parse_weapon_value(value: &str) -> ParseResult {
     if WeaponRunic::parse_partial(value).is_some() {
         return ParseResult::Runic;
     }
+    if is_runic_alternatives(value, |runic| WeaponRunic::parse_partial(runic).is_some()) {
+        return ParseResult::Runic;
+    }
     if let Some(v) = parse_in_vault(value) {
         return ParseResult::InVault(v);
     }
@@ -432,11 +681,12 @@ fn parse_weapon_value(value: &str) -> ParseResult {
 }
 
 /// Attempts to parse an `Ally` object from values of a search argument.
-pub fn parse_allies(values: clap::Values) -> Vec<Result<ObjectParameter>> {
+pub fn parse_allies<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<Result<ObjectParameter>> {
     let mut prep = PrepParams::default();    
     let mut params = Vec::with_capacity(1);
 
-    for value in values.into_iter() { 
+    for value in values.into_iter() {
+        let (value, optional) = split_optional(value);
 
         match parse_ally_value(value) {
             ParseResult::Count(count_type, new_count) => {
@@ -446,6 +696,7 @@ pub fn parse_allies(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 prep.count = Some(new_count);
                 prep.count_type = count_type;
             }   
+            ParseResult::Stage(count, depth) => prep.stages.push((count, depth)),
             ParseResult::Depth(new_depth) => {
                 if prep.depth.is_some() {                    
                     add_parameter(Category::Ally, &mut prep, &mut params);
@@ -484,6 +735,7 @@ pub fn parse_allies(values: clap::Values) -> Vec<Result<ObjectParameter>> {
             }            
             _ => params.push(Err(anyhow!("'{}' is not a valid ally search term!", value))),
         }
+        prep.optional |= optional;
     }
     add_parameter(Category::Ally, &mut prep, &mut params);
     
@@ -491,11 +743,12 @@ pub fn parse_allies(values: clap::Values) -> Vec<Result<ObjectParameter>> {
 }
 
 /// Attempts to parse an `Altar` object from values of a search argument.
-pub fn parse_altars(values: clap::Values) -> Vec<Result<ObjectParameter>> {
+pub fn parse_altars<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<Result<ObjectParameter>> {
     let mut prep = PrepParams::default();    
     let mut params = Vec::with_capacity(1);
 
-    for value in values.into_iter() {       
+    for value in values.into_iter() {
+        let (value, optional) = split_optional(value);
 
         match parse_altar_value(value) {
             ParseResult::Count(count_type, new_count) => {
@@ -505,6 +758,7 @@ pub fn parse_altars(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 prep.count = Some(new_count);
                 prep.count_type = count_type;
             }
+            ParseResult::Stage(count, depth) => prep.stages.push((count, depth)),
             ParseResult::Depth(new_depth) => {
                 if prep.depth.is_some() {                    
                     add_parameter(Category::Altar, &mut prep, &mut params);
@@ -519,6 +773,7 @@ pub fn parse_altars(values: clap::Values) -> Vec<Result<ObjectParameter>> {
             }
             _ => params.push(Err(anyhow!("'{}' is not a valid altar search term!", value))),
         }
+        prep.optional |= optional;
     }
     
     add_parameter(Category::Altar, &mut prep, &mut params);
@@ -527,11 +782,12 @@ pub fn parse_altars(values: clap::Values) -> Vec<Result<ObjectParameter>> {
 }
 
 /// Attempts to parse an `Armor` object from values of a search argument.
-pub fn parse_armors(values: clap::Values) -> Vec<Result<ObjectParameter>> {
+pub fn parse_armors<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<Result<ObjectParameter>> {
     let mut prep = PrepParams::default();    
     let mut params = Vec::with_capacity(1);
 
     for value in values.into_iter() {
+        let (value, optional) = split_optional(value);
 
         match parse_armor_value(value) {
             ParseResult::Count(count_type, new_count) => {
@@ -541,6 +797,7 @@ pub fn parse_armors(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 prep.count = Some(new_count);
                 prep.count_type = count_type;
             }
+            ParseResult::Stage(count, depth) => prep.stages.push((count, depth)),
             ParseResult::Depth(new_depth) => {
                 if prep.depth.is_some() {                    
                     add_parameter(Category::Armor, &mut prep, &mut params);
@@ -565,12 +822,30 @@ pub fn parse_armors(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 }
                 prep.runic = Some(value.to_owned());
             }
+            ParseResult::RunicFamily(family) => {
+                if prep.runic.is_some() || prep.any_runic {
+                    add_parameter(Category::Armor, &mut prep, &mut params);
+                }
+                prep.runic = Some(family.to_owned());
+            }
             ParseResult::AnyRunic => {
                 if prep.runic.is_some() || prep.any_runic {                    
                     add_parameter(Category::Armor, &mut prep, &mut params);
                 }
                 prep.any_runic = true;
             }
+            ParseResult::MaxStrength(strength) => {
+                if prep.max_strength.is_some() {
+                    add_parameter(Category::Armor, &mut prep, &mut params);
+                }
+                prep.max_strength = Some(strength);
+            }
+            ParseResult::MinArmorValue(armor_value) => {
+                if prep.min_armor_value.is_some() {
+                    add_parameter(Category::Armor, &mut prep, &mut params);
+                }
+                prep.min_armor_value = Some(armor_value);
+            }
             ParseResult::InVault(in_vault) => {
                 if prep.in_vault.is_some() {                    
                     add_parameter(Category::Armor, &mut prep, &mut params);
@@ -585,6 +860,7 @@ pub fn parse_armors(values: clap::Values) -> Vec<Result<ObjectParameter>> {
             }              
             _ => params.push(Err(anyhow!("'{}' is not a valid armor search term!", value))),
         }
+        prep.optional |= optional;
     }
 
     add_parameter(Category::Armor, &mut prep, &mut params);
@@ -593,11 +869,12 @@ pub fn parse_armors(values: clap::Values) -> Vec<Result<ObjectParameter>> {
 }
 
 /// Attempts to parse a `Charm` object from values of a search argument.
-pub fn parse_charms(values: clap::Values) -> Vec<Result<ObjectParameter>> {
+pub fn parse_charms<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<Result<ObjectParameter>> {
     let mut prep = PrepParams::default();    
     let mut params = Vec::with_capacity(1);
 
     for value in values.into_iter() {
+        let (value, optional) = split_optional(value);
 
         match parse_charm_value(value) {
             ParseResult::Count(count_type, new_count) => {
@@ -607,6 +884,7 @@ pub fn parse_charms(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 prep.count = Some(new_count);
                 prep.count_type = count_type;
             }
+            ParseResult::Stage(count, depth) => prep.stages.push((count, depth)),
             ParseResult::Depth(new_depth) => {
                 if prep.depth.is_some() {                    
                     add_parameter(Category::Charm, &mut prep, &mut params);
@@ -639,6 +917,7 @@ pub fn parse_charms(values: clap::Values) -> Vec<Result<ObjectParameter>> {
             }            
             _ => params.push(Err(anyhow!("'{}' is not a valid charm search term!", value))),
         }
+        prep.optional |= optional;
     }
 
     add_parameter(Category::Charm, &mut prep, &mut params);
@@ -647,11 +926,12 @@ pub fn parse_charms(values: clap::Values) -> Vec<Result<ObjectParameter>> {
 }
 
 /// Attempts to parse `Equipment` category objects from values of a search argument.
-pub fn parse_equipment(values: clap::Values) -> Vec<Result<ObjectParameter>> {
+pub fn parse_equipment<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<Result<ObjectParameter>> {
     let mut prep = PrepParams::default();    
     let mut params = Vec::with_capacity(1);
 
     for value in values.into_iter() {
+        let (value, optional) = split_optional(value);
 
         match parse_equipment_value(value) {
             ParseResult::Count(count_type, new_count) => {
@@ -661,6 +941,7 @@ pub fn parse_equipment(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 prep.count = Some(new_count);
                 prep.count_type = count_type;
             }
+            ParseResult::Stage(count, depth) => prep.stages.push((count, depth)),
             ParseResult::Depth(new_depth) => {
                 if prep.depth.is_some() {                    
                     add_parameter(Category::Equipment, &mut prep, &mut params);
@@ -693,6 +974,7 @@ pub fn parse_equipment(values: clap::Values) -> Vec<Result<ObjectParameter>> {
             }            
             _ => params.push(Err(anyhow!("'{}' is not a valid equipment search term!", value))),
         }
+        prep.optional |= optional;
     }
 
     add_parameter(Category::Equipment, &mut prep, &mut params);
@@ -701,11 +983,12 @@ pub fn parse_equipment(values: clap::Values) -> Vec<Result<ObjectParameter>> {
 }
 
 /// Attempts to parse a `Food` object from values of a search argument.
-pub fn parse_food(values: clap::Values) -> Vec<Result<ObjectParameter>> {
+pub fn parse_food<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<Result<ObjectParameter>> {
     let mut prep = PrepParams::default();    
     let mut params = Vec::with_capacity(1);
 
     for value in values.into_iter() {
+        let (value, optional) = split_optional(value);
 
         match parse_food_value(value) {
             ParseResult::Count(count_type, new_count) => {
@@ -715,6 +998,7 @@ pub fn parse_food(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 prep.count = Some(new_count);
                 prep.count_type = count_type;
             }
+            ParseResult::Stage(count, depth) => prep.stages.push((count, depth)),
             ParseResult::Depth(new_depth) => {
                 if prep.depth.is_some() {                    
                     add_parameter(Category::Food, &mut prep, &mut params);
@@ -729,6 +1013,7 @@ pub fn parse_food(values: clap::Values) -> Vec<Result<ObjectParameter>> {
             }
             _ => params.push(Err(anyhow!("'{}' is not a valid food search term!", value))),
         }
+        prep.optional |= optional;
     }
 
     add_parameter(Category::Food, &mut prep, &mut params);
@@ -737,11 +1022,12 @@ pub fn parse_food(values: clap::Values) -> Vec<Result<ObjectParameter>> {
 }
 
 /// Attempts to parse a `Gold` object from values of a search argument.
-pub fn parse_gold(values: clap::Values) -> Vec<Result<ObjectParameter>> {
+pub fn parse_gold<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<Result<ObjectParameter>> {
     let mut prep = PrepParams::default();    
     let mut params = Vec::with_capacity(1);
 
     for value in values.into_iter() {
+        let (value, optional) = split_optional(value);
 
         match parse_gold_value(value) {
             ParseResult::Count(count_type, new_count) => {
@@ -751,6 +1037,7 @@ pub fn parse_gold(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 prep.count = Some(new_count);
                 prep.count_type = count_type;
             }
+            ParseResult::Stage(count, depth) => prep.stages.push((count, depth)),
             ParseResult::Depth(new_depth) => {
                 if prep.depth.is_some() {                    
                     add_parameter(Category::Gold, &mut prep, &mut params);
@@ -759,6 +1046,7 @@ pub fn parse_gold(values: clap::Values) -> Vec<Result<ObjectParameter>> {
             }  
             _ => params.push(Err(anyhow!("'{}' is not a valid gold search term!", value))),
         }
+        prep.optional |= optional;
     }
 
     add_parameter(Category::Gold, &mut prep, &mut params);
@@ -767,11 +1055,12 @@ pub fn parse_gold(values: clap::Values) -> Vec<Result<ObjectParameter>> {
 }
 
 /// Attempts to parse `Item` category objects from values of a search argument.
-pub fn parse_items(values: clap::Values) -> Vec<Result<ObjectParameter>> {
+pub fn parse_items<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<Result<ObjectParameter>> {
     let mut prep = PrepParams::default();    
     let mut params = Vec::with_capacity(1);
 
     for value in values.into_iter() {
+        let (value, optional) = split_optional(value);
 
         match parse_item_value(value) {
             ParseResult::Count(count_type, new_count) => {
@@ -781,6 +1070,7 @@ pub fn parse_items(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 prep.count = Some(new_count);
                 prep.count_type = count_type;
             }
+            ParseResult::Stage(count, depth) => prep.stages.push((count, depth)),
             ParseResult::Depth(new_depth) => {
                 if prep.depth.is_some() {                    
                     add_parameter(Category::Item, &mut prep, &mut params);
@@ -813,6 +1103,7 @@ pub fn parse_items(values: clap::Values) -> Vec<Result<ObjectParameter>> {
             }
             _ => params.push(Err(anyhow!("'{}' is not a valid item search term!", value))),
         }
+        prep.optional |= optional;
     }
 
     add_parameter(Category::Item, &mut prep, &mut params);
@@ -820,12 +1111,37 @@ pub fn parse_items(values: clap::Values) -> Vec<Result<ObjectParameter>> {
     params
 }
 
+/// Attempts to parse the potions of strength of a `--strength` argument:  a `Potion`
+/// object of the strength kind, with only COUNT, DEPTH, stage, and VAULT values.
+pub fn parse_strength<'a>(
+    values: impl IntoIterator<Item = &'a str>,
+) -> Vec<Result<ObjectParameter>> {
+    let values: Vec<&str> = values.into_iter().collect();
+
+    for value in values.iter() {
+        let (term, _) = split_optional(value);
+
+        if !matches!(
+            parse_potion_value(term),
+            ParseResult::Count(..)
+                | ParseResult::Depth(_)
+                | ParseResult::Stage(..)
+                | ParseResult::InVault(_)
+        ) {
+            return vec![Err(anyhow!("'{}' is not a valid strength search term!", value))];
+        }
+    }
+
+    parse_potions(std::iter::once("strength").chain(values))
+}
+
 /// Attempts to parse a `Potion` object from values of a search argument.
-pub fn parse_potions(values: clap::Values) -> Vec<Result<ObjectParameter>> {
+pub fn parse_potions<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<Result<ObjectParameter>> {
     let mut prep = PrepParams::default();    
     let mut params = Vec::with_capacity(1);
 
     for value in values.into_iter() {
+        let (value, optional) = split_optional(value);
 
         match parse_potion_value(value) {
             ParseResult::Count(count_type, new_count) => {
@@ -835,6 +1151,7 @@ pub fn parse_potions(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 prep.count = Some(new_count);
                 prep.count_type = count_type;
             }
+            ParseResult::Stage(count, depth) => prep.stages.push((count, depth)),
             ParseResult::Depth(new_depth) => {
                 if prep.depth.is_some() {                    
                     add_parameter(Category::Potion, &mut prep, &mut params);
@@ -861,6 +1178,7 @@ pub fn parse_potions(values: clap::Values) -> Vec<Result<ObjectParameter>> {
             }
             _ => params.push(Err(anyhow!("'{}' is not a valid potion search term!", value))),
         }
+        prep.optional |= optional;
     }
 
     add_parameter(Category::Potion, &mut prep, &mut params);
@@ -869,11 +1187,12 @@ pub fn parse_potions(values: clap::Values) -> Vec<Result<ObjectParameter>> {
 }
 
 /// Attempts to parse a `Ring` object from values of a search argument.
-pub fn parse_rings(values: clap::Values) -> Vec<Result<ObjectParameter>> {
+pub fn parse_rings<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<Result<ObjectParameter>> {
     let mut prep = PrepParams::default();    
     let mut params = Vec::with_capacity(1);
 
     for value in values.into_iter() {
+        let (value, optional) = split_optional(value);
 
         match parse_ring_value(value) {
             ParseResult::Count(count_type, new_count) => {
@@ -883,6 +1202,7 @@ pub fn parse_rings(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 prep.count = Some(new_count);
                 prep.count_type = count_type;
             }
+            ParseResult::Stage(count, depth) => prep.stages.push((count, depth)),
             ParseResult::Depth(new_depth) => {
                 if prep.depth.is_some() {                    
                     add_parameter(Category::Ring, &mut prep, &mut params);
@@ -915,6 +1235,7 @@ pub fn parse_rings(values: clap::Values) -> Vec<Result<ObjectParameter>> {
             }            
             _ => params.push(Err(anyhow!("'{}' is not a valid ring search term!", value))),
         }
+        prep.optional |= optional;
     }
 
     add_parameter(Category::Ring, &mut prep, &mut params);
@@ -923,11 +1244,12 @@ pub fn parse_rings(values: clap::Values) -> Vec<Result<ObjectParameter>> {
 }
 
 /// Attempts to parse a `Scroll` object from values of a search argument.
-pub fn parse_scrolls(values: clap::Values) -> Vec<Result<ObjectParameter>> {
+pub fn parse_scrolls<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<Result<ObjectParameter>> {
     let mut prep = PrepParams::default();    
     let mut params = Vec::with_capacity(1);
 
     for value in values.into_iter() {
+        let (value, optional) = split_optional(value);
         match parse_scroll_value(value) {
             ParseResult::Count(count_type, new_count) => {
                 if prep.count.is_some() {                    
@@ -936,6 +1258,7 @@ pub fn parse_scrolls(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 prep.count = Some(new_count);
                 prep.count_type = count_type;
             }
+            ParseResult::Stage(count, depth) => prep.stages.push((count, depth)),
             ParseResult::Depth(new_depth) => {
                 if prep.depth.is_some() {                    
                     add_parameter(Category::Scroll, &mut prep, &mut params);
@@ -962,6 +1285,7 @@ pub fn parse_scrolls(values: clap::Values) -> Vec<Result<ObjectParameter>> {
             }            
             _ => params.push(Err(anyhow!("'{}' is not a valid scroll search term!", value))),
         }
+        prep.optional |= optional;
     }
 
     add_parameter(Category::Scroll, &mut prep, &mut params);
@@ -970,11 +1294,12 @@ pub fn parse_scrolls(values: clap::Values) -> Vec<Result<ObjectParameter>> {
 }
 
 /// Attempts to parse a `Staff` object from values of a search argument.
-pub fn parse_staves(values: clap::Values) -> Vec<Result<ObjectParameter>> {
+pub fn parse_staves<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<Result<ObjectParameter>> {
     let mut prep = PrepParams::default();    
     let mut params = Vec::with_capacity(1);
 
     for value in values.into_iter() {
+        let (value, optional) = split_optional(value);
 
         match parse_staff_value(value) {
             ParseResult::Count(count_type, new_count) => {
@@ -984,6 +1309,7 @@ pub fn parse_staves(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 prep.count = Some(new_count);
                 prep.count_type = count_type;
             }
+            ParseResult::Stage(count, depth) => prep.stages.push((count, depth)),
             ParseResult::Depth(new_depth) => {
                 if prep.depth.is_some() {                    
                     add_parameter(Category::Staff, &mut prep, &mut params);
@@ -996,6 +1322,12 @@ pub fn parse_staves(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 }
                 prep.enchantment = Some(new_enchantment);
             }
+            ParseResult::MaxCharges(charges) => {
+                if prep.max_charges.is_some() {
+                    add_parameter(Category::Staff, &mut prep, &mut params);
+                }
+                prep.max_charges = Some(charges);
+            }
             ParseResult::Kind => {
                 if prep.kind.is_some() {                    
                     add_parameter(Category::Staff, &mut prep, &mut params);
@@ -1016,6 +1348,7 @@ pub fn parse_staves(values: clap::Values) -> Vec<Result<ObjectParameter>> {
             }            
             _ => params.push(Err(anyhow!("'{}' is not a valid staff search term!", value))),
         }
+        prep.optional |= optional;
     }
 
     add_parameter(Category::Staff, &mut prep, &mut params);
@@ -1024,11 +1357,12 @@ pub fn parse_staves(values: clap::Values) -> Vec<Result<ObjectParameter>> {
 }
 
 /// Attempts to parse a `Wand` object from values of a search argument.
-pub fn parse_wands(values: clap::Values) -> Vec<Result<ObjectParameter>> {
+pub fn parse_wands<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<Result<ObjectParameter>> {
     let mut prep = PrepParams::default();    
     let mut params = Vec::with_capacity(1);
 
     for value in values.into_iter() {
+        let (value, optional) = split_optional(value);
 
         match parse_wand_value(value) {
             ParseResult::Count(count_type, new_count) => {
@@ -1038,6 +1372,7 @@ pub fn parse_wands(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 prep.count = Some(new_count);
                 prep.count_type = count_type;
             }
+            ParseResult::Stage(count, depth) => prep.stages.push((count, depth)),
             ParseResult::Depth(new_depth) => {
                 if prep.depth.is_some() {                    
                     add_parameter(Category::Wand, &mut prep, &mut params);
@@ -1050,6 +1385,12 @@ pub fn parse_wands(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 }
                 prep.enchantment = Some(new_enchantment);
             }
+            ParseResult::MaxCharges(charges) => {
+                if prep.max_charges.is_some() {
+                    add_parameter(Category::Wand, &mut prep, &mut params);
+                }
+                prep.max_charges = Some(charges);
+            }
             ParseResult::Kind => {
                 if prep.kind.is_some() {                    
                     add_parameter(Category::Wand, &mut prep, &mut params);
@@ -1070,6 +1411,7 @@ pub fn parse_wands(values: clap::Values) -> Vec<Result<ObjectParameter>> {
             }            
             _ => params.push(Err(anyhow!("'{}' is not a valid wand search term!", value))),
         }
+        prep.optional |= optional;
     }
 
     add_parameter(Category::Wand, &mut prep, &mut params);
@@ -1078,11 +1420,12 @@ pub fn parse_wands(values: clap::Values) -> Vec<Result<ObjectParameter>> {
 }
 
 /// Attempts to parse a `Weapon` object from values of a search argument.
-pub fn parse_weapons(values: clap::Values) -> Vec<Result<ObjectParameter>> {
+pub fn parse_weapons<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<Result<ObjectParameter>> {
     let mut prep = PrepParams::default();    
     let mut params = Vec::with_capacity(1);
 
     for value in values.into_iter() {
+        let (value, optional) = split_optional(value);
 
         match parse_weapon_value(value) {
             ParseResult::Count(count_type, new_count) => {
@@ -1092,6 +1435,7 @@ pub fn parse_weapons(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 prep.count = Some(new_count);
                 prep.count_type = count_type;
             }
+            ParseResult::Stage(count, depth) => prep.stages.push((count, depth)),
             ParseResult::Depth(new_depth) => {
                 if prep.depth.is_some() {                    
                     add_parameter(Category::Weapon, &mut prep, &mut params);
@@ -1116,12 +1460,42 @@ pub fn parse_weapons(values: clap::Values) -> Vec<Result<ObjectParameter>> {
                 }
                 prep.runic = Some(value.to_owned());
             }
+            ParseResult::RunicFamily(family) => {
+                if prep.runic.is_some() || prep.any_runic {
+                    add_parameter(Category::Weapon, &mut prep, &mut params);
+                }
+                prep.runic = Some(family.to_owned());
+            }
             ParseResult::AnyRunic => {
                 if prep.runic.is_some() || prep.any_runic {                    
                     add_parameter(Category::Weapon, &mut prep, &mut params);
                 }
                 prep.any_runic = true;
             }
+            ParseResult::MaxStrength(strength) => {
+                if prep.max_strength.is_some() {
+                    add_parameter(Category::Weapon, &mut prep, &mut params);
+                }
+                prep.max_strength = Some(strength);
+            }
+            ParseResult::TwoHanded(two_handed) => {
+                if prep.two_handed.is_some() {
+                    add_parameter(Category::Weapon, &mut prep, &mut params);
+                }
+                prep.two_handed = Some(two_handed);
+            }
+            ParseResult::Thrown => {
+                if prep.kind.is_some() || prep.thrown {
+                    add_parameter(Category::Weapon, &mut prep, &mut params);
+                }
+                prep.thrown = true;
+            }
+            ParseResult::MinDamageTier(tier) => {
+                if prep.min_damage_tier.is_some() {
+                    add_parameter(Category::Weapon, &mut prep, &mut params);
+                }
+                prep.min_damage_tier = Some(tier);
+            }
             ParseResult::InVault(in_vault) => {
                 if prep.in_vault.is_some() {                    
                     add_parameter(Category::Weapon, &mut prep, &mut params);
@@ -1136,9 +1510,115 @@ pub fn parse_weapons(values: clap::Values) -> Vec<Result<ObjectParameter>> {
             }            
             _ => params.push(Err(anyhow!("'{}' is not a valid weapon search term!", value))),
         }
+        prep.optional |= optional;
     }
 
     add_parameter(Category::Weapon, &mut prep, &mut params);
     
     params
 }
+
+/// Attempts to parse an `Empower` composite constraint from values of `--empower`:
+/// an optional CHARGES count (default 1) and an optional DEPTH (default `depth_max`).
+pub fn parse_empower(values: clap::Values, depth_max: u8) -> Result<Empower> {
+    let mut charges = None;
+    let mut depth = None;
+
+    for value in values.into_iter() {
+        match (parse_depth(value), parse_count(value)) {
+            (Some(d), _) if depth.is_none() => depth = Some(d),
+            (None, Some((CountType::AtLeast, c))) if charges.is_none() => charges = Some(c),
+            _ => return Err(anyhow!("'{}' is not a valid empower search term!", value)),
+        }
+    }
+
+    Ok(Empower::new(
+        charges.unwrap_or(1),
+        depth.unwrap_or(depth_max).min(depth_max),
+    ))
+}
+
+/// Attempts to parse a group of object parameters linked by depth:  comma-separated,
+/// 1-based object numbers (e.g. '1,3') or 'all'.  Returns zero-based indices.
+pub fn parse_param_group(value: &str, param_count: usize) -> Result<Vec<usize>> {
+    let mut group: Vec<usize> = match value {
+        "all" => (0..param_count).collect(),
+        _ => {
+            let mut group = Vec::new();
+
+            for number in value.split(',') {
+                match number.trim().parse::<usize>() {
+                    Ok(n) if (1..=param_count).contains(&n) => group.push(n - 1),
+                    _ => return Err(anyhow!(
+                        "'{}' is not a valid object number (1 to {})", number, param_count
+                    )),
+                }
+            }
+            group
+        }
+    };
+
+    group.sort_unstable();
+    group.dedup();
+
+    if group.len() < 2 {
+        return Err(anyhow!(
+            "object group '{}' must have at least 2 objects",
+            value
+        ));
+    }
+
+    Ok(group)
+}
+
+/// Attempts to parse a combined total in the form 'COUNT:OBJECTS', where OBJECTS is a
+/// group of object parameters (as for `parse_param_group`).
+pub fn parse_total(value: &str, param_count: usize) -> Result<Total> {
+    let (count, objects) = match value.split_once(':') {
+        Some(parts) => parts,
+        None => return Err(anyhow!("--total '{}' must be in the form 'COUNT:OBJECTS'", value)),
+    };
+
+    match count.trim().parse::<u32>() {
+        Ok(count) if count > 0 => Ok(Total {
+            count,
+            objects: parse_param_group(objects, param_count)?,
+        }),
+        _ => Err(anyhow!("'{}' is not a valid total count", count)),
+    }
+}
+
+/// Attempts to parse a proximity constraint in the form 'A,B,LEVELS', where A and B are
+/// 1-based object numbers.
+pub fn parse_within(value: &str, param_count: usize) -> Result<Within> {
+    let parts: Vec<&str> = value.split(',').map(|part| part.trim()).collect();
+
+    let (a, b, levels) = match parts.as_slice() {
+        [a, b, levels] => (a, b, levels),
+        _ => return Err(anyhow!("--within '{}' must be in the form 'A,B,LEVELS'", value)),
+    };
+
+    let object = |number: &str| -> Result<usize> {
+        match number.parse::<usize>() {
+            Ok(n) if (1..=param_count).contains(&n) => Ok(n - 1),
+            _ => Err(anyhow!(
+                "'{}' is not a valid object number (1 to {})",
+                number,
+                param_count
+            )),
+        }
+    };
+    let (a, b) = (object(a)?, object(b)?);
+
+    if a == b {
+        return Err(anyhow!(
+            "--within '{}' must use two different objects",
+            value
+        ));
+    }
+
+    match levels.parse::<u8>() {
+        Ok(levels) => Ok(Within { a, b, levels }),
+        Err(_) => Err(anyhow!("'{}' is not a valid number of levels", levels)),
+    }
+}
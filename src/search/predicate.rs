@@ -0,0 +1,201 @@
+//! Row predicates (`--filter`) for Brogue Seed Scanner.
+//!
+//! A row predicate is a boolean expression over the columns of a catalog Record, for
+//! objects the term-based parameters can't describe.  Each `--filter` is searched for like
+//! an object parameter of any category:  a seed matches if at least one of its Records in
+//! the depth range passes the predicate.  For example:
+//!
+//! - `category=='weapon' && ench>=2 && depth<5`
+//! - `kind~'slaying' || (category=='armor' && runic && !vault)`
+//!
+//! Columns are named just like `--where` columns (partial header names, or zero-based
+//! indexes), and comparisons work the same way:  `==` (or `=`), `!=`, `~` (contains), `<`,
+//! `<=`, `>`, and `>=`, numeric when both sides are numbers and text otherwise.  Values
+//! are numbers, or text (quoted with `'` or `"`, or a single unquoted word).  A column on
+//! its own is true if it isn't empty.  Conditions combine with `!`, `&&`, and `||` (from
+//! highest precedence), and parentheses.
+
+use crate::search::expr::{tokenize, Token};
+use crate::search::filter::{FilterOp, RowFilter};
+use anyhow::{anyhow, Result};
+use csv::StringRecord;
+
+/// A parsed row predicate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Predicate {
+    /// Predicate as given, for display.
+    text: String,
+    expr: PredicateExpr,
+}
+
+/// Parsed form of a row predicate.
+#[derive(Debug, Clone, PartialEq)]
+enum PredicateExpr {
+    Compare(RowFilter),
+    Not(Box<PredicateExpr>),
+    And(Box<PredicateExpr>, Box<PredicateExpr>),
+    Or(Box<PredicateExpr>, Box<PredicateExpr>),
+}
+
+impl PredicateExpr {
+    /// Returns `true` if the Record passes the predicate.
+    fn matches(&self, record: &StringRecord) -> bool {
+        match self {
+            Self::Compare(filter) => filter.matches(record),
+            Self::Not(a) => !a.matches(record),
+            Self::And(a, b) => a.matches(record) && b.matches(record),
+            Self::Or(a, b) => a.matches(record) || b.matches(record),
+        }
+    }
+    /// Resolves the columns of every comparison against the header of a catalog file.
+    fn resolve(&mut self, headers: &StringRecord) -> Result<()> {
+        match self {
+            Self::Compare(filter) => filter.resolve(headers),
+            Self::Not(a) => a.resolve(headers),
+            Self::And(a, b) | Self::Or(a, b) => {
+                a.resolve(headers)?;
+                b.resolve(headers)
+            }
+        }
+    }
+}
+
+impl Predicate {
+    /// Parses a row predicate.
+    pub fn parse(text: &str) -> Result<Self> {
+        let parse = || -> Result<PredicateExpr> {
+            let mut parser = Parser {
+                tokens: tokenize(text)?,
+                pos: 0,
+            };
+            let expr = parser.or()?;
+
+            match parser.tokens.get(parser.pos) {
+                None => Ok(expr),
+                Some(token) => Err(anyhow!("unexpected {}", token)),
+            }
+        };
+
+        match parse() {
+            Ok(expr) => Ok(Self {
+                text: text.trim().to_string(),
+                expr,
+            }),
+            Err(e) => Err(anyhow!("--filter '{}': {}", text, e)),
+        }
+    }
+    /// Returns `true` if the Record passes the predicate.  The predicate must be resolved.
+    pub fn matches(&self, record: &StringRecord) -> bool {
+        self.expr.matches(record)
+    }
+    /// Resolves column indexes against the header of a catalog file.
+    pub fn resolve(&mut self, headers: &StringRecord) -> Result<()> {
+        self.expr.resolve(headers)
+    }
+}
+
+impl std::fmt::Display for Predicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// Recursive descent parser over the tokens of a row predicate.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    /// Consumes and returns the next token.
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| anyhow!("unexpected end"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+    /// Consumes the next token if it's the given operator.
+    fn operator(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.tokens.get(self.pos), Some(Token::Op(op)) if *op == symbol);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+    /// Parses conditions joined by `||` (lowest precedence).
+    fn or(&mut self) -> Result<PredicateExpr> {
+        let mut expr = self.and()?;
+        while self.operator("||") {
+            expr = PredicateExpr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+
+        Ok(expr)
+    }
+    /// Parses conditions joined by `&&`.
+    fn and(&mut self) -> Result<PredicateExpr> {
+        let mut expr = self.not()?;
+        while self.operator("&&") {
+            expr = PredicateExpr::And(Box::new(expr), Box::new(self.not()?));
+        }
+
+        Ok(expr)
+    }
+    /// Parses a negated condition, or a condition.
+    fn not(&mut self) -> Result<PredicateExpr> {
+        match self.operator("!") {
+            true => Ok(PredicateExpr::Not(Box::new(self.not()?))),
+            false => self.condition(),
+        }
+    }
+    /// Parses a comparison, a column on its own, or a parenthesized condition.
+    fn condition(&mut self) -> Result<PredicateExpr> {
+        let column = match self.next()? {
+            Token::Open => {
+                let expr = self.or()?;
+                return match self.next()? {
+                    Token::Close => Ok(expr),
+                    token => Err(anyhow!("expected ')', found {}", token)),
+                };
+            }
+            Token::Name(column) => column,
+            Token::Number(index) if index.fract() == 0.0 => index.to_string(),
+            token => return Err(anyhow!("expected a column, found {}", token)),
+        };
+
+        let op = match self.tokens.get(self.pos) {
+            Some(Token::Op(symbol)) => match *symbol {
+                "==" | "=" => Some(FilterOp::Equal),
+                "!=" => Some(FilterOp::NotEqual),
+                "~" => Some(FilterOp::Contains),
+                "<" => Some(FilterOp::Less),
+                "<=" => Some(FilterOp::LessEqual),
+                ">" => Some(FilterOp::Greater),
+                ">=" => Some(FilterOp::GreaterEqual),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        // A column on its own is true if it isn't empty
+        let op = match op {
+            Some(op) => op,
+            None => return Ok(PredicateExpr::Compare(RowFilter::new(&column, FilterOp::NotEqual, String::new()))),
+        };
+        self.pos += 1;
+
+        let value = match self.next()? {
+            Token::Number(value) => value.to_string(),
+            Token::Op("-") => match self.next()? {
+                Token::Number(value) => (-value).to_string(),
+                token => return Err(anyhow!("expected a number, found {}", token)),
+            },
+            Token::Text(text) | Token::Name(text) => text,
+            token => return Err(anyhow!("expected a value, found {}", token)),
+        };
+
+        Ok(PredicateExpr::Compare(RowFilter::new(&column, op, value)))
+    }
+}
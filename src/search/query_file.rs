@@ -0,0 +1,110 @@
+//! Query files for batch searches in Brogue Seed Scanner.
+//!
+//! A query file is a TOML table whose keys are the long names of command line options.
+//! String values are split on whitespace into separate terms, arrays repeat the option
+//! once per element (just like repeating it on the command line), `true` sets a flag,
+//! and numbers are used as-is.  An optional `name`
+//! key names the query in the results (default: the file name).  For example:
+//!
+//! ```toml
+//! name = "melee start"
+//! depth = 4
+//! weapon = "+3 sword"
+//! potion = ["2 life", "5 descent"]
+//! ```
+//!
+//! Options taking an expression (`where`, `filter`, `logic`, and `score-expr`) aren't
+//! split, so `logic = "(1 || 2) && !3"` works as expected.
+//!
+//! Options given on the command line (e.g. `--filepath`, `--utf8`) apply to every query
+//! file, and are overridden by the same option inside a query file.
+
+use crate::search::SearchParameters;
+use anyhow::{anyhow, Context, Result};
+use std::path::Path;
+use toml::Value;
+
+/// Returns the command line arguments with any `--query-file` options removed, so they
+/// can be used as the base arguments of each query file.
+pub fn base_args<I: IntoIterator<Item = String>>(args: I) -> Vec<String> {
+    let mut base = Vec::new();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--query-file" {
+            args.next();
+        } else if !arg.starts_with("--query-file=") {
+            base.push(arg);
+        }
+    }
+
+    base
+}
+
+/// Loads a query file into `SearchParameters`, using `base_args` (program name first) as
+/// the defaults for any option not set in the file.
+pub fn load_query_file<P: AsRef<Path>>(path: P, base_args: &[String]) -> Result<SearchParameters> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("could not read query file {:?}", path))?;
+    let table: toml::Table = text
+        .parse()
+        .with_context(|| format!("invalid TOML in query file {:?}", path))?;
+
+    let mut name = path.file_stem().map(|s| s.to_string_lossy().into_owned());
+    let mut args = base_args.to_vec();
+
+    for (key, value) in table.iter() {
+        match key.as_str() {
+            "name" => match value {
+                Value::String(s) => name = Some(s.clone()),
+                _ => return Err(anyhow!("'name' must be a string in query file {:?}", path)),
+            },
+            "query-file" => {
+                return Err(anyhow!("query file {:?} cannot include other query files", path))
+            }
+            _ => push_option(&mut args, key, value)
+                .with_context(|| format!("invalid option '{}' in query file {:?}", key, path))?,
+        }
+    }
+
+    // The file's options come after the command line's, and replace them
+    let matches = crate::new_app()
+        .setting(clap::AppSettings::AllArgsOverrideSelf)
+        .get_matches_from_safe(args)
+        .map_err(|e| anyhow!("in query file {:?}: {}", path, e.message))?;
+    let mut search = SearchParameters::from_matches(matches)
+        .with_context(|| format!("in query file {:?}", path))?;
+    search.name = name;
+
+    Ok(search)
+}
+
+/// Options whose string values are a single expression, rather than whitespace-separated
+/// terms.
+const EXPRESSION_OPTIONS: [&str; 4] = ["where", "filter", "logic", "score-expr"];
+
+/// Pushes a single query file option (and its values) onto a list of arguments.
+fn push_option(args: &mut Vec<String>, key: &str, value: &Value) -> Result<()> {
+    let option = format!("--{}", key);
+
+    match value {
+        Value::Boolean(true) => args.push(option),
+        Value::Boolean(false) => (),
+        Value::Integer(n) => args.extend([option, n.to_string()]),
+        Value::Float(n) => args.extend([option, n.to_string()]),
+        Value::String(s) if EXPRESSION_OPTIONS.contains(&key) => args.extend([option, s.clone()]),
+        Value::String(s) => {
+            args.push(option);
+            args.extend(s.split_whitespace().map(String::from));
+        }
+        Value::Array(values) => {
+            for value in values {
+                push_option(args, key, value)?;
+            }
+        }
+        _ => return Err(anyhow!("expected a string, number, boolean, or array")),
+    }
+
+    Ok(())
+}
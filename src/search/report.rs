@@ -0,0 +1,220 @@
+//! Reports (`--report`) for Brogue Seed Scanner.
+//!
+//! Reports present the matches of a search for sharing and browsing, rather than for the
+//! terminal (`display_matches`) or for programs (`schema`).
+//!
+//! `--report md` writes a Markdown document, ready to post on forums and chat (e.g. Reddit
+//! or Discord):  a heading per seed (with its dungeon version and, if known, the depth
+//! it's complete by), then a bold depth line and a bullet list of the matching objects at
+//! that depth.
+//! ```markdown
+//! # Seed finds:  reflection armor
+//!
+//! ## Seed 12345 (CE 1.9)
+//!
+//! Complete by depth 3.
+//!
+//! **Depth 3**
+//!
+//! - A +2 banded mail of reflection (vault 1)
+//!
+//! _1 seed found._
+//! ```
+//! `--report html` writes a standalone HTML page (redirect it to a file) with a table of
+//! the matching objects:  seed, depth, item, vault, and carrier.  Clicking a column
+//! heading sorts the table by it, and the filter box hides rows without every word typed
+//! in it.  The page needs no network access.
+//!
+//! Both title the report with the query name, when it has one (query files).
+
+use crate::search::{SearchMatch, SearchParameters};
+use anyhow::Result;
+use std::io::Write;
+
+/// Writes the matches of a search as a Markdown report.
+pub fn write_markdown<W: Write>(
+    writer: &mut W,
+    matches: &[SearchMatch],
+    params: &SearchParameters,
+) -> Result<()> {
+    match params.name.as_ref() {
+        Some(name) => writeln!(writer, "# Seed finds:  {}\n", escape_markdown(name))?,
+        None => writeln!(writer, "# Seed finds\n")?,
+    }
+
+    let seeds: Vec<&[SearchMatch]> = matches.chunk_by(|a, b| a.seed == b.seed).collect();
+
+    for seed_matches in seeds.iter() {
+        let first = &seed_matches[0];
+        match first.version.is_empty() {
+            true => writeln!(writer, "## Seed {}\n", first.seed)?,
+            false => writeln!(
+                writer,
+                "## Seed {} ({})\n",
+                first.seed,
+                escape_markdown(&first.version)
+            )?,
+        }
+        if let Some(depth) = params.required_depth(first.seed) {
+            writeln!(writer, "Complete by depth {}.\n", depth)?;
+        }
+
+        for depth_matches in seed_matches.chunk_by(|a, b| a.depth == b.depth) {
+            writeln!(writer, "**Depth {}**\n", depth_matches[0].depth)?;
+            for m in depth_matches {
+                writeln!(writer, "- {}", escape_markdown(&m.to_string()))?;
+            }
+            writeln!(writer)?;
+        }
+    }
+
+    writeln!(
+        writer,
+        "_{} found._",
+        params.counted(seeds.len() as u64, "seed", "seeds")
+    )?;
+
+    Ok(())
+}
+
+/// Writes the matches of a search as a standalone HTML page.
+pub fn write_html<W: Write>(
+    writer: &mut W,
+    matches: &[SearchMatch],
+    params: &SearchParameters,
+) -> Result<()> {
+    let title = match params.name.as_ref() {
+        Some(name) => format!("Seed finds:  {}", escape_html(name)),
+        None => "Seed finds".to_string(),
+    };
+    let seeds = matches.chunk_by(|a, b| a.seed == b.seed).count();
+
+    writeln!(
+        writer,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">"
+    )?;
+    writeln!(
+        writer,
+        "<title>{}</title>\n<style>{}</style>\n</head>\n<body>",
+        title, HTML_STYLE
+    )?;
+    writeln!(writer, "<h1>{}</h1>", title)?;
+    writeln!(
+        writer,
+        "<p>{} found, with {}.</p>",
+        params.counted(seeds as u64, "seed", "seeds"),
+        params.counted(matches.len() as u64, "matching object", "matching objects")
+    )?;
+    writeln!(
+        writer,
+        "<input id=\"filter\" type=\"search\" placeholder=\"Filter (e.g. 'reflection vault')\">"
+    )?;
+    writeln!(writer, "<table id=\"matches\">\n<thead><tr>")?;
+    writeln!(
+        writer,
+        "<th data-type=\"number\">Seed</th><th data-type=\"number\">Depth</th><th>Item</th>"
+    )?;
+    writeln!(
+        writer,
+        "<th data-type=\"number\">Vault</th><th>Carried by</th>\n</tr></thead>\n<tbody>"
+    )?;
+
+    for m in matches {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        writeln!(
+            writer,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            m.seed,
+            m.depth,
+            escape_html(&m.object.to_string()),
+            optional(m.vault.map(|v| v.to_string())),
+            escape_html(&optional(m.carried_by.map(|c| c.to_string())))
+        )?;
+    }
+
+    writeln!(
+        writer,
+        "</tbody>\n</table>\n<script>{}</script>\n</body>\n</html>",
+        HTML_SCRIPT
+    )?;
+
+    Ok(())
+}
+
+/// Styles of HTML reports.
+const HTML_STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; }
+#filter { margin-bottom: 1em; padding: 0.3em; width: 20em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.8em; text-align: left; }
+th { background: #eee; cursor: pointer; user-select: none; }
+th[data-dir=asc]::after { content: ' \\25B2'; }
+th[data-dir=desc]::after { content: ' \\25BC'; }
+tbody tr:nth-child(even) { background: #f7f7f7; }
+";
+
+/// Sorting (by clicking a column heading) and filtering of HTML reports.
+const HTML_SCRIPT: &str = "
+const table = document.getElementById('matches');
+const rows = Array.from(table.tBodies[0].rows);
+const headings = Array.from(table.tHead.rows[0].cells);
+
+document.getElementById('filter').addEventListener('input', (event) => {
+  const words = event.target.value.toLowerCase().split(/\\s+/).filter(Boolean);
+  for (const row of rows) {
+    const text = row.textContent.toLowerCase();
+    row.hidden = !words.every((word) => text.includes(word));
+  }
+});
+
+headings.forEach((heading, column) => {
+  heading.addEventListener('click', () => {
+    const dir = heading.dataset.dir === 'asc' ? -1 : 1;
+    const number = (text) => (text === '' ? Infinity : Number(text));
+    const key = (row) => row.cells[column].textContent;
+    const compare = heading.dataset.type === 'number'
+      ? (a, b) => Math.sign(number(key(a)) - number(key(b))) || 0
+      : (a, b) => key(a).localeCompare(key(b));
+
+    headings.forEach((other) => delete other.dataset.dir);
+    heading.dataset.dir = dir === 1 ? 'asc' : 'desc';
+    rows.sort((a, b) => dir * compare(a, b));
+    table.tBodies[0].append(...rows);
+  });
+});
+";
+
+/// Escapes the characters HTML would read as markup.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Escapes the characters Markdown would read as formatting.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
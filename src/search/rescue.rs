@@ -0,0 +1,120 @@
+//! Captive ally rescue summaries for Brogue Seed Scanner.
+//!
+//! With `--rescue`, the cage keys and monsters carrying items on each searched depth of a
+//! seed are noted during the seed pass.  Every caged or shackled ally matched by a
+//! matching seed is then listed with what's on its level, to judge how realistic its
+//! rescue is:
+//! ```
+//! Seed 2
+//!     A caged troll on depth 19:  cage keys: 2 (1 carried by imp);  captors: imp, dar blademaster
+//! ```
+
+use super::SearchMatch;
+use crate::columns;
+use crate::objects::{AllyStatus, Category, KeyKind, MonsterKind};
+use csv::StringRecord;
+
+/// Cage keys and monsters carrying items on a depth of a seed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LevelRows {
+    depth: u8,
+    /// Cage keys on the depth, with the monster carrying each (if any).
+    cage_keys: Vec<Option<String>>,
+    /// Monsters carrying items on the depth (each listed once).
+    captors: Vec<String>,
+}
+
+/// A caged or shackled ally matched by a seed, with the cage keys and monsters carrying
+/// items on its depth.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllyRescue {
+    pub depth: u8,
+    /// The ally, as displayed (e.g. "A caged troll").
+    pub ally: String,
+    /// Cage keys on the depth, with the monster carrying each (if any).
+    pub cage_keys: Vec<Option<String>>,
+    /// Monsters carrying items on the depth.
+    pub captors: Vec<String>,
+}
+
+impl std::fmt::Display for AllyRescue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} on depth {}:  ", self.ally, self.depth)?;
+
+        let carried: Vec<&str> = self.cage_keys.iter().flatten().map(String::as_str).collect();
+        match (self.cage_keys.len(), carried.is_empty()) {
+            (0, _) => write!(f, "no cage key")?,
+            (keys, true) => write!(f, "cage keys: {}", keys)?,
+            (keys, false) => write!(
+                f,
+                "cage keys: {} ({} carried by {})",
+                keys,
+                carried.len(),
+                carried.join(", ")
+            )?,
+        }
+        match self.captors.is_empty() {
+            true => write!(f, ";  no captors"),
+            false => write!(f, ";  captors: {}", self.captors.join(", ")),
+        }
+    }
+}
+
+/// Notes a Record (line in .csv file) of a seed on its depth's rows:  cage keys, and the
+/// monster carrying it (if any).
+pub(crate) fn add_level_record(
+    levels: &mut Vec<LevelRows>,
+    category: Category,
+    depth: u8,
+    record: &StringRecord,
+) {
+    let carrier =
+        MonsterKind::parse(&record[columns::CARRIED_BY]).map(|monster| monster.to_string());
+    let cage_key = category == Category::Key
+        && matches!(
+            KeyKind::parse(&record[columns::KIND]),
+            Some(KeyKind::CageKey)
+        );
+
+    if !cage_key && carrier.is_none() {
+        return;
+    }
+
+    let level = match levels.iter().position(|level| level.depth == depth) {
+        Some(ix) => &mut levels[ix],
+        None => {
+            levels.push(LevelRows {
+                depth,
+                ..LevelRows::default()
+            });
+            levels.last_mut().unwrap()
+        }
+    };
+
+    if let Some(carrier) = carrier.as_ref() {
+        if !level.captors.contains(carrier) {
+            level.captors.push(carrier.clone());
+        }
+    }
+    if cage_key {
+        level.cage_keys.push(carrier);
+    }
+}
+
+/// Returns the rescue summary of every caged or shackled ally among a seed's matches.
+pub(crate) fn ally_rescues(matches: &[SearchMatch], levels: &[LevelRows]) -> Vec<AllyRescue> {
+    matches
+        .iter()
+        .filter(|m| matches!(m.object.ally_status(), Some(AllyStatus::Caged) | Some(AllyStatus::Shackled)))
+        .map(|m| {
+            let level = levels.iter().find(|level| level.depth == m.depth);
+
+            AllyRescue {
+                depth: m.depth,
+                ally: m.object.to_string(),
+                cage_keys: level.map(|level| level.cage_keys.clone()).unwrap_or_default(),
+                captors: level.map(|level| level.captors.clone()).unwrap_or_default(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,774 @@
+//! Versioned machine output schema for Brogue Seed Scanner.
+//!
+//! JSON (`--json`) and NDJSON (`--ndjson`) output is built from the structs in this
+//! module.  Every document (JSON) or line (NDJSON) carries a `format_version` field and
+//! a `type` tag.  Within a format version, fields are only ever _added_ (never renamed,
+//! removed, or changed in meaning), so consumers should ignore fields they don't know.
+//!
+//! Format version 1:
+//!
+//! - `match`: one matching object.
+//!   - `seed` (integer), `depth` (integer), `quantity` (integer)
+//!   - `category` (string): e.g. "armor", "ally"
+//!   - `kind` (string): e.g. "scale mail", "goblin mystic"
+//!   - `enchantment` (integer or null): charges for staves and wands
+//!   - `runic` (string or null)
+//!   - `vault` (integer or null): vault number the object is held in
+//!   - `carried_by` (string or null): monster carrying the object
+//!   - `ally_status` (string or null): "caged", "shackled", or "legendary"
+//!   - `mutation` (string or null)
+//! - `summary`: totals for the whole search.
+//!   - `seeds` (integer): number of matching seeds
+//!   - `matches` (integer): number of matching objects
+//!   - `status` (string): "matches" or "no_matches"
+//! - `status`: a search that failed (see `status`), as the only record.
+//!   - `status` (string): "no_files", "bad_query", or "error"
+//!   - `message` (string)
+//! - `stats`: depth-bucketed statistics (`stats` subcommand).
+//!   - `category` (string), `kind` (string or null)
+//!   - `depth_min`, `depth_max` (integer): depth range scanned
+//!   - `seeds` (integer): number of seeds scanned
+//!   - `objects` (integer), `objects_per_seed` (number): total quantity of objects
+//!   - `seeds_with` (integer): number of seeds with at least one object
+//!   - `buckets` (array): per bucket, `depth_min`, `depth_max`, `objects`,
+//!     `objects_per_seed`, `seeds`, and `seeds_by_depth` (seeds with at least one object
+//!     by the bucket's deepest depth)
+//! - `explain`: the parsed search, without running it (`--explain-json`).
+//!   - `depth_min`, `depth_max`, `seed_min`, `seed_max` (integer)
+//!   - `seed_list` (integer or null): number of seeds in the `--seedlist`
+//!   - `files` (array of strings): catalog files to search
+//!   - `file_format` (string): "utf8" or "utf16"
+//!   - `matches` (integer or null): matching seeds to find (null for all)
+//!   - `where`, `within`, `totals`, `exclusions` (arrays of strings), `same_depth` (array
+//!     of arrays of object numbers), `no_malevolent` (integer or null), `require_key`
+//!     (boolean)
+//!   - `logic` (string): the object logic, by object number (e.g. "1 && 2")
+//!   - `objects` (array): per object parameter, with defaults applied:
+//!     - `category` (string), `category_flags` (integer), `categories` (array of strings)
+//!     - `count` (integer), `count_type` (string): "at_least", "less_than", or "equal_to"
+//!     - `depth` (integer): deepest depth searched for the object
+//!     - `stages` (array): counts needed by earlier depths (`N@dD`), each with `count`
+//!       and `depth` (integer)
+//!     - `kind`, `runic`, `mutation` (term or null):  `term` (string) as given, and
+//!       `matches` (array of strings), every name the term partially matches
+//!     - `enchantment` (integer or null), `any_runic` (boolean), `ally_status` (string or
+//!       null), `any_legendary`, `any_mutation` (boolean), `in_vault` (boolean or null),
+//!       `magic` (string or null): "benevolent" or "malevolent"
+//!     - `max_strength` (integer or null): most strength the armor or weapon may
+//!       need (`str<=N`)
+//!     - `min_armor_value` (integer or null): lowest base armor value of the armor
+//!       (`av>=N`)
+//!     - `max_charges` (integer or null): most charges of the staff or wand (`N-`)
+//!     - `two_handed` (boolean or null): whether the weapon is two-handed (`2h`), i.e.
+//!       needs 18 or more strength, or one-handed (`1h`)
+//!     - `thrown` (boolean): whether any thrown weapon matches (`thrown`)
+//!     - `min_damage_tier` (integer or null): lowest damage tier of the weapon
+//!       (`dmg>=tierN`)
+//!     - `filter` (string or null): the row predicate, for `--filter` objects
+//!     - `optional` (boolean): whether the object only adds to the score
+//!   - `empower` (object or null): `charges` and `depth` (integer)
+//! - `approx`: approximate count from a sample of files (`--approx`).
+//!   - `sampled_files`, `files`, `sampled_seeds`, `matching_seeds` (integer)
+//!   - `rate`, `rate_low`, `rate_high` (number): match rate per seed, with 95% interval
+//!   - `library_seeds` (integer): estimated seeds in the whole library
+//!   - `estimate`, `estimate_low`, `estimate_high` (number): estimated matching seeds
+//! - `upload`: matches sent to a community seed database (`--upload`, see `upload`).
+//!   - `seeds` (array): per matching seed, `seed` (integer), `version` (string):  the
+//!     dungeon version, `share` (string):  its share string (see `share`), and `matches`
+//!     (array of `match` records without their own `format_version`/`type` fields)
+//!
+//! `--json` emits one `results` document holding `matches` (an array of `match`
+//! records without their own `format_version`/`type` fields) and a `summary` object.
+//! `--ndjson` emits one `match` record per line, followed by a final `summary` line.
+//!
+//! With `--query-file`, every record also carries a `query` field (string) naming the
+//! query it belongs to, and each query gets its own `results` document or `summary` line.
+//!
+//! `--output FILE` writes the output to FILE instead of standard output (as NDJSON, unless
+//! another format is chosen).  Line-based output (NDJSON `match` lines, porcelain, share
+//! strings, and `--format` lines) is appended to FILE as soon as each seed is confirmed,
+//! so a scan that's cut short keeps the results found so far:  each seed's lines are
+//! written in a single append to the locked file and synced to disk before the scan moves
+//! on, and NDJSON's `summary` line is only added once the search is done.  Documents
+//! (JSON and reports) replace FILE once the search is done.
+//!
+//! `--porcelain` is a fixed line format for scripts, which doesn't depend on
+//! `--format-version` and will never change:  one line per matching object, with eight
+//! tab-separated fields (empty if there's no value), and nothing else (no banner, search
+//! details, or summary).  Queries from query files are written one after the other.
+//!
+//! 1. seed
+//! 2. depth
+//! 3. category
+//! 4. kind
+//! 5. enchantment
+//! 6. runic
+//! 7. vault
+//! 8. carried by
+//!
+//! `--share` prints one share string per matching seed (see `share`), with nothing else.
+
+use crate::objects::{Category, Mutation};
+use crate::search::{CountType, ObjectParameter, SearchMatch, SearchParameters};
+use crate::search::report::{write_html, write_markdown};
+use crate::file_handling::FileFormat;
+use crate::approx::Approx;
+use crate::share::share_strings;
+use crate::stats::Stats;
+use crate::status::Status;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+/// Latest (and default) machine output format version.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Machine output formats for search results.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputMode {
+    /// Human-readable text (the default).
+    Text,
+    /// A single JSON document.
+    Json,
+    /// Newline-delimited JSON:  one record per line.
+    Ndjson,
+    /// Stable tab-separated lines, one per match (`--porcelain`).
+    Porcelain,
+    /// Share strings, one per matching seed (`--share`).
+    Share,
+    /// A Markdown report, grouped by seed and depth (`--report md`).
+    Markdown,
+    /// A standalone HTML page with a sortable table of matches (`--report html`).
+    Html,
+    /// A line per match, as set by the user's template (`--format`).
+    Template,
+}
+
+impl OutputMode {
+    /// Returns the output mode requested on the command line.
+    pub fn from_matches(matches: &clap::ArgMatches) -> Self {
+        match (matches.is_present("json"), matches.is_present("ndjson")) {
+            (true, _) => OutputMode::Json,
+            (_, true) => OutputMode::Ndjson,
+            _ if matches.is_present("porcelain") => OutputMode::Porcelain,
+            _ if matches.is_present("share") => OutputMode::Share,
+            _ if matches.is_present("template") => OutputMode::Template,
+            _ if matches.value_of("report") == Some("md") => OutputMode::Markdown,
+            _ if matches.value_of("report") == Some("html") => OutputMode::Html,
+            _ => OutputMode::Text,
+        }
+    }
+    /// Returns `true` if matches are written as lines of their own (no document around
+    /// them), so an output file can take them seed by seed (`--output`).
+    pub fn is_line_based(self) -> bool {
+        matches!(
+            self,
+            Self::Ndjson | Self::Porcelain | Self::Share | Self::Template
+        )
+    }
+}
+
+/// Validates a requested `--format-version` value.
+pub(crate) fn parse_format_version(value: &str) -> Result<u32> {
+    match value.parse::<u32>() {
+        Ok(FORMAT_VERSION) => Ok(FORMAT_VERSION),
+        _ => Err(anyhow!(
+            "unsupported --format-version '{}' (supported: {})",
+            value,
+            FORMAT_VERSION
+        )),
+    }
+}
+
+/// Wraps a record with the format version it was written with.
+#[derive(Serialize)]
+struct Versioned<'a, T: Serialize> {
+    format_version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    query: Option<&'a str>,
+    #[serde(flatten)]
+    record: T,
+}
+
+/// Any top-level record, tagged by `type`.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record<'a> {
+    Match(MatchV1),
+    Summary(SummaryV1),
+    Results {
+        matches: &'a [MatchV1],
+        summary: SummaryV1,
+    },
+    Stats(&'a Stats),
+    Approx(&'a Approx),
+    Status {
+        status: Status,
+        message: String,
+    },
+    Explain(ExplainV1),
+    #[cfg(feature = "upload")]
+    Upload {
+        seeds: Vec<SeedV1>,
+    },
+}
+
+/// A single matching object (format version 1).
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchV1 {
+    pub seed: u32,
+    pub depth: u8,
+    pub quantity: u32,
+    pub category: String,
+    pub kind: String,
+    pub enchantment: Option<i8>,
+    pub runic: Option<String>,
+    pub vault: Option<u8>,
+    pub carried_by: Option<String>,
+    pub ally_status: Option<String>,
+    pub mutation: Option<String>,
+}
+
+impl MatchV1 {
+    /// Makes a new schema record from a `SearchMatch`.
+    pub fn from_match(m: &SearchMatch) -> Self {
+        Self {
+            seed: m.seed,
+            depth: m.depth,
+            quantity: m.quantity,
+            category: m.object.category().to_string(),
+            kind: m.object.kind_name(),
+            enchantment: m.object.enchantment(),
+            runic: m.object.runic_name(),
+            vault: m.vault,
+            carried_by: m.carried_by.map(|c| c.to_string()),
+            ally_status: m.object.ally_status().map(|s| s.to_string()),
+            mutation: m.object.mutation().map(|s| s.to_string()),
+        }
+    }
+}
+
+/// The matches of a seed, as uploaded (format version 1).
+#[derive(Debug, Clone, Serialize)]
+#[cfg(feature = "upload")]
+pub struct SeedV1 {
+    pub seed: u32,
+    pub version: String,
+    pub share: String,
+    pub matches: Vec<MatchV1>,
+}
+
+/// Totals for a whole search (format version 1).
+#[derive(Debug, Clone, Serialize)]
+pub struct SummaryV1 {
+    pub seeds: usize,
+    pub matches: usize,
+    pub status: Status,
+}
+
+impl SummaryV1 {
+    /// Makes a new summary from a list of `SearchMatch`es.
+    pub fn from_matches(matches: &[SearchMatch]) -> Self {
+        let mut seeds = 0;
+        let mut prev_seed = None;
+
+        for m in matches {
+            if prev_seed != Some(m.seed) {
+                prev_seed = Some(m.seed);
+                seeds += 1;
+            }
+        }
+
+        Self {
+            seeds,
+            matches: matches.len(),
+            status: Status::of_seeds(seeds),
+        }
+    }
+}
+
+/// A parsed search, with defaults applied (format version 1).
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplainV1 {
+    pub depth_min: u8,
+    pub depth_max: u8,
+    pub seed_min: u32,
+    pub seed_max: u32,
+    pub seed_list: Option<usize>,
+    pub files: Vec<String>,
+    pub file_format: &'static str,
+    pub matches: Option<usize>,
+    #[serde(rename = "where")]
+    pub row_filters: Vec<String>,
+    pub same_depth: Vec<Vec<usize>>,
+    pub within: Vec<String>,
+    pub totals: Vec<String>,
+    pub no_malevolent: Option<u8>,
+    pub exclusions: Vec<String>,
+    pub require_key: bool,
+    pub logic: String,
+    pub objects: Vec<ObjectV1>,
+    pub empower: Option<EmpowerV1>,
+}
+
+impl ExplainV1 {
+    /// Makes a new schema record from `SearchParameters`.
+    pub fn from_search(search: &SearchParameters) -> Self {
+        Self {
+            depth_min: search.depth_min,
+            depth_max: search.depth_max,
+            seed_min: search.seed_min,
+            seed_max: search.seed_max,
+            seed_list: search.seed_list.as_ref().map(|seeds| seeds.len()),
+            files: search
+                .file_paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect(),
+            file_format: match search.format {
+                FileFormat::Utf8 => "utf8",
+                FileFormat::Utf16 => "utf16",
+            },
+            matches: search.seed_limit(),
+            row_filters: search.row_filters.iter().map(|f| f.to_string()).collect(),
+            same_depth: search
+                .same_depth
+                .iter()
+                .map(|group| group.iter().map(|i| i + 1).collect())
+                .collect(),
+            within: search.within.iter().map(|w| w.to_string()).collect(),
+            totals: search.totals.iter().map(|t| t.to_string()).collect(),
+            no_malevolent: search.no_malevolent,
+            exclusions: search.exclusions.iter().map(|e| e.to_string()).collect(),
+            require_key: search.require_key,
+            logic: search.logic.to_string(),
+            objects: search
+                .object_params
+                .iter()
+                .map(|p| ObjectV1::from_param(p, search.depth_max))
+                .collect(),
+            empower: search.empower.as_ref().map(|e| EmpowerV1 {
+                charges: e.charges,
+                depth: e.depth.min(search.depth_max),
+            }),
+        }
+    }
+}
+
+/// A parsed object parameter (format version 1).
+#[derive(Debug, Clone, Serialize)]
+pub struct ObjectV1 {
+    pub category: String,
+    pub category_flags: u16,
+    pub categories: Vec<String>,
+    pub count: u32,
+    pub count_type: &'static str,
+    pub depth: u8,
+    pub stages: Vec<StageV1>,
+    pub kind: Option<TermV1>,
+    pub enchantment: Option<i8>,
+    pub runic: Option<TermV1>,
+    pub any_runic: bool,
+    pub max_strength: Option<u8>,
+    pub min_armor_value: Option<u8>,
+    pub max_charges: Option<u8>,
+    pub two_handed: Option<bool>,
+    pub thrown: bool,
+    pub min_damage_tier: Option<u8>,
+    pub ally_status: Option<String>,
+    pub any_legendary: bool,
+    pub mutation: Option<TermV1>,
+    pub any_mutation: bool,
+    pub in_vault: Option<bool>,
+    pub magic: Option<String>,
+    pub filter: Option<String>,
+    pub optional: bool,
+}
+
+impl ObjectV1 {
+    /// Makes a new schema record from an `ObjectParameter` of a search to `depth_max`.
+    pub fn from_param(param: &ObjectParameter, depth_max: u8) -> Self {
+        let category = param.category;
+        let term = |value: &Option<String>, names: fn(Category, &str) -> Vec<&'static str>| {
+            value.as_ref().map(|term| TermV1 {
+                term: term.clone(),
+                matches: names(category, term),
+            })
+        };
+
+        Self {
+            category: category.to_string(),
+            category_flags: param.category_flags.0,
+            categories: category.categories().iter().map(|c| c.to_string()).collect(),
+            count: param.count_target,
+            count_type: match param.count_type {
+                CountType::AtLeast => "at_least",
+                CountType::LessThan => "less_than",
+                CountType::EqualTo => "equal_to",
+            },
+            depth: param.depth.min(depth_max),
+            stages: param
+                .stages
+                .iter()
+                .map(|&(count, depth)| StageV1 { count, depth })
+                .collect(),
+            kind: term(&param.kind, Category::kinds_matching),
+            enchantment: param.enchantment,
+            runic: param.runic.as_ref().map(|term| TermV1 {
+                term: term.clone(),
+                matches: param
+                    .runics()
+                    .flat_map(|runic| category.runics_matching(runic))
+                    .collect(),
+            }),
+            any_runic: param.any_runic,
+            max_strength: param.max_strength,
+            min_armor_value: param.min_armor_value,
+            max_charges: param.max_charges,
+            two_handed: param.two_handed,
+            thrown: param.thrown,
+            min_damage_tier: param.min_damage_tier,
+            ally_status: param.ally_status.clone(),
+            any_legendary: param.any_legendary,
+            mutation: term(&param.mutation, |_, term| Mutation::names_matching(term)),
+            any_mutation: param.any_mutation,
+            in_vault: param.in_vault,
+            magic: param.magic_type.map(|m| m.to_string()),
+            filter: param.predicate.as_ref().map(|p| p.to_string()),
+            optional: param.optional,
+        }
+    }
+}
+
+/// A search term, and every name it partially matches (format version 1).
+#[derive(Debug, Clone, Serialize)]
+pub struct TermV1 {
+    pub term: String,
+    pub matches: Vec<&'static str>,
+}
+
+/// A count needed by an earlier depth (format version 1).
+#[derive(Debug, Clone, Serialize)]
+pub struct StageV1 {
+    pub count: u32,
+    pub depth: u8,
+}
+
+/// The empowered-ally constraint (format version 1).
+#[derive(Debug, Clone, Serialize)]
+pub struct EmpowerV1 {
+    pub charges: u32,
+    pub depth: u8,
+}
+
+/// Writes the parsed search as a single JSON document (`--explain-json`).
+pub fn write_explain<W: Write>(writer: &mut W, params: &SearchParameters) -> Result<()> {
+    let format_version = params.format_version;
+    let query = params.name.as_deref();
+    let record = Record::Explain(ExplainV1::from_search(params));
+
+    serde_json::to_writer_pretty(
+        &mut *writer,
+        &Versioned {
+            format_version,
+            query,
+            record,
+        },
+    )?;
+    writeln!(writer)?;
+
+    Ok(())
+}
+
+/// Writes all `SearchMatch` instances in the machine format chosen in the parameters.
+pub fn write_machine_matches<W: Write>(
+    writer: &mut W,
+    matches: &[SearchMatch],
+    params: &SearchParameters,
+) -> Result<()> {
+    let format_version = params.format_version;
+    let query = params.name.as_deref();
+    let summary = SummaryV1::from_matches(matches);
+
+    match params.output_mode {
+        OutputMode::Json => {
+            let records: Vec<MatchV1> = matches.iter().map(MatchV1::from_match).collect();
+            let record = Record::Results {
+                matches: &records,
+                summary,
+            };
+            serde_json::to_writer_pretty(
+                &mut *writer,
+                &Versioned {
+                    format_version,
+                    query,
+                    record,
+                },
+            )?;
+            writeln!(writer)?;
+        }
+        OutputMode::Ndjson => {
+            write_match_lines(writer, matches, params)?;
+            let record = Record::Summary(summary);
+            serde_json::to_writer(
+                &mut *writer,
+                &Versioned {
+                    format_version,
+                    query,
+                    record,
+                },
+            )?;
+            writeln!(writer)?;
+        }
+        OutputMode::Porcelain | OutputMode::Share | OutputMode::Template => write_match_lines(writer, matches, params)?,
+        OutputMode::Markdown => write_markdown(writer, matches, params)?,
+        OutputMode::Html => write_html(writer, matches, params)?,
+        OutputMode::Text => (),
+    }
+
+    Ok(())
+}
+
+/// Writes the lines of matches in a line-based output mode (nothing in other modes).
+fn write_match_lines<W: Write>(
+    writer: &mut W,
+    matches: &[SearchMatch],
+    params: &SearchParameters,
+) -> Result<()> {
+    let format_version = params.format_version;
+    let query = params.name.as_deref();
+
+    match params.output_mode {
+        OutputMode::Ndjson => {
+            for m in matches {
+                let record = Record::Match(MatchV1::from_match(m));
+                serde_json::to_writer(
+                    &mut *writer,
+                    &Versioned {
+                        format_version,
+                        query,
+                        record,
+                    },
+                )?;
+                writeln!(writer)?;
+            }
+        }
+        OutputMode::Porcelain => {
+            for m in matches {
+                write_porcelain(&mut *writer, &MatchV1::from_match(m))?;
+            }
+        }
+        OutputMode::Share => {
+            for share in share_strings(matches) {
+                writeln!(writer, "{}", share)?;
+            }
+        }
+        OutputMode::Template => {
+            if let Some(template) = params.template.as_ref() {
+                for m in matches {
+                    writeln!(writer, "{}", template.render(m))?;
+                }
+            }
+        }
+        _ => (),
+    }
+
+    Ok(())
+}
+
+/// Appends the matches of a confirmed seed to an output file as lines of the output mode
+/// (`--output`):  a single write to the locked file, synced to disk.
+pub fn append_matches(
+    path: &Path,
+    matches: &[SearchMatch],
+    params: &SearchParameters,
+) -> Result<()> {
+    let mut lines = Vec::new();
+    write_match_lines(&mut lines, matches, params)?;
+
+    append_output(path, &lines)
+}
+
+/// Finishes the output file of a search (`--output`) once it's done:  appends the NDJSON
+/// summary, or the whole document of other output modes that aren't line-based (whose
+/// matches weren't appended seed by seed).
+pub fn finish_output(
+    path: &Path,
+    matches: &[SearchMatch],
+    params: &SearchParameters,
+) -> Result<()> {
+    let mut output = Vec::new();
+
+    match params.output_mode {
+        OutputMode::Ndjson => {
+            let format_version = params.format_version;
+            let query = params.name.as_deref();
+            let record = Record::Summary(SummaryV1::from_matches(matches));
+            serde_json::to_writer(
+                &mut output,
+                &Versioned {
+                    format_version,
+                    query,
+                    record,
+                },
+            )?;
+            writeln!(output)?;
+        }
+        mode if mode.is_line_based() => (),
+        _ => write_machine_matches(&mut output, matches, params)?,
+    }
+
+    append_output(path, &output)
+}
+
+/// Error for an output file that can't be written (`--output`).  Unlike other errors in a
+/// catalog file, this ends the search (the matches found would be lost).
+#[derive(Debug)]
+pub struct OutputError(String);
+
+impl std::fmt::Display for OutputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for OutputError {}
+
+/// Appends bytes to an output file in a single write to the locked file, synced to disk.
+fn append_output(path: &Path, bytes: &[u8]) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| OutputError(format!("can't open output {:?}: {}", path, e)))?;
+    file.lock()
+        .and_then(|_| file.write_all(bytes))
+        .and_then(|_| file.sync_data())
+        .map_err(|e| OutputError(format!("can't write output {:?}: {}", path, e)))?;
+
+    Ok(())
+}
+
+/// Writes the matches of a search as a single `upload` JSON document (`--upload`), and
+/// returns the number of seeds written.  No query name is written.
+#[cfg(feature = "upload")]
+pub fn write_upload<W: Write>(writer: &mut W, matches: &[SearchMatch]) -> Result<usize> {
+    let seeds: Vec<SeedV1> = matches
+        .chunk_by(|a, b| a.seed == b.seed)
+        .zip(share_strings(matches))
+        .map(|(seed_matches, share)| SeedV1 {
+            seed: seed_matches[0].seed,
+            version: seed_matches[0].version.clone(),
+            share,
+            matches: seed_matches.iter().map(MatchV1::from_match).collect(),
+        })
+        .collect();
+    let count = seeds.len();
+    let record = Record::Upload { seeds };
+
+    serde_json::to_writer(
+        &mut *writer,
+        &Versioned {
+            format_version: FORMAT_VERSION,
+            query: None,
+            record,
+        },
+    )?;
+
+    Ok(count)
+}
+
+/// Writes a single match as a porcelain line.
+fn write_porcelain<W: Write>(writer: &mut W, m: &MatchV1) -> Result<()> {
+    let optional = |value: Option<String>| value.unwrap_or_default();
+    let fields = [
+        m.seed.to_string(),
+        m.depth.to_string(),
+        m.category.clone(),
+        m.kind.clone(),
+        optional(m.enchantment.map(|e| e.to_string())),
+        optional(m.runic.clone()),
+        optional(m.vault.map(|v| v.to_string())),
+        optional(m.carried_by.clone()),
+    ];
+
+    // Tabs and line breaks in a field would split it
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| field.replace(['\t', '\n', '\r'], " "))
+        .collect();
+    writeln!(writer, "{}", fields.join("\t"))?;
+
+    Ok(())
+}
+
+/// Writes depth-bucketed statistics as a single JSON document (`--json`) or line.
+pub fn write_stats<W: Write>(
+    writer: &mut W,
+    stats: &Stats,
+    params: &SearchParameters,
+) -> Result<()> {
+    let format_version = params.format_version;
+    let query = params.name.as_deref();
+    let record = Record::Stats(stats);
+
+    match params.output_mode {
+        OutputMode::Porcelain | OutputMode::Share | OutputMode::Markdown | OutputMode::Html | OutputMode::Template => {
+            return Err(anyhow!("--porcelain, --share, --report, and --format only apply to search matches"))
+        }
+        OutputMode::Json => {
+            serde_json::to_writer_pretty(&mut *writer, &Versioned { format_version, query, record })?
+        }
+        _ => serde_json::to_writer(&mut *writer, &Versioned { format_version, query, record })?,
+    }
+    writeln!(writer)?;
+
+    Ok(())
+}
+
+/// Writes an approximate count as a single JSON document (`--json`) or line.
+pub fn write_approx<W: Write>(
+    writer: &mut W,
+    approx: &Approx,
+    params: &SearchParameters,
+) -> Result<()> {
+    let format_version = params.format_version;
+    let query = params.name.as_deref();
+    let record = Record::Approx(approx);
+
+    match params.output_mode {
+        OutputMode::Porcelain | OutputMode::Share | OutputMode::Markdown | OutputMode::Html | OutputMode::Template => {
+            return Err(anyhow!("--porcelain, --share, --report, and --format only apply to search matches"))
+        }
+        OutputMode::Json => {
+            serde_json::to_writer_pretty(&mut *writer, &Versioned { format_version, query, record })?
+        }
+        _ => serde_json::to_writer(&mut *writer, &Versioned { format_version, query, record })?,
+    }
+    writeln!(writer)?;
+
+    Ok(())
+}
+
+/// Writes the status of a failed search as a single JSON document (`--json`) or line.
+pub fn write_status<W: Write>(
+    writer: &mut W,
+    mode: OutputMode,
+    status: Status,
+    message: String,
+) -> Result<()> {
+    let format_version = FORMAT_VERSION;
+    let record = Record::Status { status, message };
+
+    match mode {
+        OutputMode::Json => {
+            serde_json::to_writer_pretty(&mut *writer, &Versioned { format_version, query: None, record })?
+        }
+        _ => serde_json::to_writer(&mut *writer, &Versioned { format_version, query: None, record })?,
+    }
+    writeln!(writer)?;
+
+    Ok(())
+}
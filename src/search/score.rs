@@ -0,0 +1,328 @@
+//! Seed scoring and grading for Brogue Seed Scanner.
+
+use crate::objects::{Ally, AllyStatus, Category, Object, ScrollKind};
+use crate::search::{SearchMatch, SearchParameters};
+use anyhow::{anyhow, Result};
+use std::convert::TryFrom;
+
+/// What matching seeds are ranked by to keep the best of them (`--best`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BestBy {
+    /// Shallowest required depth first.
+    Depth,
+    /// Highest score first (as for `--grade`).
+    Score,
+}
+
+impl BestBy {
+    /// Parses a ranking from its name (`depth` or `score`).
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "depth" => Ok(BestBy::Depth),
+            "score" => Ok(BestBy::Score),
+            _ => Err(anyhow!("--best must be 'depth' or 'score'")),
+        }
+    }
+}
+
+/// Keeps the best seeds of a list of matches (grouped by seed) by the search's `--best`
+/// ranking, up to its seed limit, and orders them best first.  Seeds of equal rank are
+/// taken in ascending seed order.  Totals recorded for dropped seeds are forgotten.
+pub(crate) fn keep_best(matches: &mut Vec<SearchMatch>, search: &mut SearchParameters) {
+    let best = match search.best {
+        Some(best) => best,
+        None => return,
+    };
+    let scores = match best {
+        BestBy::Score => score_seeds(matches, search),
+        BestBy::Depth => Vec::new(),
+    };
+    let mut seeds: Vec<_> = matches
+        .chunk_by(|a, b| a.seed == b.seed)
+        .enumerate()
+        .map(|(i, seed_matches)| {
+            let seed = seed_matches[0].seed;
+            let rank = match best {
+                BestBy::Score => (-(scores[i].score as i64), -(scores[i].gold as i64), seed),
+                BestBy::Depth => (search.required_depth(seed).unwrap_or(0) as i64, 0, seed),
+            };
+            (rank, seed_matches)
+        })
+        .collect();
+
+    seeds.sort_by_key(|(rank, _)| *rank);
+
+    if let Some(limit) = search.seed_limit() {
+        for (_, seed_matches) in seeds.iter().skip(limit) {
+            let seed = seed_matches[0].seed;
+            search.seed_gold.remove(&seed);
+            search.seed_optionals.remove(&seed);
+            search.seed_met_depths.remove(&seed);
+            search.seed_vaults.remove(&seed);
+            search.seed_rescues.remove(&seed);
+        }
+        seeds.truncate(limit);
+    }
+
+    search.search_matches = u32::try_from(seeds.len()).unwrap_or(u32::MAX);
+    *matches = seeds
+        .into_iter()
+        .flat_map(|(_, seed_matches)| seed_matches.iter().cloned())
+        .collect();
+}
+
+/// Score added for each optional object parameter a seed meets.
+pub const OPTIONAL_WEIGHT: i32 = 5;
+
+/// Score of a single matching seed, built from all of its `SearchMatch`es.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeedScore {
+    pub seed: u32,
+    /// Number of matching objects (gold piles are not counted).
+    pub objects: u32,
+    /// Total positive enchantment of matching gear plus scrolls of enchanting.
+    pub enchant_potential: i32,
+    /// Total quality of matching allies (see `AllyWeights`).
+    pub ally_quality: i32,
+    /// Number of optional object parameters met.
+    pub optionals: u32,
+    /// Overall score:  `objects + enchant_potential + ally_quality`, plus `OPTIONAL_WEIGHT`
+    /// per optional object met, unless computed by a scoring expression (`--score-expr`).
+    pub score: i32,
+    /// Gold in the depth range, which breaks ties between equal scores.
+    pub gold: u32,
+}
+
+impl SeedScore {
+    /// Makes a new, zeroed score for a seed.
+    fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            objects: 0,
+            enchant_potential: 0,
+            ally_quality: 0,
+            optionals: 0,
+            score: 0,
+            gold: 0,
+        }
+    }
+    /// Adds a single match to the score.
+    fn add(&mut self, m: &SearchMatch, weights: &AllyWeights) {
+        let category = m.object.category();
+
+        if category != Category::Gold {
+            self.objects += m.quantity;
+        }
+
+        // Wand "enchantment" is its number of charges, which isn't enchant potential.
+        match &m.object {
+            Object::Ally(ally) => {
+                self.ally_quality += weights.quality(ally) * m.quantity as i32;
+            }
+            Object::Wand(_) => (),
+            Object::Scroll(s) => {
+                if let ScrollKind::Enchanting = s.kind {
+                    self.enchant_potential += m.quantity as i32;
+                }
+            }
+            o => {
+                if let Some(e) = o.enchantment() {
+                    self.enchant_potential += e.max(0) as i32;
+                }
+            }
+        }
+
+        self.score = self.objects as i32
+            + self.enchant_potential
+            + self.ally_quality
+            + self.optionals as i32 * OPTIONAL_WEIGHT;
+    }
+}
+
+/// Weights of ally quality in a seed's score.  An ally's quality is the sum of:
+/// - `legendary`, if the ally is legendary
+/// - `mutation`, if the ally has a mutation
+/// - `tier` times the tier of its kind (1 to 3, e.g. 1 for a monkey and 3 for a dar
+///   blademaster)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AllyWeights {
+    pub legendary: i32,
+    pub mutation: i32,
+    pub tier: i32,
+}
+
+impl AllyWeights {
+    /// Parses weights in the form `LEGENDARY,MUTATION,TIER` (e.g. "4,2,1").
+    pub fn parse(value: &str) -> Result<Self> {
+        let values: Vec<Option<i32>> = value.split(',').map(|v| v.trim().parse().ok()).collect();
+
+        match values.as_slice() {
+            [Some(legendary), Some(mutation), Some(tier)] => {
+                Ok(Self { legendary: *legendary, mutation: *mutation, tier: *tier })
+            }
+            _ => Err(anyhow!(
+                "--ally-weights must be three integers 'LEGENDARY,MUTATION,TIER' (e.g. '4,2,1')"
+            )),
+        }
+    }
+    /// Returns the quality of an ally.
+    pub fn quality(&self, ally: &Ally) -> i32 {
+        let legendary = match ally.status {
+            AllyStatus::Allied => self.legendary,
+            _ => 0,
+        };
+        let mutation = match ally.mutation {
+            Some(_) => self.mutation,
+            None => 0,
+        };
+
+        legendary + mutation + self.tier * ally.kind.tier() as i32
+    }
+}
+
+impl Default for AllyWeights {
+    fn default() -> Self {
+        Self {
+            legendary: 4,
+            mutation: 2,
+            tier: 1,
+        }
+    }
+}
+
+/// Scores each seed in a list of matches (which are grouped by seed), in seed order, with
+/// the search's ally weights, gold and optional object totals (seeds without a total have
+/// none), and scoring expression (if any).
+pub fn score_seeds(matches: &[SearchMatch], search: &SearchParameters) -> Vec<SeedScore> {
+    matches
+        .chunk_by(|a, b| a.seed == b.seed)
+        .map(|seed_matches| {
+            let mut score = SeedScore::new(seed_matches[0].seed);
+            score.gold = search.seed_gold.get(&score.seed).copied().unwrap_or(0);
+            score.optionals = search.seed_optionals.get(&score.seed).copied().unwrap_or(0);
+
+            for m in seed_matches {
+                score.add(m, &search.ally_weights);
+            }
+            if let Some(expr) = search.score_expr.as_ref() {
+                score.score = expr.eval(&score, seed_matches).round() as i32;
+            }
+
+            score
+        })
+        .collect()
+}
+
+/// Grading tiers for scored seeds, from best (S) to worst (C).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tier {
+    S,
+    A,
+    B,
+    C,
+}
+
+impl std::fmt::Display for Tier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let result = match self {
+            Tier::S => "S",
+            Tier::A => "A",
+            Tier::B => "B",
+            Tier::C => "C",
+        };
+        write!(f, "{}", result)
+    }
+}
+
+/// Minimum scores needed for the S, A, and B tiers.  Anything lower is tier C.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradeThresholds {
+    pub s: i32,
+    pub a: i32,
+    pub b: i32,
+}
+
+impl GradeThresholds {
+    /// Parses thresholds in the form `S,A,B` (e.g. "12,8,4").
+    pub fn parse(value: &str) -> Result<Self> {
+        let values: Vec<Option<i32>> = value.split(',').map(|v| v.trim().parse().ok()).collect();
+
+        match values.as_slice() {
+            [Some(s), Some(a), Some(b)] if s >= a && a >= b => Ok(Self {
+                s: *s,
+                a: *a,
+                b: *b,
+            }),
+            _ => Err(anyhow!(
+                "--grade thresholds must be three descending integers 'S,A,B' (e.g. '12,8,4')"
+            )),
+        }
+    }
+    /// Returns the tier for a given score.
+    pub fn tier(&self, score: i32) -> Tier {
+        if score >= self.s {
+            Tier::S
+        } else if score >= self.a {
+            Tier::A
+        } else if score >= self.b {
+            Tier::B
+        } else {
+            Tier::C
+        }
+    }
+}
+
+impl Default for GradeThresholds {
+    fn default() -> Self {
+        Self { s: 12, a: 8, b: 4 }
+    }
+}
+
+/// Scores the seeds of a list of matches, best first:  by score, then by gold, then by
+/// seed.
+pub fn rank_seeds(matches: &[SearchMatch], search: &SearchParameters) -> Vec<SeedScore> {
+    let mut scores = score_seeds(matches, search);
+    scores.sort_by(|a, b| b.score.cmp(&a.score).then(b.gold.cmp(&a.gold)).then(a.seed.cmp(&b.seed)));
+
+    scores
+}
+
+/// Prints a compact leaderboard of matching seeds, best first, grouped into tiers.
+pub fn display_grades(
+    matches: &[SearchMatch],
+    search: &SearchParameters,
+    thresholds: &GradeThresholds,
+) {
+    let scores = rank_seeds(matches, search);
+
+    if scores.is_empty() {
+        return;
+    }
+
+    println!(
+        "Grades (S >= {}, A >= {}, B >= {}):\n",
+        thresholds.s, thresholds.a, thresholds.b
+    );
+
+    let any_optional = search.object_params.iter().any(|p| p.optional);
+
+    for score in scores.iter() {
+        let optionals = match any_optional {
+            true => format!(", +{} optional", score.optionals as i32 * OPTIONAL_WEIGHT),
+            false => String::new(),
+        };
+
+        println!(
+            "  {}  seed {:<10}  score {:>3}  ({} objects, +{} enchant, +{} allies{}, {} gold)",
+            thresholds.tier(score.score),
+            score.seed,
+            score.score,
+            score.objects,
+            score.enchant_potential,
+            score.ally_quality,
+            optionals,
+            search.number(score.gold.into()),
+        );
+    }
+    println!();
+}
@@ -0,0 +1,189 @@
+//! Seed lists (`--seedlist`) for Brogue Seed Scanner.
+//!
+//! A seed list restricts a search to the seeds it names, so the output of one search can
+//! feed the next.  The file may hold:
+//!
+//! - plain seeds, separated by whitespace or commas (`#` starts a comment)
+//! - `--ndjson` output:  the seed of every `match` line
+//! - `--json` output:  the seed of every match in the document
+//!
+//! Seeds in the list are also used to narrow the seed range, so catalog files holding none
+//! of them are skipped like files outside `--minseed` and `--maxseed`.
+//!
+//! `--write-seedlist FILE` writes the matching seeds of a search as a plain seed list, one
+//! seed per line.  Searches from query files that write to the same file each get a section
+//! headed by a `# query: NAME` comment, so the file lists every seed any of them matched.
+//!
+//! `brogue-scanner seeds OPERATION FILE...` combines seed lists without rescanning:
+//!
+//! - `intersect`:  seeds in every list
+//! - `union`:  seeds in any list
+//! - `diff`:  seeds in the first list, but in none of the others
+
+use crate::file_handling::{read_text, EncodedWriter, OutputEncoding};
+use crate::search::{SearchMatch, SearchParameters};
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::convert::TryFrom;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Set operation over seed lists (`seeds` subcommand).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SeedSetOp {
+    Intersect,
+    Union,
+    Diff,
+}
+
+impl SeedSetOp {
+    /// Parses an operation by name.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "intersect" => Some(Self::Intersect),
+            "union" => Some(Self::Union),
+            "diff" => Some(Self::Diff),
+            _ => None,
+        }
+    }
+    /// Combines seed lists (each ascending, without duplicates).  Returns the seeds in
+    /// ascending order.
+    pub fn apply(self, lists: &[Vec<u32>]) -> Vec<u32> {
+        let (first, rest) = match lists.split_first() {
+            Some(split) => split,
+            None => return Vec::new(),
+        };
+        let listed_in =
+            |seed: &u32| rest.iter().filter(|list| list.binary_search(seed).is_ok()).count();
+
+        match self {
+            Self::Intersect => first
+                .iter()
+                .filter(|seed| listed_in(seed) == rest.len())
+                .copied()
+                .collect(),
+            Self::Union => {
+                let mut seeds: Vec<u32> = lists.concat();
+                seeds.sort_unstable();
+                seeds.dedup();
+                seeds
+            }
+            Self::Diff => first.iter().filter(|seed| listed_in(seed) == 0).copied().collect(),
+        }
+    }
+}
+
+/// Reads a seed list file.  Returns its seeds in ascending order, without duplicates.
+pub fn load_seed_list<P: AsRef<Path>>(path: P) -> Result<Vec<u32>> {
+    let path = path.as_ref();
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("could not read seed list {:?}", path))?;
+
+    // Seed lists with a byte order mark may be in UTF-16 (see `--out-encoding`)
+    let text = read_text(file).with_context(|| format!("could not read seed list {:?}", path))?;
+
+    let mut seeds = parse_seed_list(&text).with_context(|| format!("in seed list {:?}", path))?;
+    seeds.sort_unstable();
+    seeds.dedup();
+
+    Ok(seeds)
+}
+
+/// Parses the seeds of a seed list, in the order listed.
+fn parse_seed_list(text: &str) -> Result<Vec<u32>> {
+    // A single JSON document (`--json` output)
+    if text.trim_start().starts_with('{') {
+        if let Ok(document) = serde_json::from_str::<Value>(text) {
+            return match document.get("matches").and_then(Value::as_array) {
+                Some(matches) => matches.iter().filter_map(json_seed).collect(),
+                None => Err(anyhow!("JSON document has no 'matches'")),
+            };
+        }
+    }
+
+    let mut seeds = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+
+        // One JSON record per line (`--ndjson` output); only matches carry a seed
+        if line.starts_with('{') {
+            let record: Value = serde_json::from_str(line)
+                .with_context(|| format!("invalid JSON on line {}", i + 1))?;
+            if let Some(seed) = json_seed(&record) {
+                seeds.push(seed.with_context(|| format!("on line {}", i + 1))?);
+            }
+            continue;
+        }
+
+        for value in line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|v| !v.is_empty())
+        {
+            match value.parse::<u32>() {
+                Ok(seed) => seeds.push(seed),
+                Err(_) => return Err(anyhow!("invalid seed '{}' on line {}", value, i + 1)),
+            }
+        }
+    }
+
+    Ok(seeds)
+}
+
+/// Returns the seed of a JSON match record, if it has one.
+fn json_seed(record: &Value) -> Option<Result<u32>> {
+    let seed = record.get("seed")?;
+
+    Some(
+        seed.as_u64()
+            .and_then(|seed| u32::try_from(seed).ok())
+            .ok_or_else(|| anyhow!("invalid seed {}", seed)),
+    )
+}
+
+/// Writes the matching seeds of one or more searches as a seed list in the given encoding.
+/// Returns the number of seeds written.
+pub fn write_seed_list<'a, I>(path: &Path, encoding: OutputEncoding, lists: I) -> Result<usize>
+where
+    I: IntoIterator<Item = (&'a SearchParameters, &'a Vec<SearchMatch>)>,
+{
+    let mut writer = create_seed_list(path, encoding)?;
+    let mut count = 0;
+
+    for (search, matches) in lists {
+        if let Some(name) = search.name.as_ref() {
+            writeln!(writer, "# query: {}", name)?;
+        }
+
+        // Matches are grouped by seed
+        let mut seeds: Vec<u32> = matches.iter().map(|m| m.seed).collect();
+        seeds.dedup();
+
+        write_seeds(&mut writer, &seeds)?;
+        count += seeds.len();
+    }
+
+    writer.into_inner()?.flush()?;
+
+    Ok(count)
+}
+
+/// Creates a seed list file in the given encoding.
+pub fn create_seed_list(
+    path: &Path,
+    encoding: OutputEncoding,
+) -> Result<EncodedWriter<BufWriter<std::fs::File>>> {
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("could not create seed list {:?}", path))?;
+
+    Ok(EncodedWriter::new(BufWriter::new(file), encoding)?)
+}
+
+/// Writes seeds as a plain seed list, one seed per line.
+pub fn write_seeds<W: Write>(writer: &mut W, seeds: &[u32]) -> Result<()> {
+    for seed in seeds.iter() {
+        writeln!(writer, "{}", seed)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+//! Streaming of matching seeds for Brogue Seed Scanner.
+//!
+//! Callers embedding the scanner (the daemon's socket) pass a channel to `Engine::search`,
+//! which gives each search a `SeedSink`.  The matches of each seed are sent as soon as the
+//! seed is confirmed, in the order the search keeps them, so a long scan delivers its
+//! first seeds right away.  Seeds confirmed by parallel
+//! workers are sent once they're merged (workers find more seeds than are kept), and
+//! those of `--best` searches once the search ends.
+
+use crate::search::SearchMatch;
+use std::sync::mpsc::Sender;
+
+/// The matches of a seed, as streamed.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "server"), allow(dead_code))]
+pub struct StreamedSeed {
+    /// Name of the search, for searches from query files.
+    pub name: Option<String>,
+    pub matches: Vec<SearchMatch>,
+}
+
+/// Sends the matches of a search's seeds as they're confirmed.
+#[derive(Debug, Clone)]
+pub struct SeedSink {
+    name: Option<String>,
+    sender: Sender<StreamedSeed>,
+}
+
+impl SeedSink {
+    /// Makes a new sink for a search (named if it's from a query file).
+    #[cfg(feature = "server")]
+    pub fn new(name: Option<String>, sender: Sender<StreamedSeed>) -> Self {
+        Self { name, sender }
+    }
+    /// Sends the matches of each seed in a list (grouped by seed).  Seeds are dropped if
+    /// the receiver is gone.
+    pub fn send(&self, matches: &[SearchMatch]) {
+        for seed_matches in matches.chunk_by(|a, b| a.seed == b.seed) {
+            let seed = StreamedSeed {
+                name: self.name.clone(),
+                matches: seed_matches.to_vec(),
+            };
+            let _ = self.sender.send(seed);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Match templates (`--format`) for Brogue Seed Scanner.
+//!
+//! A match template sets exactly what the line printed for each matching object holds,
+//! e.g. `--format "{seed}\t{depth}\t{object}"`.  Fields in braces are replaced by the
+//! values of the match, and everything else is copied as is:
+//!
+//! - `{seed}`, `{version}` (dungeon version), `{depth}`, `{quantity}`
+//! - `{category}`, `{kind}`, `{enchantment}`, `{runic}`, `{ally_status}`, `{mutation}`
+//! - `{vault}` (vault the object is held in), `{opens_vault}` (for keys and altars),
+//!   and `{carried_by}` (monster carrying the object)
+//! - `{object}`:  the object as described in text output (e.g. "A +2 scale mail of
+//!   reflection"), and `{match}`:  the same, with its vault or carrier
+//!
+//! Fields the object doesn't have (e.g. the runic of an unrunic weapon) are empty.
+//! `{{` and `}}` are literal braces, and `\t`, `\n`, and `\\` are a tab, a line break,
+//! and a backslash, as shells don't expand them in quotes.
+
+use crate::search::SearchMatch;
+use anyhow::{anyhow, Result};
+
+/// Values of a match a template can hold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Seed,
+    Version,
+    Depth,
+    Quantity,
+    Category,
+    Kind,
+    Enchantment,
+    Runic,
+    AllyStatus,
+    Mutation,
+    Vault,
+    OpensVault,
+    CarriedBy,
+    Object,
+    Match,
+}
+
+impl Field {
+    /// Names of the fields, for errors.
+    const NAMES: &'static str = "seed, version, depth, quantity, category, kind, enchantment, runic, \
+        ally_status, mutation, vault, opens_vault, carried_by, object, match";
+
+    /// Parses a field by name.
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "seed" => Some(Self::Seed),
+            "version" => Some(Self::Version),
+            "depth" => Some(Self::Depth),
+            "quantity" => Some(Self::Quantity),
+            "category" => Some(Self::Category),
+            "kind" => Some(Self::Kind),
+            "enchantment" => Some(Self::Enchantment),
+            "runic" => Some(Self::Runic),
+            "ally_status" => Some(Self::AllyStatus),
+            "mutation" => Some(Self::Mutation),
+            "vault" => Some(Self::Vault),
+            "opens_vault" => Some(Self::OpensVault),
+            "carried_by" => Some(Self::CarriedBy),
+            "object" => Some(Self::Object),
+            "match" => Some(Self::Match),
+            _ => None,
+        }
+    }
+    /// Returns the value of the field for a match (empty if it has none).
+    fn value(self, m: &SearchMatch) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+
+        match self {
+            Self::Seed => m.seed.to_string(),
+            Self::Version => m.version.clone(),
+            Self::Depth => m.depth.to_string(),
+            Self::Quantity => m.quantity.to_string(),
+            Self::Category => m.object.category().to_string(),
+            Self::Kind => m.object.kind_name(),
+            Self::Enchantment => optional(m.object.enchantment().map(|e| e.to_string())),
+            Self::Runic => optional(m.object.runic_name()),
+            Self::AllyStatus => optional(m.object.ally_status().map(|s| s.to_string())),
+            Self::Mutation => optional(m.object.mutation().map(|s| s.to_string())),
+            Self::Vault => optional(m.vault.map(|v| v.to_string())),
+            Self::OpensVault => optional(m.object.opens_vault().map(|v| v.to_string())),
+            Self::CarriedBy => optional(m.carried_by.map(|c| c.to_string())),
+            Self::Object => m.object.to_string(),
+            Self::Match => m.to_string(),
+        }
+    }
+}
+
+/// A piece of a template.
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Field(Field),
+}
+
+/// A parsed `--format` template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchTemplate {
+    parts: Vec<Part>,
+}
+
+impl MatchTemplate {
+    /// Parses a template, checking its field names.  Empty templates aren't valid.
+    pub fn parse(template: &str) -> Result<Self> {
+        let invalid = |reason: String| anyhow!("invalid --format '{}':  {}", template, reason);
+        if template.is_empty() {
+            return Err(invalid(
+                "empty template (it would print a blank line per match)".to_string(),
+            ));
+        }
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('}').ok_or_else(|| invalid("unclosed '{'".to_string()))?;
+                    let name = rest[..end].trim();
+                    let field = Field::parse(name).ok_or_else(|| {
+                        invalid(format!(
+                            "unknown field '{{{}}}' (fields: {})",
+                            name,
+                            Field::NAMES
+                        ))
+                    })?;
+                    chars = rest[end + 1..].chars();
+
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(field));
+                }
+                '}' => return Err(invalid("unmatched '}' (use '}}' for a brace)".to_string())),
+                '\\' => match chars.next() {
+                    Some('t') => text.push('\t'),
+                    Some('n') => text.push('\n'),
+                    Some('\\') => text.push('\\'),
+                    Some(other) => {
+                        text.push('\\');
+                        text.push(other);
+                    }
+                    None => text.push('\\'),
+                },
+                _ => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Self { parts })
+    }
+    /// Returns the line of a match.
+    pub fn render(&self, m: &SearchMatch) -> String {
+        let mut line = String::new();
+
+        for part in self.parts.iter() {
+            match part {
+                Part::Text(text) => line.push_str(text),
+                Part::Field(field) => line.push_str(&field.value(m)),
+            }
+        }
+
+        line
+    }
+}
@@ -0,0 +1,99 @@
+//! Vault listings for Brogue Seed Scanner.
+//!
+//! With `--vaults`, every vault on the searched depths of a seed is assembled during the
+//! seed pass (from the vault and opens columns), and listed under each matching seed
+//! with its full contents and whether its key was found on the depth:
+//! ```
+//! Seed 2
+//!     Vault 1 on depth 3 (key found):  +3 ring of regeneration, +1 charm of guardian, ...
+//! ```
+
+use super::{MatchResponse, SearchMatch};
+use crate::columns;
+use crate::objects::Category;
+use anyhow::Result;
+use csv::StringRecord;
+
+/// A vault of a seed, with its contents in catalog order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeedVault {
+    pub depth: u8,
+    pub number: u8,
+    /// Objects in the vault, as displayed.
+    pub contents: Vec<String>,
+    /// Whether a key opening the vault was found on its depth.
+    pub key_found: bool,
+}
+
+impl SeedVault {
+    /// Makes a new (empty) vault, without a key.
+    fn new(depth: u8, number: u8) -> Self {
+        Self {
+            depth,
+            number,
+            contents: Vec::new(),
+            key_found: false,
+        }
+    }
+}
+
+impl std::fmt::Display for SeedVault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let key = match self.key_found {
+            true => "key found",
+            false => "no key found",
+        };
+        match self.contents.is_empty() {
+            true => write!(
+                f,
+                "Vault {} on depth {} ({}):  empty",
+                self.number, self.depth, key
+            ),
+            false => write!(
+                f,
+                "Vault {} on depth {} ({}):  {}",
+                self.number,
+                self.depth,
+                key,
+                self.contents.join(", ")
+            ),
+        }
+    }
+}
+
+/// Adds a Record (line in .csv file) of a seed to its vaults:  vault objects to the
+/// contents of their vault, and keys to the vault they open.
+pub(crate) fn add_vault_record(
+    vaults: &mut Vec<SeedVault>,
+    category: Category,
+    seed: u32,
+    depth: u8,
+    record: &StringRecord,
+) -> Result<()> {
+    if let Ok(number) = record[columns::VAULT].parse::<u8>() {
+        let object =
+            SearchMatch::from_record(category, MatchResponse::DoNothing, seed, depth, record)?
+                .object;
+        vault_entry(vaults, depth, number).contents.push(object.to_string());
+    }
+    if category == Category::Key {
+        if let Ok(number) = record[columns::OPENS_VAULT].parse::<u8>() {
+            vault_entry(vaults, depth, number).key_found = true;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the vault with the given number on a depth, adding it if not yet seen.
+fn vault_entry(vaults: &mut Vec<SeedVault>, depth: u8, number: u8) -> &mut SeedVault {
+    let ix = match vaults.iter().position(|v| v.depth == depth && v.number == number) {
+        Some(ix) => ix,
+        None => {
+            vaults.push(SeedVault::new(depth, number));
+            vaults.len() - 1
+        }
+    };
+
+    &mut vaults[ix]
+}
@@ -0,0 +1,137 @@
+//! Seed detail for Brogue Seed Scanner.
+//!
+//! The `show` subcommand prints the complete catalog of a single seed:  every object on
+//! every depth (within `--mindepth` and `--maxdepth`), color-coded by category (see
+//! `--color`).  It's meant for vetting a seed a search turned up without
+//! opening the catalog files by hand:
+//! ```
+//! brogue-scanner -F ./catalogs show 2381
+//! ```
+//! `--dump-seed SEED` prints the same catalog without colors in place of a search, or
+//! writes it as `--json`/`--ndjson` matches for other tools.
+//!
+//! Files named by a seed range, or recorded by the catalog index, that can't hold the seed
+//! are skipped, and the seed is read from the first file that has it (as in `compact`).
+
+use crate::columns;
+use crate::color::{category_color, RESET};
+use crate::file_handling::{open_catalog, seed_range_from_name, CatalogReader};
+use crate::index::CatalogIndexes;
+use crate::objects::Category;
+use crate::search::{MatchResponse, RowError, SearchMatch, SearchParameters};
+use crate::status::no_files;
+use anyhow::Result;
+
+/// Returns every object of the seed within the search's depth range, in catalog order,
+/// from the first of the search's catalog files that has the seed.
+pub fn seed_catalog(search: &SearchParameters, seed: u32) -> Result<Vec<SearchMatch>> {
+    if search.file_paths.is_empty() {
+        return Err(no_files());
+    }
+
+    let mut seed_search = search.clone();
+    seed_search.seed_min = seed;
+    seed_search.seed_max = seed;
+    let indexes = CatalogIndexes::load(&search.file_dirs);
+
+    for file_path in search.file_paths.iter() {
+        let out_of_range = seed_range_from_name(file_path)
+            .is_some_and(|(first, last)| seed < first || seed > last);
+
+        if out_of_range
+            || indexes
+                .skipped_seeds(file_path, std::slice::from_ref(&seed_search))
+                .is_some()
+        {
+            if search.debug {
+                println!("skipping file: {:?}", file_path);
+            }
+            continue;
+        }
+
+        // Invalid files are skipped, as in a search, but not objects the tables can't read
+        let objects = match open_catalog(file_path, search.format, &search.schema)
+            .and_then(|mut rdr| seed_objects(&mut rdr, &seed_search))
+        {
+            Err(e) if e.is::<RowError>() => return Err(e.context(format!("invalid catalog file {:?}", file_path))),
+            result => result.unwrap_or_default(),
+        };
+
+        if !objects.is_empty() {
+            return Ok(objects);
+        }
+    }
+
+    Ok(Vec::new())
+}
+
+/// Returns every object of the search's (single) seed in a catalog file.
+fn seed_objects(rdr: &mut CatalogReader, search: &SearchParameters) -> Result<Vec<SearchMatch>> {
+    let mut objects = Vec::new();
+
+    for record_result in rdr.records() {
+        let record = record_result?;
+        let seed = record[columns::SEED].parse::<u32>()?;
+
+        // Seeds are ascending within a file
+        if seed < search.seed_min {
+            continue;
+        }
+        if seed > search.seed_max {
+            break;
+        }
+
+        let depth = record[columns::DEPTH].parse::<u8>()?;
+
+        if depth < search.depth_min || depth > search.depth_max {
+            continue;
+        }
+        if let Some(category) = Category::parse(&record[columns::CATEGORY]) {
+            objects.push(SearchMatch::from_record(
+                category,
+                MatchResponse::Increment,
+                seed,
+                depth,
+                &record,
+            )?);
+        }
+    }
+
+    Ok(objects)
+}
+
+/// Prints the catalog of a seed by depth, coloring each object by category if `color`.
+pub fn display_seed(seed: u32, objects: &[SearchMatch], color: bool) {
+    if objects.is_empty() {
+        println!("Seed {} isn't in any catalog file.\n", seed);
+        return;
+    }
+
+    let mut depths: Vec<u8> = objects.iter().map(|o| o.depth).collect();
+    depths.dedup();
+    println!("Seed {}", seed);
+
+    let mut depth = 0;
+
+    for object in objects {
+        if object.depth != depth {
+            depth = object.depth;
+            println!("    Depth {}", depth);
+        }
+        match color {
+            true => println!(
+                "        {}{}{}",
+                category_color(object.object.category()),
+                object,
+                RESET
+            ),
+            false => println!("        {}", object),
+        }
+    }
+
+    println!(
+        "\n...{} objects on {} depths.\n",
+        objects.len(),
+        depths.len()
+    );
+}
@@ -0,0 +1,197 @@
+//! Self-update for Brogue Seed Scanner.
+//!
+//! `brogue-scanner self-update` replaces the running executable with the latest release
+//! of the project, for users who can't easily rebuild it.  Each release on GitHub holds
+//! a binary per platform, named `brogue-scanner-OS-ARCH` (with `.exe` on Windows, e.g.
+//! `brogue-scanner-linux-x86_64` or `brogue-scanner-windows-x86_64.exe`), and a
+//! `SHA256SUMS` file listing the SHA-256 checksum of each (`CHECKSUM  NAME` lines, as
+//! written by `sha256sum`).
+//!
+//! The release information and files are downloaded over HTTPS (see `http`), and the
+//! binary is only installed if its checksum matches.  The old executable is renamed out of the way
+//! before the new one takes its place, and restored if that fails.
+//!
+//! `SHA256SUMS` comes from the same release as the binary, so the checksum only shows the
+//! download is intact, not who built it:  anyone able to change the release's files can
+//! change both.  Trust in an update rests on HTTPS and the project's GitHub account.
+
+use crate::http::get;
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Latest release of the project.
+const RELEASES_URL: &str =
+    "https://api.github.com/repos/ArchTangent/brogue-scanner/releases/latest";
+
+/// Checksums file of a release.
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+
+/// A release of the project, as read from the GitHub API.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Release {
+    pub tag_name: String,
+    assets: Vec<Asset>,
+}
+
+/// A file of a release.
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    /// Returns the download URL of a file of the release.
+    pub fn asset_url(&self, name: &str) -> Result<&str> {
+        self.assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .map(|asset| asset.browser_download_url.as_str())
+            .ok_or_else(|| anyhow!("release {} has no '{}' file (no build for this platform?)", self.tag_name, name))
+    }
+}
+
+/// What `self-update` did.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    /// The running version is the latest.
+    UpToDate(String),
+    /// A newer version is out (`--check`).
+    Available(String),
+    /// The executable at the path was updated to the version.
+    Updated(String, PathBuf),
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UpToDate(version) => write!(f, "Brogue Seed Scanner {} is up to date.", version),
+            Self::Available(version) => {
+                write!(
+                    f,
+                    "Version {} is out (running {}); run 'brogue-scanner self-update' to install it.",
+                    version,
+                    env!("CARGO_PKG_VERSION")
+                )
+            }
+            Self::Updated(version, path) => write!(f, "Updated {:?} to version {}.", path, version),
+        }
+    }
+}
+
+/// Updates the running executable to the latest release, or only checks for one.
+pub fn self_update(check_only: bool) -> Result<Outcome> {
+    let current = env!("CARGO_PKG_VERSION");
+    let release: Release =
+        serde_json::from_slice(&get(RELEASES_URL)?).context("invalid release information")?;
+
+    if !is_newer(&release.tag_name, current)? {
+        return Ok(Outcome::UpToDate(current.to_string()));
+    }
+    if check_only {
+        return Ok(Outcome::Available(release.tag_name));
+    }
+
+    let name = asset_name();
+    let binary = get(release.asset_url(&name)?)?;
+    let checksums = get(release.asset_url(CHECKSUMS_ASSET)?)?;
+    verify_checksum(&binary, &String::from_utf8_lossy(&checksums), &name)?;
+
+    let exe = std::env::current_exe().context("can't find the running executable")?;
+    replace_executable(&exe, &binary)?;
+
+    Ok(Outcome::Updated(release.tag_name, exe))
+}
+
+/// Returns the name of the release file for this platform.
+pub(crate) fn asset_name() -> String {
+    format!(
+        "brogue-scanner-{}-{}{}",
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        std::env::consts::EXE_SUFFIX
+    )
+}
+
+/// Returns `true` if a release tag (e.g. "v0.9.7") is a later version than `current`.
+/// Missing parts count as 0 ("v1.0" is the same version as "1.0.0").
+pub(crate) fn is_newer(tag: &str, current: &str) -> Result<bool> {
+    let parse = |version: &str| {
+        let mut parts = version
+            .trim_start_matches('v')
+            .split('.')
+            .map(|part| part.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| anyhow!("invalid version '{}'", version))?;
+        while parts.last() == Some(&0) {
+            parts.pop();
+        }
+        Ok::<_, anyhow::Error>(parts)
+    };
+
+    Ok(parse(tag)? > parse(current)?)
+}
+
+/// Checks a downloaded file against its line in a checksums file.
+pub(crate) fn verify_checksum(data: &[u8], checksums: &str, name: &str) -> Result<()> {
+    let expected = checksums
+        .lines()
+        .find_map(|line| {
+            let (checksum, file) = line.split_once(char::is_whitespace)?;
+            (file.trim_start().trim_start_matches('*') == name)
+                .then(|| checksum.to_ascii_lowercase())
+        })
+        .ok_or_else(|| anyhow!("no checksum for '{}' in {}", name, CHECKSUMS_ASSET))?;
+    let actual = sha256_hex(data);
+
+    match actual == expected {
+        true => Ok(()),
+        false => Err(anyhow!(
+            "checksum mismatch for '{}' (expected {}, got {}); nothing was changed",
+            name,
+            expected,
+            actual
+        )),
+    }
+}
+
+/// Replaces an executable with a new binary, keeping its permissions.  The old executable
+/// is renamed first (a running executable can be renamed, even on Windows), and restored
+/// if the new one can't take its place.  On failure, no new binary is left behind.
+pub(crate) fn replace_executable(exe: &Path, binary: &[u8]) -> Result<()> {
+    let (new, old) = (exe.with_extension("new"), exe.with_extension("old"));
+    let permissions = fs::metadata(exe)
+        .with_context(|| format!("can't read {:?}", exe))?
+        .permissions();
+
+    fs::write(&new, binary).with_context(|| format!("can't write {:?}", new))?;
+    if let Err(e) = fs::set_permissions(&new, permissions) {
+        let _ = fs::remove_file(&new);
+        return Err(anyhow!("can't set the permissions of {:?}:  {}", new, e));
+    }
+    let _ = fs::remove_file(&old);
+    if let Err(e) = fs::rename(exe, &old) {
+        let _ = fs::remove_file(&new);
+        return Err(anyhow!("can't move {:?} out of the way:  {}", exe, e));
+    }
+
+    if let Err(e) = fs::rename(&new, exe) {
+        let _ = fs::rename(&old, exe);
+        return Err(anyhow!("can't install {:?}:  {}", exe, e));
+    }
+    // Windows won't remove a running executable:  it's removed by the next update instead
+    let _ = fs::remove_file(&old);
+
+    Ok(())
+}
+
+/// Returns the SHA-256 digest of `data`, in lowercase hex.
+pub(crate) fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,168 @@
+//! Shareable seed strings for Brogue Seed Scanner.
+//!
+//! `--share` prints each matching seed as a single line that can be posted anywhere and
+//! read back, losslessly, with the `decode` subcommand:
+//! ```text
+//! brogue:1/CE+1.9/12345/3,1,armor,banded+mail,2,reflection/5,1,ally,goblin+mystic,,,,,,shackled
+//! ```
+//! After the `brogue:` scheme and the format version (`1`) come the dungeon version, the
+//! seed, and one segment per matching object.  Each object holds its catalog fields after
+//! `seed`, in standard column order (depth, quantity, category, kind, enchantment, runic,
+//! vault, opens vault, carried by, ally status, and mutation), separated by commas and
+//! without trailing empty fields.  Text is percent-encoded, with spaces as `+`.
+
+use crate::columns;
+use crate::objects::Category;
+use crate::search::{MatchResponse, SearchMatch};
+use anyhow::{anyhow, Result};
+use csv::StringRecord;
+
+/// Scheme of share strings.
+pub const SCHEME: &str = "brogue:";
+
+/// Format version of share strings.
+const SHARE_VERSION: &str = "1";
+
+/// Returns a share string for each seed of a list of matches, in order.
+pub fn share_strings(matches: &[SearchMatch]) -> Vec<String> {
+    matches.chunk_by(|a, b| a.seed == b.seed).map(encode).collect()
+}
+
+/// Returns the share string of the matches of a single seed.
+fn encode(matches: &[SearchMatch]) -> String {
+    let mut share = format!(
+        "{}{}/{}/{}",
+        SCHEME,
+        SHARE_VERSION,
+        escape(&matches[0].version),
+        matches[0].seed
+    );
+
+    for m in matches {
+        let mut fields = record_fields(m);
+        while fields.last().is_some_and(String::is_empty) {
+            fields.pop();
+        }
+        let fields: Vec<String> = fields.iter().map(|field| escape(field)).collect();
+
+        share.push('/');
+        share.push_str(&fields.join(","));
+    }
+
+    share
+}
+
+/// Reads the matches of a seed back from its share string.
+pub fn decode(value: &str) -> Result<Vec<SearchMatch>> {
+    let invalid = |reason: &str| anyhow!("invalid share string '{}':  {}", value, reason);
+    let rest = value
+        .trim()
+        .strip_prefix(SCHEME)
+        .ok_or_else(|| invalid("expected 'brogue:' at the start"))?;
+    let mut segments = rest.split('/');
+
+    if segments.next() != Some(SHARE_VERSION) {
+        return Err(invalid("unsupported format version (expected 'brogue:1/')"));
+    }
+    let version = segments
+        .next()
+        .and_then(unescape)
+        .ok_or_else(|| invalid("bad dungeon version"))?;
+    let seed = segments
+        .next()
+        .and_then(|seed| seed.parse::<u32>().ok())
+        .ok_or_else(|| invalid("bad seed"))?;
+    let matches = segments
+        .enumerate()
+        .map(|(i, segment)| {
+            decode_object(&version, seed, segment)
+                .ok_or_else(|| invalid(&format!("bad object {} ('{}')", i + 1, segment)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    match matches.is_empty() {
+        true => Err(invalid("no objects")),
+        false => Ok(matches),
+    }
+}
+
+/// Reads an object of a share string, if it's valid and nothing in it would be lost.
+fn decode_object(version: &str, seed: u32, segment: &str) -> Option<SearchMatch> {
+    let mut fields = vec![version.to_string(), seed.to_string()];
+
+    for field in segment.split(',') {
+        fields.push(unescape(field)?);
+    }
+    if fields.len() > columns::HEADERS.len() {
+        return None;
+    }
+    fields.resize(columns::HEADERS.len(), String::new());
+
+    let record = StringRecord::from(fields);
+    let category = Category::parse(&record[columns::CATEGORY])
+        .filter(|c| c.to_string() == record[columns::CATEGORY])?;
+    let depth = record[columns::DEPTH].parse::<u8>().ok()?;
+    let m = SearchMatch::parse_record(category, MatchResponse::Increment, seed, depth, &record)?;
+
+    // Values the object doesn't keep (e.g. an unknown runic) would be lost
+    let kept = record_fields(&m)
+        .iter()
+        .zip(record.iter().skip(columns::DEPTH))
+        .all(|(a, b)| a == b);
+    kept.then_some(m)
+}
+
+/// Returns the catalog fields of a match after `seed`, in standard column order.
+fn record_fields(m: &SearchMatch) -> Vec<String> {
+    let optional = |value: Option<String>| value.unwrap_or_default();
+
+    vec![
+        m.depth.to_string(),
+        m.quantity.to_string(),
+        m.object.category().to_string(),
+        m.object.kind_name(),
+        optional(m.object.enchantment().map(|e| e.to_string())),
+        optional(m.object.runic_name()),
+        optional(m.vault.map(|v| v.to_string())),
+        optional(m.object.opens_vault().map(|v| v.to_string())),
+        optional(m.carried_by.map(|c| c.to_string())),
+        optional(m.object.ally_status().map(|s| s.to_string())),
+        optional(m.object.mutation().map(|s| s.to_string())),
+    ]
+}
+
+/// Percent-encodes text for a share string, with spaces as `+`.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => escaped.push(byte as char),
+            b' ' => escaped.push('+'),
+            _ => escaped.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    escaped
+}
+
+/// Decodes percent-encoded text of a share string, if it's valid.
+fn unescape(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+
+    while let Some((&byte, after)) = rest.split_first() {
+        rest = after;
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = rest.get(..2).filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))?;
+                bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+                rest = &rest[2..];
+            }
+            _ => bytes.push(byte),
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
@@ -0,0 +1,284 @@
+//! Depth-bucketed statistics for Brogue Seed Scanner.
+//!
+//! The `stats` subcommand counts a category (or kind) of object across every seed in the
+//! scanned range, bucketed by depth, to answer questions like "how rare is X before
+//! depth 5".  General options (`--filepath`, `--minseed`, `--json`, etc.) go before the
+//! subcommand:
+//! ```
+//! brogue-scanner --maxseed 5000 stats armor banded --bucket 5
+//! ```
+
+use crate::columns;
+use crate::file_handling::{open_catalog, CatalogReader};
+use crate::objects::Category;
+use crate::search::SearchParameters;
+use crate::status::no_files;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+/// Parameters for the `stats` subcommand.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsParameters {
+    pub category: Category,
+    /// Object kind (partial match allowed), if any.
+    pub kind: Option<String>,
+    /// Number of depths per bucket.
+    pub bucket_size: u8,
+}
+
+impl StatsParameters {
+    /// Creates a new instance from `stats` subcommand matches.
+    pub fn from_matches(matches: &clap::ArgMatches) -> Result<Self> {
+        // CATEGORY is required, so always present.
+        let category_val = matches.value_of("category").unwrap();
+        let category = match Category::parse(&category_val.to_lowercase()) {
+            Some(category) => category,
+            None => return Err(anyhow!("invalid stats category '{}'", category_val)),
+        };
+
+        let kind = matches.value_of("kind").map(|kind| kind.to_lowercase());
+
+        // BUCKET has default of 3, so always present.
+        let bucket_size = match matches.value_of("bucket").unwrap().parse::<u8>() {
+            Ok(val) if (1..=26).contains(&val) => val,
+            _ => return Err(anyhow!("--bucket must be from 1 to 26")),
+        };
+
+        Ok(Self {
+            category,
+            kind,
+            bucket_size,
+        })
+    }
+}
+
+/// Counts for a single bucket of depths.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DepthBucket {
+    pub depth_min: u8,
+    pub depth_max: u8,
+    /// Total quantity of matching objects in the bucket.
+    pub objects: u64,
+    /// Average quantity of matching objects in the bucket per seed.
+    pub objects_per_seed: f64,
+    /// Number of seeds with at least one matching object in the bucket.
+    pub seeds: u64,
+    /// Number of seeds with at least one matching object by the bucket's deepest depth.
+    pub seeds_by_depth: u64,
+}
+
+impl DepthBucket {
+    fn new(depth_min: u8, depth_max: u8) -> Self {
+        Self {
+            depth_min,
+            depth_max,
+            objects: 0,
+            objects_per_seed: 0.0,
+            seeds: 0,
+            seeds_by_depth: 0,
+        }
+    }
+}
+
+/// Depth-bucketed statistics over all scanned seeds.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Stats {
+    pub category: String,
+    pub kind: Option<String>,
+    pub depth_min: u8,
+    pub depth_max: u8,
+    /// Number of seeds scanned.
+    pub seeds: u64,
+    /// Total quantity of matching objects.
+    pub objects: u64,
+    /// Average quantity of matching objects per seed.
+    pub objects_per_seed: f64,
+    /// Number of seeds with at least one matching object.
+    pub seeds_with: u64,
+    pub buckets: Vec<DepthBucket>,
+}
+
+/// Per-seed accumulator for `collect_stats`.
+struct SeedTally {
+    prev_seed: Option<u32>,
+    /// Whether each bucket had a matching object this seed.
+    found: Vec<bool>,
+}
+
+impl SeedTally {
+    /// Closes out the current seed, adding its bucket hits to the statistics.
+    fn finish_seed(&mut self, stats: &mut Stats) {
+        if let Some(first) = self.found.iter().position(|found| *found) {
+            stats.seeds_with += 1;
+
+            for (i, bucket) in stats.buckets.iter_mut().enumerate() {
+                if self.found[i] {
+                    bucket.seeds += 1;
+                }
+                if i >= first {
+                    bucket.seeds_by_depth += 1;
+                }
+            }
+        }
+        self.found.iter_mut().for_each(|found| *found = false);
+    }
+}
+
+/// Collects depth-bucketed statistics for an object over the seed and depth range and
+/// catalog files of the given search.
+pub fn collect_stats(search: &SearchParameters, params: &StatsParameters) -> Result<Stats> {
+    if search.file_paths.is_empty() {
+        return Err(no_files());
+    }
+
+    let size = params.bucket_size;
+    let buckets: Vec<DepthBucket> = (search.depth_min..=search.depth_max)
+        .step_by(size.into())
+        .map(|d| DepthBucket::new(d, d.saturating_add(size - 1).min(search.depth_max)))
+        .collect();
+
+    let mut tally = SeedTally {
+        prev_seed: None,
+        found: vec![false; buckets.len()],
+    };
+    let mut stats = Stats {
+        category: params.category.to_string(),
+        kind: params.kind.clone(),
+        depth_min: search.depth_min,
+        depth_max: search.depth_max,
+        seeds: 0,
+        objects: 0,
+        objects_per_seed: 0.0,
+        seeds_with: 0,
+        buckets,
+    };
+
+    for file_path in search.file_paths.iter() {
+        if search.debug {
+            println!("scanning file: {:?}", file_path);
+        }
+
+        // Invalid files are skipped, as in a search
+        let _ = open_catalog(file_path, search.format, &search.schema)
+            .and_then(|mut rdr| stats_file(&mut rdr, search, params, &mut tally, &mut stats));
+    }
+
+    tally.finish_seed(&mut stats);
+
+    if stats.seeds > 0 {
+        let seeds = stats.seeds as f64;
+        stats.objects_per_seed = stats.objects as f64 / seeds;
+
+        for bucket in stats.buckets.iter_mut() {
+            bucket.objects_per_seed = bucket.objects as f64 / seeds;
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Adds the Records of a single catalog file to the statistics.
+fn stats_file(
+    rdr: &mut CatalogReader,
+    search: &SearchParameters,
+    params: &StatsParameters,
+    tally: &mut SeedTally,
+    stats: &mut Stats,
+) -> Result<()> {
+    let category_flags = params.category.to_flags();
+
+    for record_result in rdr.records() {
+        let record = record_result?;
+        let seed = record[columns::SEED].parse::<u32>()?;
+
+        // Seeds are ascending within a file
+        if seed < search.seed_min {
+            continue;
+        }
+        if seed > search.seed_max {
+            break;
+        }
+        if !search.is_seed_searched(seed) {
+            continue;
+        }
+
+        if tally.prev_seed != Some(seed) {
+            tally.finish_seed(stats);
+            tally.prev_seed = Some(seed);
+            stats.seeds += 1;
+        }
+
+        let depth = record[columns::DEPTH].parse::<u8>()?;
+
+        if depth < search.depth_min || depth > search.depth_max {
+            continue;
+        }
+
+        let matched = Category::parse(&record[columns::CATEGORY])
+            .is_some_and(|category| category.to_flags().intersects(category_flags))
+            && params
+                .kind
+                .as_ref()
+                .is_none_or(|kind| record[columns::KIND].contains(kind.as_str()));
+
+        if matched {
+            let index = usize::from((depth - search.depth_min) / params.bucket_size);
+            let quantity = record[columns::QUANTITY].parse::<u64>()?;
+
+            stats.buckets[index].objects += quantity;
+            stats.objects += quantity;
+            tally.found[index] = true;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns `part` as a percentage of `whole`.
+fn percent(part: u64, whole: u64) -> f64 {
+    match whole {
+        0 => 0.0,
+        _ => part as f64 * 100.0 / whole as f64,
+    }
+}
+
+/// Prints statistics as a table, formatting counts as for the search.
+pub fn display_stats(stats: &Stats, search: &SearchParameters) {
+    match stats.kind.as_ref() {
+        Some(kind) => print!("Stats: {} '{}'", stats.category, kind),
+        None => print!("Stats: {}", stats.category),
+    }
+    println!(
+        " over {} (depths {} to {})\n",
+        search.counted(stats.seeds, "seed", "seeds"),
+        stats.depth_min,
+        stats.depth_max
+    );
+
+    println!("  Depths    Objects   Per Seed      Seeds   % Seeds   % By Depth");
+
+    for bucket in stats.buckets.iter() {
+        let depths = match bucket.depth_min == bucket.depth_max {
+            true => format!("{}", bucket.depth_min),
+            false => format!("{}-{}", bucket.depth_min, bucket.depth_max),
+        };
+        println!(
+            "  {:<7} {:>9}  {:>9.3}  {:>9}  {:>7.1}%  {:>10.1}%",
+            depths,
+            search.number(bucket.objects),
+            bucket.objects_per_seed,
+            search.number(bucket.seeds),
+            percent(bucket.seeds, stats.seeds),
+            percent(bucket.seeds_by_depth, stats.seeds),
+        );
+    }
+
+    println!(
+        "  {:<7} {:>9}  {:>9.3}  {:>9}  {:>7.1}%\n",
+        "Total",
+        search.number(stats.objects),
+        stats.objects_per_seed,
+        search.number(stats.seeds_with),
+        percent(stats.seeds_with, stats.seeds),
+    );
+}
@@ -0,0 +1,122 @@
+//! Final status of a search for Brogue Seed Scanner.
+//!
+//! Every search ends with a status, so users and wrapper tools can tell what to fix:
+//!
+//! - `matches`:  at least one seed matched
+//! - `no_matches`:  the query is valid, but no seed in range matched (widen the seed or
+//!   depth range, or relax the query)
+//! - `no_files`:  no catalog files were found (fix `--filepath`); exit code 3
+//! - `bad_query`:  the query (or a query file) is invalid (fix the query); exit code 2
+//! - `error`:  anything else, e.g. an unreadable file; exit code 1
+//!
+//! Text output ends with a `Status:` line (on standard error for failures).  In machine
+//! output, each `summary` carries a `status` field (`matches` or `no_matches`), and a
+//! failure is reported as a `status` record (see `search::schema`).  Porcelain output
+//! only reports failures, on standard error.
+
+use crate::search::ColumnError;
+use serde::Serialize;
+
+/// Final status of a search.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Matches,
+    NoMatches,
+    NoFiles,
+    BadQuery,
+    Error,
+}
+
+impl Status {
+    /// Returns the status of a search that found the given number of matching seeds.
+    pub fn of_seeds(seeds: usize) -> Self {
+        match seeds {
+            0 => Self::NoMatches,
+            _ => Self::Matches,
+        }
+    }
+    /// Returns the status of a failed search.
+    pub fn of_error(e: &anyhow::Error) -> Self {
+        if e.is::<NoFilesError>() {
+            Self::NoFiles
+        } else if e.is::<QueryError>() || e.is::<ColumnError>() {
+            Self::BadQuery
+        } else {
+            Self::Error
+        }
+    }
+    /// Returns the process exit code for the status.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Matches | Self::NoMatches => 0,
+            Self::Error => 1,
+            Self::BadQuery => 2,
+            Self::NoFiles => 3,
+        }
+    }
+    /// Returns what to do about the status, if anything.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Self::Matches => None,
+            Self::NoMatches => Some("widen the seed or depth range, or relax the query"),
+            Self::NoFiles => Some("check --filepath, and --utf8 for UTF-8 catalogs"),
+            Self::BadQuery => Some("fix the query"),
+            Self::Error => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Matches => "matches found",
+            Self::NoMatches => "no matches",
+            Self::NoFiles => "no catalog files found",
+            Self::BadQuery => "bad query",
+            Self::Error => "error",
+        };
+
+        match self.hint() {
+            Some(hint) => write!(f, "{} ({})", name, hint),
+            None => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Error for a search without catalog files to search.
+#[derive(Debug)]
+pub struct NoFilesError(pub String);
+
+impl std::fmt::Display for NoFilesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for NoFilesError {}
+
+/// Error for an invalid query (command line options or query file).
+#[derive(Debug)]
+pub struct QueryError(String);
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// Returns the error for a search without catalog files.
+pub fn no_files() -> anyhow::Error {
+    anyhow::Error::new(NoFilesError("No files found!".to_string()))
+}
+
+/// Marks an error from parsing a query as a bad query (unless no files were found).
+pub fn bad_query(e: anyhow::Error) -> anyhow::Error {
+    match e.is::<NoFilesError>() {
+        true => e,
+        false => anyhow::Error::new(QueryError(format!("{:#}", e))),
+    }
+}
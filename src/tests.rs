@@ -4,9 +4,48 @@
 // TODO: search with different Object categories (e.g. -a, -w, -p)
 
 use crate::*;
+use crate::search::{
+    SearchParameters, SearchMatch, search_files, search_files_batch, sort_by_required_depth, write_machine_matches
+};
 
 const FILE: &str = "./src/test_data.csv";
 
+/// A temporary directory for a test, removed with everything in it when dropped (even if
+/// the test fails first).
+struct TempDir(std::path::PathBuf);
+
+impl TempDir {
+    /// Makes an empty directory named for the test (`brogue-NAME-test-PID`).
+    fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("brogue-{}-test-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+
+        Self(path)
+    }
+}
+
+impl std::ops::Deref for TempDir {
+    type Target = std::path::PathBuf;
+
+    fn deref(&self) -> &std::path::PathBuf {
+        &self.0
+    }
+}
+
+impl AsRef<std::path::Path> for TempDir {
+    fn as_ref(&self) -> &std::path::Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
 // Checks number of *lines* (not seeds) that match the armor query.
 // --armor [COUNT] [ENCHANTMENT] [DEPTH] [KIND] [MAGIC] [RUNIC] [VAULT] {"runic"}
 #[test]
@@ -23,4 +62,8333 @@ fn armor() {
     let match_count = search_matches.len();
 
     assert_eq!(match_count, 7);
-}
\ No newline at end of file
+}
+
+// Checks that NDJSON output has one versioned record per match plus a summary line.
+#[test]
+fn ndjson_schema() {
+    let args = &["brogue-scanner", "-a", "scale", "--ndjson"];
+    let matches = new_app().get_matches_from(args);
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.set_file(FILE);
+
+    let search_matches = search_files(&mut search).unwrap();
+    let mut buffer = Vec::new();
+    write_machine_matches(&mut buffer, &search_matches, &search).unwrap();
+
+    let output = String::from_utf8(buffer).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+
+    assert_eq!(lines.len(), 8);
+    assert!(lines[0].starts_with("{\"format_version\":1,\"type\":\"match\""));
+    assert!(lines[7].starts_with("{\"format_version\":1,\"type\":\"summary\""));
+}
+
+// Checks that porcelain output is one tab-separated line per match, and nothing else.
+#[test]
+fn porcelain_output() {
+    let args = &["brogue-scanner", "-a", "scale", "-m", "2", "--porcelain"];
+    let matches = new_app().get_matches_from(args);
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.set_file(FILE);
+
+    let search_matches = search_files(&mut search).unwrap();
+    let mut buffer = Vec::new();
+    write_machine_matches(&mut buffer, &search_matches, &search).unwrap();
+
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        "1\t3\tarmor\tscale mail\t0\t\t1\t\n2\t19\tarmor\tscale mail\t0\t\t\tdar blademaster\n"
+    );
+}
+
+// Checks that a batch search finds the same matches as each query searched on its own.
+#[test]
+fn batch_search() {
+    let queries: &[&[&str]] = &[
+        &["brogue-scanner", "-a", "scale"],
+        &["brogue-scanner", "-a", "banded", "+2", "-m", "3"],
+    ];
+    let new_searches = || -> Vec<SearchParameters> {
+        queries
+            .iter()
+            .map(|args| {
+                let matches = new_app().get_matches_from(*args);
+                let mut search = SearchParameters::from_matches(matches).unwrap();
+                search.set_file(FILE);
+                search
+            })
+            .collect()
+    };
+    let mut searches = new_searches();
+    let mut singles = new_searches();
+
+    let batch_matches = search_files_batch(&mut searches).unwrap();
+
+    for (search, batch) in singles.iter_mut().zip(batch_matches.iter()) {
+        let single = search_files(search).unwrap();
+        let seeds = |m: &[SearchMatch]| m.iter().map(|m| (m.seed, m.depth)).collect::<Vec<_>>();
+        assert_eq!(seeds(&single), seeds(batch));
+    }
+    assert_eq!(batch_matches[0].len(), 7);
+}
+
+// Checks that raw column filters (partial header names) restrict matches.
+#[test]
+fn where_filter() {
+    let args = &["brogue-scanner", "-a", "scale", "--where", "col:vault=1"];
+    let matches = new_app().get_matches_from(args);
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.set_file(FILE);
+
+    let search_matches = search_files(&mut search).unwrap();
+
+    assert_eq!(search_matches.len(), 3);
+    assert!(search_matches.iter().all(|m| m.vault == Some(1)));
+}
+
+// Checks that object logic combines object parameters into a boolean tree.
+#[test]
+fn object_logic() {
+    let seeds_matching = |logic: &str| -> Result<Vec<u32>> {
+        let args = vec![
+            "brogue-scanner",
+            "-a",
+            "banded",
+            "-p",
+            "life",
+            "-w",
+            "war axe",
+            "-d",
+            "26",
+            "-m",
+            "all",
+            "--logic",
+            logic,
+        ];
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args))?;
+        search.set_file(FILE);
+
+        let mut seeds: Vec<u32> = search_files(&mut search)?.iter().map(|m| m.seed).collect();
+        seeds.dedup();
+        Ok(seeds)
+    };
+
+    assert_eq!(seeds_matching("1 && 2 && 3").unwrap(), vec![3]);
+    assert_eq!(seeds_matching("1 && 2 && !3").unwrap(), vec![1, 2, 4, 5]);
+    assert_eq!(seeds_matching("3 || (1 && !2)").unwrap(), vec![3]);
+    assert!(seeds_matching("(1 || 3) && !2").unwrap().is_empty());
+
+    assert!(seeds_matching("1 || 2").is_err());
+    assert!(seeds_matching("1 || (2 && 4)").is_err());
+    assert!(seeds_matching("(1 || 2 && 3").is_err());
+}
+
+// Checks that the most selective object parameters are checked first, except before
+// parameters that can match the same categories.
+#[test]
+fn param_evaluation_order() {
+    let args = &["brogue-scanner", "-a", "banded", "-w", "+2", "sword", "-i", "good"];
+    let search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    let categories: Vec<String> =
+        search.object_params.iter().map(|p| p.category.to_string()).collect();
+
+    assert_eq!(categories, vec!["armor", "weapon", "item"]);
+    assert_eq!(search.param_order, vec![1, 0, 2]);
+}
+
+// Checks that each object parameter counts the scanned seeds it was met in on its own.
+#[test]
+fn param_seeds_met() {
+    let args = &[
+        "brogue-scanner",
+        "-a",
+        "banded",
+        "-w",
+        "war axe",
+        "-p",
+        "<7",
+        "life",
+        "-d",
+        "26",
+        "-m",
+        "all",
+        "--param-stats",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+
+    search_files(&mut search).unwrap();
+    let seeds_met: Vec<u64> = search.object_params.iter().map(|p| p.seeds_met).collect();
+
+    assert_eq!(search.seeds_scanned, 5);
+    assert_eq!(seeds_met, vec![5, 3, 1]);
+}
+
+// Checks that `--relax` loosens counts, then enchantments, then depths, one step at a time.
+#[test]
+fn relax_order() {
+    let args = &["brogue-scanner", "-S", "3", "enchanting", "-w", "+2", "-d", "24"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    let mut steps = Vec::new();
+
+    while let Some((constraint, value)) = search.relax() {
+        steps.push(format!("{}: {}", constraint, value));
+    }
+
+    assert_eq!(
+        steps,
+        vec![
+            "object 1 count: 2 or more",
+            "object 1 count: 1 or more",
+            "object 2 ench: 1",
+            "object 2 ench: any",
+            "depth: 1 to 25",
+            "depth: 1 to 26",
+        ]
+    );
+}
+
+// Checks that optional objects don't gate matching, can't be used by `--logic`, and add to
+// the score of seeds that have them.
+#[test]
+fn optional_params() {
+    let args = &[
+        "brogue-scanner",
+        "-a",
+        "banded",
+        "-w",
+        "?war axe",
+        "-d",
+        "26",
+        "-m",
+        "all",
+        "--grade",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    assert!(!search.object_params[0].optional);
+    assert!(search.object_params[1].optional);
+    assert_eq!(search.object_match_target, 1);
+
+    let required = &["brogue-scanner", "-a", "banded", "-d", "26", "-m", "all"];
+    let mut required =
+        SearchParameters::from_matches(new_app().get_matches_from(required)).unwrap();
+    required.set_file(FILE);
+    let seeds = |matches: Vec<SearchMatch>| matches.iter().map(|m| m.seed).collect::<std::collections::HashSet<_>>();
+    let search_matches = search_files(&mut search).unwrap();
+    assert_eq!(
+        seeds(search_matches.clone()),
+        seeds(search_files(&mut required).unwrap())
+    );
+
+    for seed in seeds(search_matches.clone()) {
+        let has_axe = search_matches
+            .iter()
+            .any(|m| m.seed == seed && m.object.kind_name() == "war axe");
+        assert_eq!(
+            search.seed_optionals.get(&seed).copied().unwrap_or(0),
+            has_axe as u32
+        );
+    }
+
+    let logic = &[
+        "brogue-scanner",
+        "-a",
+        "banded",
+        "-w",
+        "opt:war axe",
+        "--logic",
+        "1 || 2",
+    ];
+    assert!(SearchParameters::from_matches(new_app().get_matches_from(logic)).is_err());
+}
+
+// Checks that row predicates match objects of any category, and reject bad expressions.
+#[test]
+fn filter_predicate() {
+    let args = &[
+        "brogue-scanner",
+        "--filter",
+        "category=='weapon' && ench>=2 && depth<5",
+    ];
+    let matches = new_app().get_matches_from(args);
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.set_file(FILE);
+
+    let search_matches = search_files(&mut search).unwrap();
+
+    assert_eq!(search_matches.len(), 1);
+    assert_eq!((search_matches[0].seed, search_matches[0].depth), (4, 4));
+
+    for bad in &["category=='weapon' &&", "(ench>2", "ench>=2 ench"] {
+        let matches = new_app().get_matches_from(vec!["brogue-scanner", "--filter", bad]);
+        assert!(SearchParameters::from_matches(matches).is_err());
+    }
+}
+
+// Checks that depth-bucketed stats cover every seed and add up to the total.
+#[test]
+fn stats_buckets() {
+    let args = &["brogue-scanner", "stats", "potion", "life", "--bucket", "5"];
+    let matches = new_app().get_matches_from(args);
+    let params =
+        stats::StatsParameters::from_matches(matches.subcommand_matches("stats").unwrap()).unwrap();
+    let mut search = SearchParameters::from_matches(matches.clone()).unwrap();
+    search.set_file(FILE);
+
+    let stats = stats::collect_stats(&search, &params).unwrap();
+    let bucket_total: u64 = stats.buckets.iter().map(|b| b.objects).sum();
+
+    assert_eq!(stats.seeds, 5);
+    assert_eq!(stats.objects, 32);
+    assert_eq!(bucket_total, stats.objects);
+    assert_eq!(stats.buckets.len(), 6);
+}
+
+// Checks that a seed's catalog holds every Record of the seed in its depth range.
+#[test]
+fn seed_detail_catalog() {
+    let args = &["brogue-scanner", "--maxdepth", "10", "show", "3"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+
+    let objects = seed_detail::seed_catalog(&search, 3).unwrap();
+    let expected = std::fs::read_to_string(FILE)
+        .unwrap()
+        .lines()
+        .map(|line| line.split(',').collect::<Vec<_>>())
+        .filter(|fields| fields[1] == "3" && fields[2].parse::<u8>().unwrap() <= 10)
+        .count();
+
+    assert!(expected > 0);
+    assert_eq!(objects.len(), expected);
+    assert!(objects.iter().all(|o| o.seed == 3 && o.depth <= 10));
+    assert!(seed_detail::seed_catalog(&search, 99).unwrap().is_empty());
+}
+
+// Checks that the summary line of a depth counts its matches by category, in the plural
+// of each category.
+#[test]
+fn depth_summaries() {
+    let args = &["brogue-scanner", "--maxdepth", "1", "show", "1"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let objects = seed_detail::seed_catalog(&search, 1).unwrap();
+
+    assert_eq!(
+        search::depth_summary(&objects),
+        "Depth 1: 6 matches (3 potions, 2 scrolls, 1 gold)"
+    );
+    assert_eq!(
+        search::depth_summary(&objects[..1]),
+        "Depth 1: 1 match (1 potion)"
+    );
+
+    let args = &["brogue-scanner", "--maxdepth", "5", "show", "4"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let objects = seed_detail::seed_catalog(&search, 4).unwrap();
+    let staves: Vec<_> = objects
+        .into_iter()
+        .filter(|o| o.depth == 5 && o.object.category() == objects::Category::Staff)
+        .collect();
+    assert_eq!(
+        search::depth_summary(&staves),
+        "Depth 5: 4 matches (4 staves)"
+    );
+    assert_eq!(objects::Category::Ally.plural(), "allies");
+}
+
+// Checks rarity estimates built from the test catalog.
+#[test]
+fn rarity_table() {
+    let mut search = SearchParameters::default();
+    search.set_file(FILE);
+
+    let table = rarity::RarityTable::build(&search).unwrap();
+    let life = table.estimate(objects::Category::Potion, Some("life"), 26).unwrap();
+
+    assert_eq!(table.seeds, 5);
+    assert_eq!(life.chance, 1.0);
+    assert!((life.mean - 6.4).abs() < 1e-9);
+    assert_eq!(life.count_chance(search::CountType::AtLeast, 1), 1.0);
+    assert_eq!(life.count_chance(search::CountType::LessThan, 1), 0.0);
+    assert!(life.count_chance(search::CountType::AtLeast, 20) < 0.01);
+}
+
+// Checks that an approximate count searches every seed of the sample.
+#[test]
+fn approx_count() {
+    let args = &["brogue-scanner", "-a", "scale", "-m", "1", "--approx", "100"];
+    let matches = new_app().get_matches_from(args);
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.set_file(FILE);
+
+    let approxes = approx::approximate(std::slice::from_mut(&mut search), 100.0).unwrap();
+
+    assert_eq!(approxes[0].sampled_seeds, 5);
+    assert_eq!(approxes[0].matching_seeds, 4);
+    assert!(approxes[0].rate_low < 0.8 && approxes[0].rate_high > 0.8);
+}
+
+// Checks that the empower build links the captive ally and wand charges within a depth.
+#[test]
+fn empower_build() {
+    let search_seeds = |args: &[&str]| -> Vec<u32> {
+        let matches = new_app().get_matches_from(args);
+        let mut search = SearchParameters::from_matches(matches).unwrap();
+        search.set_file(FILE);
+
+        let mut seeds: Vec<u32> =
+            search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+        seeds.dedup();
+        seeds
+    };
+
+    assert_eq!(
+        search_seeds(&["brogue-scanner", "--empower", "2", "d14"]),
+        vec![2]
+    );
+    assert_eq!(
+        search_seeds(&["brogue-scanner", "--empower", "d12"]),
+        vec![4]
+    );
+    assert!(search_seeds(&["brogue-scanner", "--empower", "3"]).is_empty());
+}
+
+// Checks that a same-depth group needs its objects on a single depth.
+#[test]
+fn same_depth_group() {
+    let args = &[
+        "brogue-scanner",
+        "-p",
+        "life",
+        "-a",
+        "scale",
+        "-m",
+        "10",
+        "--same-depth",
+        "1,2",
+    ];
+    let matches = new_app().get_matches_from(args);
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.set_file(FILE);
+
+    let mut seeds: Vec<u32> = search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+    seeds.dedup();
+
+    assert_eq!(seeds, vec![2, 3]);
+    assert!(
+        SearchParameters::from_matches(new_app().get_matches_from([
+            "brogue-scanner",
+            "-p",
+            "life",
+            "--same-depth",
+            "1,2"
+        ]))
+        .is_err()
+    );
+}
+
+// Checks that a proximity constraint widens with the number of levels allowed.
+#[test]
+fn within_levels() {
+    let search_seeds = |levels: &str| -> Vec<u32> {
+        let within = format!("1,2,{}", levels);
+        let args = &[
+            "brogue-scanner",
+            "-p",
+            "life",
+            "-a",
+            "scale",
+            "-m",
+            "10",
+            "--within",
+            &within,
+        ];
+        let matches = new_app().get_matches_from(args);
+        let mut search = SearchParameters::from_matches(matches).unwrap();
+        search.set_file(FILE);
+
+        let mut seeds: Vec<u32> =
+            search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+        seeds.dedup();
+        seeds
+    };
+
+    assert_eq!(search_seeds("0"), vec![2, 3]);
+    assert_eq!(search_seeds("1"), vec![2, 3, 4]);
+    assert_eq!(search_seeds("2"), vec![1, 2, 3, 4]);
+}
+
+// Checks that seeds with a malevolent object within the depth are rejected.
+#[test]
+fn no_malevolent() {
+    let args = &["brogue-scanner", "-p", "life", "-m", "10", "--no-malevolent", "1"];
+    let matches = new_app().get_matches_from(args);
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.set_file(FILE);
+
+    let mut seeds: Vec<u32> = search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+    seeds.dedup();
+
+    assert_eq!(seeds, vec![2, 4]);
+}
+
+// Checks that seeds with an excluded kind in the depth range are rejected.
+#[test]
+fn exclude_category() {
+    let args = &[
+        "brogue-scanner",
+        "-S",
+        "enchanting",
+        "-d",
+        "3",
+        "-m",
+        "10",
+        "--exclude-category",
+        "scroll:aggravate,potion:darkness",
+    ];
+    let matches = new_app().get_matches_from(args);
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.set_file(FILE);
+
+    let mut seeds: Vec<u32> = search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+    seeds.dedup();
+
+    assert_eq!(seeds, vec![1, 5]);
+}
+
+// Checks that vault objects need a key opening their vault on the same depth.
+#[test]
+fn require_key() {
+    let args = &["brogue-scanner", "-i", "vault", "-m", "10", "--require-key"];
+    let matches = new_app().get_matches_from(args);
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.set_file(FILE);
+
+    let search_matches = search_files(&mut search).unwrap();
+
+    assert_eq!(search_matches.len(), 68);
+}
+
+// Checks that files from two catalog directories are scanned in parallel and merged.
+#[test]
+fn parallel_dirs() {
+    let args = &["brogue-scanner", "-a", "scale", "-m", "6"];
+    let matches = new_app().get_matches_from(args);
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.file_paths = vec![FILE.into(), "src/test_data.csv".into()];
+    search.file_dirs = vec!["./src".into(), "src".into()];
+
+    assert_eq!(search.file_groups(&search.file_paths).len(), 2);
+
+    let search_matches = search_files(&mut search).unwrap();
+    let mut seeds: Vec<u32> = search_matches.iter().map(|m| m.seed).collect();
+    seeds.dedup();
+
+    assert_eq!(seeds.len(), 6);
+    assert_eq!(search.search_matches, 6);
+    assert_eq!(search.seeds_scanned, 10);
+}
+
+// Checks that ordered parallel results are merged in ascending seed order.
+#[test]
+fn ordered_merge() {
+    let args = &["brogue-scanner", "-a", "scale", "-m", "3", "--ordered"];
+    let matches = new_app().get_matches_from(args);
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.file_paths = vec![FILE.into(), "src/test_data.csv".into()];
+    search.file_dirs = vec!["./src".into(), "src".into()];
+
+    let seeds: Vec<u32> = search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+
+    assert!(seeds.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert_eq!(seeds.last(), Some(&3));
+}
+
+// Checks that daemon requests split on whitespace, with quotes grouping words.
+#[test]
+#[cfg(feature = "server")]
+fn daemon_request_args() {
+    let args =
+        daemon::split_line(r#"-a scale  --where "col:carried~goblin conjurer" -m 2"#).unwrap();
+
+    assert_eq!(
+        args,
+        vec!["-a", "scale", "--where", "col:carried~goblin conjurer", "-m", "2"]
+    );
+    assert!(daemon::split_line("--where 'col:opens=3").is_err());
+
+    let base = daemon::daemon_base_args(
+        ["brogue-scanner", "-F", "catalogs", "daemon"]
+            .iter()
+            .map(|arg| arg.to_string()),
+    );
+    assert_eq!(base, vec!["brogue-scanner", "-F", "catalogs"]);
+}
+
+// Checks the IPC protocol:  a query streams its matches and a `done` frame, and an
+// unreadable request gets an error frame.
+#[test]
+#[cfg(all(unix, feature = "server"))]
+fn ipc_query() {
+    let mut input = Vec::new();
+    ipc::write_frame(
+        &mut input,
+        &serde_json::json!({"type": "query", "id": 7, "args": ["-a", "scale"]}),
+    )
+    .unwrap();
+    ipc::write_frame(&mut input, &serde_json::json!({"type": "bogus"})).unwrap();
+
+    let base_args: Vec<String> = ["brogue-scanner", "--filepath", "./src"]
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+    let mut engine = engine::Engine::new();
+    let mut output = Vec::new();
+    ipc::serve(
+        &mut engine,
+        &base_args,
+        std::io::Cursor::new(input),
+        &mut output,
+    )
+    .unwrap();
+
+    let mut reader = std::io::Cursor::new(output);
+    let mut types = Vec::new();
+
+    while let Some(frame) = ipc::read_frame(&mut reader).unwrap() {
+        let value: serde_json::Value = serde_json::from_slice(&frame).unwrap();
+        types.push(value["type"].as_str().unwrap().to_string());
+
+        if value["type"] == "done" {
+            assert_eq!(value["id"], 7);
+            assert_eq!(value["matches"], 7);
+        }
+    }
+
+    assert_eq!(types.iter().filter(|t| *t == "match").count(), 7);
+    assert!(types.contains(&"error".to_string()));
+    assert!(types.contains(&"done".to_string()));
+}
+
+// Checks that the IPC socket only replaces a stale socket (not other files), and is only
+// open to its owner.
+#[test]
+#[cfg(all(unix, feature = "server"))]
+fn ipc_socket() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempDir::new("socket");
+    let path = dir.join("scanner.sock");
+
+    std::fs::write(&path, "not a socket").unwrap();
+    assert!(
+        ipc::bind_socket(&path)
+            .unwrap_err()
+            .to_string()
+            .contains("isn't a socket")
+    );
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "not a socket");
+    std::fs::remove_file(&path).unwrap();
+
+    drop(ipc::bind_socket(&path).unwrap());
+    let listener = ipc::bind_socket(&path).unwrap();
+    assert_eq!(
+        std::fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+        0o600
+    );
+    drop(listener);
+}
+
+// Checks that the engine streams each seed's matches (from disk or memory, from parallel
+// workers, and for `--best` searches) just as the search returns them.
+#[test]
+#[cfg(feature = "server")]
+fn streamed_seeds() {
+    use search::CancelToken;
+
+    for resident in [false, true] {
+        for params in [
+            &["-a", "scale", "-m", "all"][..],
+            &["-F", "src", "-a", "scale", "-m", "2"],
+            &[
+                "-S",
+                "3",
+                "enchanting",
+                "-p",
+                "2",
+                "life",
+                "--best",
+                "depth",
+                "-m",
+                "2",
+            ],
+        ] {
+            let mut args = vec!["brogue-scanner", "-U", "-F", "./src"];
+            args.extend_from_slice(params);
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            let mut engine = match resident {
+                true => engine::Engine::resident(),
+                false => engine::Engine::new(),
+            };
+
+            let (sender, receiver) = std::sync::mpsc::channel();
+            let results = engine
+                .search(
+                    new_app().get_matches_from(&args),
+                    &args,
+                    &CancelToken::new(),
+                    Some(&sender),
+                )
+                .unwrap();
+            let returned: Vec<Vec<u32>> = results[0]
+                .1
+                .chunk_by(|a, b| a.seed == b.seed)
+                .map(|matches| matches.iter().map(|m| m.seed).collect())
+                .collect();
+            let streamed: Vec<Vec<u32>> = receiver
+                .try_iter()
+                .map(|seed| seed.matches.iter().map(|m| m.seed).collect())
+                .collect();
+
+            assert!(!returned.is_empty(), "{:?}", params);
+            assert_eq!(streamed, returned, "{:?}", params);
+        }
+    }
+}
+
+// Checks that daemon metrics count requests, scans, and cache lookups.
+#[test]
+#[cfg(feature = "server")]
+fn daemon_metrics() {
+    use metrics::{Cache, Metrics};
+    use search::CancelToken;
+
+    let metrics = std::sync::Arc::new(Metrics::default());
+    let mut engine = engine::Engine::resident();
+    engine.set_metrics(metrics.clone());
+
+    let base_args = vec![
+        "brogue-scanner".to_string(),
+        "-U".to_string(),
+        "-F".to_string(),
+        "./src".to_string(),
+    ];
+    for _ in 0..2 {
+        let (matches, args) =
+            daemon::run_args(&base_args, &["-a".to_string(), "scale".to_string()]).unwrap();
+        engine.search(matches, &args, &CancelToken::new(), None).unwrap();
+    }
+    engine.record(request_log::LogEntry::request(
+        1,
+        &[],
+        std::time::Instant::now(),
+        "ok",
+        None,
+    ));
+    metrics.add_cache_lookup(Cache::Rarity, false);
+
+    let rows = std::fs::read_to_string(FILE).unwrap().lines().count() - 1;
+    let text = metrics.render();
+    assert!(text.contains("brogue_requests_total{status=\"ok\"} 1\n"));
+    assert!(text.contains(&format!("brogue_rows_scanned_total {}\n", 2 * rows)));
+    assert!(text.contains("brogue_scan_duration_seconds_count 2\n"));
+    assert!(text.contains("brogue_cache_hits_total{cache=\"catalog\"} 1\n"));
+    assert!(text.contains("brogue_cache_misses_total{cache=\"catalog\"} 1\n"));
+    assert!(text.contains("brogue_cache_misses_total{cache=\"rarity\"} 1\n"));
+}
+
+// Checks that the request log rotates by size, keeping only the newest rotated logs.
+#[test]
+#[cfg(feature = "server")]
+fn request_log_rotation() {
+    use request_log::{LogEntry, RequestLog};
+
+    let dir = TempDir::new("log");
+    let path = dir.join("daemon.log");
+    let numbered = |n: u32| dir.join(format!("daemon.log.{}", n));
+
+    let mut log = RequestLog::open(&path, 200, 2).unwrap();
+    let started = std::time::Instant::now();
+    for id in 1..=8 {
+        let args = vec!["-a".to_string(), "scale".to_string()];
+        log.write(&LogEntry::request(id, &args, started, "ok", None)).unwrap();
+    }
+
+    let lines = |path: &std::path::Path| std::fs::read_to_string(path).unwrap().lines().count();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    let entry: serde_json::Value = serde_json::from_str(contents.lines().last().unwrap()).unwrap();
+    assert_eq!(
+        (entry["event"].as_str(), entry["id"].as_u64()),
+        (Some("request"), Some(8))
+    );
+    assert!(numbered(1).exists() && numbered(2).exists() && !numbered(3).exists());
+    assert!(std::fs::metadata(&path).unwrap().len() <= 200);
+    assert!(lines(&path) + lines(&numbered(1)) + lines(&numbered(2)) < 8);
+}
+
+// Checks that the catalog index is only rescanned for changed files, and that searches
+// skip indexed files outside their seed range (unless the file changed since).
+#[test]
+fn catalog_index() {
+    let dir = TempDir::new("index");
+    std::fs::copy(FILE, dir.join("test_data.csv")).unwrap();
+
+    let args = &[
+        "brogue-scanner",
+        "--filepath",
+        dir.to_str().unwrap(),
+        "--minseed",
+        "6",
+        "-a",
+        "scale",
+    ];
+    let matches = new_app().get_matches_from(args);
+    let search = SearchParameters::from_matches(matches).unwrap();
+
+    let updates = index::update_indexes(&search).unwrap();
+    assert_eq!(updates[0].1.added, 1);
+    let updates = index::update_indexes(&search).unwrap();
+    assert_eq!(updates[0].1.unchanged, 1);
+
+    let catalog_index = index::CatalogIndex::load(dir.join(index::INDEX_FILE)).unwrap();
+    let entry = catalog_index.entry("test_data.csv").unwrap();
+    assert_eq!((entry.seed_min, entry.seed_max, entry.seeds), (1, 5, 5));
+
+    let path = &search.file_paths[0];
+    let indexes = index::CatalogIndexes::load(&search.file_dirs);
+    assert_eq!(
+        indexes.skipped_seeds(path, std::slice::from_ref(&search)),
+        Some(vec![0])
+    );
+
+    let mut file = std::fs::OpenOptions::new().append(true).open(path).unwrap();
+    std::io::Write::write_all(&mut file, b"CE 1.9,6,1,1,gold,gold pieces,,,,,,,\n").unwrap();
+    assert_eq!(
+        indexes.skipped_seeds(path, std::slice::from_ref(&search)),
+        None
+    );
+
+    let updates = index::update_indexes(&search).unwrap();
+    assert_eq!(updates[0].1.rescanned, 1);
+}
+
+// Checks that searches skip indexed files that can't hold a required kind or runic.
+#[test]
+fn kind_filter() {
+    let dir = TempDir::new("filter");
+    std::fs::copy(FILE, dir.join("test_data.csv")).unwrap();
+
+    let search_for = |terms: &[&str]| {
+        let mut args = vec!["brogue-scanner", "--filepath", dir.to_str().unwrap()];
+        args.extend_from_slice(terms);
+        SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap()
+    };
+    index::update_indexes(&search_for(&["-a", "scale"])).unwrap();
+
+    let indexes = index::CatalogIndexes::load(std::slice::from_ref(&*dir));
+    let skipped = |terms: &[&str]| {
+        let search = search_for(terms);
+        indexes.skipped_seeds(&search.file_paths[0], std::slice::from_ref(&search))
+    };
+
+    assert_eq!(skipped(&["-w", "quietus"]), Some(vec![5]));
+    assert_eq!(skipped(&["-a", "reflection"]), Some(vec![5]));
+    assert_eq!(skipped(&["-a", "absorption"]), None);
+    assert_eq!(skipped(&["-p", "life"]), None);
+    assert_eq!(skipped(&["-w", "quietus", "<1"]), None);
+
+    let mut search = search_for(&["-w", "quietus"]);
+    search_files(&mut search).unwrap();
+    assert_eq!(search.seeds_scanned, 5);
+}
+
+// Checks that searches of in-memory catalogs (resident engine) match searches of files.
+#[test]
+#[cfg(feature = "server")]
+fn resident_catalog() {
+    use search::CancelToken;
+
+    let queries: &[&[&str]] = &[
+        &["-a", "scale"],
+        &["-i", "vault", "-m", "10", "--require-key"],
+        &[
+            "-S",
+            "enchanting",
+            "-d",
+            "3",
+            "--exclude-category",
+            "scroll:aggravate,potion:darkness",
+        ],
+        &["-w", "runic", "-m", "20"],
+        &["-A", "dar", "--where", "col:ally_status=caged"],
+    ];
+    let mut resident = engine::Engine::resident();
+
+    for query in queries {
+        let mut args = vec!["brogue-scanner".to_string(), "--filepath".into(), "./src".into()];
+        args.extend(query.iter().map(|arg| arg.to_string()));
+
+        let found = |engine: &mut engine::Engine| -> Vec<(u32, u8, String)> {
+            let matches = new_app().get_matches_from(args.iter());
+            let results = engine.search(matches, &args, &CancelToken::new(), None).unwrap();
+            results[0]
+                .1
+                .iter()
+                .map(|m| (m.seed, m.depth, m.to_string()))
+                .collect()
+        };
+
+        let expected = found(&mut engine::Engine::new());
+        assert!(!expected.is_empty());
+        assert_eq!(found(&mut resident), expected);
+        assert_eq!(found(&mut resident), expected);
+    }
+}
+
+// Checks that compacting overlapping catalogs writes each seed once, and that the
+// compressed result is searched like the original.
+#[test]
+fn compact_catalogs() {
+    let dir = TempDir::new("compact");
+    let output = dir.join("compact.csv.gz");
+
+    let args = &["brogue-scanner", "--filepath", "./src", "-a", "scale"];
+    let matches = new_app().get_matches_from(args);
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.file_paths = vec![FILE.into(), FILE.into()];
+
+    let stats = library::compact(&search, &output, true).unwrap();
+    assert_eq!((stats.seeds, stats.duplicates), (5, 5));
+
+    search.file_paths = vec![output];
+    assert_eq!(search_files(&mut search).unwrap().len(), 7);
+}
+
+// Checks that splitting writes one file per seed range, named by the range.
+#[test]
+fn split_catalogs() {
+    let dir = TempDir::new("split");
+
+    let args = &[
+        "brogue-scanner",
+        "--filepath",
+        "./src",
+        "--minseed",
+        "2",
+        "-a",
+        "scale",
+    ];
+    let matches = new_app().get_matches_from(args);
+    let search = SearchParameters::from_matches(matches).unwrap();
+
+    let (stats, files) = library::split(&search, &dir, 2, false).unwrap();
+    assert_eq!((stats.seeds, files), (4, 3));
+
+    let path = dir.join("seeds-3-4.csv");
+    assert_eq!(file_handling::seed_range_from_name(&path), Some((3, 4)));
+    assert_eq!(file_handling::seed_range_from_name("test_data.csv"), None);
+
+    let mut search = SearchParameters {
+        file_paths: vec![path],
+        ..search
+    };
+    let mut seeds: Vec<u32> = search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+    seeds.dedup();
+    assert_eq!(seeds, vec![3, 4]);
+}
+
+// Checks that converting a UTF-16 catalog (with a BOM) gives back the UTF-8 original.
+#[test]
+#[cfg(feature = "utf16")]
+fn convert_catalogs() {
+    let dir = TempDir::new("convert");
+    let (source, output) = (dir.join("utf16"), dir.join("utf8"));
+    std::fs::create_dir_all(&source).unwrap();
+
+    let text = std::fs::read_to_string(FILE).unwrap();
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(text.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+    std::fs::write(source.join("seeds.csv"), bytes).unwrap();
+
+    let args = &[
+        "brogue-scanner",
+        "--filepath",
+        source.to_str().unwrap(),
+        "-a",
+        "scale",
+    ];
+    let matches = new_app().get_matches_from(args);
+    let search = SearchParameters::from_matches(matches).unwrap();
+    assert_eq!(search.format, file_handling::FileFormat::Utf16);
+
+    assert_eq!(library::convert_utf8(&search, Some(&output)).unwrap(), 1);
+    assert_eq!(
+        std::fs::read_to_string(output.join("seeds.csv")).unwrap(),
+        text
+    );
+}
+
+// Checks that a manifest only goes stale when its files' contents change.
+#[test]
+fn manifest_staleness() {
+    let dir = TempDir::new("manifest");
+    let (path, other) = (dir.join("a.csv"), dir.join("b.csv"));
+    std::fs::copy(FILE, &path).unwrap();
+
+    let manifest = manifest::Manifest::build(std::slice::from_ref(&path)).unwrap();
+    assert!(manifest.stale_files(std::slice::from_ref(&path)).is_empty());
+
+    // A touched file with the same contents is still current
+    let file = std::fs::File::options().write(true).open(&path).unwrap();
+    file.set_modified(std::time::SystemTime::UNIX_EPOCH).unwrap();
+    assert!(manifest.stale_files(std::slice::from_ref(&path)).is_empty());
+
+    std::fs::copy(FILE, &other).unwrap();
+    assert_eq!(manifest.stale_files(&[path.clone(), other]), vec!["b.csv"]);
+
+    let mut text = std::fs::read(&path).unwrap();
+    text[0] = b'D';
+    std::fs::write(&path, text).unwrap();
+    assert_eq!(manifest.stale_files(&[path]), vec!["a.csv"]);
+    assert_eq!(manifest.stale_files(&[]), vec!["a.csv"]);
+}
+
+// Checks that '--matches all' (or 0) keeps every matching seed, past the old limit of 255.
+#[test]
+fn unlimited_matches() {
+    for limit in ["all", "0", "1000"] {
+        let args = &["brogue-scanner", "-a", "scale", "-m", limit];
+        let matches = new_app().get_matches_from(args);
+        let mut search = SearchParameters::from_matches(matches).unwrap();
+        search.file_paths = vec![FILE.into(), FILE.into()];
+
+        search_files(&mut search).unwrap();
+
+        assert_eq!(search.search_matches, 8);
+        assert!(!search.is_complete());
+    }
+
+    let matches = new_app().get_matches_from(["brogue-scanner", "-a", "scale", "-m", "any"]);
+    assert!(SearchParameters::from_matches(matches).is_err());
+}
+
+// Checks that single seeds are searched alone, and can't be combined with a seed range.
+#[test]
+fn single_seed() {
+    let args = &["brogue-scanner", "--seed", "4", "-a", "scale", "-m", "all"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+
+    assert_eq!(
+        (search.seed_min, search.seed_max, search.seeds_scanned),
+        (4, 4, 1)
+    );
+    assert!(search_matches.iter().all(|m| m.seed == 4));
+    assert!(
+        new_app()
+            .get_matches_from_safe(["brogue-scanner", "--seed", "4", "--minseed", "2"])
+            .is_err()
+    );
+    let zero = new_app().get_matches_from(["brogue-scanner", "--seed", "0"]);
+    assert!(SearchParameters::from_matches(zero).is_err());
+
+    let args = &[
+        "brogue-scanner",
+        "--seed",
+        "5",
+        "--seed",
+        "2",
+        "--seed",
+        "5",
+        "-a",
+        "scale",
+        "-m",
+        "all",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    search_files(&mut search).unwrap();
+
+    assert_eq!(search.seed_list, Some(vec![2, 5]));
+    assert_eq!(
+        (search.seed_min, search.seed_max, search.seeds_scanned),
+        (2, 5, 2)
+    );
+}
+
+// Checks color modes, and that `--color` reaches the search parameters.
+#[test]
+fn color_modes() {
+    use color::ColorMode;
+
+    assert_eq!(
+        ColorMode::parse("always").unwrap().is_enabled(),
+        cfg!(feature = "color")
+    );
+    assert!(!ColorMode::parse("never").unwrap().is_enabled());
+    assert!(ColorMode::parse("sometimes").is_err());
+
+    let matches = new_app().get_matches_from(["brogue-scanner", "--color", "never"]);
+    assert_eq!(
+        SearchParameters::from_matches(matches).unwrap().color,
+        ColorMode::Never
+    );
+    assert!(
+        new_app()
+            .get_matches_from_safe(["brogue-scanner", "--color", "sometimes"])
+            .is_err()
+    );
+}
+
+// Checks that summary numbers are grouped by thousands, unless plain numbers are asked for.
+#[test]
+fn summary_numbers() {
+    let mut search = SearchParameters::default();
+    assert_eq!(search.number(0), "0");
+    assert_eq!(search.number(999), "999");
+    assert_eq!(search.number(1000), "1,000");
+    assert_eq!(search.number(4_294_967_295), "4,294,967,295");
+    assert!(search.to_string().contains("seed: 1 to 4,294,967,295"));
+
+    search.plain_numbers = true;
+    assert_eq!(search.number(4_294_967_295), "4294967295");
+}
+
+// Checks that a seed step searches every K-th seed from the minimum seed.
+#[test]
+fn seed_step() {
+    let args = &[
+        "brogue-scanner",
+        "--minseed",
+        "2",
+        "--seed-step",
+        "2",
+        "-a",
+        "scale",
+        "-m",
+        "all",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+
+    assert_eq!(search.seeds_scanned, 2);
+    assert!(search_matches.iter().all(|m| m.seed == 2 || m.seed == 4));
+    assert_eq!(search.stepped_seeds(2, 5), 2);
+    assert_eq!(search.stepped_seeds(3, 5), 1);
+    assert_eq!(search.stepped_seeds(3, 3), 0);
+}
+
+// Checks that a seed list (plain, or a search's --ndjson output) restricts the seeds searched.
+#[test]
+fn seed_list() {
+    let dir = TempDir::new("seedlist");
+    let (plain, ndjson) = (dir.join("seeds.txt"), dir.join("seeds.ndjson"));
+    std::fs::write(&plain, "# seeds\n5, 2\n4 4\n").unwrap();
+
+    let search_seeds = |args: &[&str]| {
+        let matches = new_app().get_matches_from(args);
+        let mut search = SearchParameters::from_matches(matches).unwrap();
+        search.set_file(FILE);
+        let search_matches = search_files(&mut search).unwrap();
+        let mut seeds: Vec<u32> = search_matches.iter().map(|m| m.seed).collect();
+        seeds.dedup();
+        (search, search_matches, seeds)
+    };
+
+    let (search, search_matches, seeds) = search_seeds(&[
+        "brogue-scanner",
+        "-a",
+        "scale",
+        "-m",
+        "all",
+        "--seedlist",
+        plain.to_str().unwrap(),
+    ]);
+    assert_eq!(seeds, vec![2, 4]);
+    assert_eq!(
+        (search.seed_min, search.seed_max, search.seeds_scanned),
+        (2, 5, 3)
+    );
+
+    // The next stage only searches seeds the first one matched
+    let mut buffer = Vec::new();
+    let ndjson_search = SearchParameters {
+        output_mode: search::OutputMode::Ndjson,
+        ..search
+    };
+    write_machine_matches(&mut buffer, &search_matches, &ndjson_search).unwrap();
+    std::fs::write(&ndjson, buffer).unwrap();
+
+    let (search, _, seeds) = search_seeds(&[
+        "brogue-scanner",
+        "-p",
+        "life",
+        "-m",
+        "all",
+        "--seedlist",
+        ndjson.to_str().unwrap(),
+    ]);
+    assert_eq!(seeds, vec![2, 4]);
+    assert_eq!(search.seeds_scanned, 2);
+
+    // Written seed lists use the plain format, with a section per named query
+    let search_matches = search_files(&mut search.clone()).unwrap();
+    let search = SearchParameters {
+        name: Some("life".into()),
+        ..search
+    };
+    assert_eq!(
+        search::write_seed_list(
+            &plain,
+            search.out_encoding,
+            std::iter::once((&search, &search_matches))
+        )
+        .unwrap(),
+        2
+    );
+    assert_eq!(
+        std::fs::read_to_string(&plain).unwrap(),
+        "# query: life\n2\n4\n"
+    );
+}
+
+// Checks that matches are exported as CSV, or as TSV for `*.tsv` files.
+#[test]
+fn export_matches() {
+    let dir = TempDir::new("export");
+    let (csv, tsv) = (dir.join("matches.csv"), dir.join("matches.tsv"));
+
+    let matches = new_app().get_matches_from([
+        "brogue-scanner",
+        "-a",
+        "scale",
+        "-m",
+        "all",
+        "--export",
+        csv.to_str().unwrap(),
+    ]);
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+    assert_eq!(search.export.as_ref(), Some(&csv));
+
+    for path in [&csv, &tsv] {
+        assert_eq!(
+            search::write_export(path, search.out_encoding, std::iter::once(&search_matches))
+                .unwrap(),
+            search_matches.len()
+        );
+    }
+    let text = std::fs::read_to_string(&csv).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(
+        lines[0],
+        "seed,depth,category,kind,enchantment,runic,vault,carried_by"
+    );
+    assert!(lines.contains(&"2,19,armor,scale mail,0,,,dar blademaster"));
+    assert!(lines.contains(&"3,11,armor,scale mail,-2,vulnerability,,"));
+    assert_eq!(lines.len(), search_matches.len() + 1);
+    assert_eq!(
+        std::fs::read_to_string(&tsv).unwrap(),
+        text.replace(',', "\t")
+    );
+
+    // An empty export still has its header
+    assert_eq!(
+        search::write_export(&csv, search.out_encoding, std::iter::once(&Vec::new())).unwrap(),
+        0
+    );
+    assert_eq!(
+        std::fs::read_to_string(&csv).unwrap(),
+        format!("{}\n", lines[0])
+    );
+}
+
+// Checks seed list set operations.
+#[test]
+fn seed_set_ops() {
+    use search::SeedSetOp;
+
+    let lists = vec![vec![1, 2, 3, 5], vec![2, 3, 4], vec![3, 5]];
+
+    assert_eq!(SeedSetOp::Intersect.apply(&lists), vec![3]);
+    assert_eq!(SeedSetOp::Union.apply(&lists), vec![1, 2, 3, 4, 5]);
+    assert_eq!(SeedSetOp::Diff.apply(&lists), vec![1]);
+    assert_eq!(SeedSetOp::Diff.apply(&lists[..1]), vec![1, 2, 3, 5]);
+    assert_eq!(SeedSetOp::parse("xor"), None);
+}
+
+// Checks that compacted catalogs and seed lists can be written with a BOM, or in UTF-16.
+#[test]
+#[cfg(feature = "utf16")]
+fn output_encodings() {
+    let dir = TempDir::new("encoding");
+
+    for (encoding, bom) in [("utf8-bom", &[0xEF, 0xBB, 0xBF][..]), ("utf16", &[0xFF, 0xFE][..])] {
+        let output = dir.join(format!("{}.csv", encoding));
+        let seeds = dir.join(format!("{}.txt", encoding));
+
+        let args = &[
+            "brogue-scanner",
+            "--out-encoding",
+            encoding,
+            "-a",
+            "scale",
+            "-m",
+            "all",
+        ];
+        let matches = new_app().get_matches_from(args);
+        let mut search = SearchParameters::from_matches(matches).unwrap();
+        search.set_file(FILE);
+
+        library::compact(&search, &output, false).unwrap();
+        assert!(std::fs::read(&output).unwrap().starts_with(bom));
+
+        let format = match encoding {
+            "utf16" => file_handling::FileFormat::Utf16,
+            _ => file_handling::FileFormat::Utf8,
+        };
+        let mut search = SearchParameters {
+            file_paths: vec![output],
+            format,
+            ..search
+        };
+        let search_matches = search_files(&mut search).unwrap();
+        assert_eq!(search_matches.len(), 7);
+
+        search::write_seed_list(
+            &seeds,
+            search.out_encoding,
+            std::iter::once((&search, &search_matches)),
+        )
+        .unwrap();
+        assert!(std::fs::read(&seeds).unwrap().starts_with(bom));
+        assert_eq!(search::load_seed_list(&seeds).unwrap(), vec![1, 2, 3, 4]);
+    }
+}
+
+// Checks that failed searches are categorized by what the user should fix.
+#[test]
+fn search_status() {
+    use status::{bad_query, no_files, Status};
+
+    assert_eq!(Status::of_error(&no_files()), Status::NoFiles);
+    assert_eq!(Status::of_error(&bad_query(no_files())), Status::NoFiles);
+    assert_eq!(
+        Status::of_error(&anyhow::anyhow!("disk full")),
+        Status::Error
+    );
+
+    let matches = new_app().get_matches_from(["brogue-scanner", "-a", "zzz"]);
+    let e = SearchParameters::from_matches(matches)
+        .map_err(bad_query)
+        .unwrap_err();
+    assert_eq!(Status::of_error(&e), Status::BadQuery);
+    assert_eq!(Status::of_error(&e).exit_code(), 2);
+
+    let matches =
+        new_app().get_matches_from(["brogue-scanner", "-F", "/nonexistent/path", "-a", "scale"]);
+    let e = SearchParameters::from_matches(matches)
+        .map_err(bad_query)
+        .unwrap_err();
+    assert_eq!(Status::of_error(&e), Status::NoFiles);
+    assert_eq!(Status::of_seeds(0), Status::NoMatches);
+}
+
+// Checks that the search summary describes the whole query, and the files searched.
+#[test]
+fn search_display() {
+    let dir = TempDir::new("display");
+    std::fs::copy(FILE, dir.join("seeds-1-3.csv")).unwrap();
+    std::fs::copy(FILE, dir.join("seeds-7-9.csv")).unwrap();
+
+    let filepath = dir.to_str().unwrap();
+    let args = [
+        "brogue-scanner",
+        "-U",
+        "-F",
+        filepath,
+        "--minseed",
+        "2",
+        "--maxseed",
+        "5",
+        "-a",
+        "novault",
+        "-p",
+        "good",
+    ];
+    let matches = new_app().get_matches_from(args);
+    let search = SearchParameters::from_matches(matches).unwrap();
+    let display = search.to_string();
+
+    assert!(display.contains("     files: 2 (1 outside seed range)\n"));
+    assert!(display.contains("  coverage: 2 of 4 seeds in range (50.0%)\n"));
+    assert!(display.contains("     vault: not in vault\n"));
+    assert!(display.contains("     magic: benevolent\n"));
+}
+
+// Checks that the parsed search is explained as JSON, with kind terms resolved.
+#[test]
+fn explain_json() {
+    let args = [
+        "brogue-scanner",
+        "--explain-json",
+        "-d",
+        "10",
+        "-w",
+        "axe",
+        "-e",
+        "vault",
+    ];
+    let matches = new_app().get_matches_from(args);
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.set_file(FILE);
+
+    let mut output = Vec::new();
+    search::write_explain(&mut output, &search).unwrap();
+    let explain: serde_json::Value = serde_json::from_slice(&output).unwrap();
+
+    assert_eq!(explain["type"], "explain");
+    let weapon = &explain["objects"][0];
+    assert_eq!(
+        weapon["kind"]["matches"],
+        serde_json::json!(["war axe", "axe"])
+    );
+    assert_eq!(weapon["depth"], 10);
+    assert_eq!(weapon["count_type"], "at_least");
+    let equipment = &explain["objects"][1];
+    assert_eq!(
+        equipment["categories"],
+        serde_json::json!(["armor", "ring", "weapon"])
+    );
+    assert_eq!(equipment["in_vault"], true);
+}
+
+// Checks that allies are scored by legendary status, mutation, and kind tier.
+#[test]
+fn ally_quality() {
+    use objects::{Ally, AllyStatus, MonsterKind, Mutation};
+    use search::AllyWeights;
+
+    let weights = AllyWeights::default();
+    let monkey = Ally::new(MonsterKind::Monkey, AllyStatus::Caged, None);
+    let blademaster = Ally::new(MonsterKind::DarBlademaster, AllyStatus::Shackled, None);
+    let legendary = Ally::new(
+        MonsterKind::Unicorn,
+        AllyStatus::Allied,
+        Some(Mutation::Agile),
+    );
+
+    assert_eq!(weights.quality(&monkey), 1);
+    assert_eq!(weights.quality(&blademaster), 3);
+    assert_eq!(weights.quality(&legendary), 4 + 2 + 3);
+
+    let weights = AllyWeights::parse("0, 5, 2").unwrap();
+    assert_eq!(weights.quality(&legendary), 5 + 6);
+    assert!(AllyWeights::parse("1,2").is_err());
+}
+
+// Checks that gold by the search depth is totalled for graded seeds (to break ties).
+#[test]
+fn gold_tie_breaker() {
+    let args = ["brogue-scanner", "-p", "1", "-d", "3", "-m", "all", "--grade"];
+    let matches = new_app().get_matches_from(args);
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.set_file(FILE);
+    search_files(&mut search).unwrap();
+
+    let expected: u32 = std::fs::read_to_string(FILE)
+        .unwrap()
+        .lines()
+        .map(|line| line.split(',').collect::<Vec<_>>())
+        .filter(|fields| fields[1] == "3" && fields[4] == "gold" && fields[2].parse::<u8>().unwrap() <= 3)
+        .map(|fields| fields[3].parse::<u32>().unwrap())
+        .sum();
+    assert!(expected > 0);
+    assert_eq!(search.seed_gold.get(&3), Some(&expected));
+}
+
+// Checks that scoring expressions parse with the usual precedence, and reject bad input.
+#[test]
+fn score_expressions() {
+    use search::ScoreExpr;
+
+    let args = ["brogue-scanner", "-S", "enchanting", "-m", "all", "--grade"];
+    let matches = new_app().get_matches_from(args);
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+    let seed_1: Vec<SearchMatch> = search_matches.into_iter().filter(|m| m.seed == 1).collect();
+    let count = seed_1.iter().map(|m| m.quantity).sum::<u32>() as f64;
+    let depth = seed_1.iter().map(|m| m.depth).min().unwrap() as f64;
+    let score = search::SeedScore {
+        seed: 1,
+        objects: 4,
+        enchant_potential: 2,
+        ally_quality: 0,
+        optionals: 0,
+        score: 6,
+        gold: 250,
+    };
+
+    let eval = |text: &str| ScoreExpr::parse(text).unwrap().eval(&score, &seed_1);
+    assert_eq!(eval("1 + 2 * 3 - -4 / 2"), 9.0);
+    assert_eq!(eval("(base + gold / 50) * (objects > 3)"), 11.0);
+    assert_eq!(eval("max(enchant, 1 / 0) + min(2, 3 <= 3)"), 3.0);
+    assert_eq!(eval("count(\"enchant\") + count(\"scroll\")"), 2.0 * count);
+    assert_eq!(eval("depth(\"Enchanting\")"), depth);
+    assert_eq!(eval("depth(\"war axe\")"), 100.0);
+
+    for bad in [
+        "1 +",
+        "gold gold",
+        "foo",
+        "count(enchanting)",
+        "(1",
+        "sqrt(4)",
+        "2 $ 3",
+    ] {
+        assert!(ScoreExpr::parse(bad).is_err(), "{}", bad);
+    }
+}
+
+// Checks that `--fuzzy` reads misspelled kinds as the nearest kinds, and only when set.
+#[test]
+fn fuzzy_kinds() {
+    let args = [
+        "brogue-scanner",
+        "--fuzzy",
+        "-w",
+        "broadsord",
+        "+2",
+        "?war axr",
+        "-a",
+        "scale",
+        "-p",
+        "zzzzz",
+    ];
+    assert!(SearchParameters::from_matches(new_app().get_matches_from(args)).is_err());
+
+    let args = [
+        "brogue-scanner",
+        "--fuzzy",
+        "-w",
+        "broadsord",
+        "+2",
+        "?war axr",
+        "-a",
+        "scale",
+    ];
+    let search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+
+    assert_eq!(
+        search.fuzzy_terms,
+        vec![
+            ("broadsord".to_string(), "broadsword".to_string()),
+            ("?war axr".to_string(), "?war axe".to_string())
+        ]
+    );
+    assert_eq!(search.object_params.len(), 3);
+    assert!(search.to_string().contains("fuzzy: 'broadsord' read as 'broadsword'"));
+    assert!(
+        SearchParameters::from_matches(new_app().get_matches_from([
+            "brogue-scanner",
+            "-w",
+            "broadsord"
+        ]))
+        .is_err()
+    );
+}
+
+// Checks that the words of multi-word kinds and runics are joined into single terms.
+#[test]
+fn multi_word_kinds() {
+    let args = [
+        "brogue-scanner",
+        "-w",
+        "war",
+        "axe",
+        "?slaying",
+        "-S",
+        "scroll",
+        "of",
+        "magic",
+        "mapping",
+        "-A",
+        "dar",
+        "blademaster",
+    ];
+    let search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    let kinds: Vec<_> = search.object_params.iter().map(|p| p.kind.clone()).collect();
+
+    assert_eq!(search.object_params.len(), 3);
+    assert!(kinds.contains(&Some("war axe".to_string())));
+    assert!(kinds.contains(&Some("magic mapping".to_string())));
+    assert!(kinds.contains(&Some("dar blademaster".to_string())));
+
+    let search = SearchParameters::from_matches(new_app().get_matches_from([
+        "brogue-scanner",
+        "-w",
+        "?war",
+        "axe",
+        "axe",
+    ]))
+    .unwrap();
+    let kinds: Vec<_> = search
+        .object_params
+        .iter()
+        .map(|p| (p.kind.clone(), p.optional))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![(Some("war axe".to_string()), true), (Some("axe".to_string()), false)]
+    );
+}
+
+// Checks that `anyslaying` and `anyimmunity` match only monster-class runics.
+#[test]
+fn runic_families() {
+    let args = ["brogue-scanner", "-w", "anyslaying", "-m", "all"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+
+    assert_eq!(search_matches.len(), 1);
+    assert_eq!(
+        search_matches[0].object.runic_name(),
+        Some("waterborne slaying".to_string())
+    );
+
+    let args = ["brogue-scanner", "-a", "anyimmunity", "-m", "all"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+
+    assert_eq!(search_matches.len(), 1);
+    assert_eq!(search_matches[0].seed, 3);
+}
+
+// Checks the strength needed by every kind of armor, and that `str<=N` keeps only armor
+// wearable with N strength.
+#[test]
+fn armor_strength() {
+    let table = [
+        ("leather armor", 10),
+        ("scale mail", 11),
+        ("chain mail", 13),
+        ("banded mail", 15),
+        ("splint mail", 17),
+        ("plate armor", 19),
+    ];
+    for (name, strength) in table {
+        assert_eq!(
+            objects::ArmorKind::parse(name).unwrap().strength(),
+            strength,
+            "{}",
+            name
+        );
+    }
+
+    let args = ["brogue-scanner", "-a", "str<=13", "-m", "all", "-v"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+    let kinds: Vec<String> = search_matches.iter().map(|m| m.object.kind_name()).collect();
+
+    assert_eq!(search_matches.len(), 26);
+    assert!(
+        kinds
+            .iter()
+            .all(|kind| ["leather armor", "scale mail", "chain mail"].contains(&kind.as_str()))
+    );
+
+    let args = ["brogue-scanner", "-a", "str<=11", "-m", "all", "-v"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let kinds: Vec<String> = search_files(&mut search)
+        .unwrap()
+        .iter()
+        .map(|m| m.object.kind_name())
+        .collect();
+    assert!(kinds.iter().any(|kind| kind == "scale mail"));
+    assert!(
+        kinds
+            .iter()
+            .all(|kind| ["leather armor", "scale mail"].contains(&kind.as_str()))
+    );
+}
+
+// Checks that `str<=N` keeps only weapons wieldable with N strength.
+#[test]
+fn weapon_strength() {
+    let args = ["brogue-scanner", "-w", "str<=16", "-m", "all", "-v"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+
+    let kinds: Vec<String> = search_matches.iter().map(|m| m.object.kind_name()).collect();
+
+    assert!(kinds.iter().any(|kind| kind == "mace"));
+    assert!(
+        !kinds
+            .iter()
+            .any(|kind| kind.starts_with("war ") || kind == "broadsword" || kind == "flail")
+    );
+}
+
+// Checks that `1h` and `2h` split weapons by handedness, which follows the strength the
+// weapons need.
+#[test]
+fn weapon_handedness() {
+    use objects::WeaponKind;
+
+    let heavy = ["broadsword", "war axe", "war hammer", "war pike"];
+    let kinds = [
+        "axe",
+        "broadsword",
+        "dagger",
+        "dart",
+        "flail",
+        "incendiary dart",
+        "javelin",
+        "mace",
+        "rapier",
+        "spear",
+        "sword",
+        "war axe",
+        "war hammer",
+        "war pike",
+        "whip",
+    ];
+    for name in kinds {
+        let kind = WeaponKind::parse(name).unwrap();
+        assert_eq!(kind.is_two_handed(), heavy.contains(&name), "{}", name);
+        assert_eq!(kind.is_two_handed(), kind.strength() >= 18, "{}", name);
+    }
+
+    for (term, two_handed) in [("1h", false), ("2h", true)] {
+        let args = ["brogue-scanner", "-w", term, "-m", "all"];
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(FILE);
+        let search_matches = search_files(&mut search).unwrap();
+
+        assert!(!search_matches.is_empty());
+        assert!(
+            search_matches
+                .iter()
+                .all(|m| heavy.contains(&m.object.kind_name().as_str()) == two_handed)
+        );
+    }
+}
+
+// Checks that `thrown` counts darts, incendiary darts, and javelins together.
+#[test]
+fn thrown_weapons() {
+    let args = ["brogue-scanner", "-w", "20", "thrown", "d6", "-m", "all"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+    let kinds: Vec<String> = search_matches.iter().map(|m| m.object.kind_name()).collect();
+
+    assert!(search_matches.iter().all(|m| m.seed == 4));
+    assert_eq!(kinds, vec!["incendiary dart", "javelin"]);
+}
+
+// Checks that `dmg>=tierN` keeps only weapons of that damage tier or more.
+#[test]
+fn weapon_damage_tier() {
+    let args = ["brogue-scanner", "-w", "dmg>=tier3", "runic", "-m", "all"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+    let seeds: Vec<u32> = search_matches.iter().map(|m| m.seed).collect();
+
+    assert_eq!(seeds, vec![4, 5]);
+    assert!(search_matches.iter().all(|m| m.object.kind_name() == "mace"));
+    assert!(
+        new_app()
+            .get_matches_from_safe(["brogue-scanner", "-w", "dmg>=tier4"])
+            .is_ok_and(|m| SearchParameters::from_matches(m).is_err())
+    );
+}
+
+// Checks that `av>=N` keeps only armor of that base armor value or more, alongside enchantment.
+#[test]
+fn armor_value() {
+    let args = ["brogue-scanner", "-a", "av>=9", "+1", "-m", "all"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+
+    assert!(!search_matches.is_empty());
+    assert!(
+        search_matches
+            .iter()
+            .all(|m| ["splint mail", "plate armor"].contains(&m.object.kind_name().as_str()))
+    );
+    assert!(
+        search_matches
+            .iter()
+            .all(|m| m.object.enchantment().is_some_and(|e| e >= 1))
+    );
+}
+
+// Checks that `--met-depths` records the depth each object was first met and last matched.
+#[test]
+fn met_depths() {
+    let args = [
+        "brogue-scanner",
+        "-w",
+        "20",
+        "thrown",
+        "-a",
+        "splint",
+        "--met-depths",
+        "-m",
+        "all",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    search_files(&mut search).unwrap();
+
+    assert_eq!(search.seed_met_depths.keys().collect::<Vec<_>>(), vec![&4]);
+    assert_eq!(
+        search.seed_met_depths[&4],
+        vec![Some((14, 24)), Some((6, 6))]
+    );
+    assert_eq!(search.object_params[1].short_name(), "weapon x20");
+}
+
+// Checks that each matching seed's required depth is the deepest first-met depth, and
+// that seeds sort by it.
+#[test]
+fn required_depth() {
+    let args = [
+        "brogue-scanner",
+        "-S",
+        "3",
+        "enchanting",
+        "-p",
+        "2",
+        "life",
+        "--sort-depth",
+        "-m",
+        "all",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let mut search_matches = search_files(&mut search).unwrap();
+
+    assert_eq!(search.required_depth(1), Some(8));
+    assert_eq!(search.required_depth(5), Some(10));
+
+    sort_by_required_depth(&mut search_matches, &search);
+    let mut seeds: Vec<u32> = search_matches.iter().map(|m| m.seed).collect();
+    seeds.dedup();
+    assert_eq!(seeds, vec![2, 3, 4, 1, 5]);
+}
+
+// Checks that `--best` scans every seed and keeps the best seeds, best first.
+#[test]
+fn best_seeds() {
+    for (rank, expected) in [("depth", vec![2, 3]), ("score", vec![3, 1])] {
+        let args = [
+            "brogue-scanner",
+            "-S",
+            "3",
+            "enchanting",
+            "-p",
+            "2",
+            "life",
+            "--best",
+            rank,
+            "-m",
+            "2",
+        ];
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(FILE);
+        let search_matches = search_files(&mut search).unwrap();
+        let mut seeds: Vec<u32> = search_matches.iter().map(|m| m.seed).collect();
+        seeds.dedup();
+
+        assert_eq!(seeds, expected);
+        assert_eq!((search.search_matches, search.seeds_scanned), (2, 5));
+    }
+}
+
+// Checks that `--random-seed` makes the random file order reproducible.
+#[test]
+fn random_seed() {
+    let args = ["brogue-scanner", "--random-seed", "42", "-a", "scale"];
+    let search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    assert_eq!(search.random_seed, Some(42));
+
+    let paths: Vec<std::path::PathBuf> =
+        (0..20).map(|i| format!("seeds-{}.csv", i).into()).collect();
+    let shuffled = |seed| {
+        let mut paths = paths.clone();
+        file_handling::shuffle_paths(&mut paths, Some(seed));
+        paths
+    };
+
+    assert_eq!(shuffled(42), shuffled(42));
+    assert_ne!(shuffled(42), paths);
+    assert_ne!(shuffled(42), shuffled(43));
+}
+
+// Checks that catalog files are sorted by the seed ranges in their names.
+#[test]
+fn catalog_path_order() {
+    let mut paths: Vec<std::path::PathBuf> = [
+        "b.csv",
+        "seeds-100-200.csv",
+        "a.csv",
+        "seeds-11-99.csv",
+        "seeds-1-10.csv.gz",
+    ]
+    .iter()
+    .map(|name| name.into())
+    .collect();
+    file_handling::sort_catalog_paths(&mut paths);
+
+    let names: Vec<&str> = paths.iter().map(|p| p.to_str().unwrap()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "seeds-1-10.csv.gz",
+            "seeds-11-99.csv",
+            "seeds-100-200.csv",
+            "a.csv",
+            "b.csv"
+        ]
+    );
+}
+
+// Checks that `--output` appends one NDJSON line per match as each seed is confirmed.
+#[test]
+fn output_appends_matches() {
+    let dir = TempDir::new("output");
+    let path = dir.join("matches.ndjson");
+    let output = path.to_str().unwrap();
+
+    let args = [
+        "brogue-scanner",
+        "-U",
+        "-F",
+        "./src",
+        "--output",
+        output,
+        "-a",
+        "scale",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    let search_matches = search_files(&mut search).unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents.lines().count(), search_matches.len());
+    for (line, m) in contents.lines().zip(&search_matches) {
+        let record: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(
+            (record["type"].as_str(), record["seed"].as_u64()),
+            (Some("match"), Some(m.seed as u64))
+        );
+    }
+}
+
+// Checks that `--output` takes the whole output of a search in the format chosen:  lines
+// with their summary, or a document replacing the file.
+#[test]
+fn output_formats() {
+    let dir = TempDir::new("output-formats");
+    let path = dir.join("matches.out");
+    let output = path.to_str().unwrap();
+
+    let run = |format: &[&str]| {
+        let mut args = vec![
+            "brogue-scanner",
+            "-U",
+            "-F",
+            "./src",
+            "--output",
+            output,
+            "-a",
+            "scale",
+            "-m",
+            "all",
+        ];
+        args.extend_from_slice(format);
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        engine::Engine::new()
+            .run(new_app().get_matches_from(&args), &args)
+            .unwrap();
+        std::fs::read_to_string(&path).unwrap()
+    };
+
+    let ndjson = run(&[]);
+    let last: serde_json::Value = serde_json::from_str(ndjson.lines().last().unwrap()).unwrap();
+    assert_eq!(
+        (
+            ndjson.lines().count(),
+            last["type"].as_str(),
+            last["matches"].as_u64()
+        ),
+        (8, Some("summary"), Some(7))
+    );
+
+    // Lines are appended, and documents replace the file
+    assert_eq!(run(&["--porcelain"]).lines().count(), 15);
+    let json: serde_json::Value = serde_json::from_str(&run(&["--json"])).unwrap();
+    assert_eq!(json["summary"]["seeds"], 4);
+    assert!(run(&["--report", "md"]).starts_with("# Seed finds\n"));
+}
+
+// Checks that `--output` only takes the seeds kept when several folders are searched in
+// parallel, rather than every seed each worker finds.
+#[test]
+fn output_parallel_folders() {
+    let dir = TempDir::new("output-parallel");
+    // The second folder holds the same catalog, with seeds 5 to 8
+    let catalog = std::fs::read_to_string(FILE).unwrap();
+    let shifted: Vec<String> = catalog
+        .lines()
+        .map(|line| {
+            let mut fields: Vec<String> = line.split(',').map(String::from).collect();
+            if let Ok(seed) = fields[1].parse::<u32>() {
+                fields[1] = (seed + 4).to_string();
+            }
+            fields.join(",")
+        })
+        .collect();
+    for (folder, contents) in [("a", catalog.clone()), ("b", shifted.join("\n"))] {
+        std::fs::create_dir_all(dir.join(folder)).unwrap();
+        std::fs::write(dir.join(folder).join("test_data.csv"), contents).unwrap();
+    }
+    let path = dir.join("matches.ndjson");
+    let (a, b) = (dir.join("a"), dir.join("b"));
+
+    let args = [
+        "brogue-scanner",
+        "-U",
+        "-F",
+        a.to_str().unwrap(),
+        "-F",
+        b.to_str().unwrap(),
+        "--output",
+        path.to_str().unwrap(),
+        "-a",
+        "scale",
+        "-m",
+        "1",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    let search_matches = search_files(&mut search).unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    let seeds: Vec<u64> = contents
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["seed"].as_u64().unwrap())
+        .collect();
+    assert_eq!(search.search_matches, 1);
+    assert_eq!(
+        seeds,
+        search_matches.iter().map(|m| m.seed as u64).collect::<Vec<_>>()
+    );
+}
+
+// Checks that `--stream` keeps the same matches, and can't be combined with reordering or
+// with several folders (whose workers find more seeds than are kept).
+#[test]
+fn stream_matches() {
+    let search = |args: &[&str]| {
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        let search_matches = search_files(&mut search).unwrap();
+        (search.stream, search_matches.len(), search.search_matches)
+    };
+    let streamed = search(&["brogue-scanner", "-U", "-F", "./src", "--stream", "-a", "scale"]);
+    let buffered = search(&["brogue-scanner", "-U", "-F", "./src", "-a", "scale"]);
+
+    assert!(streamed.0 && !buffered.0);
+    assert_eq!((streamed.1, streamed.2), (buffered.1, buffered.2));
+    assert!(
+        new_app()
+            .get_matches_from_safe(["brogue-scanner", "--stream", "--sort-depth", "-a", "scale"])
+            .is_err()
+    );
+
+    let args = [
+        "brogue-scanner",
+        "-U",
+        "-F",
+        "./src",
+        "-F",
+        "./data",
+        "--stream",
+        "-a",
+        "scale",
+    ];
+    let error = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap_err();
+    assert!(error.to_string().contains("--stream"));
+}
+
+// Checks that a cancelled search stops early and returns without error, and that copies
+// of the search share its cancel token.
+#[test]
+#[cfg(feature = "server")]
+fn cancel_search() {
+    use search::CancelToken;
+
+    let args = ["brogue-scanner", "-U", "-F", "./src", "-m", "all", "-a", "scale"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    let token = CancelToken::new();
+    search.cancel = token.clone();
+    let copy = search.clone();
+    assert!(!copy.cancel.is_cancelled());
+
+    token.cancel();
+    assert!(copy.cancel.is_cancelled());
+
+    let search_matches = search_files(&mut search).unwrap();
+    assert!(search_matches.is_empty());
+    assert_eq!(search.search_matches, 0);
+}
+
+// Checks that `--nice` scans find the same matches as full-speed scans.
+#[test]
+fn nice_scan() {
+    let search = |args: &[&str]| {
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        let search_matches = search_files(&mut search).unwrap();
+        (search.nice, search_matches.len(), search.seeds_scanned)
+    };
+    let nice = search(&["brogue-scanner", "-U", "-F", "./src", "--nice", "-a", "scale"]);
+    let full = search(&["brogue-scanner", "-U", "-F", "./src", "-a", "scale"]);
+
+    assert!(nice.0 && !full.0);
+    assert_eq!((nice.1, nice.2), (full.1, full.2));
+}
+
+// Checks that a runic term of `|`-separated alternatives matches any of them under one
+// count, and that every alternative must name a runic.
+#[test]
+fn runic_alternatives() {
+    let args = ["brogue-scanner", "-w", "speed|slow|paralysis", "-m", "all"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+    let runics: Vec<(u32, Option<String>)> = search_matches
+        .iter()
+        .map(|m| (m.seed, m.object.runic_name()))
+        .collect();
+
+    assert_eq!(search.object_params.len(), 1);
+    assert_eq!(
+        runics,
+        vec![(1, Some("slowing".to_string())), (3, Some("paralysis".to_string()))]
+    );
+
+    let args = ["brogue-scanner", "-w", "2", "mercy|plenty", "-m", "all"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let seeds: Vec<u32> = search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+    assert_eq!(seeds, vec![3, 3]);
+
+    let args = ["brogue-scanner", "-w", "speed|nonsense"];
+    assert!(SearchParameters::from_matches(new_app().get_matches_from(args)).is_err());
+}
+
+// Checks that `N-` finds staves and wands of at most N charges, alone or with `+N`.
+#[test]
+fn max_charges() {
+    let found = |args: &[&str]| -> Vec<(u32, u8)> {
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(FILE);
+        search_files(&mut search)
+            .unwrap()
+            .iter()
+            .map(|m| (m.seed, m.depth))
+            .collect()
+    };
+
+    assert_eq!(
+        found(&["brogue-scanner", "-W", "teleportation", "3-", "-m", "all"]),
+        vec![(3, 24), (5, 1), (5, 23)]
+    );
+    assert_eq!(
+        found(&["brogue-scanner", "-s", "lightning", "+2", "2-", "-m", "all"]),
+        vec![(1, 12)]
+    );
+}
+
+// Checks that `N@dD` stages each need their count by their depth, on a single object.
+#[test]
+fn staged_counts() {
+    let seeds = |args: &[&str]| -> Vec<u32> {
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(FILE);
+        let mut seeds: Vec<u32> =
+            search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+        seeds.dedup();
+        seeds
+    };
+
+    assert_eq!(
+        seeds(&["brogue-scanner", "-S", "enchanting", "6", "d10", "-m", "all"]),
+        vec![1, 2, 4]
+    );
+    assert_eq!(
+        seeds(&["brogue-scanner", "-S", "enchanting", "3@d3", "6@d10", "-m", "all"]),
+        vec![1, 4]
+    );
+
+    let args = ["brogue-scanner", "-S", "enchanting", "3@d3", "6@d10"];
+    let search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    let param = &search.object_params[0];
+    assert_eq!(
+        (search.object_params.len(), param.count_target, param.depth),
+        (1, 6, 10)
+    );
+}
+
+// Checks that `--total` needs the objects of several categories to reach a combined count,
+// without each object meeting its own count.
+#[test]
+fn combined_totals() {
+    let args = [
+        "brogue-scanner",
+        "-p",
+        "d6",
+        "-S",
+        "d6",
+        "--total",
+        "25:1,2",
+        "-m",
+        "all",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let mut seeds: Vec<u32> = search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+    seeds.dedup();
+
+    assert_eq!(seeds, vec![2, 3, 4]);
+    assert_eq!(
+        seeds
+            .iter()
+            .map(|seed| search.required_depth(*seed))
+            .collect::<Vec<_>>(),
+        vec![Some(4), Some(6), Some(5)]
+    );
+    assert!(search.object_params.iter().all(|p| p.totalled));
+
+    let args = ["brogue-scanner", "-p", "life", "-S", "enchanting", "--total", "3"];
+    assert!(SearchParameters::from_matches(new_app().get_matches_from(args)).is_err());
+}
+
+// Checks that `--strength` is read as potions of strength, and only takes COUNT, DEPTH,
+// stage, and VAULT terms.
+#[test]
+fn strength_potions() {
+    let params = |args: &[&str]| SearchParameters::from_matches(new_app().get_matches_from(args)).map(|s| s.object_params);
+
+    let strength = params(&["brogue-scanner", "--strength", "3", "d8", "novault"]).unwrap();
+    let potions = params(&["brogue-scanner", "-p", "strength", "3", "d8", "novault"]).unwrap();
+    assert_eq!(strength, potions);
+
+    let staged = params(&["brogue-scanner", "--strength", "1@d4", "3@d10"]).unwrap();
+    assert_eq!(
+        (staged[0].kind.as_deref(), staged[0].stages.len()),
+        (Some("strength"), 2)
+    );
+
+    assert!(params(&["brogue-scanner", "--strength", "3", "life"]).is_err());
+}
+
+// Checks that `--vaults` keeps every vault on the searched depths of matching seeds, with
+// its contents and whether its key was found.
+#[test]
+fn seed_vaults() {
+    let args = ["brogue-scanner", "-a", "scale", "--vaults", "-d", "7", "-m", "all"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    search_files(&mut search).unwrap();
+
+    let vaults = &search.seed_vaults[&1];
+    assert_eq!(
+        vaults
+            .iter()
+            .map(|v| (v.depth, v.number, v.key_found))
+            .collect::<Vec<_>>(),
+        vec![(3, 1, true), (7, 1, true)]
+    );
+    assert_eq!(vaults[0].contents.len(), 6);
+    assert_eq!(vaults[1].contents, vec!["A +3 leather armor of absorption"]);
+    assert!(search.seed_vaults.values().flatten().all(|v| v.depth <= 7));
+}
+
+// Checks that `--rescue` reports the cage keys (and their carriers) and captors on the
+// level of each captive ally matched.
+#[test]
+fn ally_rescue() {
+    let args = [
+        "brogue-scanner",
+        "-A",
+        "troll",
+        "caged",
+        "--rescue",
+        "-d",
+        "26",
+        "-m",
+        "all",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    search_files(&mut search).unwrap();
+
+    let rescues = &search.seed_rescues[&2];
+    assert_eq!(rescues.len(), 1);
+    assert_eq!(
+        (rescues[0].depth, rescues[0].ally.as_str()),
+        (19, "A caged troll")
+    );
+    assert_eq!(rescues[0].cage_keys, vec![None, Some("imp".to_string())]);
+    assert_eq!(rescues[0].captors, vec!["imp", "dar blademaster"]);
+}
+
+// Checks that catalog columns are found by header name, so a file with its columns in
+// another order matches the same objects.
+#[test]
+fn reordered_columns() {
+    let dir = TempDir::new("columns");
+    let reversed: Vec<String> = std::fs::read_to_string(FILE)
+        .unwrap()
+        .lines()
+        .map(|line| line.split(',').rev().collect::<Vec<_>>().join(","))
+        .collect();
+    std::fs::write(dir.join("test_data.csv"), reversed.join("\n")).unwrap();
+
+    let search = |file: &str| {
+        let args = [
+            "brogue-scanner",
+            "-a",
+            "scale",
+            "-w",
+            "quietus",
+            "-A",
+            "caged",
+            "-m",
+            "all",
+            "--logic",
+            "1 || 2 || 3",
+        ];
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(file);
+        let search_matches = search_files(&mut search).unwrap();
+        search_matches
+            .iter()
+            .map(|m| (m.seed, m.depth, m.to_string()))
+            .collect::<Vec<_>>()
+    };
+    let expected = search(FILE);
+    assert!(!expected.is_empty());
+    assert_eq!(
+        search(dir.join("test_data.csv").to_str().unwrap()),
+        expected
+    );
+}
+
+// Checks that catalogs with extra columns are searched as usual, and that the extras can
+// be filtered on with `--where`.
+#[test]
+fn extra_columns() {
+    let dir = TempDir::new("extras");
+    let lines: Vec<String> = std::fs::read_to_string(FILE)
+        .unwrap()
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let fields: Vec<&str> = line.split(',').collect();
+            let flag = match (i, fields[1]) {
+                (0, _) => "flag",
+                (_, "3") => "x",
+                _ => "",
+            };
+            format!(
+                "{},{},{},note",
+                fields[..2].join(","),
+                flag,
+                fields[2..].join(",")
+            )
+        })
+        .collect();
+    let path = dir.join("test_data.csv");
+    std::fs::write(&path, lines.join("\n")).unwrap();
+
+    let search = |file: &str, filter: &[&str]| {
+        let mut args = vec!["brogue-scanner", "-a", "scale", "-m", "all"];
+        args.extend_from_slice(filter);
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(file);
+        let search_matches = search_files(&mut search).unwrap();
+        search_matches
+            .iter()
+            .map(|m| (m.seed, m.depth, m.to_string()))
+            .collect::<Vec<_>>()
+    };
+    let expected = search(FILE, &[]);
+    assert_eq!(search(path.to_str().unwrap(), &[]), expected);
+
+    let flagged: Vec<_> = expected.into_iter().filter(|(seed, _, _)| *seed == 3).collect();
+    assert!(!flagged.is_empty());
+    assert_eq!(
+        search(path.to_str().unwrap(), &["--where", "col:flag=x"]),
+        flagged
+    );
+}
+
+// Checks that `--schema` reads catalogs with renamed columns and categories, as set by a
+// section of the config file.
+#[test]
+fn schema_columns() {
+    let dir = TempDir::new("schema");
+    let modded = std::fs::read_to_string(FILE)
+        .unwrap()
+        .replacen(",kind,", ",item_kind,", 1)
+        .replacen(",vault_number,", ",vault,", 1)
+        .replace(",armor,", ",armour,");
+    std::fs::write(dir.join("test_data.csv"), modded).unwrap();
+    std::fs::write(
+        dir.join("brogue-scanner.toml"),
+        "[schema.modded]\ncolumns = { kind = \"item_kind\", vault_number = \"vault\" }\ncategories = { armour = \"armor\" }\n",
+    )
+    .unwrap();
+
+    let search = |args: &[&str]| {
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args))?;
+        let search_matches = search_files(&mut search)?;
+        Ok::<_, anyhow::Error>(
+            search_matches
+                .iter()
+                .map(|m| (m.seed, m.depth, m.to_string()))
+                .collect::<Vec<_>>(),
+        )
+    };
+    let expected =
+        search(&["brogue-scanner", "-U", "-F", "./src", "-a", "scale", "-m", "all"]).unwrap();
+    let dir_arg = dir.to_str().unwrap();
+    let modded = search(&[
+        "brogue-scanner",
+        "-U",
+        "-F",
+        dir_arg,
+        "--schema",
+        "modded",
+        "-a",
+        "scale",
+        "-m",
+        "all",
+    ]);
+    assert_eq!(modded.unwrap(), expected);
+
+    assert!(
+        search(&[
+            "brogue-scanner",
+            "-U",
+            "-F",
+            dir_arg,
+            "--schema",
+            "vanilla",
+            "-a",
+            "scale"
+        ])
+        .is_err()
+    );
+}
+
+// Checks that `--variant` reads a variant's kinds and monsters as their Brogue CE names,
+// and uses the malevolence its data pack sets.
+#[test]
+fn variant_data_pack() {
+    let dir = TempDir::new("variant");
+    std::fs::create_dir_all(dir.join("variants")).unwrap();
+    let variant = std::fs::read_to_string(FILE)
+        .unwrap()
+        .replace(",scale mail,", ",scale armour,")
+        .replace("goblin mystic", "goblin shaman");
+    std::fs::write(dir.join("test_data.csv"), &variant).unwrap();
+    std::fs::write(
+        dir.join("variants").join("rapid.toml"),
+        "name = \"Rapid Brogue\"\n\
+         [kinds.armor]\n\"scale armour\" = \"scale mail\"\n\
+         [monsters]\n\"goblin shaman\" = \"goblin mystic\"\n\
+         [malevolent.potion]\nhallucination = false\n",
+    )
+    .unwrap();
+
+    let search = |folder: &str, terms: &[&str]| {
+        let mut args = vec!["brogue-scanner", "-U", "-F", folder, "-m", "all"];
+        args.extend_from_slice(terms);
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        let search_matches = search_files(&mut search).unwrap();
+        search_matches
+            .iter()
+            .map(|m| (m.seed, m.depth, m.to_string()))
+            .collect::<Vec<_>>()
+    };
+    let dir_arg = dir.to_str().unwrap();
+    let expected = search("./src", &["-a", "scale"]);
+    assert_eq!(
+        search(dir_arg, &["-a", "scale", "--variant", "rapid"]),
+        expected
+    );
+
+    let carried = search(
+        dir_arg,
+        &[
+            "-i",
+            "1",
+            "--variant",
+            "rapid",
+            "--where",
+            "col:carried=goblin mystic",
+        ],
+    );
+    assert!(!carried.is_empty());
+    assert_eq!(
+        carried,
+        search(
+            "./src",
+            &["-i", "1", "--where", "col:carried=goblin mystic"]
+        )
+    );
+
+    let bad = search("./src", &["-p", "bad"]);
+    let rapid_bad = search(dir_arg, &["-p", "bad", "--variant", "rapid"]);
+    assert!(bad.iter().any(|(_, _, m)| m.contains("hallucination")));
+    assert!(rapid_bad.iter().all(|(_, _, m)| !m.contains("hallucination")));
+
+    // Kinds the data pack doesn't know end the search with an error naming the file and line
+    std::fs::write(
+        dir.join("test_data.csv"),
+        variant.replace(",banded mail,", ",banded armour,"),
+    )
+    .unwrap();
+    let args = [
+        "brogue-scanner",
+        "-U",
+        "-F",
+        dir_arg,
+        "-m",
+        "all",
+        "-a",
+        "1",
+        "--variant",
+        "rapid",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    let error = format!("{:#}", search_files(&mut search).unwrap_err());
+    assert!(
+        error.contains("test_data.csv")
+            && error.contains("line 116:  invalid armor 'banded armour'"),
+        "{}",
+        error
+    );
+}
+
+// Checks that `--data-dir` name tables extend and replace the built-in ones, and name output.
+#[test]
+fn data_dir_tables() {
+    use objects::{AllyStatus, PotionKind, Tables};
+
+    let dir = TempDir::new("tables");
+    let tables = |changes: &str| {
+        std::fs::write(dir.join("tables.json"), changes).unwrap();
+        Tables::load(Some(&dir))
+    };
+
+    let built_in = Tables::load(None).unwrap();
+    assert!(built_in.parse::<PotionKind>("quickness").is_none());
+    assert!(matches!(
+        built_in.parse_partial::<PotionKind>("life"),
+        Some(PotionKind::Life)
+    ));
+
+    let extended = tables(r#"{ "extend": { "potion_kinds": [["quickness", "Speed"]] } }"#).unwrap();
+    assert!(matches!(
+        extended.parse::<PotionKind>("quickness"),
+        Some(PotionKind::Speed)
+    ));
+    assert!(matches!(
+        extended.parse::<PotionKind>("speed"),
+        Some(PotionKind::Speed)
+    ));
+    assert_eq!(
+        extended.names_matching::<PotionKind>("ness"),
+        vec!["darkness", "quickness"]
+    );
+
+    let replaced = tables(
+        r#"{ "replace": { "ally_statuses": [["legendary", "Allied"], ["caged", "Caged"], ["chained", "Shackled"]] } }"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        replaced.parse::<AllyStatus>("chained"),
+        Some(AllyStatus::Shackled)
+    ));
+    assert!(replaced.parse::<AllyStatus>("shackled").is_none());
+    assert_eq!(replaced.name(AllyStatus::Shackled), "chained");
+    assert_eq!(built_in.name(AllyStatus::Shackled), "shackled");
+    assert_eq!(
+        extended.name(PotionKind::Speed),
+        built_in.name(PotionKind::Speed)
+    );
+    assert_eq!(
+        AllyStatus::Shackled.to_string(),
+        built_in.name(AllyStatus::Shackled)
+    );
+
+    assert!(tables(r#"{ "extend": { "potion_kinds": [["quickness", "Quickness"]] } }"#).is_err());
+    assert!(tables(r#"{ "replace": { "ally_statuses": [["caged", "Caged"]] } }"#).is_err());
+    assert!(tables(r#"{ "extend": { "potions": [] } }"#).is_err());
+}
+
+// Checks that `update-tables` reads names from Brogue CE source and reports changes.
+#[test]
+fn update_tables_from_source() {
+    use brogue_source::update_tables;
+    use objects::{ArmorKind, PotionKind, Tables, WeaponRunic};
+
+    let dir = TempDir::new("update-tables");
+    std::fs::create_dir_all(dir.join("brogue")).unwrap();
+    std::fs::write(
+        dir.join("brogue").join("Globals.c"),
+        "// Item tables\n\
+         extern itemTable armorTable[NUMBER_ARMOR_KINDS];\n\
+         itemTable armorTable[NUMBER_ARMOR_KINDS] = {\n\
+             {\"leather armor\", \"\", \"\", 10, 250, 10, {30,30,0}, true, false, 0, false, \"A {soft} \\\"vest\\\".\"},\n\
+             {\"scale mail\", \"\", \"\", 10, 350, 13}, /* {\"banded mail\"} */\n\
+             {\"plate mail\", \"\", \"\", 10, 500, 19},\n\
+         };\n\
+         const itemTable potionTable_Brogue[NUMBER_POTION_KINDS] = {\n\
+             {\"life\"}, {\"strength\"}, {\"quickness\"},\n\
+         };\n\
+         const char weaponRunicNames[NUMBER_WEAPON_RUNIC_KINDS][30] = {\n\
+             \"speed\", \"quietus\", \"slaying\",\n\
+         };\n\
+         monsterClass monsterClassCatalog[MONSTER_CLASS_COUNT] = {\n\
+             {\"dragon\", 10, 0, {MK_DRAGON}},\n\
+             {\"Goblin\", 10, 0, {MK_GOBLIN}},\n\
+         };\n",
+    )
+    .unwrap();
+    let output = dir.join("tables.json");
+
+    let (files, updates) = update_tables(&dir, &output).unwrap();
+    let update = |table: &str| updates.iter().find(|u| u.table == table).unwrap().to_string();
+    assert_eq!(files, 1);
+    assert_eq!(
+        update("armor_kinds"),
+        "armor_kinds:  added plate mail;  removed plate armor;  kept (only name of its kind) banded mail, chain mail, splint mail"
+    );
+    assert!(update("potion_kinds").contains("no kind in scanner for quickness"));
+    assert_eq!(
+        update("monster_kinds"),
+        "monster_kinds:  no 'monsterCatalog' in source (unchanged)"
+    );
+
+    let tables = Tables::load(Some(&dir)).unwrap();
+    assert!(matches!(
+        tables.parse::<ArmorKind>("plate mail"),
+        Some(ArmorKind::PlateMail)
+    ));
+    assert!(tables.parse::<ArmorKind>("plate armor").is_none());
+    assert!(tables.parse::<PotionKind>("quickness").is_none());
+    assert!(matches!(
+        tables.parse::<WeaponRunic>("goblin slaying"),
+        Some(WeaponRunic::Slaying(_))
+    ));
+}
+
+// Checks that '~' and environment variables are expanded in paths given by the user.
+#[test]
+fn path_expansion() {
+    use file_handling::expand_path;
+    use std::path::PathBuf;
+
+    std::env::set_var("BROGUE_TEST_SRC", "./src");
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .unwrap();
+
+    assert_eq!(
+        expand_path("~/catalogs").unwrap(),
+        PathBuf::from(format!("{}/catalogs", home))
+    );
+    assert_eq!(
+        expand_path("~user/catalogs").unwrap(),
+        PathBuf::from("~user/catalogs")
+    );
+    assert_eq!(
+        expand_path("$BROGUE_TEST_SRC/a").unwrap(),
+        PathBuf::from("./src/a")
+    );
+    assert_eq!(
+        expand_path("${BROGUE_TEST_SRC}a").unwrap(),
+        PathBuf::from("./srca")
+    );
+    assert_eq!(
+        expand_path("%BROGUE_TEST_SRC%\\a").unwrap(),
+        PathBuf::from("./src\\a")
+    );
+    assert_eq!(
+        expand_path("50% of $ 1").unwrap(),
+        PathBuf::from("50% of $ 1")
+    );
+    let error = expand_path("%BROGUE_TEST_UNSET%/a").unwrap_err().to_string();
+    assert!(error.contains("'%BROGUE_TEST_UNSET%'"), "{}", error);
+
+    let args = &["brogue-scanner", "-U", "-F", "$BROGUE_TEST_SRC", "-a", "scale"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    assert_eq!(search_files(&mut search).unwrap().len(), 7);
+
+    let args = [
+        "brogue-scanner",
+        "-U",
+        "-F",
+        "./src",
+        "-a",
+        "scale",
+        "--output",
+        "$BROGUE_TEST_SRC/out.txt",
+        "--save-db",
+        "$BROGUE_TEST_SRC/out.db",
+        "--export",
+        "$BROGUE_TEST_SRC/out.csv",
+        "--write-seedlist",
+        "$BROGUE_TEST_SRC/seeds.txt",
+        "--rarity-cache",
+        "$BROGUE_TEST_SRC/rarity.json",
+    ];
+    let search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    assert_eq!(search.output, Some(PathBuf::from("./src/out.txt")));
+    assert_eq!(search.save_db, Some(PathBuf::from("./src/out.db")));
+    assert_eq!(search.export, Some(PathBuf::from("./src/out.csv")));
+    assert_eq!(
+        search.write_seedlist,
+        Some(PathBuf::from("./src/seeds.txt"))
+    );
+    assert_eq!(search.rarity_cache, PathBuf::from("./src/rarity.json"));
+
+    let args = [
+        "brogue-scanner",
+        "-U",
+        "-F",
+        "./src",
+        "-a",
+        "scale",
+        "--output",
+        "$BROGUE_TEST_UNSET/out.txt",
+    ];
+    assert!(SearchParameters::from_matches(new_app().get_matches_from(args)).is_err());
+}
+
+// Checks that a UTF-16 file decoded on its own thread reads whole, over many chunks, and
+// that dropping the reader early stops the decoding.
+#[test]
+#[cfg(feature = "utf16")]
+fn pipelined_decoding() {
+    use std::io::Read;
+
+    let text = std::fs::read_to_string(FILE).unwrap().repeat(20);
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(text.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+    assert!(bytes.len() > 4 * 64 * 1024);
+
+    let mut decoded = String::new();
+    let mut reader =
+        file_handling::decode_utf16(Box::new(std::io::Cursor::new(bytes.clone())), true).unwrap();
+    reader.read_to_string(&mut decoded).unwrap();
+    assert!(decoded == text);
+
+    let mut reader =
+        file_handling::decode_utf16(Box::new(std::io::Cursor::new(bytes)), true).unwrap();
+    let mut start = [0; 16];
+    reader.read_exact(&mut start).unwrap();
+    assert_eq!(&start[..], &text.as_bytes()[..16]);
+    drop(reader);
+
+    // Catalogs read the same through the pipeline
+    let dir = TempDir::new("pipeline");
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(
+        std::fs::read_to_string(FILE)
+            .unwrap()
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes()),
+    );
+    std::fs::write(dir.join("seeds.csv"), bytes).unwrap();
+
+    let args = &["brogue-scanner", "--filepath", dir.to_str().unwrap(), "-a", "scale"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    assert_eq!(search_files(&mut search).unwrap().len(), 7);
+}
+
+// Checks that share strings hold each matching seed's objects, and decode to the same
+// matches.
+#[test]
+fn share_strings() {
+    use crate::search::{MatchV1, SummaryV1};
+
+    let args = &["brogue-scanner", "-a", "scale", "-m", "all"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+
+    let shares = share::share_strings(&search_matches);
+    assert_eq!(shares.len(), SummaryV1::from_matches(&search_matches).seeds);
+    assert_eq!(shares[0], "brogue:1/CE+1.9/1/3,1,armor,scale+mail,0,,1");
+
+    let decoded: Vec<SearchMatch> = shares
+        .iter()
+        .flat_map(|share| share::decode(share).unwrap())
+        .collect();
+    let json = |matches: &[SearchMatch]| {
+        let records: Vec<MatchV1> = matches.iter().map(MatchV1::from_match).collect();
+        serde_json::to_string(&records).unwrap()
+    };
+    assert_eq!(json(&decoded), json(&search_matches));
+    assert!(decoded.iter().all(|m| m.version == "CE 1.9"));
+
+    let ally = share::decode("brogue:1/CE%201.9/7/5,1,ally,goblin+mystic,,,,,,shackled").unwrap();
+    assert_eq!(
+        MatchV1::from_match(&ally[0]).ally_status.as_deref(),
+        Some("shackled")
+    );
+
+    for invalid in [
+        "brogue:1/CE+1.9/7",
+        "brogue:1/CE+1.9/7/5,1,armor,scale+mail,0,refl",
+        "brogue:1/CE%2/7/5,1,potion,healing",
+    ] {
+        assert!(share::decode(invalid).is_err(), "{}", invalid);
+    }
+}
+
+// Checks that Markdown reports group matches under a heading per seed and a bullet list
+// per depth.
+#[test]
+fn markdown_report() {
+    let args = &[
+        "brogue-scanner",
+        "-a",
+        "scale",
+        "-m",
+        "all",
+        "--report",
+        "md",
+        "--seed",
+        "3",
+        "--seed",
+        "4",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    assert_eq!(search.output_mode, search::OutputMode::Markdown);
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+
+    let mut buffer = Vec::new();
+    write_machine_matches(&mut buffer, &search_matches, &search).unwrap();
+    let report = String::from_utf8(buffer).unwrap();
+
+    assert!(report.starts_with(
+        "# Seed finds\n\n## Seed 3 (CE 1.9)\n\nComplete by depth 10.\n\n**Depth 10**\n\n"
+    ));
+    assert!(
+        report.contains(
+            "\n**Depth 11**\n\n- A -2 scale mail of vulnerability\n\n## Seed 4 (CE 1.9)\n"
+        )
+    );
+    assert_eq!(report.matches("\n- ").count(), search_matches.len());
+    assert!(report.ends_with("\n_2 seeds found._\n"));
+
+    assert!(
+        new_app()
+            .get_matches_from_safe(["brogue-scanner", "--report", "md", "--json"])
+            .is_err()
+    );
+}
+
+// Checks that HTML reports are a standalone page with a table row per matching object,
+// and count seeds and objects in the singular or plural.
+#[test]
+fn html_report() {
+    let args = &["brogue-scanner", "-a", "scale", "-m", "all", "--report", "html"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    assert_eq!(search.output_mode, search::OutputMode::Html);
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+
+    let mut buffer = Vec::new();
+    let search = SearchParameters {
+        name: Some("scale <mail>".into()),
+        ..search
+    };
+    write_machine_matches(&mut buffer, &search_matches, &search).unwrap();
+    let report = String::from_utf8(buffer).unwrap();
+
+    assert!(report.starts_with("<!DOCTYPE html>\n"));
+    assert!(report.contains("<h1>Seed finds:  scale &lt;mail&gt;</h1>"));
+    assert_eq!(report.matches("<tr><td>").count(), search_matches.len());
+    assert!(report.contains(
+        "<tr><td>2</td><td>19</td><td>A +0 scale mail</td><td></td><td>dar blademaster</td></tr>"
+    ));
+    assert!(!report.contains("src=") && report.trim_end().ends_with("</html>"));
+    assert!(report.contains("<p>4 seeds found, with 7 matching objects.</p>"));
+
+    let mut buffer = Vec::new();
+    let seed_2: Vec<_> = search_matches.iter().filter(|m| m.seed == 2).cloned().collect();
+    write_machine_matches(&mut buffer, &seed_2, &search).unwrap();
+    assert!(
+        String::from_utf8(buffer)
+            .unwrap()
+            .contains("<p>1 seed found, with 1 matching object.</p>")
+    );
+    assert_eq!(search.counted(12345, "seed", "seeds"), "12,345 seeds");
+}
+
+// Checks that --format templates print a line per match with the fields given, and reject
+// unknown fields and stray braces.
+#[test]
+fn match_templates() {
+    use search::MatchTemplate;
+
+    let args = &[
+        "brogue-scanner",
+        "-a",
+        "scale",
+        "-m",
+        "all",
+        "--seed",
+        "2",
+        "--format",
+        r"{seed}\t{depth}\t{object} {{{carried_by}}}",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    assert_eq!(search.output_mode, search::OutputMode::Template);
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+
+    let mut buffer = Vec::new();
+    write_machine_matches(&mut buffer, &search_matches, &search).unwrap();
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        "2\t19\tA +0 scale mail {dar blademaster}\n"
+    );
+
+    let template = MatchTemplate::parse("{kind}|{runic}|{vault}|{ match }").unwrap();
+    let lines: Vec<String> = search_files(&mut SearchParameters {
+        seed_min: 3,
+        seed_max: 3,
+        ..search
+    })
+    .unwrap()
+    .iter()
+    .map(|m| template.render(m))
+    .collect();
+    assert_eq!(
+        lines,
+        vec![
+            "scale mail||1|A +0 scale mail (vault 1)",
+            "scale mail|vulnerability||A -2 scale mail of vulnerability"
+        ]
+    );
+
+    for invalid in ["{seed", "seed}", "{seeds}", "{}"] {
+        assert!(MatchTemplate::parse(invalid).is_err(), "{}", invalid);
+    }
+}
+
+// Checks that matches are uploaded as an anonymized 'upload' document, to an endpoint
+// given on the command line or in the config file, and that plain http:// is only taken
+// for endpoints on this machine.
+#[test]
+#[cfg(feature = "upload")]
+fn upload_matches() {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use upload::Endpoint;
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/api/seeds", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let (mut head, mut length) = (Vec::new(), 0);
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some((_, value)) = line
+                .split_once(':')
+                .filter(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+            {
+                length = value.trim().parse::<usize>().unwrap();
+            }
+            if line == "\r\n" {
+                break;
+            }
+            head.push(line);
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        stream
+            .write_all(b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+        (head, String::from_utf8(body).unwrap())
+    });
+
+    let args = &["brogue-scanner", "-a", "scale", "-m", "2", "--upload", &url];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+    let endpoint = search.upload.as_ref().unwrap();
+    assert_eq!(endpoint.to_string(), url);
+    assert_eq!(
+        upload::upload_matches(endpoint, &search_matches).unwrap(),
+        2
+    );
+
+    let (head, body) = server.join().unwrap();
+    assert_eq!(head[0], "POST /api/seeds HTTP/1.1\r\n");
+    let document: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(document["type"], "upload");
+    assert!(document.get("query").is_none());
+    assert_eq!(document["seeds"][0]["seed"], 1);
+    assert_eq!(document["seeds"][0]["version"], "CE 1.9");
+    assert_eq!(
+        document["seeds"][0]["share"],
+        "brogue:1/CE+1.9/1/3,1,armor,scale+mail,0,,1"
+    );
+    assert_eq!(document["seeds"][1]["matches"][0]["kind"], "scale mail");
+
+    let dir = TempDir::new("upload");
+    std::fs::write(
+        dir.join("brogue-scanner.toml"),
+        "[upload]\nendpoint = \"https://seeds.example.org:8080/api\"\n",
+    )
+    .unwrap();
+    let args = &[
+        "brogue-scanner",
+        "-F",
+        dir.to_str().unwrap(),
+        "-a",
+        "scale",
+        "--upload",
+    ];
+    let search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    assert_eq!(
+        search.upload.unwrap().to_string(),
+        "https://seeds.example.org:8080/api"
+    );
+    std::fs::write(
+        dir.join("brogue-scanner.toml"),
+        "[upload]\nendpoint = 8080\n",
+    )
+    .unwrap();
+    assert!(SearchParameters::from_matches(new_app().get_matches_from(args)).is_err());
+    std::fs::write(dir.join("brogue-scanner.toml"), "[upload]\n").unwrap();
+    assert!(SearchParameters::from_matches(new_app().get_matches_from(args)).is_err());
+
+    assert_eq!(
+        Endpoint::parse("https://seeds.example.org").unwrap().to_string(),
+        "https://seeds.example.org/"
+    );
+    assert!(
+        Endpoint::parse("http://seeds.example.org/api")
+            .unwrap_err()
+            .to_string()
+            .contains("use https://")
+    );
+    assert!(Endpoint::parse("http://localhost:8000/api").is_ok());
+    assert!(Endpoint::parse("http://[::1]/api").is_ok());
+    assert!(Endpoint::parse("seeds.example.org").is_err());
+    assert!(Endpoint::parse("https://seeds.example.org:port/").is_err());
+    assert!(Endpoint::parse("https:///api").is_err());
+
+    // Refused uploads are errors, with the answer of the database
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = Endpoint::parse(&format!(
+        "http://{}/api/seeds",
+        listener.local_addr().unwrap()
+    ))
+    .unwrap();
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 4096];
+        let _ = stream.read(&mut request).unwrap();
+        stream
+            .write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+    });
+    let error = upload::upload_matches(&endpoint, &search_matches).unwrap_err();
+    assert!(error.to_string().contains("503"));
+    server.join().unwrap();
+}
+
+// Checks the pieces of 'self-update':  version checks, release files, SHA-256 checksums,
+// and replacing the executable.
+#[test]
+#[cfg(feature = "self-update")]
+fn self_update() {
+    use self_update::{asset_name, is_newer, replace_executable, sha256_hex, verify_checksum, Release};
+
+    assert!(is_newer("v0.10.0", "0.9.6").unwrap());
+    assert!(!is_newer("0.9.6", "0.9.6").unwrap());
+    assert!(is_newer("nightly", "0.9.6").is_err());
+
+    assert_eq!(
+        sha256_hex(b""),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        sha256_hex(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    let long = "a".repeat(1000);
+    assert_eq!(
+        sha256_hex(long.as_bytes()),
+        "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+    );
+
+    let checksums = format!(
+        "{}  {}\n{} *other\n",
+        sha256_hex(b"abc"),
+        asset_name(),
+        sha256_hex(b"")
+    );
+    assert!(verify_checksum(b"abc", &checksums, &asset_name()).is_ok());
+    assert!(
+        verify_checksum(b"abd", &checksums, &asset_name())
+            .unwrap_err()
+            .to_string()
+            .contains("checksum mismatch")
+    );
+    assert!(verify_checksum(b"", &checksums, "other").is_ok());
+    assert!(verify_checksum(b"", &checksums, "missing").is_err());
+    assert!(verify_checksum(b"abc", "", &asset_name()).is_err());
+    let upper = format!("{}  {}\n", sha256_hex(b"abc").to_uppercase(), asset_name());
+    assert!(verify_checksum(b"abc", &upper, &asset_name()).is_ok());
+
+    let release: Release = serde_json::from_str(&format!(
+        r#"{{"tag_name": "v1.0.0", "assets": [{{"name": "{}", "browser_download_url": "https://example.org/bin"}}]}}"#,
+        asset_name()
+    ))
+    .unwrap();
+    assert_eq!(
+        release.asset_url(&asset_name()).unwrap(),
+        "https://example.org/bin"
+    );
+    assert!(release.asset_url("SHA256SUMS").is_err());
+
+    let dir = TempDir::new("update");
+    let exe = dir.join("brogue-scanner");
+    std::fs::write(&exe, b"old").unwrap();
+    replace_executable(&exe, b"new").unwrap();
+    assert_eq!(std::fs::read(&exe).unwrap(), b"new");
+    assert!(!dir.join("brogue-scanner.new").exists() && !dir.join("brogue-scanner.old").exists());
+}
+
+// Checks that catalogs in zip archives (stored or deflated, in folders in the archive)
+// are searched without extracting them, whether the archive is in a catalog folder or
+// given itself.
+#[test]
+#[cfg(feature = "zip")]
+fn zip_archives() {
+    use flate2::{write::DeflateEncoder, Compression};
+    use std::io::Write;
+
+    // A zip archive, without checksums (which the scanner doesn't check)
+    let zip = |files: &[(&str, bool)], text: &[u8]| {
+        let (mut data, mut directory) = (Vec::new(), Vec::new());
+        for (name, deflate) in files {
+            let stored = match deflate {
+                true => {
+                    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                    encoder.write_all(text).unwrap();
+                    encoder.finish().unwrap()
+                }
+                false => text.to_vec(),
+            };
+            let method: u16 = if *deflate { 8 } else { 0 };
+            let mut sizes = Vec::new();
+            for value in [0, stored.len() as u32, text.len() as u32] {
+                sizes.extend(value.to_le_bytes());
+            }
+            let offset = data.len() as u32;
+            data.extend(0x0403_4b50u32.to_le_bytes());
+            data.extend([20, 0, 0, 0]);
+            data.extend(method.to_le_bytes());
+            data.extend([0; 4]);
+            data.extend(&sizes);
+            data.extend((name.len() as u16).to_le_bytes());
+            data.extend([0; 2]);
+            data.extend(name.as_bytes());
+            data.extend(&stored);
+
+            directory.extend(0x0201_4b50u32.to_le_bytes());
+            directory.extend([20, 0, 20, 0, 0, 0]);
+            directory.extend(method.to_le_bytes());
+            directory.extend([0; 4]);
+            directory.extend(&sizes);
+            directory.extend((name.len() as u16).to_le_bytes());
+            directory.extend([0; 12]);
+            directory.extend(offset.to_le_bytes());
+            directory.extend(name.as_bytes());
+        }
+        let directory_offset = data.len() as u32;
+        data.extend(&directory);
+        data.extend(0x0605_4b50u32.to_le_bytes());
+        data.extend([0; 4]);
+        data.extend((files.len() as u16).to_le_bytes());
+        data.extend((files.len() as u16).to_le_bytes());
+        data.extend((directory.len() as u32).to_le_bytes());
+        data.extend(directory_offset.to_le_bytes());
+        data.extend([0; 2]);
+        data
+    };
+
+    let dir = TempDir::new("zip");
+    let text = std::fs::read(FILE).unwrap();
+    let files = [
+        ("part1/", false),
+        ("part1/seeds-1-10.csv", true),
+        ("seeds-11-20.csv", false),
+        ("notes.txt", false),
+    ];
+    std::fs::write(dir.join("catalogs.zip"), zip(&files, &text)).unwrap();
+
+    let search = |path: &std::path::Path| {
+        let args = &[
+            "brogue-scanner",
+            "-F",
+            path.to_str().unwrap(),
+            "-U",
+            "-a",
+            "scale",
+            "-m",
+            "all",
+        ];
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        let files = search.file_paths.clone();
+        (files, search_files(&mut search).unwrap().len())
+    };
+    let (files, matches) = search(&dir);
+    assert_eq!(
+        files,
+        vec![
+            dir.join("catalogs.zip/part1/seeds-1-10.csv"),
+            dir.join("catalogs.zip/seeds-11-20.csv")
+        ]
+    );
+    assert_eq!(matches, 14);
+    assert_eq!(search(&dir.join("catalogs.zip")), (files, 14));
+
+    let stamp = manifest::file_stamp(&dir.join("catalogs.zip")).unwrap();
+    assert_eq!(
+        manifest::file_stamp(&dir.join("catalogs.zip/seeds-11-20.csv")).unwrap(),
+        stamp
+    );
+    assert_eq!(
+        file_handling::file_size(&dir.join("catalogs.zip/seeds-11-20.csv")),
+        text.len() as u64
+    );
+
+    // Archives whose central directory doesn't fit the file are rejected, not allocated
+    let mut bad = zip(&[("seeds-1-10.csv", false)], &text);
+    let end = bad.len() - 22;
+    bad[end + 10..end + 12].copy_from_slice(&60000u16.to_le_bytes());
+    bad[end + 12..end + 16].copy_from_slice(&0xFFFF_FF00u32.to_le_bytes());
+    bad[end + 16..end + 20].copy_from_slice(&0x7FFF_FF00u32.to_le_bytes());
+    std::fs::write(dir.join("bad.zip"), &bad).unwrap();
+    let error = archive::ZipArchive::open(&dir.join("bad.zip")).unwrap_err();
+    assert!(format!("{:#}", error).contains("invalid zip archive"));
+    std::fs::write(dir.join("empty.zip"), []).unwrap();
+    assert!(archive::ZipArchive::open(&dir.join("empty.zip")).is_err());
+}
+
+// Checks that `--save-db` appends each run as a query, with matches that read back as found.
+#[test]
+#[cfg(feature = "save-db")]
+fn save_db_matches() {
+    let dir = TempDir::new("save-db");
+    let path = dir.join("results.db");
+
+    let run = |db: &std::path::Path, count: &str| {
+        let args = [
+            "brogue-scanner",
+            "-U",
+            "-F",
+            "./src",
+            "-a",
+            "scale",
+            "-m",
+            count,
+            "--porcelain",
+            "--save-db",
+            db.to_str().unwrap(),
+        ];
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        engine::Engine::new().run(new_app().get_matches_from(&args), &args)
+    };
+    run(&path, "1").unwrap();
+    run(&path, "all").unwrap();
+
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    let queries: Vec<(i64, String)> = conn
+        .prepare("SELECT id, query FROM queries ORDER BY id")
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(queries.len(), 2);
+    assert!(queries[1].1.contains("-a scale -m all"), "{}", queries[1].1);
+
+    type Row = (u32, u8, String, Option<i64>, Option<String>);
+    let rows: Vec<Row> = conn
+        .prepare("SELECT seed, depth, kind, enchantment, runic FROM matches WHERE query_id = ?1 ORDER BY rowid")
+        .unwrap()
+        .query_map([queries[1].0], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from([
+        "brogue-scanner",
+        "-a",
+        "scale",
+        "-m",
+        "all",
+    ]))
+    .unwrap();
+    search.set_file(FILE);
+    let found: Vec<_> = search_files(&mut search)
+        .unwrap()
+        .iter()
+        .map(|m| (m.seed, m.depth, m.object.kind_name(), m.object.enchantment().map(i64::from), m.object.runic_name()))
+        .collect();
+    assert_eq!(rows, found);
+
+    // A database that can't be opened fails the run
+    assert!(run(&dir, "all").is_err());
+}
+
+// Checks that `--grade` ranks seeds by score, then by gold, and tiers them by the thresholds.
+#[test]
+fn grade_ordering() {
+    use search::{rank_seeds, GradeThresholds};
+
+    let graded = |args: &[&str]| {
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(FILE);
+        let search_matches = search_files(&mut search).unwrap();
+        let thresholds = search.grade.unwrap();
+        rank_seeds(&search_matches, &search)
+            .iter()
+            .map(|score| (score.seed, score.score, thresholds.tier(score.score).to_string()))
+            .collect::<Vec<_>>()
+    };
+
+    // Seeds 4 and 2 tie on score, and seed 4 has more gold
+    let seeds: Vec<(u32, i32)> =
+        graded(&["brogue-scanner", "-S", "enchanting", "-m", "all", "--grade"])
+            .into_iter()
+            .map(|(seed, score, _)| (seed, score))
+            .collect();
+    assert_eq!(seeds, vec![(3, 32), (1, 30), (4, 28), (2, 28), (5, 22)]);
+
+    let tiers: Vec<(u32, String)> = graded(&[
+        "brogue-scanner",
+        "-p",
+        "1",
+        "-d",
+        "3",
+        "-m",
+        "all",
+        "--grade",
+        "13,8,6",
+    ])
+    .into_iter()
+    .map(|(seed, _, tier)| (seed, tier))
+    .collect();
+    let expected = [(2, "S"), (4, "A"), (5, "A"), (1, "B"), (3, "C")];
+    assert_eq!(tiers, expected.map(|(seed, tier)| (seed, tier.to_string())));
+
+    for bad in ["12,8", "4,8,12", "12,8,x", ""] {
+        assert!(GradeThresholds::parse(bad).is_err(), "{}", bad);
+    }
+    assert!(
+        SearchParameters::from_matches(new_app().get_matches_from([
+            "brogue-scanner",
+            "--grade",
+            "1,2,3"
+        ]))
+        .is_err()
+    );
+}
+
+// Checks that searches where every object must be in a vault, which skip rows without a
+// vault unread, find what a full scan of every row finds, in files and in catalogs.
+#[test]
+fn vault_only_fast_path() {
+    use catalog::Catalog;
+    use search::search_catalogs;
+
+    let queries: [&[&str]; 3] = [
+        &["-i", "vault", "-m", "all"],
+        &["-e", "vault", "-d", "10", "-m", "all"],
+        &["-a", "vault", "-w", "vault", "-m", "all"],
+    ];
+    let new_search = |query: &[&str]| {
+        let mut args = vec!["brogue-scanner"];
+        args.extend_from_slice(query);
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(FILE);
+        search
+    };
+    let found = |matches: &[SearchMatch]| matches.iter().map(|m| (m.seed, m.depth, m.object.to_string(), m.vault)).collect::<Vec<_>>();
+
+    for query in queries {
+        // Searched alongside a search for objects outside vaults, every row is read
+        let fast = search_files(&mut new_search(query)).unwrap();
+        let full = search_files_batch(&mut [
+            new_search(query),
+            new_search(&["-p", "novault", "-m", "all"]),
+        ])
+        .unwrap();
+        assert!(
+            !fast.is_empty() && fast.iter().all(|m| m.vault.is_some()),
+            "{:?}",
+            query
+        );
+        assert_eq!(found(&fast), found(&full[0]), "{:?}", query);
+
+        let search = new_search(query);
+        let catalog = Catalog::load(FILE, search.format, &search.schema).unwrap();
+        let in_catalog = search_catalogs(&mut [search], &[&catalog]).unwrap();
+        assert_eq!(found(&fast), found(&in_catalog[0]), "{:?}", query);
+    }
+}
+
+// Checks that `--dump-seed` writes every object of the seed in the depth range, whatever the
+// object parameters, and rejects bad seeds and search-only flags.
+#[test]
+fn dump_seed_rows() {
+    let args = [
+        "brogue-scanner",
+        "-a",
+        "scale",
+        "--dump-seed",
+        "3",
+        "--maxdepth",
+        "2",
+        "--ndjson",
+    ];
+    let matches = new_app().get_matches_from(args);
+    assert_eq!(matches.value_of("dump_seed"), Some("3"));
+    let mut search = SearchParameters::from_matches(matches).unwrap();
+    search.set_file(FILE);
+
+    let objects = seed_detail::seed_catalog(&search, 3).unwrap();
+    let mut buffer = Vec::new();
+    write_machine_matches(&mut buffer, &objects, &search).unwrap();
+    let lines: Vec<serde_json::Value> = String::from_utf8(buffer)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    let expected: Vec<u64> = std::fs::read_to_string(FILE)
+        .unwrap()
+        .lines()
+        .map(|line| line.split(',').collect::<Vec<_>>())
+        .filter(|fields| fields[1] == "3" && fields[2].parse::<u8>().unwrap() <= 2)
+        .map(|fields| fields[2].parse().unwrap())
+        .collect();
+    let (summary, rows) = lines.split_last().unwrap();
+    assert!(!expected.is_empty());
+    assert_eq!(
+        rows.iter()
+            .map(|row| row["depth"].as_u64().unwrap())
+            .collect::<Vec<_>>(),
+        expected
+    );
+    assert!(rows.iter().all(|row| row["type"] == "match" && row["seed"] == 3));
+    assert_eq!(summary["matches"].as_u64(), Some(expected.len() as u64));
+
+    let run = |seed: &str| {
+        let args: Vec<String> =
+            ["brogue-scanner", "-U", "-F", "./src", "--dump-seed", seed, "--ndjson"]
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect();
+        engine::Engine::new().run(new_app().get_matches_from(&args), &args)
+    };
+    assert!(run("seed").is_err());
+    assert!(run("4294967296").is_err());
+    assert!(
+        new_app()
+            .get_matches_from_safe(["brogue-scanner", "--dump-seed", "3", "--explain"])
+            .is_err()
+    );
+}
+
+// Checks that `--progress` draws and erases its status line on its own output (standard
+// error), and leaves the matches written to standard output unchanged.
+#[test]
+fn progress_line() {
+    use progress::Progress;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl std::io::Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let search = SearchParameters::from_matches(new_app().get_matches_from([
+        "brogue-scanner",
+        "-a",
+        "scale",
+        "--progress",
+    ]))
+    .unwrap();
+    assert!(search.progress);
+    let drawn = |enabled: bool| {
+        let out = Shared::default();
+        let mut progress = Progress::with_output(enabled, Box::new(out.clone()));
+        progress.start_file(std::path::Path::new("catalogs/seeds-1-10.csv"));
+        progress.update(3, std::slice::from_ref(&search));
+        progress.update(3, std::slice::from_ref(&search));
+        drop(progress);
+        String::from_utf8(out.0.take()).unwrap()
+    };
+    let line = "searching seeds-1-10.csv  seed 3  0 matching seeds";
+    assert_eq!(
+        drawn(true),
+        format!("\r{}\r{}\r", line, " ".repeat(line.len()))
+    );
+    assert_eq!(drawn(false), "");
+
+    let output = |progress: bool| {
+        let mut args = vec!["brogue-scanner", "-a", "scale", "-m", "all", "--ndjson"];
+        if progress {
+            args.push("--progress");
+        }
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(FILE);
+        let search_matches = search_files(&mut search).unwrap();
+        let mut buffer = Vec::new();
+        write_machine_matches(&mut buffer, &search_matches, &search).unwrap();
+        buffer
+    };
+    assert_eq!(output(true), output(false));
+}
+
+// Checks that query files read options and names from TOML over the command line's
+// options, and that files that can't be read as a query are errors naming the file.
+#[test]
+fn query_files() {
+    use search::{base_args, load_query_file};
+
+    let dir = TempDir::new("query-file");
+    let args: Vec<String> = [
+        "brogue-scanner",
+        "-U",
+        "-F",
+        "./src",
+        "--query-file",
+        "a.toml",
+        "--query-file=b.toml",
+        "-m",
+        "all",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    let base = base_args(args);
+    assert_eq!(
+        base,
+        vec!["brogue-scanner", "-U", "-F", "./src", "-m", "all"]
+    );
+
+    let load = |file: &str, text: &str| {
+        let path = dir.join(file);
+        std::fs::write(&path, text).unwrap();
+        load_query_file(&path, &base)
+    };
+
+    let search = load(
+        "scale.toml",
+        "armor = \"scale\"\nlogic = \"1 || 2\"\npotion = [\"life\"]\nutf8 = false\n",
+    )
+    .unwrap();
+    assert_eq!(search.name.as_deref(), Some("scale"));
+    assert_eq!(search.object_params.len(), 2);
+    assert_eq!(search.seed_limit(), None);
+    let search = load(
+        "named.toml",
+        "name = \"melee start\"\nmatches = 2\nweapon = \"sword\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        (search.name.as_deref(), search.search_match_target),
+        (Some("melee start"), 2)
+    );
+    let with_armor = |text: &str| {
+        let path = dir.join("armor.toml");
+        std::fs::write(&path, text).unwrap();
+        let base: Vec<String> = ["brogue-scanner", "-U", "-F", "./src", "-a", "banded"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        load_query_file(&path, &base).unwrap().object_params.len()
+    };
+    assert_eq!(with_armor("armor = \"scale\""), 2);
+    assert_eq!(with_armor(""), 1);
+
+    // The error names the query file, whatever is wrong with it
+    let error = |file: &str, text: &str| format!("{:#}", load(file, text).unwrap_err());
+    for (file, text, reason) in [
+        ("toml.toml", "armor = \"scale", "invalid TOML"),
+        (
+            "name.toml",
+            "name = 3\narmor = \"scale\"",
+            "'name' must be a string",
+        ),
+        (
+            "nested.toml",
+            "query-file = \"other.toml\"",
+            "cannot include other query files",
+        ),
+        (
+            "table.toml",
+            "armor = { kind = \"scale\" }",
+            "invalid option 'armor'",
+        ),
+        ("unknown.toml", "armour = \"scale\"", "armour"),
+        (
+            "bad.toml",
+            "armor = \"scale +x\"\nmatches = -1",
+            "in query file",
+        ),
+    ] {
+        let error = error(file, text);
+        assert!(error.contains(file) && error.contains(reason), "{}", error);
+    }
+    let error = format!(
+        "{:#}",
+        load_query_file(dir.join("missing.toml"), &base).unwrap_err()
+    );
+    assert!(
+        error.contains("could not read query file") && error.contains("missing.toml"),
+        "{}",
+        error
+    );
+
+    // A batch of no searches finds nothing, rather than failing
+    assert!(search_files_batch(&mut []).unwrap().is_empty());
+}
+
+// Checks that machine output stays a valid document when nothing matches, that failures
+// are written as `status` records, and that unsupported versions and mixed modes are
+// rejected.
+#[test]
+fn machine_output_edges() {
+    let written = |mode: &str| {
+        let args = ["brogue-scanner", "-a", "scale", "--minseed", "100", mode];
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(FILE);
+        let search_matches = search_files(&mut search).unwrap();
+        assert!(search_matches.is_empty());
+        let mut buffer = Vec::new();
+        write_machine_matches(&mut buffer, &search_matches, &search).unwrap();
+        String::from_utf8(buffer).unwrap()
+    };
+
+    let json: serde_json::Value = serde_json::from_str(&written("--json")).unwrap();
+    assert_eq!(json["matches"], serde_json::json!([]));
+    assert_eq!(
+        (
+            json["summary"]["seeds"].as_u64(),
+            json["summary"]["status"].as_str()
+        ),
+        (Some(0), Some("no_matches"))
+    );
+    let ndjson = written("--ndjson");
+    let summary: serde_json::Value = serde_json::from_str(&ndjson).unwrap();
+    assert_eq!(
+        (ndjson.lines().count(), summary["type"].as_str()),
+        (1, Some("summary"))
+    );
+    assert_eq!(written("--porcelain"), "");
+
+    let mut buffer = Vec::new();
+    search::write_status(
+        &mut buffer,
+        search::OutputMode::Ndjson,
+        status::Status::NoFiles,
+        "no catalog files".into(),
+    )
+    .unwrap();
+    let record: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+    assert_eq!(
+        (record["type"].as_str(), record["status"].as_str()),
+        (Some("status"), Some("no_files"))
+    );
+
+    let params = |args: &[&str]| new_app().get_matches_from_safe(args).map_err(anyhow::Error::from).and_then(SearchParameters::from_matches);
+    assert!(params(&["brogue-scanner", "--json", "--format-version", "2"]).is_err());
+    assert!(params(&["brogue-scanner", "--json", "--format-version", "one"]).is_err());
+    assert!(params(&["brogue-scanner", "--json", "--ndjson"]).is_err());
+    assert!(params(&["brogue-scanner", "--ndjson", "--porcelain"]).is_err());
+}
+
+// Checks that searches sharing a scan each stop at their own match limit, and that a bad
+// column in any one of them ends the shared scan with its error.
+#[test]
+fn shared_scan_limits() {
+    let new_search = |args: &[&str]| {
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(FILE);
+        search
+    };
+    let seeds = |matches: &[SearchMatch]| {
+        let mut seeds: Vec<u32> = matches.iter().map(|m| m.seed).collect();
+        seeds.dedup();
+        seeds
+    };
+
+    let mut searches = [
+        new_search(&["brogue-scanner", "-a", "scale", "-m", "1"]),
+        new_search(&["brogue-scanner", "-a", "scale", "-m", "all"]),
+        new_search(&["brogue-scanner", "-a", "scale", "--minseed", "100"]),
+    ];
+    let results = search_files_batch(&mut searches).unwrap();
+    assert_eq!(seeds(&results[0]), vec![1]);
+    assert_eq!(seeds(&results[1]), vec![1, 2, 3, 4]);
+    assert!(results[2].is_empty());
+
+    let mut searches = [
+        new_search(&["brogue-scanner", "-a", "scale"]),
+        new_search(&["brogue-scanner", "-a", "scale", "--where", "col:nonsense=1"]),
+    ];
+    let error = format!("{:#}", search_files_batch(&mut searches).unwrap_err());
+    assert!(
+        error.contains("filter column 'nonsense' not found"),
+        "{}",
+        error
+    );
+}
+
+// Checks that `--where` filters without an operator or column are rejected, that columns
+// that are missing, ambiguous, or out of range end the search, and that text compares as
+// text.
+#[test]
+fn where_filter_errors() {
+    for bad in ["col:vault", "col:=1", "  "] {
+        let args = ["brogue-scanner", "-a", "scale", "--where", bad];
+        assert!(
+            SearchParameters::from_matches(new_app().get_matches_from(args)).is_err(),
+            "{}",
+            bad
+        );
+    }
+
+    let search = |filter: &str| {
+        let args = ["brogue-scanner", "-a", "scale", "-m", "all", "--where", filter];
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(FILE);
+        search_files(&mut search).map_err(|e| format!("{:#}", e))
+    };
+    assert!(
+        search("col:_number=1")
+            .unwrap_err()
+            .contains("matches more than one header")
+    );
+    assert!(
+        search("col:99=1")
+            .unwrap_err()
+            .contains("out of range (catalog has 13 columns)")
+    );
+    assert!(search("col:colour=red").unwrap_err().contains("'colour' not found"));
+    let carried = search("col:carried~dar").unwrap();
+    assert!(!carried.is_empty() && carried.iter().all(|m| m.carried_by.is_some()));
+    assert!(search("col:seed>=3").unwrap().iter().all(|m| m.seed >= 3));
+}
+
+// Checks that stats reject bad categories and bucket sizes, and stay finite with no
+// matching objects or no seeds at all.
+#[test]
+fn stats_edges() {
+    let stats = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner"];
+        all.extend_from_slice(args);
+        let matches = new_app().get_matches_from(all);
+        let params =
+            stats::StatsParameters::from_matches(matches.subcommand_matches("stats").unwrap())?;
+        let mut search = SearchParameters::from_matches(matches.clone())?;
+        search.set_file(FILE);
+        stats::collect_stats(&search, &params)
+    };
+
+    assert!(stats(&["stats", "dragon"]).is_err());
+    for bucket in ["0", "27", "x"] {
+        assert!(
+            stats(&["stats", "potion", "--bucket", bucket]).is_err(),
+            "{}",
+            bucket
+        );
+    }
+
+    let none = stats(&["stats", "potion", "nonexistent"]).unwrap();
+    assert_eq!(
+        (
+            none.seeds,
+            none.objects,
+            none.seeds_with,
+            none.objects_per_seed
+        ),
+        (5, 0, 0, 0.0)
+    );
+    let empty = stats(&["--minseed", "100", "stats", "potion"]).unwrap();
+    assert_eq!((empty.seeds, empty.objects_per_seed), (0, 0.0));
+    assert!(empty.buckets.iter().all(|b| b.objects_per_seed == 0.0));
+    let single = stats(&[
+        "--mindepth",
+        "3",
+        "--maxdepth",
+        "4",
+        "stats",
+        "potion",
+        "--bucket",
+        "26",
+    ])
+    .unwrap();
+    assert_eq!(
+        single
+            .buckets
+            .iter()
+            .map(|b| (b.depth_min, b.depth_max))
+            .collect::<Vec<_>>(),
+        vec![(3, 4)]
+    );
+
+    let matches = new_app().get_matches_from(["brogue-scanner", "stats", "potion"]);
+    let params =
+        stats::StatsParameters::from_matches(matches.subcommand_matches("stats").unwrap()).unwrap();
+    let mut search = SearchParameters::from_matches(matches.clone()).unwrap();
+    search.file_paths.clear();
+    assert_eq!(
+        status::Status::of_error(&stats::collect_stats(&search, &params).unwrap_err()),
+        status::Status::NoFiles
+    );
+}
+
+// Checks that a rarity table is cached and read back whole, that unreadable, invalid, or
+// outdated caches are errors, and that changed catalogs make the cache stale.
+#[test]
+fn rarity_cache() {
+    use rarity::RarityTable;
+
+    let dir = TempDir::new("rarity");
+    let catalog = dir.join("seeds-1-5.csv");
+    std::fs::copy(FILE, &catalog).unwrap();
+    let cache = dir.join("rarity.json");
+
+    let mut search = SearchParameters {
+        file_paths: vec![catalog.clone()],
+        ..Default::default()
+    };
+    let table = RarityTable::build(&search).unwrap();
+    table.save(&cache).unwrap();
+    let loaded = RarityTable::load(&cache).unwrap();
+    assert_eq!(loaded.seeds, table.seeds);
+    assert_eq!(
+        loaded
+            .estimate(objects::Category::Potion, Some("life"), 10)
+            .map(|e| e.mean),
+        table
+            .estimate(objects::Category::Potion, Some("life"), 10)
+            .map(|e| e.mean)
+    );
+    assert!(loaded.stale_files(&search.file_paths).is_empty());
+
+    let text = std::fs::read_to_string(FILE).unwrap();
+    std::fs::write(&catalog, &text[..text.len() / 2]).unwrap();
+    assert_eq!(
+        loaded.stale_files(&search.file_paths),
+        vec!["seeds-1-5.csv"]
+    );
+
+    let error = |path: &std::path::Path| format!("{:#}", RarityTable::load(path).unwrap_err());
+    assert!(error(&dir.join("missing.json")).contains("could not read rarity table"));
+    std::fs::write(&cache, "{\"seeds\": 5").unwrap();
+    assert!(error(&cache).contains("invalid rarity table"));
+    let mut outdated: serde_json::Value = serde_json::to_value(&table).unwrap();
+    outdated["cache_version"] = serde_json::json!(0);
+    std::fs::write(&cache, outdated.to_string()).unwrap();
+    assert!(error(&cache).contains("from another version"));
+
+    // Tables without seeds estimate nothing, and tables need catalog files
+    search.seed_min = 100;
+    let empty = RarityTable::build(&search).unwrap();
+    assert!(empty.estimate(objects::Category::Potion, None, 26).is_none());
+    search.file_paths.clear();
+    assert!(RarityTable::build(&search).is_err());
+}
+
+// Checks that query chances multiply over object parameters, are 0 for kinds the sample
+// never had, and stay within 0 to 1 for huge counts and means (e.g. gold).
+#[test]
+fn selectivity_edges() {
+    use rarity::{query_chance, RarityTable};
+    use search::CountType;
+
+    let table = RarityTable::build(&SearchParameters {
+        file_paths: vec![FILE.into()],
+        ..Default::default()
+    })
+    .unwrap();
+    let chance = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner"];
+        all.extend_from_slice(args);
+        query_chance(
+            &table,
+            &SearchParameters::from_matches(new_app().get_matches_from(all)).unwrap(),
+        )
+    };
+
+    assert_eq!(chance(&[]), Some(1.0));
+    let (scale, life) = (
+        chance(&["-a", "scale"]).unwrap(),
+        chance(&["-p", "life"]).unwrap(),
+    );
+    assert!((chance(&["-a", "scale", "-p", "life"]).unwrap() - scale * life).abs() < 1e-12);
+    assert!(chance(&["-a", "scale", "-p", "life", "1000"]).unwrap() < 1e-6);
+    assert_eq!(chance(&["-c", "levitation"]), Some(0.0));
+
+    let gold = table.estimate(objects::Category::Gold, None, 26).unwrap();
+    for count in [0, 1, 5000, 1_000_000] {
+        for count_type in [CountType::AtLeast, CountType::LessThan, CountType::EqualTo] {
+            let chance = gold.count_chance(count_type, count);
+            assert!(
+                (0.0..=1.0).contains(&chance),
+                "{:?} {} {}",
+                count_type,
+                count,
+                chance
+            );
+        }
+    }
+
+    let none = rarity::Estimate {
+        chance: 0.0,
+        mean: 0.0,
+    };
+    assert_eq!(none.count_chance(CountType::AtLeast, 1), 0.0);
+    assert_eq!(none.count_chance(CountType::LessThan, 1), 1.0);
+    let empty = RarityTable::build(&SearchParameters {
+        file_paths: vec![FILE.into()],
+        seed_min: 100,
+        ..Default::default()
+    })
+    .unwrap();
+    assert!(query_chance(&empty, &SearchParameters::default()).is_some());
+    assert!(
+        query_chance(
+            &empty,
+            &SearchParameters::from_matches(new_app().get_matches_from([
+                "brogue-scanner",
+                "-a",
+                "scale"
+            ]))
+            .unwrap()
+        )
+        .is_none()
+    );
+}
+
+// Checks that `--approx` rejects percents outside 0 to 100, samples at least one file and
+// scales up by the files left out, and gives finite rates with no matches or no seeds.
+#[test]
+fn approx_edges() {
+    let params = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "-a", "scale"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+    };
+    for percent in ["0", "100.5", "x", "NaN"] {
+        assert!(params(&["--approx", percent]).is_err(), "{}", percent);
+    }
+    assert_eq!(params(&["--approx"]).unwrap().approx, Some(1.0));
+
+    let mut search = params(&["--approx", "1"]).unwrap();
+    search.file_paths = vec![FILE.into(), FILE.into()];
+    let approx = &approx::approximate(std::slice::from_mut(&mut search), 1.0).unwrap()[0];
+    assert_eq!(
+        (
+            approx.sampled_files,
+            approx.files,
+            approx.sampled_seeds,
+            approx.library_seeds
+        ),
+        (1, 2, 5, 10)
+    );
+    assert!((approx.estimate - 8.0).abs() < 1e-9);
+
+    for (args, seeds) in [(&["-p", "life", "100"][..], 5), (&["--minseed", "100"][..], 0)] {
+        let mut search = params(args).unwrap();
+        search.set_file(FILE);
+        let approx = &approx::approximate(std::slice::from_mut(&mut search), 100.0).unwrap()[0];
+        assert_eq!(
+            (
+                approx.sampled_seeds,
+                approx.matching_seeds,
+                approx.rate,
+                approx.rate_low
+            ),
+            (seeds, 0, 0.0, 0.0)
+        );
+        assert!(
+            approx.rate_high > 0.0 && approx.rate_high <= 1.0 && approx.estimate_high.is_finite()
+        );
+    }
+
+    let mut search = params(&[]).unwrap();
+    search.file_paths.clear();
+    assert!(approx::approximate(std::slice::from_mut(&mut search), 1.0).is_err());
+    assert!(approx::approximate(&mut [], 1.0).unwrap().is_empty());
+}
+
+// Checks that `--empower` rejects repeated or unknown terms, defaults to 1 charge by the
+// deepest depth searched, and never looks deeper than `--maxdepth`.
+#[test]
+fn empower_terms() {
+    let empower = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "--empower"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+            .map(|search| search.empower.map(|empower| (empower.charges, empower.depth)))
+    };
+
+    for bad in [&["d3", "d4"][..], &["2", "3"], &["<2"], &["=2"], &["wand"]] {
+        assert!(empower(bad).is_err(), "{:?}", bad);
+    }
+    assert_eq!(empower(&["--maxdepth", "9"]).unwrap(), Some((1, 9)));
+    assert_eq!(
+        empower(&["d30", "--maxdepth", "12"]).unwrap(),
+        Some((1, 12))
+    );
+    assert_eq!(empower(&["d5", "3"]).unwrap(), Some((3, 5)));
+}
+
+// Checks that `--same-depth` groups reject repeated, unknown, or too few object numbers,
+// and that 'all' matches the same seeds as listing every object.
+#[test]
+fn same_depth_groups() {
+    let same_depth = |group: &str| {
+        let args = [
+            "brogue-scanner",
+            "-p",
+            "life",
+            "-a",
+            "scale",
+            "-m",
+            "10",
+            "--same-depth",
+            group,
+        ];
+        SearchParameters::from_matches(new_app().get_matches_from(args))
+            .map(|search| search.same_depth)
+    };
+
+    for bad in ["1,1", "0,1", "1,3", "1,x", "1", "", "1,,2"] {
+        assert!(same_depth(bad).is_err(), "{:?}", bad);
+    }
+    assert_eq!(same_depth("2, 1").unwrap(), vec![vec![0, 1]]);
+    assert_eq!(same_depth("all").unwrap(), vec![vec![0, 1]]);
+
+    let search_seeds = |group: &str| -> Vec<u32> {
+        let args = [
+            "brogue-scanner",
+            "-p",
+            "life",
+            "-a",
+            "scale",
+            "-m",
+            "10",
+            "--same-depth",
+            group,
+        ];
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(FILE);
+
+        let mut seeds: Vec<u32> =
+            search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+        seeds.dedup();
+        seeds
+    };
+
+    assert_eq!(search_seeds("all"), search_seeds("1,2"));
+}
+
+// Checks that `--within` rejects malformed pairs, a repeated object, and bad level counts,
+// and that a pair matches the same seeds in either order.
+#[test]
+fn within_pairs() {
+    let search_seeds = |within: &str| -> anyhow::Result<Vec<u32>> {
+        let args = [
+            "brogue-scanner",
+            "-p",
+            "life",
+            "-a",
+            "scale",
+            "-m",
+            "10",
+            "--within",
+            within,
+        ];
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args))?;
+        search.set_file(FILE);
+
+        let mut seeds: Vec<u32> = search_files(&mut search)?.iter().map(|m| m.seed).collect();
+        seeds.dedup();
+        Ok(seeds)
+    };
+
+    for bad in [
+        "1,2", "1,2,3,4", "", "1,1,2", "0,2,1", "1,3,1", "x,2,1", "1,2,x", "1,2,256",
+    ] {
+        assert!(search_seeds(bad).is_err(), "{:?}", bad);
+    }
+    assert_eq!(
+        search_seeds("2,1,1").unwrap(),
+        search_seeds("1,2,1").unwrap()
+    );
+    assert_eq!(search_seeds(" 1 , 2 , 0 ").unwrap(), vec![2, 3]);
+}
+
+// Checks that `--no-malevolent` rejects a zero or non-numeric depth, defaults to the
+// maximum depth, and never looks deeper than it.
+#[test]
+fn no_malevolent_depths() {
+    let no_malevolent = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "-p", "life", "--maxdepth", "8", "--no-malevolent"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+            .map(|search| search.no_malevolent)
+    };
+
+    for bad in ["0", "x", "2.5", "256"] {
+        assert!(no_malevolent(&[bad]).is_err(), "{:?}", bad);
+    }
+    assert_eq!(no_malevolent(&[]).unwrap(), Some(8));
+    assert_eq!(no_malevolent(&["26"]).unwrap(), Some(8));
+    assert_eq!(no_malevolent(&["3"]).unwrap(), Some(3));
+
+    let search_seeds = |depth: &str| -> Vec<u32> {
+        let args = ["brogue-scanner", "-p", "life", "-m", "10", "--no-malevolent", depth];
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(FILE);
+
+        let mut seeds: Vec<u32> =
+            search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+        seeds.dedup();
+        seeds
+    };
+
+    assert_eq!(search_seeds("1"), vec![2, 4]);
+    assert!(search_seeds("3").is_empty());
+}
+
+// Checks that `--exclude-category` rejects unknown categories, ignores case and empty
+// terms, excludes a whole category without a kind, and excludes nothing for an unknown kind.
+#[test]
+fn exclude_category_terms() {
+    let search_seeds = |exclude: &str| -> anyhow::Result<Vec<u32>> {
+        let args = [
+            "brogue-scanner",
+            "-S",
+            "enchanting",
+            "-d",
+            "3",
+            "-m",
+            "10",
+            "--exclude-category",
+            exclude,
+        ];
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args))?;
+        search.set_file(FILE);
+
+        let mut seeds: Vec<u32> = search_files(&mut search)?.iter().map(|m| m.seed).collect();
+        seeds.dedup();
+        Ok(seeds)
+    };
+
+    for bad in ["wizard", "scroll:aggravate,wand:x,tome", ":aggravate"] {
+        assert!(search_seeds(bad).is_err(), "{:?}", bad);
+    }
+    assert_eq!(search_seeds(",").unwrap(), vec![1, 2, 3, 4, 5]);
+    assert_eq!(
+        search_seeds("scroll:nonsense").unwrap(),
+        vec![1, 2, 3, 4, 5]
+    );
+    assert_eq!(search_seeds(" Scroll:Aggravate ,").unwrap(), vec![1, 5]);
+    assert_eq!(search_seeds("ring").unwrap(), vec![3]);
+    assert!(search_seeds("scroll:").unwrap().is_empty());
+}
+
+// Checks that `--require-key` keeps objects outside vaults, only drops vault objects, and
+// keeps each vault object only when a key on its seed and depth opens its vault.
+#[test]
+fn require_key_vaults() {
+    let search = |args: &[&str]| -> Vec<SearchMatch> {
+        let mut all = vec!["brogue-scanner", "-m", "10"];
+        all.extend_from_slice(args);
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(all)).unwrap();
+        search.set_file(FILE);
+        search_files(&mut search).unwrap()
+    };
+    let keys: Vec<(u32, u8, u8)> = std::fs::read_to_string(FILE)
+        .unwrap()
+        .lines()
+        .map(|line| line.split(',').collect::<Vec<_>>())
+        .filter(|row| row[4] == "key" && !row[9].is_empty())
+        .map(|row| (row[1].parse().unwrap(), row[2].parse().unwrap(), row[9].parse().unwrap()))
+        .collect();
+
+    let all = search(&["-i", "vault"]);
+    let keyed = search(&["-i", "vault", "--require-key"]);
+    assert!(keyed.len() < all.len());
+    for m in keyed.iter() {
+        assert!(
+            all.iter()
+                .any(|other| other.to_string() == m.to_string() && other.seed == m.seed)
+        );
+        assert!(keys.contains(&(m.seed, m.depth, m.vault.unwrap())), "{}", m);
+    }
+
+    let outside = |matches: Vec<SearchMatch>| -> Vec<String> {
+        matches
+            .iter()
+            .filter(|m| m.vault.is_none())
+            .map(|m| format!("{} {}", m.seed, m))
+            .collect()
+    };
+    assert_eq!(
+        outside(search(&["-p", "life", "--require-key"])),
+        outside(search(&["-p", "life"]))
+    );
+}
+
+// Checks that an empty catalog directory adds no worker, a missing one is an error, files
+// go to the first directory holding them, and `--stream` rejects several directories.
+#[test]
+fn parallel_dir_groups() {
+    let dir = TempDir::new("dirs");
+    let empty = dir.to_str().unwrap();
+    let missing = dir.join("missing");
+
+    let params = |dirs: &[&str], extra: &[&str]| {
+        let mut args = vec!["brogue-scanner", "-U", "-a", "scale", "-m", "10"];
+        for path in dirs {
+            args.extend_from_slice(&["-F", path]);
+        }
+        args.extend_from_slice(extra);
+        SearchParameters::from_matches(new_app().get_matches_from(args))
+    };
+    let seeds = |search: &mut SearchParameters| -> Vec<u32> {
+        search_files(search).unwrap().iter().map(|m| m.seed).collect()
+    };
+
+    let mut single = params(&["./src"], &[]).unwrap();
+    let mut with_empty = params(&["./src", empty], &[]).unwrap();
+    assert_eq!(with_empty.file_groups(&with_empty.file_paths).len(), 1);
+    assert_eq!(with_empty.dir_groups(&with_empty.file_paths).len(), 3);
+    assert_eq!(seeds(&mut with_empty), seeds(&mut single));
+
+    let mut only_empty = params(&[empty], &[]).unwrap();
+    assert!(only_empty.file_groups(&only_empty.file_paths).is_empty());
+    assert!(search_files(&mut only_empty).is_err());
+
+    assert!(params(&["./src", missing.to_str().unwrap()], &[]).is_err());
+    assert!(params(&["./src", empty], &["--stream"]).is_err());
+
+    let search = SearchParameters {
+        file_dirs: vec!["src".into(), "src/nested".into()],
+        ..Default::default()
+    };
+    let paths = vec!["src/nested/a.csv".into(), "other/b.csv".into()];
+    assert_eq!(
+        search.dir_groups(&paths),
+        vec![vec![paths[0].clone()], vec![], vec![paths[1].clone()]]
+    );
+}
+
+// Checks that `--ordered` changes nothing for a single folder, keeps each seed's matches
+// from every folder together (first folder first), and conflicts with `--stream`.
+#[test]
+fn ordered_merge_edges() {
+    let search = |dirs: &[&str], extra: &[&str]| -> Vec<(u32, String)> {
+        let mut args = vec!["brogue-scanner", "-U", "-a", "scale", "-m", "10"];
+        for path in dirs {
+            args.extend_from_slice(&["-F", path]);
+        }
+        args.extend_from_slice(extra);
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search_files(&mut search)
+            .unwrap()
+            .iter()
+            .map(|m| (m.seed, m.to_string()))
+            .collect()
+    };
+
+    let single = search(&["./src"], &[]);
+    assert_eq!(search(&["./src"], &["--ordered"]), single);
+
+    let merged = search(&["./src", "src"], &["--ordered"]);
+    let mut expected = Vec::new();
+    for seed in [1, 2, 3, 4] {
+        let seed_matches: Vec<_> = single.iter().filter(|(s, _)| *s == seed).cloned().collect();
+        expected.extend(seed_matches.clone());
+        expected.extend(seed_matches);
+    }
+    assert_eq!(merged, expected);
+
+    assert!(
+        new_app()
+            .get_matches_from_safe(["brogue-scanner", "--ordered", "--stream"])
+            .is_err()
+    );
+}
+
+// Checks that daemon request lines split blank lines to nothing, keep empty and adjacent
+// quoted words, and that a request's unknown or conflicting options are an error (not an
+// exit).
+#[test]
+#[cfg(feature = "server")]
+fn daemon_request_edges() {
+    assert!(daemon::split_line("").unwrap().is_empty());
+    assert!(daemon::split_line(" \t ").unwrap().is_empty());
+    assert_eq!(
+        daemon::split_line(r#"-a "" scale"#).unwrap(),
+        vec!["-a", "", "scale"]
+    );
+    assert_eq!(daemon::split_line(r#"ab"c d"'e'"#).unwrap(), vec!["abc de"]);
+    assert_eq!(
+        daemon::split_line(r#"'say "hi"'"#).unwrap(),
+        vec![r#"say "hi""#]
+    );
+    assert!(daemon::split_line(r#"-a "scale"#).is_err());
+
+    let everything = ["brogue-scanner", "-F", "catalogs"].iter().map(|arg| arg.to_string());
+    assert_eq!(daemon::daemon_base_args(everything).len(), 3);
+    assert!(daemon::daemon_base_args(Vec::new()).is_empty());
+
+    let base_args: Vec<String> = ["brogue-scanner", "-U", "-F", "./src"]
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+    let request = |line: &str| daemon::run_args(&base_args, &daemon::split_line(line).unwrap());
+
+    let (_, args) = request("-a scale -m 2").unwrap();
+    assert_eq!(args[..4], base_args[..]);
+    assert_eq!(args.len(), 8);
+    assert!(request("--nonsense").is_err());
+    assert!(request("--json --ndjson").is_err());
+    assert!(request("--help").is_err());
+}
+
+// Checks that IPC frames reject oversized lengths and truncated input, and that a served
+// connection answers bad JSON, unknown options, and unknown cancels without stopping.
+#[test]
+#[cfg(all(unix, feature = "server"))]
+fn ipc_frame_errors() {
+    let frame = |bytes: &[u8]| ipc::read_frame(&mut std::io::Cursor::new(bytes.to_vec()));
+
+    assert!(frame(&[]).unwrap().is_none());
+    assert!(frame(&[0, 0]).is_err());
+    assert!(frame(&[0, 0, 0, 5, b'{']).is_err());
+    assert!(frame(&u32::MAX.to_be_bytes()).is_err());
+    assert_eq!(frame(&[0, 0, 0, 0]).unwrap(), Some(Vec::new()));
+
+    let serve = |input: Vec<u8>| -> Vec<serde_json::Value> {
+        let base_args: Vec<String> = ["brogue-scanner", "-U", "-F", "./src"]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        let mut output = Vec::new();
+        ipc::serve(
+            &mut engine::Engine::new(),
+            &base_args,
+            std::io::Cursor::new(input),
+            &mut output,
+        )
+        .unwrap();
+
+        let mut reader = std::io::Cursor::new(output);
+        let mut frames = Vec::new();
+        while let Some(frame) = ipc::read_frame(&mut reader).unwrap() {
+            frames.push(serde_json::from_slice(&frame).unwrap());
+        }
+        frames
+    };
+
+    let mut input = Vec::new();
+    input.extend_from_slice(&[0, 0, 0, 3]);
+    input.extend_from_slice(b"{x}");
+    ipc::write_frame(&mut input, &serde_json::json!({"type": "cancel", "id": 9})).unwrap();
+    ipc::write_frame(&mut input, &serde_json::json!({"type": "query", "id": 1})).unwrap();
+    ipc::write_frame(
+        &mut input,
+        &serde_json::json!({"type": "query", "id": 2, "args": ["--nonsense"]}),
+    )
+    .unwrap();
+    ipc::write_frame(
+        &mut input,
+        &serde_json::json!({"type": "query", "id": 3, "args": ["-a", "scale", "-m", "1"]}),
+    )
+    .unwrap();
+
+    let frames = serve(input);
+    let types: Vec<(&str, serde_json::Value)> = frames
+        .iter()
+        .map(|f| (f["type"].as_str().unwrap(), f["id"].clone()))
+        .collect();
+
+    assert_eq!(
+        types.iter().filter(|(t, id)| *t == "error" && id.is_null()).count(),
+        2
+    );
+    assert!(types.contains(&("error", serde_json::json!(2))));
+    assert_eq!(types.last(), Some(&("done", serde_json::json!(3))));
+    assert!(!types.iter().any(|(t, _)| *t == "cancelled"));
+
+    let mut input = Vec::new();
+    ipc::write_frame(
+        &mut input,
+        &serde_json::json!({"type": "query", "id": 4, "args": ["-a", "scale"]}),
+    )
+    .unwrap();
+    input.extend_from_slice(&u32::MAX.to_be_bytes());
+    // The bad frame is answered whenever it's read, among the streamed matches
+    let frames = serve(input);
+    assert!(frames.iter().any(|f| f["type"] == "error" && f["id"].is_null()));
+    assert_eq!(frames.last().unwrap()["type"], "done");
+}
+
+// Checks that index updates drop removed files, keep touched but unchanged files, and
+// rebuild an unreadable index, and that bad, truncated, or outdated indexes fail to load.
+#[test]
+fn catalog_index_updates() {
+    let dir = TempDir::new("reindex");
+    let index_path = dir.join(index::INDEX_FILE);
+
+    let update = || {
+        let args = [
+            "brogue-scanner",
+            "-U",
+            "--filepath",
+            dir.to_str().unwrap(),
+            "--minseed",
+            "6",
+            "-a",
+            "scale",
+        ];
+        let search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        let update = index::update_indexes(&search).unwrap().remove(0).1;
+        (
+            update.added,
+            update.rescanned,
+            update.removed,
+            update.unchanged,
+        )
+    };
+
+    assert_eq!(update(), (0, 0, 0, 0));
+    assert!(index::CatalogIndex::load(&index_path).unwrap().entries.is_empty());
+
+    std::fs::copy(FILE, dir.join("a.csv")).unwrap();
+    std::fs::copy(FILE, dir.join("b.csv")).unwrap();
+    assert_eq!(update(), (2, 0, 0, 0));
+
+    std::fs::remove_file(dir.join("b.csv")).unwrap();
+    let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+    std::fs::File::options()
+        .write(true)
+        .open(dir.join("a.csv"))
+        .unwrap()
+        .set_modified(later)
+        .unwrap();
+    assert_eq!(update(), (0, 0, 1, 1));
+    assert_eq!(update(), (0, 0, 0, 1));
+
+    let bytes = std::fs::read(&index_path).unwrap();
+    let mut outdated = bytes.clone();
+    outdated[4] = outdated[4].wrapping_add(1);
+    for bad in [
+        b"XXXX".to_vec(),
+        Vec::new(),
+        bytes[..bytes.len() - 1].to_vec(),
+        outdated,
+    ] {
+        std::fs::write(&index_path, &bad).unwrap();
+        assert!(index::CatalogIndex::load(&index_path).is_err());
+    }
+
+    let search = SearchParameters::from_matches(new_app().get_matches_from([
+        "brogue-scanner",
+        "-U",
+        "--filepath",
+        dir.to_str().unwrap(),
+        "--minseed",
+        "6",
+    ]))
+    .unwrap();
+    let indexes = index::CatalogIndexes::load(&search.file_dirs);
+    assert_eq!(
+        indexes.skipped_seeds(&search.file_paths[0], std::slice::from_ref(&search)),
+        None
+    );
+    assert_eq!(update(), (1, 0, 0, 0));
+}
+
+// Checks that Bloom filters never skip files for short terms, skip a runic search only if
+// no alternative can be in the file, and skip on any required term of a parameter.
+#[test]
+fn kind_filter_edges() {
+    let empty = index::KindFilter::default();
+    assert!(empty.may_contain(0, ""));
+    assert!(empty.may_contain(0, "ab"));
+    assert!(!empty.may_contain(0, "abc"));
+
+    let dir = TempDir::new("bloom");
+    std::fs::copy(FILE, dir.join("test_data.csv")).unwrap();
+
+    let search_for = |terms: &[&str]| {
+        let mut args = vec!["brogue-scanner", "-U", "--filepath", dir.to_str().unwrap()];
+        args.extend_from_slice(terms);
+        SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap()
+    };
+    index::update_indexes(&search_for(&["-a", "scale"])).unwrap();
+
+    let indexes = index::CatalogIndexes::load(std::slice::from_ref(&*dir));
+    let skipped = |terms: &[&str]| {
+        let search = search_for(terms);
+        indexes.skipped_seeds(&search.file_paths[0], std::slice::from_ref(&search))
+    };
+
+    assert_eq!(skipped(&["-w", "qu"]), None);
+    assert_eq!(skipped(&["-w", "quietus|slaying"]), None);
+    assert_eq!(skipped(&["-w", "quietus|speed"]), Some(vec![5]));
+    assert_eq!(skipped(&["-w", "mace"]), None);
+    assert_eq!(skipped(&["-w", "mace", "quietus"]), Some(vec![5]));
+    assert_eq!(skipped(&["-w", "quietus", "-a", "scale"]), Some(vec![5]));
+}
+
+// Checks that in-memory catalogs rebuild each Record as read, hold a header-only file as
+// empty, reject unreadable values, and are reloaded by a resident engine when changed.
+#[test]
+#[cfg(feature = "server")]
+fn catalog_columns() {
+    use catalog::Catalog;
+    use search::CancelToken;
+
+    let dir = TempDir::new("columns-2");
+    let path = dir.join("test_data.csv");
+    let text = std::fs::read_to_string(FILE).unwrap();
+    let (header, rows) = text.split_once('\n').unwrap();
+
+    let search = SearchParameters::from_matches(new_app().get_matches_from([
+        "brogue-scanner",
+        "-U",
+        "-F",
+        "./src",
+    ]))
+    .unwrap();
+    let load = |contents: &str| {
+        std::fs::write(&path, contents).unwrap();
+        Catalog::load(&path, search.format, &search.schema)
+    };
+
+    let catalog = load(&text).unwrap();
+    assert_eq!(catalog.len(), rows.lines().count());
+    for (row, line) in rows.lines().enumerate() {
+        assert_eq!(
+            catalog.record(row).iter().collect::<Vec<_>>().join(","),
+            line
+        );
+    }
+    assert_eq!(load(&format!("{}\n", header)).unwrap().len(), 0);
+
+    for bad in [
+        "CE 1.9,x,1,1,potion,life,,,,,,,",
+        "CE 1.9,1,1,1,wizard,life,,,,,,,",
+        "CE 1.9,1,1,1,weapon,mace,200,,,,,,",
+        "CE 1.9,1,300,1,potion,life,,,,,,,",
+    ] {
+        assert!(load(&format!("{}\n{}\n", header, bad)).is_err(), "{}", bad);
+    }
+    assert!(Catalog::load(dir.join("missing.csv"), search.format, &search.schema).is_err());
+
+    load(&text).unwrap();
+    let args: Vec<String> = [
+        "brogue-scanner",
+        "-U",
+        "-F",
+        dir.to_str().unwrap(),
+        "-a",
+        "scale",
+        "-m",
+        "10",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    let mut resident = engine::Engine::resident();
+    let mut seeds = || -> Vec<u32> {
+        let matches = new_app().get_matches_from(args.iter());
+        let results = resident.search(matches, &args, &CancelToken::new(), None).unwrap();
+        results[0].1.iter().map(|m| m.seed).collect()
+    };
+
+    assert_eq!(seeds().len(), 7);
+    let first_seed: String = rows
+        .lines()
+        .filter(|line| line.split(',').nth(1) == Some("1"))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    std::fs::write(&path, format!("{}\n{}", header, first_seed)).unwrap();
+    assert!(seeds().iter().all(|seed| *seed == 1));
+}
+
+// Checks that compacting keeps only the seed range, writes a header-only catalog for no
+// seeds, and refuses no files, a source as output, mismatched columns, or unsorted seeds.
+#[test]
+fn compact_errors() {
+    let dir = TempDir::new("compact-errors");
+    let output = dir.join("compact.csv");
+    let text = std::fs::read_to_string(FILE).unwrap();
+    let (header, rows) = text.split_once('\n').unwrap();
+
+    let search_of = |args: &[&str], files: &[&std::path::Path]| {
+        let mut all = vec!["brogue-scanner", "-U", "-F", "./src"];
+        all.extend_from_slice(args);
+        let search = SearchParameters::from_matches(new_app().get_matches_from(all)).unwrap();
+        SearchParameters {
+            file_paths: files.iter().map(|file| file.to_path_buf()).collect(),
+            ..search
+        }
+    };
+    let file = std::path::Path::new(FILE);
+
+    let stats = library::compact(
+        &search_of(&["--minseed", "2", "--maxseed", "3"], &[file]),
+        &output,
+        false,
+    )
+    .unwrap();
+    assert_eq!((stats.seeds, stats.duplicates), (2, 0));
+    let seeds: Vec<String> = std::fs::read_to_string(&output)
+        .unwrap()
+        .lines()
+        .skip(1)
+        .map(|line| line.split(',').nth(1).unwrap().to_string())
+        .collect();
+    assert!(seeds.iter().all(|seed| seed == "2" || seed == "3"));
+
+    let stats = library::compact(&search_of(&["--minseed", "6"], &[file]), &output, false).unwrap();
+    assert_eq!(stats.seeds, 0);
+    assert_eq!(
+        std::fs::read_to_string(&output).unwrap(),
+        format!("{}\n", header)
+    );
+
+    assert!(library::compact(&search_of(&[], &[]), &output, false).is_err());
+    assert!(library::compact(&search_of(&[], &[file]), file, false).is_err());
+    assert!(
+        library::compact(
+            &search_of(&[], &[file]),
+            std::path::Path::new("./src"),
+            false
+        )
+        .is_err()
+    );
+
+    let extra = dir.join("extra.csv");
+    std::fs::write(&extra, format!("{},note\n", header)).unwrap();
+    assert!(library::compact(&search_of(&[], &[file, &extra]), &output, false).is_err());
+
+    let unsorted = dir.join("unsorted.csv");
+    let mut lines: Vec<&str> = rows.lines().collect();
+    lines.reverse();
+    std::fs::write(&unsorted, format!("{}\n{}\n", header, lines.join("\n"))).unwrap();
+    assert!(library::compact(&search_of(&[], &[&unsorted]), &output, false).is_err());
+}
+
+// Checks that `split` rejects a zero or non-numeric chunk and an output that's a file,
+// writes a file per seed for chunks of 1, and names a last chunk by the largest seed.
+#[test]
+fn split_chunks() {
+    let dir = TempDir::new("split-chunks");
+
+    let split = |output: &std::path::Path, chunk: &str| {
+        let args: Vec<String> = [
+            "brogue-scanner",
+            "-U",
+            "-F",
+            "./src",
+            "split",
+            output.to_str().unwrap(),
+            "--chunk",
+            chunk,
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        engine::Engine::new().run(new_app().get_matches_from(&args), &args)
+    };
+    let names = |output: &std::path::Path| -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(output)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    };
+
+    for bad in ["0", "x", "4294967296"] {
+        assert!(split(&dir.join("bad"), bad).is_err(), "{}", bad);
+    }
+    assert!(!dir.join("bad").exists());
+
+    split(&dir.join("ones"), "1").unwrap();
+    assert_eq!(
+        names(&dir.join("ones")),
+        (1..=5)
+            .map(|seed| format!("seeds-{}-{}.csv", seed, seed))
+            .collect::<Vec<_>>()
+    );
+
+    split(&dir.join("all"), "4294967295").unwrap();
+    assert_eq!(names(&dir.join("all")), vec!["seeds-1-4294967295.csv"]);
+
+    let file = dir.join("file");
+    std::fs::write(&file, "").unwrap();
+    assert!(split(&file, "2").is_err());
+}
+
+// Checks that `convert` needs exactly one of `--in-place` and `--output`, refuses UTF-16
+// output and converting a file onto itself, skips UTF-8 catalogs, and converts in place.
+#[test]
+#[cfg(feature = "utf16")]
+fn convert_edges() {
+    let dir = TempDir::new("convert-edges");
+    let path = dir.join("seeds.csv");
+
+    let text = std::fs::read_to_string(FILE).unwrap();
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(text.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+    std::fs::write(&path, &bytes).unwrap();
+
+    let convert = |general: &[&str], options: &[&str]| {
+        let mut args = vec!["brogue-scanner"];
+        args.extend_from_slice(general);
+        args.push("convert");
+        args.extend_from_slice(options);
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        new_app()
+            .get_matches_from_safe(&args)
+            .map_err(anyhow::Error::from)
+            .and_then(|matches| engine::Engine::new().run(matches, &args))
+    };
+    let source = ["-F", dir.to_str().unwrap()];
+
+    assert!(convert(&source, &[]).is_err());
+    assert!(convert(&source, &["--in-place", "--output", "out"]).is_err());
+    assert!(convert(&source, &["--to", "utf16", "--in-place"]).is_err());
+    assert!(
+        convert(
+            &["-F", dir.to_str().unwrap(), "--out-encoding", "utf16"],
+            &["--in-place"]
+        )
+        .is_err()
+    );
+    assert!(convert(&source, &["--output", dir.to_str().unwrap()]).is_err());
+    assert_eq!(std::fs::read(&path).unwrap(), bytes);
+
+    let search = SearchParameters::from_matches(new_app().get_matches_from([
+        "brogue-scanner",
+        "-U",
+        "-F",
+        "./src",
+    ]))
+    .unwrap();
+    assert_eq!(
+        library::convert_utf8(&search, Some(&dir.join("unused"))).unwrap(),
+        0
+    );
+    assert!(!dir.join("unused").exists());
+
+    convert(&source, &["--in-place"]).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), text);
+    assert!(!dir.join("seeds.csv.tmp").exists());
+}
+
+// Checks that manifests of missing files can't be built, that an empty manifest is only
+// current for no files, and that a stale rarity table is left alone unless
+// `--rebuild-stale` is given.
+#[test]
+fn manifest_rebuilds() {
+    use manifest::{stale_summary, Manifest};
+    use rarity::RarityTable;
+
+    let dir = TempDir::new("manifest-rebuild");
+    let path = dir.join("a.csv");
+    std::fs::copy(FILE, &path).unwrap();
+
+    assert!(Manifest::build(&[dir.join("missing.csv")]).is_err());
+    let empty = Manifest::build(&[]).unwrap();
+    assert!(empty.stale_files(&[]).is_empty());
+    assert_eq!(
+        empty.stale_files(std::slice::from_ref(&path)),
+        vec!["a.csv"]
+    );
+
+    let names: Vec<String> = ["a", "b", "c", "d", "e"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    assert_eq!(stale_summary(&[]), "");
+    assert_eq!(stale_summary(&names[..3]), "a, b, c");
+    assert_eq!(stale_summary(&names), "a, b, c, and 2 more");
+
+    let run = |extra: &[&str]| {
+        let mut args = vec!["brogue-scanner", "-U", "-F", dir.to_str().unwrap()];
+        args.extend_from_slice(extra);
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        engine::Engine::new()
+            .run(new_app().get_matches_from(&args), &args)
+            .unwrap();
+    };
+    let cache = dir.join(rarity::RARITY_CACHE_FILE);
+    let stale = || RarityTable::load(&cache).unwrap().stale_files(std::slice::from_ref(&path));
+
+    run(&["rarity"]);
+    assert!(stale().is_empty());
+
+    let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+    std::io::Write::write_all(&mut file, b"CE 1.9,6,1,1,gold,gold pieces,,,,,,,\n").unwrap();
+    let before = std::fs::read(&cache).unwrap();
+    run(&["-p", "life", "--explain"]);
+    assert_eq!(std::fs::read(&cache).unwrap(), before);
+    assert_eq!(stale(), vec!["a.csv"]);
+
+    run(&["-p", "life", "--explain", "--rebuild-stale"]);
+    assert!(stale().is_empty());
+    assert_eq!(RarityTable::load(&cache).unwrap().seeds, 6);
+}
+
+// Checks that `--matches` rejects values that aren't a u32 (or 'all'), that a limit of 1
+// stops at the first matching seed, and that the largest limit is unlimited in practice.
+#[test]
+fn matches_limits() {
+    let search = |limit: &str| {
+        let args = ["brogue-scanner", "-a", "scale", "-m", limit];
+        SearchParameters::from_matches(new_app().get_matches_from(args)).map(|mut search| {
+            search.set_file(FILE);
+            search_files(&mut search).unwrap();
+            (
+                search.search_matches,
+                search.seeds_scanned,
+                search.seed_limit(),
+            )
+        })
+    };
+
+    for bad in ["", "ALL", "any", "2.5", "4294967296", "+"] {
+        assert!(search(bad).is_err(), "{:?}", bad);
+    }
+    let (matches, seeds_scanned, limit) = search("1").unwrap();
+    assert_eq!((matches, limit), (1, Some(1)));
+    assert!(seeds_scanned < 5);
+    assert_eq!(search("4294967295").unwrap(), (4, 5, Some(4294967295)));
+    assert_eq!(search("all").unwrap(), (4, 5, None));
+}
+
+// Checks that seed lists reject missing files, bad seeds, malformed JSON, and lists with
+// no seeds in the seed range, and read seeds from a `--json` document.
+#[test]
+fn seed_list_errors() {
+    let dir = TempDir::new("seedlist-errors");
+    let path = dir.join("seeds.txt");
+
+    let seed_list = |contents: &str, extra: &[&str]| {
+        std::fs::write(&path, contents).unwrap();
+        let mut args = vec!["brogue-scanner", "-a", "scale", "--seedlist", path.to_str().unwrap()];
+        args.extend_from_slice(extra);
+        SearchParameters::from_matches(new_app().get_matches_from(args))
+            .map(|search| (search.seed_list.unwrap(), search.seed_min, search.seed_max))
+    };
+
+    for bad in [
+        "",
+        "# nothing\n\n",
+        "1 2 x\n",
+        "4294967296\n",
+        "{\"type\": \"match\", \"seed\": 2\n",
+        "{\"type\": \"match\", \"seed\": -2}\n",
+        "{\"type\": \"match\", \"seed\": \"2\"}\n",
+        "{\"seeds\": [1, 2]}",
+    ] {
+        assert!(seed_list(bad, &[]).is_err(), "{:?}", bad);
+    }
+    assert!(seed_list("7 9\n", &["--maxseed", "5"]).is_err());
+    assert!(seed_list("1 2\n", &["--seed", "3", "--seed", "4"]).is_err());
+
+    assert_eq!(
+        seed_list("9,1 5\n3\n", &["--minseed", "2", "--maxseed", "8"]).unwrap(),
+        (vec![3, 5], 3, 5)
+    );
+    assert_eq!(
+        seed_list("1 2 3\n", &["--seed", "2", "--seed", "3"]).unwrap(),
+        (vec![2, 3], 2, 3)
+    );
+    let ndjson = "{\"type\": \"match\", \"seed\": 4}\n{\"type\": \"summary\", \"matches\": 1}\n";
+    assert_eq!(seed_list(ndjson, &[]).unwrap(), (vec![4], 4, 4));
+    let json = "{\"matches\": [{\"seed\": 3}, {\"seed\": 1}, {\"seed\": 3}]}";
+    assert_eq!(seed_list(json, &[]).unwrap(), (vec![1, 3], 1, 3));
+
+    let missing = dir.join("missing.txt");
+    let args = ["brogue-scanner", "--seedlist", missing.to_str().unwrap()];
+    assert!(SearchParameters::from_matches(new_app().get_matches_from(args)).is_err());
+}
+
+// Checks that `--write-seedlist` writes only the seeds kept by `--matches`, writes an empty
+// list when nothing matches, can overwrite the `--seedlist` it reads, and fails for a folder.
+#[test]
+fn write_seedlist_edges() {
+    let dir = TempDir::new("write-seedlist");
+    let path = dir.join("seeds.txt");
+
+    let run = |extra: &[&str]| {
+        let mut args = vec!["brogue-scanner", "-U", "-F", "./src", "--porcelain"];
+        args.extend_from_slice(extra);
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        engine::Engine::new().run(new_app().get_matches_from(&args), &args)
+    };
+    let written = || std::fs::read_to_string(&path).unwrap();
+
+    run(&["-a", "scale", "-m", "2", "--write-seedlist", path.to_str().unwrap()]).unwrap();
+    assert_eq!(written(), "1\n2\n");
+
+    let seedlist = [
+        "--seedlist",
+        path.to_str().unwrap(),
+        "--write-seedlist",
+        path.to_str().unwrap(),
+    ];
+    run(&[
+        &[
+            "-S",
+            "enchanting",
+            "-d",
+            "3",
+            "--exclude-category",
+            "scroll:aggravate",
+        ][..],
+        &seedlist,
+    ]
+    .concat())
+    .unwrap();
+    assert_eq!(written(), "1\n");
+
+    run(&[
+        "-a",
+        "scale",
+        "--minseed",
+        "5",
+        "--write-seedlist",
+        path.to_str().unwrap(),
+    ])
+    .unwrap();
+    assert_eq!(written(), "");
+
+    assert!(run(&["-a", "scale", "--write-seedlist", dir.to_str().unwrap()]).is_err());
+}
+
+// Checks that the `seeds` subcommand rejects unknown operations, missing files, and bad
+// lists, and that empty lists combine as empty sets.
+#[test]
+fn seed_set_edges() {
+    use search::SeedSetOp;
+
+    let dir = TempDir::new("seed-sets");
+    let (a, empty, bad, output) = (
+        dir.join("a.txt"),
+        dir.join("empty.txt"),
+        dir.join("bad.txt"),
+        dir.join("out.txt"),
+    );
+    std::fs::write(&a, "3 1\n2, 3\n").unwrap();
+    std::fs::write(&empty, "# no seeds\n").unwrap();
+    std::fs::write(&bad, "1 two\n").unwrap();
+
+    let seeds = |op: &str, files: &[&std::path::Path]| {
+        let mut args = vec!["brogue-scanner", "seeds", op];
+        args.extend(files.iter().map(|file| file.to_str().unwrap()));
+        args.extend_from_slice(&["--output", output.to_str().unwrap()]);
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        new_app()
+            .get_matches_from_safe(&args)
+            .map_err(anyhow::Error::from)
+            .and_then(|matches| engine::Engine::new().run(matches, &args))
+            .map(|_| std::fs::read_to_string(&output).unwrap())
+    };
+
+    assert!(seeds("xor", &[&a]).is_err());
+    assert!(seeds("union", &[]).is_err());
+    assert!(seeds("union", &[&a, &dir.join("missing.txt")]).is_err());
+    assert!(seeds("union", &[&a, &bad]).is_err());
+
+    assert_eq!(seeds("union", &[&a]).unwrap(), "1\n2\n3\n");
+    assert_eq!(seeds("intersect", &[&a, &empty]).unwrap(), "");
+    assert_eq!(seeds("diff", &[&a, &empty]).unwrap(), "1\n2\n3\n");
+    assert_eq!(seeds("diff", &[&empty, &a]).unwrap(), "");
+    assert_eq!(seeds("intersect", &[&a, &a]).unwrap(), "1\n2\n3\n");
+
+    assert!(SeedSetOp::Union.apply(&[]).is_empty());
+    assert!(SeedSetOp::Intersect.apply(&[]).is_empty());
+}
+
+// Checks that porcelain lines always have 8 fields (empty for missing values, and without
+// extra catalog columns), and that porcelain conflicts with other report options.
+#[test]
+fn porcelain_fields() {
+    let dir = TempDir::new("porcelain");
+    let text = std::fs::read_to_string(FILE).unwrap();
+    let extra: String = text.lines().map(|line| format!("{},note\n", line)).collect();
+    std::fs::write(dir.join("extra.csv"), extra).unwrap();
+
+    let porcelain = |dir: &str, args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "-U", "-F", dir, "--porcelain", "-m", "all"];
+        all.extend_from_slice(args);
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(all)).unwrap();
+        let mut buffer = Vec::new();
+        write_machine_matches(&mut buffer, &search_files(&mut search).unwrap(), &search).unwrap();
+        String::from_utf8(buffer).unwrap()
+    };
+
+    let lines = porcelain("./src", &["-i", "vault", "-p", "paralysis"]);
+    assert!(lines.lines().count() > 50);
+    assert!(
+        lines.lines().all(|line| line.split('\t').count() == 8),
+        "{}",
+        lines
+    );
+    assert!(
+        lines
+            .lines()
+            .any(|line| line.starts_with("1\t1\tpotion\tparalysis\t\t"))
+    );
+    assert_eq!(
+        porcelain(dir.to_str().unwrap(), &["-i", "vault", "-p", "paralysis"]),
+        lines
+    );
+
+    for option in ["--json", "--met-depths", "--param-stats", "--explain-json"] {
+        assert!(
+            new_app()
+                .get_matches_from_safe(["brogue-scanner", "--porcelain", option])
+                .is_err(),
+            "{}",
+            option
+        );
+    }
+    assert!(
+        new_app()
+            .get_matches_from_safe(["brogue-scanner", "--porcelain", "--report", "md"])
+            .is_err()
+    );
+}
+
+// Checks that output encodings reject unknown names, write a BOM even with no text, carry
+// characters split across writes, and refuse invalid or unfinished UTF-8.
+#[test]
+fn encoded_writer_edges() {
+    use file_handling::{EncodedWriter, OutputEncoding};
+    use std::io::Write;
+
+    assert!(OutputEncoding::parse("utf-8").is_err());
+    assert!(OutputEncoding::parse("").is_err());
+    assert!(
+        new_app()
+            .get_matches_from_safe(["brogue-scanner", "--out-encoding", "latin1"])
+            .is_err()
+    );
+
+    let written = |encoding: OutputEncoding, chunks: &[&[u8]]| -> std::io::Result<Vec<u8>> {
+        let mut writer = EncodedWriter::new(Vec::new(), encoding)?;
+        for chunk in chunks {
+            writer.write_all(chunk)?;
+        }
+        writer.into_inner()
+    };
+
+    assert_eq!(written(OutputEncoding::Utf8, &[]).unwrap(), b"");
+    assert_eq!(
+        written(OutputEncoding::Utf8Bom, &[]).unwrap(),
+        [0xEF, 0xBB, 0xBF]
+    );
+    assert_eq!(written(OutputEncoding::Utf16, &[]).unwrap(), [0xFF, 0xFE]);
+
+    // 'é' (0xC3 0xA9) split between writes is written once, as a single UTF-16 unit
+    assert_eq!(
+        written(OutputEncoding::Utf16, &[b"a\xC3", b"\xA9"]).unwrap(),
+        [0xFF, 0xFE, b'a', 0, 0xE9, 0]
+    );
+    assert_eq!(
+        written(OutputEncoding::Utf8, &[b"a\xC3", b"\xA9"]).unwrap(),
+        "aé".as_bytes()
+    );
+    assert!(written(OutputEncoding::Utf16, &[b"a\xC3"]).is_err());
+    assert!(written(OutputEncoding::Utf16, &[b"\xFFa"]).is_err());
+}
+
+// Checks that statuses survive added context, that a bad column found while scanning is
+// a bad query, that an empty catalog folder is "no files", and each status's exit code.
+#[test]
+fn search_status_edges() {
+    use anyhow::Context;
+    use status::{no_files, Status};
+
+    let wrapped = Err::<(), _>(no_files()).context("while searching").unwrap_err();
+    assert_eq!(Status::of_error(&wrapped), Status::NoFiles);
+
+    let args = ["brogue-scanner", "-a", "scale", "--where", "col:nonsense=1"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    assert_eq!(
+        Status::of_error(&search_files(&mut search).unwrap_err()),
+        Status::BadQuery
+    );
+
+    let dir = TempDir::new("status");
+    let args = ["brogue-scanner", "-U", "-F", dir.to_str().unwrap(), "-a", "scale"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    assert_eq!(
+        Status::of_error(&search_files(&mut search).unwrap_err()),
+        Status::NoFiles
+    );
+
+    let statuses = [
+        Status::Matches,
+        Status::NoMatches,
+        Status::NoFiles,
+        Status::BadQuery,
+        Status::Error,
+    ];
+    let codes: Vec<i32> = statuses.iter().map(|status| status.exit_code()).collect();
+    assert_eq!(codes, vec![0, 0, 3, 2, 1]);
+    assert_eq!(Status::of_seeds(1), Status::Matches);
+    assert_eq!(Status::Matches.to_string(), "matches found");
+    assert!(Status::NoFiles.to_string().contains("--filepath"));
+}
+
+// Checks that file coverage counts overlapping ranges once, is left out if any file isn't
+// named by a range, and that the summary shows vault and magic constraints both ways.
+#[test]
+fn search_display_edges() {
+    let coverage = |names: &[&str], seed_min: u32, seed_max: u32| {
+        let search = SearchParameters {
+            file_paths: names.iter().map(|name| name.into()).collect(),
+            seed_min,
+            seed_max,
+            ..Default::default()
+        };
+        search.file_coverage()
+    };
+
+    assert_eq!(
+        coverage(&["seeds-1-10.csv", "seeds-5-20.csv"], 1, 20),
+        (0, Some(20))
+    );
+    assert_eq!(
+        coverage(&["seeds-1-10.csv", "seeds-30-40.csv"], 5, 25),
+        (1, Some(6))
+    );
+    assert_eq!(
+        coverage(&["seeds-1-10.csv", "test_data.csv"], 1, 10),
+        (0, None)
+    );
+    assert_eq!(coverage(&[], 1, 10), (0, Some(0)));
+
+    let display = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "-U", "-F", "./src"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+            .unwrap()
+            .to_string()
+    };
+
+    let summary = display(&["-a", "vault", "-p", "bad"]);
+    assert!(summary.contains("     vault: in vault\n"), "{}", summary);
+    assert!(summary.contains("     magic: malevolent\n"), "{}", summary);
+    assert!(summary.contains("     files: 1\n"), "{}", summary);
+    assert!(!summary.contains("coverage:"), "{}", summary);
+
+    let summary = display(&["-a", "scale"]);
+    assert!(
+        !summary.contains("vault:") && !summary.contains("magic:"),
+        "{}",
+        summary
+    );
+}
+
+// Checks that `--explain-json` explains an empty query, unlimited matches, depths clamped
+// to the maximum, and runic alternatives, and conflicts with other explain and output options.
+#[test]
+fn explain_json_edges() {
+    let explain = |args: &[&str]| -> serde_json::Value {
+        let mut all = vec!["brogue-scanner", "-U", "-F", "./src", "--explain-json"];
+        all.extend_from_slice(args);
+        let search = SearchParameters::from_matches(new_app().get_matches_from(all)).unwrap();
+        let mut output = Vec::new();
+        search::write_explain(&mut output, &search).unwrap();
+        serde_json::from_slice(&output).unwrap()
+    };
+
+    let empty = explain(&["-m", "all"]);
+    assert_eq!(empty["objects"], serde_json::json!([]));
+    assert!(empty["matches"].is_null());
+    assert!(empty["empower"].is_null());
+    assert_eq!(empty["files"].as_array().unwrap().len(), 1);
+
+    let weapon = &explain(&["--maxdepth", "4", "-w", "quietus|mercy", "d12"])["objects"][0];
+    assert_eq!(weapon["depth"], 4);
+    assert_eq!(
+        weapon["runic"]["matches"],
+        serde_json::json!(["quietus", "mercy"])
+    );
+
+    for option in ["--explain", "--approx", "--json", "--ndjson"] {
+        assert!(
+            new_app()
+                .get_matches_from_safe(["brogue-scanner", "--explain-json", option])
+                .is_err(),
+            "{}",
+            option
+        );
+    }
+}
+
+// Checks that ally weights reject missing, extra, fractional, and out-of-range values,
+// accept spaces and negative weights, need `--grade`, and that zero weights score every
+// ally 0.
+#[test]
+fn ally_weight_edges() {
+    use objects::{Ally, AllyStatus, MonsterKind, Mutation};
+    use search::AllyWeights;
+
+    for bad in ["", "1,2,", "1,2,3,4", "a,b,c", "1.5,2,3", "1,2,99999999999"] {
+        assert!(AllyWeights::parse(bad).is_err(), "{:?}", bad);
+    }
+
+    let legendary = Ally::new(
+        MonsterKind::Unicorn,
+        AllyStatus::Allied,
+        Some(Mutation::Agile),
+    );
+    let monkey = Ally::new(MonsterKind::Monkey, AllyStatus::Caged, None);
+
+    let zero = AllyWeights::parse(" 0 , 0 , 0 ").unwrap();
+    assert_eq!((zero.quality(&legendary), zero.quality(&monkey)), (0, 0));
+    let negative = AllyWeights::parse("-4,0,1").unwrap();
+    assert_eq!(negative.quality(&legendary), 3 - 4);
+
+    let params = |weights: &str| {
+        let arg = format!("--ally-weights={}", weights);
+        SearchParameters::from_matches(new_app().get_matches_from([
+            "brogue-scanner",
+            "-A",
+            "monkey",
+            "--grade",
+            &arg,
+        ]))
+    };
+    assert!(
+        new_app()
+            .get_matches_from_safe(["brogue-scanner", "--ally-weights", "4,2,1"])
+            .is_err()
+    );
+    assert!(params("4,2").is_err());
+    assert_eq!(
+        params("-4,0,1").unwrap().ally_weights.quality(&legendary),
+        -1
+    );
+}
+
+// Checks that gold breaks ties between equal scores (then the lower seed wins), that seeds
+// with no gold count as 0, and that gold is only totalled for scored searches.
+#[test]
+fn gold_tie_edges() {
+    let search_with = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "-S", "enchanting", "-m", "all"];
+        all.extend_from_slice(args);
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(all)).unwrap();
+        search.set_file(FILE);
+        let search_matches = search_files(&mut search).unwrap();
+        (search, search_matches)
+    };
+
+    let (plain, _) = search_with(&[]);
+    assert!(plain.seed_gold.is_empty());
+
+    let (mut search, search_matches) = search_with(&["--grade"]);
+    let ranked = |search: &SearchParameters| -> Vec<u32> {
+        search::rank_seeds(&search_matches, search)
+            .iter()
+            .map(|score| score.seed)
+            .collect()
+    };
+
+    // Seeds 2 and 4 both score 28
+    search.seed_gold.insert(2, 100);
+    search.seed_gold.insert(4, 200);
+    assert_eq!(ranked(&search), vec![3, 1, 4, 2, 5]);
+    search.seed_gold.insert(2, 300);
+    assert_eq!(ranked(&search), vec![3, 1, 2, 4, 5]);
+    search.seed_gold.insert(2, 200);
+    assert_eq!(ranked(&search), vec![3, 1, 2, 4, 5]);
+    search.seed_gold.remove(&2);
+    search.seed_gold.remove(&4);
+    assert_eq!(ranked(&search), vec![3, 1, 2, 4, 5]);
+    let scores = search::rank_seeds(&search_matches, &search);
+    assert!(
+        scores
+            .iter()
+            .filter(|score| score.seed == 2 || score.seed == 4)
+            .all(|score| score.gold == 0)
+    );
+}
+
+// Checks that empty expressions and terms are rejected, that `--score-expr` needs `--grade`,
+// and that constant or out-of-range results still rank every seed.
+#[test]
+fn score_expr_edges() {
+    use search::ScoreExpr;
+
+    for bad in [
+        "",
+        "   ",
+        "count(\"\")",
+        "depth(\" \")",
+        "()",
+        "min(1)",
+        "max(1, 2, 3)",
+    ] {
+        assert!(ScoreExpr::parse(bad).is_err(), "{:?}", bad);
+    }
+
+    let safe = |args: &[&str]| -> anyhow::Result<SearchParameters> {
+        new_app()
+            .get_matches_from_safe(args)
+            .map_err(anyhow::Error::from)
+            .and_then(SearchParameters::from_matches)
+    };
+    assert!(safe(&["brogue-scanner", "-S", "enchanting", "--score-expr", "gold"]).is_err());
+    assert!(
+        safe(&[
+            "brogue-scanner",
+            "-S",
+            "enchanting",
+            "--grade",
+            "--score-expr",
+            "gold +"
+        ])
+        .is_err()
+    );
+    assert!(safe(&["brogue-scanner", "-S", "enchanting", "--grade", "--score-expr", ""]).is_err());
+
+    let ranked = |expr: &str| -> Vec<(u32, i32)> {
+        let args = [
+            "brogue-scanner",
+            "-S",
+            "enchanting",
+            "-m",
+            "all",
+            "--grade",
+            "--score-expr",
+            expr,
+        ];
+        let mut search = safe(&args).unwrap();
+        search.set_file(FILE);
+        let search_matches = search_files(&mut search).unwrap();
+        search::rank_seeds(&search_matches, &search)
+            .iter()
+            .map(|score| (score.seed, score.score))
+            .collect()
+    };
+
+    let constant = ranked("7");
+    assert_eq!(constant.len(), 5);
+    assert!(constant.iter().all(|&(_, score)| score == 7));
+    assert!(
+        ranked("objects * 99999999999999")
+            .iter()
+            .all(|&(_, score)| score == i32::MAX)
+    );
+    assert!(
+        ranked("0 - 99999999999999")
+            .iter()
+            .all(|&(_, score)| score == i32::MIN)
+    );
+    assert!(ranked("base / 0").iter().all(|&(_, score)| score == 0));
+}
+
+// Checks that empty and malformed predicates are rejected, that unknown columns fail the
+// scan, and that empty cells are never ordered against numbers.
+#[test]
+fn filter_predicate_edges() {
+    let scan = |filters: &[&str]| -> anyhow::Result<Vec<SearchMatch>> {
+        let mut args = vec!["brogue-scanner", "-m", "all"];
+        for filter in filters {
+            args.extend_from_slice(&["--filter", filter]);
+        }
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args))?;
+        search.set_file(FILE);
+        search_files(&mut search)
+    };
+    for bad in [
+        "",
+        "  ",
+        "()",
+        "!",
+        "ench>=",
+        "ench==2 ||",
+        "ench >= -x",
+        "'weapon'==category",
+        "1.5==2",
+    ] {
+        let matches = new_app().get_matches_from(["brogue-scanner", "--filter", bad]);
+        assert!(
+            SearchParameters::from_matches(matches).is_err(),
+            "{:?}",
+            bad
+        );
+    }
+    for unknown in ["zzz==1", "99==1", "kind~x || nosuch"] {
+        assert!(scan(&[unknown]).is_err(), "{}", unknown);
+    }
+
+    let count = |filter: &str| scan(&[filter]).unwrap().len();
+    assert_eq!(count("ench<99"), count("ench!=''"));
+    assert_eq!(count("ench<-1"), count("ench <= -2"));
+    assert_eq!(count("!(ench>-99)"), count("ench==''"));
+    assert_eq!(count("runic"), count("runic!=''"));
+
+    // Each `--filter` must be met by some object of the seed
+    let seeds = |filters: &[&str]| -> std::collections::BTreeSet<u32> {
+        scan(filters).unwrap().iter().map(|m| m.seed).collect()
+    };
+    let both = seeds(&["ench<-2", "kind=='broadsword'"]);
+    let expected: std::collections::BTreeSet<u32> = seeds(&["ench<-2"])
+        .intersection(&seeds(&["kind=='broadsword'"]))
+        .copied()
+        .collect();
+    assert_eq!(both, expected);
+}
+
+// Checks that empty, out-of-range, and fractional object logic is rejected, that double
+// negation and repeated objects are accepted, and that nested groups are flattened.
+#[test]
+fn object_logic_edges() {
+    let search_with = |logic: &str| -> anyhow::Result<SearchParameters> {
+        let args = [
+            "brogue-scanner",
+            "-a",
+            "banded",
+            "-p",
+            "life",
+            "-w",
+            "war axe",
+            "-d",
+            "26",
+            "-m",
+            "all",
+            "--logic",
+            logic,
+        ];
+        SearchParameters::from_matches(new_app().get_matches_from(args))
+    };
+    for bad in [
+        "",
+        " ",
+        "()",
+        "0 && 1 && 2 && 3",
+        "1 && 2 && 4",
+        "1 && 2 && 3.5",
+        "1 && 2 && !",
+        "1 && 2 3",
+        "1 && 2 && (3",
+    ] {
+        assert!(search_with(bad).is_err(), "{:?}", bad);
+    }
+    let no_objects = new_app().get_matches_from(["brogue-scanner", "--logic", "1"]);
+    assert!(SearchParameters::from_matches(no_objects).is_err());
+
+    let seeds_matching = |logic: &str| -> Vec<u32> {
+        let mut search = search_with(logic).unwrap();
+        search.set_file(FILE);
+        let mut seeds: Vec<u32> =
+            search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+        seeds.dedup();
+        seeds
+    };
+    assert_eq!(
+        seeds_matching("!!1 && 2 && 3"),
+        seeds_matching("1 && 2 && 3")
+    );
+    assert_eq!(
+        seeds_matching("1 && 1 && 2 && !3"),
+        seeds_matching("1 && 2 && !3")
+    );
+
+    assert_eq!(
+        search_with("1 && (2 && 3)").unwrap().logic.to_string(),
+        "1 && 2 && 3"
+    );
+    assert_eq!(
+        search_with("(1 || (2 || 3))").unwrap().logic.to_string(),
+        "1 || 2 || 3"
+    );
+    assert_eq!(
+        search_with("!(1 || 2) && 3").unwrap().logic.to_string(),
+        "!(1 || 2) && 3"
+    );
+    assert!(!search_with("1 && (2 && 3)").unwrap().to_string().contains("logic:"));
+    assert!(
+        search_with("1 || 2 || 3")
+            .unwrap()
+            .to_string()
+            .contains("logic: 1 || 2 || 3")
+    );
+}
+
+// Checks that evaluation order is empty without objects, keeps the given order for equally
+// selective objects and after any-category filters, and doesn't change which seeds match.
+#[test]
+fn param_order_edges() {
+    let order = |args: &[&str]| -> Vec<usize> {
+        let mut all = vec!["brogue-scanner"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+            .unwrap()
+            .param_order
+    };
+
+    assert!(order(&["-d", "5"]).is_empty());
+    assert_eq!(order(&["-p", "life"]), vec![0]);
+    assert_eq!(order(&["-p", "life", "-S", "enchanting"]), vec![0, 1]);
+    assert_eq!(order(&["-p", "life", "-p", "strength"]), vec![0, 1]);
+    assert_eq!(order(&["--filter", "depth<3", "-a", "banded"]), vec![0, 1]);
+
+    let seeds_matching = |args: &[&str]| -> Vec<u32> {
+        let mut all = vec!["brogue-scanner", "-d", "26", "-m", "all"];
+        all.extend_from_slice(args);
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(all)).unwrap();
+        search.set_file(FILE);
+        let mut seeds: Vec<u32> =
+            search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+        seeds.dedup();
+        seeds
+    };
+    assert_eq!(
+        seeds_matching(&["-i", "good", "-a", "banded", "-w", "+2", "sword"]),
+        seeds_matching(&["-a", "banded", "-w", "+2", "sword", "-i", "good"])
+    );
+}
+
+// Checks that `--param-stats` conflicts with machine output, still counts when no seed
+// matches, and counts seeds whose 'less than' count is exceeded as not met.
+#[test]
+fn param_stats_edges() {
+    for machine in ["--json", "--ndjson", "--porcelain"] {
+        assert!(
+            new_app()
+                .get_matches_from_safe(["brogue-scanner", "-p", "life", "--param-stats", machine])
+                .is_err(),
+            "{}",
+            machine
+        );
+    }
+
+    let args = [
+        "brogue-scanner",
+        "-w",
+        "war axe",
+        "-p",
+        "<1",
+        "life",
+        "-d",
+        "26",
+        "-m",
+        "all",
+        "--param-stats",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+
+    assert!(search_files(&mut search).unwrap().is_empty());
+    let seeds_met: Vec<u64> = search.object_params.iter().map(|p| p.seeds_met).collect();
+    assert_eq!(search.seeds_scanned, 5);
+    assert_eq!(seeds_met, vec![0, 1]);
+}
+
+// Checks that `--relax` conflicts with streamed and file output, has nothing to relax at
+// full depth without numeric constraints, moves negative enchantments toward 0, and never
+// loosens 'less than' counts.
+#[test]
+fn relax_edges() {
+    for conflict in [&["--stream"][..], &["--output", "x.out"], &["--best", "3"]] {
+        let mut args = vec!["brogue-scanner", "-p", "life", "--relax"];
+        args.extend_from_slice(conflict);
+        assert!(
+            new_app().get_matches_from_safe(args).is_err(),
+            "{:?}",
+            conflict
+        );
+    }
+
+    let search_with = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "--relax"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all)).unwrap()
+    };
+    let steps = |search: &mut SearchParameters| {
+        let mut steps = Vec::new();
+        while let Some((constraint, value)) = search.relax() {
+            steps.push(format!("{}: {}", constraint, value));
+        }
+        steps
+    };
+
+    assert!(steps(&mut search_with(&["-p", "life"])).is_empty());
+    assert!(steps(&mut search_with(&["-p", "<3", "life"])).is_empty());
+
+    let mut search = search_with(&["-w", "+1", "war axe"]);
+    assert_eq!(steps(&mut search), vec!["object 1 ench: any"]);
+
+    let mut search = search_with(&["-w", "war axe", "-d", "25"]);
+    search.object_params[0].enchantment = Some(-3);
+    assert_eq!(
+        steps(&mut search),
+        vec![
+            "object 1 ench: -2",
+            "object 1 ench: -1",
+            "object 1 ench: any",
+            "depth: 1 to 26"
+        ]
+    );
+}
+
+// Checks that a bare or doubled optional marker is rejected, that a marker on any argument
+// makes the whole object optional, and that a search of only optional objects matches seeds
+// with any of them.
+#[test]
+fn optional_param_edges() {
+    let search_with = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "-d", "26", "-m", "all"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+    };
+    for bad in [
+        &["-w", "?"][..],
+        &["-w", "opt:"],
+        &["-p", "?"],
+        &["-w", "??war axe"],
+        &["-w", "?opt:war axe"],
+    ] {
+        assert!(search_with(bad).is_err(), "{:?}", bad);
+    }
+
+    let search = search_with(&["-w", "+2", "?sword"]).unwrap();
+    assert!(search.object_params[0].optional);
+    assert_eq!(search.object_match_target, 0);
+
+    let seeds_matching = |args: &[&str]| -> std::collections::BTreeSet<u32> {
+        let mut search = search_with(args).unwrap();
+        search.set_file(FILE);
+        search_files(&mut search).unwrap().iter().map(|m| m.seed).collect()
+    };
+    let either = seeds_matching(&["-w", "?war axe", "-a", "?banded"]);
+    let axe = seeds_matching(&["-w", "war axe"]);
+    let banded = seeds_matching(&["-a", "banded"]);
+    assert_eq!(either, axe.union(&banded).copied().collect());
+}
+
+// Checks that `show` rejects seeds that aren't numbers, skips malformed files and files
+// whose name rules out the seed, and keeps to the depth range.
+#[test]
+fn seed_detail_edges() {
+    for seed in ["x", "4294967296", ""] {
+        let args: Vec<String> = ["brogue-scanner", "-U", "-F", "./src", "show", seed]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        assert!(
+            engine::Engine::new()
+                .run(new_app().get_matches_from(&args), &args)
+                .is_err(),
+            "{:?}",
+            seed
+        );
+    }
+
+    let dir = TempDir::new("seed-detail");
+    let data = std::fs::read_to_string(FILE).unwrap();
+    let header = data.lines().next().unwrap();
+    std::fs::write(
+        dir.join("a-bad.csv"),
+        format!("{}\nCE 1.9,x,y,1,potion,life,,,,,,,\n", header),
+    )
+    .unwrap();
+    std::fs::write(dir.join("seeds-10-20.csv"), &data).unwrap();
+    let shallow: Vec<&str> = data.lines().filter(|line| line.starts_with("CE 1.9,3,") && line.split(',').nth(2).unwrap().parse::<u8>().unwrap() <= 10).collect();
+    std::fs::write(dir.join("z.csv"), format!("{}\n{}\n", header, shallow.join("\n"))).unwrap();
+
+    let search_with = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "-U", "-F", dir.to_str().unwrap()];
+        all.extend_from_slice(args);
+        all.extend_from_slice(&["show", "3"]);
+        SearchParameters::from_matches(new_app().get_matches_from(all)).unwrap()
+    };
+    let in_seed_3 = |depths: std::ops::RangeInclusive<u8>| {
+        data.lines()
+            .skip(1)
+            .map(|line| line.split(',').collect::<Vec<_>>())
+            .filter(|fields| fields[1] == "3" && depths.contains(&fields[2].parse::<u8>().unwrap()))
+            .count()
+    };
+
+    // Only the shallow copy of seed 3 is read
+    let search = search_with(&[]);
+    assert_eq!(
+        seed_detail::seed_catalog(&search, 3).unwrap().len(),
+        in_seed_3(1..=10)
+    );
+    assert!(seed_detail::seed_catalog(&search, 15).unwrap().is_empty());
+
+    let objects =
+        seed_detail::seed_catalog(&search_with(&["--mindepth", "5", "--maxdepth", "5"]), 3)
+            .unwrap();
+    assert_eq!(objects.len(), in_seed_3(5..=5));
+    assert!(objects.iter().all(|o| o.depth == 5));
+
+    let mut no_files = search_with(&[]);
+    no_files.file_paths.clear();
+    assert!(seed_detail::seed_catalog(&no_files, 3).is_err());
+}
+
+// Checks that `--dump-seed` of a seed in no catalog writes only an empty summary, and
+// conflicts with every search-only flag.
+#[test]
+fn dump_seed_edges() {
+    let args = ["brogue-scanner", "--dump-seed", "99", "--ndjson"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+
+    let objects = seed_detail::seed_catalog(&search, 99).unwrap();
+    let mut buffer = Vec::new();
+    write_machine_matches(&mut buffer, &objects, &search).unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+    let summary: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+    assert_eq!(output.lines().count(), 1);
+    assert_eq!(
+        (summary["type"].as_str(), summary["matches"].as_u64()),
+        (Some("summary"), Some(0))
+    );
+
+    for flag in [&["--approx"][..], &["--explain-json"], &["--relax"]] {
+        let mut args = vec!["brogue-scanner", "-p", "life", "--dump-seed", "3"];
+        args.extend_from_slice(flag);
+        assert!(new_app().get_matches_from_safe(args).is_err(), "{:?}", flag);
+    }
+}
+
+// Checks that `--seed` rejects values that aren't seeds, conflicts with `--maxseed`, scans
+// nothing for a seed in no catalog, and is shown as a single seed in the summary.
+#[test]
+fn single_seed_edges() {
+    for bad in ["x", "", "4294967296", "1.5"] {
+        let matches = new_app().get_matches_from(["brogue-scanner", "-a", "scale", "--seed", bad]);
+        assert!(
+            SearchParameters::from_matches(matches).is_err(),
+            "{:?}",
+            bad
+        );
+    }
+    assert!(
+        new_app()
+            .get_matches_from_safe(["brogue-scanner", "--seed", "4", "--maxseed", "9"])
+            .is_err()
+    );
+
+    let args = ["brogue-scanner", "--seed", "99", "-a", "scale", "-m", "all"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    assert!(search_files(&mut search).unwrap().is_empty());
+    assert_eq!((search.seeds_scanned, search.seed_list.as_ref()), (0, None));
+    assert!(search.to_string().contains("      seed: 99\n"));
+}
+
+// Checks that repeated `--seed`s reject any bad seed, collapse duplicates of one seed to a
+// single seed, intersect with `--seedlist` (failing if nothing is left), and scan nothing
+// when no listed seed is in a catalog.
+#[test]
+fn multiple_seed_edges() {
+    let search_with = |args: &[&str]| -> anyhow::Result<SearchParameters> {
+        let mut all = vec!["brogue-scanner", "-a", "scale", "-m", "all"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+    };
+    assert!(search_with(&["--seed", "2", "--seed", "0"]).is_err());
+    assert!(search_with(&["--seed", "2", "--seed", "x"]).is_err());
+
+    let search = search_with(&["--seed", "3", "--seed", "3"]).unwrap();
+    assert_eq!(
+        (search.seed_min, search.seed_max, search.seed_list),
+        (3, 3, None)
+    );
+
+    let dir = TempDir::new("multiple-seeds");
+    let path = dir.join("seeds.txt");
+    std::fs::write(&path, "1\n4\n5\n").unwrap();
+    let list = path.to_str().unwrap();
+
+    let search =
+        search_with(&["--seed", "2", "--seed", "4", "--seed", "5", "--seedlist", list]).unwrap();
+    assert_eq!(search.seed_list, Some(vec![4, 5]));
+    assert!(search_with(&["--seed", "2", "--seed", "3", "--seedlist", list]).is_err());
+
+    let mut search = search_with(&["--seed", "98", "--seed", "99"]).unwrap();
+    search.set_file(FILE);
+    assert!(search_files(&mut search).unwrap().is_empty());
+    assert_eq!(search.seeds_scanned, 0);
+    assert!(search.to_string().contains("seedlist: 2 seeds (98, 99)"));
+}
+
+// Checks that `--seed-step` rejects 0 and non-numbers, searches only the minimum seed when
+// the step passes the range, steps over listed seeds, and is shown in the summary.
+#[test]
+fn seed_step_edges() {
+    let search_with = |args: &[&str]| -> anyhow::Result<SearchParameters> {
+        let mut all = vec!["brogue-scanner", "-a", "scale", "-m", "all"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+    };
+    for bad in ["0", "x", "", "4294967296"] {
+        assert!(search_with(&["--seed-step", bad]).is_err(), "{:?}", bad);
+    }
+
+    let scanned = |args: &[&str]| {
+        let mut search = search_with(args).unwrap();
+        search.set_file(FILE);
+        let seeds: std::collections::BTreeSet<u32> =
+            search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+        (search.seeds_scanned, seeds)
+    };
+    assert_eq!(scanned(&["--minseed", "3", "--seed-step", "1000"]).0, 1);
+    assert_eq!(scanned(&["--minseed", "6", "--seed-step", "2"]).0, 0);
+
+    // Listed seeds 1, 2, and 4 are stepped from the first (1), so only 1 is on the step
+    let (count, seeds) =
+        scanned(&["--seed", "1", "--seed", "2", "--seed", "4", "--seed-step", "2"]);
+    assert_eq!(count, 1);
+    assert!(seeds.iter().all(|seed| *seed == 1));
+
+    let search = search_with(&["--seed-step", "3"]).unwrap();
+    assert!(search.to_string().contains("step: every 3 seeds"));
+    assert_eq!(search.stepped_seeds(2, 3), 0);
+    assert_eq!(search.stepped_seeds(1, 1), 1);
+    assert!(!search_with(&[]).unwrap().to_string().contains("step:"));
+}
+
+// Checks that a status line never updated isn't erased, that seeds updated in quick
+// succession are drawn once, and that a path with no file name is shown whole.
+#[test]
+fn progress_line_edges() {
+    use progress::Progress;
+
+    let dir = TempDir::new("progress");
+    let path = dir.join("progress.txt");
+    let search = SearchParameters::default();
+    let drawn = |steps: &dyn Fn(&mut Progress)| {
+        let mut progress =
+            Progress::with_output(true, Box::new(std::fs::File::create(&path).unwrap()));
+        steps(&mut progress);
+        drop(progress);
+        std::fs::read_to_string(&path).unwrap()
+    };
+
+    assert_eq!(
+        drawn(&|progress| progress.start_file(std::path::Path::new("seeds.csv"))),
+        ""
+    );
+
+    let quick = drawn(&|progress| {
+        progress.start_file(std::path::Path::new(".."));
+        progress.update(1, std::slice::from_ref(&search));
+        progress.update(2, std::slice::from_ref(&search));
+    });
+    let line = "searching ..  seed 1  0 matching seeds";
+    assert_eq!(quick, format!("\r{}\r{}\r", line, " ".repeat(line.len())));
+}
+
+// Checks digit grouping at each group boundary, singular and plural counts, and that
+// `--plain-numbers` reaches every number in the summary.
+#[test]
+fn summary_number_edges() {
+    let search = SearchParameters::default();
+    assert_eq!(search.number(10_000), "10,000");
+    assert_eq!(search.number(100_000), "100,000");
+    assert_eq!(search.number(1_000_000), "1,000,000");
+    assert_eq!(search.number(u64::MAX), "18,446,744,073,709,551,615");
+    assert_eq!(search.counted(0, "seed", "seeds"), "0 seeds");
+    assert_eq!(search.counted(1, "seed", "seeds"), "1 seed");
+    assert_eq!(search.counted(1000, "seed", "seeds"), "1,000 seeds");
+
+    let summary = |plain: bool| {
+        let mut args = vec![
+            "brogue-scanner",
+            "-a",
+            "scale",
+            "--minseed",
+            "1000",
+            "--seed-step",
+            "2500",
+        ];
+        if plain {
+            args.push("--plain-numbers");
+        }
+        SearchParameters::from_matches(new_app().get_matches_from(args))
+            .unwrap()
+            .to_string()
+    };
+    assert!(summary(false).contains("seed: 1,000 to 4,294,967,295"));
+    assert!(summary(false).contains("step: every 2,500 seeds"));
+    let plain = summary(true);
+    assert!(plain.contains("seed: 1000 to 4294967295"));
+    assert!(plain.contains("step: every 2500 seeds"));
+}
+
+// Checks that color modes are matched exactly, that `NO_COLOR` only counts when not empty
+// (and turns off `auto`, the default), and that builds without the `color` feature never
+// color.
+#[test]
+fn color_mode_edges() {
+    use color::{no_color, ColorMode};
+
+    for bad in ["", "ALWAYS", " never", "yes"] {
+        assert!(ColorMode::parse(bad).is_err(), "{:?}", bad);
+    }
+
+    let previous = std::env::var_os("NO_COLOR");
+    std::env::set_var("NO_COLOR", "");
+    assert!(!no_color());
+    std::env::set_var("NO_COLOR", "1");
+    assert!(no_color());
+    assert!(!ColorMode::Auto.is_enabled());
+    match previous {
+        Some(value) => std::env::set_var("NO_COLOR", value),
+        None => std::env::remove_var("NO_COLOR"),
+    }
+
+    let search = SearchParameters::from_matches(new_app().get_matches_from([
+        "brogue-scanner",
+        "-a",
+        "scale",
+    ]))
+    .unwrap();
+    assert_eq!(search.color, ColorMode::Auto);
+    assert_eq!(ColorMode::Always.is_enabled(), cfg!(feature = "color"));
+}
+
+// Checks that bad rotation settings and unopenable logs are rejected, that `--log-keep 0`
+// keeps no rotated logs, that an oversized entry still goes into an empty log, and that
+// the log rotates on a new day and counts what was already in it.
+#[test]
+#[cfg(feature = "server")]
+fn request_log_edges() {
+    use request_log::{LogEntry, RequestLog};
+
+    let dir = TempDir::new("log-edges");
+    let path = dir.join("daemon.log");
+    let log_path = path.to_str().unwrap();
+
+    let open = |settings: &[&str]| {
+        let mut args = vec!["brogue-scanner", "daemon", "--log", log_path];
+        args.extend_from_slice(settings);
+        let matches = new_app().get_matches_from(args);
+        daemon::open_request_log(log_path, matches.subcommand_matches("daemon").unwrap())
+    };
+    for bad in [
+        &["--log-max-size", "0"][..],
+        &["--log-max-size", "x"],
+        &["--log-max-size=-1"],
+        &["--log-keep", "x"],
+        &["--log-keep=-1"],
+    ] {
+        assert!(open(bad).is_err(), "{:?}", bad);
+    }
+    assert!(open(&["--log-max-size", "1", "--log-keep", "0"]).is_ok());
+    assert!(RequestLog::open(&dir, 200, 1).is_err());
+
+    let entry = |time_ms: u64| LogEntry {
+        time_ms,
+        ..LogEntry::event("start", Some("x".repeat(300)))
+    };
+    let lines = |path: &std::path::Path| std::fs::read_to_string(path).unwrap().lines().count();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut log = RequestLog::open(&path, 200, 0).unwrap();
+    log.write(&entry(0)).unwrap();
+    assert_eq!(lines(&path), 1);
+    log.write(&entry(0)).unwrap();
+    assert_eq!(lines(&path), 1);
+    assert!(!dir.join("daemon.log.1").exists());
+    drop(log);
+
+    // Reopened, an entry on a later day rotates the log
+    let mut log = RequestLog::open(&path, 1_000_000, 1).unwrap();
+    log.write(&entry(u64::MAX / 2)).unwrap();
+    assert_eq!((lines(&path), lines(&dir.join("daemon.log.1"))), (1, 1));
+}
+
+// Checks that unknown statuses aren't counted, that scans on a bucket bound fall in that
+// bucket and long scans only in `+Inf`, that bad addresses are rejected, and that anything
+// but `GET /metrics` is a 404.
+#[test]
+#[cfg(feature = "server")]
+fn daemon_metrics_edges() {
+    use metrics::{serve_metrics, Metrics};
+    use std::io::{Read, Write};
+    use std::time::Duration;
+
+    let metrics = std::sync::Arc::new(Metrics::default());
+    metrics.add_request("unknown");
+    metrics.add_scan(Duration::from_millis(5), 0);
+    metrics.add_scan(Duration::from_secs(61), 3);
+    let text = metrics.render();
+    assert!(!text.contains("unknown"));
+    assert!(text.contains("brogue_requests_total{status=\"ok\"} 0\n"));
+    assert!(text.contains("brogue_scan_duration_seconds_bucket{le=\"0.005\"} 1\n"));
+    assert!(text.contains("brogue_scan_duration_seconds_bucket{le=\"60\"} 1\n"));
+    assert!(text.contains("brogue_scan_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+    assert!(text.contains("brogue_scan_duration_seconds_sum 61.005\n"));
+    assert!(text.contains("brogue_rows_scanned_total 3\n"));
+
+    assert!(serve_metrics(metrics.clone(), "not an address").is_err());
+
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let addr = format!("127.0.0.1:{}", port);
+    serve_metrics(metrics, &addr).unwrap();
+    let get = |request: &str| {
+        let mut stream = std::net::TcpStream::connect(&addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+    assert!(get("GET /metrics HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 200 OK\r\n"));
+    for request in [
+        "POST /metrics HTTP/1.1\r\n\r\n",
+        "GET /metrics/x HTTP/1.1\r\n\r\n",
+        "GET\r\n\r\n",
+        "\r\n",
+    ] {
+        assert!(
+            get(request).starts_with("HTTP/1.1 404 Not Found\r\n"),
+            "{:?}",
+            request
+        );
+    }
+}
+
+// Checks that `--fuzzy` leaves valid terms alone, doesn't read short or distant arguments
+// as kinds, keeps the optional marker, and handles non-ASCII misspellings.
+#[test]
+fn fuzzy_kind_edges() {
+    let search_with = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "--fuzzy"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+    };
+
+    assert!(
+        search_with(&["-w", "broadsword", "-p", "life"])
+            .unwrap()
+            .fuzzy_terms
+            .is_empty()
+    );
+    for bad in [
+        &["-w", "zx"][..],
+        &["-w", "brxxxsword"],
+        &["-p", "lf"],
+        &["-g", "lots"],
+    ] {
+        assert!(search_with(bad).is_err(), "{:?}", bad);
+    }
+
+    let search = search_with(&["-w", "opt:broadswörd"]).unwrap();
+    assert_eq!(
+        search.fuzzy_terms,
+        vec![("opt:broadswörd".to_string(), "opt:broadsword".to_string())]
+    );
+    assert!(search.object_params[0].optional);
+    assert_eq!(search.object_params[0].kind.as_deref(), Some("broadsword"));
+}
+
+// Checks that words naming no kind are left as separate terms, that an optional marker
+// on a later word stops the join, that incomplete or capitalized names aren't joined, and
+// that single-word kinds are joined after the category's prefix.
+#[test]
+fn multi_word_kind_edges() {
+    let kinds = |args: &[&str]| -> anyhow::Result<Vec<(Option<String>, bool)>> {
+        let mut all = vec!["brogue-scanner"];
+        all.extend_from_slice(args);
+        let search = SearchParameters::from_matches(new_app().get_matches_from(all))?;
+        Ok(search
+            .object_params
+            .iter()
+            .map(|p| (p.kind.clone(), p.optional))
+            .collect())
+    };
+    let kind = |name: &str, optional: bool| (Some(name.to_string()), optional);
+
+    assert_eq!(
+        kinds(&["-w", "axe", "war"]).unwrap(),
+        vec![kind("axe", false), kind("war", false)]
+    );
+    assert_eq!(
+        kinds(&["-w", "war", "?axe"]).unwrap(),
+        vec![kind("war", false), kind("axe", true)]
+    );
+    assert_eq!(
+        kinds(&["-p", "potion", "of", "life"]).unwrap(),
+        vec![kind("life", false)]
+    );
+    assert_eq!(
+        kinds(&["-p", "?potion", "of", "life"]).unwrap(),
+        vec![kind("life", true)]
+    );
+    for bad in [
+        &["-w", "war", "zzz"][..],
+        &["-S", "scroll", "of"],
+        &["-S", "of", "magic", "mapping"],
+        &["-w", "War", "Axe"],
+    ] {
+        assert!(kinds(bad).is_err(), "{:?}", bad);
+    }
+}
+
+// Checks that runic family terms are rejected for the other category and when repeated,
+// that they combine with a kind on the same object, and that a later runic term starts a
+// new object.
+#[test]
+fn runic_family_edges() {
+    let search_with = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "-d", "26", "-m", "all"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+    };
+    for bad in [
+        &["-a", "anyslaying"][..],
+        &["-w", "anyimmunity"],
+        &["-w", "anyslaying", "anyslaying"],
+        &["-w", "AnySlaying"],
+    ] {
+        assert!(search_with(bad).is_err(), "{:?}", bad);
+    }
+
+    let matched = |args: &[&str]| {
+        let mut search = search_with(args).unwrap();
+        search.set_file(FILE);
+        search_files(&mut search).unwrap()
+    };
+    let slaying = matched(&["-w", "anyslaying"]);
+    let kind = slaying[0].object.kind_name();
+    assert_eq!(matched(&["-w", &kind, "anyslaying"]).len(), 1);
+    let other = if kind == "broadsword" { "dagger" } else { "broadsword" };
+    assert!(matched(&["-w", other, "anyslaying"]).is_empty());
+
+    assert_eq!(
+        search_with(&["-w", "anyslaying", "runic"])
+            .unwrap()
+            .object_params
+            .len(),
+        2
+    );
+}
+
+// Checks that malformed `str<=N` armor terms are rejected, that a strength below every
+// armor matches nothing, that the bound is inclusive, and that two bounds are two objects.
+#[test]
+fn armor_strength_edges() {
+    let search_with = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "-d", "26", "-m", "all"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+    };
+    for bad in ["str<=", "str<=x", "str<=256", "str<13", "str>=13", "str<=-1"] {
+        assert!(search_with(&["-a", bad]).is_err(), "{}", bad);
+    }
+
+    let matched = |args: &[&str]| {
+        let mut search = search_with(args).unwrap();
+        search.set_file(FILE);
+        search_files(&mut search).unwrap().len()
+    };
+    assert_eq!(matched(&["-a", "str<=9"]), 0);
+    assert_eq!(matched(&["-a", "plate", "str<=18"]), 0);
+    assert!(matched(&["-a", "plate"]) > 0);
+    assert_eq!(
+        matched(&["-a", "plate", "str<=19"]),
+        matched(&["-a", "plate"])
+    );
+    assert_eq!(
+        search_with(&["-a", "str<=13", "str<=15"])
+            .unwrap()
+            .object_params
+            .len(),
+        2
+    );
+}
+
+// Checks that malformed `str<=N` weapon terms are rejected, that a strength below every
+// weapon matches nothing, that the bound is inclusive, and that it applies to `thrown`.
+#[test]
+fn weapon_strength_edges() {
+    let search_with = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "-d", "26", "-m", "all"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+    };
+    for bad in ["str<=", "str<=x", "str<=256", "str<16", "str=16"] {
+        assert!(search_with(&["-w", bad]).is_err(), "{}", bad);
+    }
+
+    let matched = |args: &[&str]| {
+        let mut search = search_with(args).unwrap();
+        search.set_file(FILE);
+        search_files(&mut search).unwrap()
+    };
+    assert!(matched(&["-w", "str<=9"]).is_empty());
+    assert!(matched(&["-w", "war axe", "str<=18"]).is_empty());
+    assert!(!matched(&["-w", "war axe"]).is_empty());
+    assert_eq!(
+        matched(&["-w", "war axe", "str<=19"]).len(),
+        matched(&["-w", "war axe"]).len()
+    );
+
+    // Javelins need 15 strength, darts less
+    let thrown = matched(&["-w", "4", "thrown", "str<=14"]);
+    assert!(!thrown.is_empty());
+    assert!(thrown.iter().all(|m| m.object.kind_name().ends_with("dart")));
+}
+
+// Checks that handedness terms are rejected when malformed or repeated, that `1h` and `2h`
+// together are two objects, and that a kind of the other handedness matches nothing.
+#[test]
+fn weapon_handedness_edges() {
+    let search_with = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "-d", "26", "-m", "all"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+    };
+    for bad in [&["-w", "3h"][..], &["-w", "2H"], &["-w", "1h", "1h"], &["-a", "2h"]] {
+        assert!(search_with(bad).is_err(), "{:?}", bad);
+    }
+
+    let search = search_with(&["-w", "1h", "2h"]).unwrap();
+    let handedness: Vec<Option<bool>> = search.object_params.iter().map(|p| p.two_handed).collect();
+    assert_eq!(handedness, vec![Some(false), Some(true)]);
+
+    let matched = |args: &[&str]| {
+        let mut search = search_with(args).unwrap();
+        search.set_file(FILE);
+        search_files(&mut search).unwrap().len()
+    };
+    assert_eq!(matched(&["-w", "1h", "war axe"]), 0);
+    assert_eq!(
+        matched(&["-w", "2h", "war axe"]),
+        matched(&["-w", "war axe"])
+    );
+}
+
+// Checks that a single stack of objects passing an 'or more' count meets it, that thrown
+// counts add up across kinds for exact counts too, and that `thrown` is rejected for other
+// categories and when repeated.
+#[test]
+fn thrown_weapon_edges() {
+    let search_with = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "-m", "all"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+    };
+    for bad in [&["-a", "thrown"][..], &["-w", "thrown", "thrown"]] {
+        assert!(search_with(bad).is_err(), "{:?}", bad);
+    }
+
+    let seeds_matching = |args: &[&str]| -> Vec<u32> {
+        let mut search = search_with(args).unwrap();
+        search.set_file(FILE);
+        let mut seeds: Vec<u32> =
+            search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+        seeds.dedup();
+        seeds
+    };
+
+    // Seed 4 has a stack of 18 javelins
+    assert_eq!(seeds_matching(&["-w", "2", "javelin"]), vec![4]);
+    assert_eq!(seeds_matching(&["-w", "2", "thrown", "d6"]), vec![4, 5]);
+    assert_eq!(seeds_matching(&["-w", "=22", "thrown", "d6"]), vec![4]);
+    assert!(seeds_matching(&["-w", "=21", "thrown", "d6"]).is_empty());
+    assert_eq!(seeds_matching(&["-w", "thrown", "javelin"]), vec![4]);
+}
+
+// Checks that damage tiers outside 1 to 3 and other operators are rejected, that a bare
+// tier number is a tier, and that a light kind with a heavy tier matches nothing.
+#[test]
+fn weapon_damage_tier_edges() {
+    let search_with = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "-m", "all"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+    };
+    for bad in [
+        "dmg>=0",
+        "dmg>=tier0",
+        "dmg>=tier",
+        "dmg>=",
+        "dmg>tier2",
+        "dmg>=tier-1",
+    ] {
+        assert!(search_with(&["-w", bad]).is_err(), "{}", bad);
+    }
+    assert!(search_with(&["-a", "dmg>=tier2"]).is_err());
+
+    assert_eq!(
+        search_with(&["-w", "dmg>=3"]).unwrap().object_params[0].min_damage_tier,
+        Some(3)
+    );
+    assert_eq!(
+        search_with(&["-w", "dmg>=tier1", "dmg>=tier3"])
+            .unwrap()
+            .object_params
+            .len(),
+        2
+    );
+
+    let mut search = search_with(&["-w", "dagger", "dmg>=tier2"]).unwrap();
+    search.set_file(FILE);
+    assert!(search_files(&mut search).unwrap().is_empty());
+}
+
+// Checks that malformed armor value terms are rejected, that `av>=0` matches every armor
+// and a value above every armor none, and that a light kind with a high value matches
+// nothing.
+#[test]
+fn armor_value_edges() {
+    let search_with = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "-m", "all"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+    };
+    for bad in [
+        &["-a", "av>="][..],
+        &["-a", "av>=x"],
+        &["-a", "av>=256"],
+        &["-a", "av>9"],
+        &["-w", "av>=3"],
+    ] {
+        assert!(search_with(bad).is_err(), "{:?}", bad);
+    }
+
+    let matched = |args: &[&str]| {
+        let mut search = search_with(args).unwrap();
+        search.set_file(FILE);
+        search_files(&mut search).unwrap().len()
+    };
+    assert_eq!(
+        matched(&["-a", "av>=0"]),
+        matched(&["--filter", "category==armor"])
+    );
+    assert_eq!(matched(&["-a", "av>=99"]), 0);
+    assert_eq!(matched(&["-a", "leather", "av>=9"]), 0);
+}
+
+// Checks that `--met-depths` conflicts with machine output, has no depths for 'less than'
+// objects or optional objects a seed lacks, and that a single stack meets a count at once.
+#[test]
+fn met_depth_edges() {
+    for machine in ["--json", "--ndjson", "--porcelain"] {
+        assert!(
+            new_app()
+                .get_matches_from_safe(["brogue-scanner", "-p", "life", "--met-depths", machine])
+                .is_err(),
+            "{}",
+            machine
+        );
+    }
+
+    let args = [
+        "brogue-scanner",
+        "-w",
+        "2",
+        "javelin",
+        "-p",
+        "<9",
+        "life",
+        "-a",
+        "+2",
+        "?plate",
+        "--met-depths",
+        "-m",
+        "all",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    search_files(&mut search).unwrap();
+    let names: Vec<String> = search.object_params.iter().map(|p| p.short_name()).collect();
+    let javelin = names
+        .iter()
+        .position(|name| name == "javelin x2")
+        .unwrap_or_else(|| panic!("{:?}", names));
+
+    assert_eq!(search.seed_met_depths.keys().collect::<Vec<_>>(), vec![&4]);
+    let depths = &search.seed_met_depths[&4];
+    assert_eq!(depths[javelin], Some((6, 6)));
+    assert_eq!(depths.iter().filter(|depths| depths.is_none()).count(), 2);
+}
+
+// Checks that a required depth ignores optional objects, is missing for seeds that didn't
+// match or only have 'less than' objects, and that sorting keeps such seeds in order.
+#[test]
+fn required_depth_edges() {
+    let args = [
+        "brogue-scanner",
+        "-w",
+        "2",
+        "javelin",
+        "-a",
+        "?plate",
+        "--sort-depth",
+        "-m",
+        "all",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let mut search_matches = search_files(&mut search).unwrap();
+    assert_eq!(search.required_depth(4), Some(6));
+    assert_eq!(search.required_depth(1), None);
+    sort_by_required_depth(&mut search_matches, &search);
+    assert!(search_matches.iter().all(|m| m.seed == 4));
+
+    let args = ["brogue-scanner", "-p", "<9", "life", "--sort-depth", "-m", "all"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let mut search_matches = search_files(&mut search).unwrap();
+    let mut seeds: Vec<u32> = search_matches.iter().map(|m| m.seed).collect();
+    seeds.dedup();
+    assert!(!seeds.is_empty());
+    assert!(seeds.iter().all(|seed| search.required_depth(*seed).is_none()));
+    sort_by_required_depth(&mut search_matches, &search);
+    let mut sorted: Vec<u32> = search_matches.iter().map(|m| m.seed).collect();
+    sorted.dedup();
+    assert_eq!(sorted, seeds);
+
+    let mut empty = Vec::new();
+    sort_by_required_depth(&mut empty, &search);
+    assert!(empty.is_empty());
+}
+
+// Checks that `--best` rejects unknown rankings, keeps every matching seed when all are
+// wanted, forgets totals of dropped seeds, and keeps nothing when no seed matches.
+#[test]
+fn best_seeds_edges() {
+    for rank in ["", "gold", "Depth"] {
+        assert!(
+            new_app()
+                .get_matches_from_safe(["brogue-scanner", "-p", "life", "--best", rank])
+                .is_err(),
+            "{}",
+            rank
+        );
+    }
+
+    let args = [
+        "brogue-scanner",
+        "-p",
+        "life",
+        "--met-depths",
+        "--best",
+        "depth",
+        "-m",
+        "all",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    search_files(&mut search).unwrap();
+    let all = search.search_matches;
+    assert!(all > 1);
+    assert_eq!(search.seed_met_depths.len(), all as usize);
+
+    let args = [
+        "brogue-scanner",
+        "-p",
+        "life",
+        "--met-depths",
+        "--best",
+        "depth",
+        "-m",
+        "1",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+    assert_eq!((search.search_matches, search.seeds_scanned), (1, 5));
+    assert_eq!(
+        search.seed_met_depths.keys().collect::<Vec<_>>(),
+        vec![&search_matches[0].seed]
+    );
+
+    let args = ["brogue-scanner", "-p", "9", "life", "--best", "score", "-m", "2"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    assert!(search_files(&mut search).unwrap().is_empty());
+    assert_eq!(search.search_matches, 0);
+}
+
+// Checks that `--random-seed` rejects values that aren't whole numbers, accepts 0 and
+// the largest seed, and that shuffling no files or a single file is harmless.
+#[test]
+fn random_seed_edges() {
+    for value in ["", "abc", "1.5", "--random-seed=-1", "18446744073709551616"] {
+        let args = match value.starts_with("--") {
+            true => vec!["brogue-scanner", value, "-a", "scale"],
+            false => vec!["brogue-scanner", "--random-seed", value, "-a", "scale"],
+        };
+        assert!(
+            SearchParameters::from_matches(new_app().get_matches_from(args)).is_err(),
+            "{}",
+            value
+        );
+    }
+    for (value, expected) in [("0", 0), ("18446744073709551615", u64::MAX)] {
+        let args = ["brogue-scanner", "--random-seed", value, "-a", "scale"];
+        let search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        assert_eq!(search.random_seed, Some(expected));
+    }
+
+    let mut paths: Vec<std::path::PathBuf> = Vec::new();
+    file_handling::shuffle_paths(&mut paths, Some(42));
+    assert!(paths.is_empty());
+    let mut paths = vec![std::path::PathBuf::from("seeds-0.csv")];
+    file_handling::shuffle_paths(&mut paths, Some(42));
+    assert_eq!(paths, vec![std::path::PathBuf::from("seeds-0.csv")]);
+}
+
+// Checks that catalog files with malformed or reversed seed ranges sort with unnamed files,
+// that files starting at the same seed sort by path, and that no files sort to nothing.
+#[test]
+fn catalog_path_order_edges() {
+    let mut paths: Vec<std::path::PathBuf> = Vec::new();
+    file_handling::sort_catalog_paths(&mut paths);
+    assert!(paths.is_empty());
+
+    let mut paths: Vec<std::path::PathBuf> = [
+        "seeds-20-10.csv",
+        "seeds-x-10.csv",
+        "10-20.csv",
+        "seeds-5-.csv",
+        "b/seeds-1-10.csv",
+        "a/seeds-1-9.csv",
+    ]
+    .iter()
+    .map(|name| name.into())
+    .collect();
+    file_handling::sort_catalog_paths(&mut paths);
+
+    let names: Vec<&str> = paths.iter().map(|p| p.to_str().unwrap()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "a/seeds-1-9.csv",
+            "b/seeds-1-10.csv",
+            "10-20.csv",
+            "seeds-20-10.csv",
+            "seeds-5-.csv",
+            "seeds-x-10.csv",
+        ]
+    );
+}
+
+// Checks that `--output` conflicts with streaming and `--best`, keeps what a file already
+// holds, writes nothing while no seed matches, and fails in a folder that doesn't exist.
+#[test]
+fn output_appends_edges() {
+    for conflict in ["--stream", "--best=depth"] {
+        assert!(
+            new_app()
+                .get_matches_from_safe([
+                    "brogue-scanner",
+                    "--output",
+                    "x.out",
+                    conflict,
+                    "-a",
+                    "scale"
+                ])
+                .is_err(),
+            "{}",
+            conflict
+        );
+    }
+
+    let dir = TempDir::new("output-edges");
+    let search_to = |path: &std::path::Path, params: &[&str]| {
+        let mut args = vec![
+            "brogue-scanner",
+            "-U",
+            "-F",
+            "./src",
+            "--output",
+            path.to_str().unwrap(),
+        ];
+        args.extend_from_slice(params);
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search_files(&mut search)
+    };
+
+    let path = dir.join("kept.ndjson");
+    std::fs::write(&path, "earlier\n").unwrap();
+    let search_matches = search_to(&path, &["-a", "scale"]).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents.lines().next(), Some("earlier"));
+    assert_eq!(contents.lines().count(), search_matches.len() + 1);
+
+    let path = dir.join("none.ndjson");
+    assert!(search_to(&path, &["-p", "9", "life"]).unwrap().is_empty());
+    assert!(!path.exists());
+
+    let path = dir.join("missing").join("matches.ndjson");
+    let error = search_to(&path, &["-a", "scale"]).unwrap_err();
+    assert!(error.to_string().contains("can't open output"), "{}", error);
+}
+
+// Checks that `--stream` conflicts with every reordering and machine output, stops at the
+// match limit, and that a stream with no matching seeds is empty.
+#[test]
+fn stream_matches_edges() {
+    for conflict in [
+        "--best=depth",
+        "--json",
+        "--ndjson",
+        "--ordered",
+        "--porcelain",
+        "--relax",
+        "--sort-depth",
+        "--output=x.out",
+    ] {
+        assert!(
+            new_app()
+                .get_matches_from_safe(["brogue-scanner", "--stream", conflict, "-a", "scale"])
+                .is_err(),
+            "{}",
+            conflict
+        );
+    }
+
+    let search = |params: &[&str]| {
+        let mut args = vec!["brogue-scanner", "-U", "-F", "./src", "--stream"];
+        args.extend_from_slice(params);
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        let search_matches = search_files(&mut search).unwrap();
+        let mut seeds: Vec<u32> = search_matches.iter().map(|m| m.seed).collect();
+        seeds.dedup();
+        (seeds, search.search_matches)
+    };
+    let (seeds, found) = search(&["-a", "scale", "-m", "1"]);
+    assert_eq!((seeds.len(), found), (1, 1));
+    assert_eq!(search(&["-p", "9", "life"]), (vec![], 0));
+}
+
+// Checks that cancelling twice is harmless, that separate searches don't share a token,
+// and that a cancelled search of several folders (or through the engine) finds nothing.
+#[test]
+#[cfg(feature = "server")]
+fn cancel_search_edges() {
+    use search::CancelToken;
+
+    let args = [
+        "brogue-scanner",
+        "-U",
+        "-F",
+        "./src",
+        "-F",
+        "src",
+        "-m",
+        "all",
+        "-a",
+        "scale",
+    ];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    let other = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.cancel.cancel();
+    search.cancel.cancel();
+    assert!(search.cancel.is_cancelled() && !other.cancel.is_cancelled());
+    assert!(search_files(&mut search).unwrap().is_empty());
+    assert_eq!(search.search_matches, 0);
+
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    let cancel = CancelToken::new();
+    cancel.cancel();
+    let results = engine::Engine::new()
+        .search(new_app().get_matches_from(&args), &args, &cancel, None)
+        .unwrap();
+    assert!(results.iter().all(|(_, search_matches)| search_matches.is_empty()));
+}
+
+// Checks that throttled scans of several folders, with a match limit, or without matches
+// find the same seeds as full-speed scans.
+#[test]
+fn nice_scan_edges() {
+    let search = |params: &[&str], nice: bool| {
+        let mut args = vec!["brogue-scanner", "-U", "-F", "./src"];
+        args.extend_from_slice(params);
+        if nice {
+            args.push("--nice");
+        }
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        let seeds: Vec<u32> = search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+        (seeds, search.search_matches)
+    };
+    for params in [
+        &["-F", "src", "-a", "scale"][..],
+        &["-a", "scale", "-m", "1"],
+        &["-p", "9", "life"],
+    ] {
+        assert_eq!(search(params, true), search(params, false), "{:?}", params);
+    }
+}
+
+// Checks that runic alternatives with an empty alternative, or a runic of another category,
+// are an error, and that armor takes alternatives like weapons do.
+#[test]
+fn runic_alternatives_edges() {
+    for value in ["|", "speed|", "|speed", "speed||slow", "speed|reflection"] {
+        let args = ["brogue-scanner", "-w", value];
+        assert!(
+            SearchParameters::from_matches(new_app().get_matches_from(args)).is_err(),
+            "{}",
+            value
+        );
+    }
+    assert!(
+        SearchParameters::from_matches(new_app().get_matches_from([
+            "brogue-scanner",
+            "-a",
+            "reflection|speed"
+        ]))
+        .is_err()
+    );
+
+    let seeds = |value: &str| -> Vec<u32> {
+        let args = ["brogue-scanner", "-a", value, "-m", "all"];
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(FILE);
+        search_files(&mut search).unwrap().iter().map(|m| m.seed).collect()
+    };
+    let mut either = seeds("absorption|mutuality");
+    let mut each = [seeds("absorption"), seeds("mutuality")].concat();
+    either.sort_unstable();
+    each.sort_unstable();
+    assert_eq!(either, vec![1, 3, 3]);
+    assert_eq!(either, each);
+}
+
+// Checks that malformed `N-` charge terms are rejected, that limits below every item or
+// below a `+N` minimum match nothing, that the limit is inclusive, and that a second
+// limit starts a new object.
+#[test]
+fn max_charges_edges() {
+    let search_with = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "-m", "all"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+    };
+    for bad in [&["-W", "x-"][..], &["-W", "256-"], &["-W", "2--"], &["-s", "1.5-"]] {
+        assert!(search_with(bad).is_err(), "{:?}", bad);
+    }
+
+    let found = |args: &[&str]| -> Vec<(u32, u8)> {
+        let mut search = search_with(args).unwrap();
+        search.set_file(FILE);
+        search_files(&mut search)
+            .unwrap()
+            .iter()
+            .map(|m| (m.seed, m.depth))
+            .collect()
+    };
+    assert!(found(&["-W", "0-"]).is_empty());
+    assert!(found(&["-s", "1-"]).is_empty());
+    assert!(found(&["-W", "+4", "3-"]).is_empty());
+    assert_eq!(found(&["-W", "plenty", "1-"]), vec![(3, 10)]);
+    assert_eq!(
+        found(&["-W", "+3", "3-", "teleportation"]),
+        found(&["-W", "teleportation", "3-"])
+    );
+
+    let search = search_with(&["-W", "2-", "3-"]).unwrap();
+    let charges: Vec<Option<u8>> = search.object_params.iter().map(|p| p.max_charges).collect();
+    assert_eq!(charges, vec![Some(2), Some(3)]);
+}
+
+// Checks that malformed or zero `N@dD` stages are rejected, that a stage past the depth
+// limit only counts objects within it, and that a stage larger than the COUNT still needs
+// its own count.
+#[test]
+fn staged_counts_edges() {
+    let search_with = |args: &[&str]| {
+        let mut all = vec!["brogue-scanner", "-m", "all", "-S", "enchanting"];
+        all.extend_from_slice(args);
+        SearchParameters::from_matches(new_app().get_matches_from(all))
+    };
+    for bad in ["3@d", "@d3", "3@dx", "3@d256", "x@d3", "3@3", "0@d1"] {
+        assert!(search_with(&[bad]).is_err(), "{}", bad);
+    }
+
+    let seeds = |args: &[&str]| -> Vec<u32> {
+        let mut search = search_with(args).unwrap();
+        search.set_file(FILE);
+        let mut seeds: Vec<u32> =
+            search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+        seeds.dedup();
+        seeds
+    };
+    assert_eq!(seeds(&["d5", "6@d10"]), seeds(&["6", "d5"]));
+    assert_eq!(seeds(&["3", "6@d10"]), seeds(&["6", "d10"]));
+}
+
+// Checks that malformed, zero, and single-object totals are rejected, that repeated and
+// reordered object numbers make the same total, and that a total nothing reaches matches
+// no seeds.
+#[test]
+fn combined_totals_edges() {
+    let search_with = |total: &str| {
+        let args = [
+            "brogue-scanner",
+            "-p",
+            "d6",
+            "-S",
+            "d6",
+            "--total",
+            total,
+            "-m",
+            "all",
+        ];
+        SearchParameters::from_matches(new_app().get_matches_from(args))
+    };
+    for bad in [
+        "", ":1,2", "0:1,2", "x:1,2", "3:", "3:1", "3:1,1", "3:1,3", "3:0,1", "3;1,2", "2.5:1,2",
+    ] {
+        assert!(search_with(bad).is_err(), "{}", bad);
+    }
+    let args = ["brogue-scanner", "-p", "life", "--total", "3:all"];
+    assert!(SearchParameters::from_matches(new_app().get_matches_from(args)).is_err());
+
+    let seeds = |total: &str| -> Vec<u32> {
+        let mut search = search_with(total).unwrap();
+        search.set_file(FILE);
+        let mut seeds: Vec<u32> =
+            search_files(&mut search).unwrap().iter().map(|m| m.seed).collect();
+        seeds.dedup();
+        seeds
+    };
+    assert_eq!(seeds("25: 2, 1, 2"), seeds("25:1,2"));
+    assert_eq!(seeds("25:all"), seeds("25:1,2"));
+    assert!(seeds("999:1,2").is_empty());
+}
+
+// Checks that `--strength` alone finds a single potion, rejects kinds, magic, and
+// malformed terms, and that a second COUNT starts a second object as for potions.
+#[test]
+fn strength_potions_edges() {
+    let params = |args: &[&str]| SearchParameters::from_matches(new_app().get_matches_from(args)).map(|s| s.object_params);
+
+    assert_eq!(
+        params(&["brogue-scanner", "--strength"]).unwrap(),
+        params(&["brogue-scanner", "-p", "strength"]).unwrap()
+    );
+    for bad in ["strength", "good", "3@d", "x", ""] {
+        assert!(
+            params(&["brogue-scanner", "--strength", bad]).is_err(),
+            "{}",
+            bad
+        );
+    }
+
+    let repeated = params(&["brogue-scanner", "--strength", "1", "d4", "3", "d10"]).unwrap();
+    assert_eq!(
+        repeated,
+        params(&["brogue-scanner", "-p", "strength", "1", "d4", "3", "d10"]).unwrap()
+    );
+    assert_eq!(repeated.len(), 2);
+}
+
+// Checks that `--vaults` lists a vault without a key and a key's vault without contents,
+// and keeps no vaults when no seed matches.
+#[test]
+fn seed_vaults_edges() {
+    let search_with = |params: &[&str]| {
+        let mut args = vec!["brogue-scanner", "--vaults", "-d", "7", "-m", "all"];
+        args.extend_from_slice(params);
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(FILE);
+        search_files(&mut search).unwrap();
+        search
+    };
+
+    let search = search_with(&["-a", "splint"]);
+    let vaults: Vec<String> = search.seed_vaults[&3]
+        .iter()
+        .filter(|v| v.depth == 7)
+        .map(|v| v.to_string())
+        .collect();
+    assert_eq!(
+        vaults,
+        vec![
+            "Vault 12 on depth 7 (key found):  empty".to_string(),
+            "Vault 6 on depth 7 (no key found):  A +3 splint mail of troll immunity".to_string(),
+        ]
+    );
+
+    assert!(search_with(&["-p", "9", "life"]).seed_vaults.is_empty());
+}
+
+// Checks that `--rescue` conflicts with machine output, lists nothing for allies that
+// aren't captive, and shows a captive ally alone on its level without keys or captors.
+#[test]
+fn ally_rescue_edges() {
+    for machine in ["--json", "--ndjson", "--porcelain"] {
+        assert!(
+            new_app()
+                .get_matches_from_safe(["brogue-scanner", "-A", "troll", "--rescue", machine])
+                .is_err(),
+            "{}",
+            machine
+        );
+    }
+
+    let rescues = |params: &[&str]| {
+        let mut args = vec!["brogue-scanner", "--rescue", "-d", "26", "-m", "all"];
+        args.extend_from_slice(params);
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.set_file(FILE);
+        search_files(&mut search).unwrap();
+        search.seed_rescues
+    };
+
+    let allied = rescues(&["-A", "unicorn"]);
+    assert!(allied[&1].is_empty());
+
+    let alone = rescues(&["-A", "monkey", "d2"]);
+    assert_eq!(
+        alone[&1].iter().map(|r| r.to_string()).collect::<Vec<_>>(),
+        vec!["A shackled monkey on depth 2:  no cage key;  no captors".to_string(),]
+    );
+}
+
+// Checks that a header missing a column, or repeating one, is rejected, that a byte order
+// mark and padding around header names are ignored, that a header in standard order reads
+// Records unchanged, and that a folder's files without the catalog columns are skipped.
+#[test]
+fn reordered_columns_edges() {
+    use columns::{CatalogSchema, ColumnMap, HEADERS};
+    let schema = CatalogSchema::default();
+    let header = |names: &[&str]| csv::StringRecord::from(names.to_vec());
+
+    let missing = ColumnMap::from_headers(&header(&HEADERS[1..]), &schema).unwrap_err();
+    assert!(
+        missing.to_string().contains("no 'dungeon_version' column"),
+        "{}",
+        missing
+    );
+    let repeated =
+        ColumnMap::from_headers(&header(&[&HEADERS[..], &["seed"]].concat()), &schema).unwrap_err();
+    assert!(
+        repeated.to_string().contains("'seed' column repeated"),
+        "{}",
+        repeated
+    );
+    assert!(ColumnMap::from_headers(&header(&[]), &schema).is_err());
+
+    let mut padded = HEADERS.to_vec();
+    let first = format!("\u{feff}{}", HEADERS[0]);
+    let last = format!(" {} ", HEADERS[12]);
+    padded[0] = &first;
+    padded[12] = &last;
+    let map = ColumnMap::from_headers(&header(&padded), &schema).unwrap();
+    assert!(map.is_standard());
+
+    let record = csv::StringRecord::from(vec![
+        "CE 1.9",
+        "1",
+        "3",
+        "1",
+        "armor",
+        "scale mail",
+        "0",
+        "",
+        "",
+        "",
+        "",
+        "",
+        "",
+    ]);
+    assert_eq!(map.reorder(record.clone()), record);
+
+    let dir = TempDir::new("columns-edges");
+    std::fs::write(dir.join("a-notes.csv"), "seed,note\n1,scale\n").unwrap();
+    std::fs::copy(FILE, dir.join("b-test_data.csv")).unwrap();
+    let search = |args: &[&str]| {
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search_files(&mut search)
+            .unwrap()
+            .iter()
+            .map(|m| (m.seed, m.to_string()))
+            .collect::<Vec<_>>()
+    };
+    let expected = search(&["brogue-scanner", "-U", "-F", "./src", "-a", "scale", "-m", "all"]);
+    assert_eq!(
+        search(&[
+            "brogue-scanner",
+            "-U",
+            "-F",
+            dir.to_str().unwrap(),
+            "-a",
+            "scale",
+            "-m",
+            "all"
+        ]),
+        expected
+    );
+}
+
+// Checks that extra columns are kept after the standard ones in file order, and that
+// filtering on a column no catalog has, or a row missing an extra column, is an error
+// rather than matching nothing.
+#[test]
+fn extra_columns_edges() {
+    use columns::{CatalogSchema, ColumnMap, HEADERS};
+    let names: Vec<&str> =
+        [&["first"][..], &HEADERS[..6], &["middle"], &HEADERS[6..], &["last"]].concat();
+    let header = csv::StringRecord::from(names);
+    let map = ColumnMap::from_headers(&header, &CatalogSchema::default()).unwrap();
+    assert!(!map.is_standard());
+    let reordered: Vec<String> = map.headers(&header).iter().map(String::from).collect();
+    assert_eq!(
+        reordered,
+        [&HEADERS[..], &["first", "middle", "last"]].concat()
+    );
+
+    let args = ["brogue-scanner", "-a", "scale", "--where", "col:flag=x"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let error = search_files(&mut search).unwrap_err();
+    assert!(error.to_string().contains("flag"), "{}", error);
+
+    let dir = TempDir::new("extras-edges");
+    let lines: Vec<String> = std::fs::read_to_string(FILE)
+        .unwrap()
+        .lines()
+        .enumerate()
+        .map(|(i, line)| match i {
+            0 => format!("{},note", line),
+            4 => line.to_string(),
+            _ => format!("{},x", line),
+        })
+        .collect();
+    let path = dir.join("test_data.csv");
+    std::fs::write(&path, lines.join("\n")).unwrap();
+
+    let args = ["brogue-scanner", "-a", "scale", "-m", "all"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(path.to_str().unwrap());
+    let error = format!("{:#}", search_files(&mut search).unwrap_err());
+    assert!(
+        error.contains("invalid catalog file") && error.contains("line: 5"),
+        "{}",
+        error
+    );
+}
+
+// Checks that schemas from a missing or malformed config file, with unknown columns, keys,
+// or values that aren't strings, or reading two columns from one, are rejected, and that
+// an empty schema is Brogue CE's.
+#[test]
+fn schema_columns_edges() {
+    use columns::CatalogSchema;
+    let dir = TempDir::new("schema-edges");
+    let path = dir.join("brogue-scanner.toml");
+    let load = |text: &str| {
+        std::fs::write(&path, text).unwrap();
+        CatalogSchema::load(&path, "modded")
+    };
+
+    for (text, error) in [
+        ("[schema.modded", "invalid TOML"),
+        ("[schema.other]\n", "no '[schema.modded]' section"),
+        ("[schema]\nmodded = 3\n", "must be a table"),
+        (
+            "[schema.modded]\ncolumns = { item = \"item_kind\" }\n",
+            "unknown column 'item'",
+        ),
+        (
+            "[schema.modded]\ncolumns = { kind = 3 }\n",
+            "expected a string",
+        ),
+        (
+            "[schema.modded]\ncategories = { armour = true }\n",
+            "expected a string",
+        ),
+        (
+            "[schema.modded]\ncolumns = \"kind\"\n",
+            "invalid key 'columns'",
+        ),
+        ("[schema.modded]\nrows = {}\n", "invalid key 'rows'"),
+        (
+            "[schema.modded]\ncolumns = { kind = \"item\", runic = \"item\" }\n",
+            "columns 'kind' and 'runic' both read 'item'",
+        ),
+        (
+            "[schema.modded]\ncolumns = { kind = \"runic\" }\n",
+            "columns 'kind' and 'runic' both read 'runic'",
+        ),
+    ] {
+        let message = load(text).unwrap_err().to_string();
+        assert!(message.contains(error), "{:?}: {}", text, message);
+    }
+
+    let empty = load("[schema.modded]\n").unwrap();
+    let header = csv::StringRecord::from(columns::HEADERS.to_vec());
+    assert_eq!(empty.name.as_deref(), Some("modded"));
+    assert!(
+        columns::ColumnMap::from_headers(&header, &empty)
+            .unwrap()
+            .is_standard()
+    );
+
+    let missing = CatalogSchema::load(&dir.join("missing.toml"), "modded").unwrap_err();
+    assert!(
+        missing.to_string().contains("could not read config file"),
+        "{}",
+        missing
+    );
+}
+
+// Checks that data packs that are missing or malformed, have unknown tables or categories,
+// or name anything Brogue CE doesn't have are rejected, and that a JSON pack is named
+// after its file unless it names itself.
+#[test]
+fn variant_data_pack_edges() {
+    use variant::DataPack;
+    let dir = TempDir::new("variant-edges");
+    let load = |file: &str, text: &str| {
+        let path = dir.join(file);
+        std::fs::write(&path, text).unwrap();
+        DataPack::load(&path)
+    };
+
+    for (file, text, error) in [
+        ("bad.toml", "[kinds.armor", "invalid TOML"),
+        ("bad.json", "{\"kinds\": ", "invalid JSON"),
+        ("bad.toml", "[items.armor]\n", "unknown field"),
+        (
+            "bad.toml",
+            "[kinds.armour]\n\"a\" = \"scale mail\"\n",
+            "unknown category 'armour'",
+        ),
+        (
+            "bad.toml",
+            "[kinds.armor]\n\"a\" = \"scale\"\n",
+            "'scale' is not a Brogue CE armor kind",
+        ),
+        (
+            "bad.toml",
+            "[runics.weapon]\n\"a\" = \"reflection\"\n",
+            "'reflection' is not a Brogue CE weapon runic",
+        ),
+        (
+            "bad.toml",
+            "[monsters]\n\"a\" = \"goblin king\"\n",
+            "'goblin king' is not a Brogue CE monster",
+        ),
+        (
+            "bad.toml",
+            "[malevolent.potion]\n\"life\" = \"no\"\n",
+            "invalid TOML",
+        ),
+        (
+            "bad.toml",
+            "[malevolent.potion]\n\"quickness\" = true\n",
+            "'quickness' is not a Brogue CE potion kind or runic",
+        ),
+    ] {
+        let message = format!("{:#}", load(file, text).unwrap_err());
+        assert!(message.contains(error), "{:?}: {}", text, message);
+    }
+
+    let pack = load(
+        "rapid.json",
+        "{\"kinds\": {\"potion\": {\"quickness\": \"speed\"}}}",
+    )
+    .unwrap();
+    assert_eq!(pack.name, "rapid");
+    assert_eq!(
+        load("rapid.json", "{\"name\": \"Rapid Brogue\"}").unwrap().name,
+        "Rapid Brogue"
+    );
+
+    let missing = DataPack::find("nonesuch", &dir).unwrap_err();
+    assert!(
+        missing.to_string().contains("no data pack for variant 'nonesuch'"),
+        "{}",
+        missing
+    );
+}
+
+// Checks that a `--data-dir` without a tables file, or with malformed JSON, empty names,
+// or a name given twice is rejected, and that a file with no changes keeps the built-in
+// tables.
+#[test]
+fn data_dir_tables_edges() {
+    use objects::{PotionKind, Tables};
+
+    let dir = TempDir::new("tables-edges");
+    let missing = Tables::load(Some(&dir)).unwrap_err();
+    assert!(
+        missing.to_string().contains("could not read name tables"),
+        "{}",
+        missing
+    );
+
+    let tables = |changes: &str| {
+        std::fs::write(dir.join("tables.json"), changes).unwrap();
+        Tables::load(Some(&dir))
+    };
+    for (changes, error) in [
+        ("", "invalid JSON"),
+        (
+            r#"{ "extend": { "potion_kinds": [["quickness"]] } }"#,
+            "invalid JSON",
+        ),
+        (
+            r#"{ "extend": { "potion_kinds": [["", "Speed"]] } }"#,
+            "empty name for 'Speed'",
+        ),
+        (
+            r#"{ "extend": { "potion_kinds": [[" ", "Speed"]] } }"#,
+            "empty name for 'Speed'",
+        ),
+        (
+            r#"{ "replace": { "food_kinds": [["mango", "Mango"], ["mango", "Ration"]] } }"#,
+            "name 'mango' repeated",
+        ),
+    ] {
+        let message = format!("{:#}", tables(changes).unwrap_err());
+        assert!(message.contains(error), "{:?}: {}", changes, message);
+    }
+
+    let unchanged = tables("{}").unwrap();
+    let built_in = Tables::load(None).unwrap();
+    assert_eq!(unchanged.to_raw(), built_in.to_raw());
+    assert_eq!(
+        unchanged.names_matching::<PotionKind>(""),
+        built_in.names_matching::<PotionKind>("")
+    );
+}
+
+// Checks that `update-tables` rejects a path without C source or without monster classes,
+// leaves tables whose arrays are unterminated unchanged, keeps every name of an empty
+// array, and reports an output it can't write.
+#[test]
+fn update_tables_from_source_edges() {
+    use brogue_source::update_tables;
+
+    let dir = TempDir::new("update-tables-edges");
+    let src = dir.join("brogue");
+    std::fs::create_dir_all(&src).unwrap();
+    let output = dir.join("tables.json");
+    let update = |source: &str, output: &std::path::Path| {
+        std::fs::write(src.join("Globals.c"), source).unwrap();
+        update_tables(&src, output)
+    };
+
+    let missing = update_tables(&dir.join("nonesuch"), &output).unwrap_err();
+    assert!(
+        missing.to_string().contains("no C source files"),
+        "{}",
+        missing
+    );
+    std::fs::write(dir.join("notes.txt"), "potionTable").unwrap();
+    assert!(
+        update_tables(&dir.join("notes.txt"), &output)
+            .unwrap_err()
+            .to_string()
+            .contains("no C source files")
+    );
+
+    let classes = "monsterClass monsterClassCatalog[4] = { {\"dragon\", 10} };\n";
+    let no_classes = update("itemTable potionTable[3] = { {\"life\"} };\n", &output).unwrap_err();
+    assert!(
+        no_classes.to_string().contains("no 'monsterClassCatalog'"),
+        "{}",
+        no_classes
+    );
+
+    let source = format!(
+        "{}itemTable potionTable[3] = {{ {{\"life\"}}, {{\"strength\"}};\n",
+        classes
+    );
+    let (_, updates) = update(&source, &output).unwrap();
+    let potions = updates.iter().find(|u| u.table == "potion_kinds").unwrap();
+    assert_eq!(
+        potions.to_string(),
+        "potion_kinds:  no 'potionTable' in source (unchanged)"
+    );
+
+    let source = format!("{}itemTable armorTable[6] = {{}};\n", classes);
+    let (_, updates) = update(&source, &output).unwrap();
+    let armor = updates.iter().find(|u| u.table == "armor_kinds").unwrap();
+    assert!(
+        armor.added.is_empty() && armor.removed.is_empty() && !armor.kept.is_empty(),
+        "{}",
+        armor
+    );
+
+    let error = format!(
+        "{:#}",
+        update(classes, &dir.join("missing").join("tables.json")).unwrap_err()
+    );
+    assert!(error.contains("could not write name tables"), "{}", error);
+}
+
+// Checks that text files read the same with or without a UTF-8 byte order mark, that
+// UTF-16 text needs the `utf16` feature, and that subcommands and options left out of a
+// build say which feature they need.
+#[test]
+fn feature_flag_edges() {
+    let read = |bytes: &[u8]| file_handling::read_text(bytes).map_err(|e| e.to_string());
+    assert_eq!(read(b""), Ok(String::new()));
+    assert_eq!(read(b"\xEF\xBB\xBFseed\n1\n"), Ok("seed\n1\n".to_string()));
+
+    let utf16: Vec<u8> = vec![0xFF, 0xFE]
+        .into_iter()
+        .chain("seed\n".encode_utf16().flat_map(u16::to_le_bytes))
+        .collect();
+    match cfg!(feature = "utf16") {
+        true => assert_eq!(read(&utf16), Ok("seed\n".to_string())),
+        false => assert!(read(&utf16).unwrap_err().contains("'utf16' feature")),
+    }
+
+    if !cfg!(feature = "self-update") {
+        let args: Vec<String> = ["brogue-scanner", "self-update", "--check"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let error = engine::Engine::new()
+            .run(new_app().get_matches_from(&args), &args)
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("'self-update' feature"),
+            "{}",
+            error
+        );
+    }
+    if !cfg!(feature = "save-db") {
+        let args: Vec<String> = [
+            "brogue-scanner",
+            "-U",
+            "-F",
+            "./src",
+            "-a",
+            "scale",
+            "--save-db",
+            "x.db",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        let error = engine::Engine::new()
+            .run(new_app().get_matches_from(&args), &args)
+            .unwrap_err();
+        assert!(error.to_string().contains("'save-db' feature"), "{}", error);
+        assert!(!std::path::Path::new("x.db").exists());
+    }
+    if !cfg!(feature = "upload") {
+        let endpoint = upload::Endpoint::parse("https://seeds.example.org/api").unwrap();
+        let error = upload::upload_matches(&endpoint, &[]).unwrap_err();
+        assert!(error.to_string().contains("'upload' feature"), "{}", error);
+    }
+    if !cfg!(feature = "zip") {
+        let error = archive::ZipArchive::open(std::path::Path::new("Cargo.toml")).unwrap_err();
+        assert!(
+            format!("{:#}", error).contains("'zip' feature"),
+            "{}",
+            error
+        );
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let matches = new_app().get_matches_from(["brogue-scanner", "daemon"]);
+        let error =
+            run_server(matches.subcommand_matches("daemon").unwrap(), Vec::new()).unwrap_err();
+        assert!(error.to_string().contains("'server' feature"), "{}", error);
+    }
+}
+
+// Checks that empty paths, a bare '~', and unfinished or empty variable names are left as
+// they are, and that an unset variable in a command line path is an error.
+#[test]
+fn path_expansion_edges() {
+    use file_handling::expand_path;
+    use std::path::PathBuf;
+
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .unwrap();
+
+    assert_eq!(expand_path("").unwrap(), PathBuf::from(""));
+    assert_eq!(expand_path("~").unwrap(), PathBuf::from(&home));
+    assert_eq!(
+        expand_path("~catalogs").unwrap(),
+        PathBuf::from("~catalogs")
+    );
+    assert_eq!(
+        expand_path("catalogs/~").unwrap(),
+        PathBuf::from("catalogs/~")
+    );
+    assert_eq!(expand_path("a$").unwrap(), PathBuf::from("a$"));
+    assert_eq!(expand_path("${}").unwrap(), PathBuf::from("${}"));
+    assert_eq!(
+        expand_path("${BROGUE_TEST_OPEN").unwrap(),
+        PathBuf::from("${BROGUE_TEST_OPEN")
+    );
+    assert_eq!(expand_path("%%").unwrap(), PathBuf::from("%%"));
+    assert_eq!(expand_path("$1").unwrap(), PathBuf::from("$1"));
+
+    std::env::remove_var("BROGUE_TEST_UNSET_EDGE");
+    for path in &[
+        "$BROGUE_TEST_UNSET_EDGE",
+        "${BROGUE_TEST_UNSET_EDGE}",
+        "~/$BROGUE_TEST_UNSET_EDGE/a",
+    ] {
+        let error = expand_path(path).unwrap_err().to_string();
+        assert!(
+            error.contains("BROGUE_TEST_UNSET_EDGE isn't set"),
+            "{}",
+            error
+        );
+    }
+
+    let args = &["brogue-scanner", "-U", "-F", "$BROGUE_TEST_UNSET_EDGE", "-a", "scale"];
+    let error = SearchParameters::from_matches(new_app().get_matches_from(args))
+        .and_then(|mut search| search_files(&mut search))
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("BROGUE_TEST_UNSET_EDGE"),
+        "{}",
+        error
+    );
+}
+
+// Checks that a pipelined reader handles empty files, files ending on a chunk boundary, and
+// empty read buffers, and that a read error in the file is passed on after what was read.
+#[test]
+#[cfg(feature = "utf16")]
+fn pipelined_decoding_edges() {
+    use file_handling::PipelinedReader;
+    use std::io::Read;
+
+    struct Failing(usize);
+
+    impl Read for Failing {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0 {
+                0 => Err(std::io::Error::other("disk gone")),
+                left => {
+                    let n = left.min(buf.len());
+                    buf[..n].iter_mut().for_each(|b| *b = b'x');
+                    self.0 -= n;
+                    Ok(n)
+                }
+            }
+        }
+    }
+
+    let mut decoded = Vec::new();
+    let mut reader = PipelinedReader::new(std::io::empty());
+    assert_eq!(reader.read_to_end(&mut decoded).unwrap(), 0);
+
+    // Only the byte order mark
+    let mut text = String::new();
+    file_handling::decode_utf16(Box::new(std::io::Cursor::new(vec![0xFF, 0xFE])), true)
+        .unwrap()
+        .read_to_string(&mut text)
+        .unwrap();
+    assert_eq!(text, "");
+
+    for &len in &[64 * 1024, 2 * 64 * 1024, 64 * 1024 + 1] {
+        let mut decoded = Vec::new();
+        let mut reader = PipelinedReader::new(std::io::Cursor::new(vec![b'a'; len]));
+        assert_eq!(reader.read(&mut []).unwrap(), 0);
+        assert_eq!(reader.read_to_end(&mut decoded).unwrap(), len);
+        assert_eq!(reader.read(&mut [0; 8]).unwrap(), 0);
+    }
+
+    let mut decoded = Vec::new();
+    let error = PipelinedReader::new(Failing(100_000))
+        .read_to_end(&mut decoded)
+        .unwrap_err();
+    assert!(error.to_string().contains("disk gone"), "{}", error);
+    assert_eq!(decoded.len(), 100_000);
+}
+
+// Checks that no matches give no share strings, that --share can't be combined with other
+// output modes, and that empty, truncated, or malformed share strings are errors.
+#[test]
+fn share_strings_edges() {
+    assert!(share::share_strings(&[]).is_empty());
+
+    for flag in &["--json", "--ndjson", "--porcelain"] {
+        let args = &["brogue-scanner", "--share", flag, "-a", "scale"];
+        assert!(new_app().get_matches_from_safe(args).is_err(), "{}", flag);
+    }
+    assert!(new_app().get_matches_from_safe(["brogue-scanner", "decode"]).is_err());
+
+    // Surrounding whitespace and lowercase escapes are accepted
+    let decoded = share::decode("  brogue:1/CE%201.9/7/5,1,potion,life\n").unwrap();
+    assert_eq!(
+        (
+            decoded[0].seed,
+            decoded[0].depth,
+            decoded[0].version.as_str()
+        ),
+        (7, 5, "CE 1.9")
+    );
+    assert_eq!(
+        share::decode("brogue:1/CE%2b1.9/7/5,1,potion,life").unwrap()[0].version,
+        "CE+1.9"
+    );
+
+    let invalid = vec![
+        ("", "expected 'brogue:'"),
+        (
+            "https://example.com/1/CE+1.9/7/5,1,potion,life",
+            "expected 'brogue:'",
+        ),
+        ("brogue:", "format version"),
+        ("brogue:2/CE+1.9/7/5,1,potion,life", "format version"),
+        ("brogue:1", "dungeon version"),
+        ("brogue:1/CE%ZZ1.9/7/5,1,potion,life", "dungeon version"),
+        ("brogue:1/CE%FF/7/5,1,potion,life", "dungeon version"),
+        ("brogue:1/CE+1.9", "bad seed"),
+        ("brogue:1/CE+1.9/-7/5,1,potion,life", "bad seed"),
+        ("brogue:1/CE+1.9/4294967296/5,1,potion,life", "bad seed"),
+        ("brogue:1/CE+1.9/7/", "bad object 1"),
+        ("brogue:1/CE+1.9/7/5,1,potion,life/", "bad object 2"),
+        ("brogue:1/CE+1.9/7/5,1,Potion,healing", "bad object 1"),
+        ("brogue:1/CE+1.9/7/5,1,potion,life,,,,,,,,", "bad object 1"),
+        ("brogue:1/CE+1.9/7/x,1,potion,life", "bad object 1"),
+        ("brogue:1/CE+1.9/7/5,1,potion,life%", "bad object 1"),
+    ];
+    for (share, reason) in invalid {
+        let error = share::decode(share).unwrap_err().to_string();
+        assert!(error.contains(reason), "{}: {}", share, error);
+    }
+}
+
+// Checks that malformed or insecure upload endpoints and config files are errors, and that
+// an upload refused by the server or sent to no server at all fails with the reason.
+#[test]
+#[cfg(feature = "upload")]
+fn upload_matches_edges() {
+    use std::io::{BufRead, BufReader, Write};
+    use upload::Endpoint;
+
+    assert_eq!(
+        Endpoint::parse("http://localhost").unwrap().to_string(),
+        "http://localhost/"
+    );
+    assert_eq!(
+        Endpoint::parse("http://[::1]:8080/api").unwrap().to_string(),
+        "http://[::1]:8080/api"
+    );
+    assert_eq!(
+        Endpoint::parse("https://seeds.example.org:443/api")
+            .unwrap()
+            .to_string(),
+        "https://seeds.example.org/api"
+    );
+    let invalid = [
+        ("", "expected 'https://"),
+        ("ftp://seeds.example.org/api", "expected 'https://"),
+        ("https://", "no host"),
+        ("https:///api", "no host"),
+        ("https://:8080/api", "no host"),
+        ("https://seeds.example.org:70000/api", "invalid port"),
+        ("https://seeds.example.org:/api", "invalid port"),
+        ("http://10.0.0.1/api", "use https://"),
+        ("http://localhost.example.org/api", "use https://"),
+    ];
+    for (url, reason) in invalid {
+        let error = Endpoint::parse(url).unwrap_err().to_string();
+        assert!(error.contains(reason), "{}: {}", url, error);
+    }
+
+    let error = SearchParameters::from_matches(new_app().get_matches_from([
+        "brogue-scanner",
+        "-a",
+        "scale",
+        "--upload=",
+    ]))
+    .unwrap_err();
+    assert!(
+        error.to_string().contains("invalid upload endpoint"),
+        "{}",
+        error
+    );
+
+    let dir = TempDir::new("upload-edges");
+    let args = &[
+        "brogue-scanner",
+        "-F",
+        dir.to_str().unwrap(),
+        "-a",
+        "scale",
+        "--upload",
+    ];
+    let configs = [
+        (None, "could not read config file"),
+        (
+            Some("[upload\nendpoint = \"https://seeds.example.org\"\n"),
+            "invalid TOML",
+        ),
+        (Some(""), "needs a URL"),
+        (Some("[upload]\n"), "needs a URL"),
+        (Some("[upload]\nendpoint = 443\n"), "must be a string"),
+        (
+            Some("[upload]\nendpoint = \"seeds.example.org\"\n"),
+            "invalid upload endpoint",
+        ),
+    ];
+    for (config, reason) in configs {
+        if let Some(config) = config {
+            std::fs::write(dir.join("brogue-scanner.toml"), config).unwrap();
+        }
+        let error = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap_err();
+        assert!(
+            format!("{:#}", error).contains(reason),
+            "{:?}: {:#}",
+            config,
+            error
+        );
+    }
+
+    let args = &["brogue-scanner", "-a", "scale", "-m", "1"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+
+    // A server that turns the upload down
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = Endpoint::parse(&format!(
+        "http://{}/api/seeds",
+        listener.local_addr().unwrap()
+    ))
+    .unwrap();
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut line = String::new();
+        while line != "\r\n" {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+        }
+        stream
+            .write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+    });
+    let error = upload::upload_matches(&endpoint, &search_matches).unwrap_err();
+    assert!(error.to_string().contains("503"), "{}", error);
+    server.join().unwrap();
+
+    // No server at all
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let endpoint = Endpoint::parse(&format!("http://127.0.0.1:{}/api/seeds", port)).unwrap();
+    let error = upload::upload_matches(&endpoint, &search_matches).unwrap_err();
+    assert!(error.to_string().contains("couldn't reach"), "{}", error);
+}
+
+// Checks that versions with missing or non-numeric parts compare as they should, that
+// malformed checksum lines match nothing, and that a failed install leaves no files behind.
+#[test]
+#[cfg(feature = "self-update")]
+fn self_update_edges() {
+    use self_update::{is_newer, replace_executable, sha256_hex, verify_checksum};
+
+    assert!(!is_newer("v1.0", "1.0.0").unwrap());
+    assert!(!is_newer("1.0.0", "1.0").unwrap());
+    assert!(is_newer("v1.0.1", "1.0").unwrap());
+    assert!(is_newer("v0.10", "0.9.6").unwrap());
+    assert!(!is_newer("v0.9.5", "0.9.6").unwrap());
+    for tag in ["", "v", "1..0", "v1.0.0-rc1", "v-1.0.0"] {
+        let error = is_newer(tag, "0.9.6").unwrap_err().to_string();
+        assert!(error.contains("invalid version"), "{}: {}", tag, error);
+    }
+
+    // A name is only matched in full, and lines without a name match nothing
+    let checksums = format!(
+        "{}\n{}  brogue-scanner-linux\n\n",
+        sha256_hex(b"abc"),
+        sha256_hex(b"abc")
+    );
+    assert!(verify_checksum(b"abc", &checksums, "brogue-scanner-linux").is_ok());
+    for name in ["brogue-scanner", "brogue-scanner-linux-x86_64", ""] {
+        let error = verify_checksum(b"abc", &checksums, name).unwrap_err().to_string();
+        assert!(error.contains("no checksum"), "{}: {}", name, error);
+    }
+    let error = verify_checksum(b"abc", "nothex  name\n", "name")
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("checksum mismatch"), "{}", error);
+
+    let dir = TempDir::new("update-edges");
+    let exe = dir.join("brogue-scanner");
+
+    // No executable to replace
+    let error = replace_executable(&exe, b"new").unwrap_err();
+    assert!(error.to_string().contains("can't read"), "{}", error);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+    // An empty binary, and an old executable left by an earlier update
+    std::fs::write(&exe, b"old").unwrap();
+    std::fs::write(dir.join("brogue-scanner.old"), b"older").unwrap();
+    replace_executable(&exe, b"").unwrap();
+    assert_eq!(std::fs::read(&exe).unwrap(), b"");
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+}
+
+// Checks that files missing from an archive, unsupported compression methods, and
+// truncated archives are errors naming the archive, that an archive without catalogs
+// gives no files, and that a changed archive is read again.
+#[test]
+#[cfg(feature = "zip")]
+fn zip_archives_edges() {
+    use std::io::Write;
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    let zip = |files: &[(&str, &[u8])]| {
+        let mut writer = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options =
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        for (name, data) in files {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    };
+    let paths = |path: &std::path::Path| {
+        file_handling::get_brogue_csv_paths(path, 1, file_handling::FileFormat::Utf8)
+            .map(|(paths, _)| paths)
+    };
+
+    let dir = TempDir::new("zip-edges");
+    let text = std::fs::read(FILE).unwrap();
+    let path = dir.join("catalogs.zip");
+
+    // No catalogs at all
+    std::fs::write(&path, zip(&[])).unwrap();
+    assert!(paths(&path).unwrap().is_empty());
+    std::fs::write(&path, zip(&[("notes.txt", b"seeds")])).unwrap();
+    assert!(paths(&path).unwrap().is_empty());
+
+    // The archive is read again once it changes
+    std::fs::write(&path, zip(&[("seeds-1-10.csv", &text)])).unwrap();
+    assert_eq!(paths(&path).unwrap(), vec![path.join("seeds-1-10.csv")]);
+    let archive = archive::ZipArchive::open(&path).unwrap();
+    let error = archive.open_entry("seeds-11-20.csv").err().unwrap();
+    assert!(
+        error.to_string().contains("no 'seeds-11-20.csv' in archive"),
+        "{}",
+        error
+    );
+    assert_eq!(archive.compressed_size("seeds-11-20.csv"), None);
+
+    // Compression methods other than stored and deflate (here, bzip2)
+    let mut bzip2 = zip(&[("seeds-1-10.csv", &text)]);
+    let directory = bzip2
+        .windows(4)
+        .position(|bytes| bytes == [0x50, 0x4b, 0x01, 0x02])
+        .unwrap();
+    bzip2[8..10].copy_from_slice(&12u16.to_le_bytes());
+    bzip2[directory + 10..directory + 12].copy_from_slice(&12u16.to_le_bytes());
+    std::fs::write(&path, &bzip2).unwrap();
+    let error = archive::ZipArchive::open(&path)
+        .unwrap()
+        .open_entry("seeds-1-10.csv")
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("isn't supported"), "{}", error);
+
+    // A truncated archive in a catalog folder
+    let full = zip(&[("seeds-1-10.csv", &text)]);
+    std::fs::write(&path, &full[..full.len() - 10]).unwrap();
+    let error = paths(&dir).unwrap_err();
+    assert!(
+        format!("{:#}", error).contains("invalid zip archive"),
+        "{:#}",
+        error
+    );
+    assert!(
+        format!("{:#}", error).contains("catalogs.zip"),
+        "{:#}",
+        error
+    );
+}
+
+// Checks that export files that can't be created are errors, that several searches share
+// one header, and that only `*.tsv` files (in any case) are tab-separated.
+#[test]
+fn export_matches_edges() {
+    use file_handling::OutputEncoding;
+
+    let dir = TempDir::new("export-edges");
+
+    let args = &["brogue-scanner", "-a", "scale", "-m", "2"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let search_matches = search_files(&mut search).unwrap();
+
+    for path in [
+        dir.join("missing/matches.csv"),
+        dir.clone(),
+        std::path::PathBuf::new(),
+    ] {
+        let error = search::write_export(
+            &path,
+            OutputEncoding::Utf8,
+            std::iter::once(&search_matches),
+        )
+        .unwrap_err();
+        assert!(
+            error.to_string().contains("could not create export file"),
+            "{:?}: {}",
+            path,
+            error
+        );
+    }
+
+    let lists = vec![search_matches.clone(), Vec::new(), search_matches.clone()];
+    for (name, delimiter) in [("matches.TSV", '\t'), ("matches.tsv.csv", ','), ("matches", ',')] {
+        let path = dir.join(name);
+        assert_eq!(
+            search::write_export(&path, OutputEncoding::Utf8, &lists).unwrap(),
+            4
+        );
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(text.lines().count(), 5, "{}", name);
+        assert_eq!(text.matches("seed").count(), 1, "{}", name);
+        assert_eq!(
+            text.lines().next().unwrap().matches(delimiter).count(),
+            7,
+            "{}",
+            name
+        );
+    }
+
+    // UTF-16 exports start with a byte order mark, and read back the same
+    let path = dir.join("matches-utf16.csv");
+    assert_eq!(
+        search::write_export(&path, OutputEncoding::Utf16, std::iter::once(&Vec::new())).unwrap(),
+        0
+    );
+    let bytes = std::fs::read(&path).unwrap();
+    assert!(bytes.starts_with(&[0xFF, 0xFE]));
+    if cfg!(feature = "utf16") {
+        assert!(
+            file_handling::read_text(&bytes[..])
+                .unwrap()
+                .starts_with("seed,depth,")
+        );
+    }
+}
+
+// Checks that reports of no matches still have a title and a count, that query names and
+// dungeon versions are escaped, and that unknown formats and other output modes are
+// rejected.
+#[test]
+fn markdown_report_edges() {
+    for format in ["pdf", "MD", ""] {
+        let arg = format!("--report={}", format);
+        assert!(
+            new_app()
+                .get_matches_from_safe(["brogue-scanner", "-a", "scale", &arg])
+                .is_err(),
+            "{}",
+            format
+        );
+    }
+    for flag in ["--ndjson", "--porcelain", "--share", "--stream", "--format={seed}"] {
+        let args = ["brogue-scanner", "-a", "scale", "--report", "md", flag];
+        assert!(new_app().get_matches_from_safe(args).is_err(), "{}", flag);
+    }
+
+    let report = |format: &str, search_matches: &[SearchMatch], name: Option<&str>| {
+        let args = &["brogue-scanner", "-a", "scale", "--report", format];
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search.name = name.map(str::to_string);
+        let mut buffer = Vec::new();
+        write_machine_matches(&mut buffer, search_matches, &search).unwrap();
+        String::from_utf8(buffer).unwrap()
+    };
+    assert_eq!(
+        report("md", &[], None),
+        "# Seed finds\n\n_0 seeds found._\n"
+    );
+    assert!(report("html", &[], None).contains("<p>0 seeds found, with 0 matching objects.</p>"));
+    assert!(report("html", &[], None).contains("<tbody>\n</tbody>"));
+
+    let args = &["brogue-scanner", "-a", "scale", "--seed", "3"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let mut search_matches = search_files(&mut search).unwrap();
+    search_matches
+        .iter_mut()
+        .for_each(|m| m.version = "CE_1.9 <beta>".to_string());
+
+    let md = report("md", &search_matches, Some("*best* [scale]"));
+    assert!(
+        md.starts_with(
+            "# Seed finds:  \\*best\\* \\[scale\\]\n\n## Seed 3 (CE\\_1.9 \\<beta\\>)\n"
+        ),
+        "{}",
+        md
+    );
+    assert!(md.ends_with("\n_1 seed found._\n"), "{}", md);
+    let html = report("html", &search_matches, Some("<b>scale</b> & 'more'"));
+    assert!(
+        html.contains("<h1>Seed finds:  &lt;b&gt;scale&lt;/b&gt; &amp; &#39;more&#39;</h1>"),
+        "{}",
+        html
+    );
+    assert!(!html.contains("<b>"));
+
+    search_matches.iter_mut().for_each(|m| m.version.clear());
+    assert!(report("md", &search_matches, None).contains("\n## Seed 3\n\n"));
+}
+
+// Checks that HTML reports count a single match in the singular, leave cells without a
+// value empty, and keep script and styles in the page even with no matches.
+#[test]
+fn html_report_edges() {
+    let args = &["brogue-scanner", "-a", "scale", "--seed", "3", "--report", "html"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let mut search_matches = search_files(&mut search).unwrap();
+    search_matches.truncate(1);
+
+    let report = |matches: &[SearchMatch]| {
+        let mut buffer = Vec::new();
+        write_machine_matches(&mut buffer, matches, &search).unwrap();
+        String::from_utf8(buffer).unwrap()
+    };
+    let html = report(&search_matches);
+    assert!(
+        html.contains("<p>1 seed found, with 1 matching object.</p>"),
+        "{}",
+        html
+    );
+    assert!(
+        html.contains("<tr><td>3</td><td>10</td><td>A +0 scale mail</td><td>1</td><td></td></tr>"),
+        "{}",
+        html
+    );
+    assert_eq!(html.matches("data-type=\"number\"").count(), 3);
+
+    let empty = report(&[]);
+    assert!(empty.contains("<title>Seed finds</title>"));
+    assert!(empty.contains("<script>") && empty.contains("<style>"));
+    assert!(!empty.contains("http://") && !empty.contains("https://"));
+    assert_eq!(empty.matches("<tr><td>").count(), 0);
+}
+
+// Checks that empty templates, unknown or misspelled fields, and unbalanced braces are
+// rejected, that escapes and adjacent fields read as they should, and that --format can't
+// be combined with other output modes.
+#[test]
+fn match_templates_edges() {
+    use search::MatchTemplate;
+
+    let error = SearchParameters::from_matches(new_app().get_matches_from([
+        "brogue-scanner",
+        "-a",
+        "scale",
+        "--format=",
+    ]))
+    .unwrap_err();
+    assert!(error.to_string().contains("empty template"), "{}", error);
+    for flag in [
+        "--json",
+        "--ndjson",
+        "--porcelain",
+        "--share",
+        "--stream",
+        "--report=md",
+    ] {
+        let args = ["brogue-scanner", "-a", "scale", "--format", "{seed}", flag];
+        assert!(new_app().get_matches_from_safe(args).is_err(), "{}", flag);
+    }
+
+    let invalid = [
+        ("{SEED}", "unknown field '{SEED}'"),
+        ("{seed}{dept}", "unknown field '{dept}'"),
+        ("{ }", "unknown field '{}'"),
+        ("{seed", "unclosed '{'"),
+        ("{{seed}", "unmatched '}'"),
+        ("{seed}}", "unmatched '}'"),
+        ("}", "unmatched '}'"),
+    ];
+    for (template, reason) in invalid {
+        let error = MatchTemplate::parse(template).unwrap_err().to_string();
+        assert!(error.contains(reason), "{}: {}", template, error);
+    }
+
+    let args = &["brogue-scanner", "-a", "scale", "--seed", "3"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let m = &search_files(&mut search).unwrap()[0];
+
+    let lines = [
+        ("{seed}{depth}", "310"),
+        ("{{seed}}", "{seed}"),
+        ("{{{seed}}}", "{3}"),
+        (r"a\\tb\x\", r"a\tb\x\"),
+        (r"{depth}\n", "10\n"),
+        ("{runic}|{carried_by}|{opens_vault}", "||"),
+        ("é {kind} ✓", "é scale mail ✓"),
+    ];
+    for (template, line) in lines {
+        assert_eq!(
+            MatchTemplate::parse(template).unwrap().render(m),
+            line,
+            "{}",
+            template
+        );
+    }
+}
+
+// Checks that `--output` can't be combined with --best, --relax, or --stream, that a
+// search without matches still writes its summary or an empty document, that documents
+// replace longer output, and that a document output that can't be created is an error.
+#[test]
+fn output_formats_edges() {
+    for flag in ["--best", "--relax", "--stream"] {
+        let args = ["brogue-scanner", "--output", "matches.out", "-a", "scale", flag];
+        assert!(new_app().get_matches_from_safe(args).is_err(), "{}", flag);
+    }
+
+    let dir = TempDir::new("output-formats-edges");
+    let path = dir.join("matches.out");
+
+    let run = |output: &std::path::Path, query: &[&str]| {
+        let mut args = vec![
+            "brogue-scanner",
+            "-U",
+            "-F",
+            "./src",
+            "--output",
+            output.to_str().unwrap(),
+        ];
+        args.extend_from_slice(query);
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        engine::Engine::new().run(new_app().get_matches_from(&args), &args)
+    };
+    let read = || std::fs::read_to_string(&path).unwrap();
+
+    // No matches
+    run(&path, &["-p", "9", "life"]).unwrap();
+    let summary: serde_json::Value = serde_json::from_str(&read()).unwrap();
+    assert_eq!(
+        (summary["type"].as_str(), summary["matches"].as_u64()),
+        (Some("summary"), Some(0))
+    );
+    run(&path, &["-p", "9", "life", "--json"]).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&read()).unwrap();
+    assert_eq!(json["summary"]["seeds"], 0);
+    run(&path, &["-p", "9", "life", "--report", "md"]).unwrap();
+    assert_eq!(read(), "# Seed finds\n\n_0 seeds found._\n");
+
+    // Lines are added after the document, which replaces them in turn
+    run(&path, &["-a", "scale", "-m", "all", "--share"]).unwrap();
+    run(&path, &["-a", "scale", "-m", "all", "--format", "{seed}"]).unwrap();
+    assert!(read().starts_with("# Seed finds\n"));
+    assert!(read().ends_with("\n1\n2\n3\n3\n4\n4\n4\n"), "{}", read());
+    run(&path, &["-p", "9", "life", "--report", "md"]).unwrap();
+    assert_eq!(read(), "# Seed finds\n\n_0 seeds found._\n");
+
+    let missing = dir.join("missing/matches.json");
+    let error = run(&missing, &["-a", "scale", "--json"]).unwrap_err();
+    assert!(
+        error.to_string().contains("can't create output"),
+        "{}",
+        error
+    );
+    assert!(!missing.exists());
+}
+
+// Checks that depth summaries of no matches say so, that categories are counted in order
+// of appearance even when they alternate, and that uncountable categories keep their name.
+#[test]
+fn depth_summaries_edges() {
+    assert_eq!(search::depth_summary(&[]), "No matches");
+
+    let args = &["brogue-scanner", "--maxdepth", "1", "show", "1"];
+    let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+    search.set_file(FILE);
+    let objects = seed_detail::seed_catalog(&search, 1).unwrap();
+
+    // Scrolls, then potions, then scrolls again
+    let scrolls = objects
+        .iter()
+        .filter(|o| o.object.category() == objects::Category::Scroll);
+    let potions = objects
+        .iter()
+        .filter(|o| o.object.category() == objects::Category::Potion);
+    let mixed: Vec<SearchMatch> = scrolls
+        .clone()
+        .take(1)
+        .chain(potions)
+        .chain(scrolls.skip(1))
+        .cloned()
+        .collect();
+    assert_eq!(
+        search::depth_summary(&mixed),
+        "Depth 1: 5 matches (2 scrolls, 3 potions)"
+    );
+
+    let gold: Vec<SearchMatch> = objects
+        .iter()
+        .filter(|o| o.object.category() == objects::Category::Gold)
+        .cloned()
+        .collect();
+    let gold = vec![gold[0].clone(), gold[0].clone()];
+    assert_eq!(search::depth_summary(&gold), "Depth 1: 2 matches (2 gold)");
+    assert_eq!(objects::Category::Armor.plural(), "armor");
+    assert_eq!(objects::Category::Staff.plural(), "staves");
+}
+
+// Checks that an index doesn't skip files without the kind or runic of an optional object.
+#[test]
+fn kind_filter_optional() {
+    let dir = TempDir::new("filter-optional");
+    std::fs::copy(FILE, dir.join("test_data.csv")).unwrap();
+
+    let search = || {
+        let args = &[
+            "brogue-scanner",
+            "-U",
+            "-F",
+            dir.to_str().unwrap(),
+            "-a",
+            "banded",
+            "-w",
+            "?quietus",
+            "-m",
+            "all",
+        ];
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search_files(&mut search).unwrap().len()
+    };
+    let unindexed = search();
+    assert!(unindexed > 0);
+
+    let args = &["brogue-scanner", "-U", "-F", dir.to_str().unwrap(), "-a", "scale"];
+    index::update_indexes(
+        &SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap(),
+    )
+    .unwrap();
+    assert!(dir.join(index::INDEX_FILE).exists());
+    assert_eq!(search(), unindexed);
+}
+
+// Checks that an index only skips files for objects the --logic needs, not for objects
+// under `||` or `!`.
+#[test]
+fn kind_filter_logic() {
+    let dir = TempDir::new("filter-logic");
+    std::fs::copy(FILE, dir.join("test_data.csv")).unwrap();
+
+    let search = |logic: &str| {
+        let args = &[
+            "brogue-scanner",
+            "-U",
+            "-F",
+            dir.to_str().unwrap(),
+            "-a",
+            "banded",
+            "-w",
+            "quietus",
+            "-m",
+            "all",
+            "--logic",
+            logic,
+        ];
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        search_files(&mut search).unwrap().len()
+    };
+    let logics = ["1 || 2", "1 && !2", "!(2 && 1)", "1 && 2"];
+    let unindexed: Vec<usize> = logics.iter().map(|logic| search(logic)).collect();
+    assert!(unindexed[0] > 0 && unindexed[1] > 0);
+
+    let args = &["brogue-scanner", "-U", "-F", dir.to_str().unwrap(), "-a", "scale"];
+    index::update_indexes(
+        &SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap(),
+    )
+    .unwrap();
+    assert!(dir.join(index::INDEX_FILE).exists());
+    let indexed: Vec<usize> = logics.iter().map(|logic| search(logic)).collect();
+    assert_eq!(indexed, unindexed);
+}
+
+// Checks that good and bad magic terms don't panic on kinds the data tables don't have
+// (e.g. from a variant's catalogs), but report the row.
+#[test]
+fn magic_terms_unknown_kinds() {
+    let dir = TempDir::new("magic-kinds");
+    let text = std::fs::read_to_string(FILE).unwrap();
+    let text = text
+        .replace(",potion,life,", ",potion,elixir,")
+        .replace(",wand,slowness,", ",wand,stasis,");
+    std::fs::write(dir.join("seeds.csv"), text).unwrap();
+
+    for terms in [["-p", "good"], ["-p", "bad"], ["--wand", "good"], ["--wand", "bad"]] {
+        let mut args = vec!["brogue-scanner", "-U", "-F", dir.to_str().unwrap(), "-m", "all"];
+        args.extend_from_slice(&terms);
+        let mut search = SearchParameters::from_matches(new_app().get_matches_from(args)).unwrap();
+        let result = search_files(&mut search);
+        assert!(
+            result.is_ok() || result.unwrap_err().is::<search::RowError>(),
+            "{:?}",
+            terms
+        );
+    }
+}
+
+// Checks that `show` and `--dump-seed` take the seeds `--seed` does, and that other seeds
+// are bad queries.
+#[test]
+fn show_seed_range() {
+    use status::Status;
+
+    let run = |args: &[&str]| {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        engine::Engine::new().run(new_app().get_matches_from(&args), &args)
+    };
+    for seed in ["0", "4294967296", "x"] {
+        let (dump_seed, search_seed) =
+            (format!("--dump-seed={}", seed), format!("--seed={}", seed));
+        let errors = [
+            run(&["brogue-scanner", "-U", "-F", "./src", &dump_seed, "--ndjson"]).unwrap_err(),
+            run(&["brogue-scanner", "-U", "-F", "./src", "--ndjson", "show", seed]).unwrap_err(),
+            run(&["brogue-scanner", "-U", "-F", "./src", &search_seed, "-a", "scale"]).unwrap_err(),
+        ];
+        for error in errors {
+            assert!(
+                error.to_string().contains("must be from 1 to 4294967295"),
+                "{}",
+                error
+            );
+            assert_eq!(Status::of_error(&error), Status::BadQuery, "{}", error);
+        }
+    }
+    assert!(
+        run(&[
+            "brogue-scanner",
+            "-U",
+            "-F",
+            "./src",
+            "--ndjson",
+            "show",
+            "4294967295"
+        ])
+        .is_ok()
+    );
+}
+
+// Checks that uploads to https:// endpoints never go over plain http://, and that an
+// endpoint that can't be reached is named in the error.
+#[test]
+#[cfg(feature = "upload")]
+fn upload_https_only() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let url = format!("http://127.0.0.1:{}/api/seeds", port);
+
+    let error = http::post_json(&url, b"{}", true).unwrap_err();
+    assert!(error.to_string().contains(&url), "{}", error);
+    assert!(
+        error.to_string().to_lowercase().contains("https"),
+        "{}",
+        error
+    );
+
+    let error = http::post_json(&url, b"{}", false).unwrap_err();
+    assert!(
+        error.to_string().starts_with(&format!("couldn't reach {}:", url)),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,73 @@
+//! Low-priority scans for Brogue Seed Scanner.
+//!
+//! With `--nice`, a scan of catalog files runs at a lower CPU priority (on unix), and
+//! pauses between seeds so that it only works about half the time, which also spaces out
+//! its reads.  A long sweep of a catalog then leaves the machine usable (e.g. for a game of
+//! Brogue) at the cost of taking about twice as long.  Parallel workers (one per catalog
+//! folder) are each throttled on their own.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Longest time spent searching before a pause.
+const WORK_SLICE: Duration = Duration::from_millis(20);
+/// Length of each pause.
+const PAUSE: Duration = Duration::from_millis(20);
+/// CPU priority (nice value) of throttled scans.
+#[cfg(unix)]
+const NICE_LEVEL: i32 = 10;
+
+/// Pauses of a throttled scan.
+#[derive(Debug)]
+pub(crate) struct Throttle {
+    /// Whether the scan is throttled at all.
+    enabled: bool,
+    /// Seed of the last update.
+    seed: Option<u32>,
+    /// Time the scan last resumed after a pause.
+    resumed: Instant,
+}
+
+impl Throttle {
+    /// Makes a new instance, lowering the priority of the current thread if `enabled`.
+    pub(crate) fn new(enabled: bool) -> Self {
+        if enabled {
+            lower_priority();
+        }
+
+        Self {
+            enabled,
+            seed: None,
+            resumed: Instant::now(),
+        }
+    }
+    /// Pauses at the start of a new seed, once the scan has worked for a full slice.
+    pub(crate) fn update(&mut self, seed: u32) {
+        if !self.enabled || self.seed == Some(seed) {
+            return;
+        }
+        self.seed = Some(seed);
+
+        if self.resumed.elapsed() >= WORK_SLICE {
+            thread::sleep(PAUSE);
+            self.resumed = Instant::now();
+        }
+    }
+}
+
+/// Lowers the CPU priority of the current thread (of the whole process, on some systems).
+/// A priority that is already lower is kept, and failures are ignored:  the pauses still
+/// throttle the scan.
+#[cfg(unix)]
+fn lower_priority() {
+    // SAFETY:  `getpriority` and `setpriority` only read and set the priority of the caller
+    unsafe {
+        if libc::getpriority(libc::PRIO_PROCESS, 0) < NICE_LEVEL {
+            libc::setpriority(libc::PRIO_PROCESS, 0, NICE_LEVEL);
+        }
+    }
+}
+
+/// Priorities are left as they are on other systems.
+#[cfg(not(unix))]
+fn lower_priority() {}
@@ -0,0 +1,135 @@
+//! Uploads of matches to a community seed database for Brogue Seed Scanner.
+//!
+//! With `--upload URL`, the matches of each search are sent to a community seed database
+//! (e.g. a crowd-sourced list of great seeds) once the search is done.  Nothing is ever
+//! sent without `--upload`, but its endpoint can be kept in the config file
+//! (`brogue-scanner.toml` in the catalog folder, or `--config`) for `--upload` alone:
+//! ```toml
+//! [upload]
+//! endpoint = "https://seeds.example.org/api/matches"
+//! ```
+//! Uploads are anonymized:  only each matching seed, its dungeon version, and its
+//! matching objects are sent (as an `upload` document, see `schema`), never file paths,
+//! queries, or anything about the machine.  The document is POSTed as JSON over HTTPS
+//! (plain `http://` is only allowed for endpoints on this machine, e.g. for testing),
+//! and any `2xx` answer counts as accepted.
+//!
+//! Endpoints are read in every build, but only builds with the `upload` feature send
+//! anything.
+
+#[cfg(feature = "upload")]
+use crate::http::post_json;
+#[cfg(feature = "upload")]
+use crate::search::write_upload;
+use crate::search::SearchMatch;
+use anyhow::{anyhow, Context, Result};
+use std::path::Path;
+
+/// A community seed database endpoint (`--upload`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoint {
+    https: bool,
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl Endpoint {
+    /// Parses an `https://HOST[:PORT][/PATH]` URL (or an `http://` one, for a loopback
+    /// host).
+    pub fn parse(url: &str) -> Result<Self> {
+        let (https, rest) = match (url.strip_prefix("https://"), url.strip_prefix("http://")) {
+            (Some(rest), _) => (true, rest),
+            (None, Some(rest)) => (false, rest),
+            _ => return Err(anyhow!("invalid upload endpoint '{}' (expected 'https://HOST[:PORT]/PATH')", url)),
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(ix) => (&rest[..ix], &rest[ix..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':').filter(|(_, port)| !port.ends_with(']'))
+        {
+            Some((host, port)) => {
+                let port = port
+                    .parse::<u16>()
+                    .map_err(|_| anyhow!("invalid port in upload endpoint '{}'", url))?;
+                (host, port)
+            }
+            None => (authority, if https { 443 } else { 80 }),
+        };
+        if host.is_empty() {
+            return Err(anyhow!("no host in upload endpoint '{}'", url));
+        }
+        if !https && !is_loopback(host) {
+            return Err(anyhow!(
+                "can't upload to '{}':  use https:// (http:// is only for endpoints on this machine)",
+                url
+            ));
+        }
+
+        Ok(Self {
+            https,
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+    /// Loads the endpoint of the `[upload]` section of a config file.
+    pub fn from_config(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("could not read config file {:?} (for --upload without URL)", path))?;
+        let table: toml::Table = text.parse()
+            .with_context(|| format!("invalid TOML in config file {:?}", path))?;
+
+        match table.get("upload").and_then(|upload| upload.get("endpoint")) {
+            Some(toml::Value::String(url)) => Self::parse(url),
+            Some(_) => Err(anyhow!(
+                "'upload.endpoint' must be a string in config file {:?}",
+                path
+            )),
+            None => Err(anyhow!(
+                "--upload needs a URL, or 'endpoint' in the [upload] section of {:?}",
+                path
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.https, self.port) {
+            (true, 443) => write!(f, "https://{}{}", self.host, self.path),
+            (false, 80) => write!(f, "http://{}{}", self.host, self.path),
+            (true, port) => write!(f, "https://{}:{}{}", self.host, port, self.path),
+            (false, port) => write!(f, "http://{}:{}{}", self.host, port, self.path),
+        }
+    }
+}
+
+/// Uploads the matches of a search to a community seed database, and returns the number
+/// of seeds sent.
+#[cfg(feature = "upload")]
+pub fn upload_matches(endpoint: &Endpoint, matches: &[SearchMatch]) -> Result<usize> {
+    let mut body = Vec::new();
+    let seeds = write_upload(&mut body, matches)?;
+    // An https:// endpoint can't be redirected to plain http://
+    post_json(&endpoint.to_string(), &body, endpoint.https)?;
+
+    Ok(seeds)
+}
+
+/// Uploads the matches of a search (only in builds with the `upload` feature).
+#[cfg(not(feature = "upload"))]
+pub fn upload_matches(_endpoint: &Endpoint, _matches: &[SearchMatch]) -> Result<usize> {
+    Err(anyhow!(
+        "this build can't upload matches (built without the 'upload' feature)"
+    ))
+}
+
+/// Returns `true` for hosts on this machine (`localhost`, `127.x.x.x`, and `[::1]`).
+fn is_loopback(host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
@@ -0,0 +1,205 @@
+//! Data packs for Brogue variants in Brogue Seed Scanner.
+//!
+//! Variants of Brogue (e.g. Rapid Brogue) rename some of Brogue CE's kinds, runics, and
+//! monsters, and change which objects are malevolent.  A data pack maps each name of the
+//! variant to the Brogue CE name it stands for, so the variant's catalogs can be searched
+//! without a fork of the scanner.  Packs are TOML or JSON files, selected with
+//! `--variant NAME` (read from `variants/NAME.toml` or `variants/NAME.json` in the catalog
+//! folder) or `--variant FILE`:
+//! ```toml
+//! name = "Rapid Brogue"
+//!
+//! [kinds.potion]
+//! "quickness" = "speed"
+//!
+//! [runics.weapon]
+//! "stunning" = "paralysis"
+//!
+//! [monsters]
+//! "goblin warchief" = "goblin chieftain"
+//!
+//! [malevolent.potion]
+//! "darkness" = false
+//! ```
+//! Catalog Records are read with the Brogue CE names, so searches (and the objects shown)
+//! use those.  `malevolent` tables set whether each Brogue CE kind (or runic) counts as
+//! malevolent, for `--no-malevolent` and the `good`/`bad` search terms.
+
+use crate::columns;
+use crate::file_handling::expand_path;
+use crate::objects::{Category, MonsterKind};
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Folder of data packs, in the (first) catalog folder.
+pub const VARIANTS_DIR: &str = "variants";
+
+/// Names of a data pack by category, as read from its file.
+type NamesByCategory<T> = BTreeMap<String, BTreeMap<String, T>>;
+
+/// A data pack file, as read.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PackFile {
+    name: Option<String>,
+    #[serde(default)]
+    kinds: NamesByCategory<String>,
+    #[serde(default)]
+    runics: NamesByCategory<String>,
+    #[serde(default)]
+    monsters: BTreeMap<String, String>,
+    #[serde(default)]
+    malevolent: NamesByCategory<bool>,
+}
+
+/// Names and malevolence flags of a Brogue variant (`--variant`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DataPack {
+    pub name: String,
+    /// Kinds of the variant, with the Brogue CE kind of each.
+    kinds: Vec<(Category, String, String)>,
+    /// Runics of the variant, with the Brogue CE runic of each.
+    runics: Vec<(Category, String, String)>,
+    /// Monsters of the variant, with the Brogue CE monster of each.
+    monsters: Vec<(String, String)>,
+    /// Brogue CE kinds and runics, with whether each is malevolent in the variant.
+    malevolent: Vec<(Category, String, bool)>,
+}
+
+impl DataPack {
+    /// Returns the path of a data pack:  `value` itself if it's a file, otherwise
+    /// `variants/NAME.toml` (or `.json`) in the catalog folder.
+    pub fn find(value: &str, catalog_dir: &Path) -> Result<PathBuf> {
+        let path = expand_path(value)?;
+        if path.is_file() {
+            return Ok(path);
+        }
+
+        let dir = catalog_dir.join(VARIANTS_DIR);
+        ["toml", "json"]
+            .iter()
+            .map(|ext| dir.join(format!("{}.{}", value, ext)))
+            .find(|path| path.is_file())
+            .ok_or_else(|| anyhow!("no data pack for variant '{}' (expected {:?})", value, dir.join(format!("{}.toml", value))))
+    }
+    /// Loads a data pack from a TOML file, or a JSON file (named `*.json`).
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("could not read data pack {:?}", path))?;
+        let file: PackFile = match path.extension().is_some_and(|ext| ext == "json") {
+            true => serde_json::from_str(&text)
+                .with_context(|| format!("invalid JSON in data pack {:?}", path))?,
+            false => toml::from_str(&text)
+                .with_context(|| format!("invalid TOML in data pack {:?}", path))?,
+        };
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        Self::from_file(file, stem).with_context(|| format!("in data pack {:?}", path))
+    }
+    /// Makes a data pack from its file (named `default_name` unless the file names it),
+    /// checking every Brogue CE name.
+    fn from_file(file: PackFile, default_name: String) -> Result<Self> {
+        let mut pack = Self {
+            name: file.name.unwrap_or(default_name),
+            ..Self::default()
+        };
+
+        for (category, names) in file.kinds {
+            let category = parse_category(&category)?;
+            for (variant, standard) in names {
+                if !category.kinds_matching(&standard).contains(&standard.as_str()) {
+                    return Err(anyhow!(
+                        "'{}' is not a Brogue CE {} kind",
+                        standard,
+                        category
+                    ));
+                }
+                pack.kinds.push((category, variant, standard));
+            }
+        }
+        for (category, names) in file.runics {
+            let category = parse_category(&category)?;
+            for (variant, standard) in names {
+                if !category.runics_matching(&standard).contains(&standard.as_str()) {
+                    return Err(anyhow!(
+                        "'{}' is not a Brogue CE {} runic",
+                        standard,
+                        category
+                    ));
+                }
+                pack.runics.push((category, variant, standard));
+            }
+        }
+        for (variant, standard) in file.monsters {
+            if MonsterKind::parse(&standard).is_none() {
+                return Err(anyhow!("'{}' is not a Brogue CE monster", standard));
+            }
+            pack.monsters.push((variant, standard));
+        }
+        for (category, names) in file.malevolent {
+            let category = parse_category(&category)?;
+            for (standard, malevolent) in names {
+                let known = category.kinds_matching(&standard).contains(&standard.as_str())
+                    || category.runics_matching(&standard).contains(&standard.as_str());
+                if !known {
+                    return Err(anyhow!(
+                        "'{}' is not a Brogue CE {} kind or runic",
+                        standard,
+                        category
+                    ));
+                }
+                pack.malevolent.push((category, standard, malevolent));
+            }
+        }
+
+        Ok(pack)
+    }
+    /// Replaces the variant's names in the fields of a Record (in standard column order)
+    /// with their Brogue CE names.
+    pub fn translate<'a>(&'a self, category: Category, fields: &mut [&'a str]) {
+        let standard = |names: &'a [(Category, String, String)], value: &str| {
+            names
+                .iter()
+                .find(|(c, variant, _)| *c == category && variant == value)
+                .map(|(_, _, standard)| standard.as_str())
+        };
+        let monster = |value: &str| {
+            self.monsters
+                .iter()
+                .find(|(variant, _)| variant == value)
+                .map(|(_, standard)| standard.as_str())
+        };
+
+        let kind = match category {
+            Category::Ally => monster(fields[columns::KIND]),
+            _ => standard(&self.kinds, fields[columns::KIND]),
+        };
+        if let Some(kind) = kind {
+            fields[columns::KIND] = kind;
+        }
+        if let Some(runic) = standard(&self.runics, fields[columns::RUNIC]) {
+            fields[columns::RUNIC] = runic;
+        }
+        if let Some(carrier) = monster(fields[columns::CARRIED_BY]) {
+            fields[columns::CARRIED_BY] = carrier;
+        }
+    }
+    /// Returns whether a Brogue CE kind or runic is malevolent in the variant, if the pack
+    /// sets it.
+    pub fn malevolence(&self, category: Category, name: &str) -> Option<bool> {
+        self.malevolent
+            .iter()
+            .find(|(c, standard, _)| *c == category && standard == name)
+            .map(|(_, _, malevolent)| *malevolent)
+    }
+}
+
+/// Parses a category name of a data pack.
+fn parse_category(value: &str) -> Result<Category> {
+    Category::parse(value).ok_or_else(|| anyhow!("unknown category '{}'", value))
+}